@@ -0,0 +1,985 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Typed public keys, secret keys and signatures for all curves supported by Tezos
+//! (ed25519, secp256k1 and P-256), compatible with the Octez `Signature` module.
+//!
+//! Tezos never signs the raw data, it signs the blake2b-256 digest of the data
+//! prefixed with an optional [`Watermark`].
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use digest::generic_array::GenericArray;
+use digest::{consts::U32, BlockInput, FixedOutput, Reset, Update};
+use failure::Fail;
+use k256::ecdsa::signature::{DigestSigner, DigestVerifier};
+use num_bigint::BigUint;
+use rand::RngCore;
+use sha2::Sha256;
+use sodiumoxide::crypto::sign::ed25519;
+
+use crate::base58::{FromBase58Check, FromBase58CheckError, ToBase58Check};
+use crate::blake2b::{self, Blake2bError};
+use crate::hash::{
+    ChainId, ContractTz1Hash, ContractTz2Hash, ContractTz3Hash, FromBytesError, HashType,
+    PublicKeyEd25519, PublicKeyP256, PublicKeySecp256k1,
+};
+
+mod prefix_bytes {
+    pub const ED25519_SEED: [u8; 4] = [13, 15, 58, 7];
+    pub const ED25519_SECRET_KEY: [u8; 4] = [43, 246, 78, 7];
+    pub const SECP256K1_SECRET_KEY: [u8; 4] = [17, 162, 224, 201];
+    pub const P256_SECRET_KEY: [u8; 4] = [16, 81, 238, 189];
+    pub const ED25519_SIGNATURE: [u8; 5] = [9, 245, 205, 134, 18];
+    pub const SECP256K1_SIGNATURE: [u8; 5] = [13, 115, 101, 19, 63];
+    pub const P256_SIGNATURE: [u8; 4] = [54, 240, 44, 52];
+    pub const GENERIC_SIGNATURE: [u8; 3] = [4, 130, 43];
+}
+
+/// Size of the signature for all curves (ed25519 signature or ECDSA `r || s`)
+pub const SIGNATURE_SIZE: usize = 64;
+/// Size of the ed25519 seed, secp256k1 and P-256 secret scalars
+pub const SECRET_KEY_SIZE: usize = 32;
+/// Size of the expanded ed25519 secret key (`seed || public key`)
+const ED25519_EXPANDED_SECRET_KEY_SIZE: usize = 64;
+/// Order of the P-256 group, used for "low S" normalization of signatures
+const P256_ORDER: &str = "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551";
+
+#[derive(Debug, Fail)]
+pub enum SignatureError {
+    #[fail(display = "Invalid base58check encoding: {}", _0)]
+    Base58(FromBase58CheckError),
+    #[fail(display = "Unsupported base58check prefix or length: {}", encoded)]
+    UnknownPrefix { encoded: String },
+    #[fail(display = "Invalid key, reason: {}", reason)]
+    InvalidKey { reason: String },
+    #[fail(display = "Invalid signature, reason: {}", reason)]
+    InvalidSignature { reason: String },
+    #[fail(display = "Failed to sign data, reason: {}", reason)]
+    FailedToSign { reason: String },
+    #[fail(display = "Failed to hash signed data: {}", _0)]
+    Blake2b(Blake2bError),
+}
+
+impl From<FromBase58CheckError> for SignatureError {
+    fn from(error: FromBase58CheckError) -> Self {
+        SignatureError::Base58(error)
+    }
+}
+
+impl From<Blake2bError> for SignatureError {
+    fn from(error: Blake2bError) -> Self {
+        SignatureError::Blake2b(error)
+    }
+}
+
+impl From<FromBytesError> for SignatureError {
+    fn from(_: FromBytesError) -> Self {
+        SignatureError::InvalidKey {
+            reason: "invalid public key size".to_string(),
+        }
+    }
+}
+
+/// Elliptic curve used by a key or a signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Curve {
+    Ed25519,
+    Secp256k1,
+    P256,
+}
+
+/// Magic bytes prepended to the signed data, they prevent a signature of one kind
+/// of data to be valid for another kind (e.g. an operation signature replayed as a block).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Watermark {
+    /// `0x01` followed by the chain id
+    Block(ChainId),
+    /// `0x02` followed by the chain id
+    Endorsement(ChainId),
+    /// `0x03`
+    GenericOperation,
+    /// Arbitrary bytes
+    Custom(Vec<u8>),
+}
+
+impl Watermark {
+    pub fn bytes(&self) -> Vec<u8> {
+        match self {
+            Watermark::Block(chain_id) => [&[0x01], chain_id.as_ref().as_slice()].concat(),
+            Watermark::Endorsement(chain_id) => [&[0x02], chain_id.as_ref().as_slice()].concat(),
+            Watermark::GenericOperation => vec![0x03],
+            Watermark::Custom(bytes) => bytes.clone(),
+        }
+    }
+}
+
+/// Digest which is actually signed for `data` with the optional `watermark`.
+pub fn signed_digest(
+    watermark: Option<&Watermark>,
+    data: &[u8],
+) -> Result<Vec<u8>, SignatureError> {
+    match watermark {
+        Some(watermark) => Ok(blake2b::digest_256(&[&watermark.bytes(), data].concat())?),
+        None => Ok(blake2b::digest_256(data)?),
+    }
+}
+
+/// Signature of any supported curve.
+///
+/// `Generic` is a signature of unknown curve (base58 prefix `sig`), e.g. the signature
+/// contained in an operation. Its curve is given by the public key used to verify it.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Signature {
+    Ed25519([u8; SIGNATURE_SIZE]),
+    Secp256k1([u8; SIGNATURE_SIZE]),
+    P256([u8; SIGNATURE_SIZE]),
+    Generic([u8; SIGNATURE_SIZE]),
+}
+
+impl Signature {
+    /// Creates generic signature from its raw binary representation.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SignatureError> {
+        Ok(Signature::Generic(signature_bytes(bytes)?))
+    }
+
+    pub fn from_base58_check(encoded: &str) -> Result<Self, SignatureError> {
+        let bytes = encoded.from_base58check()?;
+        if let Some(data) = strip_prefix(&bytes, &prefix_bytes::ED25519_SIGNATURE, SIGNATURE_SIZE) {
+            Ok(Signature::Ed25519(signature_bytes(data)?))
+        } else if let Some(data) =
+            strip_prefix(&bytes, &prefix_bytes::SECP256K1_SIGNATURE, SIGNATURE_SIZE)
+        {
+            Ok(Signature::Secp256k1(signature_bytes(data)?))
+        } else if let Some(data) =
+            strip_prefix(&bytes, &prefix_bytes::P256_SIGNATURE, SIGNATURE_SIZE)
+        {
+            Ok(Signature::P256(signature_bytes(data)?))
+        } else if let Some(data) =
+            strip_prefix(&bytes, &prefix_bytes::GENERIC_SIGNATURE, SIGNATURE_SIZE)
+        {
+            Ok(Signature::Generic(signature_bytes(data)?))
+        } else {
+            Err(SignatureError::UnknownPrefix {
+                encoded: encoded.to_string(),
+            })
+        }
+    }
+
+    pub fn to_base58_check(&self) -> String {
+        match self {
+            Signature::Ed25519(bytes) => encode(&prefix_bytes::ED25519_SIGNATURE, bytes),
+            Signature::Secp256k1(bytes) => encode(&prefix_bytes::SECP256K1_SIGNATURE, bytes),
+            Signature::P256(bytes) => encode(&prefix_bytes::P256_SIGNATURE, bytes),
+            Signature::Generic(bytes) => encode(&prefix_bytes::GENERIC_SIGNATURE, bytes),
+        }
+    }
+
+    /// Curve of the signature, `None` for generic signature.
+    pub fn curve(&self) -> Option<Curve> {
+        match self {
+            Signature::Ed25519(_) => Some(Curve::Ed25519),
+            Signature::Secp256k1(_) => Some(Curve::Secp256k1),
+            Signature::P256(_) => Some(Curve::P256),
+            Signature::Generic(_) => None,
+        }
+    }
+
+    /// Raw binary representation, the same for all curves.
+    pub fn as_bytes(&self) -> &[u8; SIGNATURE_SIZE] {
+        match self {
+            Signature::Ed25519(bytes)
+            | Signature::Secp256k1(bytes)
+            | Signature::P256(bytes)
+            | Signature::Generic(bytes) => bytes,
+        }
+    }
+
+    pub fn to_generic(&self) -> Signature {
+        Signature::Generic(*self.as_bytes())
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Signature({})", self.to_base58_check())
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_base58_check())
+    }
+}
+
+/// Public key of any supported curve, secp256k1 and P-256 keys are stored in compressed form.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PublicKey {
+    Ed25519(PublicKeyEd25519),
+    Secp256k1(PublicKeySecp256k1),
+    P256(PublicKeyP256),
+}
+
+impl PublicKey {
+    pub fn from_base58_check(encoded: &str) -> Result<Self, SignatureError> {
+        let bytes = encoded.from_base58check()?;
+        let public_key = [
+            HashType::PublicKeyEd25519,
+            HashType::PublicKeySecp256k1,
+            HashType::PublicKeyP256,
+        ]
+        .iter()
+        .find_map(|hash_type| {
+            strip_prefix(&bytes, hash_type.base58check_prefix(), hash_type.size())
+                .map(|data| (hash_type, data))
+        });
+        let public_key = match public_key {
+            Some((HashType::PublicKeyEd25519, data)) => {
+                PublicKey::Ed25519(PublicKeyEd25519::try_from(data)?)
+            }
+            Some((HashType::PublicKeySecp256k1, data)) => {
+                PublicKey::Secp256k1(PublicKeySecp256k1::try_from(data)?)
+            }
+            Some((_, data)) => PublicKey::P256(PublicKeyP256::try_from(data)?),
+            None => {
+                return Err(SignatureError::UnknownPrefix {
+                    encoded: encoded.to_string(),
+                })
+            }
+        };
+        // reject points which are not on the curve right away
+        public_key.validate()?;
+        Ok(public_key)
+    }
+
+    pub fn to_base58_check(&self) -> String {
+        match self {
+            PublicKey::Ed25519(key) => key.to_base58_check(),
+            PublicKey::Secp256k1(key) => key.to_base58_check(),
+            PublicKey::P256(key) => key.to_base58_check(),
+        }
+    }
+
+    pub fn curve(&self) -> Curve {
+        match self {
+            PublicKey::Ed25519(_) => Curve::Ed25519,
+            PublicKey::Secp256k1(_) => Curve::Secp256k1,
+            PublicKey::P256(_) => Curve::P256,
+        }
+    }
+
+    /// Raw binary representation of the key (without the curve tag).
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            PublicKey::Ed25519(key) => key.as_ref(),
+            PublicKey::Secp256k1(key) => key.as_ref(),
+            PublicKey::P256(key) => key.as_ref(),
+        }
+    }
+
+    /// Hash of the public key, i.e. the implicit account address (tz1/tz2/tz3).
+    pub fn public_key_hash(&self) -> Result<PublicKeyHash, SignatureError> {
+        let hash = blake2b::digest_160(self.as_bytes())?;
+        Ok(match self {
+            PublicKey::Ed25519(_) => PublicKeyHash::Ed25519(ContractTz1Hash::try_from(hash)?),
+            PublicKey::Secp256k1(_) => PublicKeyHash::Secp256k1(ContractTz2Hash::try_from(hash)?),
+            PublicKey::P256(_) => PublicKeyHash::P256(ContractTz3Hash::try_from(hash)?),
+        })
+    }
+
+    /// Verifies `signature` of `data` signed with `watermark`.
+    ///
+    /// Returns `Ok(false)` if the signature does not match, error is returned only
+    /// if the key or the signature is malformed or belongs to another curve.
+    pub fn verify(
+        &self,
+        watermark: Option<&Watermark>,
+        data: &[u8],
+        signature: &Signature,
+    ) -> Result<bool, SignatureError> {
+        if let Some(curve) = signature.curve() {
+            if curve != self.curve() {
+                return Err(SignatureError::InvalidSignature {
+                    reason: format!(
+                        "{:?} signature cannot be verified with {:?} public key",
+                        curve,
+                        self.curve()
+                    ),
+                });
+            }
+        }
+        let digest = signed_digest(watermark, data)?;
+        let signature = signature.as_bytes();
+
+        match self {
+            PublicKey::Ed25519(key) => {
+                let key = ed25519_public_key(key.as_ref())?;
+                let signature = ed25519::Signature(*signature);
+                Ok(ed25519::verify_detached(&signature, &digest, &key))
+            }
+            PublicKey::Secp256k1(key) => {
+                let key = secp256k1_public_key(key.as_ref())?;
+                let signature = match k256::ecdsa::Signature::try_from(&signature[..]) {
+                    Ok(signature) => signature,
+                    Err(_) => return Ok(false),
+                };
+                // high `s` signatures are rejected (as by libsecp256k1)
+                Ok(key
+                    .verify_digest(Prehashed::new(digest), &signature)
+                    .is_ok())
+            }
+            PublicKey::P256(key) => {
+                let key = p256_public_key(key.as_ref())?;
+                let signature = match p256::ecdsa::Signature::try_from(&signature[..]) {
+                    Ok(signature) => signature,
+                    Err(_) => return Ok(false),
+                };
+                Ok(key
+                    .verify_digest(Prehashed::new(digest), &signature)
+                    .is_ok())
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<(), SignatureError> {
+        match self {
+            PublicKey::Ed25519(key) => ed25519_public_key(key.as_ref()).map(|_| ()),
+            PublicKey::Secp256k1(key) => secp256k1_public_key(key.as_ref()).map(|_| ()),
+            PublicKey::P256(key) => p256_public_key(key.as_ref()).map(|_| ()),
+        }
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_base58_check())
+    }
+}
+
+/// Hash of the public key (implicit account)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PublicKeyHash {
+    Ed25519(ContractTz1Hash),
+    Secp256k1(ContractTz2Hash),
+    P256(ContractTz3Hash),
+}
+
+impl PublicKeyHash {
+    pub fn from_base58_check(encoded: &str) -> Result<Self, SignatureError> {
+        let hash = match &encoded.get(..3) {
+            Some("tz1") => PublicKeyHash::Ed25519(ContractTz1Hash::from_base58_check(encoded)?),
+            Some("tz2") => PublicKeyHash::Secp256k1(ContractTz2Hash::from_base58_check(encoded)?),
+            Some("tz3") => PublicKeyHash::P256(ContractTz3Hash::from_base58_check(encoded)?),
+            _ => {
+                return Err(SignatureError::UnknownPrefix {
+                    encoded: encoded.to_string(),
+                })
+            }
+        };
+        Ok(hash)
+    }
+
+    pub fn to_base58_check(&self) -> String {
+        match self {
+            PublicKeyHash::Ed25519(hash) => hash.to_base58_check(),
+            PublicKeyHash::Secp256k1(hash) => hash.to_base58_check(),
+            PublicKeyHash::P256(hash) => hash.to_base58_check(),
+        }
+    }
+
+    pub fn curve(&self) -> Curve {
+        match self {
+            PublicKeyHash::Ed25519(_) => Curve::Ed25519,
+            PublicKeyHash::Secp256k1(_) => Curve::Secp256k1,
+            PublicKeyHash::P256(_) => Curve::P256,
+        }
+    }
+}
+
+impl fmt::Display for PublicKeyHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_base58_check())
+    }
+}
+
+/// Secret key of any supported curve.
+///
+/// `Debug` intentionally does not print the key itself.
+#[derive(Clone)]
+pub enum SecretKey {
+    Ed25519(ed25519::SecretKey),
+    Secp256k1(k256::SecretKey),
+    P256(p256::SecretKey),
+}
+
+impl SecretKey {
+    /// Generates new random secret key.
+    pub fn generate(curve: Curve) -> Self {
+        match curve {
+            Curve::Ed25519 => SecretKey::Ed25519(ed25519::gen_keypair().1),
+            Curve::Secp256k1 => SecretKey::Secp256k1(random_scalar(|bytes| {
+                k256::SecretKey::from_bytes(bytes).ok()
+            })),
+            Curve::P256 => SecretKey::P256(random_scalar(|bytes| {
+                p256::SecretKey::from_bytes(bytes).ok()
+            })),
+        }
+    }
+
+    /// Creates secret key from its raw binary representation
+    /// (ed25519 seed, secp256k1 or P-256 scalar).
+    pub fn from_bytes(curve: Curve, bytes: &[u8]) -> Result<Self, SignatureError> {
+        if bytes.len() != SECRET_KEY_SIZE {
+            return Err(SignatureError::InvalidKey {
+                reason: format!(
+                    "invalid secret key size - expected: {}, actual: {}",
+                    SECRET_KEY_SIZE,
+                    bytes.len()
+                ),
+            });
+        }
+        match curve {
+            Curve::Ed25519 => {
+                let seed =
+                    ed25519::Seed::from_slice(bytes).ok_or_else(|| SignatureError::InvalidKey {
+                        reason: "invalid ed25519 seed".to_string(),
+                    })?;
+                Ok(SecretKey::Ed25519(ed25519::keypair_from_seed(&seed).1))
+            }
+            Curve::Secp256k1 => k256::SecretKey::from_bytes(bytes)
+                .map(SecretKey::Secp256k1)
+                .map_err(|e| SignatureError::InvalidKey {
+                    reason: e.to_string(),
+                }),
+            Curve::P256 => p256::SecretKey::from_bytes(bytes)
+                .map(SecretKey::P256)
+                .map_err(|e| SignatureError::InvalidKey {
+                    reason: e.to_string(),
+                }),
+        }
+    }
+
+    /// Accepts both ed25519 forms used by Octez - the seed (`edsk`, 54 characters)
+    /// and the expanded secret key (`edsk`, 98 characters).
+    pub fn from_base58_check(encoded: &str) -> Result<Self, SignatureError> {
+        let bytes = encoded.from_base58check()?;
+        if let Some(seed) = strip_prefix(&bytes, &prefix_bytes::ED25519_SEED, SECRET_KEY_SIZE) {
+            SecretKey::from_bytes(Curve::Ed25519, seed)
+        } else if let Some(expanded) = strip_prefix(
+            &bytes,
+            &prefix_bytes::ED25519_SECRET_KEY,
+            ED25519_EXPANDED_SECRET_KEY_SIZE,
+        ) {
+            let secret_key = SecretKey::from_bytes(Curve::Ed25519, &expanded[..SECRET_KEY_SIZE])?;
+            // public key part has to match the seed
+            if secret_key.public_key().as_bytes() != &expanded[SECRET_KEY_SIZE..] {
+                return Err(SignatureError::InvalidKey {
+                    reason: "public key does not match the ed25519 seed".to_string(),
+                });
+            }
+            Ok(secret_key)
+        } else if let Some(data) =
+            strip_prefix(&bytes, &prefix_bytes::SECP256K1_SECRET_KEY, SECRET_KEY_SIZE)
+        {
+            SecretKey::from_bytes(Curve::Secp256k1, data)
+        } else if let Some(data) =
+            strip_prefix(&bytes, &prefix_bytes::P256_SECRET_KEY, SECRET_KEY_SIZE)
+        {
+            SecretKey::from_bytes(Curve::P256, data)
+        } else {
+            Err(SignatureError::UnknownPrefix {
+                encoded: "<secret key>".to_string(),
+            })
+        }
+    }
+
+    /// Ed25519 keys are encoded as seeds, the same as Octez does.
+    pub fn to_base58_check(&self) -> String {
+        match self {
+            SecretKey::Ed25519(_) => encode(&prefix_bytes::ED25519_SEED, &self.to_bytes()),
+            SecretKey::Secp256k1(_) => {
+                encode(&prefix_bytes::SECP256K1_SECRET_KEY, &self.to_bytes())
+            }
+            SecretKey::P256(_) => encode(&prefix_bytes::P256_SECRET_KEY, &self.to_bytes()),
+        }
+    }
+
+    /// Raw binary representation, see [`SecretKey::from_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            SecretKey::Ed25519(key) => key.0[..SECRET_KEY_SIZE].to_vec(),
+            SecretKey::Secp256k1(key) => key.to_bytes().to_vec(),
+            SecretKey::P256(key) => key.to_bytes().to_vec(),
+        }
+    }
+
+    pub fn curve(&self) -> Curve {
+        match self {
+            SecretKey::Ed25519(_) => Curve::Ed25519,
+            SecretKey::Secp256k1(_) => Curve::Secp256k1,
+            SecretKey::P256(_) => Curve::P256,
+        }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        match self {
+            SecretKey::Ed25519(key) => {
+                PublicKey::Ed25519(PublicKeyEd25519(key.public_key().0.to_vec()))
+            }
+            SecretKey::Secp256k1(key) => PublicKey::Secp256k1(PublicKeySecp256k1(
+                k256::ecdsa::SigningKey::from(key)
+                    .verify_key()
+                    .to_bytes()
+                    .to_vec(),
+            )),
+            SecretKey::P256(key) => PublicKey::P256(PublicKeyP256(
+                p256::ecdsa::SigningKey::from(key.clone())
+                    .verify_key()
+                    .to_encoded_point(true)
+                    .as_bytes()
+                    .to_vec(),
+            )),
+        }
+    }
+
+    /// Signs `data` with `watermark`, ECDSA signatures are always normalized to low `s`
+    /// as required by Tezos.
+    pub fn sign(
+        &self,
+        watermark: Option<&Watermark>,
+        data: &[u8],
+    ) -> Result<Signature, SignatureError> {
+        let digest = signed_digest(watermark, data)?;
+        match self {
+            SecretKey::Ed25519(key) => Ok(Signature::Ed25519(
+                ed25519::sign_detached(&digest, key).0,
+            )),
+            // deterministic nonce (RFC 6979 with HMAC-SHA256) - the same as libsecp256k1 used by Octez,
+            // k256 always returns signature normalized to low `s`
+            SecretKey::Secp256k1(key) => {
+                let signature: k256::ecdsa::Signature = k256::ecdsa::SigningKey::from(key)
+                    .try_sign_digest(Prehashed::new(digest))
+                    .map_err(|e| SignatureError::FailedToSign {
+                        reason: e.to_string(),
+                    })?;
+                Ok(Signature::Secp256k1(signature_bytes(signature.as_ref())?))
+            }
+            SecretKey::P256(key) => {
+                let signature: p256::ecdsa::Signature = p256::ecdsa::SigningKey::from(key.clone())
+                    .try_sign_digest(Prehashed::new(digest))
+                    .map_err(|e| SignatureError::FailedToSign {
+                        reason: e.to_string(),
+                    })?;
+                Ok(Signature::P256(normalize_s(
+                    signature_bytes(signature.as_ref())?,
+                    P256_ORDER,
+                )))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey::{:?}(..)", self.curve())
+    }
+}
+
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.curve() == other.curve() && self.to_bytes() == other.to_bytes()
+    }
+}
+
+fn encode(prefix: &[u8], data: &[u8]) -> String {
+    [prefix, data]
+        .concat()
+        .to_base58check()
+        // the longest encoded value (ed25519 signature) has 69 bytes, that is below the 128 bytes limit
+        .unwrap_or_else(|_| unreachable!("Signature data should not exceed allowed 128 bytes"))
+}
+
+fn strip_prefix<'a>(bytes: &'a [u8], prefix: &[u8], size: usize) -> Option<&'a [u8]> {
+    if bytes.len() == prefix.len() + size && bytes.starts_with(prefix) {
+        Some(&bytes[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+fn signature_bytes(bytes: &[u8]) -> Result<[u8; SIGNATURE_SIZE], SignatureError> {
+    <[u8; SIGNATURE_SIZE]>::try_from(bytes).map_err(|_| SignatureError::InvalidSignature {
+        reason: format!(
+            "invalid signature size - expected: {}, actual: {}",
+            SIGNATURE_SIZE,
+            bytes.len()
+        ),
+    })
+}
+
+/// Normalizes ECDSA signature `r || s` to "low S" form (`s <= order / 2`)
+fn normalize_s(mut signature: [u8; SIGNATURE_SIZE], order: &str) -> [u8; SIGNATURE_SIZE] {
+    let order = BigUint::parse_bytes(order.as_bytes(), 16)
+        .unwrap_or_else(|| unreachable!("Group order is valid hex number"));
+    let s = BigUint::from_bytes_be(&signature[32..]);
+    if s > &order >> 1 {
+        let low_s = (order - s).to_bytes_be();
+        let s_bytes = &mut signature[32..];
+        s_bytes.iter_mut().for_each(|b| *b = 0);
+        s_bytes[32 - low_s.len()..].copy_from_slice(&low_s);
+    }
+    signature
+}
+
+/// Already computed digest of the signed data for RustCrypto ECDSA API, which expects
+/// a hasher - finalizing returns the digest itself.
+///
+/// Other instances (created by `Default`) are plain SHA-256, which is used by HMAC-DRBG
+/// for RFC 6979 deterministic nonce (the same as libsecp256k1 nonce function).
+#[derive(Clone, Default)]
+struct Prehashed {
+    digest: Option<[u8; 32]>,
+    sha256: Sha256,
+}
+
+impl Prehashed {
+    fn new(digest: Vec<u8>) -> Self {
+        let mut prehashed = Self::default();
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&digest);
+        prehashed.digest = Some(bytes);
+        prehashed
+    }
+}
+
+impl Update for Prehashed {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.sha256.update(data);
+    }
+}
+
+impl BlockInput for Prehashed {
+    type BlockSize = <Sha256 as BlockInput>::BlockSize;
+}
+
+impl FixedOutput for Prehashed {
+    type OutputSize = U32;
+
+    fn finalize_into(self, out: &mut GenericArray<u8, Self::OutputSize>) {
+        match self.digest {
+            Some(digest) => out.copy_from_slice(&digest),
+            None => self.sha256.finalize_into(out),
+        }
+    }
+
+    fn finalize_into_reset(&mut self, out: &mut GenericArray<u8, Self::OutputSize>) {
+        match self.digest.take() {
+            Some(digest) => {
+                out.copy_from_slice(&digest);
+                self.sha256.reset();
+            }
+            None => self.sha256.finalize_into_reset(out),
+        }
+    }
+}
+
+impl Reset for Prehashed {
+    fn reset(&mut self) {
+        self.digest = None;
+        self.sha256.reset();
+    }
+}
+
+fn random_scalar<K>(from_bytes: impl Fn(&[u8]) -> Option<K>) -> K {
+    let mut rng = rand::thread_rng();
+    let mut bytes = [0u8; SECRET_KEY_SIZE];
+    loop {
+        // probability that random bytes are not a valid scalar is negligible
+        rng.fill_bytes(&mut bytes);
+        if let Some(key) = from_bytes(&bytes) {
+            return key;
+        }
+    }
+}
+
+fn ed25519_public_key(bytes: &[u8]) -> Result<ed25519::PublicKey, SignatureError> {
+    ed25519::PublicKey::from_slice(bytes).ok_or_else(|| SignatureError::InvalidKey {
+        reason: "invalid ed25519 public key".to_string(),
+    })
+}
+
+fn secp256k1_public_key(bytes: &[u8]) -> Result<k256::ecdsa::VerifyingKey, SignatureError> {
+    k256::ecdsa::VerifyingKey::from_sec1_bytes(bytes).map_err(|e| SignatureError::InvalidKey {
+        reason: format!("invalid secp256k1 public key: {}", e),
+    })
+}
+
+fn p256_public_key(bytes: &[u8]) -> Result<p256::ecdsa::VerifyingKey, SignatureError> {
+    p256::ecdsa::VerifyingKey::from_sec1_bytes(bytes).map_err(|e| SignatureError::InvalidKey {
+        reason: format!("invalid P-256 public key: {}", e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Octez sandbox bootstrap accounts
+    const BOOTSTRAP1_SK: &str = "edsk3gUfUPyBSfrS9CCgmCiQsTCHGkviBDusMxDJstFtojtc1zcpsh";
+    const BOOTSTRAP1_PK: &str = "edpkuBknW28nW72KG6RoHtYW7p12T6GKc7nAbwYX5m8Wd9sDVC9yav";
+    const BOOTSTRAP1_PKH: &str = "tz1KqTpEZ7Yob7QbPE4Hy4Wo8fHG8LhKxZSx";
+    const BOOTSTRAP2_SK: &str = "edsk39qAm1fiMjgmPkw1EgQYkMzkJezLNewd7PLNHTkr6w9XA2zdfo";
+    const BOOTSTRAP2_PK: &str = "edpktzNbDAUjUk697W7gYg2CRuBQjyPxbEg8dLccYYwKSKvkPvjtV9";
+    const BOOTSTRAP2_PKH: &str = "tz1gjaF81ZRRvdzjobyfVNsAeSC6PScjfQwN";
+
+    #[test]
+    fn test_ed25519_bootstrap_keys() -> Result<(), failure::Error> {
+        for (sk, pk, pkh) in &[
+            (BOOTSTRAP1_SK, BOOTSTRAP1_PK, BOOTSTRAP1_PKH),
+            (BOOTSTRAP2_SK, BOOTSTRAP2_PK, BOOTSTRAP2_PKH),
+        ] {
+            let secret_key = SecretKey::from_base58_check(sk)?;
+            assert_eq!(Curve::Ed25519, secret_key.curve());
+            assert_eq!(*sk, secret_key.to_base58_check());
+
+            let public_key = secret_key.public_key();
+            assert_eq!(PublicKey::from_base58_check(pk)?, public_key);
+            assert_eq!(*pk, public_key.to_base58_check());
+            assert_eq!(
+                PublicKeyHash::from_base58_check(pkh)?,
+                public_key.public_key_hash()?
+            );
+            assert_eq!(*pkh, public_key.public_key_hash()?.to_base58_check());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_ed25519_expanded_secret_key() -> Result<(), failure::Error> {
+        let secret_key = SecretKey::from_base58_check(BOOTSTRAP1_SK)?;
+        let expanded = encode(
+            &prefix_bytes::ED25519_SECRET_KEY,
+            &[
+                secret_key.to_bytes(),
+                secret_key.public_key().as_bytes().to_vec(),
+            ]
+            .concat(),
+        );
+        assert!(expanded.starts_with("edsk"));
+        assert_eq!(98, expanded.len());
+        assert_eq!(secret_key, SecretKey::from_base58_check(&expanded)?);
+
+        // public key part does not match the seed
+        let other = SecretKey::from_base58_check(BOOTSTRAP2_SK)?;
+        let mismatched = encode(
+            &prefix_bytes::ED25519_SECRET_KEY,
+            &[
+                secret_key.to_bytes(),
+                other.public_key().as_bytes().to_vec(),
+            ]
+            .concat(),
+        );
+        assert!(SecretKey::from_base58_check(&mismatched).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_base58_prefixes() {
+        let checks: Vec<(&[u8], usize, &str, usize)> = vec![
+            (&prefix_bytes::ED25519_SEED, 32, "edsk", 54),
+            (&prefix_bytes::ED25519_SECRET_KEY, 64, "edsk", 98),
+            (&prefix_bytes::SECP256K1_SECRET_KEY, 32, "spsk", 54),
+            (&prefix_bytes::P256_SECRET_KEY, 32, "p2sk", 54),
+            (&prefix_bytes::ED25519_SIGNATURE, 64, "edsig", 99),
+            (&prefix_bytes::SECP256K1_SIGNATURE, 64, "spsig1", 99),
+            (&prefix_bytes::P256_SIGNATURE, 64, "p2sig", 98),
+            (&prefix_bytes::GENERIC_SIGNATURE, 64, "sig", 96),
+        ];
+        for (prefix, size, expected_prefix, expected_len) in checks {
+            for byte in &[0x00, 0xff] {
+                let encoded = encode(prefix, &vec![*byte; size]);
+                assert!(
+                    encoded.starts_with(expected_prefix),
+                    "{} should start with {}",
+                    encoded,
+                    expected_prefix
+                );
+                assert_eq!(expected_len, encoded.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_signature_base58_round_trip() -> Result<(), failure::Error> {
+        // Octez `Signature.zero` and its ed25519 form
+        let zero = "sigMzJ4GVAvXEd2RjsKGfG2H9QvqTSKCZsuB2KiHbZRGFz72XgF6KaKADznh674fQgBatxw3xdHqTtMHUZAGRprxy64wg1aq";
+        let ed25519_zero = "edsigtXomBKi5CTRf5cjATJWSyaRvhfYNHqSUGrn4SdbYRcGwQrUGjzEfQDTuqHhuA8b2d8NarZjz8TRf65WkpQmo423BtomS8Q";
+        assert_eq!(
+            Signature::Generic([0; SIGNATURE_SIZE]),
+            Signature::from_base58_check(zero)?
+        );
+        assert_eq!(
+            zero,
+            Signature::Generic([0; SIGNATURE_SIZE]).to_base58_check()
+        );
+        assert_eq!(
+            Signature::Ed25519([0; SIGNATURE_SIZE]),
+            Signature::from_base58_check(ed25519_zero)?
+        );
+        assert_eq!(
+            ed25519_zero,
+            Signature::Ed25519([0; SIGNATURE_SIZE]).to_base58_check()
+        );
+
+        for signature in &[
+            Signature::Ed25519([1; SIGNATURE_SIZE]),
+            Signature::Secp256k1([2; SIGNATURE_SIZE]),
+            Signature::P256([3; SIGNATURE_SIZE]),
+            Signature::Generic([4; SIGNATURE_SIZE]),
+        ] {
+            let encoded = signature.to_base58_check();
+            assert_eq!(*signature, Signature::from_base58_check(&encoded)?);
+        }
+
+        assert!(Signature::from_base58_check(BOOTSTRAP1_PK).is_err());
+        assert!(Signature::from_bytes(&[0; 63]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_and_verify_all_curves() -> Result<(), failure::Error> {
+        let chain_id = ChainId::from_base58_check("NetXdQprcVkpaWU")?;
+        let watermarks = vec![
+            None,
+            Some(Watermark::Block(chain_id.clone())),
+            Some(Watermark::Endorsement(chain_id)),
+            Some(Watermark::GenericOperation),
+        ];
+        let data = hex::decode("0a0b0c0d")?;
+
+        for (curve, pk_prefix, pkh_prefix) in &[
+            (Curve::Ed25519, "edpk", "tz1"),
+            (Curve::Secp256k1, "sppk", "tz2"),
+            (Curve::P256, "p2pk", "tz3"),
+        ] {
+            let secret_key = SecretKey::generate(*curve);
+            assert_eq!(
+                secret_key,
+                SecretKey::from_base58_check(&secret_key.to_base58_check())?
+            );
+
+            let public_key = secret_key.public_key();
+            let encoded = public_key.to_base58_check();
+            assert!(encoded.starts_with(pk_prefix));
+            assert_eq!(public_key, PublicKey::from_base58_check(&encoded)?);
+            let pkh = public_key.public_key_hash()?;
+            assert!(pkh.to_base58_check().starts_with(pkh_prefix));
+            assert_eq!(
+                pkh,
+                PublicKeyHash::from_base58_check(&pkh.to_base58_check())?
+            );
+
+            for watermark in &watermarks {
+                let signature = secret_key.sign(watermark.as_ref(), &data)?;
+                assert_eq!(Some(*curve), signature.curve());
+                assert!(public_key.verify(watermark.as_ref(), &data, &signature)?);
+                assert!(public_key.verify(watermark.as_ref(), &data, &signature.to_generic())?);
+                assert!(!public_key.verify(watermark.as_ref(), b"other data", &signature)?);
+            }
+
+            // signature with one watermark is not valid with another one
+            let signature = secret_key.sign(Some(&Watermark::GenericOperation), &data)?;
+            assert!(!public_key.verify(None, &data, &signature)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_ed25519_signature_is_deterministic() -> Result<(), failure::Error> {
+        let secret_key = SecretKey::from_base58_check(BOOTSTRAP1_SK)?;
+        let public_key = PublicKey::from_base58_check(BOOTSTRAP1_PK)?;
+        let data = hex::decode("0a0b0c0d")?;
+
+        let signature = secret_key.sign(Some(&Watermark::GenericOperation), &data)?;
+        assert_eq!(
+            signature,
+            secret_key.sign(Some(&Watermark::GenericOperation), &data)?
+        );
+        assert!(public_key.verify(Some(&Watermark::GenericOperation), &data, &signature)?);
+
+        // curves do not match
+        let other = SecretKey::generate(Curve::P256).public_key();
+        assert!(other.verify(None, &data, &signature).is_err());
+        Ok(())
+    }
+
+    // ECDSA vectors generated with OpenSSL (RFC 6979 deterministic nonce with HMAC-SHA256,
+    // the same nonce function as libsecp256k1 used by Octez, normalized to low `s`),
+    // signed data is `0a0b0c0d` without watermark and with generic operation watermark
+    const SECP256K1_SK: &str = "spsk25ByooAyJRVipxkE3z15LEhEZySmenbHzFiNSA9sK1F95sgvsm";
+    const SECP256K1_PK: &str = "sppk7cu8p3fK4DGALe9URuUgAQzD7Pf5xTEiyccHS4kcibAWBXgLE5u";
+    const SECP256K1_SIG: &str = "spsig1MsApxKYjShmWpTbsT9yck1iDRzEon3AEsyobScVG5nDj6N8D7jTwZhx33QacnPw6gjAsqvsSuM98b7DmmyQj4tpQYsmju";
+    const SECP256K1_SIG_GENERIC_OPERATION: &str = "spsig1BAXPKD5vQYuB8MrnWmX8HW7ccCJCVj5p3RHnCxUaRoV77fjA5QJGKNALAZB5HUHBX5U5B8ybXDTLKdCfZuu3VQ5XviHN5";
+    const P256_SK: &str = "p2sk4DSAnUjKDXM98t8PET1wuvFAQ6fvs6xMRJTYQKoPkwzwZBT1qQ";
+    const P256_PK: &str = "p2pk668yvoUm76XjxL9guv1GaQemo1jwduzJCrd8xF6GsRLqt189jHb";
+    const P256_SIG: &str = "p2sigsjprpkTBCczwqWMcPW1F1BDYDBQ1iCfphoWtManZmwA6bahdJogMJnA6CzDswxnBRzWeKdvCa9HKSKGDgyh4go5NvcXft";
+    const P256_SIG_GENERIC_OPERATION: &str = "p2sigQyUitsnjxRiakUU27bCvDxFs6eXAfvboQRuwmkivCeoAbz7Eaz8tAZUTpvkFrjHBgF4DMKe2A8VgY6mtQoNzp4yat2BhN";
+
+    #[test]
+    fn test_ecdsa_signature_vectors() -> Result<(), failure::Error> {
+        let data = hex::decode("0a0b0c0d")?;
+        for (sk, pk, sig, sig_generic_operation) in &[
+            (
+                SECP256K1_SK,
+                SECP256K1_PK,
+                SECP256K1_SIG,
+                SECP256K1_SIG_GENERIC_OPERATION,
+            ),
+            (P256_SK, P256_PK, P256_SIG, P256_SIG_GENERIC_OPERATION),
+        ] {
+            let secret_key = SecretKey::from_base58_check(sk)?;
+            let public_key = PublicKey::from_base58_check(pk)?;
+            assert_eq!(public_key, secret_key.public_key());
+
+            for (watermark, expected) in &[
+                (None, sig),
+                (Some(&Watermark::GenericOperation), sig_generic_operation),
+            ] {
+                let expected = Signature::from_base58_check(expected)?;
+                assert!(public_key.verify(*watermark, &data, &expected)?);
+                assert!(public_key.verify(*watermark, &data, &expected.to_generic())?);
+                // signing is deterministic, so we produce exactly the same signature
+                assert_eq!(expected, secret_key.sign(*watermark, &data)?);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_secp256k1_high_s_signature_is_rejected() -> Result<(), failure::Error> {
+        let public_key = PublicKey::from_base58_check(SECP256K1_PK)?;
+        let data = hex::decode("0a0b0c0d")?;
+        // `s` of `SECP256K1_SIG` replaced by `order - s`
+        let high_s = hex::decode(
+            "7ab80974b5643bcb8bb5f919dc4f577beb45f142bc5ad64b15bad4864e1af7a7\
+             b2546ddf146aeee2b8e879ab7ac1040d746da0598c8eb109f7af463b388bdfa1",
+        )?;
+        let signature = Signature::Secp256k1(signature_bytes(&high_s)?);
+        assert!(!public_key.verify(None, &data, &signature)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ecdsa_signature_is_low_s() -> Result<(), failure::Error> {
+        // half of the secp256k1 group order
+        let half_order =
+            hex::decode("7fffffffffffffffffffffffffffffff5d576e7357a4501ddfe92f46681b20a0")?;
+        let secret_key = SecretKey::generate(Curve::Secp256k1);
+        for i in 0..16u8 {
+            let signature = secret_key.sign(None, &[i])?;
+            assert!(signature.as_bytes()[32..] <= half_order[..]);
+        }
+        Ok(())
+    }
+}
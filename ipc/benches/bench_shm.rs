@@ -12,7 +12,11 @@ use test::Bencher;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use ipc::{temp_sock, IpcClient, IpcServer};
+use ipc::{temp_sock, IpcClient, IpcServer, IpcTransport};
+
+const SHARED_MEMORY: IpcTransport = IpcTransport::SharedMemory {
+    capacity: 32 * 1024 * 1024,
+};
 
 #[derive(Serialize, Deserialize)]
 struct BenchData {
@@ -33,35 +37,34 @@ fn fork<F: FnOnce()>(child_func: F) -> libc::pid_t {
     }
 }
 
-fn rand_file_name() -> String {
-    let path = temp_sock();
-    path.as_os_str().to_str().unwrap().to_string()
-}
-
-#[bench]
-fn bench_shm(b: &mut Bencher) {
-    use ipmpsc::*;
-    let name_parent = rand_file_name();
-    let name_child = rand_file_name();
-    let buffer_parent = SharedRingBuffer::create(&name_parent, 32 * 1024 * 1024).unwrap();
-    let buffer_child = SharedRingBuffer::create(&name_child, 32 * 1024 * 1024).unwrap();
+/// Child echoes every message back, parent measures round trips
+fn bench_round_trip(b: &mut Bencher, transport: IpcTransport) {
+    let sock_path = temp_sock();
 
     let child_pid = fork(|| {
-        let rx = Receiver::new(buffer_child);
-        let tx = Sender::new(SharedRingBuffer::open(&name_parent).unwrap());
+        // wait for parent to be ready
+        let sock_path = sock_path.as_path();
+        while !sock_path.exists() {
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let client: IpcClient<BenchData, BenchData> = IpcClient::new(&sock_path);
+        let (mut rx, mut tx) = client.connect().unwrap();
         let mut rng = rand::thread_rng();
         let bench_data = BenchData {
             block_hash: (0..100).map(|_| rng.gen_range(0, 254)).collect(),
             chain_hash: (0..64).map(|_| rng.gen_range(0, 254)).collect(),
         };
-        while rx.recv::<BenchData>().is_ok() {
+        while rx.receive().is_ok() {
             tx.send(&bench_data).unwrap();
         }
     });
     assert!(child_pid > 0);
 
-    let rx = Receiver::new(buffer_parent);
-    let tx = Sender::new(SharedRingBuffer::open(&name_child).unwrap());
+    let mut server: IpcServer<BenchData, BenchData> = IpcServer::bind_path(&sock_path)
+        .unwrap()
+        .with_transport(transport);
+    let (mut rx, mut tx) = server.try_accept(Duration::from_secs(3)).unwrap();
 
     let mut rng = rand::thread_rng();
     let bench_data = BenchData {
@@ -71,47 +74,60 @@ fn bench_shm(b: &mut Bencher) {
     b.iter(|| {
         for _ in 0..100 {
             tx.send(&bench_data).unwrap();
-            let _ = rx.recv::<BenchData>().unwrap();
+            let _ = rx.receive().unwrap();
         }
     });
 }
 
-#[bench]
-fn bench_uds(b: &mut Bencher) {
+/// Only the child sends (like context actions from protocol runner), parent measures throughput
+fn bench_one_way(b: &mut Bencher, transport: IpcTransport) {
     let sock_path = temp_sock();
 
     let child_pid = fork(|| {
-        // wait for parent to be ready
         let sock_path = sock_path.as_path();
         while !sock_path.exists() {
             thread::sleep(Duration::from_millis(20));
         }
 
         let client: IpcClient<BenchData, BenchData> = IpcClient::new(&sock_path);
-        let (mut rx, mut tx) = client.connect().unwrap();
+        let (_, mut tx) = client.connect().unwrap();
         let mut rng = rand::thread_rng();
         let bench_data = BenchData {
             block_hash: (0..100).map(|_| rng.gen_range(0, 254)).collect(),
             chain_hash: (0..64).map(|_| rng.gen_range(0, 254)).collect(),
         };
-        while rx.receive().is_ok() {
-            tx.send(&bench_data).unwrap();
-        }
+        while tx.send(&bench_data).is_ok() {}
     });
     assert!(child_pid > 0);
 
-    let mut server: IpcServer<BenchData, BenchData> = IpcServer::bind_path(&sock_path).unwrap();
-    let (mut rx, mut tx) = server.try_accept(Duration::from_secs(3)).unwrap();
+    let mut server: IpcServer<BenchData, BenchData> = IpcServer::bind_path(&sock_path)
+        .unwrap()
+        .with_transport(transport);
+    let (mut rx, _) = server.try_accept(Duration::from_secs(3)).unwrap();
 
-    let mut rng = rand::thread_rng();
-    let bench_data = BenchData {
-        block_hash: (0..100).map(|_| rng.gen_range(0, 254)).collect(),
-        chain_hash: (0..64).map(|_| rng.gen_range(0, 254)).collect(),
-    };
     b.iter(|| {
-        for _ in 0..100 {
-            tx.send(&bench_data).unwrap();
+        for _ in 0..1000 {
             let _ = rx.receive().unwrap();
         }
     });
 }
+
+#[bench]
+fn bench_shm(b: &mut Bencher) {
+    bench_round_trip(b, SHARED_MEMORY)
+}
+
+#[bench]
+fn bench_uds(b: &mut Bencher) {
+    bench_round_trip(b, IpcTransport::Socket)
+}
+
+#[bench]
+fn bench_shm_one_way(b: &mut Bencher) {
+    bench_one_way(b, SHARED_MEMORY)
+}
+
+#[bench]
+fn bench_uds_one_way(b: &mut Bencher) {
+    bench_one_way(b, IpcTransport::Socket)
+}
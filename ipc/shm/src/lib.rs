@@ -0,0 +1,14 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+#![deny(unsafe_code)]
+
+//! Memory mapped files shared between processes.
+//!
+//! This is the only place of the IPC which needs `unsafe` code, so it is kept in a separate crate
+//! and the `ipc` crate itself can `#![forbid(unsafe_code)]`. All unsafe code is in the `mapping`
+//! module, which exposes a safe, bounds checked API.
+
+pub use mapping::SharedMapping;
+
+#[allow(unsafe_code)]
+mod mapping;
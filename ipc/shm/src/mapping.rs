@@ -0,0 +1,178 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::fs::File;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicU32, AtomicU64};
+
+/// Read-write shared mapping of the whole file.
+///
+/// Memory is shared with other processes, so its content can change at any time. Shared state must be
+/// accessed through atomics, plain data are copied in/out only by [`SharedMapping::write`] and [`SharedMapping::read`]
+/// and it is up to the users (e.g. ring buffer positions) to never copy the same region concurrently.
+pub struct SharedMapping {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: the mapping is not tied to the thread which created it and all accesses go through
+// atomics or bounds checked copies, see the type documentation.
+unsafe impl Send for SharedMapping {}
+// SAFETY: as above, `&self` methods only use atomics or copies of regions coordinated by the users.
+unsafe impl Sync for SharedMapping {}
+
+impl SharedMapping {
+    /// Maps the first `len` bytes of `file`, which has to be opened for reading and writing.
+    pub fn map(file: &File, len: usize) -> io::Result<Self> {
+        if len == 0 || (file.metadata()?.len() as usize) < len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot map {} bytes of the file", len),
+            ));
+        }
+        // SAFETY: new mapping at the address chosen by the kernel does not alias any Rust memory,
+        // file is long enough (checked above), so no page of the mapping is beyond its end.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        match NonNull::new(ptr as *mut u8) {
+            Some(ptr) => Ok(Self { ptr, len }),
+            None => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "mmap returned null pointer",
+            )),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Atomic at `offset`, which has to be a multiple of 8.
+    pub fn atomic_u64(&self, offset: usize) -> &AtomicU64 {
+        self.check_atomic(offset, 8);
+        // SAFETY: in bounds and aligned (mapping is page aligned), atomics are valid for any bit pattern
+        // and can be shared between threads (and processes) through shared reference.
+        unsafe { &*(self.ptr.as_ptr().add(offset) as *const AtomicU64) }
+    }
+
+    /// Atomic at `offset`, which has to be a multiple of 4.
+    pub fn atomic_u32(&self, offset: usize) -> &AtomicU32 {
+        self.check_atomic(offset, 4);
+        // SAFETY: the same as for `atomic_u64`
+        unsafe { &*(self.ptr.as_ptr().add(offset) as *const AtomicU32) }
+    }
+
+    /// Copy `data` to the mapping at `offset`.
+    pub fn write(&self, offset: usize, data: &[u8]) {
+        self.check_range(offset, data.len());
+        // SAFETY: destination is in bounds (checked above) and cannot overlap `data`,
+        // which is an ordinary Rust slice.
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), self.ptr.as_ptr().add(offset), data.len());
+        }
+    }
+
+    /// Copy data from the mapping at `offset` to `data`.
+    pub fn read(&self, offset: usize, data: &mut [u8]) {
+        self.check_range(offset, data.len());
+        // SAFETY: source is in bounds (checked above) and cannot overlap `data`,
+        // which is an ordinary Rust slice.
+        unsafe {
+            ptr::copy_nonoverlapping(self.ptr.as_ptr().add(offset), data.as_mut_ptr(), data.len());
+        }
+    }
+
+    fn check_atomic(&self, offset: usize, size: usize) {
+        assert_eq!(offset % size, 0, "unaligned atomic at offset {}", offset);
+        self.check_range(offset, size);
+    }
+
+    fn check_range(&self, offset: usize, size: usize) {
+        assert!(
+            offset
+                .checked_add(size)
+                .map_or(false, |end| end <= self.len),
+            "range {}+{} out of the mapping of {} bytes",
+            offset,
+            size,
+            self.len
+        );
+    }
+}
+
+impl Drop for SharedMapping {
+    fn drop(&mut self) {
+        // SAFETY: unmaps exactly the region mapped in `map`, references returned by `&self` methods
+        // cannot outlive `self`.
+        unsafe {
+            libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    fn file(len: u64) -> io::Result<File> {
+        let file = tempfile::tempfile()?;
+        file.set_len(len)?;
+        Ok(file)
+    }
+
+    #[test]
+    fn test_mappings_share_data() -> io::Result<()> {
+        let file = file(64)?;
+        let first = SharedMapping::map(&file, 64)?;
+        let second = SharedMapping::map(&file, 64)?;
+
+        first.atomic_u64(8).store(42, Ordering::Release);
+        assert_eq!(42, second.atomic_u64(8).load(Ordering::Acquire));
+
+        first.write(60, b"abcd");
+        let mut data = [0u8; 4];
+        second.read(60, &mut data);
+        assert_eq!(b"abcd", &data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_beyond_file_fails() -> io::Result<()> {
+        assert!(SharedMapping::map(&file(64)?, 65).is_err());
+        assert!(SharedMapping::map(&file(64)?, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_write_out_of_bounds_panics() {
+        let mapping = SharedMapping::map(&file(64).unwrap(), 64).unwrap();
+        mapping.write(61, b"abcd");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unaligned_atomic_panics() {
+        let mapping = SharedMapping::map(&file(64).unwrap(), 64).unwrap();
+        mapping.atomic_u32(2);
+    }
+}
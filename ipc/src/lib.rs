@@ -5,6 +5,8 @@
 //! Provides IPC communication.
 //!
 //! The IPC is implemented as unix domain sockets. Functionality is similar to how network sockets work.
+//! Messages of a connection can be transferred over the socket itself or over ring buffers in shared memory,
+//! see [`IpcTransport`].
 //!
 //! TODO: TE-292 - investigate/reimplement
 
@@ -24,6 +26,10 @@ use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
 
+use crate::shm::{SharedRingBuffer, ShmReceiver, ShmSender};
+
+mod shm;
+
 /// IPC communication errors
 #[derive(Debug, Fail)]
 pub enum IpcError {
@@ -47,25 +53,75 @@ pub enum IpcError {
     SplitError { reason: io::Error },
     #[fail(display = "Socker configuration error: {}", reason)]
     SocketConfigurationError { reason: io::Error },
+    #[fail(display = "Shared memory error: {}", reason)]
+    SharedMemoryError { reason: io::Error },
+}
+
+/// How messages of the IPC channel are transferred, selected by [`IpcServer`] for every accepted connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IpcTransport {
+    /// Length-prefixed frames written to the unix socket
+    Socket,
+    /// Lock-free ring buffers in shared memory (one for each direction) with `capacity` in bytes,
+    /// unix socket is used only to set up the channel and to detect disconnected peer.
+    ///
+    /// Single message cannot be larger than the capacity.
+    SharedMemory { capacity: usize },
+}
+
+impl Default for IpcTransport {
+    fn default() -> Self {
+        IpcTransport::Socket
+    }
+}
+
+/// The first message of every connection, sent by the server to the client.
+#[derive(Serialize, Deserialize, Debug)]
+enum ChannelSetup {
+    Socket,
+    SharedMemory {
+        server_to_client: PathBuf,
+        client_to_server: PathBuf,
+    },
 }
 
 /// Represents sending end of the IPC channel.
-pub struct IpcSender<S>(UnixStream, PhantomData<S>);
+pub struct IpcSender<S>(SenderChannel, PhantomData<S>);
+
+enum SenderChannel {
+    Socket(UnixStream),
+    SharedMemory(ShmSender),
+}
 
 impl<S> IpcSender<S> {
     /// Close IPC channel and release associated resources.
     ///
     /// This closes only the sending part of the IPC channel.
     fn shutdown(&self) -> Result<(), io::Error> {
-        self.0.shutdown(Shutdown::Write)
+        match &self.0 {
+            SenderChannel::Socket(stream) => stream.shutdown(Shutdown::Write),
+            SenderChannel::SharedMemory(sender) => {
+                sender.shutdown();
+                Ok(())
+            }
+        }
     }
 
     pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
-        self.0.set_write_timeout(timeout)
+        match &self.0 {
+            SenderChannel::Socket(stream) => stream.set_write_timeout(timeout),
+            SenderChannel::SharedMemory(sender) => sender.set_write_timeout(timeout),
+        }
     }
 
     pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
-        self.0.set_nonblocking(nonblocking)
+        match &self.0 {
+            SenderChannel::Socket(stream) => stream.set_nonblocking(nonblocking),
+            SenderChannel::SharedMemory(sender) => {
+                sender.set_nonblocking(nonblocking);
+                Ok(())
+            }
+        }
     }
 }
 
@@ -74,19 +130,12 @@ impl<S: Serialize> IpcSender<S> {
     ///
     /// This is a blocking operation,
     pub fn send(&mut self, value: &S) -> Result<(), IpcError> {
-        let msg_buf = bincode::serialize(value).map_err(|err| IpcError::SerializationError {
-            reason: format!("{:?}", err),
-        })?;
-        let msg_len_buf = msg_buf.len().to_be_bytes();
-        self.0
-            .write_all(&msg_len_buf)
-            .map_err(|err| IpcError::SendError { reason: err })?;
-        self.0
-            .write_all(&msg_buf)
-            .map_err(|err| IpcError::SendError { reason: err })?;
-        self.0
-            .flush()
-            .map_err(|err| IpcError::SendError { reason: err })
+        let msg_buf = serialize(value)?;
+        match &mut self.0 {
+            SenderChannel::Socket(stream) => write_frame(stream, &msg_buf),
+            SenderChannel::SharedMemory(sender) => sender.send(&msg_buf),
+        }
+        .map_err(|err| IpcError::SendError { reason: err })
     }
 }
 
@@ -97,22 +146,65 @@ impl<S> Drop for IpcSender<S> {
 }
 
 /// Represents receiving end of the IPC channel.
-pub struct IpcReceiver<R>(UnixStream, PhantomData<R>);
+pub struct IpcReceiver<R>(ReceiverChannel, PhantomData<R>);
+
+enum ReceiverChannel {
+    Socket(UnixStream),
+    SharedMemory(ShmReceiver),
+}
 
 impl<R> IpcReceiver<R> {
     /// Close IPC channel and release associated resources.
     ///
     /// This closes only the receiving part of the IPC channel.
     fn shutdown(&self) -> Result<(), io::Error> {
-        self.0.shutdown(Shutdown::Read)
+        match &self.0 {
+            ReceiverChannel::Socket(stream) => stream.shutdown(Shutdown::Read),
+            ReceiverChannel::SharedMemory(receiver) => {
+                receiver.shutdown();
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns handle, which can close this IPC channel from another thread, see [`IpcChannelCloser`].
+    pub fn closer(&self) -> Result<IpcChannelCloser, IpcError> {
+        let stream = match &self.0 {
+            ReceiverChannel::Socket(stream) => stream,
+            ReceiverChannel::SharedMemory(receiver) => receiver.peer(),
+        };
+        stream
+            .try_clone()
+            .map(IpcChannelCloser)
+            .map_err(|err| IpcError::SplitError { reason: err })
     }
 
     pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
-        self.0.set_read_timeout(timeout)
+        match &self.0 {
+            ReceiverChannel::Socket(stream) => stream.set_read_timeout(timeout),
+            ReceiverChannel::SharedMemory(receiver) => receiver.set_read_timeout(timeout),
+        }
     }
 
     pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
-        self.0.set_nonblocking(nonblocking)
+        match &self.0 {
+            ReceiverChannel::Socket(stream) => stream.set_nonblocking(nonblocking),
+            ReceiverChannel::SharedMemory(receiver) => {
+                receiver.set_nonblocking(nonblocking);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Closes both directions of the IPC channel, it was created for (see [`IpcReceiver::closer`]).
+///
+/// Blocked receive on the channel fails, like if the other side disconnected, and so does the other side.
+pub struct IpcChannelCloser(UnixStream);
+
+impl IpcChannelCloser {
+    pub fn close(&self) -> Result<(), io::Error> {
+        self.0.shutdown(Shutdown::Both)
     }
 }
 
@@ -140,25 +232,16 @@ where
 
     /// Read bytes from established IPC channel and deserialize into a rust type.
     pub fn receive(&mut self) -> Result<R, IpcError> {
-        let mut msg_len_buf = [0; 8];
-        self.0.read_exact(&mut msg_len_buf).map_err(|err| {
-            if err.kind() == io::ErrorKind::WouldBlock {
-                IpcError::ReceiveMessageTimeouted
-            } else {
-                IpcError::ReceiveMessageLengthError { reason: err }
+        let msg_buf = match &mut self.0 {
+            ReceiverChannel::Socket(stream) => read_frame(stream)?,
+            ReceiverChannel::SharedMemory(receiver) => {
+                receiver.receive().map_err(|err| match err.kind() {
+                    io::ErrorKind::WouldBlock => IpcError::ReceiveMessageTimeouted,
+                    _ => IpcError::ReceiveMessageLengthError { reason: err },
+                })?
             }
-        })?;
-
-        let msg_len = usize::from_be_bytes(msg_len_buf);
-
-        let mut msg_buf = vec![0u8; msg_len];
-        self.0
-            .read_exact(&mut msg_buf)
-            .map_err(|err| IpcError::ReceiveMessageError { reason: err })?;
-
-        bincode::deserialize(&msg_buf).map_err(|err| IpcError::DeserializationError {
-            reason: format!("{:?}", err),
-        })
+        };
+        deserialize(&msg_buf)
     }
 }
 
@@ -172,6 +255,7 @@ impl<R> Drop for IpcReceiver<R> {
 pub struct IpcServer<R, S> {
     listener: UnixListener,
     pub path: PathBuf,
+    transport: IpcTransport,
     _phantom_r: PhantomData<R>,
     _phantom_s: PhantomData<S>,
 }
@@ -205,11 +289,18 @@ where
         Ok(IpcServer {
             listener,
             path: path_buf,
+            transport: IpcTransport::default(),
             _phantom_r: PhantomData,
             _phantom_s: PhantomData,
         })
     }
 
+    /// Set transport of connections accepted by this server, default is [`IpcTransport::Socket`].
+    pub fn with_transport(mut self, transport: IpcTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
     /// Try to accept new connection a return sender/receiver for it
     /// In case of timeout, can be IpcError::AcceptTimeout handled
     ///
@@ -265,7 +356,21 @@ where
         // maybe it is enought to set non_blocking to the [`stream`], but we make sure,
         // also On macOS and FreeBSD new sockets inherit flags from accepting fd,
         // but we expect this to be in blocking by default.
-        split(stream.0, false, false)
+        let mut stream = stream.0;
+        stream
+            .set_nonblocking(false)
+            .map_err(|err| IpcError::SocketConfigurationError { reason: err })?;
+
+        match self.transport {
+            IpcTransport::Socket => {
+                write_frame(&mut stream, &serialize(&ChannelSetup::Socket)?)
+                    .map_err(|err| IpcError::SendError { reason: err })?;
+                split(stream, false, false)
+            }
+            IpcTransport::SharedMemory { capacity } => {
+                accept_shared_memory(stream, capacity, timeout)
+            }
+        }
     }
 
     /// Create new IpcClient for this server
@@ -307,22 +412,147 @@ where
 
     /// Try to open new connection.
     pub fn connect(&self) -> Result<(IpcReceiver<R>, IpcSender<S>), IpcError> {
-        let stream = UnixStream::connect(&self.path)
+        let mut stream = UnixStream::connect(&self.path)
             .map_err(|err| IpcError::ConnectionError { reason: err })?;
-        split(stream, false, false)
+        match deserialize(&read_frame(&mut stream)?)? {
+            ChannelSetup::Socket => split(stream, false, false),
+            ChannelSetup::SharedMemory {
+                server_to_client,
+                client_to_server,
+            } => connect_shared_memory(stream, &server_to_client, &client_to_server),
+        }
     }
 }
 
 /// Crate new randomly named unix domain socket file in temp directory.
 pub fn temp_sock() -> PathBuf {
+    env::temp_dir().join(random_name() + ".sock")
+}
+
+fn random_name() -> String {
     let mut rng = thread_rng();
-    let temp_dir = env::temp_dir();
-    let chars = iter::repeat(())
+    iter::repeat(())
         .map(|()| rng.sample(Alphanumeric))
         .take(7)
-        .collect::<String>();
+        .collect::<String>()
+}
+
+fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, IpcError> {
+    bincode::serialize(value).map_err(|err| IpcError::SerializationError {
+        reason: format!("{:?}", err),
+    })
+}
+
+fn deserialize<T: for<'de> Deserialize<'de>>(msg_buf: &[u8]) -> Result<T, IpcError> {
+    bincode::deserialize(msg_buf).map_err(|err| IpcError::DeserializationError {
+        reason: format!("{:?}", err),
+    })
+}
 
-    temp_dir.join(chars + ".sock")
+fn write_frame(stream: &mut UnixStream, msg_buf: &[u8]) -> Result<(), io::Error> {
+    let msg_len_buf = msg_buf.len().to_be_bytes();
+    stream.write_all(&msg_len_buf)?;
+    stream.write_all(msg_buf)?;
+    stream.flush()
+}
+
+fn read_frame(stream: &mut UnixStream) -> Result<Vec<u8>, IpcError> {
+    let mut msg_len_buf = [0; 8];
+    stream.read_exact(&mut msg_len_buf).map_err(|err| {
+        if err.kind() == io::ErrorKind::WouldBlock {
+            IpcError::ReceiveMessageTimeouted
+        } else {
+            IpcError::ReceiveMessageLengthError { reason: err }
+        }
+    })?;
+
+    let msg_len = usize::from_be_bytes(msg_len_buf);
+
+    let mut msg_buf = vec![0u8; msg_len];
+    stream
+        .read_exact(&mut msg_buf)
+        .map_err(|err| IpcError::ReceiveMessageError { reason: err })?;
+    Ok(msg_buf)
+}
+
+/// Server creates both ring buffers and waits until client maps them, then their files can be removed.
+fn accept_shared_memory<R, S>(
+    mut stream: UnixStream,
+    capacity: usize,
+    timeout: Duration,
+) -> Result<(IpcReceiver<R>, IpcSender<S>), IpcError> {
+    let (server_to_client, sender_buffer) = SharedRingBuffer::create_temp(capacity)
+        .map_err(|err| IpcError::SharedMemoryError { reason: err })?;
+    let receiver_buffer = SharedRingBuffer::create_temp(capacity)
+        .map_err(|err| IpcError::SharedMemoryError { reason: err })
+        .and_then(|(client_to_server, receiver_buffer)| {
+            let result = send_shared_memory_setup(
+                &mut stream,
+                &server_to_client,
+                &client_to_server,
+                timeout,
+            );
+            let _ = fs::remove_file(&client_to_server);
+            result.map(|_| receiver_buffer)
+        });
+    let _ = fs::remove_file(&server_to_client);
+
+    split_shared_memory(stream, receiver_buffer?, sender_buffer)
+}
+
+fn send_shared_memory_setup(
+    stream: &mut UnixStream,
+    server_to_client: &Path,
+    client_to_server: &Path,
+    timeout: Duration,
+) -> Result<(), IpcError> {
+    let setup = ChannelSetup::SharedMemory {
+        server_to_client: server_to_client.to_path_buf(),
+        client_to_server: client_to_server.to_path_buf(),
+    };
+    write_frame(stream, &serialize(&setup)?).map_err(|err| IpcError::SendError { reason: err })?;
+
+    // client confirms, that buffers are mapped
+    stream
+        .set_read_timeout(Some(timeout))
+        .map_err(|err| IpcError::SocketConfigurationError { reason: err })?;
+    read_frame(stream).map(|_| ())
+}
+
+fn connect_shared_memory<R, S>(
+    mut stream: UnixStream,
+    server_to_client: &Path,
+    client_to_server: &Path,
+) -> Result<(IpcReceiver<R>, IpcSender<S>), IpcError> {
+    let receiver_buffer = SharedRingBuffer::open(server_to_client)
+        .map_err(|err| IpcError::SharedMemoryError { reason: err })?;
+    let sender_buffer = SharedRingBuffer::open(client_to_server)
+        .map_err(|err| IpcError::SharedMemoryError { reason: err })?;
+    write_frame(&mut stream, &[]).map_err(|err| IpcError::SendError { reason: err })?;
+
+    split_shared_memory(stream, receiver_buffer, sender_buffer)
+}
+
+fn split_shared_memory<R, S>(
+    stream: UnixStream,
+    receiver_buffer: SharedRingBuffer,
+    sender_buffer: SharedRingBuffer,
+) -> Result<(IpcReceiver<R>, IpcSender<S>), IpcError> {
+    // socket is only watched for disconnection from now on
+    let receiver = IpcReceiver(
+        ReceiverChannel::SharedMemory(ShmReceiver::new(
+            receiver_buffer,
+            stream
+                .try_clone()
+                .map_err(|err| IpcError::SplitError { reason: err })?,
+        )),
+        PhantomData,
+    );
+    let sender = IpcSender(
+        SenderChannel::SharedMemory(ShmSender::new(sender_buffer, stream)),
+        PhantomData,
+    );
+    Ok((receiver, sender))
 }
 
 fn split<R, S>(
@@ -335,16 +565,18 @@ where
     S: Serialize,
 {
     let receiver = IpcReceiver(
-        stream
-            .try_clone()
-            .map_err(|err| IpcError::SplitError { reason: err })?,
+        ReceiverChannel::Socket(
+            stream
+                .try_clone()
+                .map_err(|err| IpcError::SplitError { reason: err })?,
+        ),
         PhantomData,
     );
     receiver
         .set_nonblocking(receiver_non_blocking)
         .map_err(|err| IpcError::SocketConfigurationError { reason: err })?;
 
-    let sender = IpcSender(stream, PhantomData);
+    let sender = IpcSender(SenderChannel::Socket(stream), PhantomData);
     sender
         .set_nonblocking(sender_non_blocking)
         .map_err(|err| IpcError::SocketConfigurationError { reason: err })?;
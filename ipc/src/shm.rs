@@ -0,0 +1,428 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Shared memory transport of the IPC channel.
+//!
+//! Every direction of the channel is a lock-free single producer/single consumer ring buffer
+//! stored in a memory mapped file (in `/dev/shm` when available). Frames are the same as on
+//! the unix socket - length followed by the bincode serialized message. Producer and consumer
+//! publish their positions with release/acquire atomics, so the data path needs no locks nor syscalls.
+//!
+//! The unix socket of the connection is kept open just to detect a disconnected peer
+//! (e.g. crashed protocol runner), which cannot clear its closed flag in the buffer.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::net::UnixStream;
+use std::os::unix::prelude::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use std::{env, hint, thread};
+
+use ipc_shm::SharedMapping;
+use nix::sys::socket::{recv, MsgFlags};
+
+/// Marks initialized buffer
+const MAGIC: u64 = u64::from_be_bytes(*b"TZDGESHM");
+
+/// Header layout, producer and consumer positions are on separate cache lines to avoid false sharing
+const HEADER_SIZE: usize = 256;
+const MAGIC_OFFSET: usize = 0;
+const CAPACITY_OFFSET: usize = 8;
+const WRITE_POSITION_OFFSET: usize = 64;
+const READ_POSITION_OFFSET: usize = 128;
+const SENDER_CLOSED_OFFSET: usize = 192;
+const RECEIVER_CLOSED_OFFSET: usize = 196;
+
+/// Every message is prefixed with its length
+const FRAME_LENGTH_SIZE: usize = 8;
+
+/// Waiting for the peer - busy spin first, then yield and finally sleep
+const SPIN_ATTEMPTS: u32 = 64;
+const YIELD_ATTEMPTS: u32 = SPIN_ATTEMPTS + 64;
+const MAX_SLEEP: Duration = Duration::from_millis(1);
+
+/// Ring buffer mapped from a file shared by both processes.
+///
+/// Shared state is accessed only through atomics, data are copied only by the single producer/consumer,
+/// which requires `&mut self` in send/receive.
+pub(crate) struct SharedRingBuffer {
+    mapping: SharedMapping,
+    capacity: usize,
+}
+
+impl SharedRingBuffer {
+    /// Create new buffer backed by randomly named file.
+    pub(crate) fn create_temp(capacity: usize) -> io::Result<(PathBuf, Self)> {
+        let path = temp_shm_path();
+        let buffer = Self::create(&path, capacity)?;
+        Ok((path, buffer))
+    }
+
+    pub(crate) fn create(path: &Path, capacity: usize) -> io::Result<Self> {
+        if capacity <= FRAME_LENGTH_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("shared memory capacity too small: {}", capacity),
+            ));
+        }
+        // only the owner (the node and its protocol runners) can access the buffer
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)?;
+        file.set_len((HEADER_SIZE + capacity) as u64)?;
+
+        // new file is zeroed, so positions and flags are already initialized
+        let buffer = Self::map(&file, capacity)?;
+        buffer
+            .atomic_u64(CAPACITY_OFFSET)
+            .store(capacity as u64, Ordering::Relaxed);
+        buffer
+            .atomic_u64(MAGIC_OFFSET)
+            .store(MAGIC, Ordering::Release);
+        Ok(buffer)
+    }
+
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len <= HEADER_SIZE {
+            return Err(invalid_buffer(path));
+        }
+
+        let buffer = Self::map(&file, len - HEADER_SIZE)?;
+        if buffer.atomic_u64(MAGIC_OFFSET).load(Ordering::Acquire) != MAGIC
+            || buffer.atomic_u64(CAPACITY_OFFSET).load(Ordering::Relaxed) as usize
+                != buffer.capacity
+        {
+            return Err(invalid_buffer(path));
+        }
+        Ok(buffer)
+    }
+
+    fn map(file: &File, capacity: usize) -> io::Result<Self> {
+        Ok(Self {
+            mapping: SharedMapping::map(file, HEADER_SIZE + capacity)?,
+            capacity,
+        })
+    }
+
+    fn atomic_u64(&self, offset: usize) -> &AtomicU64 {
+        self.mapping.atomic_u64(offset)
+    }
+
+    fn atomic_u32(&self, offset: usize) -> &AtomicU32 {
+        self.mapping.atomic_u32(offset)
+    }
+
+    fn write_position(&self) -> &AtomicU64 {
+        self.atomic_u64(WRITE_POSITION_OFFSET)
+    }
+
+    fn read_position(&self) -> &AtomicU64 {
+        self.atomic_u64(READ_POSITION_OFFSET)
+    }
+
+    fn is_closed(&self, flag_offset: usize) -> bool {
+        self.atomic_u32(flag_offset).load(Ordering::Acquire) != 0
+    }
+
+    fn close(&self, flag_offset: usize) {
+        self.atomic_u32(flag_offset).store(1, Ordering::Release)
+    }
+
+    /// Copy `data` to the buffer at (ever increasing) `position`, wrapping around the end.
+    fn write_at(&self, position: u64, data: &[u8]) {
+        let start = (position % self.capacity as u64) as usize;
+        let first = data.len().min(self.capacity - start);
+        self.mapping.write(HEADER_SIZE + start, &data[..first]);
+        self.mapping.write(HEADER_SIZE, &data[first..]);
+    }
+
+    /// Copy data from the buffer at (ever increasing) `position` to `data`, wrapping around the end.
+    fn read_at(&self, position: u64, data: &mut [u8]) {
+        let start = (position % self.capacity as u64) as usize;
+        let first = data.len().min(self.capacity - start);
+        let (head, tail) = data.split_at_mut(first);
+        self.mapping.read(HEADER_SIZE + start, head);
+        self.mapping.read(HEADER_SIZE, tail);
+    }
+}
+
+/// Producer side of the ring buffer.
+pub(crate) struct ShmSender {
+    buffer: SharedRingBuffer,
+    peer: UnixStream,
+    options: ChannelOptions,
+}
+
+impl ShmSender {
+    /// `peer` is the socket of the connection, it is used only to detect disconnection.
+    pub(crate) fn new(buffer: SharedRingBuffer, peer: UnixStream) -> Self {
+        Self {
+            buffer,
+            peer,
+            options: ChannelOptions::default(),
+        }
+    }
+
+    pub(crate) fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.options.set_timeout(timeout)
+    }
+
+    pub(crate) fn set_nonblocking(&self, nonblocking: bool) {
+        self.options.set_nonblocking(nonblocking)
+    }
+
+    /// Write one frame, waits for the consumer if the buffer is full.
+    pub(crate) fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        let frame_length = (FRAME_LENGTH_SIZE + msg.len()) as u64;
+        let capacity = self.buffer.capacity as u64;
+        if frame_length > capacity {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "message of {} bytes does not fit into shared memory of {} bytes",
+                    msg.len(),
+                    capacity
+                ),
+            ));
+        }
+
+        let write_position = self.buffer.write_position().load(Ordering::Relaxed);
+        let mut backoff = Backoff::new(self.options.timeout());
+        loop {
+            if self.buffer.is_closed(RECEIVER_CLOSED_OFFSET) {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            let read_position = self.buffer.read_position().load(Ordering::Acquire);
+            if capacity - (write_position - read_position) >= frame_length {
+                break;
+            }
+            if self.options.is_nonblocking() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            backoff.snooze(&self.peer, io::ErrorKind::BrokenPipe)?;
+        }
+
+        self.buffer
+            .write_at(write_position, &(msg.len() as u64).to_be_bytes());
+        self.buffer
+            .write_at(write_position + FRAME_LENGTH_SIZE as u64, msg);
+        self.buffer
+            .write_position()
+            .store(write_position + frame_length, Ordering::Release);
+        Ok(())
+    }
+
+    pub(crate) fn shutdown(&self) {
+        self.buffer.close(SENDER_CLOSED_OFFSET)
+    }
+}
+
+impl Drop for ShmSender {
+    fn drop(&mut self) {
+        self.shutdown()
+    }
+}
+
+/// Consumer side of the ring buffer.
+pub(crate) struct ShmReceiver {
+    buffer: SharedRingBuffer,
+    peer: UnixStream,
+    options: ChannelOptions,
+}
+
+impl ShmReceiver {
+    /// `peer` is the socket of the connection, it is used only to detect disconnection.
+    pub(crate) fn new(buffer: SharedRingBuffer, peer: UnixStream) -> Self {
+        Self {
+            buffer,
+            peer,
+            options: ChannelOptions::default(),
+        }
+    }
+
+    pub(crate) fn peer(&self) -> &UnixStream {
+        &self.peer
+    }
+
+    pub(crate) fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.options.set_timeout(timeout)
+    }
+
+    pub(crate) fn set_nonblocking(&self, nonblocking: bool) {
+        self.options.set_nonblocking(nonblocking)
+    }
+
+    /// Read one frame, waits for the producer if the buffer is empty.
+    ///
+    /// Frames already written are delivered even after the producer was closed.
+    pub(crate) fn receive(&mut self) -> io::Result<Vec<u8>> {
+        let read_position = self.buffer.read_position().load(Ordering::Relaxed);
+        let mut backoff = Backoff::new(self.options.timeout());
+        loop {
+            if self.buffer.write_position().load(Ordering::Acquire) != read_position {
+                break;
+            }
+            if self.buffer.is_closed(SENDER_CLOSED_OFFSET) {
+                // producer could publish last frame just before it was closed
+                if self.buffer.write_position().load(Ordering::Acquire) != read_position {
+                    break;
+                }
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            if self.options.is_nonblocking() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            backoff.snooze(&self.peer, io::ErrorKind::UnexpectedEof)?;
+        }
+
+        // producer publishes whole frames, so the frame is complete
+        let mut msg_length_buf = [0; FRAME_LENGTH_SIZE];
+        self.buffer.read_at(read_position, &mut msg_length_buf);
+        let msg_length = u64::from_be_bytes(msg_length_buf) as usize;
+        if msg_length + FRAME_LENGTH_SIZE > self.buffer.capacity {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid message length in shared memory: {}", msg_length),
+            ));
+        }
+
+        let mut msg = vec![0; msg_length];
+        self.buffer
+            .read_at(read_position + FRAME_LENGTH_SIZE as u64, &mut msg);
+        self.buffer.read_position().store(
+            read_position + (FRAME_LENGTH_SIZE + msg_length) as u64,
+            Ordering::Release,
+        );
+        Ok(msg)
+    }
+
+    pub(crate) fn shutdown(&self) {
+        self.buffer.close(RECEIVER_CLOSED_OFFSET)
+    }
+}
+
+impl Drop for ShmReceiver {
+    fn drop(&mut self) {
+        self.shutdown()
+    }
+}
+
+/// Timeout and blocking mode, can be changed through shared reference like on a socket
+#[derive(Default)]
+struct ChannelOptions {
+    /// Timeout in nanoseconds, zero means no timeout (zero timeout is not allowed)
+    timeout_nanos: AtomicU64,
+    nonblocking: AtomicBool,
+}
+
+impl ChannelOptions {
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let timeout_nanos = match timeout {
+            Some(timeout) if timeout == Duration::from_secs(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "cannot set a 0 duration timeout",
+                ))
+            }
+            Some(timeout) => timeout.as_nanos().min(u64::MAX as u128) as u64,
+            None => 0,
+        };
+        self.timeout_nanos.store(timeout_nanos, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        match self.timeout_nanos.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblocking.store(nonblocking, Ordering::Relaxed)
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
+}
+
+struct Backoff {
+    attempt: u32,
+    deadline: Option<Instant>,
+}
+
+impl Backoff {
+    fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            attempt: 0,
+            deadline: timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    /// Wait a little bit longer on every call, fails with `disconnected` when the peer is gone
+    /// and with `WouldBlock` (like a socket) when timed out.
+    fn snooze(&mut self, peer: &UnixStream, disconnected: io::ErrorKind) -> io::Result<()> {
+        if self.attempt < SPIN_ATTEMPTS {
+            hint::spin_loop();
+        } else if self.attempt < YIELD_ATTEMPTS {
+            thread::yield_now();
+        } else {
+            if !is_connected(peer) {
+                return Err(disconnected.into());
+            }
+            if let Some(deadline) = self.deadline {
+                if Instant::now() >= deadline {
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+            }
+            let sleep = Duration::from_micros(10 * u64::from(self.attempt - YIELD_ATTEMPTS + 1));
+            thread::sleep(sleep.min(MAX_SLEEP));
+        }
+        self.attempt = self.attempt.saturating_add(1);
+        Ok(())
+    }
+}
+
+/// Peer closes the socket when its process exits, no data are sent over it after the setup.
+fn is_connected(peer: &UnixStream) -> bool {
+    let mut buf = [0u8; 1];
+    match recv(
+        peer.as_raw_fd(),
+        &mut buf,
+        MsgFlags::MSG_PEEK | MsgFlags::MSG_DONTWAIT,
+    ) {
+        Ok(0) => false,
+        Ok(_) => true,
+        Err(nix::Error::Sys(errno)) => {
+            errno == nix::errno::Errno::EAGAIN || errno == nix::errno::Errno::EINTR
+        }
+        Err(_) => false,
+    }
+}
+
+fn invalid_buffer(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid shared memory buffer: {:?}", path),
+    )
+}
+
+/// Randomly named file in `/dev/shm` (tmpfs on Linux) or in temp directory.
+fn temp_shm_path() -> PathBuf {
+    let shm_dir = Path::new("/dev/shm");
+    let dir = if shm_dir.is_dir() {
+        shm_dir.to_path_buf()
+    } else {
+        env::temp_dir()
+    };
+    dir.join(format!("tezedge-ipc-{}.shm", crate::random_name()))
+}
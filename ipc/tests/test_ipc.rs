@@ -152,3 +152,36 @@ fn ipc_accept_timeout() -> Result<(), failure::Error> {
         Ok(_) => Err(format_err!("Unexpected result")),
     }
 }
+
+#[test]
+#[serial]
+fn ipc_close_channel_with_blocked_receive() -> Result<(), failure::Error> {
+    let sock_path = temp_sock();
+    let mut server: IpcServer<String, String> = IpcServer::bind_path(&sock_path)?;
+
+    let client = thread::spawn(move || -> Result<(), IpcError> {
+        let client: IpcClient<String, String> = IpcClient::new(&sock_path);
+        let (mut rx, _tx) = client.connect()?;
+        let closer = rx.closer()?;
+
+        // receive is blocked until the channel is closed by another thread
+        let receiver = thread::spawn(move || rx.receive());
+        thread::sleep(Duration::from_millis(100));
+        closer
+            .close()
+            .map_err(|err| IpcError::ConnectionError { reason: err })?;
+        match receiver.join().expect("receiver thread panicked") {
+            Err(IpcError::ReceiveMessageLengthError { .. }) => Ok(()),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    });
+
+    let (mut rx, _tx) = server.try_accept(Duration::from_secs(10))?;
+    client.join().expect("client thread panicked")?;
+
+    // the other side is disconnected too
+    match rx.receive() {
+        Err(IpcError::ReceiveMessageLengthError { .. }) => Ok(()),
+        result => Err(format_err!("Unexpected result: {:?}", result)),
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::thread;
+use std::time::Duration;
+
+use failure::format_err;
+use serial_test::serial;
+
+use ipc::*;
+
+mod common;
+
+const SHARED_MEMORY: IpcTransport = IpcTransport::SharedMemory { capacity: 1024 };
+
+fn connect(sock_path: &std::path::Path) -> (IpcReceiver<String>, IpcSender<String>) {
+    // wait for socket/bind to be ready, socket file exists shortly before server listens on it
+    let client: IpcClient<String, String> = IpcClient::new(sock_path);
+    for _ in 0..500 {
+        if sock_path.exists() {
+            match client.connect() {
+                Ok(channels) => return channels,
+                Err(IpcError::ConnectionError { .. }) => (),
+                Err(e) => panic!("Failed to connect: {:?}", e),
+            }
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    panic!("Server is not listening on {:?}", sock_path)
+}
+
+#[test]
+#[serial]
+fn shm_fork_and_client_exchange() -> Result<(), failure::Error> {
+    let sock_path = temp_sock();
+
+    let child_pid = common::fork(|| {
+        let (mut rx, mut tx) = connect(&sock_path);
+
+        // messages go around the buffer many times
+        for i in 0..1000 {
+            let recv = rx.receive().unwrap();
+            assert_eq!(recv, format!("quick brown fox {}", i));
+            tx.send(&format!("lazy dog {}", i)).unwrap();
+        }
+    });
+    assert!(child_pid > 0);
+
+    let mut server: IpcServer<String, String> =
+        IpcServer::bind_path(&sock_path)?.with_transport(SHARED_MEMORY);
+    let (mut rx, mut tx) = server.try_accept(Duration::from_secs(10))?;
+
+    for i in 0..1000 {
+        tx.send(&format!("quick brown fox {}", i))?;
+        assert_eq!(rx.receive()?, format!("lazy dog {}", i));
+    }
+
+    // client is done and closed its sender
+    assert!(rx.receive().is_err());
+    assert!(common::wait(child_pid));
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn shm_fork_and_try_read_with_timeout() -> Result<(), failure::Error> {
+    let sock_path = temp_sock();
+
+    let child_pid = common::fork(|| {
+        let (_, mut tx) = connect(&sock_path);
+        tx.send(&String::from("hello")).unwrap();
+
+        thread::sleep(Duration::from_secs(2));
+        tx.send(&String::from("hello_after_2_seconds")).unwrap();
+
+        // simulate crash - buffer is not closed, just the process is gone
+        std::mem::forget(tx);
+    });
+    assert!(child_pid > 0);
+
+    let mut server: IpcServer<String, String> =
+        IpcServer::bind_path(&sock_path)?.with_transport(SHARED_MEMORY);
+    let (mut rx, _) = server.try_accept(Duration::from_secs(10))?;
+
+    assert_eq!(rx.receive()?, "hello");
+
+    // 1. try read something with timeout but nothing comes
+    match rx.try_receive(Some(Duration::from_millis(500)), None) {
+        Err(IpcError::ReceiveMessageTimeouted) => (/* ok */),
+        Err(e) => return Err(format_err!("Unexpected result: {:?}", e)),
+        Ok(_) => return Err(format_err!("Unexpected result")),
+    }
+
+    // 2. try read something with timeout
+    match rx.try_receive(Some(Duration::from_secs(5)), None) {
+        Ok(recv) => assert_eq!(recv, "hello_after_2_seconds"),
+        Err(e) => return Err(format_err!("Unexpected result: {:?}", e)),
+    }
+
+    // 3. disconnected peer is detected without timeout
+    match rx.receive() {
+        Err(IpcError::ReceiveMessageLengthError { .. }) => (/* ok */),
+        Err(e) => return Err(format_err!("Unexpected result: {:?}", e)),
+        Ok(_) => return Err(format_err!("Unexpected result")),
+    }
+    assert!(common::wait(child_pid));
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn shm_message_larger_than_capacity() -> Result<(), failure::Error> {
+    let sock_path = temp_sock();
+
+    let child_pid = common::fork(|| {
+        let (mut rx, _) = connect(&sock_path);
+        assert_eq!(rx.receive().unwrap(), "small");
+    });
+    assert!(child_pid > 0);
+
+    let mut server: IpcServer<String, String> =
+        IpcServer::bind_path(&sock_path)?.with_transport(SHARED_MEMORY);
+    let (_, mut tx) = server.try_accept(Duration::from_secs(10))?;
+
+    match tx.send(&"x".repeat(2048)) {
+        Err(IpcError::SendError { .. }) => (/* ok */),
+        Err(e) => return Err(format_err!("Unexpected result: {:?}", e)),
+        Ok(_) => return Err(format_err!("Unexpected result")),
+    }
+    tx.send(&String::from("small"))?;
+
+    assert!(common::wait(child_pid));
+    Ok(())
+}
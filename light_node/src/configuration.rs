@@ -5,30 +5,92 @@ use std::env;
 use std::ffi::OsString;
 use std::fs;
 use std::io::{self, BufRead};
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
 use std::{collections::HashMap, collections::HashSet, fmt::Debug};
 
 use clap::{App, Arg};
+use crypto::hash::BlockHash;
 use rocksdb::ColumnFamilyDescriptor;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
+use rpc::{RpcAccessControlList, RpcAccessPolicy, RpcAclRule, RpcListener};
 use shell::peer_manager::P2p;
+use shell::state::synchronization_state::DEFAULT_SYNCHRONIZATION_LATENCY;
 use shell::PeerConnectionThreshold;
 use storage::persistent::KeyValueSchema;
-use storage::KeyValueStoreBackend;
+use storage::{ContextActionSink, ContextActionSinkKind, KeyValueStoreBackend};
 use tezos_api::environment;
 use tezos_api::environment::{TezosEnvironment, ZcashParams};
 use tezos_api::ffi::PatchContext;
-use tezos_wrapper::TezosApiConnectionPoolConfiguration;
+use tezos_messages::Head;
+use tezos_wrapper::service::{IpcEvtServer, IpcTransport};
+use tezos_wrapper::{ProtocolRunnerLimits, TezosApiConnectionPoolConfiguration};
 
 #[derive(Debug, Clone)]
 pub struct Rpc {
-    pub listener_port: u16,
+    pub listener_addresses: Vec<SocketAddr>,
     pub websocket_address: SocketAddr,
+    /// Max size of cached protocol rpc responses, zero disables cache
+    pub protocol_cache_size_bytes: usize,
+    /// Access policy (authorization, limits) of rpc listeners, acl is selected per listener from acl rules
+    pub access_policy: RpcAccessPolicy,
+    pub acl_allowed: Vec<RpcAclRule>,
+    pub acl_denied: Vec<RpcAclRule>,
+    /// Separate listener for admin/dev endpoints, which are not served by rpc listeners then
+    pub admin_address: Option<SocketAddr>,
+    pub admin_bearer_token: Option<String>,
+}
+
+impl Rpc {
+    const DEFAULT_PROTOCOL_CACHE_SIZE_MB: usize = 64;
+
+    /// Returns all rpc listeners with their access policies
+    pub fn listeners(&self) -> Vec<RpcListener> {
+        let mut listeners = self
+            .listener_addresses
+            .iter()
+            .map(|address| {
+                let policy = RpcAccessPolicy {
+                    acl: RpcAccessControlList::for_listener(
+                        address,
+                        false,
+                        &self.acl_allowed,
+                        &self.acl_denied,
+                    ),
+                    ..self.access_policy.clone()
+                };
+                RpcListener {
+                    address: *address,
+                    policy: match self.admin_address {
+                        Some(_) => policy.without_admin_endpoints(),
+                        None => policy,
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(admin_address) = self.admin_address {
+            listeners.push(RpcListener {
+                address: admin_address,
+                policy: RpcAccessPolicy::admin(
+                    RpcAccessControlList::for_listener(
+                        &admin_address,
+                        true,
+                        &self.acl_allowed,
+                        &self.acl_denied,
+                    ),
+                    self.admin_bearer_token.clone(),
+                    self.access_policy.max_request_size,
+                ),
+            });
+        }
+        listeners
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -153,10 +215,12 @@ pub struct Storage {
     pub db_context_actions: RocksDBConfig<ContextActionsTableInitializer>,
     pub db_path: PathBuf,
     pub tezos_data_dir: PathBuf,
-    pub action_store_backend: Vec<ContextActionStoreBackend>,
+    pub action_store_sinks: Vec<ContextActionSink>,
     pub kv_store_backend: KeyValueStoreBackend,
     pub compute_context_action_tree_hashes: bool,
     pub patch_context: Option<PatchContext>,
+    /// Block (hash and level), which must be part of every accepted branch
+    pub checkpoint: Option<Head>,
 }
 
 impl Storage {
@@ -187,6 +251,8 @@ pub struct Ffi {
     pub tezos_readonly_prevalidation_api_pool: TezosApiConnectionPoolConfiguration,
     pub tezos_without_context_api_pool: TezosApiConnectionPoolConfiguration,
     pub zcash_param: ZcashParams,
+    /// Transport of context actions from protocol_runner to node
+    pub context_actions_ipc_transport: IpcTransport,
 }
 
 impl Ffi {
@@ -385,12 +451,97 @@ pub fn tezos_app() -> App<'static, 'static> {
             .value_name("PORT")
             .help("Socket listening port for p2p for communication with tezos world")
             .validator(parse_validator_fn!(u16, "Value must be a valid port number")))
+        .arg(Arg::with_name("listen-addr")
+            .long("listen-addr")
+            .takes_value(true)
+            .multiple(true)
+            .value_name("IP:PORT")
+            .help("Address(es) where node listens for incoming p2p connections. Ipv6 addresses must be enclosed with brackets, e.g. [::]:9732. If port is not present, then --p2p-port is used. Default: 0.0.0.0:<p2p-port>")
+            .validator(|v| {
+                v.split(',')
+                    .try_for_each(|addr| environment::parse_socket_addr(addr, P2p::DEFAULT_P2P_PORT_FOR_LOOKUP).map(|_| ()))
+                    .map_err(|e| format!("Value '{}' is not valid, reason: {:?}. Expected format is: IP:PORT or [IPv6]:PORT", v, e))
+            }))
+        .arg(Arg::with_name("discovery-addr")
+            .long("discovery-addr")
+            .takes_value(true)
+            .value_name("IP:PORT")
+            .help("Public address of the node, which is announced to other peers (e.g. for node behind NAT). If port is not present, then --p2p-port is used")
+            .validator(|v| {
+                environment::parse_socket_addr(&v, P2p::DEFAULT_P2P_PORT_FOR_LOOKUP)
+                    .map(|_| ())
+                    .map_err(|e| format!("Value '{}' is not valid, reason: {:?}. Expected format is: IP:PORT or [IPv6]:PORT", v, e))
+            }))
         .arg(Arg::with_name("rpc-port")
             .long("rpc-port")
             .takes_value(true)
             .value_name("PORT")
             .help("Rust server RPC port for communication with rust node")
             .validator(parse_validator_fn!(u16, "Value must be a valid port number")))
+        .arg(Arg::with_name("rpc-listen-addr")
+            .long("rpc-listen-addr")
+            .takes_value(true)
+            .multiple(true)
+            .value_name("IP:PORT")
+            .help("Address(es) where node listens for RPC requests. Ipv6 addresses must be enclosed with brackets, e.g. [::1]:18732. If port is not present, then --rpc-port is used. Default: 0.0.0.0:<rpc-port>")
+            .validator(|v| {
+                v.split(',')
+                    .try_for_each(|addr| environment::parse_socket_addr(addr, 0).map(|_| ()))
+                    .map_err(|e| format!("Value '{}' is not valid, reason: {:?}. Expected format is: IP:PORT or [IPv6]:PORT", v, e))
+            }))
+        .arg(Arg::with_name("rpc-protocol-cache-size-mb")
+            .long("rpc-protocol-cache-size-mb")
+            .takes_value(true)
+            .value_name("NUM")
+            .help("Max size (in megabytes) of the cache for responses of block-scoped protocol RPCs, zero disables cache, default: 64")
+            .validator(parse_validator_fn!(usize, "Value must be a valid number")))
+        .arg(Arg::with_name("rpc-acl-allowed")
+            .long("rpc-acl-allowed")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("PATTERN")
+            .help("RPC endpoints allowed on RPC listeners, can be repeated or delimited by comma. If not set, all (not denied) endpoints are allowed. \
+                   Format: [IP:PORT] [METHOD] /path, '*' matches one path segment, '**' matches the rest of the path, e.g. 'GET /chains/*/blocks/**'. \
+                   Endpoints with address apply only to the listener with that address (including --rpc-admin-address), others apply to all --rpc-listen-addr listeners")
+            .validator(|v| v.split(',').try_for_each(|rule| rule.parse::<RpcAclRule>().map(|_| ())).map_err(|e| format!("{}", e))))
+        .arg(Arg::with_name("rpc-acl-denied")
+            .long("rpc-acl-denied")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("PATTERN")
+            .help("RPC endpoints denied on RPC listeners (takes precedence over --rpc-acl-allowed), can be repeated or delimited by comma. Format is the same as for --rpc-acl-allowed")
+            .validator(|v| v.split(',').try_for_each(|rule| rule.parse::<RpcAclRule>().map(|_| ())).map_err(|e| format!("{}", e))))
+        .arg(Arg::with_name("rpc-bearer-token")
+            .long("rpc-bearer-token")
+            .takes_value(true)
+            .value_name("TOKEN")
+            .help("If set, RPC requests on --rpc-listen-addr listeners must contain header 'Authorization: Bearer <TOKEN>'"))
+        .arg(Arg::with_name("rpc-rate-limit")
+            .long("rpc-rate-limit")
+            .takes_value(true)
+            .value_name("NUM")
+            .help("Max count of RPC requests per second (and max burst) from one IP address on --rpc-listen-addr listeners, zero disables limit, default: 0")
+            .validator(parse_validator_fn!(u32, "Value must be a valid number")))
+        .arg(Arg::with_name("rpc-max-request-size-kb")
+            .long("rpc-max-request-size-kb")
+            .takes_value(true)
+            .value_name("NUM")
+            .help("Max size (in kilobytes) of RPC request body, zero disables limit, default: 0")
+            .validator(parse_validator_fn!(usize, "Value must be a valid number")))
+        .arg(Arg::with_name("rpc-admin-address")
+            .long("rpc-admin-address")
+            .takes_value(true)
+            .value_name("IP:PORT")
+            .help("If set, admin/dev RPC endpoints (/dev/*, /stats/*) are served only on this address (without rate limit) and not on --rpc-listen-addr listeners")
+            .validator(parse_validator_fn!(SocketAddr, "Value must be a valid IP:PORT")))
+        .arg(Arg::with_name("rpc-admin-bearer-token")
+            .long("rpc-admin-bearer-token")
+            .takes_value(true)
+            .value_name("TOKEN")
+            .requires("rpc-admin-address")
+            .help("If set, RPC requests on --rpc-admin-address must contain header 'Authorization: Bearer <TOKEN>'"))
         .arg(Arg::with_name("enable-testchain")
             .long("enable-testchain")
             .takes_value(true)
@@ -406,10 +557,10 @@ pub fn tezos_app() -> App<'static, 'static> {
             .long("peers")
             .takes_value(true)
             .value_name("IP:PORT")
-            .help("A peer to bootstrap the network from. Peers are delimited by a colon. Format: IP1:PORT1,IP2:PORT2,IP3:PORT3")
+            .help("A peer to bootstrap the network from. Peers are delimited by a colon. Ipv6 addresses must be enclosed with brackets. Format: IP1:PORT1,IP2:PORT2,[IPv6]:PORT3")
             .validator(|v| {
                 let err_count = v.split(',')
-                    .map(|ip_port| ip_port.parse::<SocketAddr>())
+                    .map(|ip_port| environment::parse_socket_addr(ip_port, P2p::DEFAULT_P2P_PORT_FOR_LOOKUP))
                     .filter(|v| v.is_err())
                     .count();
                 if err_count == 0 {
@@ -436,6 +587,12 @@ pub fn tezos_app() -> App<'static, 'static> {
             .value_name("NUM")
             .help("Maximal number of peers to connect to")
             .validator(parse_validator_fn!(usize, "Value must be a valid number")))
+        .arg(Arg::with_name("synchronization-latency")
+            .long("synchronization-latency")
+            .takes_value(true)
+            .value_name("SECONDS")
+            .help("Max age of peer heads (in seconds), which are still considered as recent by synchronization heuristic")
+            .validator(parse_validator_fn!(u64, "Value must be a valid number")))
         .arg(Arg::with_name("protocol-runner")
             .long("protocol-runner")
             .takes_value(true)
@@ -467,6 +624,24 @@ pub fn tezos_app() -> App<'static, 'static> {
                     .takes_value(true)
                     .value_name("NUM")
                     .help("Number of seconds to remove unused protocol_runner from pool, default: 1800 means 30 minutes")
+                    .validator(parse_validator_fn!(u64, "Value must be a valid number")),
+                Arg::with_name("ffi-pool-max-memory-in-mb")
+                    .long("ffi-pool-max-memory-in-mb")
+                    .takes_value(true)
+                    .value_name("NUM")
+                    .help("Limit of virtual memory (setrlimit) for protocol_runner in megabytes, default: unlimited")
+                    .validator(parse_validator_fn!(u64, "Value must be a valid number")),
+                Arg::with_name("ffi-pool-max-cpu-time-in-secs")
+                    .long("ffi-pool-max-cpu-time-in-secs")
+                    .takes_value(true)
+                    .value_name("NUM")
+                    .help("Limit of CPU time (setrlimit) for protocol_runner in seconds, protocol_runner is killed and restarted when exceeded, default: unlimited")
+                    .validator(parse_validator_fn!(u64, "Value must be a valid number")),
+                Arg::with_name("ffi-pool-recycle-memory-in-mb")
+                    .long("ffi-pool-recycle-memory-in-mb")
+                    .takes_value(true)
+                    .value_name("NUM")
+                    .help("Idle protocol_runner with larger resident memory (in megabytes) is replaced by a new one, default: disabled")
                     .validator(parse_validator_fn!(u64, "Value must be a valid number"))
             ])
         .args(
@@ -494,6 +669,24 @@ pub fn tezos_app() -> App<'static, 'static> {
                     .takes_value(true)
                     .value_name("NUM")
                     .help("Number of seconds to remove unused protocol_runner from pool, default: 1800 means 30 minutes")
+                    .validator(parse_validator_fn!(u64, "Value must be a valid number")),
+                Arg::with_name("ffi-trpap-pool-max-memory-in-mb")
+                    .long("ffi-trpap-pool-max-memory-in-mb")
+                    .takes_value(true)
+                    .value_name("NUM")
+                    .help("Limit of virtual memory (setrlimit) for protocol_runner in megabytes, default: unlimited")
+                    .validator(parse_validator_fn!(u64, "Value must be a valid number")),
+                Arg::with_name("ffi-trpap-pool-max-cpu-time-in-secs")
+                    .long("ffi-trpap-pool-max-cpu-time-in-secs")
+                    .takes_value(true)
+                    .value_name("NUM")
+                    .help("Limit of CPU time (setrlimit) for protocol_runner in seconds, protocol_runner is killed and restarted when exceeded, default: unlimited")
+                    .validator(parse_validator_fn!(u64, "Value must be a valid number")),
+                Arg::with_name("ffi-trpap-pool-recycle-memory-in-mb")
+                    .long("ffi-trpap-pool-recycle-memory-in-mb")
+                    .takes_value(true)
+                    .value_name("NUM")
+                    .help("Idle protocol_runner with larger resident memory (in megabytes) is replaced by a new one, default: disabled")
                     .validator(parse_validator_fn!(u64, "Value must be a valid number"))
             ])
         .args(
@@ -521,8 +714,32 @@ pub fn tezos_app() -> App<'static, 'static> {
                     .takes_value(true)
                     .value_name("NUM")
                     .help("Number of seconds to remove unused protocol_runner from pool, default: 1800 means 30 minutes")
+                    .validator(parse_validator_fn!(u64, "Value must be a valid number")),
+                Arg::with_name("ffi-twcap-pool-max-memory-in-mb")
+                    .long("ffi-twcap-pool-max-memory-in-mb")
+                    .takes_value(true)
+                    .value_name("NUM")
+                    .help("Limit of virtual memory (setrlimit) for protocol_runner in megabytes, default: unlimited")
+                    .validator(parse_validator_fn!(u64, "Value must be a valid number")),
+                Arg::with_name("ffi-twcap-pool-max-cpu-time-in-secs")
+                    .long("ffi-twcap-pool-max-cpu-time-in-secs")
+                    .takes_value(true)
+                    .value_name("NUM")
+                    .help("Limit of CPU time (setrlimit) for protocol_runner in seconds, protocol_runner is killed and restarted when exceeded, default: unlimited")
+                    .validator(parse_validator_fn!(u64, "Value must be a valid number")),
+                Arg::with_name("ffi-twcap-pool-recycle-memory-in-mb")
+                    .long("ffi-twcap-pool-recycle-memory-in-mb")
+                    .takes_value(true)
+                    .value_name("NUM")
+                    .help("Idle protocol_runner with larger resident memory (in megabytes) is replaced by a new one, default: disabled")
                     .validator(parse_validator_fn!(u64, "Value must be a valid number"))
             ])
+        .arg(Arg::with_name("ffi-context-actions-shm-size-in-mb")
+            .long("ffi-context-actions-shm-size-in-mb")
+            .takes_value(true)
+            .value_name("NUM")
+            .help("Size of shared memory buffer for context actions sent from protocol_runner in megabytes, single action cannot be larger, zero means unix socket is used instead, default: 32")
+            .validator(parse_validator_fn!(usize, "Value must be a valid number")))
         .arg(Arg::with_name("init-sapling-spend-params-file")
             .long("init-sapling-spend-params-file")
             .takes_value(true)
@@ -548,6 +765,16 @@ pub fn tezos_app() -> App<'static, 'static> {
             .value_name("STRING")
             .possible_values(&ContextActionStoreBackend::possible_values())
             .help("Activate recording of context storage actions"))
+        .arg(Arg::with_name("actions-store-sink")
+            .long("actions-store-sink")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("SINK")
+            .help("Activate recording of context storage actions to sink with filter, can be repeated. \
+                   Format: <kind>[;<option>=<value>]*, kinds: 'rocksdb', 'file', 'rotating-file', 'stream' (unix socket), \
+                   options: 'path', 'max-file-size-mb', 'max-files', 'types' (e.g. Set,Delete,Commit), 'key-prefix' (e.g. data/contracts), 'contract' (e.g. tz1...,KT1...)")
+            .validator(|v| v.parse::<ContextActionSink>().map(|_| ()).map_err(|e| format!("{}", e))))
         .arg(Arg::with_name("kv-store-backend")
             .long("kv-store-backend")
             .takes_value(true)
@@ -565,7 +792,13 @@ pub fn tezos_app() -> App<'static, 'static> {
             .value_name("PATH")
             .required(false)
             .help("Path to the json file with key-values, which will be added to empty context on startup and commit genesis.")
-            .validator(|v| if Path::new(&v).exists() { Ok(()) } else { Err(format!("Sandbox patch-context json file not found at '{}'", v)) }));
+            .validator(|v| if Path::new(&v).exists() { Ok(()) } else { Err(format!("Sandbox patch-context json file not found at '{}'", v)) }))
+        .arg(Arg::with_name("checkpoint")
+            .long("checkpoint")
+            .takes_value(true)
+            .value_name("BLOCK_HASH,LEVEL")
+            .help("Block which must be part of the chain, branches diverging from it are not accepted, e.g. BLockGenesisGenesisGenesisGenesisGenesisb83baZgbyZe,1")
+            .validator(|v| parse_checkpoint(&v).map(|_| ())));
     app
 }
 
@@ -577,40 +810,84 @@ fn pool_cfg(
         min_connections: 0,
         /* 0 means that connections are created on-demand, because of AT_LEAST_ONE_WRITE_PROTOCOL_CONTEXT_WAS_SUCCESS_AT_FIRST_LOCK */
         max_connections: args
-            .value_of(&format!(
-                "ffi-{}-pool-max-connections",
-                pool_name_discriminator
+            .value_of(&pool_arg_name(
+                pool_name_discriminator,
+                "pool-max-connections",
             ))
             .unwrap_or("10")
             .parse::<u8>()
             .expect("Provided value cannot be converted to number"),
         connection_timeout: args
-            .value_of(&format!(
-                "ffi-{}-pool-connection-timeout-in-secs",
-                pool_name_discriminator
+            .value_of(&pool_arg_name(
+                pool_name_discriminator,
+                "pool-connection-timeout-in-secs",
             ))
             .unwrap_or("60")
             .parse::<u16>()
             .map(|seconds| Duration::from_secs(seconds as u64))
             .expect("Provided value cannot be converted to number"),
         max_lifetime: args
-            .value_of(&format!(
-                "ffi-{}-pool-max-lifetime-in-secs",
-                pool_name_discriminator
+            .value_of(&pool_arg_name(
+                pool_name_discriminator,
+                "pool-max-lifetime-in-secs",
             ))
             .unwrap_or("21600")
             .parse::<u16>()
             .map(|seconds| Duration::from_secs(seconds as u64))
             .expect("Provided value cannot be converted to number"),
         idle_timeout: args
-            .value_of(&format!(
-                "ffi-{}-pool-idle-timeout-in-secs",
-                pool_name_discriminator
+            .value_of(&pool_arg_name(
+                pool_name_discriminator,
+                "pool-idle-timeout-in-secs",
             ))
             .unwrap_or("1800")
             .parse::<u16>()
             .map(|seconds| Duration::from_secs(seconds as u64))
             .expect("Provided value cannot be converted to number"),
+        limits: ProtocolRunnerLimits {
+            max_virtual_memory_bytes: args
+                .value_of(&pool_arg_name(
+                    pool_name_discriminator,
+                    "pool-max-memory-in-mb",
+                ))
+                .map(|megabytes| {
+                    megabytes
+                        .parse::<u64>()
+                        .map(|megabytes| megabytes * 1024 * 1024)
+                        .expect("Provided value cannot be converted to number")
+                }),
+            max_cpu_time: args
+                .value_of(&pool_arg_name(
+                    pool_name_discriminator,
+                    "pool-max-cpu-time-in-secs",
+                ))
+                .map(|seconds| {
+                    seconds
+                        .parse::<u64>()
+                        .map(Duration::from_secs)
+                        .expect("Provided value cannot be converted to number")
+                }),
+            recycle_resident_memory_bytes: args
+                .value_of(&pool_arg_name(
+                    pool_name_discriminator,
+                    "pool-recycle-memory-in-mb",
+                ))
+                .map(|megabytes| {
+                    megabytes
+                        .parse::<u64>()
+                        .map(|megabytes| megabytes * 1024 * 1024)
+                        .expect("Provided value cannot be converted to number")
+                }),
+        },
+    }
+}
+
+/// Readonly pool has no discriminator, so its args are named just "ffi-pool-*"
+fn pool_arg_name(pool_name_discriminator: &str, name: &str) -> String {
+    if pool_name_discriminator.is_empty() {
+        format!("ffi-{}", name)
+    } else {
+        format!("ffi-{}-{}", pool_name_discriminator, name)
     }
 }
 
@@ -641,6 +918,67 @@ fn validate_required_args(args: &clap::ArgMatches) {
     validate_required_arg(args, "identity-expected-pow", None);
 }
 
+/// Parses checkpoint in format `<block_hash>,<level>`
+fn parse_checkpoint(value: &str) -> Result<Head, String> {
+    let (block_hash, level) = match value.split(',').collect::<Vec<_>>().as_slice() {
+        [block_hash, level] => (*block_hash, *level),
+        _ => {
+            return Err(format!(
+                "Checkpoint must be in format <block_hash>,<level>, but is: {}",
+                value
+            ))
+        }
+    };
+    let block_hash = BlockHash::from_base58_check(block_hash.trim()).map_err(|e| {
+        format!(
+            "Invalid checkpoint block hash: {}, reason: {}",
+            block_hash, e
+        )
+    })?;
+    let level = level
+        .trim()
+        .parse::<i32>()
+        .map_err(|e| format!("Invalid checkpoint level: {}, reason: {}", level, e))?;
+    if level < 0 {
+        return Err(format!(
+            "Invalid checkpoint level: {}, must not be negative",
+            level
+        ));
+    }
+
+    Ok(Head::new(block_hash, level, vec![]))
+}
+
+/// Parses all (repeated or comma delimited) rpc acl rules of arg,
+/// rule with address must refer to one of `listener_addresses`
+fn parse_rpc_acl_rules(
+    args: &clap::ArgMatches,
+    arg_name: &str,
+    listener_addresses: &[SocketAddr],
+) -> Vec<RpcAclRule> {
+    args.values_of(arg_name)
+        .map(|values| {
+            values
+                .flat_map(|value| value.split(','))
+                .map(|rule| {
+                    let parsed = rule.parse::<RpcAclRule>().unwrap_or_else(|e| {
+                        panic!("Invalid value of {}: {}, reason: {}", arg_name, rule, e)
+                    });
+                    if let Some(address) = &parsed.address {
+                        if !listener_addresses.contains(address) {
+                            panic!(
+                                "Invalid value of {}: {}, reason: {} is not address of any rpc listener",
+                                arg_name, rule, address
+                            );
+                        }
+                    }
+                    parsed
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 // Validates single required arg. If missing, exit whole process
 pub fn validate_required_arg(args: &clap::ArgMatches, arg_name: &str, help: Option<String>) {
     if !args.is_present(arg_name) {
@@ -754,13 +1092,64 @@ impl Environment {
             .parse::<PathBuf>()
             .expect("Provided value cannot be converted to path");
 
+        let p2p_port = args
+            .value_of("p2p-port")
+            .unwrap_or("")
+            .parse::<u16>()
+            .expect("Was expecting value of p2p-port");
+
+        let discovery_address = args.value_of("discovery-addr").map(|addr| {
+            environment::parse_socket_addr(addr, p2p_port).unwrap_or_else(|_| {
+                panic!("Was expecting 'IP' or 'IP:PORT', invalid value: {}", addr)
+            })
+        });
+
+        let rpc_port = args
+            .value_of("rpc-port")
+            .unwrap_or("")
+            .parse::<u16>()
+            .expect("Was expecting value of rpc-port");
+        let rpc_listener_addresses = match args.values_of("rpc-listen-addr") {
+            Some(values) => values
+                .flat_map(|value| value.split(','))
+                .map(|addr| {
+                    environment::parse_socket_addr(addr, rpc_port).unwrap_or_else(|_| {
+                        panic!("Was expecting 'IP' or 'IP:PORT', invalid value: {}", addr)
+                    })
+                })
+                .collect(),
+            None => vec![SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), rpc_port)],
+        };
+        let rpc_admin_address: Option<SocketAddr> =
+            args.value_of("rpc-admin-address").map(|value| {
+                value
+                    .parse()
+                    .expect("Provided value cannot be converted into valid IP:PORT")
+            });
+        let rpc_acl_addresses = rpc_listener_addresses
+            .iter()
+            .chain(rpc_admin_address.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+
         Environment {
             p2p: crate::configuration::P2p {
-                listener_port: args
-                    .value_of("p2p-port")
-                    .unwrap_or("")
-                    .parse::<u16>()
-                    .expect("Was expecting value of p2p-port"),
+                // if we are behind NAT, we announce port of the public address
+                listener_port: discovery_address
+                    .map(|address| address.port())
+                    .unwrap_or(p2p_port),
+                listener_addresses: match args.values_of("listen-addr") {
+                    Some(values) => values
+                        .flat_map(|value| value.split(','))
+                        .map(|addr| {
+                            environment::parse_socket_addr(addr, p2p_port).unwrap_or_else(|_| {
+                                panic!("Was expecting 'IP' or 'IP:PORT', invalid value: {}", addr)
+                            })
+                        })
+                        .collect(),
+                    None => vec![SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), p2p_port)],
+                },
+                discovery_address,
                 disable_bootstrap_lookup: args.is_present("disable-bootstrap-lookup"),
                 bootstrap_lookup_addresses: args
                     .value_of("bootstrap-lookup-address")
@@ -802,7 +1191,13 @@ impl Environment {
                     .map(|peers_str| {
                         peers_str
                             .split(',')
-                            .map(|ip_port| ip_port.parse().expect("Was expecting IP:PORT"))
+                            .map(|ip_port| {
+                                environment::parse_socket_addr(
+                                    ip_port,
+                                    crate::configuration::P2p::DEFAULT_P2P_PORT_FOR_LOOKUP,
+                                )
+                                .expect("Was expecting IP:PORT")
+                            })
                             .collect()
                     })
                     .unwrap_or_default(),
@@ -821,6 +1216,15 @@ impl Environment {
                     }),
                 )
                 .expect("Invalid threashold range"),
+                synchronization_latency: args
+                    .value_of("synchronization-latency")
+                    .map(|v| {
+                        Duration::from_secs(
+                            v.parse::<u64>()
+                                .expect("Provided value cannot be converted to number"),
+                        )
+                    })
+                    .unwrap_or(DEFAULT_SYNCHRONIZATION_LATENCY),
                 private_node: args
                     .value_of("private-node")
                     .unwrap_or("false")
@@ -829,16 +1233,45 @@ impl Environment {
                 disable_mempool: args.is_present("disable-mempool"),
             },
             rpc: crate::configuration::Rpc {
-                listener_port: args
-                    .value_of("rpc-port")
-                    .unwrap_or("")
-                    .parse::<u16>()
-                    .expect("Was expecting value of rpc-port"),
+                listener_addresses: rpc_listener_addresses,
                 websocket_address: args
                     .value_of("websocket-address")
                     .unwrap_or("")
                     .parse()
                     .expect("Provided value cannot be converted into valid uri"),
+                protocol_cache_size_bytes: args.value_of("rpc-protocol-cache-size-mb").map_or(
+                    Rpc::DEFAULT_PROTOCOL_CACHE_SIZE_MB,
+                    |value| {
+                        value
+                            .parse::<usize>()
+                            .expect("Was expecting value of rpc-protocol-cache-size-mb")
+                    },
+                ) * 1024
+                    * 1024,
+                access_policy: RpcAccessPolicy {
+                    acl: RpcAccessControlList::default(),
+                    bearer_token: args.value_of("rpc-bearer-token").map(str::to_string),
+                    rate_limit: args.value_of("rpc-rate-limit").and_then(|value| {
+                        NonZeroU32::new(
+                            value
+                                .parse::<u32>()
+                                .expect("Was expecting value of rpc-rate-limit"),
+                        )
+                    }),
+                    max_request_size: args
+                        .value_of("rpc-max-request-size-kb")
+                        .map(|value| {
+                            value
+                                .parse::<usize>()
+                                .expect("Was expecting value of rpc-max-request-size-kb")
+                        })
+                        .filter(|size| *size > 0)
+                        .map(|size| size * 1024),
+                },
+                acl_allowed: parse_rpc_acl_rules(&args, "rpc-acl-allowed", &rpc_acl_addresses),
+                acl_denied: parse_rpc_acl_rules(&args, "rpc-acl-denied", &rpc_acl_addresses),
+                admin_address: rpc_admin_address,
+                admin_bearer_token: args.value_of("rpc-admin-bearer-token").map(str::to_string),
             },
             logging: crate::configuration::Logging {
                 ocaml_log_enabled: args
@@ -941,22 +1374,51 @@ impl Environment {
                     .parse::<bool>()
                     .expect("Provided value cannot be converted to bool");
 
+                let sinks: Vec<ContextActionSink> = args
+                    .values_of("actions-store-sink")
+                    .map(|v| {
+                        v.map(|sink| {
+                            sink.parse::<ContextActionSink>()
+                                .unwrap_or_else(|e| panic!("Invalid actions store sink - {}", e))
+                        })
+                        .collect()
+                    })
+                    .unwrap_or_default();
+
                 let backends: HashSet<String> = match args.values_of("actions-store-backend") {
                     Some(v) => v.map(String::from).collect(),
-                    None => std::iter::once("rocksdb".to_string()).collect(),
+                    // without any sink, we record to rocksdb by default
+                    None if sinks.is_empty() => std::iter::once("rocksdb".to_string()).collect(),
+                    None => HashSet::new(),
                 };
 
-                let action_store_backend = backends
+                let action_store_sinks = backends
                     .iter()
                     .map(|name| {
-                        ContextActionStoreBackend::from_str(name).unwrap_or_else(|_| {
-                            panic!(
-                                "Unknown backend {} - supported backends are: {:?}",
-                                &name,
-                                ContextActionStoreBackend::possible_values()
-                            )
-                        })
+                        let kind =
+                            match ContextActionStoreBackend::from_str(name).unwrap_or_else(|_| {
+                                panic!(
+                                    "Unknown backend {} - supported backends are: {:?}",
+                                    &name,
+                                    ContextActionStoreBackend::possible_values()
+                                )
+                            }) {
+                                ContextActionStoreBackend::RocksDB => {
+                                    ContextActionSinkKind::RocksDB
+                                }
+                                ContextActionStoreBackend::FileStorage => {
+                                    ContextActionSinkKind::FileStorage { path: None }
+                                }
+                                ContextActionStoreBackend::NoneBackend => {
+                                    ContextActionSinkKind::None
+                                }
+                            };
+                        ContextActionSink {
+                            kind,
+                            filter: Default::default(),
+                        }
                     })
+                    .chain(sinks)
                     .collect();
 
                 let kv_store_backend = args.value_of("kv-store-backend").map_or(
@@ -984,7 +1446,7 @@ impl Environment {
                     db_context_actions,
                     db_path,
                     compute_context_action_tree_hashes,
-                    action_store_backend,
+                    action_store_sinks,
                     kv_store_backend,
                     patch_context: {
                         match args.value_of("sandbox-patch-context-json-file") {
@@ -1027,6 +1489,10 @@ impl Environment {
                             }
                         }
                     },
+                    checkpoint: args.value_of("checkpoint").map(|v| {
+                        parse_checkpoint(v)
+                            .expect("Provided value cannot be converted to checkpoint")
+                    }),
                 }
             },
             identity: crate::configuration::Identity {
@@ -1074,6 +1540,19 @@ impl Environment {
                         .parse::<PathBuf>()
                         .expect("Provided value cannot be converted to path"),
                 },
+                context_actions_ipc_transport: args
+                    .value_of("ffi-context-actions-shm-size-in-mb")
+                    .map_or(IpcEvtServer::DEFAULT_TRANSPORT, |value| {
+                        match value
+                            .parse::<usize>()
+                            .expect("Provided value cannot be converted to number")
+                        {
+                            0 => IpcTransport::Socket,
+                            size_mb => IpcTransport::SharedMemory {
+                                capacity: size_mb * 1024 * 1024,
+                            },
+                        }
+                    }),
             },
             tokio_threads: args
                 .value_of("tokio-threads")
@@ -25,18 +25,24 @@ use shell::mempool::init_mempool_state_storage;
 use shell::mempool::mempool_prevalidator::MempoolPrevalidator;
 use shell::peer_manager::PeerManager;
 use shell::shell_channel::{ShellChannel, ShellChannelTopic, ShuttingDown};
+use shell::state::chain_state::BlockchainState;
 use shell::state::head_state::init_current_head_state;
 use shell::state::synchronization_state::init_synchronization_bootstrap_state_storage;
 use shell::stats::apply_block_stats::init_empty_apply_block_stats;
+use shell::stats::block_apply_timings::init_empty_block_apply_timings;
 use storage::persistent::{
     open_cl, open_kv, ActionRecorder, CommitLogSchema, DbConfiguration, NoRecorder,
     PersistentStorage,
 };
-use storage::ActionFileStorage;
 use storage::ContextActionStorage;
 use storage::{
     check_database_compatibility, context::TezedgeContext, persistent::DBError,
-    resolve_storage_init_chain_data, BlockStorage, StorageInitInfo,
+    resolve_storage_init_chain_data, BlockMetaStorage, BlockStorage, ChainMetaStorage,
+    StorageInitInfo,
+};
+use storage::{
+    ActionFileStorage, ActionStreamRecorder, ContextActionSinkKind, FilteredActionRecorder,
+    RotatingActionFileStorage,
 };
 use tezos_api::environment;
 use tezos_api::environment::TezosEnvironmentConfiguration;
@@ -45,7 +51,9 @@ use tezos_identity::Identity;
 use tezos_wrapper::service::IpcEvtServer;
 use tezos_wrapper::ProtocolEndpointConfiguration;
 use tezos_wrapper::TezosApiConnectionPoolError;
-use tezos_wrapper::{TezosApiConnectionPool, TezosApiConnectionPoolConfiguration};
+use tezos_wrapper::{
+    ProtocolRunnerLimits, TezosApiConnectionPool, TezosApiConnectionPoolConfiguration,
+};
 
 use crate::configuration::{Environment, LogFormat};
 
@@ -200,12 +208,13 @@ fn create_tezos_writeable_api_pool(
             connection_timeout: Duration::from_secs(30),
             min_connections: 0,
             max_connections: 1,
+            limits: ProtocolRunnerLimits::default(),
         },
         ProtocolEndpointConfiguration::new(
             TezosRuntimeConfiguration {
                 log_enabled: env.logging.ocaml_log_enabled,
                 compute_context_action_tree_hashes: env.storage.compute_context_action_tree_hashes,
-                debug_mode: !env.storage.action_store_backend.is_empty(),
+                debug_mode: !env.storage.action_store_sinks.is_empty(),
             },
             tezos_env,
             env.enable_testchain,
@@ -221,20 +230,52 @@ fn create_tezos_writeable_api_pool(
 fn build_recorders(
     env: &crate::configuration::Environment,
     storage: &PersistentStorage,
+    log: &Logger,
 ) -> Vec<Box<dyn ActionRecorder + Send>> {
     env.storage
-        .action_store_backend
+        .action_store_sinks
         .iter()
-        .map(|backend| match backend {
-            configuration::ContextActionStoreBackend::RocksDB => {
-                Box::new(ContextActionStorage::new(&storage)) as Box<dyn ActionRecorder + Send>
-            }
-            configuration::ContextActionStoreBackend::FileStorage => {
-                let action_file_path = env.storage.db_path.join("actionfile.bin");
-                Box::new(ActionFileStorage::new(action_file_path)) as Box<dyn ActionRecorder + Send>
-            }
-            configuration::ContextActionStoreBackend::NoneBackend => {
-                Box::new(NoRecorder {}) as Box<dyn ActionRecorder + Send>
+        .map(|sink| {
+            let recorder = match &sink.kind {
+                ContextActionSinkKind::None => {
+                    Box::new(NoRecorder {}) as Box<dyn ActionRecorder + Send>
+                }
+                ContextActionSinkKind::RocksDB => {
+                    Box::new(ContextActionStorage::new(&storage)) as Box<dyn ActionRecorder + Send>
+                }
+                ContextActionSinkKind::FileStorage { path } => {
+                    let action_file_path = path
+                        .clone()
+                        .unwrap_or_else(|| env.storage.db_path.join("actionfile.bin"));
+                    Box::new(ActionFileStorage::new(action_file_path))
+                        as Box<dyn ActionRecorder + Send>
+                }
+                ContextActionSinkKind::RotatingFileStorage {
+                    dir,
+                    max_file_size_bytes,
+                    max_files,
+                } => {
+                    let dir = dir
+                        .clone()
+                        .unwrap_or_else(|| env.storage.db_path.join("actionfiles"));
+                    Box::new(
+                        RotatingActionFileStorage::new(dir, *max_file_size_bytes, *max_files)
+                            .expect("Failed to initialize rotating action files"),
+                    ) as Box<dyn ActionRecorder + Send>
+                }
+                ContextActionSinkKind::Stream { path } => Box::new(
+                    ActionStreamRecorder::bind(path.clone())
+                        .expect("Failed to bind context actions stream socket"),
+                )
+                    as Box<dyn ActionRecorder + Send>,
+            };
+            info!(log, "Context actions recording activated"; "sink" => format!("{:?}", sink));
+
+            if sink.filter.is_empty() {
+                recorder
+            } else {
+                Box::new(FilteredActionRecorder::new(sink.filter.clone(), recorder))
+                    as Box<dyn ActionRecorder + Send>
             }
         })
         .collect::<Vec<_>>()
@@ -294,7 +335,8 @@ fn block_on_actors(
 
     // pool and event server dedicated for applying blocks to chain
     let context_actions_event_server =
-        IpcEvtServer::try_bind_new().expect("Failed to bind context event server");
+        IpcEvtServer::try_bind_new(env.ffi.context_actions_ipc_transport)
+            .expect("Failed to bind context event server");
     let tezos_writeable_api_pool = Arc::new(
         create_tezos_writeable_api_pool(
             context_actions_event_server.server_path(),
@@ -316,8 +358,10 @@ fn block_on_actors(
         env.p2p
             .peer_threshold
             .num_of_peers_for_bootstrap_threshold(),
+        env.p2p.synchronization_latency,
     );
     let apply_block_stats = init_empty_apply_block_stats();
+    let block_apply_timings = init_empty_block_apply_timings();
 
     // create tokio runtime
     let tokio_runtime = create_tokio_runtime(&env).expect("Failed to create tokio runtime");
@@ -338,8 +382,9 @@ fn block_on_actors(
         &actor_system,
         shell_channel.clone(),
         &persistent_storage,
-        build_recorders(&env, &persistent_storage),
+        build_recorders(&env, &persistent_storage, &log),
         context_actions_event_server,
+        block_apply_timings.clone(),
         log.clone(),
     )
     .expect("Failed to create context event listener");
@@ -363,6 +408,7 @@ fn block_on_actors(
         tezos_writeable_api_pool.clone(),
         init_storage_data.clone(),
         tezos_env.clone(),
+        block_apply_timings.clone(),
         log.clone(),
     )
     .expect("Failed to create chain feeder");
@@ -378,8 +424,9 @@ fn block_on_actors(
         local_current_head_state,
         remote_current_head_state,
         current_mempool_state_storage.clone(),
-        bootstrap_state,
+        bootstrap_state.clone(),
         apply_block_stats,
+        block_apply_timings.clone(),
         env.p2p.disable_mempool,
         identity.clone(),
     )
@@ -417,10 +464,12 @@ fn block_on_actors(
     let _ = RpcServer::actor(
         &actor_system,
         shell_channel.clone(),
-        ([0, 0, 0, 0], env.rpc.listener_port).into(),
+        env.rpc.listeners(),
         &tokio_runtime.handle(),
         &persistent_storage,
         current_mempool_state_storage,
+        block_apply_timings,
+        bootstrap_state,
         &tezedge_context,
         tezos_readonly_api_pool.clone(),
         tezos_readonly_prevalidation_api_pool.clone(),
@@ -429,6 +478,7 @@ fn block_on_actors(
         Arc::new(shell_compatibility_version.to_network_version()),
         &init_storage_data,
         is_sandbox,
+        env.rpc.protocol_cache_size_bytes,
     )
     .expect("Failed to create RPC server");
 
@@ -635,6 +685,31 @@ fn main() {
         ) {
             Ok(init_data) => {
                 info!(log, "Databases loaded successfully");
+                if let Some(checkpoint) = &env.storage.checkpoint {
+                    let chain_meta_storage = ChainMetaStorage::new(&persistent_storage);
+                    // checkpoint cannot be changed to a different branch than already stored current head
+                    let is_on_current_head_branch =
+                        BlockchainState::is_checkpoint_on_current_head_branch(
+                            &chain_meta_storage,
+                            &BlockMetaStorage::new(&persistent_storage),
+                            &init_data.chain_id,
+                            checkpoint,
+                        )
+                        .expect("Failed to validate configured checkpoint");
+                    if !is_on_current_head_branch {
+                        panic!(
+                            "Configured checkpoint (block_hash: {}, level: {}) is not on the branch of the stored current head, use different checkpoint or clean databases",
+                            checkpoint.block_hash().to_base58_check(),
+                            checkpoint.level()
+                        );
+                    }
+                    chain_meta_storage
+                        .set_checkpoint(&init_data.chain_id, checkpoint.clone())
+                        .expect("Failed to store configured checkpoint");
+                    info!(log, "Checkpoint configured";
+                               "block_hash" => checkpoint.block_hash().to_base58_check(),
+                               "level" => checkpoint.level());
+                }
                 block_on_actors(
                     env,
                     tezos_env,
@@ -166,6 +166,7 @@ mod tezos {
         ValidateOperationRequest, ValidateOperationResponse,
     };
     use tezos_client::client::*;
+    use tezos_messages::protocol::SUPPORTED_PROTOCOLS;
     use tezos_wrapper::protocol::ProtocolApi;
 
     pub struct NativeTezosLib;
@@ -263,5 +264,12 @@ mod tezos {
         ) -> Result<(), ProtocolDataError> {
             assert_encoding_for_protocol_data(protocol_hash, protocol_data)
         }
+
+        fn supported_protocols() -> Vec<ProtocolHash> {
+            SUPPORTED_PROTOCOLS
+                .keys()
+                .filter_map(|protocol_hash| ProtocolHash::from_base58_check(protocol_hash).ok())
+                .collect()
+        }
     }
 }
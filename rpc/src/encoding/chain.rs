@@ -6,7 +6,12 @@ use std::collections::HashMap;
 use serde::Serialize;
 use serde_json::Value;
 
-use crate::helpers::FullBlockInfo;
+use shell::state::synchronization_state::SynchronizationStatus;
+use tezos_messages::p2p::encoding::block_header::BlockHeader;
+use tezos_messages::protocol::receipts::Operation;
+use tezos_messages::{ts_to_rfc3339, Head};
+
+use crate::helpers::{BlockHeaderShellInfo, BlockMetadata, FullBlockInfo};
 
 use super::base_types::*;
 
@@ -22,19 +27,13 @@ pub struct BlockInfo {
     chain_id: Option<UniString>,
     hash: Option<UniString>,
     header: HashMap<String, Value>,
-    metadata: HashMap<String, Value>,
-    operations: Vec<Vec<HashMap<String, Value>>>,
+    metadata: BlockMetadata,
+    operations: Vec<Vec<Operation>>,
 }
 
 impl From<FullBlockInfo> for BlockInfo {
     fn from(val: FullBlockInfo) -> Self {
-        let protocol: Option<UniString> = match val.metadata.get("protocol") {
-            Some(value) => match value.as_str() {
-                Some(proto) => Some(proto.to_string().into()),
-                None => None,
-            },
-            None => None,
-        };
+        let protocol: Option<UniString> = val.metadata.protocol.clone().map(UniString::from);
 
         Self {
             protocol,
@@ -46,3 +45,82 @@ impl From<FullBlockInfo> for BlockInfo {
         }
     }
 }
+
+/// Block identification returned by `/chains/:chain_id/levels/*`
+#[derive(Serialize, Debug)]
+pub struct BlockLevelInfo {
+    block_hash: UniString,
+    level: i32,
+}
+
+impl From<Head> for BlockLevelInfo {
+    fn from(head: Head) -> Self {
+        Self {
+            block_hash: head.block_hash().to_base58_check().into(),
+            level: *head.level(),
+        }
+    }
+}
+
+/// Block header (shell header with raw protocol data) of checkpoint
+#[derive(Serialize, Debug)]
+pub struct CheckpointBlockHeader {
+    #[serde(flatten)]
+    shell: BlockHeaderShellInfo,
+    protocol_data: String,
+}
+
+impl From<&BlockHeader> for CheckpointBlockHeader {
+    fn from(header: &BlockHeader) -> Self {
+        Self {
+            shell: BlockHeaderShellInfo {
+                level: header.level(),
+                proto: header.proto(),
+                predecessor: header.predecessor().to_base58_check(),
+                timestamp: ts_to_rfc3339(header.timestamp()),
+                validation_pass: header.validation_pass(),
+                operations_hash: header.operations_hash().to_base58_check(),
+                fitness: header.fitness().iter().map(hex::encode).collect(),
+                context: header.context().to_base58_check(),
+            },
+            protocol_data: hex::encode(header.protocol_data()),
+        }
+    }
+}
+
+/// Checkpoint with savepoint and caboose levels returned by `/chains/:chain_id/checkpoint`
+#[derive(Serialize, Debug)]
+pub struct CheckpointInfo {
+    block: CheckpointBlockHeader,
+    savepoint: i32,
+    caboose: i32,
+    history_mode: UniString,
+}
+
+impl CheckpointInfo {
+    pub fn new(block: &BlockHeader, savepoint: i32, caboose: i32) -> Self {
+        Self {
+            block: block.into(),
+            savepoint,
+            caboose,
+            // Note: we store metadata for all blocks
+            history_mode: "archive".to_string().into(),
+        }
+    }
+}
+
+/// Bootstrapped status with synchronization state returned by `/chains/:chain_id/is_bootstrapped`
+#[derive(Serialize, Debug)]
+pub struct BootstrappedStatus {
+    bootstrapped: bool,
+    sync_state: SynchronizationStatus,
+}
+
+impl BootstrappedStatus {
+    pub fn new(bootstrapped: bool, sync_state: SynchronizationStatus) -> Self {
+        Self {
+            bootstrapped,
+            sync_state,
+        }
+    }
+}
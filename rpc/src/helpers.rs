@@ -19,8 +19,12 @@ use storage::{
     BlockStorageReader, ChainMetaStorage,
 };
 use tezos_api::ffi::{RpcMethod, RpcRequest};
+use tezos_messages::base::micheline::Micheline;
 use tezos_messages::p2p::encoding::block_header::Level;
 use tezos_messages::p2p::encoding::prelude::*;
+use tezos_messages::protocol::receipts::{
+    decode_block_header_metadata, decode_operations, BlockHeaderMetadata, Operation,
+};
 use tezos_messages::ts_to_rfc3339;
 
 use crate::encoding::base_types::UniString;
@@ -47,7 +51,7 @@ macro_rules! required_param {
     }};
 }
 
-pub type BlockMetadata = HashMap<String, Value>;
+pub type BlockMetadata = BlockHeaderMetadata;
 
 /// Object containing information to recreate the full block information
 #[derive(Serialize, Debug, Clone)]
@@ -56,7 +60,7 @@ pub struct FullBlockInfo {
     pub chain_id: String,
     pub header: InnerBlockHeader,
     pub metadata: BlockMetadata,
-    pub operations: Vec<Vec<HashMap<String, Value>>>,
+    pub operations: Vec<Vec<Operation>>,
 }
 
 /// Object containing all block header information
@@ -122,11 +126,12 @@ pub struct BlockHeaderShellInfo {
 }
 
 impl FullBlockInfo {
+    /// Fails, if stored json data (protocol data, metadata or receipts) cannot be decoded
     pub fn new(
         block: &BlockHeaderWithHash,
         block_json_data: &BlockJsonData,
         chain_id: &ChainId,
-    ) -> Self {
+    ) -> Result<Self, failure::Error> {
         let header: &BlockHeader = &block.header;
         let predecessor = header.predecessor().to_base58_check();
         let timestamp = ts_to_rfc3339(header.timestamp());
@@ -135,7 +140,7 @@ impl FullBlockInfo {
         let context = header.context().to_base58_check();
         let hash = block.hash.to_base58_check();
 
-        Self {
+        Ok(Self {
             hash,
             chain_id: chain_id_to_b58_string(chain_id),
             header: InnerBlockHeader {
@@ -147,14 +152,13 @@ impl FullBlockInfo {
                 operations_hash,
                 fitness,
                 context,
-                protocol_data: serde_json::from_str(block_json_data.block_header_proto_json())
-                    .unwrap_or_default(),
+                protocol_data: serde_json::from_str(block_json_data.block_header_proto_json())?,
             },
-            metadata: serde_json::from_str(block_json_data.block_header_proto_metadata_json())
-                .unwrap_or_default(),
-            operations: serde_json::from_str(block_json_data.operations_proto_metadata_json())
-                .unwrap_or_default(),
-        }
+            metadata: decode_block_header_metadata(
+                block_json_data.block_header_proto_metadata_json(),
+            )?,
+            operations: decode_operations(block_json_data.operations_proto_metadata_json())?,
+        })
     }
 }
 
@@ -538,6 +542,49 @@ pub(crate) fn get_action_types(action_types: &str) -> Vec<ContextActionType> {
         .collect()
 }
 
+/// Output format of Micheline values (contract code, storage and big map values) stored in the context
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum MichelineFormat {
+    /// Octez JSON - `{"prim": "Pair", "args": [...]}`
+    Json,
+    /// Michelson text - `Pair 1 "a"`
+    Text,
+}
+
+impl MichelineFormat {
+    /// Values are kept raw hex by default (as Octez does), which is returned as `None`, decoding is opt-in
+    pub(crate) fn parse(format: Option<&str>) -> Result<Option<Self>, failure::Error> {
+        match format {
+            None | Some("hex") => Ok(None),
+            Some("json") => Ok(Some(MichelineFormat::Json)),
+            Some("text") => Ok(Some(MichelineFormat::Text)),
+            Some(format) => bail!(
+                "Invalid micheline format '{}', expected 'json', 'text' or 'hex'",
+                format
+            ),
+        }
+    }
+
+    /// Decodes context value, returns `None` if it is not a valid Micheline expression.
+    pub(crate) fn decode(&self, value: &[u8]) -> Option<Value> {
+        let expression = Micheline::from_context_bytes(value).ok()?;
+        match self {
+            MichelineFormat::Json => serde_json::to_value(&expression).ok(),
+            MichelineFormat::Text => Some(Value::String(expression.to_string())),
+        }
+    }
+}
+
+/// Context keys holding Micheline values - `.../contracts/index/.../code`, `.../contracts/index/.../storage`
+/// and big map values `.../big_maps/index/.../contents/.../data`
+pub(crate) fn is_micheline_context_key(key: &[String]) -> bool {
+    match key.last().map(String::as_str) {
+        Some("code") | Some("storage") => key.iter().any(|k| k == "contracts"),
+        Some("data") => key.iter().any(|k| k == "big_maps"),
+        _ => false,
+    }
+}
+
 /// TODO: TE-238 - optimize context_hash/level index, not do deserialize whole header
 /// TODO: returns context_hash and level, but level is here just for one use-case, so maybe it could be splitted
 pub(crate) fn get_context_hash(
@@ -646,17 +693,11 @@ impl From<(BlockHeaderWithHash, BlockJsonData)> for SlimBlockData {
     fn from(
         (block_header_with_hash, block_json_data): (BlockHeaderWithHash, BlockJsonData),
     ) -> Self {
-        // TODO: TE-199 Refactor FullBlockInfo
         // deserialize the metadata
-        let metadata: BlockMetadata =
-            serde_json::from_str(block_json_data.block_header_proto_metadata_json())
-                .unwrap_or_default();
-
-        let cycle_position = if let Some(level) = metadata.get("level") {
-            level["cycle_position"].as_i64()
-        } else {
-            None
-        };
+        let cycle_position =
+            decode_block_header_metadata(block_json_data.block_header_proto_metadata_json())
+                .ok()
+                .and_then(|metadata| metadata.cycle_position());
 
         Self {
             level: block_header_with_hash.header.level(),
@@ -669,6 +710,8 @@ impl From<(BlockHeaderWithHash, BlockJsonData)> for SlimBlockData {
 
 #[cfg(test)]
 mod tests {
+    use storage::context_key;
+
     use super::*;
 
     // NOTE: safe-guard in case `http` changes to decoding percent-encoding parts of the URI.
@@ -685,4 +728,37 @@ mod tests {
         let expected = "/percent%20encoded?query=percent%20encoded";
         assert_eq!(expected, &path);
     }
+
+    #[test]
+    fn test_micheline_context_values() -> Result<(), failure::Error> {
+        assert!(is_micheline_context_key(&context_key!(
+            "data/contracts/index/ff/ff/ff/ff/ff/ff/0000ffffffff/storage"
+        )));
+        assert!(is_micheline_context_key(&context_key!(
+            "data/big_maps/index/ff/ff/ff/ff/ff/ff/1/contents/ff/ff/ff/ff/ff/ff/ffff/data"
+        )));
+        assert!(!is_micheline_context_key(&context_key!(
+            "data/contracts/index/ff/ff/ff/ff/ff/ff/0000ffffffff/balance"
+        )));
+        assert!(!is_micheline_context_key(&context_key!("data")));
+
+        let value = hex::decode("0000000a07070001010000000161")?;
+        assert_eq!(
+            Some(Value::String("Pair 1 \"a\"".to_string())),
+            MichelineFormat::Text.decode(&value)
+        );
+        assert_eq!(
+            Some(serde_json::json!({"prim": "Pair", "args": [{"int": "1"}, {"string": "a"}]})),
+            MichelineFormat::Json.decode(&value)
+        );
+        assert_eq!(None, MichelineFormat::Json.decode(&[0xff]));
+        assert_eq!(None, MichelineFormat::parse(None)?);
+        assert_eq!(
+            Some(MichelineFormat::Json),
+            MichelineFormat::parse(Some("json"))?
+        );
+        assert_eq!(None, MichelineFormat::parse(Some("hex"))?);
+        assert!(MichelineFormat::parse(Some("binary")).is_err());
+        Ok(())
+    }
 }
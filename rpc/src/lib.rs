@@ -5,6 +5,10 @@
 use hyper::{Body, Response, StatusCode};
 use slog::{error, Logger};
 
+pub use server::access_control::{
+    ParseRpcPathPatternError, RpcAccessControlList, RpcAccessPolicy, RpcAclRule, RpcListener,
+    RpcPathPattern,
+};
 pub use services::mempool_services::MempoolOperations;
 
 pub mod encoding;
@@ -125,6 +129,15 @@ pub(crate) fn not_found() -> ServiceResult {
         .body(Body::from("not found"))?)
 }
 
+/// Generate error response with given status and message as body
+pub(crate) fn error_with_status(status: StatusCode, error_msg: String) -> ServiceResult {
+    Ok(Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "text/plain")
+        .header(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Body::from(error_msg))?)
+}
+
 /// Generate 500 error
 pub(crate) fn error(error: failure::Error) -> ServiceResult {
     error_with_message(format!("{:?}", error))
@@ -1,7 +1,6 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
-use std::net::SocketAddr;
 use std::sync::{Arc, RwLock};
 
 use getset::{CopyGetters, Getters, Setters};
@@ -12,7 +11,9 @@ use tokio::runtime::Handle;
 use crypto::hash::ChainId;
 use shell::mempool::CurrentMempoolStateStorageRef;
 use shell::shell_channel::{ShellChannelMsg, ShellChannelRef};
-use shell::subscription::subscribe_to_shell_new_current_head;
+use shell::state::synchronization_state::SynchronizationBootstrapStateRef;
+use shell::stats::block_apply_timings::BlockApplyTimingsRef;
+use shell::subscription::{subscribe_to_shell_block_applied, subscribe_to_shell_new_current_head};
 use storage::context::TezedgeContext;
 use storage::persistent::PersistentStorage;
 use storage::{BlockHeaderWithHash, StorageInitInfo};
@@ -20,7 +21,14 @@ use tezos_api::environment::TezosEnvironmentConfiguration;
 use tezos_messages::p2p::encoding::version::NetworkVersion;
 use tezos_wrapper::TezosApiConnectionPool;
 
+use crate::server::access_control::RpcListener;
 use crate::server::{spawn_server, RpcServiceEnvironment};
+use crate::services::protocol::response_cache::{
+    init_protocol_rpc_response_cache, ProtocolRpcResponseCacheRef,
+};
+use crate::services::stream_services::{
+    BlockMonitorChannels, BlockMonitorChannelsRef, MONITOR_BLOCKS_CAPACITY,
+};
 
 pub type RpcServerRef = ActorRef<RpcServerMsg>;
 
@@ -43,6 +51,9 @@ pub struct RpcCollectedState {
 pub struct RpcServer {
     shell_channel: ShellChannelRef,
     state: RpcCollectedStateRef,
+    protocol_rpc_cache: ProtocolRpcResponseCacheRef,
+    block_monitor_channels: BlockMonitorChannelsRef,
+    main_chain_id: Arc<ChainId>,
 }
 
 impl RpcServer {
@@ -53,10 +64,12 @@ impl RpcServer {
     pub fn actor(
         sys: &ActorSystem,
         shell_channel: ShellChannelRef,
-        rpc_listen_address: SocketAddr,
+        rpc_listeners: Vec<RpcListener>,
         tokio_executor: &Handle,
         persistent_storage: &PersistentStorage,
         current_mempool_state_storage: CurrentMempoolStateStorageRef,
+        block_apply_timings: BlockApplyTimingsRef,
+        synchronization_state: SynchronizationBootstrapStateRef,
         tezedge_context: &TezedgeContext,
         tezos_readonly_api: Arc<TezosApiConnectionPool>,
         tezos_readonly_prevalidation_api: Arc<TezosApiConnectionPool>,
@@ -65,7 +78,10 @@ impl RpcServer {
         network_version: Arc<NetworkVersion>,
         init_storage_data: &StorageInitInfo,
         is_sandbox: bool,
+        protocol_rpc_cache_size_bytes: usize,
     ) -> Result<RpcServerRef, CreateError> {
+        let protocol_rpc_cache = init_protocol_rpc_response_cache(protocol_rpc_cache_size_bytes);
+        let block_monitor_channels = Arc::new(BlockMonitorChannels::new(MONITOR_BLOCKS_CAPACITY));
         let shared_state = Arc::new(RwLock::new(RpcCollectedState {
             current_head: load_current_head(
                 persistent_storage,
@@ -76,7 +92,13 @@ impl RpcServer {
         }));
         let actor_ref = sys.actor_of_props::<RpcServer>(
             Self::name(),
-            Props::new_args((shell_channel.clone(), shared_state.clone())),
+            Props::new_args((
+                shell_channel.clone(),
+                shared_state.clone(),
+                protocol_rpc_cache.clone(),
+                block_monitor_channels.clone(),
+                Arc::new(init_storage_data.chain_id.clone()),
+            )),
         )?;
 
         // spawn RPC JSON server
@@ -89,6 +111,10 @@ impl RpcServer {
                 network_version,
                 persistent_storage,
                 current_mempool_state_storage,
+                block_apply_timings,
+                protocol_rpc_cache,
+                block_monitor_channels,
+                synchronization_state,
                 tezedge_context,
                 tezos_readonly_api,
                 tezos_readonly_prevalidation_api,
@@ -98,25 +124,48 @@ impl RpcServer {
                 shared_state,
                 &sys.log(),
             );
-            let inner_log = sys.log();
 
-            tokio_executor.spawn(async move {
-                info!(inner_log, "Starting RPC server"; "address" => format!("{}", &rpc_listen_address));
-                if let Err(e) = spawn_server(&rpc_listen_address, env).await {
-                    error!(inner_log, "HTTP Server encountered failure"; "error" => format!("{}", e));
-                }
-            });
+            for rpc_listener in rpc_listeners {
+                let env = env.clone();
+                let inner_log = sys.log();
+
+                tokio_executor.spawn(async move {
+                    info!(inner_log, "Starting RPC server"; "address" => format!("{}", &rpc_listener.address));
+                    if let Err(e) = spawn_server(&rpc_listener, env).await {
+                        error!(inner_log, "HTTP Server encountered failure"; "address" => format!("{}", &rpc_listener.address), "error" => format!("{}", e));
+                    }
+                });
+            }
         }
 
         Ok(actor_ref)
     }
 }
 
-impl ActorFactoryArgs<(ShellChannelRef, RpcCollectedStateRef)> for RpcServer {
-    fn create_args((shell_channel, state): (ShellChannelRef, RpcCollectedStateRef)) -> Self {
+impl
+    ActorFactoryArgs<(
+        ShellChannelRef,
+        RpcCollectedStateRef,
+        ProtocolRpcResponseCacheRef,
+        BlockMonitorChannelsRef,
+        Arc<ChainId>,
+    )> for RpcServer
+{
+    fn create_args(
+        (shell_channel, state, protocol_rpc_cache, block_monitor_channels, main_chain_id): (
+            ShellChannelRef,
+            RpcCollectedStateRef,
+            ProtocolRpcResponseCacheRef,
+            BlockMonitorChannelsRef,
+            Arc<ChainId>,
+        ),
+    ) -> Self {
         Self {
             shell_channel,
             state,
+            protocol_rpc_cache,
+            block_monitor_channels,
+            main_chain_id,
         }
     }
 }
@@ -126,6 +175,7 @@ impl Actor for RpcServer {
 
     fn pre_start(&mut self, ctx: &Context<Self::Msg>) {
         subscribe_to_shell_new_current_head(&self.shell_channel, ctx.myself());
+        subscribe_to_shell_block_applied(&self.shell_channel, ctx.myself());
     }
 
     fn recv(&mut self, ctx: &Context<Self::Msg>, msg: Self::Msg, sender: Option<BasicActorRef>) {
@@ -137,9 +187,26 @@ impl Receive<ShellChannelMsg> for RpcServer {
     type Msg = RpcServerMsg;
 
     fn receive(&mut self, _ctx: &Context<Self::Msg>, msg: ShellChannelMsg, _sender: Sender) {
-        if let ShellChannelMsg::NewCurrentHead(_, block) = msg {
-            let current_head_ref = &mut *self.state.write().unwrap();
-            current_head_ref.current_head = Some(block);
+        match msg {
+            ShellChannelMsg::NewCurrentHead(_, block) => {
+                self.protocol_rpc_cache
+                    .lock()
+                    .unwrap()
+                    .current_head_changed(&block.hash, block.header.level());
+
+                {
+                    let current_head_ref = &mut *self.state.write().unwrap();
+                    current_head_ref.current_head = Some(block.clone());
+                }
+
+                self.block_monitor_channels
+                    .publish_new_head(self.main_chain_id.clone(), block);
+            }
+            ShellChannelMsg::BlockApplied(chain_id, block) => {
+                self.block_monitor_channels
+                    .publish_valid_block(chain_id, block);
+            }
+            _ => (),
         }
     }
 }
@@ -0,0 +1,692 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Access control of RPC server listeners, applied to every request before routing:
+//! per-ip rate limit, request size limit, bearer token authorization and Octez-like ACL
+//! (allowed/denied path patterns, e.g. `GET /chains/*/blocks/**`).
+
+use std::collections::{BTreeSet, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroU32;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use failure::Fail;
+use futures::StreamExt;
+use hyper::header::HeaderValue;
+use hyper::{Body, Method, Request, StatusCode};
+
+use crate::{error_with_status, ServiceResult};
+
+/// Patterns of tezedge admin/dev endpoints, which are served by admin listener (if configured)
+pub const ADMIN_ENDPOINTS: [&str; 2] = ["/dev/**", "/stats/**"];
+
+/// Max count of tracked ips, after that, the least recently used bucket is evicted
+const RATE_LIMITER_MAX_TRACKED_IPS: usize = 10_000;
+
+#[derive(Debug, Clone, Fail)]
+#[fail(display = "invalid rpc path pattern: {}", _0)]
+pub struct ParseRpcPathPatternError(String);
+
+#[derive(Debug, Clone, PartialEq)]
+enum PatternSegment {
+    Literal(String),
+    /// `*` - matches exactly one segment
+    AnySegment,
+    /// `**` - matches the rest of the path (including nothing)
+    AnySuffix,
+}
+
+/// Path pattern with optional method in format `[METHOD] /path/*/segments/**`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcPathPattern {
+    method: Option<Method>,
+    segments: Vec<PatternSegment>,
+}
+
+impl RpcPathPattern {
+    pub fn matches(&self, method: &Method, path: &str) -> bool {
+        if let Some(expected_method) = &self.method {
+            if expected_method != method {
+                return false;
+            }
+        }
+
+        let mut path_segments = path.split('/').filter(|segment| !segment.is_empty());
+        for pattern_segment in &self.segments {
+            match pattern_segment {
+                PatternSegment::AnySuffix => return true,
+                PatternSegment::AnySegment => {
+                    if path_segments.next().is_none() {
+                        return false;
+                    }
+                }
+                PatternSegment::Literal(literal) => match path_segments.next() {
+                    Some(segment) if segment == literal => (),
+                    _ => return false,
+                },
+            }
+        }
+        path_segments.next().is_none()
+    }
+}
+
+impl FromStr for RpcPathPattern {
+    type Err = ParseRpcPathPatternError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.split_whitespace();
+        let (method, path) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(path), None, None) => (None, path),
+            (Some(method), Some(path), None) => {
+                let method = method
+                    .to_ascii_uppercase()
+                    .parse::<Method>()
+                    .map_err(|_| ParseRpcPathPatternError(format!("{} (invalid method)", value)))?;
+                (Some(method), path)
+            }
+            _ => {
+                return Err(ParseRpcPathPatternError(format!(
+                    "{} (expected format: [METHOD] /path/*/segments/**)",
+                    value
+                )))
+            }
+        };
+
+        if !path.starts_with('/') {
+            return Err(ParseRpcPathPatternError(format!(
+                "{} (path must start with '/')",
+                value
+            )));
+        }
+
+        let segments = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| match segment {
+                "*" => PatternSegment::AnySegment,
+                "**" => PatternSegment::AnySuffix,
+                literal => PatternSegment::Literal(literal.to_string()),
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(position) = segments
+            .iter()
+            .position(|segment| segment == &PatternSegment::AnySuffix)
+        {
+            if position + 1 != segments.len() {
+                return Err(ParseRpcPathPatternError(format!(
+                    "{} ('**' is allowed only as the last segment)",
+                    value
+                )));
+            }
+        }
+
+        Ok(Self { method, segments })
+    }
+}
+
+/// Access control list, denied patterns take precedence over allowed,
+/// empty allowed patterns means, that everything (not denied) is allowed
+#[derive(Debug, Clone, Default)]
+pub struct RpcAccessControlList {
+    allowed: Vec<RpcPathPattern>,
+    denied: Vec<RpcPathPattern>,
+}
+
+impl RpcAccessControlList {
+    pub fn new(allowed: Vec<RpcPathPattern>, denied: Vec<RpcPathPattern>) -> Self {
+        Self { allowed, denied }
+    }
+
+    pub fn is_allowed(&self, method: &Method, path: &str) -> bool {
+        if self
+            .denied
+            .iter()
+            .any(|pattern| pattern.matches(method, path))
+        {
+            return false;
+        }
+        self.allowed.is_empty()
+            || self
+                .allowed
+                .iter()
+                .any(|pattern| pattern.matches(method, path))
+    }
+
+    /// Returns list with rules, which apply to listener with `address` (see [RpcAclRule])
+    pub fn for_listener(
+        address: &SocketAddr,
+        is_admin: bool,
+        allowed: &[RpcAclRule],
+        denied: &[RpcAclRule],
+    ) -> Self {
+        let select = |rules: &[RpcAclRule]| {
+            rules
+                .iter()
+                .filter(|rule| rule.applies_to(address, is_admin))
+                .map(|rule| rule.pattern.clone())
+                .collect()
+        };
+        Self::new(select(allowed), select(denied))
+    }
+}
+
+/// ACL rule in format `[IP:PORT] [METHOD] /path/*/segments/**`, rule with address applies only to
+/// listener with the same address (including admin listener), rule without address applies to all
+/// listeners except admin listener
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcAclRule {
+    pub address: Option<SocketAddr>,
+    pub pattern: RpcPathPattern,
+}
+
+impl RpcAclRule {
+    fn applies_to(&self, address: &SocketAddr, is_admin: bool) -> bool {
+        match &self.address {
+            Some(rule_address) => rule_address == address,
+            None => !is_admin,
+        }
+    }
+}
+
+impl FromStr for RpcAclRule {
+    type Err = ParseRpcPathPatternError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.trim().splitn(2, char::is_whitespace);
+        if let (Some(address), Some(pattern)) = (parts.next(), parts.next()) {
+            if let Ok(address) = address.parse::<SocketAddr>() {
+                return Ok(Self {
+                    address: Some(address),
+                    pattern: pattern.parse()?,
+                });
+            }
+        }
+        Ok(Self {
+            address: None,
+            pattern: value.parse()?,
+        })
+    }
+}
+
+/// Access policy of one RPC server listener
+#[derive(Debug, Clone, Default)]
+pub struct RpcAccessPolicy {
+    pub acl: RpcAccessControlList,
+    /// If set, requests must contain header `Authorization: Bearer <token>`
+    pub bearer_token: Option<String>,
+    /// Max count of requests per second from one ip (also max burst), None means unlimited
+    pub rate_limit: Option<NonZeroU32>,
+    /// Max size of request body in bytes, None means unlimited
+    pub max_request_size: Option<usize>,
+}
+
+impl RpcAccessPolicy {
+    /// Returns the same policy, but with admin endpoints denied (they are served by admin listener)
+    pub fn without_admin_endpoints(mut self) -> Self {
+        self.acl.denied.extend(admin_endpoints());
+        self
+    }
+
+    /// Policy for admin listener without rate limit, if `acl` does not allow any endpoint explicitly,
+    /// only admin endpoints are allowed
+    pub fn admin(
+        mut acl: RpcAccessControlList,
+        bearer_token: Option<String>,
+        max_request_size: Option<usize>,
+    ) -> Self {
+        if acl.allowed.is_empty() {
+            acl.allowed = admin_endpoints();
+        }
+        Self {
+            acl,
+            bearer_token,
+            rate_limit: None,
+            max_request_size,
+        }
+    }
+}
+
+fn admin_endpoints() -> Vec<RpcPathPattern> {
+    ADMIN_ENDPOINTS
+        .iter()
+        .map(|pattern| {
+            pattern
+                .parse::<RpcPathPattern>()
+                .expect("Invalid admin endpoint pattern")
+        })
+        .collect()
+}
+
+/// RPC server listen address with access policy applied to all its requests
+#[derive(Debug, Clone)]
+pub struct RpcListener {
+    pub address: SocketAddr,
+    pub policy: RpcAccessPolicy,
+}
+
+/// Request rejected by access control
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum AccessDenied {
+    TooManyRequests,
+    PayloadTooLarge(usize),
+    Unauthorized,
+    Forbidden,
+}
+
+impl AccessDenied {
+    pub(crate) fn into_response(self) -> ServiceResult {
+        let mut response = error_with_status(self.status(), self.message())?;
+        if self == AccessDenied::Unauthorized {
+            response.headers_mut().insert(
+                hyper::header::WWW_AUTHENTICATE,
+                HeaderValue::from_static("Bearer"),
+            );
+        }
+        Ok(response)
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            AccessDenied::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            AccessDenied::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AccessDenied::Unauthorized => StatusCode::UNAUTHORIZED,
+            AccessDenied::Forbidden => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            AccessDenied::TooManyRequests => "too many requests".to_string(),
+            AccessDenied::PayloadTooLarge(max_request_size) => {
+                format!("request body is too large (max {} bytes)", max_request_size)
+            }
+            AccessDenied::Unauthorized => "unauthorized".to_string(),
+            AccessDenied::Forbidden => "forbidden".to_string(),
+        }
+    }
+}
+
+/// Access control of one listener, shared by all its connections
+pub(crate) struct RpcAccessControl {
+    policy: RpcAccessPolicy,
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl RpcAccessControl {
+    pub(crate) fn new(policy: RpcAccessPolicy) -> Self {
+        let rate_limiter = policy.rate_limit.map(RateLimiter::new);
+        Self {
+            policy,
+            rate_limiter,
+        }
+    }
+
+    /// Checks request, returns request with size limited body, if it is accepted
+    pub(crate) fn check(
+        &self,
+        remote_ip: IpAddr,
+        path: &str,
+        req: Request<Body>,
+    ) -> Result<Request<Body>, AccessDenied> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            if !rate_limiter.try_acquire(remote_ip, Instant::now()) {
+                return Err(AccessDenied::TooManyRequests);
+            }
+        }
+
+        // preflight requests do not contain authorization
+        if req.method() != Method::OPTIONS {
+            if let Some(bearer_token) = &self.policy.bearer_token {
+                if !has_bearer_token(&req, bearer_token) {
+                    return Err(AccessDenied::Unauthorized);
+                }
+            }
+        }
+
+        if !self.policy.acl.is_allowed(req.method(), path) {
+            return Err(AccessDenied::Forbidden);
+        }
+
+        match self.policy.max_request_size {
+            Some(max_request_size) => {
+                let content_length = req
+                    .headers()
+                    .get(hyper::header::CONTENT_LENGTH)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<usize>().ok());
+                if matches!(content_length, Some(content_length) if content_length > max_request_size)
+                {
+                    return Err(AccessDenied::PayloadTooLarge(max_request_size));
+                }
+
+                // body without content-length (chunked) is checked during reading
+                let (parts, body) = req.into_parts();
+                Ok(Request::from_parts(
+                    parts,
+                    limit_body_size(body, max_request_size),
+                ))
+            }
+            None => Ok(req),
+        }
+    }
+}
+
+fn has_bearer_token(req: &Request<Body>, bearer_token: &str) -> bool {
+    req.headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| constant_time_eq(token.trim().as_bytes(), bearer_token.as_bytes()))
+        .unwrap_or(false)
+}
+
+/// Compares tokens without leaking position of the first difference through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn limit_body_size(body: Body, max_request_size: usize) -> Body {
+    let mut received = 0;
+    Body::wrap_stream(body.map(move |chunk| {
+        let chunk = chunk?;
+        received += chunk.len();
+        if received > max_request_size {
+            Err(Box::<dyn std::error::Error + Send + Sync>::from(
+                AccessDenied::PayloadTooLarge(max_request_size).message(),
+            ))
+        } else {
+            Ok(chunk)
+        }
+    }))
+}
+
+/// Token bucket per ip, bucket capacity and refill rate (per second) are the same
+struct RateLimiter {
+    requests_per_second: f64,
+    max_tracked_ips: usize,
+    state: Mutex<RateLimiterState>,
+}
+
+#[derive(Default)]
+struct RateLimiterState {
+    buckets: HashMap<IpAddr, (f64, Instant)>,
+    /// Buckets ordered by last update, so the least recently used one is found without scanning all buckets
+    by_last_update: BTreeSet<(Instant, IpAddr)>,
+}
+
+impl RateLimiter {
+    fn new(requests_per_second: NonZeroU32) -> Self {
+        Self {
+            requests_per_second: f64::from(requests_per_second.get()),
+            max_tracked_ips: RATE_LIMITER_MAX_TRACKED_IPS,
+            state: Mutex::new(RateLimiterState::default()),
+        }
+    }
+
+    fn try_acquire(&self, ip: IpAddr, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let RateLimiterState {
+            buckets,
+            by_last_update,
+        } = &mut *state;
+
+        // buckets, which were not used for a second, are full again, so we can forget them
+        while let Some(&(last_update, idle_ip)) = by_last_update.iter().next() {
+            if now.saturating_duration_since(last_update) < Duration::from_secs(1) {
+                break;
+            }
+            by_last_update.remove(&(last_update, idle_ip));
+            buckets.remove(&idle_ip);
+        }
+
+        // if still too many ips are tracked, evict the least recently used one
+        if !buckets.contains_key(&ip) && buckets.len() >= self.max_tracked_ips {
+            if let Some(&(last_update, lru_ip)) = by_last_update.iter().next() {
+                by_last_update.remove(&(last_update, lru_ip));
+                buckets.remove(&lru_ip);
+            }
+        }
+
+        let capacity = self.requests_per_second;
+        let (tokens, last_update) = buckets.entry(ip).or_insert((capacity, now));
+        by_last_update.remove(&(*last_update, ip));
+        let elapsed = now.saturating_duration_since(*last_update).as_secs_f64();
+        *tokens = (*tokens + elapsed * capacity).min(capacity);
+        *last_update = now;
+        by_last_update.insert((now, ip));
+
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn pattern(value: &str) -> RpcPathPattern {
+        value.parse().unwrap()
+    }
+
+    fn request(method: Method, headers: Vec<(&str, &str)>) -> Request<Body> {
+        let mut builder = Request::builder().method(method).uri("/chains/main/blocks");
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_path_pattern() {
+        let p = pattern("GET /chains/*/blocks/**");
+        assert!(p.matches(&Method::GET, "/chains/main/blocks"));
+        assert!(p.matches(&Method::GET, "/chains/main/blocks/head/header"));
+        assert!(!p.matches(&Method::POST, "/chains/main/blocks"));
+        assert!(!p.matches(&Method::GET, "/chains/main"));
+        assert!(!p.matches(&Method::GET, "/chains/main/mempool"));
+
+        let p = pattern("/injection/operation");
+        assert!(p.matches(&Method::POST, "/injection/operation"));
+        assert!(p.matches(&Method::GET, "/injection/operation/"));
+        assert!(!p.matches(&Method::POST, "/injection/operation/xyz"));
+        assert!(!p.matches(&Method::POST, "/injection"));
+
+        assert!(pattern("/**").matches(&Method::GET, "/"));
+        assert!(pattern("post /injection/*").method == Some(Method::POST));
+
+        assert!("chains/main".parse::<RpcPathPattern>().is_err());
+        assert!("/dev/**/blocks".parse::<RpcPathPattern>().is_err());
+        assert!("GET /a /b".parse::<RpcPathPattern>().is_err());
+        assert!("".parse::<RpcPathPattern>().is_err());
+    }
+
+    #[test]
+    fn test_access_control_list() {
+        // empty list allows everything
+        assert!(RpcAccessControlList::default().is_allowed(&Method::GET, "/dev/version"));
+
+        let acl = RpcAccessControlList::new(
+            vec![pattern("GET /chains/**"), pattern("/injection/**")],
+            vec![pattern("/chains/*/mempool/**")],
+        );
+        assert!(acl.is_allowed(&Method::GET, "/chains/main/blocks/head"));
+        assert!(acl.is_allowed(&Method::POST, "/injection/operation"));
+        assert!(!acl.is_allowed(&Method::GET, "/chains/main/mempool/pending_operations"));
+        assert!(!acl.is_allowed(&Method::POST, "/chains/main/blocks/head/helpers"));
+        assert!(!acl.is_allowed(&Method::GET, "/dev/version"));
+
+        let acl = RpcAccessPolicy::default().without_admin_endpoints().acl;
+        assert!(acl.is_allowed(&Method::GET, "/chains/main/blocks/head"));
+        assert!(!acl.is_allowed(&Method::GET, "/dev/version"));
+        assert!(!acl.is_allowed(&Method::GET, "/stats/memory"));
+
+        let acl = RpcAccessPolicy::admin(RpcAccessControlList::default(), None, None).acl;
+        assert!(!acl.is_allowed(&Method::GET, "/chains/main/blocks/head"));
+        assert!(acl.is_allowed(&Method::GET, "/dev/version"));
+        assert!(acl.is_allowed(&Method::GET, "/stats/memory"));
+    }
+
+    #[test]
+    fn test_acl_rules_per_listener() {
+        let rule = |value: &str| value.parse::<RpcAclRule>().unwrap();
+        let public: SocketAddr = "0.0.0.0:18732".parse().unwrap();
+        let local: SocketAddr = "[::1]:18732".parse().unwrap();
+        let admin: SocketAddr = "127.0.0.1:18733".parse().unwrap();
+
+        assert_eq!(
+            RpcAclRule {
+                address: Some(local),
+                pattern: pattern("POST /injection/**"),
+            },
+            rule("[::1]:18732 POST /injection/**")
+        );
+        assert_eq!(None, rule("GET /chains/**").address);
+        assert!("127.0.0.1:18733".parse::<RpcAclRule>().is_err());
+        assert!("127.0.0.1:18733 chains".parse::<RpcAclRule>().is_err());
+
+        let allowed = vec![
+            rule("GET /chains/**"),
+            rule("[::1]:18732 POST /injection/**"),
+        ];
+        let denied = vec![
+            rule("/chains/*/mempool/**"),
+            rule("127.0.0.1:18733 /dev/**"),
+        ];
+
+        let acl = RpcAccessControlList::for_listener(&public, false, &allowed, &denied);
+        assert!(acl.is_allowed(&Method::GET, "/chains/main/blocks/head"));
+        assert!(!acl.is_allowed(&Method::POST, "/injection/operation"));
+        assert!(!acl.is_allowed(&Method::GET, "/chains/main/mempool/pending_operations"));
+
+        let acl = RpcAccessControlList::for_listener(&local, false, &allowed, &denied);
+        assert!(acl.is_allowed(&Method::GET, "/chains/main/blocks/head"));
+        assert!(acl.is_allowed(&Method::POST, "/injection/operation"));
+
+        // rules without address do not apply to admin listener
+        let acl = RpcAccessPolicy::admin(
+            RpcAccessControlList::for_listener(&admin, true, &allowed, &denied),
+            None,
+            None,
+        )
+        .acl;
+        assert!(!acl.is_allowed(&Method::GET, "/chains/main/blocks/head"));
+        assert!(!acl.is_allowed(&Method::GET, "/dev/version"));
+        assert!(acl.is_allowed(&Method::GET, "/stats/memory"));
+
+        // admin listener can allow other endpoints explicitly
+        let allowed = vec![rule("127.0.0.1:18733 GET /chains/**")];
+        let acl = RpcAccessPolicy::admin(
+            RpcAccessControlList::for_listener(&admin, true, &allowed, &[]),
+            None,
+            None,
+        )
+        .acl;
+        assert!(acl.is_allowed(&Method::GET, "/chains/main/blocks/head"));
+        assert!(!acl.is_allowed(&Method::GET, "/stats/memory"));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let rate_limiter = RateLimiter::new(NonZeroU32::new(2).unwrap());
+        let ip1 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let ip2 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let now = Instant::now();
+
+        assert!(rate_limiter.try_acquire(ip1, now));
+        assert!(rate_limiter.try_acquire(ip1, now));
+        assert!(!rate_limiter.try_acquire(ip1, now));
+        // other ip has its own bucket
+        assert!(rate_limiter.try_acquire(ip2, now));
+
+        // half a second refills one token
+        let later = now + Duration::from_millis(500);
+        assert!(rate_limiter.try_acquire(ip1, later));
+        assert!(!rate_limiter.try_acquire(ip1, later));
+    }
+
+    #[test]
+    fn test_rate_limiter_max_tracked_ips() {
+        let mut rate_limiter = RateLimiter::new(NonZeroU32::new(1).unwrap());
+        rate_limiter.max_tracked_ips = 2;
+        let ip1 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let ip2 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let ip3 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3));
+        let now = Instant::now();
+        let tracked_ips = |rate_limiter: &RateLimiter| {
+            let state = rate_limiter.state.lock().unwrap();
+            assert_eq!(state.buckets.len(), state.by_last_update.len());
+            state.buckets.len()
+        };
+
+        assert!(rate_limiter.try_acquire(ip1, now));
+        assert!(rate_limiter.try_acquire(ip2, now + Duration::from_millis(100)));
+        assert!(!rate_limiter.try_acquire(ip1, now + Duration::from_millis(200)));
+
+        // ip2 is the least recently used, so it is evicted for ip3
+        assert!(rate_limiter.try_acquire(ip3, now + Duration::from_millis(300)));
+        assert_eq!(2, tracked_ips(&rate_limiter));
+        assert!(!rate_limiter.try_acquire(ip1, now + Duration::from_millis(400)));
+        assert!(!rate_limiter.try_acquire(ip3, now + Duration::from_millis(400)));
+
+        // idle buckets are forgotten
+        assert!(rate_limiter.try_acquire(ip1, now + Duration::from_secs(2)));
+        assert_eq!(1, tracked_ips(&rate_limiter));
+    }
+
+    #[test]
+    fn test_check_request() {
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let access_control = RpcAccessControl::new(RpcAccessPolicy {
+            acl: RpcAccessControlList::new(vec![], vec![pattern("POST /chains/**")]),
+            bearer_token: Some("secret".to_string()),
+            rate_limit: None,
+            max_request_size: Some(10),
+        });
+        let path = "/chains/main/blocks";
+
+        let check = |req| access_control.check(ip, path, req).err();
+
+        assert_eq!(
+            check(request(Method::GET, vec![])),
+            Some(AccessDenied::Unauthorized)
+        );
+        assert_eq!(
+            check(request(
+                Method::GET,
+                vec![("Authorization", "Bearer wrong")]
+            )),
+            Some(AccessDenied::Unauthorized)
+        );
+        assert_eq!(
+            check(request(
+                Method::GET,
+                vec![("Authorization", "Bearer secret")]
+            )),
+            None
+        );
+        assert_eq!(check(request(Method::OPTIONS, vec![])), None);
+        assert_eq!(
+            check(request(
+                Method::POST,
+                vec![("Authorization", "Bearer secret")]
+            )),
+            Some(AccessDenied::Forbidden)
+        );
+        assert_eq!(
+            check(request(
+                Method::GET,
+                vec![("Authorization", "Bearer secret"), ("Content-Length", "11")]
+            )),
+            Some(AccessDenied::PayloadTooLarge(10))
+        );
+    }
+}
@@ -0,0 +1,538 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Descriptions of the RPC services, which are registered together with routes
+//! and served by Tezos compatible `/describe` RPC.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use hyper::Method;
+use serde_json::{json, Map, Value};
+
+use tezos_encoding::binary_schema::binary_schema;
+use tezos_encoding::encoding::Encoding;
+use tezos_encoding::json_schema::json_schema;
+
+/// Order of methods in which services are described
+const DESCRIBED_METHODS: [Method; 5] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::PATCH,
+];
+
+#[derive(Clone, Debug)]
+pub enum QueryParamKind {
+    /// Parameter is required exactly once
+    Single,
+    /// Parameter could be omitted
+    Optional,
+    /// Parameter could be present multiple times
+    Multi,
+    /// Parameter without value, just presence is checked
+    Flag,
+}
+
+/// Description of the supported query parameter
+#[derive(Clone, Debug)]
+pub struct QueryParam {
+    name: &'static str,
+    kind: QueryParamKind,
+    arg: &'static str,
+    description: &'static str,
+}
+
+impl QueryParam {
+    pub fn single(name: &'static str, arg: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            kind: QueryParamKind::Single,
+            arg,
+            description,
+        }
+    }
+
+    pub fn optional(name: &'static str, arg: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            kind: QueryParamKind::Optional,
+            arg,
+            description,
+        }
+    }
+
+    pub fn multi(name: &'static str, arg: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            kind: QueryParamKind::Multi,
+            arg,
+            description,
+        }
+    }
+
+    pub fn flag(name: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            kind: QueryParamKind::Flag,
+            arg: "flag",
+            description,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let arg = json!({ "id": "single", "name": self.arg });
+        let kind = match self.kind {
+            QueryParamKind::Single => json!({ "single": arg }),
+            QueryParamKind::Optional => json!({ "optional": arg }),
+            QueryParamKind::Multi => json!({ "multi": arg }),
+            QueryParamKind::Flag => json!({ "flag": {} }),
+        };
+        json!({
+            "name": self.name,
+            "description": self.description,
+            "kind": kind,
+        })
+    }
+}
+
+/// Description of the RPC service (query parameters, input/output schemas)
+#[derive(Clone, Debug)]
+pub struct ServiceDescription {
+    description: String,
+    query: Vec<QueryParam>,
+    input: Option<Encoding>,
+    output: Option<Encoding>,
+}
+
+impl ServiceDescription {
+    pub fn new(description: &str) -> Self {
+        Self {
+            description: description.to_string(),
+            query: Vec::new(),
+            input: None,
+            output: None,
+        }
+    }
+
+    pub fn query(mut self, param: QueryParam) -> Self {
+        self.query.push(param);
+        self
+    }
+
+    pub fn input(mut self, encoding: Encoding) -> Self {
+        self.input = Some(encoding);
+        self
+    }
+
+    pub fn output(mut self, encoding: Encoding) -> Self {
+        self.output = Some(encoding);
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum PathSegment {
+    /// Static part of the path, e.g.: `chains`
+    Static(String),
+    /// Path parameter, e.g.: `:chain_id`
+    Arg(String),
+    /// Parameter matching the rest of the path, e.g.: `*any`
+    Rest(String),
+}
+
+impl PathSegment {
+    fn parse_path(path: &str) -> Vec<PathSegment> {
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| {
+                if let Some(name) = segment.strip_prefix(':') {
+                    PathSegment::Arg(name.to_string())
+                } else if let Some(name) = segment.strip_prefix('*') {
+                    PathSegment::Rest(name.to_string())
+                } else {
+                    PathSegment::Static(segment.to_string())
+                }
+            })
+            .collect()
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            PathSegment::Static(name) => json!(name),
+            PathSegment::Arg(name) | PathSegment::Rest(name) => arg_json(name),
+        }
+    }
+}
+
+fn arg_json(name: &str) -> Value {
+    let mut arg = Map::new();
+    arg.insert("id".to_string(), json!("single"));
+    arg.insert("name".to_string(), json!(name));
+    if let Some(descr) = arg_description(name) {
+        arg.insert("descr".to_string(), json!(descr));
+    }
+    Value::Object(arg)
+}
+
+fn arg_description(name: &str) -> Option<&'static str> {
+    match name {
+        "chain_id" => Some("A chain identifier. This is either a chain hash in Base58Check notation or a one the predefined aliases: 'main', 'test'."),
+        "block_id" => Some("A block identifier. This is either a block hash in Base58Check notation, one the predefined aliases: 'genesis', 'head' or a block level (index in the chain). One might also use 'head~N' or '<hash>~N' where N is an integer to denote the Nth predecessor of the designated block. Also, '<hash>+N' denotes the Nth successor of a block."),
+        "block_hash" => Some("A block identifier (Base58Check-encoded)"),
+        "contract_address" | "contract_id" => Some("A contract identifier encoded in b58check."),
+        "pkh" => Some("A Secp256k1 of a Ed25519 public key hash (Base58Check-encoded)"),
+        _ => None,
+    }
+}
+
+/// Registered route with its description
+struct RouteDescription {
+    path: Vec<PathSegment>,
+    methods: Arc<HashSet<Method>>,
+    service: ServiceDescription,
+}
+
+/// Route matched by requested path, with the rest of the unmatched route path
+struct MatchedRoute<'a> {
+    route: &'a RouteDescription,
+    remaining: &'a [PathSegment],
+    /// Indicates that part of the requested path was matched by [PathSegment::Rest]
+    dispatched: bool,
+}
+
+/// Directory of all registered routes with their descriptions
+#[derive(Default)]
+pub struct RpcDirectory {
+    routes: Vec<RouteDescription>,
+}
+
+impl RpcDirectory {
+    pub fn register(
+        &mut self,
+        path: &str,
+        methods: Arc<HashSet<Method>>,
+        service: ServiceDescription,
+    ) {
+        self.routes.push(RouteDescription {
+            path: PathSegment::parse_path(path),
+            methods,
+            service,
+        });
+    }
+
+    /// Describes directory at requested path in the same format as Tezos RPC `/describe`.
+    ///
+    /// Returns None, if there is no route for requested path.
+    pub fn describe(&self, path: &[&str], recurse: bool) -> Option<Value> {
+        let mut matched = self
+            .routes
+            .iter()
+            .filter_map(|route| Self::match_route(route, path))
+            .collect::<Vec<_>>();
+
+        // the same as for routing, static routes take precedence over the catch-all ones
+        if matched.iter().any(|m| !m.dispatched) {
+            matched.retain(|m| !m.dispatched);
+        }
+
+        if matched.is_empty() {
+            None
+        } else {
+            Some(Self::describe_node(path, &matched, recurse))
+        }
+    }
+
+    fn match_route<'a>(route: &'a RouteDescription, path: &[&str]) -> Option<MatchedRoute<'a>> {
+        for (idx, requested) in path.iter().enumerate() {
+            match route.path.get(idx) {
+                Some(PathSegment::Static(name)) if name == requested => continue,
+                Some(PathSegment::Static(_)) => return None,
+                Some(PathSegment::Arg(_)) => continue,
+                Some(PathSegment::Rest(_)) => {
+                    return Some(MatchedRoute {
+                        route,
+                        remaining: &route.path[route.path.len()..],
+                        dispatched: true,
+                    })
+                }
+                None => return None,
+            }
+        }
+        Some(MatchedRoute {
+            route,
+            remaining: &route.path[path.len()..],
+            dispatched: false,
+        })
+    }
+
+    fn describe_node(path: &[&str], matched: &[MatchedRoute], recurse: bool) -> Value {
+        let mut node = Map::new();
+
+        // services registered directly for this path
+        for matched_route in matched.iter().filter(|m| m.remaining.is_empty()) {
+            for method in Self::service_methods(matched_route) {
+                node.insert(
+                    format!("{}_service", method.as_str().to_lowercase()),
+                    Self::describe_service(&method, matched_route.route),
+                );
+            }
+        }
+
+        // subdirectories
+        let mut suffixes: Vec<(String, Vec<MatchedRoute>)> = Vec::new();
+        let mut dynamic: Option<(&PathSegment, Vec<MatchedRoute>)> = None;
+        for matched_route in matched.iter().filter(|m| !m.remaining.is_empty()) {
+            let child = MatchedRoute {
+                route: matched_route.route,
+                remaining: &matched_route.remaining[1..],
+                dispatched: false,
+            };
+            match &matched_route.remaining[0] {
+                PathSegment::Static(name) => match suffixes.iter_mut().find(|(n, _)| n == name) {
+                    Some((_, children)) => children.push(child),
+                    None => suffixes.push((name.clone(), vec![child])),
+                },
+                // the rest of the path is resolved dynamically, so there is nothing to describe
+                PathSegment::Rest(_) => (),
+                arg => match &mut dynamic {
+                    Some((_, children)) => children.push(child),
+                    None => dynamic = Some((arg, vec![child])),
+                },
+            }
+        }
+
+        let mut subdirs = Map::new();
+        if !suffixes.is_empty() {
+            suffixes.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let suffixes = suffixes
+                .iter()
+                .map(|(name, children)| {
+                    let tree = if recurse {
+                        let mut child_path = path.to_vec();
+                        child_path.push(name);
+                        Self::describe_node(&child_path, children, recurse)
+                    } else {
+                        json!("empty")
+                    };
+                    json!({ "name": name, "tree": tree })
+                })
+                .collect::<Vec<_>>();
+            subdirs.insert("suffixes".to_string(), json!(suffixes));
+        }
+        if let Some((arg, children)) = dynamic {
+            let tree = if recurse {
+                let mut child_path = path.to_vec();
+                child_path.push("");
+                Self::describe_node(&child_path, &children, recurse)
+            } else {
+                json!("empty")
+            };
+            subdirs.insert(
+                "dynamic_dispatch".to_string(),
+                json!({ "arg": arg.to_json(), "tree": tree }),
+            );
+        }
+        if !subdirs.is_empty() {
+            node.insert("subdirs".to_string(), Value::Object(subdirs));
+        }
+
+        json!({ "static": node })
+    }
+
+    /// Methods are always taken from the registered route, routes dispatched to the protocol
+    /// (e.g. `*any`) are described with all methods, which the route accepts
+    fn service_methods(matched_route: &MatchedRoute) -> Vec<Method> {
+        DESCRIBED_METHODS
+            .iter()
+            .filter(|method| matched_route.route.methods.contains(method))
+            .cloned()
+            .collect()
+    }
+
+    fn describe_service(method: &Method, route: &RouteDescription) -> Value {
+        let mut service = Map::new();
+        service.insert("meth".to_string(), json!(method.as_str()));
+        service.insert(
+            "path".to_string(),
+            Value::Array(route.path.iter().map(PathSegment::to_json).collect()),
+        );
+        service.insert("description".to_string(), json!(route.service.description));
+        service.insert(
+            "query".to_string(),
+            Value::Array(
+                route
+                    .service
+                    .query
+                    .iter()
+                    .map(QueryParam::to_json)
+                    .collect(),
+            ),
+        );
+        if let Some(input) = &route.service.input {
+            service.insert("input".to_string(), schemas(Some(input)));
+        }
+        service.insert("output".to_string(), schemas(route.service.output.as_ref()));
+        service.insert("error".to_string(), schemas(None));
+        Value::Object(service)
+    }
+}
+
+/// Returns json and binary schema for encoding, if no encoding is provided, then any value is described
+fn schemas(encoding: Option<&Encoding>) -> Value {
+    match encoding {
+        Some(encoding) => json!({
+            "json_schema": json_schema(encoding),
+            "binary_schema": binary_schema(encoding),
+        }),
+        None => json!({
+            "json_schema": {},
+            "binary_schema": {
+                "toplevel": {
+                    "fields": [],
+                },
+                "fields": [],
+            },
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto::hash::HashType;
+
+    use super::*;
+
+    macro_rules! methods {
+        ( $( $x:expr ),* ) => {
+            Arc::new(vec![$($x),*].into_iter().collect::<HashSet<Method>>())
+        };
+    }
+
+    fn directory() -> RpcDirectory {
+        let mut directory = RpcDirectory::default();
+        directory.register(
+            "/chains/:chain_id/blocks",
+            methods![Method::GET],
+            ServiceDescription::new("Lists known heads of the blockchain")
+                .query(QueryParam::optional(
+                    "length",
+                    "uint",
+                    "The requested number of predecessors to return",
+                ))
+                .output(Encoding::list(Encoding::list(Encoding::Hash(
+                    HashType::BlockHash,
+                )))),
+        );
+        directory.register(
+            "/chains/:chain_id/blocks/:block_id/hash",
+            methods![Method::GET],
+            ServiceDescription::new("The block's hash").output(Encoding::Hash(HashType::BlockHash)),
+        );
+        directory.register(
+            "/chains/:chain_id/chain_id",
+            methods![Method::GET],
+            ServiceDescription::new("The chain unique identifier")
+                .output(Encoding::Hash(HashType::ChainId)),
+        );
+        directory.register(
+            "/chains/:chain_id/blocks/:block_id/*any",
+            methods![Method::GET, Method::POST],
+            ServiceDescription::new("Protocol RPC"),
+        );
+        directory
+    }
+
+    #[test]
+    fn test_describe_service() {
+        let directory = directory();
+
+        let description = directory
+            .describe(&["chains", "main", "blocks", "head", "hash"], false)
+            .unwrap();
+        let service = &description["static"]["get_service"];
+        assert_eq!("GET", service["meth"]);
+        assert_eq!("The block's hash", service["description"]);
+        assert_eq!(json!("chains"), service["path"][0]);
+        assert_eq!("chain_id", service["path"][1]["name"]);
+        assert_eq!("block_id", service["path"][3]["name"]);
+        assert_eq!(
+            "#/definitions/block_hash",
+            service["output"]["json_schema"]["allOf"][0]["$ref"]
+        );
+        assert_eq!(
+            32,
+            service["output"]["binary_schema"]["toplevel"]["fields"][0]["data_kind"]["size"]
+        );
+        assert!(description["static"]["post_service"].is_null());
+
+        let description = directory
+            .describe(&["chains", "main", "blocks"], false)
+            .unwrap();
+        let service = &description["static"]["get_service"];
+        assert_eq!("length", service["query"][0]["name"]);
+        assert_eq!("uint", service["query"][0]["kind"]["optional"]["name"]);
+        assert_eq!("array", service["output"]["json_schema"]["type"]);
+        assert_eq!(
+            "block_id",
+            description["static"]["subdirs"]["dynamic_dispatch"]["arg"]["name"]
+        );
+    }
+
+    #[test]
+    fn test_describe_directory() {
+        let directory = directory();
+
+        let description = directory.describe(&["chains", "main"], false).unwrap();
+        assert!(description["static"]["get_service"].is_null());
+        assert_eq!(
+            json!([{ "name": "blocks", "tree": "empty" }, { "name": "chain_id", "tree": "empty" }]),
+            description["static"]["subdirs"]["suffixes"]
+        );
+
+        let description = directory.describe(&[], true).unwrap();
+        let chains = &description["static"]["subdirs"]["suffixes"][0];
+        assert_eq!("chains", chains["name"]);
+        let chain = &chains["tree"]["static"]["subdirs"]["dynamic_dispatch"];
+        assert_eq!("chain_id", chain["arg"]["name"]);
+        assert_eq!(
+            "chain_id",
+            chain["tree"]["static"]["subdirs"]["suffixes"][1]["name"]
+        );
+        assert_eq!(
+            "GET",
+            chain["tree"]["static"]["subdirs"]["suffixes"][1]["tree"]["static"]["get_service"]
+                ["meth"]
+        );
+
+        assert!(directory.describe(&["unknown"], false).is_none());
+        assert!(directory
+            .describe(&["chains", "main", "unknown"], false)
+            .is_none());
+    }
+
+    #[test]
+    fn test_describe_protocol_rpc() {
+        let directory = directory();
+
+        // methods are taken from the catch-all route, not guessed from the path
+        for path in &[
+            vec!["chains", "main", "blocks", "head", "context", "contracts"],
+            vec![
+                "chains",
+                "main",
+                "blocks",
+                "head",
+                "helpers",
+                "forge",
+                "operations",
+            ],
+        ] {
+            let description = directory.describe(path, false).unwrap();
+            assert_eq!("GET", description["static"]["get_service"]["meth"]);
+            assert_eq!("POST", description["static"]["post_service"]["meth"]);
+            assert!(description["static"]["put_service"].is_null());
+        }
+    }
+}
@@ -8,7 +8,10 @@ use slog::warn;
 use crate::helpers::{parse_block_hash, parse_chain_id, SlimBlockData, MAIN_CHAIN_ID};
 use crate::server::{HasSingleValue, Params, Query, RpcServiceEnvironment};
 use crate::services::{base_services, dev_services};
-use crate::{empty, make_json_response, required_param, result_to_json_response, ServiceResult};
+use crate::{
+    empty, make_json_response, required_param, result_option_to_json_response,
+    result_to_json_response, ServiceResult,
+};
 
 pub async fn dev_blocks(
     _: Request<Body>,
@@ -190,6 +193,61 @@ pub async fn dev_stats_memory_protocol_runners(
     }
 }
 
+pub async fn dev_stats_memory_protocol_runner_pools(
+    _: Request<Body>,
+    _: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    make_json_response(&dev_services::get_stats_memory_protocol_runner_pools(&env))
+}
+
+pub async fn dev_block_apply_stats(
+    _: Request<Body>,
+    params: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id_param = MAIN_CHAIN_ID;
+    let chain_id = parse_chain_id(chain_id_param, &env)?;
+    let block_hash = parse_block_hash(&chain_id, required_param!(params, "block_hash")?, &env)?;
+    result_option_to_json_response(
+        dev_services::get_block_apply_stats(&block_hash, &env),
+        env.log(),
+    )
+}
+
+pub async fn dev_slowest_block_apply_stats(
+    _: Request<Body>,
+    _: Params,
+    query: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let limit = query.get_usize("limit").unwrap_or(50);
+    result_to_json_response(
+        dev_services::get_slowest_block_apply_stats(limit, &env),
+        env.log(),
+    )
+}
+
+pub async fn dev_stats_protocol_runners(
+    _: Request<Body>,
+    _: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    make_json_response(&dev_services::get_stats_protocol_runners(&env))
+}
+
+pub async fn dev_stats_protocol_rpc_cache(
+    _: Request<Body>,
+    _: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    make_json_response(&dev_services::get_stats_protocol_rpc_cache(&env))
+}
+
 pub async fn context_stats(
     _: Request<Body>,
     _: Params,
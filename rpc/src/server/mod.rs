@@ -3,11 +3,11 @@
 
 use std::collections::{HashMap, HashSet};
 use std::future::Future;
-use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 
 use getset::Getters;
+use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Method, Request, Response};
 use riker::actors::ActorSystem;
@@ -16,6 +16,8 @@ use slog::{error, Logger};
 use crypto::hash::{BlockHash, ChainId};
 use shell::mempool::CurrentMempoolStateStorageRef;
 use shell::shell_channel::ShellChannelRef;
+use shell::state::synchronization_state::SynchronizationBootstrapStateRef;
+use shell::stats::block_apply_timings::BlockApplyTimingsRef;
 use storage::context::TezedgeContext;
 use storage::persistent::PersistentStorage;
 use tezos_api::environment::TezosEnvironmentConfiguration;
@@ -23,8 +25,13 @@ use tezos_messages::p2p::encoding::version::NetworkVersion;
 use tezos_wrapper::TezosApiConnectionPool;
 
 use crate::rpc_actor::{RpcCollectedStateRef, RpcServerRef};
+use crate::server::access_control::{RpcAccessControl, RpcListener};
+use crate::services::protocol::response_cache::ProtocolRpcResponseCacheRef;
+use crate::services::stream_services::BlockMonitorChannelsRef;
 use crate::{error_with_message, not_found, options};
 
+pub(crate) mod access_control;
+mod describe;
 mod dev_handler;
 mod protocol_handler;
 mod router;
@@ -42,6 +49,14 @@ pub struct RpcServiceEnvironment {
     #[get = "pub(crate)"]
     current_mempool_state_storage: CurrentMempoolStateStorageRef,
     #[get = "pub(crate)"]
+    block_apply_timings: BlockApplyTimingsRef,
+    #[get = "pub(crate)"]
+    protocol_rpc_cache: ProtocolRpcResponseCacheRef,
+    #[get = "pub(crate)"]
+    block_monitor_channels: BlockMonitorChannelsRef,
+    #[get = "pub(crate)"]
+    synchronization_state: SynchronizationBootstrapStateRef,
+    #[get = "pub(crate)"]
     tezedge_context: TezedgeContext,
     #[get = "pub(crate)"]
     state: RpcCollectedStateRef,
@@ -76,6 +91,10 @@ impl RpcServiceEnvironment {
         network_version: Arc<NetworkVersion>,
         persistent_storage: &PersistentStorage,
         current_mempool_state_storage: CurrentMempoolStateStorageRef,
+        block_apply_timings: BlockApplyTimingsRef,
+        protocol_rpc_cache: ProtocolRpcResponseCacheRef,
+        block_monitor_channels: BlockMonitorChannelsRef,
+        synchronization_state: SynchronizationBootstrapStateRef,
         tezedge_context: &TezedgeContext,
         tezos_readonly_api: Arc<TezosApiConnectionPool>,
         tezos_readonly_prevalidation_api: Arc<TezosApiConnectionPool>,
@@ -93,6 +112,10 @@ impl RpcServiceEnvironment {
             network_version,
             persistent_storage: persistent_storage.clone(),
             current_mempool_state_storage,
+            block_apply_timings,
+            protocol_rpc_cache,
+            block_monitor_channels,
+            synchronization_state,
             tezedge_context: tezedge_context.clone(),
             main_chain_id,
             main_chain_genesis_hash,
@@ -136,28 +159,40 @@ impl MethodHandler {
     }
 }
 
-/// Spawn new HTTP server on given address interacting with specific actor system
+/// Spawn new HTTP server on listener address interacting with specific actor system,
+/// access policy of listener is checked for every request before routing
 pub fn spawn_server(
-    bind_address: &SocketAddr,
+    listener: &RpcListener,
     env: RpcServiceEnvironment,
 ) -> impl Future<Output = Result<(), hyper::Error>> {
     let routes = Arc::new(router::create_routes(
         env.state().read().unwrap().is_sandbox(),
     ));
+    let access_control = Arc::new(RpcAccessControl::new(listener.policy.clone()));
 
-    hyper::Server::bind(bind_address)
-        .serve(make_service_fn(move |_| {
+    hyper::Server::bind(&listener.address)
+        .serve(make_service_fn(move |conn: &AddrStream| {
             let env = env.clone();
             let routes = routes.clone();
+            let access_control = access_control.clone();
+            let remote_ip = conn.remote_addr().ip();
 
             async move {
                 let env = env.clone();
                 let routes = routes.clone();
+                let access_control = access_control.clone();
                 Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
                     let env = env.clone();
                     let routes = routes.clone();
+                    let access_control = access_control.clone();
                     async move {
-                        if let Some((method_and_handler, params)) = routes.find(req.uri().path().to_string().trim_end_matches('/')) {
+                        let path = req.uri().path().trim_end_matches('/').to_string();
+                        let req = match access_control.check(remote_ip, &path, req) {
+                            Ok(req) => req,
+                            Err(denied) => return denied.into_response(),
+                        };
+
+                        if let Some((method_and_handler, params)) = routes.find(&path) {
                             let MethodHandler {
                                 allowed_methods,
                                 handler,
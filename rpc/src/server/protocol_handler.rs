@@ -7,8 +7,13 @@ use slog::warn;
 
 use crate::helpers::{create_rpc_request, parse_block_hash, parse_chain_id};
 use crate::server::{HasSingleValue, Params, Query, RpcServiceEnvironment};
-use crate::services::protocol::{ContextParamsError, RightsError, VotesError};
-use crate::{required_param, result_to_json_response, services, ServiceResult};
+use crate::services::protocol::{
+    ContextParamsError, ContractField, ContractsError, RightsError, VotesError,
+};
+use crate::{
+    required_param, result_option_to_json_response, result_to_json_response, services,
+    ServiceResult,
+};
 
 pub async fn context_constants(
     req: Request<Body>,
@@ -186,6 +191,100 @@ pub async fn votes_listings(
     }
 }
 
+pub async fn contract_field(
+    req: Request<Body>,
+    params: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id_param = required_param!(params, "chain_id")?;
+    let chain_id = parse_chain_id(chain_id_param, &env)?;
+    let block_hash = parse_block_hash(&chain_id, required_param!(params, "block_id")?, &env)?;
+    let contract_id = required_param!(params, "contract_id")?;
+
+    // field is the last segment of the route
+    let field = match req
+        .uri()
+        .path()
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .and_then(ContractField::from_path_segment)
+    {
+        Some(field) => field,
+        None => return not_found(),
+    };
+
+    // try to call our implementation
+    let result = services::protocol::get_contract_field(&block_hash, contract_id, field, &env);
+
+    // fallback, if protocol is not supported, we trigger rpc protocol router
+    if let Err(ContractsError::UnsupportedProtocolError { .. }) = result {
+        result_to_json_response(
+            services::protocol::call_protocol_rpc(
+                chain_id_param,
+                chain_id,
+                block_hash,
+                create_rpc_request(req).await?,
+                &env,
+            ),
+            env.log(),
+        )
+    } else {
+        result_option_to_json_response(result.map_err(|e| e.into()), env.log())
+    }
+}
+
+pub async fn delegate_info(
+    req: Request<Body>,
+    params: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id_param = required_param!(params, "chain_id")?;
+    let chain_id = parse_chain_id(chain_id_param, &env)?;
+    let block_hash = parse_block_hash(&chain_id, required_param!(params, "block_id")?, &env)?;
+    let pkh = required_param!(params, "pkh")?;
+
+    // single field of delegate info, e.g. `context/delegates/<pkh>/staking_balance`
+    let field = req
+        .uri()
+        .path()
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|field| *field != pkh)
+        .map(str::to_string);
+
+    // try to call our implementation
+    let result = services::protocol::get_delegate_info(&block_hash, pkh, &env).and_then(|info| {
+        let info = match info {
+            Some(info) => serde_json::to_value(info)?,
+            None => return Ok(None),
+        };
+        match field {
+            Some(field) => Ok(info.get(&field).cloned()),
+            None => Ok(Some(info)),
+        }
+    });
+
+    // fallback, if protocol is not supported, we trigger rpc protocol router
+    if let Err(ContractsError::UnsupportedProtocolError { .. }) = result {
+        result_to_json_response(
+            services::protocol::call_protocol_rpc(
+                chain_id_param,
+                chain_id,
+                block_hash,
+                create_rpc_request(req).await?,
+                &env,
+            ),
+            env.log(),
+        )
+    } else {
+        result_option_to_json_response(result.map_err(|e| e.into()), env.log())
+    }
+}
+
 pub async fn call_protocol_rpc(
     req: Request<Body>,
     params: Params,
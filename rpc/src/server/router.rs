@@ -8,6 +8,10 @@ use std::sync::Arc;
 use hyper::{Body, Method, Request};
 use path_tree::PathTree;
 
+use crypto::hash::HashType;
+use tezos_encoding::encoding::{Encoding, Field};
+
+use crate::server::describe::{QueryParam, RpcDirectory, ServiceDescription};
 use crate::server::{dev_handler, protocol_handler, shell_handler};
 use crate::server::{HResult, MethodHandler, Params, Query, RpcServiceEnvironment};
 
@@ -24,137 +28,304 @@ macro_rules! hash_set {
 }
 
 pub(crate) fn create_routes(is_sandbox: bool) -> PathTree<MethodHandler> {
-    let mut routes = PathTree::<MethodHandler>::new();
+    let mut routes = RpcRoutes::new();
 
     // Shell rpc - implemented
     routes.handle(
         hash_set![Method::GET],
         "/version",
+        ServiceDescription::new("Get information on the node version"),
         shell_handler::node_version,
     );
     routes.handle(
         hash_set![Method::GET],
         "/monitor/bootstrapped",
+        ServiceDescription::new("Wait for the node to have synchronized its chain with a few peers (configured by the node's administrator), streaming head updates that happen during the bootstrapping process, and closing the stream at the end.")
+            .output(Encoding::Obj(vec![
+                Field::new("block", Encoding::Hash(HashType::BlockHash)),
+                Field::new("timestamp", Encoding::Timestamp),
+            ])),
         shell_handler::bootstrapped,
     );
     routes.handle(
         hash_set![Method::GET],
         "/monitor/commit_hash",
+        ServiceDescription::new("DEPRECATED: use `version` instead.").output(Encoding::String),
         shell_handler::commit_hash,
     );
     routes.handle(
         hash_set![Method::GET],
         "/monitor/active_chains",
+        ServiceDescription::new("Monitor every chain creation and destruction. Currently active chains will be given as first elements"),
         shell_handler::active_chains,
     );
     routes.handle(
         hash_set![Method::GET],
         "/monitor/protocols",
+        ServiceDescription::new("Monitor all economic protocols that are retrieved and successfully loaded and compiled by the node.")
+            .output(Encoding::Hash(HashType::ProtocolHash)),
         shell_handler::protocols,
     );
     routes.handle(
         hash_set![Method::GET],
         "/monitor/valid_blocks",
+        ServiceDescription::new("Monitor all blocks that are successfully validated by the node, disregarding whether they were selected as the new head or not.")
+            .query(QueryParam::multi("protocol", "Protocol_hash", "Filter on the protocol of the block"))
+            .query(QueryParam::multi("next_protocol", "Protocol_hash", "Filter on the next protocol of the block")),
         shell_handler::valid_blocks,
     );
     routes.handle(
         hash_set![Method::GET],
         "/monitor/heads/:chain_id",
+        ServiceDescription::new("Monitor all blocks that are successfully validated by the node and selected as the new head of the given chain.")
+            .query(QueryParam::multi("protocol", "Protocol_hash", "Filter on the protocol of the head"))
+            .query(QueryParam::multi("next_protocol", "Protocol_hash", "Filter on the next protocol of the head")),
         shell_handler::head_chain,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/chain_id",
+        ServiceDescription::new("The chain unique identifier.")
+            .output(Encoding::Hash(HashType::ChainId)),
         shell_handler::get_chain_id,
     );
+    routes.handle(
+        hash_set![Method::GET],
+        "/chains/:chain_id/is_bootstrapped",
+        ServiceDescription::new("The bootstrap status of a chain")
+            .output(Encoding::Obj(vec![
+                Field::new("bootstrapped", Encoding::Bool),
+                Field::new("sync_state", Encoding::String),
+            ])),
+        shell_handler::is_bootstrapped,
+    );
+    routes.handle(
+        hash_set![Method::GET],
+        "/chains/:chain_id/checkpoint",
+        ServiceDescription::new("DEPRECATED: use `../levels/{checkpoint, savepoint, caboose, history_mode}` instead. The current checkpoint for this chain.")
+            .output(Encoding::Obj(vec![
+                Field::new("block", Encoding::Obj(vec![
+                    Field::new("level", Encoding::Int32),
+                    Field::new("proto", Encoding::Uint8),
+                    Field::new("predecessor", Encoding::Hash(HashType::BlockHash)),
+                    Field::new("timestamp", Encoding::Timestamp),
+                    Field::new("validation_pass", Encoding::Uint8),
+                    Field::new("operations_hash", Encoding::Hash(HashType::OperationListListHash)),
+                    Field::new("fitness", Encoding::list(Encoding::Bytes)),
+                    Field::new("context", Encoding::Hash(HashType::ContextHash)),
+                    Field::new("protocol_data", Encoding::Bytes),
+                ])),
+                Field::new("savepoint", Encoding::Int32),
+                Field::new("caboose", Encoding::Int32),
+                Field::new("history_mode", Encoding::String),
+            ])),
+        shell_handler::get_checkpoint,
+    );
+    routes.handle(
+        hash_set![Method::GET],
+        "/chains/:chain_id/levels/checkpoint",
+        ServiceDescription::new("The current checkpoint for this chain.")
+            .output(Encoding::Obj(vec![
+            Field::new("block_hash", Encoding::Hash(HashType::BlockHash)),
+            Field::new("level", Encoding::Int32),
+        ])),
+        shell_handler::get_levels_checkpoint,
+    );
+    routes.handle(
+        hash_set![Method::GET],
+        "/chains/:chain_id/levels/savepoint",
+        ServiceDescription::new("The savepoint for this chain.")
+            .output(Encoding::Obj(vec![
+            Field::new("block_hash", Encoding::Hash(HashType::BlockHash)),
+            Field::new("level", Encoding::Int32),
+        ])),
+        shell_handler::get_levels_savepoint,
+    );
+    routes.handle(
+        hash_set![Method::GET],
+        "/chains/:chain_id/levels/caboose",
+        ServiceDescription::new("The caboose for this chain.")
+            .output(Encoding::Obj(vec![
+            Field::new("block_hash", Encoding::Hash(HashType::BlockHash)),
+            Field::new("level", Encoding::Int32),
+        ])),
+        shell_handler::get_levels_caboose,
+    );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks",
+        ServiceDescription::new("Lists known heads of the blockchain sorted with decreasing fitness. Optional arguments allows to returns the list of predecessors for known heads or the list of predecessors for a given list of blocks.")
+            .query(QueryParam::optional("length", "uint", "The requested number of predecessors to returns (per requested head)."))
+            .query(QueryParam::multi("head", "block_hash", "An empty argument requests blocks from the current heads. A non empty list allow to request specific fragment of the chain."))
+            .query(QueryParam::optional("min_date", "date", "When `min_date` is provided, heads with a timestamp before `min_date` are filtered out"))
+            .output(Encoding::list(Encoding::list(Encoding::Hash(HashType::BlockHash)))),
         shell_handler::blocks,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id",
+        ServiceDescription::new("All the information about a block."),
         shell_handler::chains_block_id,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/live_blocks",
+        ServiceDescription::new("List the ancestors of the given block which, if referred to as the branch in an operation header, are recent enough for that operation to be included in the current block.")
+            .output(Encoding::list(Encoding::Hash(HashType::BlockHash))),
         shell_handler::live_blocks,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/header",
+        ServiceDescription::new("The whole block header."),
         shell_handler::chains_block_id_header,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/header/shell",
+        ServiceDescription::new("The shell-specific fragment of the block header."),
         shell_handler::chains_block_id_header_shell,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/mempool/pending_operations",
+        ServiceDescription::new("List the prevalidated operations."),
         shell_handler::mempool_pending_operations,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/mempool/monitor_operations",
+        ServiceDescription::new("Monitor the mempool operations.")
+            .query(QueryParam::flag(
+                "applied",
+                "Include applied operations (set by default)",
+            ))
+            .query(QueryParam::flag("refused", "Include refused operations"))
+            .query(QueryParam::flag(
+                "branch_refused",
+                "Include branch refused operations",
+            ))
+            .query(QueryParam::flag(
+                "branch_delayed",
+                "Include branch delayed operations (set by default)",
+            )),
         shell_handler::mempool_monitor_operations,
     );
     routes.handle(
         hash_set![Method::POST],
         "/chains/:chain_id/mempool/request_operations",
+        ServiceDescription::new("Request the operations of your peers.").output(Encoding::Unit),
         shell_handler::mempool_request_operations,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/protocols",
+        ServiceDescription::new("Current and next protocol.").output(Encoding::Obj(vec![
+            Field::new("protocol", Encoding::Hash(HashType::ProtocolHash)),
+            Field::new("next_protocol", Encoding::Hash(HashType::ProtocolHash)),
+        ])),
         shell_handler::get_block_protocols,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/hash",
+        ServiceDescription::new("The block's hash, its unique identifier.")
+            .output(Encoding::Hash(HashType::BlockHash)),
         shell_handler::get_block_hash,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/operation_hashes",
+        ServiceDescription::new("The hashes of all the operations included in the block.").output(
+            Encoding::list(Encoding::list(Encoding::Hash(HashType::OperationHash))),
+        ),
         shell_handler::get_block_operation_hashes,
     );
+    routes.handle(
+        hash_set![Method::GET],
+        "/chains/:chain_id/blocks/:block_id/operations",
+        ServiceDescription::new("All the operations included in the block.")
+            .query(QueryParam::optional("metadata", "metadata_rpc_arg", "Specifies whether or not if the operations metadata should be returned. To get the metadata, even if it is needed to recompute them, use \"always\". To avoid getting the metadata, use \"never\". By default, the metadata will be returned depending on the node's metadata size limit policy.")),
+        shell_handler::get_block_operations,
+    );
+    routes.handle(
+        hash_set![Method::GET],
+        "/chains/:chain_id/blocks/:block_id/operations/:list_offset",
+        ServiceDescription::new("All the operations included in `n-th` validation pass of the block.")
+            .query(QueryParam::optional("metadata", "metadata_rpc_arg", "Specifies whether or not if the operations metadata should be returned. To get the metadata, even if it is needed to recompute them, use \"always\". To avoid getting the metadata, use \"never\". By default, the metadata will be returned depending on the node's metadata size limit policy.")),
+        shell_handler::get_block_operations_validation_pass,
+    );
+    routes.handle(
+        hash_set![Method::GET],
+        "/chains/:chain_id/blocks/:block_id/operations/:list_offset/:operation_offset",
+        ServiceDescription::new("The `m-th` operation in the `n-th` validation pass of the block.")
+            .query(QueryParam::optional("metadata", "metadata_rpc_arg", "Specifies whether or not if the operation metadata should be returned. To get the metadata, even if it is needed to recompute them, use \"always\". To avoid getting the metadata, use \"never\". By default, the metadata will be returned depending on the node's metadata size limit policy.")),
+        shell_handler::get_block_operation,
+    );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/context/raw/bytes",
+        ServiceDescription::new("Returns the raw context.")
+            .query(QueryParam::optional(
+                "depth",
+                "int",
+                "Depth of the returned subtree",
+            ))
+            .query(QueryParam::optional(
+                "micheline",
+                "json|text|hex",
+                "Format of contract code, storage and big map values - raw hex (default, as Octez), Micheline JSON or Michelson text",
+            )),
         shell_handler::context_raw_bytes,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/context/raw/bytes/*any",
+        ServiceDescription::new("Returns the raw context.")
+            .query(QueryParam::optional(
+                "depth",
+                "int",
+                "Depth of the returned subtree",
+            ))
+            .query(QueryParam::optional(
+                "micheline",
+                "json|text|hex",
+                "Format of contract code, storage and big map values - raw hex (default, as Octez), Micheline JSON or Michelson text",
+            )),
         shell_handler::context_raw_bytes,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/metadata",
+        ServiceDescription::new("All the metadata associated to the block."),
         shell_handler::chains_block_id_metadata,
     );
     routes.handle(
         hash_set![Method::GET],
         "/workers/prevalidators",
+        ServiceDescription::new("Lists the Prevalidator workers and their status."),
         shell_handler::worker_prevalidators,
     );
     routes.handle(
         hash_set![Method::GET],
         "/config/network/user_activated_upgrades",
+        ServiceDescription::new("List of protocols to switch to at given levels."),
         shell_handler::config_user_activated_upgrades,
     );
     routes.handle(
         hash_set![Method::GET],
         "/config/network/user_activated_protocol_overrides",
+        ServiceDescription::new("List of protocols which replace other protocols."),
         shell_handler::config_user_activated_protocol_overrides,
     );
     routes.handle(
         hash_set![Method::POST],
         "/injection/operation",
+        ServiceDescription::new("Inject an operation in node and broadcast it. The `signedOperationContents` should be constructed using a contextual RPCs from the latest block and signed by the client.")
+            .query(QueryParam::flag("async", "Do not wait for the operation to be validated"))
+            .query(QueryParam::optional("chain", "chain_id", "A chain identifier"))
+            .input(Encoding::Bytes)
+            .output(Encoding::Hash(HashType::OperationHash)),
         shell_handler::inject_operation,
     );
     // TODO: TE-174: just for sandbox
@@ -162,6 +333,17 @@ pub(crate) fn create_routes(is_sandbox: bool) -> PathTree<MethodHandler> {
         routes.handle(
             hash_set![Method::POST],
             "/injection/block",
+            ServiceDescription::new("Inject a block in the node and broadcast it.")
+                .query(QueryParam::flag(
+                    "async",
+                    "Do not wait for the block to be validated",
+                ))
+                .query(QueryParam::optional(
+                    "chain",
+                    "chain_id",
+                    "A chain identifier",
+                ))
+                .output(Encoding::Hash(HashType::BlockHash)),
             shell_handler::inject_block,
         );
     }
@@ -170,11 +352,15 @@ pub(crate) fn create_routes(is_sandbox: bool) -> PathTree<MethodHandler> {
     routes.handle(
         hash_set![Method::POST],
         "/chains/:chain_id/blocks/:block_id/helpers/preapply/operations",
+        ServiceDescription::new("Simulate the validation of an operation."),
         shell_handler::preapply_operations,
     );
     routes.handle(
         hash_set![Method::POST],
         "/chains/:chain_id/blocks/:block_id/helpers/preapply/block",
+        ServiceDescription::new("Simulate the validation of a block that would contain the given operations and return the resulting fitness and context hash.")
+            .query(QueryParam::flag("sort", "Sort operations by validation passes"))
+            .query(QueryParam::optional("timestamp", "date", "Timestamp of the simulated block")),
         shell_handler::preapply_block,
     );
 
@@ -182,28 +368,105 @@ pub(crate) fn create_routes(is_sandbox: bool) -> PathTree<MethodHandler> {
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/context/constants",
+        ServiceDescription::new("All constants"),
         protocol_handler::context_constants,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/helpers/baking_rights",
+        ServiceDescription::new("Retrieves the list of delegates allowed to bake a block.")
+            .query(QueryParam::multi("level", "block_level", "A level integer"))
+            .query(QueryParam::multi("cycle", "block_cycle", "A cycle integer"))
+            .query(QueryParam::multi(
+                "delegate",
+                "pkh",
+                "A Secp256k1 of a Ed25519 public key hash (Base58Check-encoded)",
+            ))
+            .query(QueryParam::optional(
+                "max_priority",
+                "int",
+                "Maximum priority",
+            ))
+            .query(QueryParam::flag("all", "Include all priorities")),
         protocol_handler::baking_rights,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/helpers/endorsing_rights",
+        ServiceDescription::new("Retrieves the delegates allowed to endorse a block.")
+            .query(QueryParam::multi("level", "block_level", "A level integer"))
+            .query(QueryParam::multi("cycle", "block_cycle", "A cycle integer"))
+            .query(QueryParam::multi(
+                "delegate",
+                "pkh",
+                "A Secp256k1 of a Ed25519 public key hash (Base58Check-encoded)",
+            )),
         protocol_handler::endorsing_rights,
     );
     routes.handle(
         hash_set![Method::GET],
         "/chains/:chain_id/blocks/:block_id/votes/listings",
+        ServiceDescription::new("List of delegates with their voting weight, in number of rolls."),
         protocol_handler::votes_listings,
     );
 
+    for (field, description, output) in vec![
+        (
+            "balance",
+            "Access the balance of a contract.",
+            Encoding::Mutez,
+        ),
+        (
+            "counter",
+            "Access the counter of a contract, if any.",
+            Encoding::Z,
+        ),
+        (
+            "manager_key",
+            "Access the manager of a contract.",
+            Encoding::option(Encoding::String),
+        ),
+        (
+            "delegate",
+            "Access the delegate of a contract, if any.",
+            Encoding::String,
+        ),
+    ] {
+        routes.handle(
+            hash_set![Method::GET],
+            &format!(
+                "/chains/:chain_id/blocks/:block_id/context/contracts/:contract_id/{}",
+                field
+            ),
+            ServiceDescription::new(description).output(output),
+            protocol_handler::contract_field,
+        );
+    }
+    routes.handle(
+        hash_set![Method::GET],
+        "/chains/:chain_id/blocks/:block_id/context/delegates/:pkh",
+        ServiceDescription::new("Everything about a delegate.")
+            .output(Encoding::Obj(delegate_info_fields())),
+        protocol_handler::delegate_info,
+    );
+    for field in delegate_info_fields() {
+        routes.handle(
+            hash_set![Method::GET],
+            &format!(
+                "/chains/:chain_id/blocks/:block_id/context/delegates/:pkh/{}",
+                field.get_name()
+            ),
+            ServiceDescription::new(&format!("Access '{}' of a delegate.", field.get_name()))
+                .output(field.get_encoding().clone()),
+            protocol_handler::delegate_info,
+        );
+    }
+
     // Other Protocol rpcs - routed through ffi calls
     routes.handle(
         hash_set![Method::GET, Method::POST, Method::OPTIONS, Method::PUT],
         "/chains/:chain_id/blocks/:block_id/*any",
+        ServiceDescription::new("Protocol RPC, which is routed to the protocol of the block."),
         protocol_handler::call_protocol_rpc,
     );
 
@@ -211,41 +474,137 @@ pub(crate) fn create_routes(is_sandbox: bool) -> PathTree<MethodHandler> {
     routes.handle(
         hash_set![Method::GET],
         "/dev/chains/main/blocks",
+        ServiceDescription::new("Lists blocks from the main chain.")
+            .query(QueryParam::single(
+                "from_block_id",
+                "block_id",
+                "Block to start listing from",
+            ))
+            .query(QueryParam::optional(
+                "limit",
+                "uint",
+                "Maximum number of returned blocks",
+            ))
+            .query(QueryParam::optional(
+                "every_nth",
+                "uint",
+                "Return only every nth block",
+            )),
         dev_handler::dev_blocks,
     );
+    routes.handle(
+        hash_set![Method::GET],
+        "/dev/chains/main/blocks/:block_hash/apply_stats",
+        ServiceDescription::new(
+            "Timings of the block application broken down into phases (only for the last applied blocks).",
+        ),
+        dev_handler::dev_block_apply_stats,
+    );
+    routes.handle(
+        hash_set![Method::GET],
+        "/dev/chains/main/blocks/apply_stats/slowest",
+        ServiceDescription::new("Lists the slowest applied blocks from the last applied blocks.")
+            .query(QueryParam::optional(
+                "limit",
+                "uint",
+                "Maximum number of returned blocks",
+            )),
+        dev_handler::dev_slowest_block_apply_stats,
+    );
     routes.handle(
         hash_set![Method::GET],
         "/dev/chains/main/actions/blocks/:block_hash",
+        ServiceDescription::new("Lists context actions recorded for the block.")
+            .query(QueryParam::optional(
+                "cursor_id",
+                "uint",
+                "Id of the action to continue listing from",
+            ))
+            .query(QueryParam::optional(
+                "limit",
+                "uint",
+                "Maximum number of returned actions",
+            ))
+            .query(QueryParam::multi(
+                "action_types",
+                "action_type",
+                "Filter on action types",
+            )),
         dev_handler::dev_action_cursor,
     );
     routes.handle(
         hash_set![Method::GET],
         "/dev/chains/main/actions/blocks/:block_hash/details",
+        ServiceDescription::new("Statistics of context actions recorded for the block."),
         dev_handler::block_action_details,
     );
     routes.handle(
         hash_set![Method::GET],
         "/dev/chains/main/actions/contracts/:contract_address",
+        ServiceDescription::new("Lists context actions recorded for the contract.")
+            .query(QueryParam::optional(
+                "cursor_id",
+                "uint",
+                "Id of the action to continue listing from",
+            ))
+            .query(QueryParam::optional(
+                "limit",
+                "uint",
+                "Maximum number of returned actions",
+            ))
+            .query(QueryParam::multi(
+                "action_types",
+                "action_type",
+                "Filter on action types",
+            )),
         dev_handler::dev_action_cursor,
     );
     routes.handle(
         hash_set![Method::GET],
         "/dev/version",
+        ServiceDescription::new("Version of the TezEdge node."),
         dev_handler::dev_version,
     );
     routes.handle(
         hash_set![Method::GET],
         "/stats/memory",
+        ServiceDescription::new("Memory statistics of the node."),
         dev_handler::dev_stats_memory,
     );
     routes.handle(
         hash_set![Method::GET],
         "/stats/memory/protocol_runners",
+        ServiceDescription::new("Memory statistics of the protocol runners."),
         dev_handler::dev_stats_memory_protocol_runners,
     );
+    routes.handle(
+        hash_set![Method::GET],
+        "/stats/memory/protocol_runners/pools",
+        ServiceDescription::new(
+            "Memory limits and recycled protocol runners (with the last recycle reason) per pool.",
+        ),
+        dev_handler::dev_stats_memory_protocol_runner_pools,
+    );
+    routes.handle(
+        hash_set![Method::GET],
+        "/stats/protocol_runners",
+        ServiceDescription::new(
+            "Crashes, timeouts and restarts of the protocol runners (with the last crash report) per pool.",
+        ),
+        dev_handler::dev_stats_protocol_runners,
+    );
+    routes.handle(
+        hash_set![Method::GET],
+        "/stats/rpc/protocol_cache",
+        ServiceDescription::new(
+            "Hits, misses, evictions and size of the cache for block-scoped protocol RPC responses.",
+        ),
+        dev_handler::dev_stats_protocol_rpc_cache,
+    );
     routes.handle(
         hash_set![Method::GET],
         "/stats/context",
+        ServiceDescription::new("Statistics of the context storage."),
         dev_handler::context_stats,
     );
     //routes.handle(hash_set![Method::GET], "/stats/storage", dev_handler::dev_stats_storage);
@@ -254,44 +613,110 @@ pub(crate) fn create_routes(is_sandbox: bool) -> PathTree<MethodHandler> {
     routes.handle(
         hash_set![Method::GET],
         "/network/version",
+        ServiceDescription::new("DEPRECATED: use `version` instead."),
         shell_handler::node_version,
     );
 
-    routes
+    routes.into_path_tree()
+}
+
+/// Fields of `context/delegates/:pkh`, which are served also one by one
+fn delegate_info_fields() -> Vec<Field> {
+    vec![
+        Field::new("balance", Encoding::Mutez),
+        Field::new("frozen_balance", Encoding::Mutez),
+        Field::new(
+            "frozen_balance_by_cycle",
+            Encoding::list(Encoding::Obj(vec![
+                Field::new("cycle", Encoding::Int32),
+                Field::new("deposit", Encoding::Mutez),
+                Field::new("fees", Encoding::Mutez),
+                Field::new("rewards", Encoding::Mutez),
+            ])),
+        ),
+        Field::new("staking_balance", Encoding::Mutez),
+        Field::new("delegated_contracts", Encoding::list(Encoding::String)),
+        Field::new("delegated_balance", Encoding::Mutez),
+        Field::new("deactivated", Encoding::Bool),
+        Field::new("grace_period", Encoding::Int32),
+        Field::new("voting_power", Encoding::Int32),
+    ]
+}
+
+/// Routes registered together with descriptions of their services
+struct RpcRoutes {
+    tree: PathTree<MethodHandler>,
+    directory: RpcDirectory,
+}
+
+impl RpcRoutes {
+    fn new() -> Self {
+        Self {
+            tree: PathTree::new(),
+            directory: RpcDirectory::default(),
+        }
+    }
+
+    /// Registers `/describe` rpc, which describes all the registered routes
+    fn into_path_tree(self) -> PathTree<MethodHandler> {
+        let RpcRoutes {
+            mut tree,
+            directory,
+        } = self;
+        let directory = Arc::new(directory);
+
+        for path in &["/describe", "/describe/*any"] {
+            let directory = directory.clone();
+            tree.insert(
+                path,
+                MethodHandler::new(
+                    Arc::new(hash_set![Method::GET]),
+                    Arc::new(move |req, params, query, env| {
+                        Box::new(shell_handler::describe(
+                            directory.clone(),
+                            req,
+                            params,
+                            query,
+                            env,
+                        ))
+                    }),
+                ),
+            );
+        }
+        tree
+    }
 }
 
 trait Routes<Fut> {
-    fn handle(&mut self, method: HashSet<Method>, path: &str, f: Fut);
+    fn handle(
+        &mut self,
+        method: HashSet<Method>,
+        path: &str,
+        description: ServiceDescription,
+        f: Fut,
+    );
 }
 
-impl<T, F> Routes<T> for PathTree<MethodHandler>
+impl<T, F> Routes<T> for RpcRoutes
 where
     T: Fn(Request<Body>, Params, Query, RpcServiceEnvironment) -> F + Send + Sync + 'static,
     F: Future<Output = HResult> + Send + 'static,
 {
-    fn handle(&mut self, allowed_methods: HashSet<Method>, path: &str, f: T) {
+    fn handle(
+        &mut self,
+        allowed_methods: HashSet<Method>,
+        path: &str,
+        description: ServiceDescription,
+        f: T,
+    ) {
         let allowed_methods = Arc::new(allowed_methods);
-        self.insert(
+        self.tree.insert(
             path,
             MethodHandler::new(
                 allowed_methods.clone(),
                 Arc::new(move |req, params, query, env| Box::new(f(req, params, query, env))),
             ),
         );
-        self.insert(
-            &format!("/describe{}", path),
-            MethodHandler::new(
-                Arc::new(hash_set![Method::GET]),
-                Arc::new(move |req, params, query, env| {
-                    Box::new(shell_handler::describe(
-                        allowed_methods.clone(),
-                        req,
-                        params,
-                        query,
-                        env,
-                    ))
-                }),
-            ),
-        );
+        self.directory.register(path, allowed_methods, description);
     }
 }
@@ -1,32 +1,34 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
-use std::collections::HashSet;
 use std::sync::Arc;
 
 use failure::format_err;
 use hyper::body::Buf;
-use hyper::{Body, Method, Request};
+use hyper::{Body, Request};
 use serde::Serialize;
 
 use crypto::hash::{chain_id_to_b58_string, ProtocolHash};
 use tezos_api::ffi::ProtocolRpcError;
-use tezos_messages::ts_to_rfc3339;
 use tezos_wrapper::service::{ProtocolError, ProtocolServiceError};
 
 use crate::helpers::{
-    create_rpc_request, parse_async, parse_block_hash, parse_chain_id, SlimBlockData, MAIN_CHAIN_ID,
+    create_rpc_request, parse_async, parse_block_hash, parse_chain_id, MichelineFormat,
+    MAIN_CHAIN_ID,
 };
+use crate::server::describe::RpcDirectory;
 use crate::server::{HResult, HasSingleValue, Params, Query, RpcServiceEnvironment};
 use crate::services::{base_services, stream_services};
 use crate::{
     empty,
-    encoding::{base_types::*, monitor::BootstrapInfo},
-    helpers, make_json_response, make_json_stream_response, required_param,
+    encoding::{
+        base_types::*,
+        chain::{BlockLevelInfo, BootstrappedStatus},
+    },
+    helpers, make_json_response, make_json_stream_response, not_found, required_param,
     result_option_to_json_response, result_to_empty_json_response, result_to_json_response,
     services, ServiceResult,
 };
-use storage::BlockHeaderWithHash;
 
 #[derive(Serialize)]
 pub struct ErrorMessage {
@@ -39,22 +41,39 @@ pub async fn bootstrapped(
     _: Params,
     _: Query,
     env: RpcServiceEnvironment,
-) -> HResult {
-    let state_read = env.state().read().unwrap();
-
-    let bootstrap_info = match state_read.current_head().as_ref() {
-        Some(current_head) => {
-            let current_head: &BlockHeaderWithHash = &current_head;
-            let timestamp = ts_to_rfc3339(current_head.header.timestamp());
-            Ok(BootstrapInfo::new(
-                &current_head.hash,
-                TimeStamp::Rfc(timestamp),
-            ))
-        }
-        None => Err(format_err!("No current head")),
-    };
+) -> ServiceResult {
+    make_json_stream_response(stream_services::BootstrappedMonitorStream::new(
+        env.block_monitor_channels(),
+        env.main_chain_id(),
+        env.state(),
+        env.synchronization_state().clone(),
+    ))
+}
 
-    result_to_json_response(bootstrap_info, env.log())
+pub async fn is_bootstrapped(
+    _: Request<Body>,
+    params: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id = parse_chain_id(required_param!(params, "chain_id")?, &env)?;
+    // synchronization is resolved just for the main chain
+    if &chain_id != env.main_chain_id() {
+        return not_found();
+    }
+
+    let bootstrapped_status = env
+        .synchronization_state()
+        .read()
+        .map(|synchronization_state| {
+            BootstrappedStatus::new(
+                synchronization_state.is_bootstrapped(),
+                synchronization_state.synchronization_status(),
+            )
+        })
+        .map_err(|e| format_err!("Failed to read synchronization state, reason: {}", e));
+
+    result_to_json_response(bootstrapped_status, env.log())
 }
 
 pub async fn commit_hash(
@@ -83,10 +102,17 @@ pub async fn protocols(_: Request<Body>, _: Params, _: Query, _: RpcServiceEnvir
 pub async fn valid_blocks(
     _: Request<Body>,
     _: Params,
-    _: Query,
-    _: RpcServiceEnvironment,
-) -> HResult {
-    empty()
+    query: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let query = parse_block_monitor_query(&query)?;
+
+    make_json_stream_response(stream_services::BlockMonitorStream::valid_blocks(
+        env.block_monitor_channels(),
+        query,
+        env.persistent_storage(),
+        env.log().clone(),
+    ))
 }
 
 pub async fn head_chain(
@@ -96,26 +122,41 @@ pub async fn head_chain(
     env: RpcServiceEnvironment,
 ) -> ServiceResult {
     let chain_id = parse_chain_id(required_param!(params, "chain_id")?, &env)?;
-    let protocol = if let Some(protocol) = query.get_str("next_protocol") {
-        ProtocolHash::from_base58_check(protocol).ok()
-    } else {
-        None
-    };
+    let query = parse_block_monitor_query(&query)?;
 
-    let RpcServiceEnvironment {
-        state,
-        persistent_storage,
-        ..
-    } = env;
-
-    make_json_stream_response(stream_services::HeadMonitorStream::new(
+    make_json_stream_response(stream_services::BlockMonitorStream::new_heads(
+        env.block_monitor_channels(),
         chain_id,
-        state,
-        protocol,
-        &persistent_storage,
+        env.main_chain_id(),
+        env.state(),
+        query,
+        env.persistent_storage(),
+        env.log().clone(),
     ))
 }
 
+/// Parses (possibly repeated) `protocol` and `next_protocol` query parameters of monitor RPCs
+fn parse_block_monitor_query(
+    query: &Query,
+) -> Result<stream_services::BlockMonitorQuery, failure::Error> {
+    let parse_protocols = |key: &str| -> Result<Vec<ProtocolHash>, failure::Error> {
+        query
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(|value| {
+                ProtocolHash::from_base58_check(value)
+                    .map_err(|e| format_err!("Invalid {} '{}': {}", key, value, e))
+            })
+            .collect()
+    };
+
+    Ok(stream_services::BlockMonitorQuery {
+        protocols: parse_protocols("protocol")?,
+        next_protocols: parse_protocols("next_protocol")?,
+    })
+}
+
 pub async fn mempool_monitor_operations(
     _: Request<Body>,
     params: Params,
@@ -168,23 +209,38 @@ pub async fn blocks(
     env: RpcServiceEnvironment,
 ) -> ServiceResult {
     let chain_id = parse_chain_id(required_param!(params, "chain_id")?, &env)?;
-    let length = query.get_str("length").unwrap_or("0");
-    // TODO: mutliparameter
-    let head = parse_block_hash(&chain_id, query.get_str("head").unwrap(), &env)?;
-    // TODO: implement min_date query arg
-
-    // TODO: This can be implemented in a more optimised and cleaner way
-    // Note: Need to investigate the "more heads per level" variant
-    make_json_response(&vec![base_services::get_blocks::<SlimBlockData>(
-        chain_id,
-        head,
-        None,
-        length.parse::<usize>()?,
-        env.persistent_storage(),
-    )?
-    .iter()
-    .map(|block| block.block_hash.clone())
-    .collect::<Vec<String>>()])
+    let length = query
+        .get_str("length")
+        .map(str::parse::<usize>)
+        .transpose()?;
+    let heads = query
+        .get("head")
+        .into_iter()
+        .flatten()
+        .map(|head| parse_block_hash(&chain_id, head, &env))
+        .collect::<Result<Vec<_>, _>>()?;
+    let min_date = query.get_str("min_date").map(parse_date).transpose()?;
+
+    result_to_json_response(
+        base_services::get_blocks_list(
+            &chain_id,
+            heads,
+            length,
+            min_date,
+            env.persistent_storage(),
+        ),
+        env.log(),
+    )
+}
+
+/// Parses Octez `date` argument - either RFC3339 or number of seconds since epoch
+fn parse_date(date: &str) -> Result<i64, failure::Error> {
+    match date.parse::<i64>() {
+        Ok(timestamp) => Ok(timestamp),
+        Err(_) => chrono::DateTime::parse_from_rfc3339(date)
+            .map(|date| date.timestamp())
+            .map_err(|e| format_err!("Invalid date '{}': {}", date, e)),
+    }
 }
 
 pub async fn chains_block_id(
@@ -259,9 +315,10 @@ pub async fn context_raw_bytes(
     let block_hash = parse_block_hash(&chain_id, required_param!(params, "block_id")?, &env)?;
     let prefix = params.get_str("any");
     let depth = query.get_usize("depth");
+    let micheline = MichelineFormat::parse(query.get_str("micheline"))?;
 
     result_to_json_response(
-        base_services::get_context_raw_bytes(&block_hash, prefix, depth, &env),
+        base_services::get_context_raw_bytes(&block_hash, prefix, depth, micheline, &env),
         env.log(),
     )
 }
@@ -383,6 +440,63 @@ pub async fn get_chain_id(
     result_to_json_response(Ok(chain_id_to_b58_string(&chain_id)), env.log())
 }
 
+pub async fn get_checkpoint(
+    _: Request<Body>,
+    params: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id = parse_chain_id(required_param!(params, "chain_id")?, &env)?;
+
+    result_option_to_json_response(
+        base_services::get_checkpoint_info(&chain_id, env.persistent_storage()),
+        env.log(),
+    )
+}
+
+pub async fn get_levels_checkpoint(
+    _: Request<Body>,
+    params: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id = parse_chain_id(required_param!(params, "chain_id")?, &env)?;
+
+    result_to_json_response(
+        base_services::get_checkpoint(&chain_id, env.persistent_storage())
+            .map(BlockLevelInfo::from),
+        env.log(),
+    )
+}
+
+pub async fn get_levels_savepoint(
+    _: Request<Body>,
+    params: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id = parse_chain_id(required_param!(params, "chain_id")?, &env)?;
+
+    result_to_json_response(
+        base_services::get_savepoint(&chain_id, env.persistent_storage()).map(BlockLevelInfo::from),
+        env.log(),
+    )
+}
+
+pub async fn get_levels_caboose(
+    _: Request<Body>,
+    params: Params,
+    _: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id = parse_chain_id(required_param!(params, "chain_id")?, &env)?;
+
+    result_to_json_response(
+        base_services::get_caboose(&chain_id, env.persistent_storage()).map(BlockLevelInfo::from),
+        env.log(),
+    )
+}
+
 pub async fn get_block_operation_hashes(
     _: Request<Body>,
     params: Params,
@@ -398,6 +512,87 @@ pub async fn get_block_operation_hashes(
     )
 }
 
+pub async fn get_block_operations(
+    _: Request<Body>,
+    params: Params,
+    query: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id = parse_chain_id(required_param!(params, "chain_id")?, &env)?;
+    let block_hash = parse_block_hash(&chain_id, required_param!(params, "block_id")?, &env)?;
+    let with_metadata = parse_metadata_query(&query)?;
+
+    result_option_to_json_response(
+        base_services::get_block_operations(&block_hash, with_metadata, env.persistent_storage()),
+        env.log(),
+    )
+}
+
+pub async fn get_block_operations_validation_pass(
+    _: Request<Body>,
+    params: Params,
+    query: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id = parse_chain_id(required_param!(params, "chain_id")?, &env)?;
+    let block_hash = parse_block_hash(&chain_id, required_param!(params, "block_id")?, &env)?;
+    let list_offset = parse_offset(&params, "list_offset")?;
+    let with_metadata = parse_metadata_query(&query)?;
+
+    result_option_to_json_response(
+        base_services::get_block_operations_validation_pass(
+            &block_hash,
+            list_offset,
+            with_metadata,
+            env.persistent_storage(),
+        ),
+        env.log(),
+    )
+}
+
+pub async fn get_block_operation(
+    _: Request<Body>,
+    params: Params,
+    query: Query,
+    env: RpcServiceEnvironment,
+) -> ServiceResult {
+    let chain_id = parse_chain_id(required_param!(params, "chain_id")?, &env)?;
+    let block_hash = parse_block_hash(&chain_id, required_param!(params, "block_id")?, &env)?;
+    let list_offset = parse_offset(&params, "list_offset")?;
+    let operation_offset = parse_offset(&params, "operation_offset")?;
+    let with_metadata = parse_metadata_query(&query)?;
+
+    result_option_to_json_response(
+        base_services::get_block_operation(
+            &block_hash,
+            list_offset,
+            operation_offset,
+            with_metadata,
+            env.persistent_storage(),
+        ),
+        env.log(),
+    )
+}
+
+/// Parses Octez `metadata` query, metadata are returned by default
+fn parse_metadata_query(query: &Query) -> Result<bool, failure::Error> {
+    match query.get_str("metadata") {
+        None | Some("always") => Ok(true),
+        Some("never") => Ok(false),
+        Some(value) => Err(format_err!(
+            "Invalid metadata '{}', expected 'always' or 'never'",
+            value
+        )),
+    }
+}
+
+fn parse_offset(params: &impl HasSingleValue, key: &str) -> Result<usize, failure::Error> {
+    let value = required_param!(params, key)?;
+    value
+        .parse::<usize>()
+        .map_err(|e| format_err!("Invalid {} '{}': {}", key, value, e))
+}
+
 pub async fn live_blocks(
     _: Request<Body>,
     params: Params,
@@ -518,89 +713,22 @@ pub async fn config_user_activated_protocol_overrides(
     )
 }
 
-// TODO: TE-275 - implement correctly - at least for protocol rpcs. This is a 'fake it till you make it' handler
-/// Handler mockin the describe routes in ocaml to be compatible with tezoses python test framework
+/// Describes registered rpc services, compatible with ocaml `/describe` rpc
 pub async fn describe(
-    allowed_methods: Arc<HashSet<Method>>,
-    req: Request<Body>,
-    _: Params,
-    _: Query,
+    directory: Arc<RpcDirectory>,
+    _: Request<Body>,
+    params: Params,
+    query: Query,
     env: RpcServiceEnvironment,
 ) -> ServiceResult {
-    let path: Vec<String> = req
-        .uri()
-        .path()
-        .split('/')
-        .skip(2)
-        .map(|v| v.to_string())
-        .collect();
-
-    // TODO: dyanmically get the method from a protocol call, for now, get the first element...
-    // NOTE: protocol rpcs are dynamically created and called trough the protocol,
-    // as a next step, we should somehow get the registreds paths method from the protocol
-    let method = if !allowed_methods.is_empty() {
-        // TODO: same reasoning as above
-        if path.contains(&"injection".to_string()) || path.contains(&"forge".to_string()) {
-            &Method::POST
-        } else if path.contains(&"storage".to_string()) {
-            &Method::GET
-        } else {
-            allowed_methods.iter().next().unwrap()
-        }
-    } else {
-        return empty();
-    };
-
-    let service_fields = serde_json::json!({
-        "meth": method.as_str(),
-        "path": path,
-        "description": "Handler mockin the describe routes in ocaml to be compatible with tezoses python test framework.",
-        "query": [],
-        "output": {
-            "json_schema": {},
-            "binary_schema": {
-                "toplevel": {
-                    "fields": [],
-                },
-                "fields": [],
-            },
-        },
-        "error": {
-            "json_schema": {},
-            "binary_schema": {
-                "toplevel": {
-                    "fields": [],
-                },
-                "fields": [],
-            },
-        },
-    });
-
-    let describe_json = match *method {
-        Method::GET => serde_json::json!({
-            "static": {
-                "get_service": service_fields
-            },
-        }),
-        Method::POST => serde_json::json!({
-            "static": {
-                "post_service": service_fields
-            },
-        }),
-        Method::PUT => serde_json::json!({
-            "static": {
-                "put_service": service_fields
-            },
-        }),
-        Method::DELETE => serde_json::json!({
-            "static": {
-                "delete_service": service_fields
-            },
-        }),
-        _ => unimplemented!(),
-    };
+    let path = params.get_str("any").unwrap_or("");
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let recurse = matches!(query.get_str("recurse"), Some("yes") | Some("true"));
 
-    result_to_json_response(Ok(describe_json), env.log())
+    match directory.describe(&path, recurse) {
+        Some(description) => result_to_json_response(Ok(description), env.log()),
+        None => not_found(),
+    }
 }
 
 pub async fn worker_prevalidators(
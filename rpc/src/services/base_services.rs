@@ -1,27 +1,42 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
-use failure::bail;
+use std::collections::{HashMap, HashSet};
 
-use crypto::hash::{BlockHash, ChainId};
+use failure::{bail, format_err};
+use serde_json::Value;
+
+use crypto::hash::{BlockHash, ChainId, OperationHash};
+use shell::validation::fitness_comparator::FitnessWrapper;
 use storage::block_storage::BlockJsonData;
+use storage::chain_meta_storage::ChainMetaStorageReader;
 use storage::context::ContextApi;
 use storage::merkle_storage::StringTreeEntry;
 use storage::persistent::PersistentStorage;
 use storage::{
     context_key, BlockHeaderWithHash, BlockMetaStorage, BlockMetaStorageReader, BlockStorage,
-    BlockStorageReader,
+    BlockStorageReader, ChainMetaStorage, OperationsStorage, OperationsStorageReader,
 };
+use tezos_messages::p2p::binary_message::MessageHash;
+use tezos_messages::p2p::encoding::prelude::OperationsForBlocksMessage;
 use tezos_messages::p2p::encoding::version::NetworkVersion;
+use tezos_messages::protocol::receipts::{
+    decode_operation, decode_operations, decode_validation_pass_operations, Operation,
+};
+use tezos_messages::Head;
 
+use crate::encoding::chain::CheckpointInfo;
 use crate::helpers::{
-    get_context_hash, BlockHeaderInfo, BlockHeaderShellInfo, BlockMetadata, FullBlockInfo,
-    NodeVersion, Protocols,
+    get_context_hash, is_micheline_context_key, BlockHeaderInfo, BlockHeaderShellInfo,
+    BlockMetadata, FullBlockInfo, MichelineFormat, NodeVersion, Protocols,
 };
 use crate::server::RpcServiceEnvironment;
 
 pub type BlockOperations = Vec<String>;
 
+/// How many predecessors of current head are inspected for forks, when listing alternative heads
+const ALTERNATE_HEADS_SEARCH_DEPTH: usize = 1024;
+
 /// Retrieve blocks from database.
 pub(crate) fn get_blocks<T>(
     _chain_id: ChainId,
@@ -46,6 +61,141 @@ where
     Ok(blocks)
 }
 
+/// Lists blocks like `/chains/:chain_id/blocks`.
+///
+/// If no [requested_heads] are set, current head together with all alternative heads (fork tips) is used.
+/// Heads older than [min_date] are filtered out, the rest is sorted by fitness (best first).
+/// With [length], every head is followed by its predecessors (at most [length] blocks in total per head),
+/// stopping at blocks already listed for a better head.
+pub(crate) fn get_blocks_list(
+    chain_id: &ChainId,
+    requested_heads: Vec<BlockHash>,
+    length: Option<usize>,
+    min_date: Option<i64>,
+    persistent_storage: &PersistentStorage,
+) -> Result<Vec<Vec<String>>, failure::Error> {
+    let block_storage = BlockStorage::new(persistent_storage);
+    let block_meta_storage = BlockMetaStorage::new(persistent_storage);
+
+    let heads = if requested_heads.is_empty() {
+        match ChainMetaStorage::new(persistent_storage).get_current_head(chain_id)? {
+            Some(current_head) => {
+                let mut heads = block_meta_storage.find_alternate_heads(
+                    current_head.block_hash(),
+                    ALTERNATE_HEADS_SEARCH_DEPTH,
+                )?;
+                heads.insert(0, current_head.block_hash().clone());
+                heads
+            }
+            None => vec![],
+        }
+    } else {
+        requested_heads
+    };
+
+    // unknown heads are ignored
+    let mut heads = heads
+        .iter()
+        .filter_map(|head| block_storage.get(head).transpose())
+        .collect::<Result<Vec<BlockHeaderWithHash>, _>>()?;
+    if let Some(min_date) = min_date {
+        heads.retain(|head| head.header.timestamp() >= min_date);
+    }
+    heads.sort_by(|a, b| {
+        FitnessWrapper::new(b.header.fitness()).cmp(&FitnessWrapper::new(a.header.fitness()))
+    });
+
+    let length = match length {
+        Some(length) => length,
+        None => {
+            return Ok(heads
+                .into_iter()
+                .map(|head| vec![head.hash.to_base58_check()])
+                .collect());
+        }
+    };
+
+    let mut listed = HashSet::new();
+    let mut result = Vec::with_capacity(heads.len());
+    for head in heads {
+        let mut blocks = vec![head.hash.to_base58_check()];
+        let mut block_hash = head.hash;
+        listed.insert(block_hash.clone());
+        while blocks.len() < length {
+            let predecessor =
+                match block_meta_storage.find_block_at_distance(block_hash.clone(), 1)? {
+                    // Note: genesis is its own predecessor
+                    Some(predecessor) if predecessor != block_hash => predecessor,
+                    _ => break,
+                };
+            if listed.contains(&predecessor) {
+                break;
+            }
+            blocks.push(predecessor.to_base58_check());
+            block_hash = predecessor;
+            listed.insert(block_hash.clone());
+        }
+        result.push(blocks);
+    }
+
+    Ok(result)
+}
+
+/// Returns configured checkpoint of the chain, if not configured, genesis is the checkpoint
+pub(crate) fn get_checkpoint(
+    chain_id: &ChainId,
+    persistent_storage: &PersistentStorage,
+) -> Result<Head, failure::Error> {
+    let chain_meta_storage = ChainMetaStorage::new(persistent_storage);
+    match chain_meta_storage.get_checkpoint(chain_id)? {
+        Some(checkpoint) => Ok(checkpoint),
+        None => get_savepoint(chain_id, persistent_storage),
+    }
+}
+
+/// Returns savepoint of the chain - the lowest block with stored metadata
+pub(crate) fn get_savepoint(
+    chain_id: &ChainId,
+    persistent_storage: &PersistentStorage,
+) -> Result<Head, failure::Error> {
+    // Note: we store metadata for all blocks, so it is always genesis
+    match ChainMetaStorage::new(persistent_storage).get_genesis(chain_id)? {
+        Some(genesis) => Ok(genesis),
+        None => bail!(
+            "Genesis not found for chain_id: {}",
+            chain_id.to_base58_check()
+        ),
+    }
+}
+
+/// Returns caboose of the chain - the lowest block with stored context
+pub(crate) fn get_caboose(
+    chain_id: &ChainId,
+    persistent_storage: &PersistentStorage,
+) -> Result<Head, failure::Error> {
+    match ChainMetaStorage::new(persistent_storage).get_caboose(chain_id)? {
+        Some(caboose) => Ok(caboose),
+        None => bail!(
+            "Caboose not found for chain_id: {}",
+            chain_id.to_base58_check()
+        ),
+    }
+}
+
+/// Returns checkpoint block header with savepoint and caboose levels, if checkpoint block is already known
+pub(crate) fn get_checkpoint_info(
+    chain_id: &ChainId,
+    persistent_storage: &PersistentStorage,
+) -> Result<Option<CheckpointInfo>, failure::Error> {
+    let checkpoint = get_checkpoint(chain_id, persistent_storage)?;
+    let savepoint = get_savepoint(chain_id, persistent_storage)?;
+    let caboose = get_caboose(chain_id, persistent_storage)?;
+
+    Ok(BlockStorage::new(persistent_storage)
+        .get(checkpoint.block_hash())?
+        .map(|block| CheckpointInfo::new(&block.header, *savepoint.level(), *caboose.level())))
+}
+
 /// Get block metadata
 pub(crate) fn get_block_metadata(
     chain_id: &ChainId,
@@ -120,8 +270,9 @@ pub(crate) fn get_context_raw_bytes(
     block_hash: &BlockHash,
     prefix: Option<&str>,
     depth: Option<usize>,
+    micheline: Option<MichelineFormat>,
     env: &RpcServiceEnvironment,
-) -> Result<StringTreeEntry, failure::Error> {
+) -> Result<Value, failure::Error> {
     // we assume that root is at "/data"
     let mut key_prefix = context_key!("data");
 
@@ -132,9 +283,47 @@ pub(crate) fn get_context_raw_bytes(
     };
 
     let ctx_hash = get_context_hash(block_hash, env)?;
-    Ok(env
+    let tree = env
         .tezedge_context()
-        .get_context_tree_by_prefix(&ctx_hash, &key_prefix, depth)?)
+        .get_context_tree_by_prefix(&ctx_hash, &key_prefix, depth)?;
+
+    match micheline {
+        Some(format) => Ok(decode_micheline_values(tree, &mut key_prefix, format)),
+        None => Ok(serde_json::to_value(tree)?),
+    }
+}
+
+/// Replaces hex encoded contract code, storage and big map values with decoded Micheline,
+/// values which cannot be decoded are kept as they are.
+fn decode_micheline_values(
+    entry: StringTreeEntry,
+    key: &mut Vec<String>,
+    format: MichelineFormat,
+) -> Value {
+    match entry {
+        StringTreeEntry::Tree(children) => Value::Object(
+            children
+                .into_iter()
+                .map(|(name, child)| {
+                    key.push(name);
+                    let value = decode_micheline_values(child, key, format);
+                    let name = key.pop().unwrap_or_default();
+                    (name, value)
+                })
+                .collect(),
+        ),
+        StringTreeEntry::Blob(blob) => {
+            let decoded = if is_micheline_context_key(key) {
+                hex::decode(&blob)
+                    .ok()
+                    .and_then(|bytes| format.decode(&bytes))
+            } else {
+                None
+            };
+            decoded.unwrap_or(Value::String(blob))
+        }
+        StringTreeEntry::Null => Value::Null,
+    }
 }
 
 /// Extract the current_protocol and the next_protocol from the block metadata
@@ -144,14 +333,16 @@ pub(crate) fn get_block_protocols(
     persistent_storage: &PersistentStorage,
 ) -> Result<Protocols, failure::Error> {
     if let Some(block_info) = get_block(chain_id, &block_hash, persistent_storage)? {
-        Ok(Protocols::new(
-            block_info.metadata["protocol"]
-                .to_string()
-                .replace("\"", ""),
-            block_info.metadata["next_protocol"]
-                .to_string()
-                .replace("\"", ""),
-        ))
+        match (
+            block_info.metadata.protocol,
+            block_info.metadata.next_protocol,
+        ) {
+            (Some(protocol), Some(next_protocol)) => Ok(Protocols::new(protocol, next_protocol)),
+            _ => bail!(
+                "Cannot retrieve protocols, block_hash {} has no metadata!",
+                block_hash.to_base58_check()
+            ),
+        }
     } else {
         bail!(
             "Cannot retrieve protocols, block_hash {} not found!",
@@ -170,12 +361,7 @@ pub(crate) fn get_block_operation_hashes(
         let operations = block_info
             .operations
             .into_iter()
-            .map(|op_group| {
-                op_group
-                    .into_iter()
-                    .map(|op| op["hash"].to_string().replace("\"", ""))
-                    .collect()
-            })
+            .map(|op_group| op_group.into_iter().map(|op| op.hash).collect())
             .collect();
         Ok(operations)
     } else {
@@ -186,6 +372,153 @@ pub(crate) fn get_block_operation_hashes(
     }
 }
 
+/// Get operations of block with their receipts, grouped by validation passes
+///
+/// Operations are read from [OperationsStorage] and paired (by hash) with receipts stored after block application,
+/// without metadata, receipts are just stripped.
+pub(crate) fn get_block_operations(
+    block_hash: &BlockHash,
+    with_metadata: bool,
+    persistent_storage: &PersistentStorage,
+) -> Result<Option<Vec<Vec<Operation>>>, failure::Error> {
+    let (operations, block_json_data) =
+        match get_operations_with_json_data(block_hash, persistent_storage)? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+
+    let operation_hashes = operations
+        .iter()
+        .map(operation_hashes)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // e.g. genesis has no operations and also no receipts
+    if operation_hashes.iter().all(Vec::is_empty) {
+        return Ok(Some(operation_hashes.into_iter().map(|_| vec![]).collect()));
+    }
+
+    let receipts = decode_operations(block_json_data.operations_proto_metadata_json())?;
+    assign_receipts(operation_hashes, receipts, with_metadata).map(Some)
+}
+
+/// Get operations of one validation pass of block with their receipts,
+/// only receipts of this validation pass are decoded (see [get_block_operations])
+pub(crate) fn get_block_operations_validation_pass(
+    block_hash: &BlockHash,
+    list_offset: usize,
+    with_metadata: bool,
+    persistent_storage: &PersistentStorage,
+) -> Result<Option<Vec<Operation>>, failure::Error> {
+    let (operations, block_json_data) =
+        match get_operations_with_json_data(block_hash, persistent_storage)? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+    let operation_hashes = match operations.get(list_offset) {
+        Some(validation_pass) => operation_hashes(validation_pass)?,
+        None => return Ok(None),
+    };
+    if operation_hashes.is_empty() {
+        return Ok(Some(vec![]));
+    }
+
+    let receipts = decode_validation_pass_operations(
+        block_json_data.operations_proto_metadata_json(),
+        list_offset,
+    )?
+    .unwrap_or_default();
+    assign_receipts(vec![operation_hashes], vec![receipts], with_metadata)
+        .map(|mut operations| operations.pop())
+}
+
+/// Get one operation of block with its receipts, only receipt of this operation is decoded (see [get_block_operations])
+pub(crate) fn get_block_operation(
+    block_hash: &BlockHash,
+    list_offset: usize,
+    operation_offset: usize,
+    with_metadata: bool,
+    persistent_storage: &PersistentStorage,
+) -> Result<Option<Operation>, failure::Error> {
+    let (operations, block_json_data) =
+        match get_operations_with_json_data(block_hash, persistent_storage)? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+    let operation_hash: OperationHash = match operations
+        .get(list_offset)
+        .and_then(|validation_pass| validation_pass.operations().get(operation_offset))
+    {
+        Some(operation) => operation.message_typed_hash()?,
+        None => return Ok(None),
+    };
+
+    let receipts = decode_operation(
+        block_json_data.operations_proto_metadata_json(),
+        list_offset,
+        operation_offset,
+    )?
+    .into_iter()
+    .collect();
+    assign_receipts(vec![vec![operation_hash]], vec![receipts], with_metadata)
+        .map(|operations| operations.into_iter().flatten().next())
+}
+
+/// Operations of block (grouped by validation passes) and its json data (with receipts)
+fn get_operations_with_json_data(
+    block_hash: &BlockHash,
+    persistent_storage: &PersistentStorage,
+) -> Result<Option<(Vec<OperationsForBlocksMessage>, BlockJsonData)>, failure::Error> {
+    let block_json_data =
+        match BlockStorage::new(persistent_storage).get_with_json_data(block_hash)? {
+            Some((_, block_json_data)) => block_json_data,
+            None => return Ok(None),
+        };
+    let operations = OperationsStorage::new(persistent_storage).get_operations(block_hash)?;
+    Ok(Some((operations, block_json_data)))
+}
+
+fn operation_hashes(
+    validation_pass: &OperationsForBlocksMessage,
+) -> Result<Vec<OperationHash>, failure::Error> {
+    validation_pass
+        .operations()
+        .iter()
+        .map(|operation| operation.message_typed_hash().map_err(failure::Error::from))
+        .collect()
+}
+
+fn assign_receipts(
+    operation_hashes: Vec<Vec<OperationHash>>,
+    receipts: Vec<Vec<Operation>>,
+    with_metadata: bool,
+) -> Result<Vec<Vec<Operation>>, failure::Error> {
+    let mut receipts: HashMap<String, Operation> = receipts
+        .into_iter()
+        .flatten()
+        .map(|operation| (operation.hash.clone(), operation))
+        .collect();
+
+    operation_hashes
+        .into_iter()
+        .map(|validation_pass| {
+            validation_pass
+                .into_iter()
+                .map(|operation_hash| {
+                    let operation_hash = operation_hash.to_base58_check();
+                    match receipts.remove(&operation_hash) {
+                        Some(operation) if with_metadata => Ok(operation),
+                        Some(operation) => Ok(operation.without_metadata()),
+                        None => Err(format_err!(
+                            "Missing receipt for operation {}",
+                            operation_hash
+                        )),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
 pub(crate) fn get_node_version(network_version: &NetworkVersion) -> NodeVersion {
     NodeVersion::new(network_version)
 }
@@ -195,11 +528,12 @@ pub(crate) fn get_block(
     block_hash: &BlockHash,
     persistent_storage: &PersistentStorage,
 ) -> Result<Option<FullBlockInfo>, failure::Error> {
-    Ok(BlockStorage::new(persistent_storage)
+    BlockStorage::new(persistent_storage)
         .get_with_json_data(&block_hash)?
         .map(|(header, json_data)| {
             map_header_and_json_to_full_block_info(header, json_data, &chain_id)
-        }))
+        })
+        .transpose()
 }
 
 #[inline]
@@ -207,7 +541,7 @@ fn map_header_and_json_to_full_block_info(
     header: BlockHeaderWithHash,
     json_data: BlockJsonData,
     chain_id: &ChainId,
-) -> FullBlockInfo {
+) -> Result<FullBlockInfo, failure::Error> {
     FullBlockInfo::new(&header, &json_data, chain_id)
 }
 
@@ -219,3 +553,52 @@ fn map_header_and_json_to_block_header_info(
 ) -> BlockHeaderInfo {
     BlockHeaderInfo::new(&header, &json_data, chain_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    #[test]
+    fn test_assign_receipts() -> Result<(), failure::Error> {
+        let operation = |hash: &str| {
+            serde_json::json!({
+                "protocol": "PsCARTHAGazKbHtnKfLzQg3kms52kSRpgnDY982a9oYsSXRLQEb",
+                "chain_id": "NetXdQprcVkpaWU",
+                "hash": hash,
+                "branch": "BLuXs7bnCMDHYnXDLf3HmHsUUvwasg5ZrcBnmUxj9ksb7QwPKVi",
+                "contents": [{"kind": "endorsement", "level": 1, "metadata": {"balance_updates": []}}],
+                "signature": "sigtN5p8EYpGFTGTfuDTC68QGyUGN9jm8U3DvUV3Dbz5TjxyhCcuaTC7bymqnDMDXhAFj5mZqBKgCNCt4esiq7Nqw6wWzbTk"
+            })
+        };
+        let op1 = OperationHash::try_from(vec![1; 32])?;
+        let op2 = OperationHash::try_from(vec![2; 32])?;
+        let receipts: Vec<Vec<Operation>> = serde_json::from_value(serde_json::json!([
+            [
+                operation(&op2.to_base58_check()),
+                operation(&op1.to_base58_check())
+            ],
+            []
+        ]))?;
+
+        // order and validation passes are driven by operations storage
+        let operation_hashes = vec![vec![], vec![op1.clone(), op2.clone()]];
+        let operations = assign_receipts(operation_hashes.clone(), receipts.clone(), true)?;
+        assert_eq!(2, operations.len());
+        assert!(operations[0].is_empty());
+        assert_eq!(op1.to_base58_check(), operations[1][0].hash);
+        assert_eq!(op2.to_base58_check(), operations[1][1].hash);
+        assert!(operations[1][0].contents[0].metadata.is_some());
+
+        // without metadata
+        let operations = assign_receipts(operation_hashes, receipts.clone(), false)?;
+        assert!(operations[1][0].contents[0].metadata.is_none());
+
+        // missing receipt
+        let operation_hashes = vec![vec![OperationHash::try_from(vec![3; 32])?]];
+        assert!(assign_receipts(operation_hashes, receipts, true).is_err());
+
+        Ok(())
+    }
+}
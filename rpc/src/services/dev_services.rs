@@ -1,9 +1,15 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
+use std::collections::HashMap;
+use std::time::{Duration, UNIX_EPOCH};
+
+use failure::format_err;
+use serde::Serialize;
 use slog::Logger;
 
 use crypto::hash::BlockHash;
+use shell::stats::block_apply_timings::BlockApplyTiming;
 use shell::stats::memory::{Memory, MemoryData, MemoryStatsResult};
 use storage::context::{ContextApi, TezedgeContext};
 use storage::context_action_storage::ContextActionBlockDetails;
@@ -15,10 +21,12 @@ use storage::persistent::PersistentStorage;
 use storage::{ContextActionRecordValue, ContextActionStorage};
 use tezos_context::channel::ContextAction;
 use tezos_messages::base::rpc_support::UniversalValue;
+use tezos_wrapper::{ProtocolRunnerLimits, ProtocolRunnerRecycle, ProtocolRunnerStats};
 
-use crate::helpers::{get_action_types, PagedResult};
+use crate::helpers::{get_action_types, is_micheline_context_key, MichelineFormat, PagedResult};
 use crate::server::RpcServiceEnvironment;
 use crate::services::protocol::get_context_protocol_params;
+use crate::services::protocol::response_cache::ProtocolRpcCacheStats;
 
 /// Get actions for a specific block in ascending order.
 #[allow(dead_code)]
@@ -94,7 +102,11 @@ pub(crate) fn get_contract_actions_cursor(
     let values = context_action_storage
         .load_cursor(cursor_id, limit, filters)?
         .into_iter()
-        .map(ContextActionJson::from)
+        .map(|value| {
+            let mut value = ContextActionJson::from(value);
+            decode_micheline_value(&mut value.action);
+            value
+        })
         .collect();
     Ok(values)
 }
@@ -117,9 +129,38 @@ pub(crate) fn get_contract_actions(
         None
     };
     context_records.truncate(std::cmp::min(context_records.len(), limit));
+    context_records
+        .iter_mut()
+        .for_each(|record| decode_micheline_value(&mut record.action));
     Ok(PagedResult::new(context_records, next_id, limit))
 }
 
+/// Fills `value_as_json` of contract code, storage and big map values with decoded Micheline,
+/// unless it was already provided with the action.
+fn decode_micheline_value(action: &mut ContextAction) {
+    match action {
+        ContextAction::Set {
+            key,
+            value,
+            value_as_json,
+            ..
+        }
+        | ContextAction::Get {
+            key,
+            value,
+            value_as_json,
+            ..
+        } => {
+            if value_as_json.is_none() && is_micheline_context_key(key) {
+                *value_as_json = MichelineFormat::Json
+                    .decode(value)
+                    .map(|json| json.to_string());
+            }
+        }
+        _ => (),
+    }
+}
+
 pub(crate) fn get_stats_memory() -> MemoryStatsResult<MemoryData> {
     let memory = Memory::new();
     memory.get_memory_stats()
@@ -130,6 +171,138 @@ pub(crate) fn get_stats_memory_protocol_runners() -> MemoryStatsResult<Vec<Memor
     memory.get_memory_stats_protocol_runners()
 }
 
+#[derive(Serialize, Debug, Clone)]
+pub struct ProtocolRunnerPoolMemoryStats {
+    limits: ProtocolRunnerLimits,
+    recycled: usize,
+    last_recycle: Option<ProtocolRunnerRecycle>,
+}
+
+/// Memory limits and recycled protocol runners per pool
+pub(crate) fn get_stats_memory_protocol_runner_pools(
+    env: &RpcServiceEnvironment,
+) -> HashMap<String, ProtocolRunnerPoolMemoryStats> {
+    vec![
+        env.tezos_readonly_api(),
+        env.tezos_readonly_prevalidation_api(),
+        env.tezos_without_context_api(),
+    ]
+    .into_iter()
+    .map(|api| {
+        let stats = api.metrics.stats();
+        (
+            api.pool_name.clone(),
+            ProtocolRunnerPoolMemoryStats {
+                limits: api.limits.clone(),
+                recycled: stats.recycled,
+                last_recycle: stats.last_recycle,
+            },
+        )
+    })
+    .collect()
+}
+
+/// Timings of block application in microseconds, see [BlockApplyTiming]
+#[derive(Serialize, Debug, Clone)]
+pub struct BlockApplyStats {
+    block_hash: String,
+    level: i32,
+    /// Unix timestamp (in seconds) when block was applied
+    applied_at: u64,
+    waiting_for_operations_us: Option<u64>,
+    waiting_in_queue_us: u64,
+    load_metadata_us: u64,
+    ipc_send_us: u64,
+    protocol_apply_us: u64,
+    context_wait_us: u64,
+    context_actions_count: Option<usize>,
+    context_actions_us: Option<u64>,
+    context_commit_us: Option<u64>,
+    store_result_us: u64,
+    total_us: u64,
+}
+
+impl From<&BlockApplyTiming> for BlockApplyStats {
+    fn from(timing: &BlockApplyTiming) -> Self {
+        let micros = |duration: Duration| duration.as_micros() as u64;
+        Self {
+            block_hash: timing.block_hash.to_base58_check(),
+            level: timing.level,
+            applied_at: timing
+                .applied_at
+                .duration_since(UNIX_EPOCH)
+                .map(|applied_at| applied_at.as_secs())
+                .unwrap_or(0),
+            waiting_for_operations_us: timing.waiting_for_operations.map(micros),
+            waiting_in_queue_us: micros(timing.waiting_in_queue),
+            load_metadata_us: micros(timing.load_metadata),
+            ipc_send_us: micros(timing.ipc_send),
+            protocol_apply_us: micros(timing.protocol_apply),
+            context_wait_us: micros(timing.context_wait),
+            context_actions_count: timing.context_actions.as_ref().map(|c| c.actions_count),
+            context_actions_us: timing
+                .context_actions
+                .as_ref()
+                .map(|c| micros(c.actions_elapsed)),
+            context_commit_us: timing
+                .context_actions
+                .as_ref()
+                .map(|c| micros(c.commit_elapsed)),
+            store_result_us: micros(timing.store_result),
+            total_us: micros(timing.total),
+        }
+    }
+}
+
+/// Timings of block application, if block is still retained
+pub(crate) fn get_block_apply_stats(
+    block_hash: &BlockHash,
+    env: &RpcServiceEnvironment,
+) -> Result<Option<BlockApplyStats>, failure::Error> {
+    let block_apply_timings = env
+        .block_apply_timings()
+        .read()
+        .map_err(|e| format_err!("Failed to lock block apply timings, reason: {}", e))?;
+    Ok(block_apply_timings
+        .get(block_hash)
+        .map(BlockApplyStats::from))
+}
+
+/// Retained blocks with the longest application, the slowest first
+pub(crate) fn get_slowest_block_apply_stats(
+    limit: usize,
+    env: &RpcServiceEnvironment,
+) -> Result<Vec<BlockApplyStats>, failure::Error> {
+    let block_apply_timings = env
+        .block_apply_timings()
+        .read()
+        .map_err(|e| format_err!("Failed to lock block apply timings, reason: {}", e))?;
+    Ok(block_apply_timings
+        .slowest(limit)
+        .iter()
+        .map(BlockApplyStats::from)
+        .collect())
+}
+
+/// Failures (crashes/timeouts/restarts) of protocol runners per pool
+pub(crate) fn get_stats_protocol_runners(
+    env: &RpcServiceEnvironment,
+) -> HashMap<String, ProtocolRunnerStats> {
+    vec![
+        env.tezos_readonly_api(),
+        env.tezos_readonly_prevalidation_api(),
+        env.tezos_without_context_api(),
+    ]
+    .into_iter()
+    .map(|api| (api.pool_name.clone(), api.metrics.stats()))
+    .collect()
+}
+
+/// Hits/misses and size of the protocol rpc response cache
+pub(crate) fn get_stats_protocol_rpc_cache(env: &RpcServiceEnvironment) -> ProtocolRpcCacheStats {
+    env.protocol_rpc_cache().lock().unwrap().stats()
+}
+
 pub(crate) fn get_context_stats(
     context: &TezedgeContext,
 ) -> Result<MerkleStorageStats, failure::Error> {
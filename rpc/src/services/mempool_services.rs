@@ -26,8 +26,9 @@ use storage::{
     BlockStorageReader, MempoolStorage,
 };
 use tezos_api::ffi::{Applied, Errored};
+use tezos_encoding::encoding::HasEncoding;
+use tezos_encoding::json_reader::JsonReader;
 use tezos_messages::p2p::binary_message::{BinaryMessage, MessageHash};
-use tezos_messages::p2p::encoding::operation::DecodedOperation;
 use tezos_messages::p2p::encoding::prelude::{BlockHeader, Operation};
 
 use crate::helpers::get_prevalidators;
@@ -49,7 +50,8 @@ pub struct MempoolOperations {
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct InjectedBlockWithOperations {
     pub data: String,
-    pub operations: Vec<Vec<DecodedOperation>>,
+    /// Operations in Tezos JSON format (`{"branch": ..., "data": ...}`)
+    pub operations: Vec<Vec<Value>>,
 }
 
 pub fn get_pending_operations(
@@ -208,7 +210,7 @@ pub fn inject_operation(
         &operation_hash,
         &operation,
         env.current_mempool_state_storage().clone(),
-        &env.tezos_readonly_prevalidation_api().pool.get()?.api,
+        &mut *env.tezos_readonly_prevalidation_api().pool.get()?,
         &block_storage,
         &block_meta_storage,
     )?;
@@ -318,7 +320,10 @@ pub fn inject_block(
                 .map(|validation_pass| {
                     validation_pass
                         .into_iter()
-                        .map(|op| op.try_into())
+                        .map(|op| -> Result<Operation, failure::Error> {
+                            let op = JsonReader::new().read_binary(&op, Operation::encoding())?;
+                            Ok(Operation::from_bytes(op)?)
+                        })
                         .collect::<Result<_, _>>()
                 })
                 .collect::<Result<_, _>>()?,
@@ -348,7 +353,6 @@ pub fn inject_block(
             .tezos_without_context_api()
             .pool
             .get()?
-            .api
             .compute_path(vps.try_into()?)?;
         Some(response.operations_hashes_path)
     } else {
@@ -0,0 +1,424 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Native implementation of contract and delegate read RPCs (`context/contracts/<contract_id>/...`, `context/delegates/<pkh>`),
+//! served directly from context by the storage layout of protocols 005_2 - 008_2.
+//!
+//! Context layout (all values are binary encoded by protocol):
+//! - `data/contracts/index/<6 bytes of blake2b(contract) as hex dirs>/<contract hex>/{balance,counter,manager,delegate,...}`
+//! - `data/contracts/global_counter`
+//! - `data/rolls/index/<roll & 0xff>/<(roll >> 8) & 0xff>/<roll>/successor`
+//! - `data/votes/listings/<curve>/<5 bytes of pkh as hex dirs>/<rest of pkh hex>`
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use failure::{bail, format_err};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crypto::blake2b;
+use crypto::hash::{
+    ContextHash, ContractKt1Hash, ContractTz1Hash, ContractTz2Hash, ContractTz3Hash,
+    PublicKeyEd25519, PublicKeyP256, PublicKeySecp256k1,
+};
+use storage::context::{ContextApi, TezedgeContext};
+use storage::context_action_storage::contract_id_to_contract_address_for_index;
+use storage::merkle_storage::{ContextKey, ContextValue};
+use storage::{context_key, num_from_slice};
+
+/// Marker of set membership in context
+const CONTEXT_SET_MEMBER: &[u8] = b"inited";
+
+/// Fields of contract served natively, `context/contracts/<contract_id>/<field>`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ContractField {
+    Balance,
+    Counter,
+    ManagerKey,
+    Delegate,
+}
+
+impl ContractField {
+    pub(crate) fn from_path_segment(segment: &str) -> Option<Self> {
+        match segment {
+            "balance" => Some(ContractField::Balance),
+            "counter" => Some(ContractField::Counter),
+            "manager_key" => Some(ContractField::ManagerKey),
+            "delegate" => Some(ContractField::Delegate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct FrozenBalanceByCycle {
+    cycle: i32,
+    deposit: String,
+    fees: String,
+    rewards: String,
+}
+
+/// Response of `context/delegates/<pkh>`, fields are also served separately as `context/delegates/<pkh>/<field>`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct DelegateInfo {
+    balance: String,
+    frozen_balance: String,
+    frozen_balance_by_cycle: Vec<FrozenBalanceByCycle>,
+    staking_balance: String,
+    delegated_contracts: Vec<String>,
+    delegated_balance: String,
+    deactivated: bool,
+    grace_period: i32,
+    voting_power: i32,
+}
+
+/// Reads contracts and delegates from context of one block
+pub(crate) struct ContractsReader<'a> {
+    context: &'a TezedgeContext,
+    context_hash: &'a ContextHash,
+}
+
+impl<'a> ContractsReader<'a> {
+    pub(crate) fn new(context: &'a TezedgeContext, context_hash: &'a ContextHash) -> Self {
+        Self {
+            context,
+            context_hash,
+        }
+    }
+
+    /// Returns value of contract field as rendered by protocol, `None` if protocol responds with 404
+    pub(crate) fn get_contract_field(
+        &self,
+        contract_id: &str,
+        field: ContractField,
+    ) -> Result<Option<serde_json::Value>, failure::Error> {
+        let contract = contract_id_to_contract_address_for_index(contract_id)?;
+        let is_implicit = contract[0] == 0;
+        let contract_path = contract_path(&contract)?;
+
+        // implicit contracts always exist, originated only when allocated
+        if !is_implicit && self.get(&format!("{}/balance", contract_path))?.is_none() {
+            return Ok(None);
+        }
+
+        let value = match field {
+            ContractField::Balance => {
+                let balance = self.get_tez(&format!("{}/balance", contract_path))?;
+                Some(serde_json::Value::String(balance.to_string()))
+            }
+            ContractField::Counter if is_implicit => {
+                let counter = match self.get(&format!("{}/counter", contract_path))? {
+                    Some(counter) => counter,
+                    None => match self.get("data/contracts/global_counter")? {
+                        Some(counter) => counter,
+                        None => bail!("No global counter found in context"),
+                    },
+                };
+                Some(serde_json::Value::String(decode_z(&counter)?.to_string()))
+            }
+            ContractField::ManagerKey if is_implicit => {
+                // manager is either public key hash (tag 0) or revealed public key (tag 1)
+                match self.get(&format!("{}/manager", contract_path))? {
+                    Some(manager) if manager.first() == Some(&1) => Some(
+                        serde_json::Value::String(public_key_from_binary(&manager[1..])?),
+                    ),
+                    _ => Some(serde_json::Value::Null),
+                }
+            }
+            ContractField::Delegate => match self.get(&format!("{}/delegate", contract_path))? {
+                Some(delegate) => Some(serde_json::Value::String(pkh_from_binary(&delegate)?)),
+                None => None,
+            },
+            ContractField::Counter | ContractField::ManagerKey => None,
+        };
+        Ok(value)
+    }
+
+    /// Returns delegate info, `None` if contract does not exist or it is not registered as delegate
+    pub(crate) fn get_delegate_info(
+        &self,
+        pkh: &str,
+        tokens_per_roll: u64,
+    ) -> Result<Option<DelegateInfo>, failure::Error> {
+        if !pkh.starts_with("tz") {
+            bail!("Invalid public key hash: {}", pkh);
+        }
+        let contract = contract_id_to_contract_address_for_index(pkh)?;
+        let contract_path = contract_path(&contract)?;
+
+        // desactivation cycle is set on delegate registration, so it is missing for not registered delegates
+        let grace_period = match self.get(&format!("{}/delegate_desactivation", contract_path))? {
+            Some(cycle) => num_from_slice!(cycle, 0, i32),
+            None => return Ok(None),
+        };
+        let balance = match self.get(&format!("{}/balance", contract_path))? {
+            Some(balance) => decode_n(&balance)?,
+            None => return Ok(None),
+        };
+
+        // frozen deposits, fees and rewards per cycle
+        let mut frozen_by_cycle: BTreeMap<i32, [u64; 3]> = BTreeMap::new();
+        for (key, value) in self.get_by_prefix(&format!("{}/frozen_balance", contract_path))? {
+            let (cycle, kind) = match key.as_slice() {
+                [.., cycle, kind] => (cycle.parse::<i32>()?, kind.as_str()),
+                _ => continue,
+            };
+            let idx = match kind {
+                "deposits" => 0,
+                "fees" => 1,
+                "rewards" => 2,
+                _ => continue,
+            };
+            frozen_by_cycle.entry(cycle).or_default()[idx] = decode_n(&value)?;
+        }
+        let frozen_balance = frozen_by_cycle
+            .values()
+            .flatten()
+            .try_fold(0u64, |acc, tez| acc.checked_add(*tez))
+            .ok_or_else(|| format_err!("Frozen balance overflow"))?;
+        let full_balance = balance
+            .checked_add(frozen_balance)
+            .ok_or_else(|| format_err!("Balance overflow"))?;
+
+        let change = self.get_tez(&format!("{}/change", contract_path))?;
+        let staking_balance = tokens_per_roll
+            .checked_mul(self.count_rolls(&contract_path)?)
+            .and_then(|rolls_balance| rolls_balance.checked_add(change))
+            .ok_or_else(|| format_err!("Staking balance overflow"))?;
+
+        // sets are listed by context in reverse order
+        let mut delegated = self.get_by_prefix(&format!("{}/delegated", contract_path))?;
+        delegated.sort();
+        delegated.reverse();
+        let delegated_contracts = delegated
+            .iter()
+            .filter_map(|(key, _)| key.last())
+            .map(|contract| contract_id_from_binary(&hex::decode(contract)?))
+            .collect::<Result<Vec<_>, failure::Error>>()?;
+
+        let deactivated = self
+            .get(&format!("{}/inactive_delegate", contract_path))?
+            .as_deref()
+            == Some(CONTEXT_SET_MEMBER);
+        let voting_power = match self.get(&listings_path(&contract)?)? {
+            Some(rolls) => num_from_slice!(rolls, 0, i32),
+            None => 0,
+        };
+
+        Ok(Some(DelegateInfo {
+            balance: full_balance.to_string(),
+            frozen_balance: frozen_balance.to_string(),
+            frozen_balance_by_cycle: frozen_by_cycle
+                .into_iter()
+                .map(|(cycle, [deposit, fees, rewards])| FrozenBalanceByCycle {
+                    cycle,
+                    deposit: deposit.to_string(),
+                    fees: fees.to_string(),
+                    rewards: rewards.to_string(),
+                })
+                .collect(),
+            staking_balance: staking_balance.to_string(),
+            delegated_contracts,
+            delegated_balance: staking_balance.saturating_sub(full_balance).to_string(),
+            deactivated,
+            grace_period,
+            voting_power,
+        }))
+    }
+
+    /// Counts rolls of delegate by walking the linked list of rolls
+    fn count_rolls(&self, contract_path: &str) -> Result<u64, failure::Error> {
+        let mut roll = match self.get(&format!("{}/roll_list", contract_path))? {
+            Some(roll) => num_from_slice!(roll, 0, i32),
+            None => return Ok(0),
+        };
+        let mut count = 1;
+        while let Some(successor) = self.get(&format!(
+            "data/rolls/index/{}/{}/{}/successor",
+            roll & 0xff,
+            (roll >> 8) & 0xff,
+            roll
+        ))? {
+            roll = num_from_slice!(successor, 0, i32);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, failure::Error> {
+        Ok(self
+            .context
+            .get_key_from_history(self.context_hash, &context_key!(key))?)
+    }
+
+    /// Returns tez value, missing value is zero
+    fn get_tez(&self, key: &str) -> Result<u64, failure::Error> {
+        match self.get(key)? {
+            Some(value) => decode_n(&value),
+            None => Ok(0),
+        }
+    }
+
+    fn get_by_prefix(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<(ContextKey, ContextValue)>, failure::Error> {
+        Ok(self
+            .context
+            .get_key_values_by_prefix(self.context_hash, &context_key!(prefix))?
+            .unwrap_or_default())
+    }
+}
+
+/// Returns context path of contract, which is indexed by prefix of its hash
+fn contract_path(contract: &[u8]) -> Result<String, failure::Error> {
+    let index = blake2b::digest_256(contract)?;
+    Ok(format!(
+        "data/contracts/index/{}/{}",
+        index[..6].iter().map(|b| format!("{:02x}", b)).join("/"),
+        hex::encode(contract)
+    ))
+}
+
+/// Returns context path of vote listings for implicit contract
+fn listings_path(contract: &[u8]) -> Result<String, failure::Error> {
+    let curve = match contract {
+        [0, 0, ..] => "ed25519",
+        [0, 1, ..] => "secp256k1",
+        [0, 2, ..] => "p256",
+        _ => bail!("Contract is not implicit"),
+    };
+    let hash = &contract[2..];
+    Ok(format!(
+        "data/votes/listings/{}/{}/{}",
+        curve,
+        hash[..5].iter().map(|b| format!("{:02x}", b)).join("/"),
+        hex::encode(&hash[5..])
+    ))
+}
+
+/// Decodes binary contract id `[0, curve, hash(20)]` or `[1, hash(20), 0]`
+fn contract_id_from_binary(contract: &[u8]) -> Result<String, failure::Error> {
+    let contract_id = match contract {
+        [0, curve, hash @ ..] => pkh_from_binary(&[&[*curve], hash].concat())?,
+        [1, hash @ .., 0] => ContractKt1Hash::try_from(hash)?.to_base58_check(),
+        _ => bail!("Invalid contract: {}", hex::encode(contract)),
+    };
+    Ok(contract_id)
+}
+
+/// Decodes binary public key hash `[curve, hash(20)]`
+fn pkh_from_binary(pkh: &[u8]) -> Result<String, failure::Error> {
+    let pkh = match pkh {
+        [0, hash @ ..] => ContractTz1Hash::try_from(hash)?.to_base58_check(),
+        [1, hash @ ..] => ContractTz2Hash::try_from(hash)?.to_base58_check(),
+        [2, hash @ ..] => ContractTz3Hash::try_from(hash)?.to_base58_check(),
+        _ => bail!("Invalid public key hash: {}", hex::encode(pkh)),
+    };
+    Ok(pkh)
+}
+
+/// Decodes binary public key `[curve, key]`
+fn public_key_from_binary(public_key: &[u8]) -> Result<String, failure::Error> {
+    let public_key = match public_key {
+        [0, key @ ..] => PublicKeyEd25519::try_from(key)?.to_base58_check(),
+        [1, key @ ..] => PublicKeySecp256k1::try_from(key)?.to_base58_check(),
+        [2, key @ ..] => PublicKeyP256::try_from(key)?.to_base58_check(),
+        _ => bail!("Invalid public key: {}", hex::encode(public_key)),
+    };
+    Ok(public_key)
+}
+
+/// Decodes zarith natural number (`Tez.t`)
+fn decode_n(bytes: &[u8]) -> Result<u64, failure::Error> {
+    let mut value: u128 = 0;
+    for (idx, byte) in bytes.iter().enumerate() {
+        let shift = 7 * idx;
+        if shift >= 64 {
+            break;
+        }
+        value |= u128::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return u64::try_from(value).map_err(|_| format_err!("Natural number overflow"));
+        }
+    }
+    bail!("Invalid natural number: {}", hex::encode(bytes))
+}
+
+/// Decodes zarith integer (`Z.t`), first byte holds sign and 6 bits of value
+fn decode_z(bytes: &[u8]) -> Result<i64, failure::Error> {
+    let (first, rest) = match bytes.split_first() {
+        Some(split) => split,
+        None => bail!("Invalid integer: empty"),
+    };
+    let mut value = i128::from(first & 0x3f);
+    let mut has_next = first & 0x80 != 0;
+    for (idx, byte) in rest.iter().enumerate() {
+        let shift = 6 + 7 * idx;
+        if !has_next || shift >= 64 {
+            bail!("Invalid integer: {}", hex::encode(bytes));
+        }
+        value |= i128::from(byte & 0x7f) << shift;
+        has_next = byte & 0x80 != 0;
+    }
+    if has_next {
+        bail!("Invalid integer: {}", hex::encode(bytes));
+    }
+    if first & 0x40 != 0 {
+        value = -value;
+    }
+    i64::try_from(value).map_err(|_| format_err!("Integer overflow"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_zarith() {
+        assert_eq!(decode_n(&[0x00]).unwrap(), 0);
+        assert_eq!(decode_n(&[0x7f]).unwrap(), 127);
+        assert_eq!(decode_n(&[0x80, 0x01]).unwrap(), 128);
+        assert_eq!(
+            decode_n(&[0x80, 0x80, 0xd1, 0x94, 0xb5, 0x74]).unwrap(),
+            4_000_000_000_000
+        );
+        assert!(decode_n(&[0x80]).is_err());
+
+        assert_eq!(decode_z(&[0x00]).unwrap(), 0);
+        assert_eq!(decode_z(&[0x3f]).unwrap(), 63);
+        assert_eq!(decode_z(&[0x7f]).unwrap(), -63);
+        assert_eq!(decode_z(&[0x80, 0x01]).unwrap(), 64);
+        assert_eq!(decode_z(&[0xc0, 0x01]).unwrap(), -64);
+        assert!(decode_z(&[0x80]).is_err());
+    }
+
+    #[test]
+    fn test_contract_paths() {
+        let contract =
+            contract_id_to_contract_address_for_index("tz1Y68Da76MHixYhJhyU36bVh7a8C9UmtvrR")
+                .unwrap();
+        assert_eq!(
+            contract_id_from_binary(&contract).unwrap(),
+            "tz1Y68Da76MHixYhJhyU36bVh7a8C9UmtvrR"
+        );
+        let path = contract_path(&contract).unwrap();
+        assert!(path.starts_with("data/contracts/index/"));
+        assert!(path.ends_with(&format!("/{}", hex::encode(&contract))));
+        assert_eq!(path.split('/').count(), 10);
+
+        let listings = listings_path(&contract).unwrap();
+        assert!(listings.starts_with("data/votes/listings/ed25519/"));
+        assert_eq!(listings.split('/').count(), 10);
+
+        let contract =
+            contract_id_to_contract_address_for_index("KT1BEqzn5Wx8uJrZNvuS9DVHmLvG9td3fDLi")
+                .unwrap();
+        assert_eq!(
+            contract_id_from_binary(&contract).unwrap(),
+            "KT1BEqzn5Wx8uJrZNvuS9DVHmLvG9td3fDLi"
+        );
+        assert!(listings_path(&contract).is_err());
+    }
+}
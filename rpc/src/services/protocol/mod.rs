@@ -27,7 +27,13 @@ use tezos_messages::protocol::{SupportedProtocol, UnsupportedProtocolError};
 
 use crate::helpers::get_context_hash;
 use crate::server::RpcServiceEnvironment;
+use crate::services::protocol::contracts::{ContractsReader, DelegateInfo};
+use crate::services::protocol::response_cache::ProtocolRpcCacheKey;
+use crate::services::protocol::rights::RightsDescriptor;
 
+pub(crate) use crate::services::protocol::contracts::ContractField;
+
+mod contracts;
 mod proto_001;
 mod proto_002;
 mod proto_003;
@@ -37,6 +43,8 @@ mod proto_006;
 mod proto_007;
 mod proto_008;
 mod proto_008_2;
+pub(crate) mod response_cache;
+mod rights;
 
 #[derive(Debug, Fail)]
 pub enum RightsError {
@@ -92,101 +100,19 @@ pub(crate) fn check_and_get_baking_rights(
 ) -> Result<Option<Vec<RpcJsonMap>>, RightsError> {
     // get protocol and constants
     let context_proto_params = get_context_protocol_params(block_hash, env)?;
-
-    // split impl by protocol
-    match context_proto_params.protocol_hash {
-        SupportedProtocol::Proto001 => proto_001::rights_service::check_and_get_baking_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            max_priority,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto002 => proto_002::rights_service::check_and_get_baking_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            max_priority,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto003 => proto_003::rights_service::check_and_get_baking_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            max_priority,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto004 => proto_004::rights_service::check_and_get_baking_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            max_priority,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto005 => panic!("not yet implemented!"),
-        SupportedProtocol::Proto005_2 => proto_005_2::rights_service::check_and_get_baking_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            max_priority,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto006 => proto_006::rights_service::check_and_get_baking_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            max_priority,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto007 => proto_007::rights_service::check_and_get_baking_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            max_priority,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto008 => proto_008::rights_service::check_and_get_baking_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            max_priority,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto008_2 => proto_008_2::rights_service::check_and_get_baking_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            max_priority,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-    }
+    let descriptor = rights_descriptor(&context_proto_params.protocol_hash)?;
+
+    rights::check_and_get_baking_rights(
+        context_proto_params,
+        descriptor,
+        level,
+        delegate,
+        cycle,
+        max_priority,
+        has_all,
+        env.tezedge_context(),
+    )
+    .map_err(RightsError::from)
 }
 
 /// Return generated endorsing rights.
@@ -214,95 +140,37 @@ pub(crate) fn check_and_get_endorsing_rights(
 ) -> Result<Option<Vec<RpcJsonMap>>, RightsError> {
     // get protocol and constants
     let context_proto_params = get_context_protocol_params(block_hash, env)?;
+    let descriptor = rights_descriptor(&context_proto_params.protocol_hash)?;
+
+    rights::check_and_get_endorsing_rights(
+        context_proto_params,
+        descriptor,
+        level,
+        delegate,
+        cycle,
+        has_all,
+        env.tezedge_context(),
+    )
+    .map_err(RightsError::from)
+}
 
-    // split impl by protocol
-    match context_proto_params.protocol_hash {
-        SupportedProtocol::Proto001 => proto_001::rights_service::check_and_get_endorsing_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto002 => proto_002::rights_service::check_and_get_endorsing_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto003 => proto_003::rights_service::check_and_get_endorsing_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto004 => proto_004::rights_service::check_and_get_endorsing_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto005 => panic!("not yet implemented!"),
-        SupportedProtocol::Proto005_2 => {
-            proto_005_2::rights_service::check_and_get_endorsing_rights(
-                context_proto_params,
-                level,
-                delegate,
-                cycle,
-                has_all,
-                env.tezedge_context(),
-            )
-            .map_err(RightsError::from)
-        }
-        SupportedProtocol::Proto006 => proto_006::rights_service::check_and_get_endorsing_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto007 => proto_007::rights_service::check_and_get_endorsing_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto008 => proto_008::rights_service::check_and_get_endorsing_rights(
-            context_proto_params,
-            level,
-            delegate,
-            cycle,
-            has_all,
-            env.tezedge_context(),
-        )
-        .map_err(RightsError::from),
-        SupportedProtocol::Proto008_2 => {
-            proto_008_2::rights_service::check_and_get_endorsing_rights(
-                context_proto_params,
-                level,
-                delegate,
-                cycle,
-                has_all,
-                env.tezedge_context(),
-            )
-            .map_err(RightsError::from)
-        }
+/// Returns descriptor of rights generation for protocol, new protocol has to be registered here
+fn rights_descriptor(
+    protocol: &SupportedProtocol,
+) -> Result<&'static RightsDescriptor, RightsError> {
+    match protocol {
+        SupportedProtocol::Proto001 => Ok(&proto_001::RIGHTS_DESCRIPTOR),
+        SupportedProtocol::Proto002 => Ok(&proto_002::RIGHTS_DESCRIPTOR),
+        SupportedProtocol::Proto003 => Ok(&proto_003::RIGHTS_DESCRIPTOR),
+        SupportedProtocol::Proto004 => Ok(&proto_004::RIGHTS_DESCRIPTOR),
+        SupportedProtocol::Proto005 => Err(RightsError::UnsupportedProtocolError {
+            protocol: protocol.protocol_hash(),
+        }),
+        SupportedProtocol::Proto005_2 => Ok(&proto_005_2::RIGHTS_DESCRIPTOR),
+        SupportedProtocol::Proto006 => Ok(&proto_006::RIGHTS_DESCRIPTOR),
+        SupportedProtocol::Proto007 => Ok(&proto_007::RIGHTS_DESCRIPTOR),
+        SupportedProtocol::Proto008 => Ok(&proto_008::RIGHTS_DESCRIPTOR),
+        SupportedProtocol::Proto008_2 => Ok(&proto_008_2::RIGHTS_DESCRIPTOR),
     }
 }
 
@@ -428,6 +296,124 @@ pub(crate) fn get_votes_listings(
     }
 }
 
+#[derive(Debug, Fail)]
+pub enum ContractsError {
+    #[fail(display = "Contracts error, reason: {}", reason)]
+    ServiceError { reason: Error },
+    #[fail(display = "Unsupported protocol {}", protocol)]
+    UnsupportedProtocolError { protocol: String },
+}
+
+impl From<ContextParamsError> for ContractsError {
+    fn from(error: ContextParamsError) -> Self {
+        match error {
+            ContextParamsError::UnsupportedProtocolError { protocol } => {
+                ContractsError::UnsupportedProtocolError { protocol }
+            }
+            _ => ContractsError::ServiceError {
+                reason: error.into(),
+            },
+        }
+    }
+}
+
+impl From<failure::Error> for ContractsError {
+    fn from(error: failure::Error) -> Self {
+        ContractsError::ServiceError { reason: error }
+    }
+}
+
+impl From<serde_json::Error> for ContractsError {
+    fn from(error: serde_json::Error) -> Self {
+        ContractsError::ServiceError {
+            reason: error.into(),
+        }
+    }
+}
+
+/// Returns contract field (`balance`, `counter`, `manager_key`, `delegate`) read directly from context,
+/// `Ok(None)` means, that contract or field does not exist.
+///
+/// # Arguments
+///
+/// * `block_hash` - Resolved url path parameter 'block_id'.
+/// * `contract_id` - Url path parameter 'contract_id'.
+/// * `field` - Requested field of contract.
+/// * `env` - RPC environment with context.
+pub(crate) fn get_contract_field(
+    block_hash: &BlockHash,
+    contract_id: &str,
+    field: ContractField,
+    env: &RpcServiceEnvironment,
+) -> Result<Option<serde_json::Value>, ContractsError> {
+    let context_proto_params = get_context_protocol_params(block_hash, env)?;
+    check_contracts_layout(&context_proto_params.protocol_hash)?;
+
+    ContractsReader::new(
+        env.tezedge_context(),
+        context_proto_params.block_header.header.context(),
+    )
+    .get_contract_field(contract_id, field)
+    .map_err(ContractsError::from)
+}
+
+/// Returns delegate info read directly from context, `Ok(None)` means, that delegate does not exist.
+///
+/// # Arguments
+///
+/// * `block_hash` - Resolved url path parameter 'block_id'.
+/// * `pkh` - Url path parameter 'pkh'.
+/// * `env` - RPC environment with context.
+pub(crate) fn get_delegate_info(
+    block_hash: &BlockHash,
+    pkh: &str,
+    env: &RpcServiceEnvironment,
+) -> Result<Option<DelegateInfo>, ContractsError> {
+    let context_proto_params = get_context_protocol_params(block_hash, env)?;
+    check_contracts_layout(&context_proto_params.protocol_hash)?;
+
+    // staking balance is counted from rolls
+    let tokens_per_roll = get_tokens_per_roll(&context_proto_params)?;
+
+    ContractsReader::new(
+        env.tezedge_context(),
+        context_proto_params.block_header.header.context(),
+    )
+    .get_delegate_info(pkh, tokens_per_roll)
+    .map_err(ContractsError::from)
+}
+
+fn get_tokens_per_roll(context_proto_params: &ContextProtocolParam) -> Result<u64, failure::Error> {
+    let constants = tezos_messages::protocol::get_constants_for_rpc(
+        &context_proto_params.constants_data,
+        &context_proto_params.protocol_hash,
+    )?
+    .ok_or_else(|| format_err!("No protocol constants found"))?;
+
+    serde_json::to_value(constants)?["tokens_per_roll"]
+        .as_str()
+        .and_then(|tokens_per_roll| tokens_per_roll.parse().ok())
+        .ok_or_else(|| format_err!("Invalid protocol constant 'tokens_per_roll'"))
+}
+
+/// Contracts and delegates are read natively only for protocols with known context layout, other protocols are handled by protocol runner
+fn check_contracts_layout(protocol: &SupportedProtocol) -> Result<(), ContractsError> {
+    match protocol {
+        SupportedProtocol::Proto005_2
+        | SupportedProtocol::Proto006
+        | SupportedProtocol::Proto007
+        | SupportedProtocol::Proto008
+        | SupportedProtocol::Proto008_2 => Ok(()),
+        SupportedProtocol::Proto001
+        | SupportedProtocol::Proto002
+        | SupportedProtocol::Proto003
+        | SupportedProtocol::Proto004
+        | SupportedProtocol::Proto005 => Err(ContractsError::UnsupportedProtocolError {
+            protocol: protocol.protocol_hash(),
+        }),
+    }
+}
+
 /// Get protocol context constants from context list
 /// (just for RPC render use-case, do not use in processing or algorithms)
 ///
@@ -471,18 +457,35 @@ pub(crate) fn call_protocol_rpc(
     env: &RpcServiceEnvironment,
 ) -> Result<serde_json::value::Value, failure::Error> {
     let context_path = rpc_request.context_path.clone();
+
+    // responses are deterministic per block, so we can reuse already cached response
+    let cache_key = ProtocolRpcCacheKey::for_request(&chain_id, &block_hash, &rpc_request);
+    if let Some(cache_key) = &cache_key {
+        let cached_body = env.protocol_rpc_cache().lock().unwrap().get(cache_key);
+        if let Some(body) = cached_body {
+            return Ok(serde_json::from_str(&body)?);
+        }
+    }
+
     let request =
         create_protocol_rpc_request(chain_param, chain_id, block_hash, rpc_request, &env)?;
+    let level = request.block_header.level();
 
-    // TODO: retry?
+    // crashed protocol runner is restarted and call is retried by connection
     let response = env
         .tezos_readonly_api()
         .pool
         .get()?
-        .api
         .call_protocol_rpc(request)?;
 
-    handle_rpc_response(&response, context_path)
+    let result = handle_rpc_response(&response, context_path)?;
+    if let (Some(cache_key), ProtocolRpcResponse::RPCOk(body)) = (cache_key, response) {
+        env.protocol_rpc_cache()
+            .lock()
+            .unwrap()
+            .insert(cache_key, level, body);
+    }
+    Ok(result)
 }
 
 pub(crate) fn preapply_operations(
@@ -1,5 +1,47 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
-mod helpers;
-pub(crate) mod rights_service;
+use failure::format_err;
+
+use tezos_messages::p2p::binary_message::BinaryMessage;
+use tezos_messages::protocol::proto_002::constants::{ParametricConstants, FIXED};
+
+use crate::services::protocol::rights::{
+    BakingPriorityRange, RightsConstants, RightsContextKeys, RightsDescriptor,
+};
+
+pub(crate) static RIGHTS_DESCRIPTOR: RightsDescriptor = RightsDescriptor {
+    parse_constants: parse_rights_constants,
+    context_keys: RightsContextKeys::DEFAULT,
+    baking_use_string: RightsDescriptor::BAKING_USE_STRING,
+    endorsement_use_string: RightsDescriptor::ENDORSEMENT_USE_STRING,
+    baking_priority_range: BakingPriorityRange::Exclusive,
+    default_max_priority: 64,
+    default_max_priority_for_cycle: 64,
+};
+
+fn parse_rights_constants(constants_data: &[u8]) -> Result<RightsConstants, failure::Error> {
+    let dynamic = ParametricConstants::from_bytes(constants_data)?;
+    // in proto 002, the constants are hard coded but a few exceptions modifiable in the context
+    let dynamic_all = ParametricConstants::create_with_default_and_merge(dynamic);
+
+    Ok(RightsConstants::new(
+        dynamic_all
+            .blocks_per_cycle()
+            .ok_or_else(|| format_err!("Missing constant blocks_per_cycle"))?,
+        dynamic_all
+            .preserved_cycles()
+            .ok_or_else(|| format_err!("Missing constant preserved_cycles"))?,
+        FIXED.nonce_length(),
+        dynamic_all
+            .time_between_blocks()
+            .clone()
+            .ok_or_else(|| format_err!("Missing constant time_between_blocks"))?,
+        dynamic_all
+            .blocks_per_roll_snapshot()
+            .ok_or_else(|| format_err!("Missing constant blocks_per_roll_snapshot"))?,
+        dynamic_all
+            .endorsers_per_block()
+            .ok_or_else(|| format_err!("Missing constant endorsers_per_block"))?,
+    ))
+}
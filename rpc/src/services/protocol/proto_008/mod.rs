@@ -1,6 +1,34 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
-mod helpers;
-pub(crate) mod rights_service;
+use tezos_messages::p2p::binary_message::BinaryMessage;
+use tezos_messages::protocol::proto_008::constants::{ParametricConstants, FIXED};
+
+use crate::services::protocol::rights::{
+    BakingPriorityRange, RightsConstants, RightsContextKeys, RightsDescriptor,
+};
+
 pub(crate) mod votes_service;
+
+pub(crate) static RIGHTS_DESCRIPTOR: RightsDescriptor = RightsDescriptor {
+    parse_constants: parse_rights_constants,
+    context_keys: RightsContextKeys::DEFAULT,
+    baking_use_string: RightsDescriptor::BAKING_USE_STRING,
+    endorsement_use_string: RightsDescriptor::ENDORSEMENT_USE_STRING,
+    baking_priority_range: BakingPriorityRange::Inclusive,
+    default_max_priority: 64,
+    default_max_priority_for_cycle: 8,
+};
+
+fn parse_rights_constants(constants_data: &[u8]) -> Result<RightsConstants, failure::Error> {
+    let dynamic = ParametricConstants::from_bytes(constants_data)?;
+
+    Ok(RightsConstants::new(
+        dynamic.blocks_per_cycle(),
+        dynamic.preserved_cycles(),
+        FIXED.nonce_length(),
+        dynamic.time_between_blocks().clone(),
+        dynamic.blocks_per_roll_snapshot(),
+        dynamic.endorsers_per_block(),
+    ))
+}
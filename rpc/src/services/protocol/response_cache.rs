@@ -0,0 +1,356 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Bounded (by size of responses) LRU cache for protocol RPC responses.
+//!
+//! Responses are keyed by resolved block hash (never by alias like `head` or `head~2`), so a request with alias
+//! is cached under the block, to which alias pointed at the time of request, and new head naturally misses.
+//! Only `GET` requests without body are cached, because only those are deterministic per block.
+//!
+//! When current head moves to the same or lower level (branch switch), entries of blocks above the new head are invalidated,
+//! because they are not reachable by head-relative aliases anymore.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crypto::hash::{BlockHash, ChainId};
+use tezos_api::ffi::{RpcMethod, RpcRequest};
+use tezos_messages::p2p::encoding::block_header::Level;
+
+/// Responses larger than this part of the cache capacity are not cached, to prevent flushing of the whole cache
+const MAX_ENTRY_SIZE_DIVISOR: usize = 4;
+
+/// Shareable type for protocol rpc response cache
+pub type ProtocolRpcResponseCacheRef = Arc<Mutex<ProtocolRpcResponseCache>>;
+
+/// Inits empty protocol rpc response cache, `capacity_bytes` with zero value disables cache
+pub fn init_protocol_rpc_response_cache(capacity_bytes: usize) -> ProtocolRpcResponseCacheRef {
+    Arc::new(Mutex::new(ProtocolRpcResponseCache::new(capacity_bytes)))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProtocolRpcCacheKey {
+    chain_id: ChainId,
+    block_hash: BlockHash,
+    /// Part of the request path (with query) after block id, e.g. `/context/constants`
+    path: String,
+    accept: Option<String>,
+}
+
+impl ProtocolRpcCacheKey {
+    /// Returns key for request, if response for request could be cached
+    pub fn for_request(
+        chain_id: &ChainId,
+        block_hash: &BlockHash,
+        rpc_request: &RpcRequest,
+    ) -> Option<Self> {
+        if rpc_request.meth != RpcMethod::GET || !rpc_request.body.is_empty() {
+            return None;
+        }
+        block_scoped_path(&rpc_request.context_path).map(|path| Self {
+            chain_id: chain_id.clone(),
+            block_hash: block_hash.clone(),
+            path: path.to_string(),
+            accept: rpc_request.accept.clone(),
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.path.len() + self.accept.as_ref().map_or(0, |accept| accept.len())
+    }
+}
+
+/// Strips `/chains/<chain_id>/blocks/<block_id>` from the request path
+fn block_scoped_path(context_path: &str) -> Option<&str> {
+    let mut segments = context_path.trim_start_matches('/').splitn(5, '/');
+    match (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) {
+        (Some("chains"), Some(_), Some("blocks"), Some(block_id)) => {
+            // block id could be followed directly by query
+            let query = block_id.find('?').map_or("", |idx| &block_id[idx..]);
+            match segments.next() {
+                Some(rest) => Some(&context_path[context_path.len() - rest.len() - 1..]),
+                None => Some(query),
+            }
+        }
+        _ => None,
+    }
+}
+
+struct CacheEntry {
+    body: Arc<String>,
+    level: Level,
+    /// Position in lru queue
+    tick: u64,
+}
+
+impl CacheEntry {
+    fn size(&self, key: &ProtocolRpcCacheKey) -> usize {
+        self.body.len() + key.size()
+    }
+}
+
+/// Hit/miss metrics of the cache
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ProtocolRpcCacheStats {
+    pub enabled: bool,
+    pub hits: u64,
+    pub misses: u64,
+    pub inserts: u64,
+    /// Entries removed to free space for new entries
+    pub evictions: u64,
+    /// Entries removed because of head change
+    pub invalidations: u64,
+    pub entries: usize,
+    pub size_bytes: usize,
+    pub capacity_bytes: usize,
+}
+
+pub struct ProtocolRpcResponseCache {
+    capacity_bytes: usize,
+    size_bytes: usize,
+    entries: HashMap<ProtocolRpcCacheKey, CacheEntry>,
+    /// Least recently used entry is first
+    lru: BTreeMap<u64, ProtocolRpcCacheKey>,
+    tick: u64,
+    /// Level of the last current head
+    head_level: Option<Level>,
+    stats: ProtocolRpcCacheStats,
+}
+
+impl ProtocolRpcResponseCache {
+    pub fn new(capacity_bytes: usize) -> Self {
+        Self {
+            capacity_bytes,
+            size_bytes: 0,
+            entries: HashMap::default(),
+            lru: BTreeMap::default(),
+            tick: 0,
+            head_level: None,
+            stats: ProtocolRpcCacheStats::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity_bytes > 0
+    }
+
+    /// Returns cached response body and marks it as recently used
+    pub fn get(&mut self, key: &ProtocolRpcCacheKey) -> Option<Arc<String>> {
+        if !self.is_enabled() {
+            return None;
+        }
+        self.tick += 1;
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                self.lru.remove(&entry.tick);
+                entry.tick = self.tick;
+                self.lru.insert(self.tick, key.clone());
+                self.stats.hits += 1;
+                Some(entry.body.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Inserts response body for block at `level`, least recently used entries are evicted to fit into capacity
+    pub fn insert(&mut self, key: ProtocolRpcCacheKey, level: Level, body: String) {
+        let entry_size = body.len() + key.size();
+        if !self.is_enabled() || entry_size > self.capacity_bytes / MAX_ENTRY_SIZE_DIVISOR {
+            return;
+        }
+        self.remove(&key);
+
+        while self.size_bytes + entry_size > self.capacity_bytes {
+            let lru_key = match self.lru.values().next() {
+                Some(lru_key) => lru_key.clone(),
+                None => break,
+            };
+            self.remove(&lru_key);
+            self.stats.evictions += 1;
+        }
+
+        self.tick += 1;
+        self.size_bytes += entry_size;
+        self.lru.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            CacheEntry {
+                body: Arc::new(body),
+                level,
+                tick: self.tick,
+            },
+        );
+        self.stats.inserts += 1;
+    }
+
+    /// New current head was set, on branch switch invalidates entries of blocks above the new head
+    pub fn current_head_changed(&mut self, head_hash: &BlockHash, head_level: Level) {
+        let previous_head_level = self.head_level.replace(head_level);
+        if !self.is_enabled() {
+            return;
+        }
+        if let Some(previous_head_level) = previous_head_level {
+            if head_level > previous_head_level {
+                return;
+            }
+            let invalidated: Vec<ProtocolRpcCacheKey> = self
+                .entries
+                .iter()
+                .filter(|(key, entry)| entry.level >= head_level && &key.block_hash != head_hash)
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in invalidated {
+                self.remove(&key);
+                self.stats.invalidations += 1;
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ProtocolRpcCacheStats {
+        ProtocolRpcCacheStats {
+            enabled: self.is_enabled(),
+            entries: self.entries.len(),
+            size_bytes: self.size_bytes,
+            capacity_bytes: self.capacity_bytes,
+            ..self.stats.clone()
+        }
+    }
+
+    fn remove(&mut self, key: &ProtocolRpcCacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.lru.remove(&entry.tick);
+            self.size_bytes -= entry.size(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    fn request(context_path: &str) -> RpcRequest {
+        RpcRequest {
+            body: String::new(),
+            context_path: context_path.to_string(),
+            meth: RpcMethod::GET,
+            content_type: None,
+            accept: None,
+        }
+    }
+
+    fn key(block: u8, context_path: &str) -> ProtocolRpcCacheKey {
+        ProtocolRpcCacheKey::for_request(
+            &ChainId::try_from(vec![1, 2, 3, 4]).expect("invalid chain id"),
+            &BlockHash::try_from(vec![block; 32]).expect("invalid block hash"),
+            &request(context_path),
+        )
+        .expect("request should be cacheable")
+    }
+
+    #[test]
+    fn test_block_scoped_path() {
+        assert_eq!(
+            block_scoped_path("/chains/main/blocks/head/context/constants"),
+            Some("/context/constants")
+        );
+        assert_eq!(
+            block_scoped_path("/chains/main/blocks/head~2/helpers/baking_rights?level=5"),
+            Some("/helpers/baking_rights?level=5")
+        );
+        assert_eq!(
+            block_scoped_path("/chains/main/blocks/head?x=1"),
+            Some("?x=1")
+        );
+        assert_eq!(block_scoped_path("/chains/main/blocks"), None);
+        assert_eq!(block_scoped_path("/monitor/heads/main"), None);
+
+        // aliases of the same block share key
+        assert_eq!(
+            key(1, "/chains/main/blocks/head/context/constants"),
+            key(1, "/chains/main/blocks/BLockGenesis/context/constants")
+        );
+
+        let mut post = request("/chains/main/blocks/head/helpers/preapply/operations");
+        post.meth = RpcMethod::POST;
+        assert!(ProtocolRpcCacheKey::for_request(
+            &ChainId::try_from(vec![1, 2, 3, 4]).expect("invalid chain id"),
+            &BlockHash::try_from(vec![1; 32]).expect("invalid block hash"),
+            &post,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let path = "/chains/main/blocks/head/context/constants";
+        let entry_size = 10 + key(1, path).size();
+        let mut cache = ProtocolRpcResponseCache::new(MAX_ENTRY_SIZE_DIVISOR * entry_size);
+
+        cache.insert(key(1, path), 1, "x".repeat(10));
+        cache.insert(key(2, path), 2, "x".repeat(10));
+        cache.insert(key(3, path), 3, "x".repeat(10));
+        cache.insert(key(4, path), 4, "x".repeat(10));
+        assert!(cache.get(&key(1, path)).is_some());
+
+        // block 2 is the least recently used
+        cache.insert(key(5, path), 5, "x".repeat(10));
+        assert!(cache.get(&key(2, path)).is_none());
+        assert!(cache.get(&key(1, path)).is_some());
+
+        // too large entry is not cached
+        cache.insert(key(6, path), 6, "x".repeat(11));
+        assert!(cache.get(&key(6, path)).is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 4);
+        assert_eq!(stats.size_bytes, 4 * entry_size);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 2);
+    }
+
+    #[test]
+    fn test_invalidates_blocks_above_head_on_branch_switch() {
+        let path = "/chains/main/blocks/head/context/constants";
+        let mut cache = ProtocolRpcResponseCache::new(1024 * 1024);
+        cache.current_head_changed(&BlockHash::try_from(vec![2; 32]).unwrap(), 2);
+        cache.insert(key(1, path), 1, "{}".to_string());
+        cache.insert(key(2, path), 2, "{}".to_string());
+        cache.current_head_changed(&BlockHash::try_from(vec![3; 32]).unwrap(), 3);
+        cache.insert(key(3, path), 3, "{}".to_string());
+
+        // branch switch to block 4 on the same level
+        cache.current_head_changed(&BlockHash::try_from(vec![4; 32]).unwrap(), 3);
+        assert!(cache.get(&key(3, path)).is_none());
+        assert!(cache.get(&key(2, path)).is_some());
+        assert!(cache.get(&key(1, path)).is_some());
+        assert_eq!(cache.stats().invalidations, 1);
+    }
+
+    #[test]
+    fn test_disabled_cache() {
+        let path = "/chains/main/blocks/head/context/constants";
+        let mut cache = ProtocolRpcResponseCache::new(0);
+        cache.insert(key(1, path), 1, "{}".to_string());
+        assert!(cache.get(&key(1, path)).is_none());
+        assert_eq!(
+            cache.stats(),
+            ProtocolRpcCacheStats {
+                enabled: false,
+                ..Default::default()
+            }
+        );
+    }
+}
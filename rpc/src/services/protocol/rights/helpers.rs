@@ -13,12 +13,11 @@ use crypto::{
     crypto_box::PublicKeyError,
 };
 use storage::context::{ContextApi, TezedgeContext};
-use storage::{context_key, num_from_slice, BlockHeaderWithHash};
+use storage::{context_key, num_from_slice};
 use tezos_messages::base::signature_public_key_hash::SignaturePublicKeyHash;
-use tezos_messages::p2p::binary_message::BinaryMessage;
 
 use crate::merge_slices;
-use crate::services::protocol::ContextProtocolParam;
+use crate::services::protocol::rights::{RightsContextKeys, RightsDescriptor};
 
 /// Context constants used in baking and endorsing rights
 #[derive(Debug, Clone, Getters)]
@@ -39,7 +38,7 @@ pub struct RightsConstants {
 
 impl RightsConstants {
     /// simple constructor to create RightsConstants
-    pub fn new(
+    pub(crate) fn new(
         blocks_per_cycle: i32,
         preserved_cycles: u8,
         nonce_length: u8,
@@ -56,31 +55,6 @@ impl RightsConstants {
             endorsers_per_block,
         }
     }
-
-    /// Get all context constants which are used in endorsing and baking rights generation
-    ///
-    /// # Arguments
-    ///
-    /// * `context_proto_param`
-    #[inline]
-    pub(crate) fn parse_rights_constants(
-        context_proto_param: &ContextProtocolParam,
-    ) -> Result<Self, failure::Error> {
-        let dynamic =
-            tezos_messages::protocol::proto_006::constants::ParametricConstants::from_bytes(
-                &context_proto_param.constants_data,
-            )?;
-        let fixed = tezos_messages::protocol::proto_006::constants::FIXED;
-
-        Ok(RightsConstants::new(
-            dynamic.blocks_per_cycle(),
-            dynamic.preserved_cycles(),
-            fixed.nonce_length(),
-            dynamic.time_between_blocks().clone(),
-            dynamic.blocks_per_roll_snapshot(),
-            dynamic.endorsers_per_block(),
-        ))
-    }
 }
 
 /// Data from context DB for completing baking and endorsing rights
@@ -115,12 +89,14 @@ impl RightsContextData {
     ///
     /// * `parameters` - Parameters created by [RightsParams](RightsParams::parse_rights_parameters).
     /// * `constants` - Context constants used in baking and endorsing rights.
+    /// * `context_keys` - Context keys of the protocol [RightsDescriptor::context_keys].
     /// * `list` - Context list handler.
     ///
     /// Return RightsContextData.
     pub(crate) fn prepare_context_data_for_rights(
         parameters: RightsParams,
         constants: RightsConstants,
+        context_keys: &RightsContextKeys,
         (ctx_hash, context): (&ContextHash, &TezedgeContext),
     ) -> Result<Self, failure::Error> {
         // prepare constants that are used
@@ -140,7 +116,7 @@ impl RightsContextData {
         let roll_snapshot: i16 = {
             if let Some(data) = context.get_key_from_history(
                 &ctx_hash,
-                &context_key!("data/cycle/{}/roll_snapshot", requested_cycle),
+                &context_key!("{}/{}/roll_snapshot", context_keys.cycle, requested_cycle),
             )? {
                 num_from_slice!(data, 0, i16)
             } else {
@@ -152,7 +128,7 @@ impl RightsContextData {
         let random_seed = {
             if let Some(data) = context.get_key_from_history(
                 &ctx_hash,
-                &context_key!("data/cycle/{}/random_seed", requested_cycle),
+                &context_key!("{}/{}/random_seed", context_keys.cycle, requested_cycle),
             )? {
                 data
             } else {
@@ -165,7 +141,12 @@ impl RightsContextData {
         let last_roll = {
             if let Some(data) = context.get_key_from_history(
                 &ctx_hash,
-                &context_key!("data/cycle/{}/last_roll/{}", requested_cycle, roll_snapshot),
+                &context_key!(
+                    "{}/{}/last_roll/{}",
+                    context_keys.cycle,
+                    requested_cycle,
+                    roll_snapshot
+                ),
             )? {
                 num_from_slice!(data, 0, i32)
             } else {
@@ -175,9 +156,12 @@ impl RightsContextData {
         };
 
         // get list of rolls from context list
-        let context_rolls = if let Some(rolls) =
-            Self::get_context_rolls((&ctx_hash, &context), requested_cycle, roll_snapshot)?
-        {
+        let context_rolls = if let Some(rolls) = Self::get_context_rolls(
+            (&ctx_hash, &context),
+            context_keys,
+            requested_cycle,
+            roll_snapshot,
+        )? {
             rolls
         } else {
             return Err(format_err!("rolls"));
@@ -195,12 +179,18 @@ impl RightsContextData {
     /// Return rollers for [RightsContextData.rolls](RightsContextData.rolls)
     fn get_context_rolls(
         (ctx_hash, context): (&ContextHash, &TezedgeContext),
+        context_keys: &RightsContextKeys,
         cycle: i64,
         snapshot: i16,
     ) -> Result<Option<HashMap<i32, String>>, failure::Error> {
         let rolls = if let Some(val) = context.get_key_values_by_prefix(
             &ctx_hash,
-            &context_key!("data/rolls/owner/snapshot/{}/{}", cycle, snapshot),
+            &context_key!(
+                "{}/{}/{}",
+                context_keys.rolls_owner_snapshot,
+                cycle,
+                snapshot
+            ),
         )? {
             val
         } else {
@@ -303,10 +293,9 @@ impl RightsParams {
     /// * `param_cycle` - Url query parameter 'cycle'.
     /// * `param_max_priority` - Url query parameter 'max_priority'.
     /// * `param_has_all` - Url query parameter 'all'.
-    /// * `block_level` - Block level from block_id.
     /// * `rights_constants` - Context constants used in baking and endorsing rights.
-    /// * `context_list` - Context list handler.
-    /// * `persistent_storage` - Persistent storage handler.
+    /// * `descriptor` - Differences of the protocol.
+    /// * `(block_level, block_timestamp)` - Level and timestamp of block from block_id.
     /// * `is_baking_rights` - flag to identify if are parsed baking or endorsing rights
     ///
     /// Return RightsParams
@@ -317,10 +306,10 @@ impl RightsParams {
         param_max_priority: Option<&str>,
         param_has_all: bool,
         rights_constants: &RightsConstants,
-        block_header: &BlockHeaderWithHash,
+        descriptor: &RightsDescriptor,
+        (block_level, block_timestamp): (i64, i64),
         is_baking_rights: bool,
     ) -> Result<Self, failure::Error> {
-        let block_level: i64 = block_header.header.level().into();
         let preserved_cycles = *rights_constants.preserved_cycles();
         let blocks_per_cycle = *rights_constants.blocks_per_cycle();
 
@@ -379,12 +368,18 @@ impl RightsParams {
         // set max_priority from param value or default
         let max_priority = match param_max_priority {
             Some(val) => val.parse()?,
-            None => 64,
+            None => {
+                if requested_cycle.is_some() {
+                    descriptor.default_max_priority_for_cycle
+                } else {
+                    descriptor.default_max_priority
+                }
+            }
         };
 
         Ok(Self::new(
             block_level,
-            block_header.header.timestamp(),
+            block_timestamp,
             param_delegate.map(String::from),
             requested_cycle,
             requested_level,
@@ -0,0 +1,183 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Baking and endorsing rights generated with Tezos PRNG from context data, shared by all supported protocols.
+//!
+//! Differences between protocols are described by [RightsDescriptor], so a new protocol needs only
+//! a descriptor in its `proto_XYZ` module and a test vector in `rights/test_vectors`.
+
+pub(crate) use helpers::RightsConstants;
+pub(crate) use rights_service::{check_and_get_baking_rights, check_and_get_endorsing_rights};
+
+mod helpers;
+mod rights_service;
+
+/// Range of priorities, for which baking rights are listed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BakingPriorityRange {
+    /// `0..max_priority`
+    Exclusive,
+    /// `0..=max_priority`
+    Inclusive,
+}
+
+/// Context keys used for rights generation
+#[derive(Debug)]
+pub(crate) struct RightsContextKeys {
+    /// Prefix of cycle data - `<cycle>/<cycle_number>/{roll_snapshot,random_seed,last_roll/<snapshot>}`
+    pub cycle: &'static str,
+    /// Prefix of rolls owners - `<rolls_owner_snapshot>/<cycle_number>/<snapshot>/...`
+    pub rolls_owner_snapshot: &'static str,
+}
+
+/// Describes, how rights are generated for a protocol
+pub(crate) struct RightsDescriptor {
+    /// Decodes constants used for rights generation from protocol constants stored in context
+    pub parse_constants: fn(&[u8]) -> Result<RightsConstants, failure::Error>,
+    pub context_keys: RightsContextKeys,
+    /// Bytes used for seeding of Tezos PRNG for baking rights
+    pub baking_use_string: &'static [u8],
+    /// Bytes used for seeding of Tezos PRNG for endorsing rights
+    pub endorsement_use_string: &'static [u8],
+    pub baking_priority_range: BakingPriorityRange,
+    /// Default for query parameter 'max_priority'
+    pub default_max_priority: i64,
+    /// Default for query parameter 'max_priority', when query parameter 'cycle' is present
+    pub default_max_priority_for_cycle: i64,
+}
+
+impl RightsContextKeys {
+    /// Context keys used by all supported protocols
+    pub(crate) const DEFAULT: RightsContextKeys = RightsContextKeys {
+        cycle: "data/cycle",
+        rolls_owner_snapshot: "data/rolls/owner/snapshot",
+    };
+}
+
+impl RightsDescriptor {
+    /// Tezos PRNG seeding for baking rights used by all supported protocols
+    pub(crate) const BAKING_USE_STRING: &'static [u8] = b"level baking:";
+    /// Tezos PRNG seeding for endorsing rights used by all supported protocols
+    pub(crate) const ENDORSEMENT_USE_STRING: &'static [u8] = b"level endorsement:";
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::Value;
+
+    use crate::services::protocol::{
+        proto_001, proto_002, proto_003, proto_004, proto_005_2, proto_006, proto_007, proto_008,
+        proto_008_2,
+    };
+
+    use super::helpers::{RightsContextData, RightsParams};
+    use super::rights_service::{get_baking_rights, get_endorsing_rights};
+    use super::*;
+
+    /// Vectors were generated by the former per-protocol rights implementations
+    fn check_test_vector(descriptor: &RightsDescriptor, test_vector: &str) {
+        let test_vector: Value = serde_json::from_str(test_vector).expect("invalid test vector");
+        let protocol = test_vector["protocol"].as_str().unwrap();
+
+        let constants_data = hex::decode(test_vector["constants_data"].as_str().unwrap()).unwrap();
+        let constants = (descriptor.parse_constants)(&constants_data)
+            .unwrap_or_else(|e| panic!("{}: failed to parse constants: {}", protocol, e));
+
+        let context_data = &test_vector["context_data"];
+        let rolls: HashMap<i32, String> = context_data["rolls"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|roll| {
+                (
+                    roll[0].as_i64().unwrap() as i32,
+                    roll[1].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        let context_data = RightsContextData::new(
+            hex::decode(context_data["random_seed"].as_str().unwrap()).unwrap(),
+            context_data["last_roll"].as_i64().unwrap() as i32,
+            rolls,
+        );
+        let block = (
+            test_vector["block"]["level"].as_i64().unwrap(),
+            test_vector["block"]["timestamp"].as_i64().unwrap(),
+        );
+
+        for case in test_vector["cases"].as_array().unwrap() {
+            let query = &case["query"];
+            let is_baking = case["rights"] == "baking";
+            let params = RightsParams::parse_rights_parameters(
+                query["level"].as_str(),
+                query["delegate"].as_str(),
+                query["cycle"].as_str(),
+                query["max_priority"].as_str(),
+                query["all"].as_bool().unwrap_or(false),
+                &constants,
+                descriptor,
+                block,
+                is_baking,
+            )
+            .unwrap_or_else(|e| panic!("{}: invalid query {}: {}", protocol, query, e));
+
+            let rights = if is_baking {
+                get_baking_rights(&context_data, &params, &constants, descriptor)
+            } else {
+                get_endorsing_rights(&context_data, &params, &constants, descriptor)
+            }
+            .unwrap_or_else(|e| panic!("{}: failed to get rights: {}", protocol, e));
+
+            assert_eq!(
+                serde_json::to_value(&rights).unwrap(),
+                case["expected"],
+                "{}: {} rights for query {}",
+                protocol,
+                case["rights"],
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn test_rights_vectors() {
+        check_test_vector(
+            &proto_001::RIGHTS_DESCRIPTOR,
+            include_str!("test_vectors/proto_001.json"),
+        );
+        check_test_vector(
+            &proto_002::RIGHTS_DESCRIPTOR,
+            include_str!("test_vectors/proto_002.json"),
+        );
+        check_test_vector(
+            &proto_003::RIGHTS_DESCRIPTOR,
+            include_str!("test_vectors/proto_003.json"),
+        );
+        check_test_vector(
+            &proto_004::RIGHTS_DESCRIPTOR,
+            include_str!("test_vectors/proto_004.json"),
+        );
+        check_test_vector(
+            &proto_005_2::RIGHTS_DESCRIPTOR,
+            include_str!("test_vectors/proto_005_2.json"),
+        );
+        check_test_vector(
+            &proto_006::RIGHTS_DESCRIPTOR,
+            include_str!("test_vectors/proto_006.json"),
+        );
+        check_test_vector(
+            &proto_007::RIGHTS_DESCRIPTOR,
+            include_str!("test_vectors/proto_007.json"),
+        );
+        check_test_vector(
+            &proto_008::RIGHTS_DESCRIPTOR,
+            include_str!("test_vectors/proto_008.json"),
+        );
+        check_test_vector(
+            &proto_008_2::RIGHTS_DESCRIPTOR,
+            include_str!("test_vectors/proto_008_2.json"),
+        );
+    }
+}
@@ -1,15 +1,6 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
-// TODO: (anagy) - check TODO's
-// TODO: refactor errors so this will be removed
-// Enum defining possible response structures for RPC calls
-// there is reason to have this structure because of format of error responses from ocaml node:
-// [{"kind":"permanent","id":"proto.005-PsBabyM1.context.storage_error","missing_key":["cycle","4","random_seed"],"function":"get"}]
-// [{"kind":"permanent","id":"proto.005-PsBabyM1.seed.unknown_seed","oldest":9,"requested":20,"latest":15}]
-// if there have to be same response format then RpcErrorMsg is covering it
-// this enum can be removed if errors are generated from error context directly in result_to_json_response function
-
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 
@@ -20,30 +11,30 @@ use storage::context::TezedgeContext;
 use storage::context_action_storage::contract_id_to_contract_address_for_index;
 use tezos_messages::base::rpc_support::{RpcJsonMap, ToRpcJsonMap};
 use tezos_messages::base::signature_public_key_hash::SignaturePublicKeyHash;
-use tezos_messages::protocol::proto_003::rights::{BakingRights, EndorsingRight};
+use tezos_messages::protocol::rights::{BakingRights, EndorsingRight};
 
-use crate::services::protocol::proto_003::helpers::{
+use crate::services::protocol::rights::helpers::{
     get_prng_number, init_prng, EndorserSlots, RightsConstants, RightsContextData, RightsParams,
 };
+use crate::services::protocol::rights::{BakingPriorityRange, RightsDescriptor};
 use crate::services::protocol::ContextProtocolParam;
 
 /// Return generated baking rights.
 ///
 /// # Arguments
 ///
-/// * `block_id` - Url path parameter 'block_id', it contains string "head", block level or block hash.
 /// * `level` - Url query parameter 'level'.
 /// * `delegate` - Url query parameter 'delegate'.
 /// * `cycle` - Url query parameter 'cycle'.
 /// * `max_priority` - Url query parameter 'max_priority'.
 /// * `has_all` - Url query parameter 'all'.
-/// * `list` - Context list handler.
-/// * `persistent_storage` - Persistent storage handler.
-/// * `state` - Current RPC collected state (head).
+/// * `descriptor` - Differences of the protocol, for which rights are generated.
+/// * `context` - Context handler.
 ///
 /// Prepare all data to generate baking rights and then use Tezos PRNG to generate them.
 pub(crate) fn check_and_get_baking_rights(
     context_proto_params: ContextProtocolParam,
+    descriptor: &RightsDescriptor,
     level: Option<&str>,
     delegate: Option<&str>,
     cycle: Option<&str>,
@@ -52,7 +43,7 @@ pub(crate) fn check_and_get_baking_rights(
     context: &TezedgeContext,
 ) -> Result<Option<Vec<RpcJsonMap>>, failure::Error> {
     let constants: RightsConstants =
-        RightsConstants::parse_rights_constants(&context_proto_params)?;
+        (descriptor.parse_constants)(&context_proto_params.constants_data)?;
 
     let params: RightsParams = RightsParams::parse_rights_parameters(
         level,
@@ -61,17 +52,19 @@ pub(crate) fn check_and_get_baking_rights(
         max_priority,
         has_all,
         &constants,
-        &context_proto_params.block_header,
+        descriptor,
+        block_level_and_timestamp(&context_proto_params),
         true,
     )?;
 
     let context_data: RightsContextData = RightsContextData::prepare_context_data_for_rights(
         params.clone(),
         constants.clone(),
+        &descriptor.context_keys,
         (&context_proto_params.block_header.header.context(), context),
     )?;
 
-    get_baking_rights(&context_data, &params, &constants)
+    get_baking_rights(&context_data, &params, &constants, descriptor)
 }
 
 /// Use prepared data to generate baking rights
@@ -80,12 +73,14 @@ pub(crate) fn check_and_get_baking_rights(
 ///
 /// * `context_data` - Data from context list used in baking and endorsing rights generation filled in [RightsContextData](RightsContextData::prepare_context_data_for_rights).
 /// * `parameters` - Parameters created by [RightsParams](RightsParams::parse_rights_parameters).
-/// * `constants` - Context constants used in baking and endorsing rights [RightsConstants](RightsConstants::parse_rights_constants).
+/// * `constants` - Context constants used in baking and endorsing rights [RightsConstants](RightsDescriptor::parse_constants).
+/// * `descriptor` - Differences of the protocol, for which rights are generated.
 #[inline]
 pub(crate) fn get_baking_rights(
     context_data: &RightsContextData,
     parameters: &RightsParams,
     constants: &RightsConstants,
+    descriptor: &RightsDescriptor,
 ) -> Result<Option<Vec<RpcJsonMap>>, failure::Error> {
     let mut baking_rights = Vec::<BakingRights>::new();
 
@@ -108,6 +103,7 @@ pub(crate) fn get_baking_rights(
             baking_rights_assign_rolls(
                 &parameters,
                 &constants,
+                descriptor,
                 &context_data,
                 level.try_into()?,
                 estimated_timestamp,
@@ -125,6 +121,7 @@ pub(crate) fn get_baking_rights(
         baking_rights_assign_rolls(
             &parameters,
             &constants,
+            descriptor,
             &context_data,
             level.try_into()?,
             estimated_timestamp,
@@ -158,7 +155,8 @@ pub(crate) fn get_baking_rights(
 /// # Arguments
 ///
 /// * `parameters` - Parameters created by [RightsParams](RightsParams::parse_rights_parameters).
-/// * `constants` - Context constants used in baking and endorsing rights [RightsConstants](RightsConstants::parse_rights_constants).
+/// * `constants` - Context constants used in baking and endorsing rights [RightsConstants](RightsDescriptor::parse_constants).
+/// * `descriptor` - Differences of the protocol, for which rights are generated.
 /// * `context_data` - Data from context list used in baking and endorsing rights generation filled in [RightsContextData](RightsContextData::prepare_context_data_for_rights).
 /// * `level` - Level to feed Tezos PRNG.
 /// * `estimated_head_timestamp` - Estimated time of baking, is set to None if in past relative to block_id.
@@ -168,14 +166,13 @@ pub(crate) fn get_baking_rights(
 fn baking_rights_assign_rolls(
     parameters: &RightsParams,
     constants: &RightsConstants,
+    descriptor: &RightsDescriptor,
     context_data: &RightsContextData,
     level: i32,
     estimated_head_timestamp: i64,
     is_cycle: bool,
     baking_rights: &mut Vec<BakingRights>,
 ) -> Result<(), failure::Error> {
-    const BAKING_USE_STRING: &[u8] = b"level baking:";
-
     // hashset is defined to keep track of the delegates with priorities already assigned
     let mut assigned = HashSet::new();
 
@@ -188,14 +185,19 @@ fn baking_rights_assign_rolls(
     let rolls_map = context_data.rolls();
     let display_level: i32 = (*parameters.display_level()).try_into()?;
 
-    for priority in 0..max_priority {
+    let priorities = match descriptor.baking_priority_range {
+        BakingPriorityRange::Exclusive => 0..max_priority,
+        BakingPriorityRange::Inclusive => 0..max_priority + 1,
+    };
+
+    for priority in priorities {
         // draw the rolls for the requested parameters
         let delegate_to_assign;
         // TODO: priority can overflow in the ocaml code, do a priority % i32::max_value()
         let mut state = init_prng(
             &context_data,
             &constants,
-            BAKING_USE_STRING,
+            descriptor.baking_use_string,
             level,
             priority.try_into()?,
         )?;
@@ -246,18 +248,17 @@ fn baking_rights_assign_rolls(
 ///
 /// # Arguments
 ///
-/// * `block_id` - Url path parameter 'block_id', it contains string "head", block level or block hash.
 /// * `level` - Url query parameter 'level'.
 /// * `delegate` - Url query parameter 'delegate'.
 /// * `cycle` - Url query parameter 'cycle'.
 /// * `has_all` - Url query parameter 'all'.
-/// * `list` - Context list handler.
-/// * `persistent_storage` - Persistent storage handler.
-/// * `state` - Current RPC collected state (head).
+/// * `descriptor` - Differences of the protocol, for which rights are generated.
+/// * `context` - Context handler.
 ///
 /// Prepare all data to generate endorsing rights and then use Tezos PRNG to generate them.
 pub(crate) fn check_and_get_endorsing_rights(
     context_proto_params: ContextProtocolParam,
+    descriptor: &RightsDescriptor,
     level: Option<&str>,
     delegate: Option<&str>,
     cycle: Option<&str>,
@@ -265,7 +266,7 @@ pub(crate) fn check_and_get_endorsing_rights(
     context: &TezedgeContext,
 ) -> Result<Option<Vec<RpcJsonMap>>, failure::Error> {
     let constants: RightsConstants =
-        RightsConstants::parse_rights_constants(&context_proto_params)?;
+        (descriptor.parse_constants)(&context_proto_params.constants_data)?;
 
     let params: RightsParams = RightsParams::parse_rights_parameters(
         level,
@@ -274,17 +275,19 @@ pub(crate) fn check_and_get_endorsing_rights(
         None,
         has_all,
         &constants,
-        &context_proto_params.block_header,
+        descriptor,
+        block_level_and_timestamp(&context_proto_params),
         false,
     )?;
 
     let context_data: RightsContextData = RightsContextData::prepare_context_data_for_rights(
         params.clone(),
         constants.clone(),
+        &descriptor.context_keys,
         (&context_proto_params.block_header.header.context(), context),
     )?;
 
-    get_endorsing_rights(&context_data, &params, &constants)
+    get_endorsing_rights(&context_data, &params, &constants, descriptor)
 }
 
 /// Use prepared data to generate endosring rights
@@ -293,11 +296,13 @@ pub(crate) fn check_and_get_endorsing_rights(
 ///
 /// * `context_data` - Data from context list used in baking and endorsing rights generation filled in [RightsContextData](RightsContextData::prepare_context_data_for_rights).
 /// * `parameters` - Parameters created by [RightsParams](RightsParams::parse_rights_parameters).
-/// * `constants` - Context constants used in baking and endorsing rights [RightsConstants](RightsConstants::parse_rights_constants).
-fn get_endorsing_rights(
+/// * `constants` - Context constants used in baking and endorsing rights [RightsConstants](RightsDescriptor::parse_constants).
+/// * `descriptor` - Differences of the protocol, for which rights are generated.
+pub(crate) fn get_endorsing_rights(
     context_data: &RightsContextData,
     parameters: &RightsParams,
     constants: &RightsConstants,
+    descriptor: &RightsDescriptor,
 ) -> Result<Option<Vec<RpcJsonMap>>, failure::Error> {
     // define helper and output variables
     let mut endorsing_rights = Vec::<EndorsingRight>::new();
@@ -317,6 +322,7 @@ fn get_endorsing_rights(
                 context_data,
                 parameters,
                 constants,
+                descriptor,
                 level,
                 level,
                 estimated_time,
@@ -332,6 +338,7 @@ fn get_endorsing_rights(
             context_data,
             parameters,
             constants,
+            descriptor,
             *parameters.requested_level(),
             *parameters.display_level(),
             estimated_time,
@@ -353,7 +360,8 @@ fn get_endorsing_rights(
 ///
 /// * `context_data` - Data from context list used in baking and endorsing rights generation filled in [RightsContextData](RightsContextData::prepare_context_data_for_rights).
 /// * `parameters` - Parameters created by [RightsParams](RightsParams::parse_rights_parameters).
-/// * `constants` - Context constants used in baking and endorsing rights [RightsConstants](RightsConstants::parse_rights_constants).
+/// * `constants` - Context constants used in baking and endorsing rights [RightsConstants](RightsDescriptor::parse_constants).
+/// * `descriptor` - Differences of the protocol, for which rights are generated.
 /// * `level` - Level to feed Tezos PRNG.
 /// * `display_level` - Level to be displayed in output.
 /// * `estimated_time` - Estimated time of endorsement, is set to None if in past relative to block_id.
@@ -362,13 +370,14 @@ fn complete_endorsing_rights_for_level(
     context_data: &RightsContextData,
     parameters: &RightsParams,
     constants: &RightsConstants,
+    descriptor: &RightsDescriptor,
     level: i64,
     display_level: i64,
     estimated_time: Option<i64>,
     endorsing_rights: &mut Vec<EndorsingRight>,
 ) -> Result<(), failure::Error> {
     // endorsers_slots is needed to group all slots by delegate
-    let endorsers_slots = get_endorsers_slots(constants, context_data, level)?;
+    let endorsers_slots = get_endorsers_slots(constants, descriptor, context_data, level)?;
 
     // convert contract id to hash contract address hex byte string (needed for ordering)
     let mut endorers_slots_keys_for_order: HashMap<String, String> = HashMap::new();
@@ -412,17 +421,17 @@ fn complete_endorsing_rights_for_level(
 ///
 /// # Arguments
 ///
-/// * `constants` - Context constants used in baking and endorsing rights [RightsConstants](RightsConstants::parse_rights_constants).
+/// * `constants` - Context constants used in baking and endorsing rights [RightsConstants](RightsDescriptor::parse_constants).
+/// * `descriptor` - Differences of the protocol, for which rights are generated.
 /// * `context_data` - Data from context list used in baking and endorsing rights generation filled in [RightsContextData](RightsContextData::prepare_context_data_for_rights).
 /// * `level` - Level to feed Tezos PRNG.
 #[inline]
 fn get_endorsers_slots(
     constants: &RightsConstants,
+    descriptor: &RightsDescriptor,
     context_data: &RightsContextData,
     level: i64,
 ) -> Result<HashMap<String, EndorserSlots>, failure::Error> {
-    // special byte string used in Tezos PRNG
-    const ENDORSEMENT_USE_STRING: &[u8] = b"level endorsement:";
     // prepare helper variable
     let mut endorsers_slots: HashMap<String, EndorserSlots> = HashMap::new();
 
@@ -432,7 +441,7 @@ fn get_endorsers_slots(
         let mut state = init_prng(
             &context_data,
             &constants,
-            ENDORSEMENT_USE_STRING,
+            descriptor.endorsement_use_string,
             level.try_into()?,
             endorser_slot.try_into()?,
         )?;
@@ -453,3 +462,9 @@ fn get_endorsers_slots(
     }
     Ok(endorsers_slots)
 }
+
+/// Returns level and timestamp of the block, for which rights are requested
+fn block_level_and_timestamp(context_proto_params: &ContextProtocolParam) -> (i64, i64) {
+    let header = &context_proto_params.block_header.header;
+    (header.level().into(), header.timestamp())
+}
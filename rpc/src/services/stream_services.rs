@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: MIT
 use std::collections::{HashMap, HashSet};
 use std::pin::Pin;
+use std::sync::Arc;
 
 use failure::format_err;
 use futures::task::{Context, Poll};
@@ -9,23 +10,37 @@ use futures::Stream;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use slog::{warn, Logger};
+use tokio::sync::broadcast;
 use tokio::time::{interval_at, Interval};
 use tokio::time::{Duration, Instant};
 
-use crypto::hash::{BlockHash, ChainId, ProtocolHash};
+use crypto::hash::{chain_id_to_b58_string, BlockHash, ChainId, ProtocolHash};
 use shell::mempool::CurrentMempoolStateStorageRef;
+use shell::state::synchronization_state::SynchronizationBootstrapStateRef;
 use storage::persistent::PersistentStorage;
 use storage::{BlockHeaderWithHash, BlockStorage, BlockStorageReader};
+use tezos_messages::protocol::receipts::decode_block_header_metadata;
+use tezos_messages::ts_to_rfc3339;
 
-use crate::helpers::{BlockHeaderInfo, FullBlockInfo};
+use crate::encoding::base_types::TimeStamp;
+use crate::encoding::monitor::BootstrapInfo;
+use crate::helpers::BlockHeaderInfo;
 use crate::rpc_actor::RpcCollectedStateRef;
 use crate::services::mempool_services::get_pending_operations;
 
 pub const MONITOR_TIMER_MILIS: u64 = 100;
 
+/// How many blocks can be queued for one monitor client, if the client falls behind more, its stream is closed
+pub const MONITOR_BLOCKS_CAPACITY: usize = 1024;
+
+/// Node can become bootstrapped also without new head, so bootstrapped monitor checks status at least with this interval
+pub const MONITOR_BOOTSTRAPPED_CHECK_MILIS: u64 = 1000;
+
 /// Object containing information to recreate the block header shell information
 #[derive(Serialize, Debug, Clone)]
 struct BlockHeaderMonitorInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<String>,
     pub hash: String,
     pub level: i32,
     pub proto: u8,
@@ -41,6 +56,7 @@ struct BlockHeaderMonitorInfo {
 impl From<(&BlockHeaderInfo, &BlockHeaderWithHash)> for BlockHeaderMonitorInfo {
     fn from((block_header_info, block): (&BlockHeaderInfo, &BlockHeaderWithHash)) -> Self {
         BlockHeaderMonitorInfo {
+            chain_id: None,
             hash: block_header_info.hash.clone(),
             level: block_header_info.level,
             proto: block_header_info.proto,
@@ -77,16 +93,6 @@ pub struct MonitoredOperation {
     error: Option<Value>,
 }
 
-pub struct HeadMonitorStream {
-    block_storage: BlockStorage,
-
-    chain_id: ChainId,
-    state: RpcCollectedStateRef,
-    last_checked_head: Option<BlockHash>,
-    delay: Option<Interval>,
-    protocol: Option<ProtocolHash>,
-}
-
 pub struct OperationMonitorStream {
     chain_id: ChainId,
     current_mempool_state_storage: CurrentMempoolStateStorageRef,
@@ -225,142 +231,338 @@ impl OperationMonitorStream {
     }
 }
 
-impl HeadMonitorStream {
-    pub fn new(
+/// Block published to monitor streams, together with the chain it was validated for
+pub type MonitoredBlock = (Arc<ChainId>, Arc<BlockHeaderWithHash>);
+
+/// Thread safe reference to shared monitor channels
+pub type BlockMonitorChannelsRef = Arc<BlockMonitorChannels>;
+
+/// Fan-out of validated blocks and new current heads from shell channel to all connected monitor streams.
+///
+/// Publishing never blocks, every client has its own bounded queue, so a slow client cannot stall the shell channel.
+pub struct BlockMonitorChannels {
+    valid_blocks: broadcast::Sender<MonitoredBlock>,
+    new_heads: broadcast::Sender<MonitoredBlock>,
+}
+
+impl BlockMonitorChannels {
+    pub fn new(capacity: usize) -> Self {
+        let (valid_blocks, _) = broadcast::channel(capacity);
+        let (new_heads, _) = broadcast::channel(capacity);
+        Self {
+            valid_blocks,
+            new_heads,
+        }
+    }
+
+    pub fn publish_valid_block(&self, chain_id: Arc<ChainId>, block: Arc<BlockHeaderWithHash>) {
+        // error here just means, that there is no connected client
+        let _ = self.valid_blocks.send((chain_id, block));
+    }
+
+    pub fn publish_new_head(&self, chain_id: Arc<ChainId>, block: Arc<BlockHeaderWithHash>) {
+        // error here just means, that there is no connected client
+        let _ = self.new_heads.send((chain_id, block));
+    }
+}
+
+/// Octez `protocol` and `next_protocol` query filters, empty list means no filtering
+#[derive(Clone, Debug, Default)]
+pub struct BlockMonitorQuery {
+    pub protocols: Vec<ProtocolHash>,
+    pub next_protocols: Vec<ProtocolHash>,
+}
+
+impl BlockMonitorQuery {
+    fn is_empty(&self) -> bool {
+        self.protocols.is_empty() && self.next_protocols.is_empty()
+    }
+
+    fn matches(&self, protocol: &ProtocolHash, next_protocol: &ProtocolHash) -> bool {
+        (self.protocols.is_empty() || self.protocols.contains(protocol))
+            && (self.next_protocols.is_empty() || self.next_protocols.contains(next_protocol))
+    }
+}
+
+/// Streams blocks published to [BlockMonitorChannels] in the order, in which they were validated.
+///
+/// If the client cannot keep up and misses more than [MONITOR_BLOCKS_CAPACITY] blocks, the stream is closed,
+/// so the client never silently skips any block.
+pub struct BlockMonitorStream {
+    inner: Pin<Box<dyn Stream<Item = Result<String, failure::Error>> + Send>>,
+}
+
+struct BlockMonitorState {
+    block_storage: BlockStorage,
+    receiver: broadcast::Receiver<MonitoredBlock>,
+    query: BlockMonitorQuery,
+    /// If set, only blocks of this chain are streamed and chain_id is not part of the output
+    chain_id: Option<ChainId>,
+    /// Block yielded before any received block (current head for heads monitor)
+    initial_block: Option<MonitoredBlock>,
+    last_yielded_block: Option<BlockHash>,
+    log: Logger,
+}
+
+impl BlockMonitorStream {
+    /// Stream for `/monitor/valid_blocks` - every successfully validated block of every chain
+    pub fn valid_blocks(
+        channels: &BlockMonitorChannels,
+        query: BlockMonitorQuery,
+        persistent_storage: &PersistentStorage,
+        log: Logger,
+    ) -> Self {
+        Self::new(BlockMonitorState {
+            block_storage: BlockStorage::new(persistent_storage),
+            receiver: channels.valid_blocks.subscribe(),
+            query,
+            chain_id: None,
+            initial_block: None,
+            last_yielded_block: None,
+            log,
+        })
+    }
+
+    /// Stream for `/monitor/heads/:chain_id` - starts with the current head, then every new current head
+    pub fn new_heads(
+        channels: &BlockMonitorChannels,
         chain_id: ChainId,
-        state: RpcCollectedStateRef,
-        protocol: Option<ProtocolHash>,
+        main_chain_id: &ChainId,
+        state: &RpcCollectedStateRef,
+        query: BlockMonitorQuery,
         persistent_storage: &PersistentStorage,
+        log: Logger,
     ) -> Self {
-        Self {
-            chain_id,
-            state,
-            protocol,
-            last_checked_head: None,
-            delay: None,
+        // subscribe before reading current head, so we cannot miss any head change in between
+        let receiver = channels.new_heads.subscribe();
+        // collected current head belongs to the main chain
+        let initial_block = if &chain_id == main_chain_id {
+            state
+                .read()
+                .unwrap()
+                .current_head()
+                .clone()
+                .map(|current_head| (Arc::new(chain_id.clone()), current_head))
+        } else {
+            None
+        };
+
+        Self::new(BlockMonitorState {
             block_storage: BlockStorage::new(persistent_storage),
+            receiver,
+            query,
+            chain_id: Some(chain_id),
+            initial_block,
+            last_yielded_block: None,
+            log,
+        })
+    }
+
+    fn new(state: BlockMonitorState) -> Self {
+        Self {
+            inner: Box::pin(futures::stream::unfold(state, |state| state.next_block())),
         }
     }
+}
+
+impl BlockMonitorState {
+    async fn next_block(mut self) -> Option<(Result<String, failure::Error>, Self)> {
+        // Note: the stream only ends on the client dropping the connection or falling behind
+        loop {
+            let (chain_id, block) = match self.initial_block.take() {
+                Some(initial_block) => initial_block,
+                None => match self.receiver.recv().await {
+                    Ok(block) => block,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(self.log, "Closing block monitor stream, client is too slow"; "skipped_blocks" => skipped);
+                        return None;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+            };
+
+            if let Some(expected_chain_id) = &self.chain_id {
+                if expected_chain_id != chain_id.as_ref() {
+                    continue;
+                }
+            }
+            if self.last_yielded_block.as_ref() == Some(&block.hash) {
+                // current head could be received once more, if changed during subscription
+                continue;
+            }
 
-    fn yield_head(
+            match self.yield_block(&chain_id, &block) {
+                Ok(Some(block_string)) => {
+                    self.last_yielded_block = Some(block.hash.clone());
+                    return Some((Ok(block_string), self));
+                }
+                // filtered out by query
+                Ok(None) => continue,
+                Err(e) => return Some((Err(e), self)),
+            }
+        }
+    }
+
+    fn yield_block(
         &self,
-        current_head: &BlockHeaderWithHash,
+        chain_id: &ChainId,
+        block: &BlockHeaderWithHash,
     ) -> Result<Option<String>, failure::Error> {
-        let HeadMonitorStream {
-            chain_id, protocol, ..
-        } = self;
-
-        let block_json_data = match self.block_storage.get_with_json_data(&current_head.hash)? {
+        let block_json_data = match self.block_storage.get_with_json_data(&block.hash)? {
             Some((_, block_json_data)) => block_json_data,
             None => {
                 return Err(format_err!(
                     "Missing block json data for block_hash: {}",
-                    current_head.hash.to_base58_check(),
+                    block.hash.to_base58_check(),
                 ));
             }
         };
 
-        let current_head_header = BlockHeaderMonitorInfo::from((
-            &BlockHeaderInfo::new(&current_head, &block_json_data, chain_id),
-            current_head,
-        ));
-
-        if let Some(protocol) = &protocol {
-            let block_info = { FullBlockInfo::new(&current_head, &block_json_data, chain_id) };
-            let block_next_protocol = block_info.metadata["next_protocol"]
-                .to_string()
-                .replace("\"", "");
-            if &ProtocolHash::from_base58_check(&block_next_protocol)? != protocol {
+        if !self.query.is_empty() {
+            let metadata =
+                decode_block_header_metadata(block_json_data.block_header_proto_metadata_json())?;
+            let protocol =
+                ProtocolHash::from_base58_check(metadata.protocol.as_deref().unwrap_or_default())?;
+            let next_protocol = ProtocolHash::from_base58_check(
+                metadata.next_protocol.as_deref().unwrap_or_default(),
+            )?;
+            if !self.query.matches(&protocol, &next_protocol) {
                 return Ok(None);
             }
         }
 
+        let mut block_header = BlockHeaderMonitorInfo::from((
+            &BlockHeaderInfo::new(block, &block_json_data, chain_id),
+            block,
+        ));
+        if self.chain_id.is_none() {
+            block_header.chain_id = Some(chain_id_to_b58_string(chain_id));
+        }
+
         // serialize the struct to a json string to yield by the stream
-        let mut head_string = serde_json::to_string(&current_head_header)?;
+        let mut block_string = serde_json::to_string(&block_header)?;
 
         // push a newline character to the stream
-        head_string.push('\n');
+        block_string.push('\n');
 
-        Ok(Some(head_string))
+        Ok(Some(block_string))
     }
 }
 
-impl Stream for HeadMonitorStream {
-    type Item = Result<String, failure::Error>;
-
-    fn poll_next(
-        mut self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-    ) -> Poll<Option<Result<String, failure::Error>>> {
-        // Note: the stream only ends on the client dropping the connection
-
-        // create or get a delay future, that blocks for MONITOR_TIMER_MILIS
-        let delay = self.delay.get_or_insert_with(|| {
-            interval_at(Instant::now(), Duration::from_millis(MONITOR_TIMER_MILIS))
-        });
+/// Stream for `/monitor/bootstrapped` - streams current head and every new head, until node is bootstrapped
+pub struct BootstrappedMonitorStream {
+    inner: Pin<Box<dyn Stream<Item = Result<String, failure::Error>> + Send>>,
+}
 
-        // poll the delay future
-        match delay.poll_tick(cx) {
-            Poll::Pending => Poll::Pending,
-            _ => {
-                // get rid of the used delay
-                self.delay = None;
+struct BootstrappedMonitorState {
+    receiver: broadcast::Receiver<MonitoredBlock>,
+    chain_id: ChainId,
+    synchronization_state: SynchronizationBootstrapStateRef,
+    /// Current head, which is yielded as the first one
+    initial_block: Option<Arc<BlockHeaderWithHash>>,
+    finished: bool,
+}
 
-                let state = self.state.read().unwrap();
-                let current_head = state.current_head().clone();
+impl BootstrappedMonitorStream {
+    pub fn new(
+        channels: &BlockMonitorChannels,
+        main_chain_id: &ChainId,
+        state: &RpcCollectedStateRef,
+        synchronization_state: SynchronizationBootstrapStateRef,
+    ) -> Self {
+        // subscribe before reading current head, so we cannot miss any head change in between
+        let receiver = channels.new_heads.subscribe();
+        let initial_block = state.read().unwrap().current_head().clone();
+
+        let state = BootstrappedMonitorState {
+            receiver,
+            chain_id: main_chain_id.clone(),
+            synchronization_state,
+            initial_block,
+            finished: false,
+        };
+        Self {
+            inner: Box::pin(futures::stream::unfold(state, |state| state.next_head())),
+        }
+    }
+}
 
-                // drop the immutable borrow so we can borrow self again as mutable
-                // TODO: refactor this drop (remove if possible)
-                drop(state);
+impl BootstrappedMonitorState {
+    async fn next_head(mut self) -> Option<(Result<String, failure::Error>, Self)> {
+        if self.finished {
+            return None;
+        }
 
-                // if last_checked_head is None, this is the first poll, yield the current_head
-                let last_checked_head = if let Some(head_hash) = &self.last_checked_head {
-                    head_hash
-                } else {
-                    // first poll
-                    if let Some(current_head) = current_head {
-                        self.last_checked_head = Some(current_head.hash.clone());
-                        // If there is no head with the desired protocol, [yield_head] returns Ok(None) which is transposed to None, meaning we
-                        // would end the stream, in this case, we need to Pend.
-                        if let Some(head_string_result) = self.yield_head(&current_head).transpose()
-                        {
-                            return Poll::Ready(Some(head_string_result));
-                        } else {
-                            cx.waker().wake_by_ref();
-                            return Poll::Pending;
-                        };
-                    } else {
-                        // No current head found, storage not ready yet
-                        cx.waker().wake_by_ref();
-                        return Poll::Pending;
+        let block = match self.initial_block.take() {
+            Some(block) => block,
+            None => loop {
+                match tokio::time::timeout(
+                    Duration::from_millis(MONITOR_BOOTSTRAPPED_CHECK_MILIS),
+                    self.receiver.recv(),
+                )
+                .await
+                {
+                    Ok(Ok((chain_id, block))) => {
+                        if chain_id.as_ref() == &self.chain_id {
+                            break block;
+                        }
                     }
-                };
-
-                if let Some(current_head) = current_head {
-                    if last_checked_head == &current_head.hash {
-                        // current head not changed, yield nothing
-                        cx.waker().wake_by_ref();
-                        Poll::Pending
-                    } else {
-                        // Head change, yield new head
-                        self.last_checked_head = Some(current_head.hash.clone());
-                        // If there is no head with the desired protocol, [yield_head] returns Ok(None) which is transposed to None, meaning we
-                        // would end the stream, in this case, we need to Pend.
-                        if let Some(head_string_result) = self.yield_head(&current_head).transpose()
-                        {
-                            Poll::Ready(Some(head_string_result))
-                        } else {
-                            cx.waker().wake_by_ref();
-                            Poll::Pending
+                    // here we need just the latest heads, so skipped ones does not matter
+                    Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                    Ok(Err(broadcast::error::RecvError::Closed)) => return None,
+                    Err(_) => {
+                        // no new head, but we could be bootstrapped by peers in the meantime
+                        if self.is_bootstrapped() {
+                            return None;
                         }
                     }
-                } else {
-                    // No current head found, storage not ready yet, wait
-                    cx.waker().wake_by_ref();
-                    Poll::Pending
                 }
-            }
-        }
+            },
+        };
+
+        // the last streamed head is the one, on which we are bootstrapped
+        self.finished = self.is_bootstrapped();
+
+        let bootstrap_info = BootstrapInfo::new(
+            &block.hash,
+            TimeStamp::Rfc(ts_to_rfc3339(block.header.timestamp())),
+        );
+        let result = serde_json::to_string(&bootstrap_info)
+            .map(|mut info_string| {
+                info_string.push('\n');
+                info_string
+            })
+            .map_err(failure::Error::from);
+        Some((result, self))
+    }
+
+    fn is_bootstrapped(&self) -> bool {
+        self.synchronization_state
+            .read()
+            .map(|synchronization_state| synchronization_state.is_bootstrapped())
+            .unwrap_or(false)
+    }
+}
+
+impl Stream for BootstrappedMonitorStream {
+    type Item = Result<String, failure::Error>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<String, failure::Error>>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl Stream for BlockMonitorStream {
+    type Item = Result<String, failure::Error>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<String, failure::Error>>> {
+        self.inner.as_mut().poll_next(cx)
     }
 }
 
@@ -416,3 +618,38 @@ impl Stream for OperationMonitorStream {
 }
 
 // TODO: add tests for both Streams!
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_monitor_query_matches() -> Result<(), failure::Error> {
+        let proto_a =
+            ProtocolHash::from_base58_check("PsCARTHAGazKbHtnKfLzQg3kms52kSRpgnDY982a9oYsSXRLQEb")?;
+        let proto_b =
+            ProtocolHash::from_base58_check("PsDELPH1Kxsxt8f9eWbxQeRxkjfbxoqM52jvs5Y5fBxWWh4ifpo")?;
+
+        let query = BlockMonitorQuery::default();
+        assert!(query.is_empty());
+        assert!(query.matches(&proto_a, &proto_b));
+
+        let query = BlockMonitorQuery {
+            protocols: vec![],
+            next_protocols: vec![proto_b.clone()],
+        };
+        assert!(!query.is_empty());
+        assert!(query.matches(&proto_a, &proto_b));
+        assert!(!query.matches(&proto_b, &proto_a));
+
+        let query = BlockMonitorQuery {
+            protocols: vec![proto_a.clone(), proto_b.clone()],
+            next_protocols: vec![proto_b.clone()],
+        };
+        assert!(query.matches(&proto_a, &proto_b));
+        assert!(query.matches(&proto_b, &proto_b));
+        assert!(!query.matches(&proto_a, &proto_a));
+
+        Ok(())
+    }
+}
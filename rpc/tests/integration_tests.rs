@@ -36,6 +36,109 @@ async fn test_rpc_compare() {
     integration_tests_rpc(from_block_header(), to_block_header()).await
 }
 
+#[ignore]
+#[tokio::test]
+async fn test_rpc_compare_contracts() {
+    integration_tests_contracts_rpc(from_block_header(), to_block_header()).await
+}
+
+/// Compares contracts and delegates rpcs (which are served natively by Tezedge for supported protocols),
+/// checks the last level of every cycle and `to_block`
+async fn integration_tests_contracts_rpc(from_block: i64, to_block: i64) {
+    assert!(
+        from_block < to_block,
+        "from_block({}) should be smaller then to_block({})",
+        from_block,
+        to_block
+    );
+
+    for level in std::cmp::max(1, from_block)..to_block + 1 {
+        let constants_json = try_get_data_as_json(&format!(
+            "{}/{}/{}",
+            "chains/main/blocks", level, "context/constants"
+        ))
+        .await
+        .expect("Failed to get constants");
+        let blocks_per_cycle = constants_json["blocks_per_cycle"]
+            .as_i64()
+            .unwrap_or_else(|| panic!("No constant 'blocks_per_cycle' for block_id: {}", level));
+        if level != to_block && level % blocks_per_cycle != 0 {
+            continue;
+        }
+
+        let delegates = try_get_data_as_json(&format!(
+            "{}/{}/{}",
+            "chains/main/blocks", level, "context/delegates"
+        ))
+        .await
+        .expect("Failed to get delegates");
+        let delegates = delegates.as_array().expect("No delegates data");
+        println!(
+            "run contracts tests, level: {}, delegates: {}",
+            level,
+            delegates.len()
+        );
+
+        for delegate in delegates {
+            let delegate = delegate
+                .as_str()
+                .unwrap_or_else(|| panic!("Invalid delegate: {}", delegate));
+
+            test_rpc_compare_json(&format!(
+                "{}/{}/{}/{}",
+                "chains/main/blocks", level, "context/delegates", delegate
+            ))
+            .await
+            .expect("test failed");
+            for field in &["staking_balance", "frozen_balance_by_cycle", "grace_period"] {
+                test_rpc_compare_json(&format!(
+                    "{}/{}/{}/{}/{}",
+                    "chains/main/blocks", level, "context/delegates", delegate, field
+                ))
+                .await
+                .expect("test failed");
+            }
+            for field in &["balance", "counter", "manager_key", "delegate"] {
+                test_rpc_compare_json(&format!(
+                    "{}/{}/{}/{}/{}",
+                    "chains/main/blocks", level, "context/contracts", delegate, field
+                ))
+                .await
+                .expect("test failed");
+            }
+
+            // originated contracts have only balance and delegate
+            let delegated_contracts = try_get_data_as_json(&format!(
+                "{}/{}/{}/{}/{}",
+                "chains/main/blocks", level, "context/delegates", delegate, "delegated_contracts"
+            ))
+            .await
+            .expect("Failed to get delegated contracts");
+            for contract in delegated_contracts
+                .as_array()
+                .expect("No delegated contracts")
+            {
+                let contract = contract
+                    .as_str()
+                    .unwrap_or_else(|| panic!("Invalid contract: {}", contract));
+                let fields: &[&str] = if contract.starts_with("KT1") {
+                    &["balance", "delegate"]
+                } else {
+                    &["balance", "counter", "manager_key", "delegate"]
+                };
+                for field in fields {
+                    test_rpc_compare_json(&format!(
+                        "{}/{}/{}/{}/{}",
+                        "chains/main/blocks", level, "context/contracts", contract, field
+                    ))
+                    .await
+                    .expect("test failed");
+                }
+            }
+        }
+    }
+}
+
 async fn integration_tests_rpc(from_block: i64, to_block: i64) {
     assert!(
         from_block < to_block,
@@ -102,6 +205,66 @@ async fn integration_tests_rpc(from_block: i64, to_block: i64) {
         ))
         .await
         .expect("test failed");
+        test_rpc_compare_json(&format!(
+            "{}/{}/{}",
+            "chains/main/blocks", level, "operations"
+        ))
+        .await
+        .expect("test failed");
+        test_rpc_compare_json(&format!(
+            "{}/{}/{}",
+            "chains/main/blocks", level, "operations?metadata=always"
+        ))
+        .await
+        .expect("test failed");
+        test_rpc_compare_json(&format!(
+            "{}/{}/{}",
+            "chains/main/blocks", level, "operations?metadata=never"
+        ))
+        .await
+        .expect("test failed");
+
+        // offsets are taken from operation hashes, because out of range offsets are not json responses
+        let operation_hashes = try_get_data_as_json(&format!(
+            "{}/{}/{}",
+            "chains/main/blocks", level, "operation_hashes"
+        ))
+        .await
+        .expect("Failed to get block operation hashes");
+        for (list_offset, validation_pass) in operation_hashes
+            .as_array()
+            .expect("operation_hashes should be array")
+            .iter()
+            .enumerate()
+        {
+            test_rpc_compare_json(&format!(
+                "{}/{}/{}/{}",
+                "chains/main/blocks", level, "operations", list_offset
+            ))
+            .await
+            .expect("test failed");
+
+            let operations_count = validation_pass
+                .as_array()
+                .expect("validation pass should be array")
+                .len();
+            if operations_count > 0 {
+                for operation_offset in &[0, operations_count - 1] {
+                    test_rpc_compare_json(&format!(
+                        "{}/{}/{}/{}/{}",
+                        "chains/main/blocks", level, "operations", list_offset, operation_offset
+                    ))
+                    .await
+                    .expect("test failed");
+                    test_rpc_compare_json(&format!(
+                        "{}/{}/{}/{}/{}?metadata=never",
+                        "chains/main/blocks", level, "operations", list_offset, operation_offset
+                    ))
+                    .await
+                    .expect("test failed");
+                }
+            }
+        }
         test_rpc_compare_json(&format!(
             "{}/{}/{}",
             "chains/main/blocks", level, "context/raw/bytes/cycle"
@@ -18,6 +18,7 @@ pub struct LauncherEnvironment {
     pub log_level: slog::Level,
     pub sandbox_rpc_port: u16,
     pub tezos_client_path: PathBuf,
+    pub signer_path: Option<PathBuf>,
     pub zcash_param: ZcashParams,
 }
 
@@ -83,6 +84,20 @@ fn sandbox_app() -> App<'static, 'static> {
                     }
                 }),
         )
+        .arg(
+            Arg::with_name("signer-path")
+                .long("signer-path")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Path to the signer binary, if set, the wallets secret keys are held by the signer and the tezos-client uses them as remote keys")
+                .validator(|v| {
+                    if Path::new(&v).exists() {
+                        Ok(())
+                    } else {
+                        Err(format!("Signer binary not found at '{}'", v))
+                    }
+                }),
+        )
         .arg(
             Arg::with_name("log-level")
                 .long("log-level")
@@ -152,6 +167,7 @@ impl LauncherEnvironment {
                 .unwrap_or("")
                 .parse::<PathBuf>()
                 .expect("Provided value cannot be converted to path"),
+            signer_path: args.value_of("signer-path").map(PathBuf::from),
             zcash_param: ZcashParams {
                 init_sapling_spend_params_file: args
                     .value_of("init-sapling-spend-params-file")
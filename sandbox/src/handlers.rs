@@ -299,6 +299,7 @@ pub async fn handle_rejection(err: Rejection, log: Logger) -> Result<impl Reply,
                 | TezosClientRunnerError::UnavailableSandboxNodeError
                 | TezosClientRunnerError::IOError { .. }
                 | TezosClientRunnerError::SandboxDataDirNotInitialized { .. }
+                | TezosClientRunnerError::SignerError { .. }
                 | TezosClientRunnerError::SerdeError { .. } => {
                     let message = format!("{}", tcre);
                     error!(log, "Rpc handle error (tezos-client)"; "message" => message.clone());
@@ -49,6 +49,7 @@ async fn main() {
     let client_runner = Arc::new(RwLock::new(tezos_client_runner::TezosClientRunner::new(
         "tezos-client",
         env.tezos_client_path,
+        env.signer_path,
     )));
 
     // the port to open the rpc server on
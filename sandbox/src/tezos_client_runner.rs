@@ -4,9 +4,12 @@
 use std::collections::HashMap;
 use std::fs;
 use std::io::{BufReader, Read};
-use std::path::PathBuf;
-use std::process::Command;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
 use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use failure::Fail;
 use itertools::Itertools;
@@ -53,6 +56,10 @@ pub enum TezosClientRunnerError {
         node_ref
     )]
     SandboxDataDirNotInitialized { node_ref: NodeRpcIpPort },
+
+    /// Signer failed to import keys or to start
+    #[fail(display = "Signer error, reason: {}", reason)]
+    SignerError { reason: String },
 }
 
 impl From<std::io::Error> for TezosClientRunnerError {
@@ -95,20 +102,113 @@ pub struct TezosClientErrorReply {
 }
 
 /// Like wallets we need to store per node, because, if we run multiple nodes, we can have different wallet setting per node
-#[derive(Clone)]
 pub struct SandboxData {
     pub data_dir_path: PathBuf,
     pub wallets: HashMap<String, Wallet>,
+    /// Signer holding the wallets secret keys, if the sandbox runs with `--signer-path`
+    signer: Option<SandboxSigner>,
+}
+
+/// Running signer process, which signs for the tezos-client of one sandbox node
+struct SandboxSigner {
+    process: Child,
+    listen_addr: SocketAddr,
+}
+
+impl SandboxSigner {
+    const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Imports the wallets secret keys into the new signer key store in `base_dir` and launches the signer
+    fn start(
+        executable_path: &Path,
+        base_dir: &Path,
+        wallets: &[Wallet],
+    ) -> Result<Self, TezosClientRunnerError> {
+        fs::create_dir_all(base_dir)?;
+        let password_file = base_dir.join("password");
+        fs::write(&password_file, rand_chars(32))?;
+
+        let signer_command = || {
+            let mut command = Command::new(executable_path);
+            command
+                .arg("--base-dir")
+                .arg(base_dir)
+                .arg("--password-file")
+                .arg(&password_file);
+            command
+        };
+
+        for wallet in wallets {
+            let output = signer_command()
+                .args(&["import-secret-key", &wallet.alias])
+                .arg(format!("unencrypted:{}", &wallet.secret_key))
+                .arg("--force")
+                .output()?;
+            if !output.status.success() {
+                return Err(TezosClientRunnerError::SignerError {
+                    reason: format!(
+                        "failed to import key '{}': {}",
+                        &wallet.alias,
+                        String::from_utf8_lossy(&output.stderr)
+                    ),
+                });
+            }
+        }
+
+        // let the OS choose a free port for the signer
+        let listen_addr = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?.local_addr()?;
+        let process = signer_command()
+            .arg("launch-http-signer")
+            .arg("--listen-addr")
+            .arg(listen_addr.to_string())
+            .stdout(Stdio::null())
+            .spawn()?;
+        let mut signer = Self {
+            process,
+            listen_addr,
+        };
+
+        // wait until the signer accepts connections
+        let started_at = Instant::now();
+        while TcpStream::connect(listen_addr).is_err() {
+            if let Some(status) = signer.process.try_wait()? {
+                return Err(TezosClientRunnerError::SignerError {
+                    reason: format!("signer exited on start up with {}", status),
+                });
+            }
+            if started_at.elapsed() > Self::STARTUP_TIMEOUT {
+                return Err(TezosClientRunnerError::SignerError {
+                    reason: format!("signer is not listening on {}", listen_addr),
+                });
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        Ok(signer)
+    }
+
+    /// Remote key URI of the wallet for the tezos-client
+    fn key_uri(&self, wallet: &Wallet) -> String {
+        format!("http://{}/{}", self.listen_addr, &wallet.public_key_hash)
+    }
+}
+
+impl Drop for SandboxSigner {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
 }
 
 /// Thread-safe reference to the client runner
 pub type TezosClientRunnerRef = Arc<RwLock<TezosClientRunner>>;
 
 /// Structure holding data to use a tezos-client binary
-#[derive(Clone)]
 pub struct TezosClientRunner {
     pub name: String,
     pub executable_path: PathBuf,
+    /// If set, wallets secret keys are held by the signer and tezos-client uses them as remote keys
+    pub signer_path: Option<PathBuf>,
 
     /// Temporary data per node
     sandbox_data: HashMap<NodeRpcIpPort, SandboxData>,
@@ -141,10 +241,11 @@ impl Default for TezosClientReply {
 }
 
 impl TezosClientRunner {
-    pub fn new(name: &str, executable_path: PathBuf) -> Self {
+    pub fn new(name: &str, executable_path: PathBuf, signer_path: Option<PathBuf>) -> Self {
         Self {
             name: name.to_string(),
             executable_path,
+            signer_path,
             sandbox_data: HashMap::default(),
         }
     }
@@ -155,6 +256,7 @@ impl TezosClientRunner {
             SandboxData {
                 data_dir_path,
                 wallets: HashMap::default(),
+                signer: None,
             },
         );
     }
@@ -319,28 +421,48 @@ impl TezosClientRunner {
             &mut client_output,
         )?;
 
+        let signer = match &self.signer_path {
+            Some(signer_path) => {
+                let data = self.sandbox_data.get_mut(node_ref).ok_or_else(|| {
+                    TezosClientRunnerError::SandboxDataDirNotInitialized {
+                        node_ref: node_ref.clone(),
+                    }
+                })?;
+                // stop the previous signer first, its key store is replaced
+                data.signer = None;
+                let signer_base_dir = data.data_dir_path.join("signer");
+                Some(SandboxSigner::start(
+                    signer_path,
+                    &signer_base_dir,
+                    &requested_wallets,
+                )?)
+            }
+            None => None,
+        };
+
         for wallet in requested_wallets {
+            let secret_key = match &signer {
+                Some(signer) => signer.key_uri(&wallet),
+                None => format!("unencrypted:{}", &wallet.secret_key),
+            };
             self.run_client(
                 node_ref,
-                [
-                    "import",
-                    "secret",
-                    "key",
-                    &wallet.alias,
-                    &format!("unencrypted:{}", &wallet.secret_key),
-                ]
-                .to_vec(),
+                ["import", "secret", "key", &wallet.alias, &secret_key].to_vec(),
                 &mut client_output,
             )?;
             self.insert_wallet(node_ref, wallet)?;
         }
 
+        if let Some(data) = self.sandbox_data.get_mut(node_ref) {
+            data.signer = signer;
+        }
+
         Ok(client_output)
     }
 
     /// Cleanup the tezos-client directory
     pub fn cleanup(&mut self, node_ref: &NodeRpcIpPort) -> Result<(), failure::Error> {
-        // clear node sandbox data
+        // clear node sandbox data (running signer is stopped on drop)
         if let Some(data) = self.sandbox_data.remove(node_ref) {
             // remove work dir
             fs::remove_dir_all(&data.data_dir_path)?;
@@ -147,6 +147,15 @@ impl ChainCurrentHeadManager {
             }
         };
 
+        // notify other actors (e.g. rpc monitors) about every validated block, not only about new heads
+        self.shell_channel.tell(
+            Publish {
+                msg: ShellChannelMsg::BlockApplied(chain_id.clone(), block.clone()),
+                topic: ShellChannelTopic::ShellBlockApplied.into(),
+            },
+            None,
+        );
+
         // we try to set it as "new current head", if some means set, if none means just ignore block
         if let Some((new_head, new_head_result)) =
             self.head_state.try_update_new_current_head(&block)?
@@ -167,7 +176,19 @@ impl ChainCurrentHeadManager {
                 None,
             );
 
-            let mut is_bootstrapped = self.current_bootstrap_state.read()?.is_bootstrapped();
+            // synchronization status is resolved continuously, so it is updated with every new head
+            let mut is_bootstrapped = {
+                let mut current_bootstrap_state = self.current_bootstrap_state.write()?;
+                let was_bootstrapped = current_bootstrap_state.is_bootstrapped();
+                let is_bootstrapped =
+                    current_bootstrap_state.update_by_new_local_head_block(&block);
+                if !was_bootstrapped && is_bootstrapped {
+                    info!(ctx.system.log(), "Bootstrapped (chain_current_head_manager - synchronization heuristic)";
+                       "synchronization_status" => format!("{:?}", current_bootstrap_state.synchronization_status()),
+                       "reached_on_level" => new_head.level());
+                }
+                is_bootstrapped
+            };
 
             if !is_bootstrapped {
                 let chain_manager_current_level = new_head.level();
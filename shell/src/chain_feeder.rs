@@ -37,6 +37,7 @@ use crate::chain_current_head_manager::{ChainCurrentHeadManagerRef, ProcessValid
 use crate::peer_branch_bootstrapper::{BlockAlreadyApplied, PeerBranchBootstrapperRef};
 use crate::shell_channel::{ShellChannelMsg, ShellChannelRef};
 use crate::stats::apply_block_stats::BlockValidationTimer;
+use crate::stats::block_apply_timings::{BlockApplyTiming, BlockApplyTimingsRef};
 use crate::subscription::subscribe_to_shell_shutdown;
 use crate::utils::{dispatch_condvar_result, CondvarResult};
 use crate::validation;
@@ -172,6 +173,7 @@ impl ChainFeeder {
         tezos_writeable_api: Arc<TezosApiConnectionPool>,
         init_storage_data: StorageInitInfo,
         tezos_env: TezosEnvironmentConfiguration,
+        block_apply_timings: BlockApplyTimingsRef,
         log: Logger,
     ) -> Result<ChainFeederRef, CreateError> {
         // spawn inner thread
@@ -182,6 +184,7 @@ impl ChainFeeder {
                 Arc::new(init_storage_data),
                 Arc::new(tezos_env),
                 tezos_writeable_api,
+                block_apply_timings,
                 log,
             )
             .spawn_feeder_thread();
@@ -541,6 +544,7 @@ pub(crate) struct BlockApplierThreadSpawner {
     init_storage_data: Arc<StorageInitInfo>,
     tezos_env: Arc<TezosEnvironmentConfiguration>,
     tezos_writeable_api: Arc<TezosApiConnectionPool>,
+    block_apply_timings: BlockApplyTimingsRef,
     log: Logger,
 }
 
@@ -551,6 +555,7 @@ impl BlockApplierThreadSpawner {
         init_storage_data: Arc<StorageInitInfo>,
         tezos_env: Arc<TezosEnvironmentConfiguration>,
         tezos_writeable_api: Arc<TezosApiConnectionPool>,
+        block_apply_timings: BlockApplyTimingsRef,
         log: Logger,
     ) -> Self {
         Self {
@@ -559,6 +564,7 @@ impl BlockApplierThreadSpawner {
             tezos_writeable_api,
             init_storage_data,
             tezos_env,
+            block_apply_timings,
             log,
         }
     }
@@ -581,6 +587,7 @@ impl BlockApplierThreadSpawner {
             let tezos_writeable_api = self.tezos_writeable_api.clone();
             let init_storage_data = self.init_storage_data.clone();
             let tezos_env = self.tezos_env.clone();
+            let block_apply_timings = self.block_apply_timings.clone();
             let log = self.log.clone();
             let block_applier_run = block_applier_run.clone();
 
@@ -611,6 +618,7 @@ impl BlockApplierThreadSpawner {
                             &context,
                             &protocol_controller.api,
                             &mut block_applier_event_receiver,
+                            &block_apply_timings,
                             &log,
                         ) {
                             Ok(()) => {
@@ -654,6 +662,7 @@ fn feed_chain_to_protocol(
     context: &Box<dyn ContextApi>,
     protocol_controller: &ProtocolController,
     block_applier_event_receiver: &mut QueueReceiver<Event>,
+    block_apply_timings: &BlockApplyTimingsRef,
     log: &Logger,
 ) -> Result<(), FeedChainError> {
     // at first we initialize protocol runtime and ffi context
@@ -698,6 +707,7 @@ fn feed_chain_to_protocol(
                     request,
                 }) => {
                     let block_hash = Arc::new(block_hash);
+                    let waiting_in_queue_elapsed = roundtrip_timer.elapsed();
                     let validated_at_timer = Instant::now();
                     debug!(log, "Applying block"; "block_header_hash" => block_hash.to_base58_check(), "chain_id" => chain_id.to_base58_check(), "sender" => sender_to_string(&bootstrapper));
 
@@ -746,9 +756,10 @@ fn feed_chain_to_protocol(
                     let load_metadata_elapsed = load_metadata_timer.elapsed();
 
                     // try apply block
+                    let level = request.block_header.level();
                     let protocol_call_timer = Instant::now();
-                    match protocol_controller.apply_block(request) {
-                        Ok(apply_block_result) => {
+                    match protocol_controller.apply_block_with_timer(request) {
+                        Ok((apply_block_result, apply_block_call_timer)) => {
                             let protocol_call_elapsed = protocol_call_timer.elapsed();
                             debug!(log, "Block was applied";
                                 "block_header_hash" => block_hash.to_base58_check(),
@@ -841,6 +852,28 @@ fn feed_chain_to_protocol(
                             };
                             let store_result_elapsed = store_result_timer.elapsed();
 
+                            match block_apply_timings.write() {
+                                Ok(mut block_apply_timings) => {
+                                    block_apply_timings.block_applied(BlockApplyTiming {
+                                        block_hash: block_hash.as_ref().clone(),
+                                        level,
+                                        applied_at: SystemTime::now(),
+                                        waiting_for_operations: None,
+                                        waiting_in_queue: waiting_in_queue_elapsed,
+                                        load_metadata: load_metadata_elapsed,
+                                        ipc_send: apply_block_call_timer.send_elapsed,
+                                        protocol_apply: apply_block_call_timer.receive_elapsed,
+                                        context_wait: context_wait_elapsed,
+                                        context_actions: None,
+                                        store_result: store_result_elapsed,
+                                        total: validated_at_timer.elapsed(),
+                                    })
+                                }
+                                Err(e) => {
+                                    warn!(log, "Failed to lock block apply timings"; "reason" => format!("{}", e))
+                                }
+                            }
+
                             // notify others
                             if apply_block_run.load(Ordering::Acquire) {
                                 // now we want to parallelize and speed-up
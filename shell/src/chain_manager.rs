@@ -52,6 +52,7 @@ use crate::state::synchronization_state::{
 };
 use crate::state::StateError;
 use crate::stats::apply_block_stats::ApplyBlockStatsRef;
+use crate::stats::block_apply_timings::BlockApplyTimingsRef;
 use crate::subscription::*;
 use crate::utils::{dispatch_condvar_result, CondvarResult};
 use crate::validation;
@@ -166,6 +167,8 @@ struct Stats {
 
     /// Shared statistics for applying blocks
     apply_block_stats: ApplyBlockStatsRef,
+    /// Shared per-block timings, here we register waiting for operations
+    block_apply_timings: BlockApplyTimingsRef,
 }
 
 /// Purpose of this actor is to perform chain synchronization.
@@ -241,6 +244,7 @@ impl ChainManager {
         current_mempool_state: CurrentMempoolStateStorageRef,
         current_bootstrap_state: SynchronizationBootstrapStateRef,
         apply_block_stats: ApplyBlockStatsRef,
+        block_apply_timings: BlockApplyTimingsRef,
         p2p_disable_mempool: bool,
         identity: Arc<Identity>,
     ) -> Result<ChainManagerRef, CreateError> {
@@ -259,6 +263,7 @@ impl ChainManager {
                 current_mempool_state,
                 current_bootstrap_state,
                 apply_block_stats,
+                block_apply_timings,
                 p2p_disable_mempool,
                 identity.peer_id(),
             )),
@@ -345,6 +350,13 @@ impl ChainManager {
                                     {
                                         warn!(log, "Failed to update remote head (by current branch)"; "reason" => e);
                                     }
+                                    self.current_bootstrap_state
+                                        .write()
+                                        .map_err(StateError::from)?
+                                        .update_by_peer_current_head(
+                                            &peer.peer_id.peer_public_key_hash,
+                                            &message_current_head,
+                                        );
 
                                     // schedule to download missing branch blocks
                                     chain_state.schedule_history_bootstrap(
@@ -408,6 +420,7 @@ impl ChainManager {
                                         stats,
                                         chain_state,
                                         shell_channel,
+                                        network_channel,
                                         &log,
                                     )?;
 
@@ -468,6 +481,13 @@ impl ChainManager {
                                         &block_hash,
                                         &operations,
                                     )? {
+                                        if let Ok(mut block_apply_timings) =
+                                            stats.block_apply_timings.write()
+                                        {
+                                            block_apply_timings
+                                                .block_operations_completed(&block_hash);
+                                        }
+
                                         // TODO: TE-369 - is this necessery?
                                         // notify others that new all operations for block were received
                                         let block_meta = block_meta_storage
@@ -521,7 +541,7 @@ impl ChainManager {
                                     match chain_state.can_accept_head(
                                         &message,
                                         &current_head.local,
-                                        &self.tezos_readonly_prevalidation_api.pool.get()?.api,
+                                        &mut *self.tezos_readonly_prevalidation_api.pool.get()?,
                                     )? {
                                         BlockAcceptanceResult::AcceptBlock => {
                                             let message_current_head = BlockHeaderWithHash::new(
@@ -535,6 +555,13 @@ impl ChainManager {
                                             {
                                                 warn!(log, "Failed to update remote head (by current head)"; "reason" => e);
                                             }
+                                            self.current_bootstrap_state
+                                                .write()
+                                                .map_err(StateError::from)?
+                                                .update_by_peer_current_head(
+                                                    &peer.peer_id.peer_public_key_hash,
+                                                    &message_current_head,
+                                                );
 
                                             // process downloaded block directly
                                             Self::process_downloaded_header(
@@ -543,6 +570,7 @@ impl ChainManager {
                                                 stats,
                                                 chain_state,
                                                 shell_channel,
+                                                network_channel,
                                                 &log,
                                             )?;
 
@@ -655,7 +683,7 @@ impl ChainManager {
                                                 &operation_hash,
                                                 &operation,
                                                 self.current_mempool_state.clone(),
-                                                &self.tezos_readonly_prevalidation_api.pool.get()?.api,
+                                                &mut *self.tezos_readonly_prevalidation_api.pool.get()?,
                                                 block_storage,
                                                 block_meta_storage,
                                             ) {
@@ -838,13 +866,43 @@ impl ChainManager {
         stats: &mut Stats,
         chain_state: &mut BlockchainState,
         shell_channel: &ShellChannelRef,
+        network_channel: &NetworkChannelRef,
         log: &Logger,
     ) -> Result<(), Error> {
+        // block diverging from checkpoint means, that peer is on different branch, we dont want it
+        if chain_state.is_diverging_from_checkpoint(&received_block)? {
+            warn!(log, "Received block header does not match checkpoint - blacklisting peer";
+                       "block_header_hash" => received_block.hash.to_base58_check(),
+                       "level" => received_block.header.level());
+
+            // clear peer stuff immediatelly
+            peer.clear();
+
+            // blacklist peer
+            network_channel.tell(
+                Publish {
+                    msg: NetworkChannelMsg::BlacklistPeer(
+                        peer.peer_id.clone(),
+                        format!(
+                            "Block {} does not match checkpoint",
+                            received_block.hash.to_base58_check()
+                        ),
+                    ),
+                    topic: NetworkChannelTopic::NetworkCommands.into(),
+                },
+                None,
+            );
+            return Ok(());
+        }
+
         // store header
         if chain_state.process_block_header_from_peer(peer, &received_block, log)? {
             // update stats for new header
             stats.unseen_block_last = Instant::now();
             stats.unseen_block_count += 1;
+            if let Ok(mut block_apply_timings) = stats.block_apply_timings.write() {
+                block_apply_timings.block_header_received(&received_block.hash);
+            }
 
             // notify others that new block was received
             shell_channel.tell(
@@ -1298,6 +1356,7 @@ impl
         CurrentMempoolStateStorageRef,
         SynchronizationBootstrapStateRef,
         ApplyBlockStatsRef,
+        BlockApplyTimingsRef,
         bool,
         CryptoboxPublicKeyHash,
     )> for ChainManager
@@ -1316,6 +1375,7 @@ impl
             current_mempool_state,
             current_bootstrap_state,
             apply_block_stats,
+            block_apply_timings,
             p2p_disable_mempool,
             identity_peer_id,
         ): (
@@ -1331,6 +1391,7 @@ impl
             CurrentMempoolStateStorageRef,
             SynchronizationBootstrapStateRef,
             ApplyBlockStatsRef,
+            BlockApplyTimingsRef,
             bool,
             CryptoboxPublicKeyHash,
         ),
@@ -1364,6 +1425,7 @@ impl
                 unseen_block_last: Instant::now(),
                 unseen_block_operations_last: Instant::now(),
                 apply_block_stats,
+                block_apply_timings,
             },
             is_sandbox,
             identity_peer_id,
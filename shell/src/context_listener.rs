@@ -13,7 +13,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crypto::hash::{BlockHash, ContextHash, FromBytesError, HashType};
 use storage::context::{ContextApi, TezedgeContext, TreeId};
@@ -24,6 +24,7 @@ use tezos_context::channel::ContextAction;
 use tezos_wrapper::service::IpcEvtServer;
 
 use crate::shell_channel::{ShellChannelMsg, ShellChannelRef};
+use crate::stats::block_apply_timings::{BlockApplyTimingsRef, ContextActionsTimer};
 use crate::subscription::subscribe_to_shell_shutdown;
 
 type SharedJoinHandle = Arc<Mutex<Option<JoinHandle<Result<(), Error>>>>>;
@@ -57,6 +58,7 @@ impl ContextListener {
         persistent_storage: &PersistentStorage,
         action_store_backend: Vec<Box<dyn ActionRecorder + Send>>,
         mut event_server: IpcEvtServer,
+        block_apply_timings: BlockApplyTimingsRef,
         log: Logger,
     ) -> Result<ContextListenerRef, CreateError> {
         let listener_run = Arc::new(AtomicBool::new(true));
@@ -79,6 +81,7 @@ impl ContextListener {
                         Self::IPC_ACCEPT_TIMEOUT,
                         &mut action_store_backend,
                         &mut context,
+                        &block_apply_timings,
                         &log,
                     ) {
                         Ok(()) => info!(log, "Context listener finished"),
@@ -171,6 +174,7 @@ fn listen_protocol_events(
     event_server_accept_timeout: Duration,
     action_store_backend: &mut Vec<Box<dyn ActionRecorder + Send>>,
     context: &mut Box<dyn ContextApi>,
+    block_apply_timings: &BlockApplyTimingsRef,
     log: &Logger,
 ) -> Result<(), Error> {
     info!(
@@ -184,6 +188,8 @@ fn listen_protocol_events(
     );
 
     let mut event_count = 0;
+    // timings of context actions for currently processed block (closed by commit)
+    let mut block_context_actions = ContextActionsTimer::default();
 
     while apply_block_run.load(Ordering::Acquire) {
         match rx.receive() {
@@ -215,7 +221,31 @@ fn listen_protocol_events(
                     }
                 }
 
+                let action_timer = Instant::now();
                 perform_context_action(&action, context)?;
+                let action_elapsed = action_timer.elapsed();
+
+                block_context_actions.actions_count += 1;
+                match &action {
+                    ContextAction::Commit {
+                        block_hash: Some(block_hash),
+                        ..
+                    } => {
+                        block_context_actions.commit_elapsed = action_elapsed;
+                        let block_context_actions = std::mem::take(&mut block_context_actions);
+                        match block_apply_timings.write() {
+                            Ok(mut block_apply_timings) => block_apply_timings
+                                .context_actions_processed(
+                                    BlockHash::try_from(block_hash.clone())?,
+                                    block_context_actions,
+                                ),
+                            Err(e) => {
+                                warn!(log, "Failed to lock block apply timings"; "reason" => format!("{}", e))
+                            }
+                        }
+                    }
+                    _ => block_context_actions.actions_elapsed += action_elapsed,
+                }
             }
             Err(err) => {
                 warn!(log, "Failed to receive event from protocol runner"; "reason" => format!("{:?}", err));
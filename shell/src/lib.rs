@@ -173,6 +173,23 @@ pub mod subscription {
         );
     }
 
+    #[inline]
+    pub fn subscribe_to_shell_block_applied<M, E>(
+        shell_channel: &ChannelRef<E>,
+        myself: ActorRef<M>,
+    ) where
+        M: Message,
+        E: Message + Into<M>,
+    {
+        shell_channel.tell(
+            Subscribe {
+                actor: Box::new(myself),
+                topic: ShellChannelTopic::ShellBlockApplied.into(),
+            },
+            None,
+        );
+    }
+
     #[inline]
     pub(crate) fn subscribe_to_shell_commands<M, E>(
         shell_channel: &ChannelRef<E>,
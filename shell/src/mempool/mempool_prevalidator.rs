@@ -30,10 +30,8 @@ use tezos_api::ffi::{
     Applied, BeginConstructionRequest, PrevalidatorWrapper, ValidateOperationRequest,
 };
 use tezos_messages::p2p::encoding::block_header::BlockHeader;
-use tezos_wrapper::service::{
-    handle_protocol_service_error, ProtocolController, ProtocolServiceError,
-};
-use tezos_wrapper::TezosApiConnectionPool;
+use tezos_wrapper::service::{handle_protocol_service_error, ProtocolServiceError};
+use tezos_wrapper::{ProtocolRunnerConnection, RunnerType, TezosApiConnectionPool};
 
 use crate::mempool::mempool_state::collect_mempool;
 use crate::mempool::CurrentMempoolStateStorageRef;
@@ -102,7 +100,7 @@ impl MempoolPrevalidator {
                             &chain_id,
                             &validator_run,
                             &shell_channel,
-                            &protocol_controller.api,
+                            &mut protocol_controller,
                             &mut validator_event_receiver,
                             &log,
                         ) {
@@ -291,7 +289,7 @@ fn process_prevalidation(
     chain_id: &ChainId,
     validator_run: &AtomicBool,
     shell_channel: &ShellChannelRef,
-    api: &ProtocolController,
+    api: &mut ProtocolRunnerConnection<RunnerType>,
     validator_event_receiver: &mut QueueReceiver<Event>,
     log: &Logger,
 ) -> Result<(), PrevalidationError> {
@@ -304,7 +302,7 @@ fn process_prevalidation(
         chain_meta_storage,
         mempool_storage,
         current_mempool_state_storage.clone(),
-        &api,
+        api,
         &chain_id,
         &log,
     )?;
@@ -320,7 +318,7 @@ fn process_prevalidation(
 
                     // try to begin construction new context
                     let (prevalidator, head) =
-                        begin_construction(&api, &chain_id, header_hash, header, &log)?;
+                        begin_construction(api, &chain_id, header_hash, header, &log)?;
 
                     // reinitialize state for new prevalidator and head
                     let operations_to_delete = current_mempool_state_storage
@@ -388,7 +386,7 @@ fn process_prevalidation(
         // 2. lets handle pending operations (if any)
         handle_pending_operations(
             &shell_channel,
-            &api,
+            api,
             current_mempool_state_storage.clone(),
             &log,
         )?;
@@ -403,7 +401,7 @@ fn hydrate_state(
     chain_meta_storage: &ChainMetaStorage,
     mempool_storage: &MempoolStorage,
     current_mempool_state_storage: CurrentMempoolStateStorageRef,
-    api: &ProtocolController,
+    api: &mut ProtocolRunnerConnection<RunnerType>,
     chain_id: &ChainId,
     log: &Logger,
 ) -> Result<(), PrevalidationError> {
@@ -436,13 +434,13 @@ fn hydrate_state(
     drop(state);
 
     // and process it immediatly on startup, before any event received to clean old stored unprocessed operations
-    handle_pending_operations(&shell_channel, &api, current_mempool_state_storage, &log)?;
+    handle_pending_operations(&shell_channel, api, current_mempool_state_storage, &log)?;
 
     Ok(())
 }
 
 fn begin_construction(
-    api: &ProtocolController,
+    api: &mut ProtocolRunnerConnection<RunnerType>,
     chain_id: &ChainId,
     block_hash: BlockHash,
     block_header: Arc<BlockHeader>,
@@ -468,7 +466,7 @@ fn begin_construction(
 
 fn handle_pending_operations(
     shell_channel: &ShellChannelRef,
-    api: &ProtocolController,
+    api: &mut ProtocolRunnerConnection<RunnerType>,
     current_mempool_state_storage: CurrentMempoolStateStorageRef,
     log: &Logger,
 ) -> Result<(), PrevalidationError> {
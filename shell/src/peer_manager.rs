@@ -29,6 +29,10 @@ use networking::p2p::{
 };
 use networking::{LocalPeerInfo, PeerId, ShellCompatibilityVersion};
 use tezos_identity::Identity;
+use tezos_messages::p2p::encoding::advertise::{
+    canonical_ip, p2p_point_to_string, parse_p2p_point,
+};
+use tezos_messages::p2p::encoding::limits::ADVERTISE_ID_LIST_MAX_LENGTH;
 use tezos_messages::p2p::encoding::prelude::*;
 
 use crate::shell_channel::{ShellChannelMsg, ShellChannelRef};
@@ -68,12 +72,18 @@ pub struct ConnectToPeer {
 
 #[derive(Debug, Clone)]
 pub struct P2p {
-    /// Node p2p port
+    /// Node p2p port, which is announced to remote peers in connection message
     pub listener_port: u16,
+    /// Addresses (IPv4/IPv6), where node listens for incoming connections
+    pub listener_addresses: Vec<SocketAddr>,
+    /// Public address announced to other peers (e.g. for node behind NAT)
+    pub discovery_address: Option<SocketAddr>,
     pub disable_mempool: bool,
     pub private_node: bool,
 
     pub peer_threshold: PeerConnectionThreshold,
+    /// Max age of peer heads, which are considered as recent for synchronization status
+    pub synchronization_latency: Duration,
 
     /// Bootstrap lookup addresses disable/enable
     pub disable_bootstrap_lookup: bool,
@@ -125,8 +135,13 @@ pub struct PeerManager {
     /// Indicates that p2p is working in private mode
     private_node: bool,
 
+    /// Addresses, where we listen for incoming connections
+    listener_addresses: Vec<SocketAddr>,
+    /// Public address, which we advertise to other peers
+    discovery_address: Option<SocketAddr>,
+
     /// Local node info covers:
-    /// - listener_port - we will listen for incoming connection at this port
+    /// - listener_port - port announced to remote peers
     /// - identity
     /// - Network/protocol version
     local_node_info: Arc<LocalPeerInfo>,
@@ -234,6 +249,17 @@ impl PeerManager {
         Peer::actor(sys, network_channel, tokio_executor, info)
     }
 
+    /// Check if given address is our own public (discovery) address, so we dont try to connect to ourselves
+    fn is_local_address(&self, address: &SocketAddr) -> bool {
+        match &self.discovery_address {
+            Some(discovery_address) => {
+                canonical_ip(discovery_address.ip()) == canonical_ip(address.ip())
+                    && discovery_address.port() == address.port()
+            }
+            None => false,
+        }
+    }
+
     /// Check if given ip address is blacklisted to connect to
     fn is_blacklisted(&self, ip_address: &IpAddr) -> bool {
         self.ip_blacklist.contains(ip_address)
@@ -391,6 +417,8 @@ impl
             tokio_executor,
             bootstrap_addresses,
             threshold: p2p_config.peer_threshold,
+            listener_addresses: p2p_config.listener_addresses,
+            discovery_address: p2p_config.discovery_address,
             local_node_info: Arc::new(LocalPeerInfo::new(
                 p2p_config.listener_port,
                 identity,
@@ -433,15 +461,17 @@ impl Actor for PeerManager {
             WhitelistAllIpAddresses.into(),
         );
 
-        let listener_port = self.local_node_info.listener_port();
-        let myself = ctx.myself();
-        let rx_run = self.rx_run.clone();
-        let log = ctx.system.log();
+        // start to listen for incoming p2p connections on all configured addresses
+        for listener_address in &self.listener_addresses {
+            let listener_address = *listener_address;
+            let myself = ctx.myself();
+            let rx_run = self.rx_run.clone();
+            let log = ctx.system.log();
 
-        // start to listen for incoming p2p connections
-        self.tokio_executor.spawn(async move {
-            begin_listen_incoming(listener_port, myself, rx_run, &log).await;
-        });
+            self.tokio_executor.spawn(async move {
+                begin_listen_incoming(listener_address, myself, rx_run, &log).await;
+            });
+        }
     }
 
     fn post_start(&mut self, ctx: &Context<Self::Msg>) {
@@ -538,22 +568,28 @@ impl Receive<NetworkChannelMsg> for PeerManager {
             NetworkChannelMsg::ProcessAdvertisedPeers(peer, message) => {
                 // extract potential peers from the advertise message
                 info!(ctx.system.log(), "Received advertise message"; "peer_id" => peer.peer_id_marker.clone(), "peers" => format!("{:?}", message.id().join(", ")));
-                self.process_new_potential_peers(
-                    message
-                        .id()
-                        .iter()
-                        .filter_map(|str_ip_port| str_ip_port.parse().ok())
-                        .collect::<Vec<SocketAddr>>(),
-                );
+                let potential_peers = message
+                    .id()
+                    .iter()
+                    .filter_map(|str_ip_port| parse_p2p_point(str_ip_port))
+                    .filter(|address| !self.is_local_address(address))
+                    .collect::<Vec<SocketAddr>>();
+                self.process_new_potential_peers(potential_peers);
             }
             NetworkChannelMsg::SendBootstrapPeers(peer) => {
                 // to a bootstrap message we will respond with list of potential peers
                 trace!(ctx.system.log(), "Received bootstrap message"; "peer_id" => peer.peer_id_marker.clone());
                 let addresses = self
-                    .peers
-                    .values()
-                    .filter(|peer_state| peer_state.peer_id.peer_ref != peer.peer_ref)
-                    .map(|peer_state| peer_state.peer_id.peer_address)
+                    .discovery_address
+                    .iter()
+                    .cloned()
+                    .chain(
+                        self.peers
+                            .values()
+                            .filter(|peer_state| peer_state.peer_id.peer_ref != peer.peer_ref)
+                            .map(|peer_state| peer_state.peer_id.peer_address),
+                    )
+                    .take(ADVERTISE_ID_LIST_MAX_LENGTH)
                     .collect::<Vec<_>>();
                 let msg = Arc::new(AdvertiseMessage::new(&addresses).into());
                 peer.peer_ref.tell(SendMessage::new(msg), None);
@@ -565,12 +601,12 @@ impl Receive<NetworkChannelMsg> for PeerManager {
                 // received message that bootstrap process failed for the peer
                 match potential_peers_to_connect {
                     Some(peers) => {
-                        self.process_new_potential_peers(
-                            peers
-                                .iter()
-                                .filter_map(|str_ip_port| str_ip_port.parse().ok())
-                                .collect::<Vec<SocketAddr>>(),
-                        );
+                        let potential_peers = peers
+                            .iter()
+                            .filter_map(|str_ip_port| parse_p2p_point(str_ip_port))
+                            .filter(|address| !self.is_local_address(address))
+                            .collect::<Vec<SocketAddr>>();
+                        self.process_new_potential_peers(potential_peers);
                         self.trigger_check_peer_count(ctx);
                     }
                     None => {
@@ -729,18 +765,20 @@ fn failed_bootstrap_peer(
 
 /// Start to listen for incoming connections indefinitely.
 async fn begin_listen_incoming(
-    listener_port: u16,
+    listener_address: SocketAddr,
     peer_manager: PeerManagerRef,
     rx_run: Arc<AtomicBool>,
     log: &Logger,
 ) {
-    let listener_address = format!("0.0.0.0:{}", listener_port)
-        .parse::<SocketAddr>()
-        .expect("Failed to parse listener address");
     let listener = TcpListener::bind(&listener_address)
         .await
-        .expect("Failed to bind to address");
-    info!(log, "Start to listen for incoming p2p connections"; "port" => listener_port);
+        .unwrap_or_else(|e| {
+            panic!(
+                "Failed to bind to address: {}, reason: {}",
+                listener_address, e
+            )
+        });
+    info!(log, "Start to listen for incoming p2p connections"; "address" => p2p_point_to_string(&listener_address));
 
     while rx_run.load(Ordering::Acquire) {
         if let Ok((stream, address)) = listener.accept().await {
@@ -756,7 +794,7 @@ async fn begin_listen_incoming(
         }
     }
 
-    info!(log, "Stop listening for incoming p2p connections"; "port" => listener_port);
+    info!(log, "Stop listening for incoming p2p connections"; "address" => p2p_point_to_string(&listener_address));
 }
 
 /// Do DNS lookup for collection of names and create collection of socket addresses
@@ -61,7 +61,8 @@ pub enum ShellChannelMsg {
     /// If chain_manager resolved new current head for chain
     NewCurrentHead(Head, Arc<BlockHeaderWithHash>),
     BlockReceived(BlockReceived),
-    BlockApplied(Arc<BlockHash>),
+    /// Every successfully validated and applied block, regardless of whether it was selected as the new current head
+    BlockApplied(Arc<ChainId>, Arc<BlockHeaderWithHash>),
     AllBlockOperationsReceived(AllBlockOperationsReceived),
     MempoolOperationReceived(MempoolOperationReceived),
 
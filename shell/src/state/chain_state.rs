@@ -1,6 +1,7 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::sync::Arc;
@@ -17,11 +18,15 @@ use storage::{
     BlockHeaderWithHash, BlockMetaStorage, BlockMetaStorageReader, BlockStorage,
     BlockStorageReader, ChainMetaStorage, OperationsMetaStorage, OperationsStorage, StorageError,
 };
+use tezos_messages::p2p::binary_message::MessageHash;
+use tezos_messages::p2p::encoding::block_header::{BlockHeader, Level};
 use tezos_messages::p2p::encoding::current_branch::CurrentBranchMessage;
+use tezos_messages::p2p::encoding::limits::HISTORY_MAX_SIZE;
 use tezos_messages::p2p::encoding::prelude::{CurrentHeadMessage, OperationsForBlocksMessage};
-use tezos_messages::p2p::encoding::{block_header::BlockHeader, limits::HISTORY_MAX_SIZE};
 use tezos_messages::Head;
-use tezos_wrapper::service::{ProtocolController, ProtocolServiceError};
+use tezos_wrapper::runner::ProtocolRunner;
+use tezos_wrapper::service::ProtocolServiceError;
+use tezos_wrapper::ProtocolRunnerConnection;
 
 use crate::peer_branch_bootstrapper::{
     PeerBranchBootstrapper, StartBranchBootstraping, UpdateBlockState, UpdateOperationsState,
@@ -126,6 +131,13 @@ impl BlockchainState {
             return Ok(false);
         }
 
+        // branch must not diverge from checkpoint
+        let branch_head = branch.current_branch().current_head();
+        let branch_head_hash = || branch_head.message_typed_hash::<BlockHash>();
+        if !self.is_acceptable_by_checkpoint(branch_head.level(), branch_head_hash)? {
+            return Ok(false);
+        }
+
         if let Some(current_head) = current_head.read()?.as_ref() {
             // (only_if_fitness_increases) we can accept branch if increases fitness
             if validation::is_fitness_increases(
@@ -142,11 +154,11 @@ impl BlockchainState {
     }
 
     /// Validate if we can accept head
-    pub fn can_accept_head(
+    pub fn can_accept_head<Runner: ProtocolRunner + 'static>(
         &self,
         head: &CurrentHeadMessage,
         current_head: &CurrentHeadRef,
-        api: &ProtocolController,
+        api: &mut ProtocolRunnerConnection<Runner>,
     ) -> Result<BlockAcceptanceResult, StateError> {
         // validate chain which we operate on
         if self.chain_id.as_ref() != head.chain_id() {
@@ -158,6 +170,12 @@ impl BlockchainState {
             return Ok(BlockAcceptanceResult::IgnoreBlock);
         }
 
+        // head must not diverge from checkpoint
+        let validated_header_hash = || validated_header.message_typed_hash::<BlockHash>();
+        if !self.is_acceptable_by_checkpoint(validated_header.level(), validated_header_hash)? {
+            return Ok(BlockAcceptanceResult::IgnoreBlock);
+        }
+
         // we need our current head at first
         if let Some(current_head) = current_head.read()?.as_ref() {
             // same header means only mempool operations were changed
@@ -208,6 +226,36 @@ impl BlockchainState {
         }
     }
 
+    /// Checks block against configured checkpoint (like Octez):
+    /// - blocks below checkpoint level are not acceptable,
+    /// - block at checkpoint level must be the checkpoint itself,
+    /// - blocks above checkpoint level are acceptable (their predecessors are checked, when downloaded).
+    ///
+    /// [block_hash] is resolved lazily, only if needed
+    fn is_acceptable_by_checkpoint<E, F>(
+        &self,
+        level: Level,
+        block_hash: F,
+    ) -> Result<bool, StateError>
+    where
+        E: Into<failure::Error>,
+        F: FnOnce() -> Result<BlockHash, E>,
+    {
+        let checkpoint = match self.chain_meta_storage.get_checkpoint(&self.chain_id)? {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(true),
+        };
+
+        match level.cmp(checkpoint.level()) {
+            Ordering::Less => Ok(false),
+            Ordering::Equal => {
+                let block_hash = block_hash().map_err(Into::<failure::Error>::into)?;
+                Ok(&block_hash == checkpoint.block_hash())
+            }
+            Ordering::Greater => Ok(true),
+        }
+    }
+
     /// Returns triplet:
     /// 1. protocol_hash
     /// 2. applied_predecessor (only if is already applied)
@@ -464,9 +512,49 @@ impl BlockchainState {
         Ok(())
     }
 
+    /// Returns true, if block is at checkpoint level, but it is not the checkpoint,
+    /// such block (and its whole branch) is never stored and peer, which sent it, should be penalized.
+    ///
+    /// Note: blocks below checkpoint are fine here, we need them for history bootstrap
+    pub fn is_diverging_from_checkpoint(
+        &self,
+        received_block: &BlockHeaderWithHash,
+    ) -> Result<bool, StorageError> {
+        match self.chain_meta_storage.get_checkpoint(&self.chain_id)? {
+            Some(checkpoint) => Ok(received_block.header.level() == *checkpoint.level()
+                && &received_block.hash != checkpoint.block_hash()),
+            None => Ok(false),
+        }
+    }
+
+    /// Checks (configured) checkpoint against already stored current head,
+    /// if current head is at or above checkpoint level, checkpoint must be on its branch.
+    pub fn is_checkpoint_on_current_head_branch(
+        chain_meta_storage: &ChainMetaStorage,
+        block_meta_storage: &BlockMetaStorage,
+        chain_id: &ChainId,
+        checkpoint: &Head,
+    ) -> Result<bool, StorageError> {
+        let current_head = match chain_meta_storage.get_current_head(chain_id)? {
+            Some(current_head) => current_head,
+            None => return Ok(true),
+        };
+        if current_head.level() < checkpoint.level() {
+            return Ok(true);
+        }
+
+        let block_at_checkpoint_level = block_meta_storage.find_block_at_distance(
+            current_head.block_hash().clone(),
+            current_head.level() - checkpoint.level(),
+        )?;
+        Ok(block_at_checkpoint_level.as_ref() == Some(checkpoint.block_hash()))
+    }
+
     /// Process block_header, stores/updates storages,
     /// schedules missing stuff to peer
     ///
+    /// Block diverging from checkpoint should be rejected before, see [is_diverging_from_checkpoint]
+    ///
     /// Returns bool - true, if it is a new block or false for previosly stored
     pub fn process_block_header_from_peer(
         &mut self,
@@ -705,15 +793,418 @@ impl BlockchainState {
 
 #[cfg(test)]
 mod tests {
+    use std::convert::TryInto;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
     use slog::Level;
 
     use crypto::hash::chain_id_from_block_hash;
     use storage::tests_common::TmpStorage;
+    use storage::BlockJsonDataBuilder;
+    use tezos_api::environment::{TezosEnvironment, TEZOS_ENV};
+    use tezos_api::ffi::{
+        BeginApplicationError, BeginApplicationResponse, TezosRuntimeConfiguration,
+    };
+    use tezos_messages::p2p::encoding::block_header::BlockHeaderBuilder;
+    use tezos_messages::p2p::encoding::current_branch::CurrentBranch;
+    use tezos_messages::p2p::encoding::prelude::Mempool;
+    use tezos_wrapper::mock::{MockProtocolRunner, MockProtocolScript};
+    use tezos_wrapper::{
+        ProtocolEndpointConfiguration, ProtocolRunnerLimits, TezosApiConnectionPool,
+        TezosApiConnectionPoolConfiguration,
+    };
 
-    use crate::state::tests::prerequisites::create_logger;
+    use crate::shell_channel::ShellChannel;
+    use crate::state::data_requester::DataRequester;
+    use crate::state::head_state::init_current_head_state;
+    use crate::state::tests::prerequisites::{
+        chain_feeder_mock, create_logger, create_test_actor_system,
+    };
 
     use super::*;
 
+    #[test]
+    fn test_checkpoint() -> Result<(), failure::Error> {
+        let log = create_logger(Level::Debug);
+        let actor_system = create_test_actor_system(log.clone());
+        let storage = TmpStorage::create_to_out_dir("__test_checkpoint")?;
+        let block_meta_storage = BlockMetaStorage::new(storage.storage());
+        let chain_meta_storage = ChainMetaStorage::new(storage.storage());
+
+        /*
+         * Genesis - 1 - 2 - 3 - 4 (checkpoint is 3)
+         *               \
+         *                3' - 4'
+         */
+        let genesis = block_header(None, 0, 0)?;
+        let chain_id = Arc::new(chain_id_from_block_hash(&genesis.hash)?);
+        block_meta_storage.put(
+            &genesis.hash,
+            &Meta::genesis_meta(&genesis.hash, &chain_id, true),
+        )?;
+        let block_1 = block_header(Some(&genesis), 1, 0)?;
+        let block_2 = block_header(Some(&block_1), 2, 0)?;
+        let block_3 = block_header(Some(&block_2), 3, 0)?;
+        let block_4 = block_header(Some(&block_3), 4, 0)?;
+        let fork_3 = block_header(Some(&block_2), 3, 1)?;
+        let fork_4 = block_header(Some(&fork_3), 4, 1)?;
+        for block in &[&block_1, &block_2, &block_3, &block_4, &fork_3, &fork_4] {
+            block_meta_storage.put_block_header(block, &chain_id, &log)?;
+        }
+
+        let chain_state = BlockchainState::new(
+            Arc::new(DataRequester::new(
+                block_meta_storage,
+                OperationsMetaStorage::new(storage.storage()),
+                chain_feeder_mock(&actor_system, storage.storage().clone())?,
+            )),
+            storage.storage(),
+            // chain feeder mock already created shell channel with the default name
+            actor_system
+                .actor_of::<ShellChannel>("test-shell-event-channel")
+                .expect("Failed to create shell channel"),
+            chain_id.clone(),
+            Arc::new(genesis.hash),
+        );
+        let pool = mock_pool(MockProtocolScript::default())?;
+        let mut api = pool.pool.get()?;
+
+        let is_acceptable_by_checkpoint = |block: &BlockHeaderWithHash| {
+            chain_state.is_acceptable_by_checkpoint(block.header.level(), || {
+                Ok::<_, failure::Error>(block.hash.clone())
+            })
+        };
+        let can_accept_branch = |block: &BlockHeaderWithHash| {
+            chain_state.can_accept_branch(
+                &CurrentBranchMessage::new(
+                    chain_id.as_ref().clone(),
+                    CurrentBranch::new(block.header.as_ref().clone(), vec![]),
+                ),
+                &init_current_head_state(),
+            )
+        };
+        let mut can_accept_head = |block: &BlockHeaderWithHash| {
+            // the same block as current head is accepted, if not rejected by checkpoint
+            let current_head = init_current_head_state();
+            *current_head.write().unwrap() = Some(Head::new(
+                block.hash.clone(),
+                block.header.level(),
+                block.header.fitness().clone(),
+            ));
+            chain_state
+                .can_accept_head(
+                    &CurrentHeadMessage::new(
+                        chain_id.as_ref().clone(),
+                        block.header.as_ref().clone(),
+                        Mempool::default(),
+                    ),
+                    &current_head,
+                    &mut api,
+                )
+                .map(|result| matches!(result, BlockAcceptanceResult::AcceptBlock))
+        };
+
+        // without checkpoint everything is acceptable
+        for block in &[&block_2, &block_3, &fork_3, &fork_4] {
+            assert!(is_acceptable_by_checkpoint(block)?);
+            assert!(can_accept_branch(block)?);
+            assert!(can_accept_head(block)?);
+            assert!(!chain_state.is_diverging_from_checkpoint(block)?);
+        }
+
+        chain_meta_storage.set_checkpoint(
+            &chain_id,
+            Head::new(block_3.hash.clone(), 3, block_3.header.fitness().clone()),
+        )?;
+
+        // below checkpoint level
+        assert!(!is_acceptable_by_checkpoint(&block_2)?);
+        assert!(!can_accept_branch(&block_2)?);
+        assert!(!can_accept_head(&block_2)?);
+        assert!(!chain_state.is_diverging_from_checkpoint(&block_2)?);
+
+        // at checkpoint level
+        assert!(is_acceptable_by_checkpoint(&block_3)?);
+        assert!(can_accept_branch(&block_3)?);
+        assert!(can_accept_head(&block_3)?);
+        assert!(!chain_state.is_diverging_from_checkpoint(&block_3)?);
+
+        assert!(!is_acceptable_by_checkpoint(&fork_3)?);
+        assert!(!can_accept_branch(&fork_3)?);
+        assert!(!can_accept_head(&fork_3)?);
+        assert!(chain_state.is_diverging_from_checkpoint(&fork_3)?);
+
+        // above checkpoint level (predecessors are checked, when downloaded)
+        for block in &[&block_4, &fork_4] {
+            assert!(is_acceptable_by_checkpoint(block)?);
+            assert!(can_accept_branch(block)?);
+            assert!(can_accept_head(block)?);
+            assert!(!chain_state.is_diverging_from_checkpoint(block)?);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_checkpoint_on_current_head_branch() -> Result<(), failure::Error> {
+        let log = create_logger(Level::Debug);
+        let storage = TmpStorage::create_to_out_dir("__test_is_checkpoint_on_current_head_branch")?;
+        let block_meta_storage = BlockMetaStorage::new(storage.storage());
+        let chain_meta_storage = ChainMetaStorage::new(storage.storage());
+
+        let genesis = block_header(None, 0, 0)?;
+        let chain_id = chain_id_from_block_hash(&genesis.hash)?;
+        block_meta_storage.put(
+            &genesis.hash,
+            &Meta::genesis_meta(&genesis.hash, &chain_id, true),
+        )?;
+        let block_1 = block_header(Some(&genesis), 1, 0)?;
+        let block_2 = block_header(Some(&block_1), 2, 0)?;
+        let block_3 = block_header(Some(&block_2), 3, 0)?;
+        let fork_2 = block_header(Some(&block_1), 2, 1)?;
+        for block in &[&block_1, &block_2, &block_3, &fork_2] {
+            let meta = block_meta_storage.put_block_header(block, &chain_id, &log)?;
+            block_meta_storage.store_predecessors(&block.hash, &meta)?;
+        }
+        let head = |block: &BlockHeaderWithHash| {
+            Head::new(
+                block.hash.clone(),
+                block.header.level(),
+                block.header.fitness().clone(),
+            )
+        };
+        let is_checkpoint_on_current_head_branch = |checkpoint: &BlockHeaderWithHash| {
+            BlockchainState::is_checkpoint_on_current_head_branch(
+                &chain_meta_storage,
+                &block_meta_storage,
+                &chain_id,
+                &head(checkpoint),
+            )
+        };
+
+        // without current head any checkpoint is fine
+        assert!(is_checkpoint_on_current_head_branch(&fork_2)?);
+
+        chain_meta_storage.set_current_head(&chain_id, head(&block_3))?;
+        assert!(is_checkpoint_on_current_head_branch(&block_2)?);
+        assert!(is_checkpoint_on_current_head_branch(&block_3)?);
+        assert!(!is_checkpoint_on_current_head_branch(&fork_2)?);
+
+        // checkpoint above current head cannot be checked yet
+        let block_4 = block_header(Some(&block_3), 4, 0)?;
+        assert!(is_checkpoint_on_current_head_branch(&block_4)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_can_accept_head_with_mock_protocol() -> Result<(), failure::Error> {
+        let log = create_logger(Level::Debug);
+        let actor_system = create_test_actor_system(log.clone());
+        let storage = TmpStorage::create_to_out_dir("__test_can_accept_head_with_mock_protocol")?;
+        let block_storage = BlockStorage::new(storage.storage());
+        let block_meta_storage = BlockMetaStorage::new(storage.storage());
+
+        /*
+         * Genesis - 1 - 2 (current head)
+         *               \
+         *                2' - 3'
+         *                 \
+         *                  2'' (rejected by protocol)
+         */
+        let genesis = block_header(None, 0, 0)?;
+        let chain_id = Arc::new(chain_id_from_block_hash(&genesis.hash)?);
+        block_meta_storage.put(
+            &genesis.hash,
+            &Meta::genesis_meta(&genesis.hash, &chain_id, true),
+        )?;
+        let block_1 = block_header(Some(&genesis), 1, 0)?;
+        let block_2 = block_header(Some(&block_1), 2, 0)?;
+        let fork_2 = block_header(Some(&block_1), 2, 1)?;
+        let fork_3 = block_header(Some(&fork_2), 3, 1)?;
+        let invalid_2 = block_header(Some(&block_1), 2, 2)?;
+        let unknown_predecessor = block_header(None, 3, 3)?;
+        let lower_fitness = block_header(Some(&genesis), 1, 1)?;
+
+        // only 1 and 2 are applied, fork is just downloaded
+        let protocol_hash = "PsDELPH1Kxsxt8f9eWbxQeRxkjfbxoqM52jvs5Y5fBxWWh4ifpo";
+        for block in &[&block_1, &block_2] {
+            block_meta_storage.put_block_header(block, &chain_id, &log)?;
+            let mut meta = block_meta_storage
+                .get(&block.hash)?
+                .expect("block meta should be stored");
+            meta.set_is_applied(true);
+            block_meta_storage.put(&block.hash, &meta)?;
+            block_storage.put_block_header(block)?;
+            block_storage.put_block_json_data(
+                &block.hash,
+                BlockJsonDataBuilder::default()
+                    .block_header_proto_json("{}".to_string())
+                    .block_header_proto_metadata_json(format!(
+                        "{{\"protocol\":\"{}\",\"next_protocol\":\"{}\"}}",
+                        protocol_hash, protocol_hash
+                    ))
+                    .operations_proto_metadata_json("[]".to_string())
+                    .build()
+                    .map_err(|e| failure::format_err!("{}", e))?,
+            )?;
+        }
+        for block in &[&fork_2, &fork_3, &invalid_2] {
+            block_meta_storage.put_block_header(block, &chain_id, &log)?;
+            block_storage.put_block_header(block)?;
+        }
+
+        // protocol rejects only 2''
+        let begin_application_calls = Arc::new(AtomicUsize::new(0));
+        let pool = mock_pool(MockProtocolScript::default().with_begin_application({
+            let begin_application_calls = begin_application_calls.clone();
+            let invalid_hash = invalid_2.hash.clone();
+            move |request| {
+                begin_application_calls.fetch_add(1, Ordering::SeqCst);
+                if request.block_header.message_typed_hash::<BlockHash>().ok()
+                    == Some(invalid_hash.clone())
+                {
+                    Err(BeginApplicationError::FailedToBeginApplication {
+                        message: "invalid block".to_string(),
+                    })
+                } else {
+                    Ok(BeginApplicationResponse {
+                        result: "ok".to_string(),
+                    })
+                }
+            }
+        }))?;
+        let mut api = pool.pool.get()?;
+
+        let chain_state = BlockchainState::new(
+            Arc::new(DataRequester::new(
+                block_meta_storage,
+                OperationsMetaStorage::new(storage.storage()),
+                chain_feeder_mock(&actor_system, storage.storage().clone())?,
+            )),
+            storage.storage(),
+            // chain feeder mock already created shell channel with the default name
+            actor_system
+                .actor_of::<ShellChannel>("test-shell-event-channel")
+                .expect("Failed to create shell channel"),
+            chain_id.clone(),
+            Arc::new(genesis.hash),
+        );
+        let current_head = init_current_head_state();
+        *current_head.write().unwrap() = Some(Head::new(
+            block_2.hash.clone(),
+            block_2.header.level(),
+            block_2.header.fitness().clone(),
+        ));
+        let mut can_accept_head = |block: &BlockHeaderWithHash| {
+            chain_state.can_accept_head(
+                &CurrentHeadMessage::new(
+                    chain_id.as_ref().clone(),
+                    block.header.as_ref().clone(),
+                    Mempool::default(),
+                ),
+                &current_head,
+                &mut api,
+            )
+        };
+
+        // fork with the same fitness and applied predecessor is validated by protocol
+        assert!(matches!(
+            can_accept_head(&fork_2)?,
+            BlockAcceptanceResult::AcceptBlock
+        ));
+        assert_eq!(1, begin_application_calls.load(Ordering::SeqCst));
+
+        // fork with not applied predecessor (reorg) uses protocol of current head without begin_application
+        assert!(matches!(
+            can_accept_head(&fork_3)?,
+            BlockAcceptanceResult::AcceptBlock
+        ));
+        assert_eq!(1, begin_application_calls.load(Ordering::SeqCst));
+
+        // protocol error
+        assert!(matches!(
+            can_accept_head(&invalid_2)?,
+            BlockAcceptanceResult::MutlipassValidationError(_)
+        ));
+        assert_eq!(2, begin_application_calls.load(Ordering::SeqCst));
+
+        // missing predecessor
+        assert!(matches!(
+            can_accept_head(&unknown_predecessor)?,
+            BlockAcceptanceResult::UnknownBranch
+        ));
+
+        // lower fitness
+        assert!(matches!(
+            can_accept_head(&lower_fitness)?,
+            BlockAcceptanceResult::IgnoreBlock
+        ));
+        assert_eq!(2, begin_application_calls.load(Ordering::SeqCst));
+
+        Ok(())
+    }
+
+    /// Creates block header, different `fork` creates different block for the same predecessor
+    fn block_header(
+        predecessor: Option<&BlockHeaderWithHash>,
+        level: i32,
+        fork: i64,
+    ) -> Result<BlockHeaderWithHash, failure::Error> {
+        let predecessor_hash = match predecessor {
+            Some(predecessor) => predecessor.hash.clone(),
+            None => vec![0; 32].try_into()?,
+        };
+        let header = BlockHeaderBuilder::default()
+            .level(level)
+            .proto(1)
+            .predecessor(predecessor_hash)
+            .timestamp(level as i64 * 60 + fork)
+            .validation_pass(4)
+            .operations_hash(vec![0; 32].try_into()?)
+            .fitness(vec![vec![0], vec![level as u8]])
+            .context(vec![0; 32].try_into()?)
+            .protocol_data(vec![])
+            .build()
+            .map_err(|e| failure::format_err!("{}", e))?;
+        Ok(BlockHeaderWithHash::new(header)?)
+    }
+
+    /// Pool with mock protocol runner, which answers according to the `script`
+    fn mock_pool(
+        script: MockProtocolScript,
+    ) -> Result<TezosApiConnectionPool<MockProtocolRunner>, failure::Error> {
+        let tezos_env = TEZOS_ENV
+            .get(&TezosEnvironment::Sandbox)
+            .expect("no environment configuration");
+        Ok(TezosApiConnectionPool::new_without_context(
+            "test_mock_pool".to_string(),
+            TezosApiConnectionPoolConfiguration {
+                min_connections: 0,
+                max_connections: 1,
+                connection_timeout: Duration::from_secs(5),
+                max_lifetime: Duration::from_secs(60),
+                idle_timeout: Duration::from_secs(60),
+                limits: ProtocolRunnerLimits::default(),
+            },
+            ProtocolEndpointConfiguration::new(
+                TezosRuntimeConfiguration {
+                    log_enabled: false,
+                    debug_mode: false,
+                    compute_context_action_tree_hashes: false,
+                },
+                tezos_env.clone(),
+                false,
+                std::env::temp_dir().as_path(),
+                script.register().as_path(),
+                Level::Debug,
+                None,
+            ),
+            create_logger(Level::Debug),
+        )?)
+    }
+
     /// This test is rewritten according to [test_state.ml -> test_locator]
     #[test]
     fn test_history_and_compute_locator() -> Result<(), failure::Error> {
@@ -501,30 +501,24 @@ fn tell_peer(msg: Arc<PeerMessageResponse>, peer: &PeerId) {
 #[cfg(test)]
 mod tests {
     use std::collections::{HashMap, HashSet};
-    use std::sync::atomic::AtomicBool;
-    use std::sync::mpsc::channel;
-    use std::sync::{Arc, Mutex};
-    use std::thread;
+    use std::sync::Arc;
 
-    use riker::actors::*;
     use serial_test::serial;
     use slog::Level;
 
     use crypto::hash::ChainId;
     use networking::p2p::network_channel::NetworkChannel;
-    use storage::persistent::PersistentStorage;
     use storage::tests_common::TmpStorage;
     use storage::{
         block_meta_storage, operations_meta_storage, BlockMetaStorage, OperationsMetaStorage,
     };
     use tezos_messages::p2p::encoding::prelude::OperationsForBlock;
 
-    use crate::chain_feeder::{ChainFeeder, ChainFeederRef};
-    use crate::shell_channel::ShellChannel;
     use crate::state::data_requester::DataRequester;
     use crate::state::tests::block;
     use crate::state::tests::prerequisites::{
-        create_logger, create_test_actor_system, create_test_tokio_runtime, test_peer,
+        chain_feeder_mock, create_logger, create_test_actor_system, create_test_tokio_runtime,
+        test_peer,
     };
     use crate::state::StateError;
 
@@ -916,29 +910,4 @@ mod tests {
 
         Ok(())
     }
-
-    fn chain_feeder_mock(
-        actor_system: &ActorSystem,
-        persistent_storage: PersistentStorage,
-    ) -> Result<ChainFeederRef, failure::Error> {
-        // run actor's
-        let shell_channel =
-            ShellChannel::actor(&actor_system).expect("Failed to create shell channel");
-
-        let (block_applier_event_sender, _) = channel();
-        let block_applier_run = Arc::new(AtomicBool::new(false));
-
-        actor_system
-            .actor_of_props::<ChainFeeder>(
-                "mocked_chain_feeder",
-                Props::new_args((
-                    shell_channel,
-                    persistent_storage,
-                    Arc::new(Mutex::new(block_applier_event_sender)),
-                    block_applier_run,
-                    Arc::new(Mutex::new(Some(thread::spawn(|| Ok(()))))),
-                )),
-            )
-            .map_err(|e| e.into())
-    }
 }
@@ -289,7 +289,10 @@ mod tests {
 
     pub(crate) mod prerequisites {
         use std::net::SocketAddr;
-        use std::sync::Arc;
+        use std::sync::atomic::AtomicBool;
+        use std::sync::mpsc::channel;
+        use std::sync::{Arc, Mutex};
+        use std::thread;
 
         use futures::lock::Mutex as TokioMutex;
         use riker::actors::*;
@@ -299,9 +302,12 @@ mod tests {
         use networking::p2p::network_channel::NetworkChannelRef;
         use networking::p2p::peer::{BootstrapOutput, Peer};
         use networking::PeerId;
+        use storage::persistent::PersistentStorage;
         use tezos_identity::Identity;
         use tezos_messages::p2p::encoding::prelude::{MetadataMessage, NetworkVersion};
 
+        use crate::chain_feeder::{ChainFeeder, ChainFeederRef};
+        use crate::shell_channel::ShellChannel;
         use crate::state::peer_state::{DataQueuesLimits, PeerState};
 
         pub(crate) fn test_peer(
@@ -352,6 +358,31 @@ mod tests {
             )
         }
 
+        pub(crate) fn chain_feeder_mock(
+            actor_system: &ActorSystem,
+            persistent_storage: PersistentStorage,
+        ) -> Result<ChainFeederRef, failure::Error> {
+            // run actor's
+            let shell_channel =
+                ShellChannel::actor(&actor_system).expect("Failed to create shell channel");
+
+            let (block_applier_event_sender, _) = channel();
+            let block_applier_run = Arc::new(AtomicBool::new(false));
+
+            actor_system
+                .actor_of_props::<ChainFeeder>(
+                    "mocked_chain_feeder",
+                    Props::new_args((
+                        shell_channel,
+                        persistent_storage,
+                        Arc::new(Mutex::new(block_applier_event_sender)),
+                        block_applier_run,
+                        Arc::new(Mutex::new(Some(thread::spawn(|| Ok(()))))),
+                    )),
+                )
+                .map_err(|e| e.into())
+        }
+
         pub(crate) fn create_test_actor_system(log: Logger) -> ActorSystem {
             SystemBuilder::new()
                 .name("create_actor_system")
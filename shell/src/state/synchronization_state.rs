@@ -7,11 +7,18 @@
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crypto::hash::CryptoboxPublicKeyHash;
+use serde::Serialize;
+
+use crypto::hash::{BlockHash, CryptoboxPublicKeyHash};
 use networking::PeerId;
+use storage::BlockHeaderWithHash;
 use tezos_messages::p2p::encoding::block_header::Level;
 
+/// Default for max age of the heads, which are still considered as recent (the same as Octez default)
+pub const DEFAULT_SYNCHRONIZATION_LATENCY: Duration = Duration::from_secs(150);
+
 /// Type hold information if node is bootstrapped shareable between threads/actors
 /// Indicates that node/shell is bootstrapped, which means, that can broadcast stuff (new branch, new head) to the network
 type BootstrappedStatusRef = Arc<AtomicBool>;
@@ -47,13 +54,117 @@ pub type SynchronizationBootstrapStateRef = Arc<RwLock<SynchronizationBootstrapS
 /// Inits empty mempool state storage
 pub fn init_synchronization_bootstrap_state_storage(
     num_of_peers_for_bootstrap_threshold: usize,
+    synchronization_latency: Duration,
 ) -> SynchronizationBootstrapStateRef {
     Arc::new(RwLock::new(SynchronizationBootstrapState::new(
         num_of_peers_for_bootstrap_threshold,
+        synchronization_latency,
         BootstrappedStatusRef::new(AtomicBool::new(false)),
     )))
 }
 
+/// Synchronization status of the chain (Octez compatible)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SynchronizationStatus {
+    /// Node has enough peers with recent heads, which we validated too
+    Synced,
+    /// Node is (still) not synchronized with the network
+    Unsynced,
+    /// Enough peers agree on the same head, but it is too old, so the whole chain looks stuck
+    Stuck,
+}
+
+/// Octez synchronization heuristic.
+///
+/// Every peer is represented by the best head it advertised, but only if it is not newer than our local head,
+/// because we cannot consider head, which we have not validated yet.
+/// From these candidates, `threshold` most recent ones are taken and:
+/// - if the least recent of them is not older than `latency`, node is [SynchronizationStatus::Synced],
+/// - if all of them are the same block (which is older than `latency`) and `threshold` > 1, node is [SynchronizationStatus::Stuck],
+/// - otherwise node is [SynchronizationStatus::Unsynced].
+pub struct SynchronizationHeuristic {
+    threshold: usize,
+    latency: Duration,
+
+    /// Best known head (hash, timestamp) advertised by peer
+    peer_heads: HashMap<CryptoboxPublicKeyHash, (BlockHash, i64)>,
+    /// Our current head (hash, timestamp)
+    local_head: Option<(BlockHash, i64)>,
+}
+
+impl SynchronizationHeuristic {
+    pub fn new(threshold: usize, latency: Duration) -> Self {
+        Self {
+            threshold,
+            latency,
+            peer_heads: HashMap::default(),
+            local_head: None,
+        }
+    }
+
+    pub fn update_peer_head(
+        &mut self,
+        peer_key: &CryptoboxPublicKeyHash,
+        block_hash: &BlockHash,
+        timestamp: i64,
+    ) {
+        match self.peer_heads.get_mut(peer_key) {
+            Some(peer_head) => {
+                if peer_head.1 <= timestamp {
+                    *peer_head = (block_hash.clone(), timestamp);
+                }
+            }
+            None => {
+                self.peer_heads
+                    .insert(peer_key.clone(), (block_hash.clone(), timestamp));
+            }
+        }
+    }
+
+    pub fn update_local_head(&mut self, block_hash: &BlockHash, timestamp: i64) {
+        self.local_head = Some((block_hash.clone(), timestamp));
+    }
+
+    /// Resolves status against `now` (unix timestamp in seconds)
+    pub fn status(&self, now: i64) -> SynchronizationStatus {
+        if self.threshold == 0 {
+            return SynchronizationStatus::Synced;
+        }
+
+        let local_head_timestamp = match &self.local_head {
+            Some((_, timestamp)) => *timestamp,
+            None => return SynchronizationStatus::Unsynced,
+        };
+
+        // peer heads newer than our head are not validated by us yet, so they cannot be considered
+        let mut candidates: Vec<(&BlockHash, i64)> = self
+            .peer_heads
+            .values()
+            .filter(|(_, timestamp)| *timestamp <= local_head_timestamp)
+            .map(|(hash, timestamp)| (hash, *timestamp))
+            .collect();
+        if candidates.len() < self.threshold {
+            return SynchronizationStatus::Unsynced;
+        }
+
+        // the most recent first and take just threshold
+        candidates.sort_by(|(_, t1), (_, t2)| t2.cmp(t1));
+        candidates.truncate(self.threshold);
+
+        let (best_hash, _) = candidates[0];
+        let (_, least_timestamp) = candidates[candidates.len() - 1];
+
+        if least_timestamp >= now.saturating_sub(self.latency.as_secs() as i64) {
+            SynchronizationStatus::Synced
+        } else if self.threshold > 1 && candidates.iter().all(|(hash, _)| *hash == best_hash) {
+            SynchronizationStatus::Stuck
+        } else {
+            SynchronizationStatus::Unsynced
+        }
+    }
+}
+
 /// Manages bootstrap status based on number of bootstrapped peers
 pub struct SynchronizationBootstrapState {
     /// Indicates threshold for minimal count of bootstrapped peers to mark chain_manager as bootstrapped
@@ -64,6 +175,9 @@ pub struct SynchronizationBootstrapState {
 
     /// holder of bootstrapped peers with they highest level
     state: HashMap<CryptoboxPublicKeyHash, Level>,
+
+    /// Octez synchronization heuristic, which is resolved continuously (also after bootstrap)
+    heuristic: SynchronizationHeuristic,
 }
 
 impl SynchronizationBootstrapState {
@@ -73,6 +187,7 @@ impl SynchronizationBootstrapState {
 
     pub fn new(
         num_of_peers_for_bootstrap_threshold: usize,
+        synchronization_latency: Duration,
         current_bootstrapped_status: BootstrappedStatusRef,
     ) -> Self {
         // if no limit, just mark as bootstrapped
@@ -84,6 +199,10 @@ impl SynchronizationBootstrapState {
             num_of_peers_for_bootstrap_threshold,
             current_bootstrapped_status,
             state: HashMap::default(),
+            heuristic: SynchronizationHeuristic::new(
+                num_of_peers_for_bootstrap_threshold,
+                synchronization_latency,
+            ),
         }
     }
 
@@ -91,6 +210,49 @@ impl SynchronizationBootstrapState {
         self.current_bootstrapped_status.load(Ordering::Acquire)
     }
 
+    /// Returns actual synchronization status resolved by Octez heuristic
+    pub fn synchronization_status(&self) -> SynchronizationStatus {
+        self.heuristic.status(unix_timestamp_now())
+    }
+
+    /// Updates heuristic with (accepted) current head of peer, returns actual bootstrapped status
+    pub(crate) fn update_by_peer_current_head(
+        &mut self,
+        peer_key: &CryptoboxPublicKeyHash,
+        peer_current_head: &BlockHeaderWithHash,
+    ) -> bool {
+        self.heuristic.update_peer_head(
+            peer_key,
+            &peer_current_head.hash,
+            peer_current_head.header.timestamp(),
+        );
+        self.update_by_heuristic()
+    }
+
+    /// Updates heuristic with our new current head, returns actual bootstrapped status
+    pub(crate) fn update_by_new_local_head_block(
+        &mut self,
+        local_current_head: &BlockHeaderWithHash,
+    ) -> bool {
+        self.heuristic.update_local_head(
+            &local_current_head.hash,
+            local_current_head.header.timestamp(),
+        );
+        self.update_by_heuristic()
+    }
+
+    /// Once we are synchronized (or the whole chain is stuck), we are considered as bootstrapped for good
+    fn update_by_heuristic(&mut self) -> bool {
+        if !self.is_bootstrapped()
+            && self.synchronization_status() != SynchronizationStatus::Unsynced
+        {
+            self.current_bootstrapped_status
+                .store(true, Ordering::Release);
+            self.state.clear();
+        }
+        self.is_bootstrapped()
+    }
+
     fn consider_as_bootstrapped(
         tested_level: Level,
         target_level: Level,
@@ -198,8 +360,16 @@ impl SynchronizationBootstrapState {
     }
 }
 
+fn unix_timestamp_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs() as i64)
+        .unwrap_or(0)
+}
+
 #[cfg(test)]
 pub mod tests {
+    use std::convert::TryFrom;
     use std::sync::atomic::AtomicBool;
     use std::sync::Arc;
     use std::time::Duration;
@@ -219,7 +389,11 @@ pub mod tests {
     fn test_resolve_is_bootstrapped_no_threshold() {
         // prepare empty states
         let bootstrap_status = BootstrappedStatusRef::new(AtomicBool::new(false));
-        let bootstrap_state = SynchronizationBootstrapState::new(0, bootstrap_status);
+        let bootstrap_state = SynchronizationBootstrapState::new(
+            0,
+            DEFAULT_SYNCHRONIZATION_LATENCY,
+            bootstrap_status,
+        );
 
         // check
         assert!(bootstrap_state.is_bootstrapped());
@@ -242,7 +416,11 @@ pub mod tests {
 
         // prepare empty states with threshold = 2
         let bootstrap_status = BootstrappedStatusRef::new(AtomicBool::new(false));
-        let mut bootstrap_state = SynchronizationBootstrapState::new(2, bootstrap_status);
+        let mut bootstrap_state = SynchronizationBootstrapState::new(
+            2,
+            DEFAULT_SYNCHRONIZATION_LATENCY,
+            bootstrap_status,
+        );
 
         // check
         assert!(!bootstrap_state.is_bootstrapped());
@@ -339,6 +517,78 @@ pub mod tests {
         });
     }
 
+    #[test]
+    fn test_synchronization_heuristic() -> Result<(), failure::Error> {
+        let peer = |idx: u8| CryptoboxPublicKeyHash::try_from(vec![idx; 16]);
+        let block = |idx: u8| BlockHash::try_from(vec![idx; 32]);
+        let latency = Duration::from_secs(150);
+        let now = 1_000_000;
+
+        // no threshold means always synced
+        assert_eq!(
+            SynchronizationStatus::Synced,
+            SynchronizationHeuristic::new(0, latency).status(now)
+        );
+
+        let mut heuristic = SynchronizationHeuristic::new(2, latency);
+        assert_eq!(SynchronizationStatus::Unsynced, heuristic.status(now));
+
+        // recent heads, but not validated by us
+        heuristic.update_local_head(&block(1)?, now - 1000);
+        heuristic.update_peer_head(&peer(1)?, &block(2)?, now - 10);
+        heuristic.update_peer_head(&peer(2)?, &block(3)?, now - 5);
+        assert_eq!(SynchronizationStatus::Unsynced, heuristic.status(now));
+
+        // we validated the same head as peer 1, but peer 2 still does not count
+        heuristic.update_local_head(&block(2)?, now - 10);
+        assert_eq!(SynchronizationStatus::Unsynced, heuristic.status(now));
+
+        // older head of peer is ignored
+        heuristic.update_peer_head(&peer(2)?, &block(4)?, now - 3000);
+        assert_eq!(SynchronizationStatus::Unsynced, heuristic.status(now));
+
+        // both peers are recent enough
+        heuristic.update_local_head(&block(3)?, now - 5);
+        assert_eq!(SynchronizationStatus::Synced, heuristic.status(now));
+        assert_eq!(SynchronizationStatus::Synced, heuristic.status(now + 140));
+
+        // time goes on and the heads are too old
+        assert_eq!(
+            SynchronizationStatus::Unsynced,
+            heuristic.status(now + 1000)
+        );
+
+        // all peers agree on the same old head
+        heuristic.update_peer_head(&peer(1)?, &block(3)?, now - 5);
+        assert_eq!(SynchronizationStatus::Stuck, heuristic.status(now + 1000));
+
+        // with threshold 1 the chain cannot be stuck
+        let mut heuristic = SynchronizationHeuristic::new(1, latency);
+        heuristic.update_local_head(&block(1)?, now - 1000);
+        heuristic.update_peer_head(&peer(1)?, &block(1)?, now - 1000);
+        assert_eq!(SynchronizationStatus::Unsynced, heuristic.status(now));
+        assert_eq!(SynchronizationStatus::Synced, heuristic.status(now - 900));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_synchronization_status_serialization() -> Result<(), failure::Error> {
+        assert_eq!(
+            "\"synced\"",
+            serde_json::to_string(&SynchronizationStatus::Synced)?
+        );
+        assert_eq!(
+            "\"unsynced\"",
+            serde_json::to_string(&SynchronizationStatus::Unsynced)?
+        );
+        assert_eq!(
+            "\"stuck\"",
+            serde_json::to_string(&SynchronizationStatus::Stuck)?
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_consider_as_bootstrapped() {
         assert!(SynchronizationBootstrapState::consider_as_bootstrapped(
@@ -0,0 +1,268 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Per-block timings of block application, retained for the last applied blocks.
+//!
+//! Phases are collected by different actors:
+//! - `chain_manager` - when block header was received and when its operations were completed
+//! - `context_listener` - processing of context actions and merkle commit
+//! - `chain_feeder` - queue wait, IPC, protocol apply, context wait and storage writes
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use crypto::hash::BlockHash;
+use tezos_messages::p2p::encoding::block_header::Level;
+
+/// How many applied blocks are retained
+pub const BLOCK_APPLY_TIMINGS_RETAINED_BLOCKS: usize = 1024;
+
+/// Max count of blocks waiting for operations, which are tracked
+const MAX_BLOCKS_WAITING_FOR_OPERATIONS: usize = 64 * 1024;
+/// Blocks waiting for operations longer than this are not tracked anymore
+const BLOCKS_WAITING_FOR_OPERATIONS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Shareable type for block apply timings
+pub type BlockApplyTimingsRef = Arc<RwLock<BlockApplyTimings>>;
+
+/// Inits empty block apply timings, which retains last [BLOCK_APPLY_TIMINGS_RETAINED_BLOCKS]
+pub fn init_empty_block_apply_timings() -> BlockApplyTimingsRef {
+    Arc::new(RwLock::new(BlockApplyTimings::new(
+        BLOCK_APPLY_TIMINGS_RETAINED_BLOCKS,
+    )))
+}
+
+/// Timings of context actions processing in `context_listener` for one block
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContextActionsTimer {
+    /// Count of processed context actions (including commit)
+    pub actions_count: usize,
+    /// Duration of processing of context actions (without commit)
+    pub actions_elapsed: Duration,
+    /// Duration of merkle commit
+    pub commit_elapsed: Duration,
+}
+
+/// Timings of one applied block broken down into phases
+#[derive(Clone, Debug)]
+pub struct BlockApplyTiming {
+    pub block_hash: BlockHash,
+    pub level: Level,
+    /// When was block applied
+    pub applied_at: SystemTime,
+
+    /// Duration between received block header and completed operations (if block was received from peer)
+    pub waiting_for_operations: Option<Duration>,
+    /// Duration between scheduling block for apply and start of application in `chain_feeder`
+    pub waiting_in_queue: Duration,
+    /// Duration of loading block metadata
+    pub load_metadata: Duration,
+    /// Duration of sending request to `protocol_runner`
+    pub ipc_send: Duration,
+    /// Duration of waiting for response from `protocol_runner` (ocaml apply)
+    pub protocol_apply: Duration,
+    /// Duration of waiting for context to be processed by `context_listener`
+    pub context_wait: Duration,
+    /// Context actions processing in `context_listener` (if already processed)
+    pub context_actions: Option<ContextActionsTimer>,
+    /// Duration of storing apply block result
+    pub store_result: Duration,
+    /// Total duration of block application in `chain_feeder`
+    pub total: Duration,
+}
+
+/// Collected timings for the last applied blocks
+pub struct BlockApplyTimings {
+    /// Max count of retained applied blocks
+    capacity: usize,
+    /// Last applied blocks, the oldest is at front
+    applied: VecDeque<BlockApplyTiming>,
+    /// Blocks waiting for operations - (header received, operations completed)
+    waiting_for_operations: HashMap<BlockHash, (Instant, Option<Instant>)>,
+    /// Context actions processed before `chain_feeder` registered block as applied
+    context_actions: VecDeque<(BlockHash, ContextActionsTimer)>,
+}
+
+impl BlockApplyTimings {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            applied: VecDeque::with_capacity(capacity),
+            waiting_for_operations: HashMap::default(),
+            context_actions: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Block header was received from peer, but operations are not complete yet
+    pub fn block_header_received(&mut self, block_hash: &BlockHash) {
+        if self.waiting_for_operations.contains_key(block_hash) {
+            return;
+        }
+        if self.waiting_for_operations.len() >= MAX_BLOCKS_WAITING_FOR_OPERATIONS {
+            self.waiting_for_operations
+                .retain(|_, (received, _)| received.elapsed() <= BLOCKS_WAITING_FOR_OPERATIONS_TTL);
+            if self.waiting_for_operations.len() >= MAX_BLOCKS_WAITING_FOR_OPERATIONS {
+                return;
+            }
+        }
+        self.waiting_for_operations
+            .insert(block_hash.clone(), (Instant::now(), None));
+    }
+
+    /// All operations for the block were received
+    pub fn block_operations_completed(&mut self, block_hash: &BlockHash) {
+        if let Some((_, completed @ None)) = self.waiting_for_operations.get_mut(block_hash) {
+            *completed = Some(Instant::now());
+        }
+    }
+
+    /// Context actions for block were processed by `context_listener`
+    pub fn context_actions_processed(
+        &mut self,
+        block_hash: BlockHash,
+        context_actions: ContextActionsTimer,
+    ) {
+        // context listener is asynchronous, so block could be already registered
+        if let Some(timing) = self
+            .applied
+            .iter_mut()
+            .rev()
+            .find(|timing| timing.block_hash == block_hash)
+        {
+            timing.context_actions = Some(context_actions);
+            return;
+        }
+
+        if self.context_actions.len() >= self.capacity {
+            self.context_actions.pop_front();
+        }
+        self.context_actions
+            .push_back((block_hash, context_actions));
+    }
+
+    /// Block was applied by `chain_feeder`, timings collected by other actors are merged here
+    pub fn block_applied(&mut self, mut timing: BlockApplyTiming) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Some((received, Some(completed))) =
+            self.waiting_for_operations.remove(&timing.block_hash)
+        {
+            timing.waiting_for_operations = Some(completed.duration_since(received));
+        }
+        if let Some(position) = self
+            .context_actions
+            .iter()
+            .position(|(block_hash, _)| block_hash == &timing.block_hash)
+        {
+            timing.context_actions = self
+                .context_actions
+                .remove(position)
+                .map(|(_, context_actions)| context_actions);
+        }
+
+        if self.applied.len() >= self.capacity {
+            self.applied.pop_front();
+        }
+        self.applied.push_back(timing);
+    }
+
+    /// Returns timings for block, if it is still retained
+    pub fn get(&self, block_hash: &BlockHash) -> Option<&BlockApplyTiming> {
+        self.applied
+            .iter()
+            .rev()
+            .find(|timing| &timing.block_hash == block_hash)
+    }
+
+    /// Returns retained blocks with the longest total application, the slowest first
+    pub fn slowest(&self, limit: usize) -> Vec<BlockApplyTiming> {
+        let mut slowest: Vec<&BlockApplyTiming> = self.applied.iter().collect();
+        slowest.sort_by_key(|timing| Reverse(timing.total));
+        slowest.into_iter().take(limit).cloned().collect()
+    }
+
+    /// Count of retained applied blocks
+    pub fn len(&self) -> usize {
+        self.applied.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    fn timing(block_hash: &BlockHash, level: Level, total_millis: u64) -> BlockApplyTiming {
+        BlockApplyTiming {
+            block_hash: block_hash.clone(),
+            level,
+            applied_at: SystemTime::now(),
+            waiting_for_operations: None,
+            waiting_in_queue: Duration::default(),
+            load_metadata: Duration::default(),
+            ipc_send: Duration::default(),
+            protocol_apply: Duration::default(),
+            context_wait: Duration::default(),
+            context_actions: None,
+            store_result: Duration::default(),
+            total: Duration::from_millis(total_millis),
+        }
+    }
+
+    fn block_hash(b: u8) -> BlockHash {
+        BlockHash::try_from(vec![b; 32]).expect("invalid block hash")
+    }
+
+    #[test]
+    fn test_retains_last_blocks_and_returns_slowest() {
+        let mut timings = BlockApplyTimings::new(3);
+        timings.block_applied(timing(&block_hash(1), 1, 500));
+        timings.block_applied(timing(&block_hash(2), 2, 10));
+        timings.block_applied(timing(&block_hash(3), 3, 30));
+        timings.block_applied(timing(&block_hash(4), 4, 20));
+
+        assert_eq!(timings.len(), 3);
+        assert!(timings.get(&block_hash(1)).is_none());
+        assert_eq!(timings.get(&block_hash(4)).map(|t| t.level), Some(4));
+
+        let slowest: Vec<Level> = timings.slowest(2).iter().map(|t| t.level).collect();
+        assert_eq!(slowest, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_merges_operations_and_context_actions() {
+        let mut timings = BlockApplyTimings::new(3);
+        let context_actions = ContextActionsTimer {
+            actions_count: 10,
+            actions_elapsed: Duration::from_millis(5),
+            commit_elapsed: Duration::from_millis(1),
+        };
+
+        // context actions processed before block was registered as applied
+        timings.block_header_received(&block_hash(1));
+        timings.block_operations_completed(&block_hash(1));
+        timings.context_actions_processed(block_hash(1), context_actions.clone());
+        timings.block_applied(timing(&block_hash(1), 1, 10));
+
+        let applied = timings.get(&block_hash(1)).expect("block timing missing");
+        assert!(applied.waiting_for_operations.is_some());
+        assert_eq!(applied.context_actions, Some(context_actions.clone()));
+
+        // context actions processed after block was registered as applied
+        timings.block_applied(timing(&block_hash(2), 2, 10));
+        timings.context_actions_processed(block_hash(2), context_actions.clone());
+
+        let applied = timings.get(&block_hash(2)).expect("block timing missing");
+        assert!(applied.waiting_for_operations.is_none());
+        assert_eq!(applied.context_actions, Some(context_actions));
+    }
+}
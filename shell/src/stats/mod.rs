@@ -4,4 +4,5 @@
 //! This module contains all structs used to hold shell stats.
 
 pub mod apply_block_stats;
+pub mod block_apply_timings;
 pub mod memory;
@@ -22,7 +22,9 @@ use tezos_messages::p2p::binary_message::MessageHash;
 use tezos_messages::p2p::encoding::block_header::Fitness;
 use tezos_messages::p2p::encoding::prelude::{BlockHeader, Operation};
 use tezos_messages::Head;
-use tezos_wrapper::service::{ProtocolController, ProtocolServiceError};
+use tezos_wrapper::runner::ProtocolRunner;
+use tezos_wrapper::service::ProtocolServiceError;
+use tezos_wrapper::ProtocolRunnerConnection;
 
 use crate::mempool::CurrentMempoolStateStorageRef;
 use crate::validation::fitness_comparator::FitnessWrapper;
@@ -203,12 +205,12 @@ impl From<StorageError> for PrevalidateOperationError {
 
 /// Validates operation before added to mempool
 /// Operation is decoded and applied to context according to current head in mempool
-pub fn prevalidate_operation(
+pub fn prevalidate_operation<Runner: ProtocolRunner + 'static>(
     chain_id: &ChainId,
     operation_hash: &OperationHash,
     operation: &Operation,
     current_mempool_state: CurrentMempoolStateStorageRef,
-    api: &ProtocolController,
+    api: &mut ProtocolRunnerConnection<Runner>,
     block_storage: &Box<dyn BlockStorageReader>,
     block_meta_storage: &Box<dyn BlockMetaStorageReader>,
 ) -> Result<ValidateOperationResult, PrevalidateOperationError> {
@@ -280,12 +282,12 @@ pub fn prevalidate_operation(
 /// - checks begin_application, if predecessor
 ///
 /// Returns None if everything, else return error
-pub fn check_multipass_validation(
+pub fn check_multipass_validation<Runner: ProtocolRunner + 'static>(
     chain_id: &ChainId,
     protocol_hash: ProtocolHash,
     validated_block_header: &BlockHeader,
     predecessor: Option<BlockHeaderWithHash>,
-    api: &ProtocolController,
+    api: &mut ProtocolRunnerConnection<Runner>,
 ) -> Option<ProtocolServiceError> {
     // 1. check encoding for protocol_data
     if let Err(e) = api.assert_encoding_for_protocol_data(
@@ -9,6 +9,7 @@ extern crate test;
 /// Runs like: `PROTOCOL_RUNNER=./target/release/protocol-runner cargo test --release -- --ignored`
 use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant, SystemTime};
 
@@ -18,6 +19,7 @@ use serial_test::serial;
 use crypto::hash::OperationHash;
 use networking::ShellCompatibilityVersion;
 use shell::peer_manager::P2p;
+use shell::state::synchronization_state::DEFAULT_SYNCHRONIZATION_LATENCY;
 use shell::PeerConnectionThreshold;
 use storage::tests_common::TmpStorage;
 use storage::{BlockMetaStorage, BlockMetaStorageReader};
@@ -36,12 +38,15 @@ lazy_static! {
     pub static ref NODE_P2P_CFG: (P2p, ShellCompatibilityVersion) = (
         P2p {
             listener_port: *NODE_P2P_PORT,
+            listener_addresses: vec![SocketAddr::new(IpAddr::from([0, 0, 0, 0]), *NODE_P2P_PORT)],
+            discovery_address: None,
             bootstrap_lookup_addresses: vec![],
             disable_bootstrap_lookup: true,
             disable_mempool: false,
             private_node: false,
             bootstrap_peers: vec![],
             peer_threshold: PeerConnectionThreshold::try_new(0, 10, Some(0)).expect("Invalid range"),
+            synchronization_latency: DEFAULT_SYNCHRONIZATION_LATENCY,
         },
         SHELL_COMPATIBILITY_VERSION.clone(),
     );
@@ -90,8 +90,11 @@ pub mod infra {
     use shell::peer_manager::{P2p, PeerManager, PeerManagerRef, WhitelistAllIpAddresses};
     use shell::shell_channel::{ShellChannel, ShellChannelRef, ShellChannelTopic, ShuttingDown};
     use shell::state::head_state::init_current_head_state;
-    use shell::state::synchronization_state::init_synchronization_bootstrap_state_storage;
+    use shell::state::synchronization_state::{
+        init_synchronization_bootstrap_state_storage, DEFAULT_SYNCHRONIZATION_LATENCY,
+    };
     use shell::stats::apply_block_stats::init_empty_apply_block_stats;
+    use shell::stats::block_apply_timings::init_empty_block_apply_timings;
     use shell::PeerConnectionThreshold;
     use storage::chain_meta_storage::ChainMetaStorageReader;
     use storage::context::{ContextApi, TezedgeContext};
@@ -101,7 +104,7 @@ pub mod infra {
     use tezos_api::ffi::{PatchContext, TezosRuntimeConfiguration};
     use tezos_identity::Identity;
     use tezos_wrapper::service::IpcEvtServer;
-    use tezos_wrapper::ProtocolEndpointConfiguration;
+    use tezos_wrapper::{ProtocolEndpointConfiguration, ProtocolRunnerLimits};
     use tezos_wrapper::{TezosApiConnectionPool, TezosApiConnectionPoolConfiguration};
 
     use crate::common;
@@ -167,6 +170,7 @@ pub mod infra {
                     connection_timeout: Duration::from_secs(3),
                     max_lifetime: Duration::from_secs(60),
                     idle_timeout: Duration::from_secs(60),
+                    limits: ProtocolRunnerLimits::default(),
                 },
                 ProtocolEndpointConfiguration::new(
                     TezosRuntimeConfiguration {
@@ -185,7 +189,8 @@ pub mod infra {
             )?);
 
             // create pool for ffi protocol runner connections (used just for readonly context)
-            let apply_protocol_events = IpcEvtServer::try_bind_new()?;
+            let apply_protocol_events =
+                IpcEvtServer::try_bind_new(IpcEvtServer::DEFAULT_TRANSPORT)?;
             let tezos_writeable_api = Arc::new(TezosApiConnectionPool::new_without_context(
                 String::from(&format!("{}_writeable_runner_pool", name)),
                 TezosApiConnectionPoolConfiguration {
@@ -194,6 +199,7 @@ pub mod infra {
                     connection_timeout: Duration::from_secs(3),
                     max_lifetime: Duration::from_secs(60),
                     idle_timeout: Duration::from_secs(60),
+                    limits: ProtocolRunnerLimits::default(),
                 },
                 ProtocolEndpointConfiguration::new(
                     TezosRuntimeConfiguration {
@@ -216,8 +222,10 @@ pub mod infra {
             let current_mempool_state_storage = init_mempool_state_storage();
             let bootstrap_state = init_synchronization_bootstrap_state_storage(
                 p2p_threshold.num_of_peers_for_bootstrap_threshold(),
+                DEFAULT_SYNCHRONIZATION_LATENCY,
             );
             let apply_block_stats = init_empty_apply_block_stats();
+            let block_apply_timings = init_empty_block_apply_timings();
 
             let tokio_runtime = create_tokio_runtime();
 
@@ -238,6 +246,7 @@ pub mod infra {
                 &persistent_storage,
                 vec![],
                 apply_protocol_events,
+                block_apply_timings.clone(),
                 log.clone(),
             )
             .expect("Failed to create context event listener");
@@ -261,6 +270,7 @@ pub mod infra {
                 tezos_writeable_api,
                 init_storage_data.clone(),
                 tezos_env.clone(),
+                block_apply_timings.clone(),
                 log.clone(),
             )
             .expect("Failed to create chain feeder");
@@ -278,6 +288,7 @@ pub mod infra {
                 current_mempool_state_storage.clone(),
                 bootstrap_state,
                 apply_block_stats,
+                block_apply_timings,
                 false,
                 identity.clone(),
             )
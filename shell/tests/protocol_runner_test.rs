@@ -15,9 +15,11 @@ use slog::{error, info, o, warn, Level, Logger};
 use tezos_api::environment::{TezosEnvironmentConfiguration, TEZOS_ENV};
 use tezos_api::ffi::{InitProtocolContextResult, TezosRuntimeConfiguration};
 use tezos_wrapper::runner::{ExecutableProtocolRunner, ProtocolRunner};
-use tezos_wrapper::service::{IpcCmdServer, ProtocolRunnerEndpoint};
+use tezos_wrapper::service::{IpcCmdServer, ProtocolRunnerEndpoint, IPC_PROTOCOL_VERSION};
 use tezos_wrapper::ProtocolEndpointConfiguration;
-use tezos_wrapper::{TezosApiConnectionPool, TezosApiConnectionPoolConfiguration};
+use tezos_wrapper::{
+    ProtocolRunnerLimits, TezosApiConnectionPool, TezosApiConnectionPoolConfiguration,
+};
 
 mod common;
 
@@ -189,6 +191,8 @@ fn test_readonly_protocol_runner_connection_pool() -> Result<(), failure::Error>
         )?;
 
     let mut write_api = write_context_commands.try_accept(Duration::from_secs(3))?;
+    assert_eq!(IPC_PROTOCOL_VERSION, write_api.runner().version);
+    assert!(!write_api.runner().supported_protocols.is_empty());
     let genesis_context_hash = write_api
         .init_protocol_for_write(true, &None)?
         .genesis_commit_hash
@@ -204,6 +208,7 @@ fn test_readonly_protocol_runner_connection_pool() -> Result<(), failure::Error>
         connection_timeout: Duration::from_secs(1),
         max_lifetime: Duration::from_secs(1),
         idle_timeout: Duration::from_secs(1),
+        limits: ProtocolRunnerLimits::default(),
     };
 
     // cfg for protocol runner
@@ -223,7 +228,8 @@ fn test_readonly_protocol_runner_connection_pool() -> Result<(), failure::Error>
 
     // create pool
     let pool_name = "test_pool_with_readonly_context";
-    let pool_wrapper = Arc::new(TezosApiConnectionPool::new_with_readonly_context(
+    let pool_wrapper: Arc<TezosApiConnectionPool> =
+        Arc::new(TezosApiConnectionPool::new_with_readonly_context(
         pool_name.to_string(),
         pool_cfg,
         endpoint_cfg,
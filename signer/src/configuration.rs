@@ -0,0 +1,276 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use crypto::signature::Curve;
+
+use crate::policy::SigningPolicy;
+
+pub const DEFAULT_BASE_DIR: &str = ".tezedge-signer";
+pub const DEFAULT_LISTEN_ADDR: &str = "127.0.0.1:6732";
+/// Password is read from this environment variable, if `--password-file` is not used
+pub const PASSWORD_ENV_VAR: &str = "TEZEDGE_SIGNER_PASSWORD";
+
+pub enum Command {
+    /// Generates new key and stores it encrypted
+    GenKeys {
+        name: String,
+        curve: Curve,
+        force: bool,
+    },
+    /// Imports existing secret key (`unencrypted:edsk...` or just `edsk...`)
+    ImportSecretKey {
+        name: String,
+        secret_key: String,
+        force: bool,
+    },
+    ListKeys,
+    /// Adds public key, which can authenticate signing requests
+    AddAuthorizedKey {
+        name: String,
+        public_key: String,
+        force: bool,
+    },
+    LaunchHttpSigner {
+        listen_addr: SocketAddr,
+        policy: SigningPolicy,
+    },
+}
+
+pub struct SignerEnvironment {
+    pub base_dir: PathBuf,
+    pub log_level: slog::Level,
+    password_file: Option<PathBuf>,
+    pub command: Command,
+}
+
+fn signer_app() -> App<'static, 'static> {
+    let name_arg = Arg::with_name("name")
+        .takes_value(true)
+        .value_name("NAME")
+        .help("Name of the key")
+        .required(true);
+    let force_arg = Arg::with_name("force")
+        .long("force")
+        .short("f")
+        .help("Overwrite existing key with the same name");
+
+    App::new("Tezedge Signer")
+        .version("0.1.0")
+        .author("SimpleStaking and the project contributors")
+        .about("Remote signer compatible with Octez tezos-signer HTTP API")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("base-dir")
+                .long("base-dir")
+                .short("d")
+                .global(true)
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Directory with key store and high watermarks (default: $HOME/.tezedge-signer)"),
+        )
+        .arg(
+            Arg::with_name("password-file")
+                .long("password-file")
+                .global(true)
+                .takes_value(true)
+                .value_name("PATH")
+                .help(
+                    "File with the password of the key store, if not set, environment variable TEZEDGE_SIGNER_PASSWORD is used",
+                ),
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .long("log-level")
+                .global(true)
+                .takes_value(true)
+                .value_name("LEVEL")
+                .possible_values(&["critical", "error", "warn", "info", "debug", "trace"])
+                .help("Set log level"),
+        )
+        .subcommand(
+            SubCommand::with_name("gen-keys")
+                .about("Generate new key")
+                .arg(name_arg.clone())
+                .arg(
+                    Arg::with_name("sig")
+                        .long("sig")
+                        .short("s")
+                        .takes_value(true)
+                        .value_name("CURVE")
+                        .possible_values(&["ed25519", "secp256k1", "p256"])
+                        .default_value("ed25519")
+                        .help("Signature algorithm"),
+                )
+                .arg(force_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("import-secret-key")
+                .about("Import existing secret key")
+                .arg(name_arg.clone())
+                .arg(
+                    Arg::with_name("secret-key")
+                        .takes_value(true)
+                        .value_name("SECRET_KEY")
+                        .help("Secret key, e.g. unencrypted:edsk...")
+                        .required(true),
+                )
+                .arg(force_arg.clone()),
+        )
+        .subcommand(SubCommand::with_name("list-keys").about("List all stored keys"))
+        .subcommand(
+            SubCommand::with_name("add-authorized-key")
+                .about("Authorize key to sign signing requests")
+                .arg(
+                    Arg::with_name("public-key")
+                        .takes_value(true)
+                        .value_name("PUBLIC_KEY")
+                        .help("Public key, e.g. edpk...")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("name")
+                        .long("name")
+                        .takes_value(true)
+                        .value_name("NAME")
+                        .help("Name of the authorized key (default: public key hash)"),
+                )
+                .arg(force_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("launch-http-signer")
+                .about("Launch signer with Octez compatible HTTP API")
+                .arg(
+                    Arg::with_name("listen-addr")
+                        .long("listen-addr")
+                        .short("a")
+                        .takes_value(true)
+                        .value_name("IP:PORT")
+                        .default_value(DEFAULT_LISTEN_ADDR)
+                        .help("Address the HTTP server listens on")
+                        .validator(|v| {
+                            v.parse::<SocketAddr>()
+                                .map(|_| ())
+                                .map_err(|e| format!("Invalid listen address '{}': {}", v, e))
+                        }),
+                )
+                .arg(
+                    Arg::with_name("magic-bytes")
+                        .long("magic-bytes")
+                        .short("M")
+                        .takes_value(true)
+                        .value_name("0xHH,0xHH,...")
+                        .help("Only data starting with one of these magic bytes can be signed, e.g. 0x01,0x02 for baking and endorsing")
+                        .validator(|v| parse_magic_bytes(&v).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("check-high-watermark")
+                        .long("check-high-watermark")
+                        .short("W")
+                        .help("Refuse to sign blocks and endorsements at or below already signed level (double baking/endorsing protection)"),
+                )
+                .arg(
+                    Arg::with_name("require-authentication")
+                        .long("require-authentication")
+                        .short("A")
+                        .help("Signing requests have to be authenticated by one of the authorized keys"),
+                ),
+        )
+}
+
+fn parse_magic_bytes(value: &str) -> Result<Vec<u8>, String> {
+    value
+        .split(',')
+        .map(|byte| {
+            let byte = byte.trim();
+            u8::from_str_radix(byte.trim_start_matches("0x"), 16)
+                .map_err(|e| format!("Invalid magic byte '{}': {}", byte, e))
+        })
+        .collect()
+}
+
+impl SignerEnvironment {
+    pub fn from_args() -> Self {
+        let app = signer_app();
+        let args = app.clone().get_matches();
+
+        let base_dir = match args.value_of("base-dir") {
+            Some(base_dir) => PathBuf::from(base_dir),
+            None => env::var("HOME")
+                .map(PathBuf::from)
+                .unwrap_or_default()
+                .join(DEFAULT_BASE_DIR),
+        };
+        let command = match args.subcommand() {
+            ("gen-keys", Some(args)) => Command::GenKeys {
+                name: value(args, "name"),
+                curve: match args.value_of("sig") {
+                    Some("secp256k1") => Curve::Secp256k1,
+                    Some("p256") => Curve::P256,
+                    _ => Curve::Ed25519,
+                },
+                force: args.is_present("force"),
+            },
+            ("import-secret-key", Some(args)) => Command::ImportSecretKey {
+                name: value(args, "name"),
+                secret_key: value(args, "secret-key"),
+                force: args.is_present("force"),
+            },
+            ("list-keys", _) => Command::ListKeys,
+            ("add-authorized-key", Some(args)) => Command::AddAuthorizedKey {
+                name: args.value_of("name").unwrap_or("").to_string(),
+                public_key: value(args, "public-key"),
+                force: args.is_present("force"),
+            },
+            ("launch-http-signer", Some(args)) => Command::LaunchHttpSigner {
+                listen_addr: value(args, "listen-addr")
+                    .parse::<SocketAddr>()
+                    .expect("Was expecting valid listen-addr"),
+                policy: SigningPolicy {
+                    allowed_magic_bytes: args
+                        .value_of("magic-bytes")
+                        .map(|v| parse_magic_bytes(v).expect("Was expecting valid magic-bytes")),
+                    check_high_watermark: args.is_present("check-high-watermark"),
+                    require_authentication: args.is_present("require-authentication"),
+                },
+            },
+            _ => unreachable!("Subcommand is required"),
+        };
+
+        SignerEnvironment {
+            base_dir,
+            log_level: args
+                .value_of("log-level")
+                .unwrap_or("info")
+                .parse::<slog::Level>()
+                .expect("Was expecting one value from slog::Level"),
+            password_file: args.value_of("password-file").map(PathBuf::from),
+            command,
+        }
+    }
+
+    /// Reads password from `--password-file` or from the environment variable.
+    pub fn password(&self) -> Result<Vec<u8>, failure::Error> {
+        match &self.password_file {
+            Some(path) => Ok(fs::read_to_string(path)?.trim_end().as_bytes().to_vec()),
+            None => env::var(PASSWORD_ENV_VAR)
+                .map(String::into_bytes)
+                .map_err(|_| {
+                    failure::format_err!(
+                        "Key store password is required, use --password-file or {} environment variable",
+                        PASSWORD_ENV_VAR
+                    )
+                }),
+        }
+    }
+}
+
+fn value(args: &ArgMatches, name: &str) -> String {
+    args.value_of(name).unwrap_or("").to_string()
+}
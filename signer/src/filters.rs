@@ -0,0 +1,72 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use slog::Logger;
+use warp::Filter;
+
+use crate::handlers::{authorized_keys, get_public_key, handle_rejection, sign, SignQuery};
+use crate::service::SignerRef;
+
+/// Remote signer API compatible with Octez `tezos-signer launch http signer`
+pub fn signer(
+    log: Logger,
+    signer: SignerRef,
+) -> impl Filter<Extract = impl warp::Reply, Error = std::convert::Infallible> + Clone {
+    public_key(log.clone(), signer.clone())
+        .or(sign_data(log.clone(), signer.clone()))
+        .or(authorized(log.clone(), signer))
+        .recover(move |rejection| handle_rejection(rejection, log.clone()))
+}
+
+pub fn public_key(
+    log: Logger,
+    signer: SignerRef,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("keys" / String)
+        .and(warp::get())
+        .and(with_log(log))
+        .and(with_signer(signer))
+        .and_then(get_public_key)
+}
+
+pub fn sign_data(
+    log: Logger,
+    signer: SignerRef,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("keys" / String)
+        .and(warp::post())
+        .and(warp::query::<SignQuery>())
+        .and(data_json_body())
+        .and(with_log(log))
+        .and(with_signer(signer))
+        .and_then(sign)
+}
+
+pub fn authorized(
+    log: Logger,
+    signer: SignerRef,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("authorized_keys")
+        .and(warp::get())
+        .and(with_log(log))
+        .and(with_signer(signer))
+        .and_then(authorized_keys)
+}
+
+fn data_json_body() -> impl Filter<Extract = (String,), Error = warp::Rejection> + Clone {
+    // Signed data are sent as JSON string with hex encoded bytes,
+    // bodies larger than 1 MiB are rejected before deserialization
+    warp::body::content_length_limit(1024 * 1024).and(warp::body::json())
+}
+
+fn with_log(
+    log: Logger,
+) -> impl Filter<Extract = (Logger,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || log.clone())
+}
+
+fn with_signer(
+    signer: SignerRef,
+) -> impl Filter<Extract = (SignerRef,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || signer.clone())
+}
@@ -0,0 +1,58 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+/// Replaces the file at `path` with `content`, so it is never left half written, even after crash.
+///
+/// Content is written and synced to a temporary file (readable only by the owner),
+/// which is then renamed over `path`, the rename is persisted by syncing the parent directory.
+pub fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let mut tmp_file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp_path)?;
+    tmp_file.write_all(content)?;
+    tmp_file.sync_all()?;
+    drop(tmp_file);
+
+    fs::rename(&tmp_path, path)?;
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    #[test]
+    fn test_write_atomically_replaces_content_and_restricts_permissions() -> io::Result<()> {
+        let dir = env::temp_dir().join("signer_test_write_atomically");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("secret_keys.json");
+
+        write_atomically(&path, b"first")?;
+        write_atomically(&path, b"second")?;
+
+        assert_eq!(fs::read(&path)?, b"second");
+        assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+        assert!(!dir.join("secret_keys.json.tmp").exists());
+
+        fs::remove_dir_all(&dir)
+    }
+}
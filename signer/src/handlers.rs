@@ -0,0 +1,153 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::convert::Infallible;
+
+use serde::{Deserialize, Serialize};
+use slog::{info, warn, Logger};
+use warp::http::StatusCode;
+use warp::{reject, Rejection, Reply};
+
+use crypto::signature::Signature;
+
+use crate::service::{SignerError, SignerRef};
+
+impl reject::Reject for SignerError {}
+
+#[derive(Debug, Deserialize)]
+pub struct SignQuery {
+    /// Base58check encoded signature of the request by one of the authorized keys
+    authentication: Option<String>,
+}
+
+/// Error in the format of Octez RPC errors, so `tezos-client` can display it
+#[derive(Debug, Serialize)]
+struct ErrorMessage {
+    kind: &'static str,
+    id: String,
+    msg: String,
+}
+
+impl ErrorMessage {
+    fn new(id: &str, msg: String) -> Vec<Self> {
+        vec![Self {
+            kind: "permanent",
+            id: format!("signer.{}", id),
+            msg,
+        }]
+    }
+}
+
+/// Handler for GET /keys/:pkh
+pub async fn get_public_key(
+    public_key_hash: String,
+    log: Logger,
+    signer: SignerRef,
+) -> Result<impl warp::Reply, reject::Rejection> {
+    info!(log, "Request for public key"; "public_key_hash" => &public_key_hash);
+
+    let public_key = signer.public_key(&public_key_hash)?;
+    Ok(warp::reply::json(&serde_json::json!({
+        "public_key": public_key.to_base58_check()
+    })))
+}
+
+/// Handler for POST /keys/:pkh, body is JSON string with hex encoded data
+pub async fn sign(
+    public_key_hash: String,
+    query: SignQuery,
+    data: String,
+    log: Logger,
+    signer: SignerRef,
+) -> Result<impl warp::Reply, reject::Rejection> {
+    info!(log, "Request for signature"; "public_key_hash" => &public_key_hash, "data" => &data);
+
+    let data = match hex::decode(data.trim_start_matches("0x")) {
+        Ok(data) => data,
+        Err(e) => {
+            return Ok(error_reply(
+                StatusCode::BAD_REQUEST,
+                "invalid_data",
+                format!("Data is not valid hex string: {}", e),
+            ))
+        }
+    };
+    let authentication = match query.authentication {
+        Some(authentication) => Some(
+            Signature::from_base58_check(&authentication).map_err(|_| SignerError::Unauthorized)?,
+        ),
+        None => None,
+    };
+
+    let signature = signer.sign(&public_key_hash, &data, authentication.as_ref())?;
+    info!(log, "Data signed"; "public_key_hash" => &public_key_hash, "signature" => signature.to_base58_check());
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "signature": signature.to_base58_check()
+        })),
+        StatusCode::OK,
+    ))
+}
+
+/// Handler for GET /authorized_keys
+pub async fn authorized_keys(
+    log: Logger,
+    signer: SignerRef,
+) -> Result<impl warp::Reply, reject::Rejection> {
+    info!(log, "Request for authorized keys");
+
+    let reply = match signer.authorized_keys()? {
+        Some(keys) => serde_json::json!({
+            "authorized_keys": keys.iter().map(|key| key.to_base58_check()).collect::<Vec<_>>()
+        }),
+        None => serde_json::json!({}),
+    };
+    Ok(warp::reply::json(&reply))
+}
+
+pub async fn handle_rejection(err: Rejection, log: Logger) -> Result<impl Reply, Infallible> {
+    let (code, id, msg) = if err.is_not_found() {
+        (
+            StatusCode::NOT_FOUND,
+            "not_found",
+            "rpc not found".to_string(),
+        )
+    } else if let Some(e) = err.find::<warp::filters::body::BodyDeserializeError>() {
+        (
+            StatusCode::BAD_REQUEST,
+            "invalid_data",
+            format!("Request deserialization error: {}", e),
+        )
+    } else if let Some(e) = err.find::<warp::reject::UnsupportedMediaType>() {
+        (StatusCode::BAD_REQUEST, "invalid_data", format!("{}", e))
+    } else if let Some(e) = err.find::<SignerError>() {
+        let (code, id) = match e {
+            SignerError::UnknownKey { .. } => (StatusCode::NOT_FOUND, "unknown_key"),
+            SignerError::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized"),
+            SignerError::Policy(_) => (StatusCode::FORBIDDEN, "policy"),
+            SignerError::HighWatermark(_) => (StatusCode::FORBIDDEN, "high_watermark"),
+            SignerError::Signature(_) | SignerError::LockError { .. } => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal")
+            }
+        };
+        (code, id, format!("{}", e))
+    } else {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal",
+            format!("unhandled error occurred: {:?}", err),
+        )
+    };
+    warn!(log, "Signer request failed"; "code" => code.as_u16(), "message" => &msg);
+
+    Ok(error_reply(code, id, msg))
+}
+
+fn error_reply(
+    code: StatusCode,
+    id: &str,
+    msg: String,
+) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(warp::reply::json(&ErrorMessage::new(id, msg)), code)
+}
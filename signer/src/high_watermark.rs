@@ -0,0 +1,252 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! High watermarks protect bakers against double baking and double endorsing.
+//!
+//! For every chain, kind of signed data and key the highest signed level is persisted
+//! together with the hash of the signed data and its signature. The signer then refuses
+//! to sign anything below this level and anything different at the same level,
+//! the very same data is answered with the stored signature.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::Fail;
+use serde::{Deserialize, Serialize};
+
+use crypto::hash::ChainId;
+use crypto::signature::Signature;
+
+use crate::fs_util::write_atomically;
+
+pub const HIGH_WATERMARK_FILE: &str = "high_watermarks.json";
+
+#[derive(Debug, Fail)]
+pub enum HighWatermarkError {
+    #[fail(display = "High watermark I/O error: {}", reason)]
+    IoError { reason: String },
+    #[fail(display = "Invalid high watermark file, reason: {}", reason)]
+    InvalidFile { reason: String },
+    #[fail(
+        display = "{:?} level {} below high watermark {} for {}",
+        kind, level, current, public_key_hash
+    )]
+    LevelTooLow {
+        kind: SignedKind,
+        public_key_hash: String,
+        level: i32,
+        current: i32,
+    },
+    #[fail(
+        display = "{:?} level {} already signed with different data for {}",
+        kind, level, public_key_hash
+    )]
+    DifferentData {
+        kind: SignedKind,
+        public_key_hash: String,
+        level: i32,
+    },
+}
+
+impl From<std::io::Error> for HighWatermarkError {
+    fn from(error: std::io::Error) -> Self {
+        HighWatermarkError::IoError {
+            reason: format!("{}", error),
+        }
+    }
+}
+
+impl From<serde_json::Error> for HighWatermarkError {
+    fn from(error: serde_json::Error) -> Self {
+        HighWatermarkError::InvalidFile {
+            reason: format!("{}", error),
+        }
+    }
+}
+
+/// Kind of signed data protected by high watermark
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignedKind {
+    Block,
+    Endorsement,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HighWatermark {
+    pub level: i32,
+    /// Hex encoded blake2b hash of the signed data
+    pub hash: String,
+    pub signature: String,
+}
+
+/// chain id (base58check) -> public key hash -> high watermark
+type HighWatermarksByChain = HashMap<String, HashMap<String, HighWatermark>>;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct HighWatermarksContent {
+    block: HighWatermarksByChain,
+    endorsement: HighWatermarksByChain,
+}
+
+pub struct HighWatermarks {
+    path: PathBuf,
+    content: HighWatermarksContent,
+}
+
+impl HighWatermarks {
+    pub fn open(base_dir: &Path) -> Result<Self, HighWatermarkError> {
+        let path = base_dir.join(HIGH_WATERMARK_FILE);
+        let content = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            HighWatermarksContent::default()
+        };
+        Ok(Self { path, content })
+    }
+
+    pub fn get(
+        &self,
+        kind: SignedKind,
+        chain_id: &ChainId,
+        public_key_hash: &str,
+    ) -> Option<&HighWatermark> {
+        self.by_kind(kind)
+            .get(&chain_id.to_base58_check())
+            .and_then(|by_key| by_key.get(public_key_hash))
+    }
+
+    /// Checks if data with `hash` can be signed at `level`.
+    ///
+    /// Returns already stored signature, if exactly the same data was signed before.
+    pub fn check(
+        &self,
+        kind: SignedKind,
+        chain_id: &ChainId,
+        public_key_hash: &str,
+        level: i32,
+        hash: &[u8],
+    ) -> Result<Option<Signature>, HighWatermarkError> {
+        let current = match self.get(kind, chain_id, public_key_hash) {
+            Some(current) => current,
+            None => return Ok(None),
+        };
+
+        match level.cmp(&current.level) {
+            Ordering::Less => Err(HighWatermarkError::LevelTooLow {
+                kind,
+                public_key_hash: public_key_hash.to_string(),
+                level,
+                current: current.level,
+            }),
+            Ordering::Equal if current.hash == hex::encode(hash) => {
+                Signature::from_base58_check(&current.signature)
+                    .map(Some)
+                    .map_err(|e| HighWatermarkError::InvalidFile {
+                        reason: format!("{}", e),
+                    })
+            }
+            Ordering::Equal => Err(HighWatermarkError::DifferentData {
+                kind,
+                public_key_hash: public_key_hash.to_string(),
+                level,
+            }),
+            Ordering::Greater => Ok(None),
+        }
+    }
+
+    /// Stores new high watermark, the file is written before the signature is returned to the client.
+    pub fn update(
+        &mut self,
+        kind: SignedKind,
+        chain_id: &ChainId,
+        public_key_hash: &str,
+        level: i32,
+        hash: &[u8],
+        signature: &Signature,
+    ) -> Result<(), HighWatermarkError> {
+        let high_watermark = HighWatermark {
+            level,
+            hash: hex::encode(hash),
+            signature: signature.to_base58_check(),
+        };
+        let by_kind = match kind {
+            SignedKind::Block => &mut self.content.block,
+            SignedKind::Endorsement => &mut self.content.endorsement,
+        };
+        by_kind
+            .entry(chain_id.to_base58_check())
+            .or_insert_with(HashMap::new)
+            .insert(public_key_hash.to_string(), high_watermark);
+        self.save()
+    }
+
+    fn by_kind(&self, kind: SignedKind) -> &HighWatermarksByChain {
+        match kind {
+            SignedKind::Block => &self.content.block,
+            SignedKind::Endorsement => &self.content.endorsement,
+        }
+    }
+
+    fn save(&self) -> Result<(), HighWatermarkError> {
+        let content = serde_json::to_string_pretty(&self.content)?;
+        write_atomically(&self.path, content.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crypto::signature::SIGNATURE_SIZE;
+
+    use super::*;
+
+    #[test]
+    fn test_high_watermark_rules() -> Result<(), failure::Error> {
+        let base_dir = env::temp_dir().join("tezedge-signer-high-watermark-test");
+        let _ = fs::remove_dir_all(&base_dir);
+
+        let chain_id = ChainId::from_base58_check("NetXdQprcVkpaWU")?;
+        let pkh = "tz1KqTpEZ7Yob7QbPE4Hy4Wo8fHG8LhKxZSx";
+        let signature = Signature::Ed25519([1; SIGNATURE_SIZE]);
+
+        let mut high_watermarks = HighWatermarks::open(&base_dir)?;
+        assert_eq!(
+            None,
+            high_watermarks.check(SignedKind::Block, &chain_id, pkh, 10, &[1])?
+        );
+        high_watermarks.update(SignedKind::Block, &chain_id, pkh, 10, &[1], &signature)?;
+
+        // persisted
+        let high_watermarks = HighWatermarks::open(&base_dir)?;
+        // the same data at the same level
+        assert_eq!(
+            Some(signature),
+            high_watermarks.check(SignedKind::Block, &chain_id, pkh, 10, &[1])?
+        );
+        // different data at the same level
+        assert!(high_watermarks
+            .check(SignedKind::Block, &chain_id, pkh, 10, &[2])
+            .is_err());
+        // lower level
+        assert!(high_watermarks
+            .check(SignedKind::Block, &chain_id, pkh, 9, &[1])
+            .is_err());
+        // higher level
+        assert_eq!(
+            None,
+            high_watermarks.check(SignedKind::Block, &chain_id, pkh, 11, &[2])?
+        );
+        // endorsements are independent of blocks
+        assert_eq!(
+            None,
+            high_watermarks.check(SignedKind::Endorsement, &chain_id, pkh, 9, &[1])?
+        );
+
+        fs::remove_dir_all(&base_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Local key store, secret keys are encrypted with a key derived from the password (argon2id)
+//! and never leave the process unencrypted.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::Fail;
+use serde::{Deserialize, Serialize};
+use sodiumoxide::crypto::pwhash::argon2id13;
+use sodiumoxide::crypto::secretbox;
+
+use crypto::signature::{PublicKey, SecretKey, SignatureError};
+
+use crate::fs_util::write_atomically;
+
+pub const KEY_STORE_FILE: &str = "secret_keys.json";
+
+#[derive(Debug, Fail)]
+pub enum KeyStoreError {
+    #[fail(display = "Key store I/O error: {}", reason)]
+    IoError { reason: String },
+    #[fail(display = "Invalid key store file, reason: {}", reason)]
+    InvalidFile { reason: String },
+    #[fail(display = "Key with name '{}' already exists", name)]
+    AlreadyExists { name: String },
+    #[fail(display = "Failed to decrypt key '{}', invalid password?", name)]
+    InvalidPassword { name: String },
+    #[fail(display = "Failed to encrypt key, reason: {}", reason)]
+    EncryptionError { reason: String },
+    #[fail(display = "Invalid key: {}", _0)]
+    InvalidKey(SignatureError),
+}
+
+impl From<std::io::Error> for KeyStoreError {
+    fn from(error: std::io::Error) -> Self {
+        KeyStoreError::IoError {
+            reason: format!("{}", error),
+        }
+    }
+}
+
+impl From<serde_json::Error> for KeyStoreError {
+    fn from(error: serde_json::Error) -> Self {
+        KeyStoreError::InvalidFile {
+            reason: format!("{}", error),
+        }
+    }
+}
+
+impl From<SignatureError> for KeyStoreError {
+    fn from(error: SignatureError) -> Self {
+        KeyStoreError::InvalidKey(error)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredKey {
+    pub name: String,
+    pub public_key_hash: String,
+    pub public_key: String,
+    encrypted_secret_key: EncryptedSecretKey,
+}
+
+/// Base58check encoded secret key encrypted with `secretbox`, all values are hex encoded
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct EncryptedSecretKey {
+    salt: String,
+    nonce: String,
+    data: String,
+}
+
+/// Public key allowed to authenticate signing requests
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthorizedKey {
+    pub name: String,
+    pub public_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct KeyStoreContent {
+    keys: Vec<StoredKey>,
+    authorized_keys: Vec<AuthorizedKey>,
+}
+
+pub struct KeyStore {
+    path: PathBuf,
+    content: KeyStoreContent,
+}
+
+impl KeyStore {
+    /// Opens key store in `base_dir`, empty key store is created if it does not exist yet.
+    pub fn open(base_dir: &Path) -> Result<Self, KeyStoreError> {
+        let path = base_dir.join(KEY_STORE_FILE);
+        let content = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            KeyStoreContent::default()
+        };
+        Ok(Self { path, content })
+    }
+
+    pub fn keys(&self) -> &[StoredKey] {
+        &self.content.keys
+    }
+
+    pub fn authorized_keys(&self) -> &[AuthorizedKey] {
+        &self.content.authorized_keys
+    }
+
+    /// Encrypts and stores the secret key, existing key with the same name is replaced only if `force` is set.
+    pub fn add_key(
+        &mut self,
+        name: &str,
+        secret_key: &SecretKey,
+        password: &[u8],
+        force: bool,
+    ) -> Result<&StoredKey, KeyStoreError> {
+        remove_existing(&mut self.content.keys, name, force, |key| &key.name)?;
+
+        let public_key = secret_key.public_key();
+        self.content.keys.push(StoredKey {
+            name: name.to_string(),
+            public_key_hash: public_key.public_key_hash()?.to_base58_check(),
+            public_key: public_key.to_base58_check(),
+            encrypted_secret_key: encrypt(secret_key, password)?,
+        });
+        self.save()?;
+
+        Ok(&self.content.keys[self.content.keys.len() - 1])
+    }
+
+    pub fn add_authorized_key(
+        &mut self,
+        name: &str,
+        public_key: &PublicKey,
+        force: bool,
+    ) -> Result<(), KeyStoreError> {
+        remove_existing(&mut self.content.authorized_keys, name, force, |key| {
+            &key.name
+        })?;
+
+        self.content.authorized_keys.push(AuthorizedKey {
+            name: name.to_string(),
+            public_key: public_key.to_base58_check(),
+        });
+        self.save()
+    }
+
+    /// Decrypts all secret keys, returns them indexed by public key hash.
+    pub fn unlock(&self, password: &[u8]) -> Result<HashMap<String, SecretKey>, KeyStoreError> {
+        self.content
+            .keys
+            .iter()
+            .map(|key| {
+                let secret_key = decrypt(&key.encrypted_secret_key, password).ok_or_else(|| {
+                    KeyStoreError::InvalidPassword {
+                        name: key.name.clone(),
+                    }
+                })?;
+                if secret_key.public_key().to_base58_check() != key.public_key {
+                    return Err(KeyStoreError::InvalidFile {
+                        reason: format!("secret key '{}' does not match its public key", key.name),
+                    });
+                }
+                Ok((key.public_key_hash.clone(), secret_key))
+            })
+            .collect()
+    }
+
+    pub fn parsed_authorized_keys(&self) -> Result<Vec<PublicKey>, KeyStoreError> {
+        self.content
+            .authorized_keys
+            .iter()
+            .map(|key| PublicKey::from_base58_check(&key.public_key).map_err(|e| e.into()))
+            .collect()
+    }
+
+    /// Writes the whole key store atomically, so it is never left half written.
+    fn save(&self) -> Result<(), KeyStoreError> {
+        let content = serde_json::to_string_pretty(&self.content)?;
+        write_atomically(&self.path, content.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn remove_existing<T>(
+    items: &mut Vec<T>,
+    name: &str,
+    force: bool,
+    item_name: impl Fn(&T) -> &str,
+) -> Result<(), KeyStoreError> {
+    if items.iter().any(|item| item_name(item) == name) {
+        if !force {
+            return Err(KeyStoreError::AlreadyExists {
+                name: name.to_string(),
+            });
+        }
+        items.retain(|item| item_name(item) != name);
+    }
+    Ok(())
+}
+
+fn derive_key(password: &[u8], salt: &argon2id13::Salt) -> Result<secretbox::Key, KeyStoreError> {
+    let mut key = [0; secretbox::KEYBYTES];
+    argon2id13::derive_key(
+        &mut key,
+        password,
+        salt,
+        argon2id13::OPSLIMIT_INTERACTIVE,
+        argon2id13::MEMLIMIT_INTERACTIVE,
+    )
+    .map_err(|_| KeyStoreError::EncryptionError {
+        reason: "failed to derive key from password".to_string(),
+    })?;
+    Ok(secretbox::Key(key))
+}
+
+fn encrypt(secret_key: &SecretKey, password: &[u8]) -> Result<EncryptedSecretKey, KeyStoreError> {
+    let salt = argon2id13::gen_salt();
+    let nonce = secretbox::gen_nonce();
+    let key = derive_key(password, &salt)?;
+    let data = secretbox::seal(secret_key.to_base58_check().as_bytes(), &nonce, &key);
+
+    Ok(EncryptedSecretKey {
+        salt: hex::encode(salt.as_ref()),
+        nonce: hex::encode(nonce.as_ref()),
+        data: hex::encode(data),
+    })
+}
+
+fn decrypt(encrypted: &EncryptedSecretKey, password: &[u8]) -> Option<SecretKey> {
+    let salt = argon2id13::Salt::from_slice(&hex::decode(&encrypted.salt).ok()?)?;
+    let nonce = secretbox::Nonce::from_slice(&hex::decode(&encrypted.nonce).ok()?)?;
+    let key = derive_key(password, &salt).ok()?;
+    let data = secretbox::open(&hex::decode(&encrypted.data).ok()?, &nonce, &key).ok()?;
+
+    SecretKey::from_base58_check(std::str::from_utf8(&data).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crypto::signature::Curve;
+
+    use super::*;
+
+    #[test]
+    fn test_key_store_round_trip() -> Result<(), failure::Error> {
+        let base_dir = env::temp_dir().join("tezedge-signer-key-store-test");
+        let _ = fs::remove_dir_all(&base_dir);
+
+        let secret_key =
+            SecretKey::from_base58_check("edsk3gUfUPyBSfrS9CCgmCiQsTCHGkviBDusMxDJstFtojtc1zcpsh")?;
+        let mut key_store = KeyStore::open(&base_dir)?;
+        let stored = key_store.add_key("bootstrap1", &secret_key, b"password", false)?;
+        assert_eq!(
+            "tz1KqTpEZ7Yob7QbPE4Hy4Wo8fHG8LhKxZSx",
+            stored.public_key_hash
+        );
+        assert!(key_store
+            .add_key(
+                "bootstrap1",
+                &SecretKey::generate(Curve::P256),
+                b"password",
+                false
+            )
+            .is_err());
+        key_store.add_key(
+            "other",
+            &SecretKey::generate(Curve::Secp256k1),
+            b"password",
+            false,
+        )?;
+
+        // secret key is not stored in plain text
+        let content = fs::read_to_string(base_dir.join(KEY_STORE_FILE))?;
+        assert!(!content.contains(&secret_key.to_base58_check()));
+
+        let key_store = KeyStore::open(&base_dir)?;
+        assert_eq!(2, key_store.keys().len());
+        let keys = key_store.unlock(b"password")?;
+        assert_eq!(
+            Some(&secret_key),
+            keys.get("tz1KqTpEZ7Yob7QbPE4Hy4Wo8fHG8LhKxZSx")
+        );
+        assert!(key_store.unlock(b"wrong password").is_err());
+
+        fs::remove_dir_all(&base_dir)?;
+        Ok(())
+    }
+}
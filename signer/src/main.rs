@@ -0,0 +1,129 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+#![forbid(unsafe_code)]
+
+//! Remote signer compatible with Octez `tezos-signer` HTTP API.
+
+use std::sync::Arc;
+
+use slog::{error, info, Drain, Level, Logger};
+
+use crypto::signature::{PublicKey, SecretKey};
+
+use crate::configuration::{Command, SignerEnvironment};
+use crate::high_watermark::HighWatermarks;
+use crate::key_store::KeyStore;
+use crate::service::Signer;
+
+mod configuration;
+mod filters;
+mod fs_util;
+mod handlers;
+mod high_watermark;
+mod key_store;
+mod policy;
+mod service;
+
+#[tokio::main]
+async fn main() {
+    // parse and validate program arguments
+    let env = SignerEnvironment::from_args();
+
+    // create an slog logger
+    let log = create_logger(env.log_level);
+
+    if let Err(e) = run(env, &log).await {
+        error!(log, "Signer failed"; "reason" => format!("{}", e));
+        std::process::exit(1);
+    }
+}
+
+async fn run(env: SignerEnvironment, log: &Logger) -> Result<(), failure::Error> {
+    // libsodium must be initialized before keys are encrypted/decrypted
+    sodiumoxide::init().map_err(|_| failure::format_err!("Failed to initialize libsodium"))?;
+
+    let mut key_store = KeyStore::open(&env.base_dir)?;
+
+    match &env.command {
+        Command::GenKeys { name, curve, force } => {
+            let secret_key = SecretKey::generate(*curve);
+            let stored = key_store.add_key(name, &secret_key, &env.password()?, *force)?;
+            info!(log, "Key generated"; "name" => name, "public_key_hash" => &stored.public_key_hash);
+        }
+        Command::ImportSecretKey {
+            name,
+            secret_key,
+            force,
+        } => {
+            let secret_key =
+                SecretKey::from_base58_check(secret_key.trim_start_matches("unencrypted:"))?;
+            let stored = key_store.add_key(name, &secret_key, &env.password()?, *force)?;
+            info!(log, "Key imported"; "name" => name, "public_key_hash" => &stored.public_key_hash);
+        }
+        Command::ListKeys => {
+            for key in key_store.keys() {
+                println!("{}: {} ({})", key.name, key.public_key_hash, key.public_key);
+            }
+            for key in key_store.authorized_keys() {
+                println!("authorized {}: {}", key.name, key.public_key);
+            }
+        }
+        Command::AddAuthorizedKey {
+            name,
+            public_key,
+            force,
+        } => {
+            let public_key = PublicKey::from_base58_check(public_key)?;
+            let name = if name.is_empty() {
+                public_key.public_key_hash()?.to_base58_check()
+            } else {
+                name.clone()
+            };
+            key_store.add_authorized_key(&name, &public_key, *force)?;
+            info!(log, "Authorized key added"; "name" => name);
+        }
+        Command::LaunchHttpSigner {
+            listen_addr,
+            policy,
+        } => {
+            let keys = key_store.unlock(&env.password()?)?;
+            let authorized_keys = key_store.parsed_authorized_keys()?;
+            if policy.require_authentication && authorized_keys.is_empty() {
+                failure::bail!("Authentication is required, but there are no authorized keys");
+            }
+            let high_watermarks = HighWatermarks::open(&env.base_dir)?;
+            info!(log, "Keys unlocked"; "count" => keys.len(), "authorized_keys" => authorized_keys.len());
+
+            let signer = Arc::new(Signer::new(
+                keys,
+                authorized_keys,
+                policy.clone(),
+                high_watermarks,
+            ));
+
+            info!(log, "Start to serving signer RPCs"; "listen_addr" => listen_addr.to_string(),
+                       "magic_bytes" => format!("{:?}", policy.allowed_magic_bytes),
+                       "check_high_watermark" => policy.check_high_watermark,
+                       "require_authentication" => policy.require_authentication);
+            warp::serve(filters::signer(log.clone(), signer))
+                .run(*listen_addr)
+                .await;
+        }
+    }
+    Ok(())
+}
+
+/// Creates a slog Logger
+fn create_logger(level: Level) -> Logger {
+    let drain = slog_async::Async::new(
+        slog_term::FullFormat::new(slog_term::TermDecorator::new().build())
+            .build()
+            .fuse(),
+    )
+    .chan_size(32768)
+    .overflow_strategy(slog_async::OverflowStrategy::Block)
+    .build()
+    .filter_level(level)
+    .fuse();
+    Logger::root(drain, slog::o!())
+}
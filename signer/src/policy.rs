@@ -0,0 +1,182 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Signing policies - which data is allowed to be signed, based on its magic byte.
+
+use std::convert::TryFrom;
+
+use failure::Fail;
+
+use crypto::hash::ChainId;
+
+use crate::high_watermark::SignedKind;
+
+pub const BLOCK_MAGIC_BYTE: u8 = 0x01;
+pub const ENDORSEMENT_MAGIC_BYTE: u8 = 0x02;
+
+/// magic byte (1) + chain id (4)
+const CHAIN_ID_OFFSET: usize = 1;
+const CHAIN_ID_SIZE: usize = 4;
+/// level of the block header follows chain id
+const BLOCK_LEVEL_OFFSET: usize = CHAIN_ID_OFFSET + CHAIN_ID_SIZE;
+/// operation branch (32) follows chain id, then operation contents tag (1)
+const ENDORSEMENT_TAG_OFFSET: usize = CHAIN_ID_OFFSET + CHAIN_ID_SIZE + 32;
+const ENDORSEMENT_TAG: u8 = 0;
+/// endorsement with slot (proto 008) wraps the whole endorsement operation with dynamic size (4),
+/// branch (32) and its own tag (1)
+const ENDORSEMENT_WITH_SLOT_TAG: u8 = 10;
+
+#[derive(Debug, Fail)]
+pub enum PolicyError {
+    #[fail(display = "Cannot sign empty data")]
+    EmptyData,
+    #[fail(
+        display = "Magic byte 0x{:02x} is not allowed, allowed magic bytes: {}",
+        magic_byte, allowed
+    )]
+    MagicByteNotAllowed { magic_byte: u8, allowed: String },
+    #[fail(display = "Invalid {:?} data, reason: {}", kind, reason)]
+    InvalidData { kind: SignedKind, reason: String },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SigningPolicy {
+    /// If set, only data starting with one of these bytes can be signed
+    pub allowed_magic_bytes: Option<Vec<u8>>,
+    /// Refuse to sign blocks and endorsements below high watermark
+    pub check_high_watermark: bool,
+    /// Signing requests have to be signed by one of the authorized keys
+    pub require_authentication: bool,
+}
+
+impl SigningPolicy {
+    pub fn check_magic_byte(&self, data: &[u8]) -> Result<(), PolicyError> {
+        let magic_byte = *data.first().ok_or(PolicyError::EmptyData)?;
+        match &self.allowed_magic_bytes {
+            Some(allowed) if !allowed.contains(&magic_byte) => {
+                Err(PolicyError::MagicByteNotAllowed {
+                    magic_byte,
+                    allowed: allowed
+                        .iter()
+                        .map(|byte| format!("0x{:02x}", byte))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Data which are protected by high watermark
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeveledData {
+    pub kind: SignedKind,
+    pub chain_id: ChainId,
+    pub level: i32,
+}
+
+/// Extracts chain id and level from a block header or an endorsement,
+/// returns `None` for any other data.
+pub fn leveled_data(data: &[u8]) -> Result<Option<LeveledData>, PolicyError> {
+    let (kind, level_offset) = match data.first() {
+        Some(&BLOCK_MAGIC_BYTE) => (SignedKind::Block, BLOCK_LEVEL_OFFSET),
+        Some(&ENDORSEMENT_MAGIC_BYTE) => {
+            let level_offset = match data.get(ENDORSEMENT_TAG_OFFSET) {
+                Some(&ENDORSEMENT_TAG) => ENDORSEMENT_TAG_OFFSET + 1,
+                Some(&ENDORSEMENT_WITH_SLOT_TAG) => ENDORSEMENT_TAG_OFFSET + 1 + 4 + 32 + 1,
+                tag => {
+                    return Err(PolicyError::InvalidData {
+                        kind: SignedKind::Endorsement,
+                        reason: format!("unsupported endorsement tag: {:?}", tag),
+                    })
+                }
+            };
+            (SignedKind::Endorsement, level_offset)
+        }
+        _ => return Ok(None),
+    };
+
+    let chain_id = data
+        .get(CHAIN_ID_OFFSET..CHAIN_ID_OFFSET + CHAIN_ID_SIZE)
+        .and_then(|bytes| ChainId::try_from(bytes).ok());
+    let level = data
+        .get(level_offset..level_offset + 4)
+        .and_then(|bytes| <[u8; 4]>::try_from(bytes).ok())
+        .map(i32::from_be_bytes);
+
+    match (chain_id, level) {
+        (Some(chain_id), Some(level)) => Ok(Some(LeveledData {
+            kind,
+            chain_id,
+            level,
+        })),
+        _ => Err(PolicyError::InvalidData {
+            kind,
+            reason: "data too short".to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENERIC_OPERATION_MAGIC_BYTE: u8 = 0x03;
+
+    #[test]
+    fn test_check_magic_byte() {
+        let policy = SigningPolicy {
+            allowed_magic_bytes: Some(vec![BLOCK_MAGIC_BYTE, ENDORSEMENT_MAGIC_BYTE]),
+            ..Default::default()
+        };
+        assert!(policy.check_magic_byte(&[BLOCK_MAGIC_BYTE, 0]).is_ok());
+        assert!(policy
+            .check_magic_byte(&[GENERIC_OPERATION_MAGIC_BYTE, 0])
+            .is_err());
+        assert!(policy.check_magic_byte(&[]).is_err());
+        assert!(SigningPolicy::default()
+            .check_magic_byte(&[GENERIC_OPERATION_MAGIC_BYTE])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_leveled_data() -> Result<(), failure::Error> {
+        let chain_id = ChainId::from_base58_check("NetXdQprcVkpaWU")?;
+
+        let block = [
+            &[BLOCK_MAGIC_BYTE],
+            chain_id.as_ref().as_slice(),
+            &[0, 0, 1, 2, 0xff],
+        ]
+        .concat();
+        assert_eq!(
+            Some(LeveledData {
+                kind: SignedKind::Block,
+                chain_id: chain_id.clone(),
+                level: 258,
+            }),
+            leveled_data(&block)?
+        );
+
+        let endorsement = [
+            &[ENDORSEMENT_MAGIC_BYTE],
+            chain_id.as_ref().as_slice(),
+            &[7; 32],
+            &[ENDORSEMENT_TAG, 0, 0, 0, 5],
+        ]
+        .concat();
+        assert_eq!(
+            Some(LeveledData {
+                kind: SignedKind::Endorsement,
+                chain_id,
+                level: 5,
+            }),
+            leveled_data(&endorsement)?
+        );
+
+        assert!(leveled_data(&endorsement[..endorsement.len() - 1]).is_err());
+        assert_eq!(None, leveled_data(&[GENERIC_OPERATION_MAGIC_BYTE, 1, 2])?);
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use failure::Fail;
+
+use crypto::blake2b::{self, Blake2bError};
+use crypto::signature::{PublicKey, PublicKeyHash, SecretKey, Signature, SignatureError};
+
+use crate::high_watermark::{HighWatermarkError, HighWatermarks};
+use crate::policy::{leveled_data, PolicyError, SigningPolicy};
+
+/// Magic byte of the data signed by authorized keys to authenticate a signing request
+const AUTHENTICATION_MAGIC_BYTE: u8 = 0x04;
+
+#[derive(Debug, Fail)]
+pub enum SignerError {
+    #[fail(display = "Unknown key: {}", public_key_hash)]
+    UnknownKey { public_key_hash: String },
+    #[fail(display = "Missing or invalid authentication of signing request")]
+    Unauthorized,
+    #[fail(display = "Signing policy violation: {}", _0)]
+    Policy(PolicyError),
+    #[fail(display = "{}", _0)]
+    HighWatermark(HighWatermarkError),
+    #[fail(display = "Signature error: {}", _0)]
+    Signature(SignatureError),
+    #[fail(display = "Lock error: {}", reason)]
+    LockError { reason: String },
+}
+
+impl From<PolicyError> for SignerError {
+    fn from(error: PolicyError) -> Self {
+        SignerError::Policy(error)
+    }
+}
+
+impl From<HighWatermarkError> for SignerError {
+    fn from(error: HighWatermarkError) -> Self {
+        SignerError::HighWatermark(error)
+    }
+}
+
+impl From<SignatureError> for SignerError {
+    fn from(error: SignatureError) -> Self {
+        SignerError::Signature(error)
+    }
+}
+
+impl From<Blake2bError> for SignerError {
+    fn from(error: Blake2bError) -> Self {
+        SignerError::Signature(error.into())
+    }
+}
+
+pub type SignerRef = Arc<Signer>;
+
+/// Signs data with unlocked keys according to the signing policy
+pub struct Signer {
+    /// secret keys by public key hash
+    keys: HashMap<String, SecretKey>,
+    authorized_keys: Vec<PublicKey>,
+    policy: SigningPolicy,
+    high_watermarks: Mutex<HighWatermarks>,
+}
+
+impl Signer {
+    pub fn new(
+        keys: HashMap<String, SecretKey>,
+        authorized_keys: Vec<PublicKey>,
+        policy: SigningPolicy,
+        high_watermarks: HighWatermarks,
+    ) -> Self {
+        Self {
+            keys,
+            authorized_keys,
+            policy,
+            high_watermarks: Mutex::new(high_watermarks),
+        }
+    }
+
+    pub fn public_key(&self, public_key_hash: &str) -> Result<PublicKey, SignerError> {
+        Ok(self.secret_key(public_key_hash)?.public_key())
+    }
+
+    /// Hashes of keys allowed to authenticate requests, `None` if authentication is not required.
+    pub fn authorized_keys(&self) -> Result<Option<Vec<PublicKeyHash>>, SignerError> {
+        if !self.policy.require_authentication {
+            return Ok(None);
+        }
+        let hashes = self
+            .authorized_keys
+            .iter()
+            .map(|key| key.public_key_hash())
+            .collect::<Result<_, _>>()?;
+        Ok(Some(hashes))
+    }
+
+    /// Signs `data` (which already contains the watermark) with the key `public_key_hash`.
+    pub fn sign(
+        &self,
+        public_key_hash: &str,
+        data: &[u8],
+        authentication: Option<&Signature>,
+    ) -> Result<Signature, SignerError> {
+        let secret_key = self.secret_key(public_key_hash)?;
+        if self.policy.require_authentication {
+            self.authenticate(
+                &secret_key.public_key().public_key_hash()?,
+                data,
+                authentication,
+            )?;
+        }
+        self.policy.check_magic_byte(data)?;
+
+        let leveled_data = if self.policy.check_high_watermark {
+            leveled_data(data)?
+        } else {
+            None
+        };
+        let leveled_data = match leveled_data {
+            Some(leveled_data) => leveled_data,
+            None => return Ok(secret_key.sign(None, data)?),
+        };
+
+        // lock is held until the high watermark is persisted, so concurrent requests
+        // cannot both pass the check for the same level
+        let mut high_watermarks =
+            self.high_watermarks
+                .lock()
+                .map_err(|e| SignerError::LockError {
+                    reason: format!("{}", e),
+                })?;
+        let hash = blake2b::digest_256(data)?;
+        if let Some(signature) = high_watermarks.check(
+            leveled_data.kind,
+            &leveled_data.chain_id,
+            public_key_hash,
+            leveled_data.level,
+            &hash,
+        )? {
+            return Ok(signature);
+        }
+
+        let signature = secret_key.sign(None, data)?;
+        high_watermarks.update(
+            leveled_data.kind,
+            &leveled_data.chain_id,
+            public_key_hash,
+            leveled_data.level,
+            &hash,
+            &signature,
+        )?;
+        Ok(signature)
+    }
+
+    fn secret_key(&self, public_key_hash: &str) -> Result<&SecretKey, SignerError> {
+        self.keys
+            .get(public_key_hash)
+            .ok_or_else(|| SignerError::UnknownKey {
+                public_key_hash: public_key_hash.to_string(),
+            })
+    }
+
+    /// Request is authenticated by a signature of `0x04 || public key hash || data`
+    /// made by any of the authorized keys.
+    fn authenticate(
+        &self,
+        public_key_hash: &PublicKeyHash,
+        data: &[u8],
+        authentication: Option<&Signature>,
+    ) -> Result<(), SignerError> {
+        let authentication = authentication.ok_or(SignerError::Unauthorized)?;
+        let signed = [
+            &[AUTHENTICATION_MAGIC_BYTE],
+            public_key_hash_bytes(public_key_hash).as_slice(),
+            data,
+        ]
+        .concat();
+
+        for key in &self.authorized_keys {
+            // signature of another curve is not an error, just try the next key
+            if let Ok(true) = key.verify(None, &signed, authentication) {
+                return Ok(());
+            }
+        }
+        Err(SignerError::Unauthorized)
+    }
+}
+
+/// Binary encoding of the public key hash - curve tag followed by the hash
+fn public_key_hash_bytes(public_key_hash: &PublicKeyHash) -> Vec<u8> {
+    let (tag, hash) = match public_key_hash {
+        PublicKeyHash::Ed25519(hash) => (0, hash.as_ref()),
+        PublicKeyHash::Secp256k1(hash) => (1, hash.as_ref()),
+        PublicKeyHash::P256(hash) => (2, hash.as_ref()),
+    };
+    [&[tag], hash.as_slice()].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crypto::hash::ChainId;
+    use crypto::signature::{Curve, Watermark};
+
+    use super::*;
+
+    const BOOTSTRAP1_SK: &str = "edsk3gUfUPyBSfrS9CCgmCiQsTCHGkviBDusMxDJstFtojtc1zcpsh";
+    const BOOTSTRAP1_PKH: &str = "tz1KqTpEZ7Yob7QbPE4Hy4Wo8fHG8LhKxZSx";
+
+    fn signer(name: &str, policy: SigningPolicy, authorized_keys: Vec<PublicKey>) -> Signer {
+        let base_dir = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&base_dir);
+        let mut keys = HashMap::new();
+        keys.insert(
+            BOOTSTRAP1_PKH.to_string(),
+            SecretKey::from_base58_check(BOOTSTRAP1_SK).unwrap(),
+        );
+        Signer::new(
+            keys,
+            authorized_keys,
+            policy,
+            HighWatermarks::open(&base_dir).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_sign_block_with_high_watermark() -> Result<(), failure::Error> {
+        let signer = signer(
+            "tezedge-signer-service-test",
+            SigningPolicy {
+                allowed_magic_bytes: Some(vec![0x01, 0x02]),
+                check_high_watermark: true,
+                require_authentication: false,
+            },
+            vec![],
+        );
+        let chain_id = ChainId::from_base58_check("NetXdQprcVkpaWU")?;
+        let block = |level: i32, fitness: u8| {
+            [
+                Watermark::Block(chain_id.clone()).bytes(),
+                level.to_be_bytes().to_vec(),
+                vec![fitness],
+            ]
+            .concat()
+        };
+
+        let signature = signer.sign(BOOTSTRAP1_PKH, &block(5, 0), None)?;
+        assert!(signer
+            .public_key(BOOTSTRAP1_PKH)?
+            .verify(None, &block(5, 0), &signature)?);
+        // repeated request is answered with the same signature
+        assert_eq!(signature, signer.sign(BOOTSTRAP1_PKH, &block(5, 0), None)?);
+        // double baking
+        assert!(signer.sign(BOOTSTRAP1_PKH, &block(5, 1), None).is_err());
+        assert!(signer.sign(BOOTSTRAP1_PKH, &block(4, 0), None).is_err());
+        assert!(signer.sign(BOOTSTRAP1_PKH, &block(6, 0), None).is_ok());
+        // magic byte not allowed
+        assert!(signer.sign(BOOTSTRAP1_PKH, &[0x03, 1, 2], None).is_err());
+        // unknown key
+        assert!(signer
+            .sign("tz1gjaF81ZRRvdzjobyfVNsAeSC6PScjfQwN", &block(7, 0), None)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_with_authentication() -> Result<(), failure::Error> {
+        let authorized = SecretKey::generate(Curve::P256);
+        let signer = signer(
+            "tezedge-signer-service-auth-test",
+            SigningPolicy {
+                require_authentication: true,
+                ..Default::default()
+            },
+            vec![authorized.public_key()],
+        );
+        let data = [0x03, 1, 2, 3];
+        let pkh = PublicKeyHash::from_base58_check(BOOTSTRAP1_PKH)?;
+        let authentication = authorized.sign(
+            None,
+            &[
+                &[AUTHENTICATION_MAGIC_BYTE],
+                public_key_hash_bytes(&pkh).as_slice(),
+                &data,
+            ]
+            .concat(),
+        )?;
+
+        assert!(signer.sign(BOOTSTRAP1_PKH, &data, None).is_err());
+        assert!(signer
+            .sign(BOOTSTRAP1_PKH, &data, Some(&authentication.to_generic()))
+            .is_ok());
+        assert!(signer
+            .sign(BOOTSTRAP1_PKH, &[0x03, 1], Some(&authentication))
+            .is_err());
+        assert_eq!(
+            Some(vec![authorized.public_key().public_key_hash()?]),
+            signer.authorized_keys()?
+        );
+        Ok(())
+    }
+}
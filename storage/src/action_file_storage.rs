@@ -4,7 +4,8 @@
 use crate::action_file::*;
 use crate::persistent::{ActionRecordError, ActionRecorder};
 use crate::StorageError;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use tezos_context::channel::ContextAction;
 
 pub struct ActionFileStorage {
@@ -46,6 +47,106 @@ impl ActionFileStorage {
     }
 }
 
+/// Stores actions to files in `dir` with the same format as [ActionFileStorage],
+/// file is rotated, when it exceeds `max_file_size_bytes`, and only `max_files` newest files are kept.
+///
+/// Files are named `actionfile.<sequence>.bin`, so they can be read in order with [ActionsFileReader].
+pub struct RotatingActionFileStorage {
+    dir: PathBuf,
+    max_file_size_bytes: u64,
+    max_files: usize,
+    staging: Vec<ContextAction>,
+    sequence: u64,
+}
+
+impl RotatingActionFileStorage {
+    pub fn new(
+        dir: PathBuf,
+        max_file_size_bytes: u64,
+        max_files: usize,
+    ) -> Result<Self, ActionRecordError> {
+        fs::create_dir_all(&dir).map_err(ActionFileError::from)?;
+
+        // continue with the newest existing file
+        let sequence = Self::list_sequences(&dir)?.last().cloned().unwrap_or(0);
+
+        Ok(RotatingActionFileStorage {
+            dir,
+            max_file_size_bytes,
+            max_files: max_files.max(1),
+            staging: Vec::new(),
+            sequence,
+        })
+    }
+
+    fn file_path(&self, sequence: u64) -> PathBuf {
+        self.dir.join(format!("actionfile.{:06}.bin", sequence))
+    }
+
+    /// Returns sorted sequences of existing action files in `dir`
+    fn list_sequences(dir: &Path) -> Result<Vec<u64>, ActionFileError> {
+        let mut sequences = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let file_name = entry?.file_name();
+            let sequence = file_name
+                .to_str()
+                .and_then(|name| name.strip_prefix("actionfile."))
+                .and_then(|name| name.strip_suffix(".bin"))
+                .and_then(|sequence| sequence.parse::<u64>().ok());
+            if let Some(sequence) = sequence {
+                sequences.push(sequence);
+            }
+        }
+        sequences.sort_unstable();
+        Ok(sequences)
+    }
+
+    fn rotate_if_needed(&mut self) -> Result<(), ActionFileError> {
+        let current_size = match fs::metadata(self.file_path(self.sequence)) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+        if current_size < self.max_file_size_bytes {
+            return Ok(());
+        }
+
+        self.sequence += 1;
+
+        // remove the oldest files
+        let sequences = Self::list_sequences(&self.dir)?;
+        let keep_from = (self.sequence + 1).saturating_sub(self.max_files as u64);
+        for sequence in sequences.into_iter().filter(|s| *s < keep_from) {
+            fs::remove_file(self.file_path(sequence))?;
+        }
+        Ok(())
+    }
+
+    fn store_commit_action(&mut self, action: &ContextAction) -> Result<(), StorageError> {
+        self.staging.push(action.clone());
+        self.rotate_if_needed().map_err(ActionRecordError::from)?;
+
+        let mut action_file_writer = ActionsFileWriter::new(self.file_path(self.sequence))
+            .map_err(ActionRecordError::from)?;
+        action_file_writer
+            .update(std::mem::take(&mut self.staging))
+            .map_err(ActionRecordError::from)?;
+        Ok(())
+    }
+}
+
+impl ActionRecorder for RotatingActionFileStorage {
+    fn record(&mut self, context_action: &ContextAction) -> Result<(), StorageError> {
+        match context_action {
+            ContextAction::Commit { .. } => self.store_commit_action(context_action),
+            ContextAction::Shutdown => Ok(()),
+            _ => {
+                self.staging.push(context_action.clone());
+                Ok(())
+            }
+        }
+    }
+}
+
 pub fn get_tree_action(action: &ContextAction) -> String {
     match action {
         ContextAction::Get { .. } => "ContextAction::Get".to_string(),
@@ -0,0 +1,446 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Configurable sinks and filters for context action recording.
+//!
+//! Every configured sink ([ActionRecorder]) can be wrapped with [FilteredActionRecorder],
+//! so it records just actions of selected types, with selected key prefixes or for selected contracts.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use failure::Fail;
+
+use crate::context_action_storage::{
+    action_key_to_contract_address, contract_id_to_contract_address_for_index, ContextActionType,
+    ContractAddress,
+};
+use crate::persistent::ActionRecorder;
+use crate::StorageError;
+use tezos_context::channel::ContextAction;
+
+/// Filter for recorded context actions, empty filter accepts every action.
+///
+/// `Commit` and `Checkout` delimit blocks (recorders flush on them), so they are always accepted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContextActionRecordFilter {
+    /// Accept only these action types
+    pub action_types: Option<Vec<ContextActionType>>,
+    /// Accept only actions, where key starts with one of these prefixes
+    pub key_prefixes: Vec<Vec<String>>,
+    /// Accept only actions, where key belongs to one of these contracts
+    pub contracts: Vec<ContractAddress>,
+}
+
+impl ContextActionRecordFilter {
+    pub fn is_empty(&self) -> bool {
+        self.action_types.is_none() && self.key_prefixes.is_empty() && self.contracts.is_empty()
+    }
+
+    pub fn accepts(&self, action: &ContextAction) -> bool {
+        if let ContextAction::Commit { .. } | ContextAction::Checkout { .. } = action {
+            return true;
+        }
+
+        if let Some(action_types) = &self.action_types {
+            match ContextActionType::extract_type(action) {
+                Some(action_type) if action_types.contains(&action_type) => (),
+                _ => return false,
+            }
+        }
+
+        let keys = action_keys(action);
+        if keys.is_empty() {
+            return true;
+        }
+
+        let has_prefix = |key: &&[String]| {
+            self.key_prefixes
+                .iter()
+                .any(|prefix| key.starts_with(prefix))
+        };
+        if !self.key_prefixes.is_empty() && !keys.iter().any(has_prefix) {
+            return false;
+        }
+
+        if !self.contracts.is_empty()
+            && !keys
+                .iter()
+                .any(|key| match action_key_to_contract_address(key) {
+                    Some(contract) => self.contracts.contains(&contract),
+                    None => false,
+                })
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+fn action_keys(action: &ContextAction) -> Vec<&[String]> {
+    match action {
+        ContextAction::Set { key, .. }
+        | ContextAction::Delete { key, .. }
+        | ContextAction::RemoveRecursively { key, .. }
+        | ContextAction::Mem { key, .. }
+        | ContextAction::DirMem { key, .. }
+        | ContextAction::Get { key, .. }
+        | ContextAction::Fold { key, .. } => vec![key.as_slice()],
+        ContextAction::Copy {
+            from_key, to_key, ..
+        } => vec![from_key.as_slice(), to_key.as_slice()],
+        ContextAction::Commit { .. } | ContextAction::Checkout { .. } | ContextAction::Shutdown => {
+            vec![]
+        }
+    }
+}
+
+/// Kind of sink for recorded context actions
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContextActionSinkKind {
+    /// Actions are not recorded [crate::persistent::NoRecorder]
+    None,
+    /// Indexed rocksdb storage [crate::ContextActionStorage]
+    RocksDB,
+    /// Single action file [crate::ActionFileStorage], default path is resolved by node
+    FileStorage { path: Option<PathBuf> },
+    /// Rotated action files [crate::RotatingActionFileStorage], default dir is resolved by node
+    RotatingFileStorage {
+        dir: Option<PathBuf>,
+        max_file_size_bytes: u64,
+        max_files: usize,
+    },
+    /// Unix domain socket stream [crate::ActionStreamRecorder]
+    Stream { path: PathBuf },
+}
+
+/// Sink for recorded context actions with filter.
+///
+/// Parsed from `<kind>[;<option>=<value>]*`, where kind is one of `none`, `rocksdb`, `file`, `rotating-file`, `stream`
+/// and options are:
+/// - `path=<PATH>` - file (`file`), directory (`rotating-file`) or unix socket (`stream`, required)
+/// - `max-file-size-mb=<NUM>`, `max-files=<NUM>` - rotation of `rotating-file`
+/// - `types=<TYPE>,<TYPE>...` - accepted action types, e.g. `Set,Delete` (`Commit` and `Checkout` are always accepted)
+/// - `key-prefix=<KEY>` - accepted key prefix, e.g. `data/contracts`, can be repeated
+/// - `contract=<ADDRESS>,<ADDRESS>...` - accepted contracts, e.g. `tz1...` or `KT1...`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextActionSink {
+    pub kind: ContextActionSinkKind,
+    pub filter: ContextActionRecordFilter,
+}
+
+impl ContextActionSink {
+    pub const DEFAULT_MAX_FILE_SIZE_MB: u64 = 256;
+    pub const DEFAULT_MAX_FILES: usize = 16;
+
+    pub fn possible_kinds() -> Vec<&'static str> {
+        vec!["none", "rocksdb", "file", "rotating-file", "stream"]
+    }
+}
+
+#[derive(Debug, Clone, Fail)]
+#[fail(display = "invalid context action sink: {}", _0)]
+pub struct ParseContextActionSinkError(String);
+
+impl FromStr for ContextActionSink {
+    type Err = ParseContextActionSinkError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.split(';').map(str::trim);
+        let kind = parts.next().unwrap_or("").to_ascii_lowercase();
+
+        let mut path = None;
+        let mut max_file_size_mb = Self::DEFAULT_MAX_FILE_SIZE_MB;
+        let mut max_files = Self::DEFAULT_MAX_FILES;
+        let mut filter = ContextActionRecordFilter::default();
+
+        for option in parts.filter(|option| !option.is_empty()) {
+            let (name, option_value) = match option.find('=') {
+                Some(idx) => (&option[..idx], &option[idx + 1..]),
+                None => {
+                    return Err(ParseContextActionSinkError(format!(
+                        "option without value: {}",
+                        option
+                    )))
+                }
+            };
+            match name {
+                "path" => path = Some(PathBuf::from(option_value)),
+                "max-file-size-mb" => {
+                    max_file_size_mb = option_value.parse().map_err(|_| {
+                        ParseContextActionSinkError(format!("invalid number: {}", option_value))
+                    })?
+                }
+                "max-files" => {
+                    max_files = option_value.parse().map_err(|_| {
+                        ParseContextActionSinkError(format!("invalid number: {}", option_value))
+                    })?
+                }
+                "types" => {
+                    let action_types = option_value
+                        .split(',')
+                        .map(|action_type| ContextActionType::from_str(action_type.trim()))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| ParseContextActionSinkError(format!("{}", e)))?;
+                    filter.action_types = Some(action_types);
+                }
+                "key-prefix" => filter.key_prefixes.push(
+                    option_value
+                        .split('/')
+                        .filter(|key| !key.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                ),
+                "contract" => {
+                    for contract in option_value.split(',') {
+                        filter.contracts.push(
+                            contract_id_to_contract_address_for_index(contract.trim()).map_err(
+                                |e| {
+                                    ParseContextActionSinkError(format!(
+                                        "invalid contract: {}, reason: {}",
+                                        contract, e
+                                    ))
+                                },
+                            )?,
+                        );
+                    }
+                }
+                _ => {
+                    return Err(ParseContextActionSinkError(format!(
+                        "unknown option: {}",
+                        name
+                    )))
+                }
+            }
+        }
+
+        let kind = match kind.as_str() {
+            "none" => ContextActionSinkKind::None,
+            "rocksdb" => ContextActionSinkKind::RocksDB,
+            "file" => ContextActionSinkKind::FileStorage { path },
+            "rotating-file" => ContextActionSinkKind::RotatingFileStorage {
+                dir: path,
+                max_file_size_bytes: max_file_size_mb * 1024 * 1024,
+                max_files,
+            },
+            "stream" => match path {
+                Some(path) => ContextActionSinkKind::Stream { path },
+                None => {
+                    return Err(ParseContextActionSinkError(
+                        "stream requires path of unix socket".to_string(),
+                    ))
+                }
+            },
+            _ => {
+                return Err(ParseContextActionSinkError(format!(
+                    "unknown kind: {}, supported kinds are: {:?}",
+                    kind,
+                    Self::possible_kinds()
+                )))
+            }
+        };
+
+        Ok(ContextActionSink { kind, filter })
+    }
+}
+
+/// Records to the inner recorder only actions accepted by filter
+pub struct FilteredActionRecorder {
+    filter: ContextActionRecordFilter,
+    recorder: Box<dyn ActionRecorder + Send>,
+}
+
+impl FilteredActionRecorder {
+    pub fn new(
+        filter: ContextActionRecordFilter,
+        recorder: Box<dyn ActionRecorder + Send>,
+    ) -> Self {
+        Self { filter, recorder }
+    }
+}
+
+impl ActionRecorder for FilteredActionRecorder {
+    fn record(&mut self, action: &ContextAction) -> Result<(), StorageError> {
+        if self.filter.accepts(action) {
+            self.recorder.record(action)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(key: &[&str]) -> ContextAction {
+        ContextAction::Set {
+            context_hash: None,
+            block_hash: None,
+            operation_hash: None,
+            key: key.iter().map(|k| k.to_string()).collect(),
+            value: vec![],
+            value_as_json: None,
+            tree_hash: None,
+            new_tree_hash: None,
+            tree_id: 0,
+            new_tree_id: 1,
+            start_time: 0_f64,
+            end_time: 0_f64,
+        }
+    }
+
+    fn checkout() -> ContextAction {
+        ContextAction::Checkout {
+            context_hash: vec![0; 32],
+            start_time: 0_f64,
+            end_time: 0_f64,
+        }
+    }
+
+    #[test]
+    fn test_filter_action_types_and_key_prefixes() {
+        let filter = ContextActionRecordFilter {
+            action_types: Some(vec![ContextActionType::Set, ContextActionType::Commit]),
+            key_prefixes: vec![vec!["data".to_string(), "votes".to_string()]],
+            contracts: vec![],
+        };
+
+        assert!(filter.accepts(&set(&["data", "votes", "listings"])));
+        assert!(!filter.accepts(&set(&["data", "rolls"])));
+        // block delimiters are accepted regardless of action types
+        assert!(filter.accepts(&checkout()));
+
+        assert!(ContextActionRecordFilter::default().accepts(&checkout()));
+    }
+
+    #[test]
+    fn test_parse_sink() -> Result<(), failure::Error> {
+        assert_eq!(
+            "rocksdb".parse::<ContextActionSink>()?,
+            ContextActionSink {
+                kind: ContextActionSinkKind::RocksDB,
+                filter: ContextActionRecordFilter::default(),
+            }
+        );
+
+        let sink: ContextActionSink =
+            "rotating-file;path=/tmp/actions;max-file-size-mb=1;types=Set,Commit;key-prefix=data/votes;key-prefix=data/rolls/"
+                .parse()?;
+        assert_eq!(
+            sink.kind,
+            ContextActionSinkKind::RotatingFileStorage {
+                dir: Some(PathBuf::from("/tmp/actions")),
+                max_file_size_bytes: 1024 * 1024,
+                max_files: ContextActionSink::DEFAULT_MAX_FILES,
+            }
+        );
+        assert_eq!(
+            sink.filter.action_types,
+            Some(vec![ContextActionType::Set, ContextActionType::Commit])
+        );
+        assert_eq!(
+            sink.filter.key_prefixes,
+            vec![
+                vec!["data".to_string(), "votes".to_string()],
+                vec!["data".to_string(), "rolls".to_string()],
+            ]
+        );
+
+        assert!("stream".parse::<ContextActionSink>().is_err());
+        assert!("file;types=Unknown".parse::<ContextActionSink>().is_err());
+        assert!("file;unknown=1".parse::<ContextActionSink>().is_err());
+        assert!("unknown".parse::<ContextActionSink>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_contracts() -> Result<(), failure::Error> {
+        let filter = ContextActionRecordFilter {
+            action_types: None,
+            key_prefixes: vec![],
+            contracts: vec![contract_id_to_contract_address_for_index(
+                "0000cf49f66b9ea137e11818f2a78b4b6fc9895b4e50",
+            )?],
+        };
+
+        assert!(filter.accepts(&set(&[
+            "data",
+            "contracts",
+            "index",
+            "b5",
+            "94",
+            "d1",
+            "1e",
+            "8e",
+            "52",
+            "0000cf49f66b9ea137e11818f2a78b4b6fc9895b4e50",
+            "roll_list",
+        ])));
+        assert!(!filter.accepts(&set(&["data", "contracts", "global_counter"])));
+        // actions without key are not filtered by contract
+        assert!(filter.accepts(&checkout()));
+
+        Ok(())
+    }
+
+    struct CollectingRecorder(std::sync::Arc<std::sync::Mutex<Vec<ContextAction>>>);
+
+    impl ActionRecorder for CollectingRecorder {
+        fn record(&mut self, action: &ContextAction) -> Result<(), StorageError> {
+            self.0.lock().unwrap().push(action.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_filtered_recorder_passes_block_delimiters() -> Result<(), failure::Error> {
+        let sink: ContextActionSink = "file;types=Set,Delete".parse()?;
+        let recorded = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut recorder = FilteredActionRecorder::new(
+            sink.filter,
+            Box::new(CollectingRecorder(recorded.clone())),
+        );
+
+        let commit = ContextAction::Commit {
+            parent_context_hash: None,
+            block_hash: Some(vec![1; 32]),
+            new_context_hash: vec![2; 32],
+            tree_hash: None,
+            tree_id: 0,
+            author: "author".to_string(),
+            message: "message".to_string(),
+            date: 0,
+            parents: vec![],
+            start_time: 0_f64,
+            end_time: 0_f64,
+        };
+        let get = ContextAction::Get {
+            context_hash: None,
+            block_hash: None,
+            operation_hash: None,
+            key: vec!["data".to_string()],
+            value: vec![],
+            value_as_json: None,
+            tree_hash: None,
+            tree_id: 0,
+            start_time: 0_f64,
+            end_time: 0_f64,
+        };
+
+        recorder.record(&checkout())?;
+        recorder.record(&set(&["data", "votes"]))?;
+        recorder.record(&get)?;
+        recorder.record(&commit)?;
+
+        let recorded = recorded.lock().unwrap();
+        assert_eq!(recorded.len(), 3);
+        assert!(matches!(recorded[0], ContextAction::Checkout { .. }));
+        assert!(matches!(recorded[1], ContextAction::Set { .. }));
+        assert!(matches!(recorded[2], ContextAction::Commit { .. }));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Streams recorded context actions to external indexers over unix domain socket.
+//!
+//! Actions are sent per block (closed by commit) with the same framing as action file:
+//! `|block len in bytes (u32 BE)||Vec<ContextAction> serialized with bincode and compressed with snap|`.
+//! Writes never block block application - data, which cannot be written immediately, are kept
+//! per client and written on the next recorded action. Clients, which do not receive the whole
+//! previous block until the next block is committed, cannot keep up and are disconnected.
+
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+
+use crate::action_file::ActionFileError;
+use crate::persistent::{ActionRecordError, ActionRecorder};
+use crate::StorageError;
+use tezos_context::channel::ContextAction;
+
+struct StreamClient {
+    stream: UnixStream,
+    /// Rest of the last block chunk, which was not written yet
+    pending: Vec<u8>,
+}
+
+impl StreamClient {
+    /// Writes as much of pending data as possible without blocking,
+    /// returns false, if client is disconnected
+    fn flush_pending(&mut self) -> bool {
+        while !self.pending.is_empty() {
+            match self.stream.write(&self.pending) {
+                Ok(0) => return false,
+                Ok(written) => {
+                    self.pending.drain(..written);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => return false,
+            }
+        }
+        true
+    }
+}
+
+pub struct ActionStreamRecorder {
+    path: PathBuf,
+    listener: UnixListener,
+    clients: Vec<StreamClient>,
+    staging: Vec<ContextAction>,
+}
+
+impl ActionStreamRecorder {
+    /// Binds unix socket on `path`, previous socket file is removed
+    pub fn bind(path: PathBuf) -> Result<Self, ActionRecordError> {
+        if path.exists() {
+            fs::remove_file(&path).map_err(ActionFileError::from)?;
+        }
+        let listener = UnixListener::bind(&path).map_err(ActionFileError::from)?;
+        listener
+            .set_nonblocking(true)
+            .map_err(ActionFileError::from)?;
+
+        Ok(Self {
+            path,
+            listener,
+            clients: Vec::new(),
+            staging: Vec::new(),
+        })
+    }
+
+    /// Returns count of connected clients
+    pub fn clients_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    fn accept_clients(&mut self) -> Result<(), ActionFileError> {
+        loop {
+            match self.listener.accept() {
+                Ok((client, _)) => {
+                    client.set_nonblocking(true)?;
+                    self.clients.push(StreamClient {
+                        stream: client,
+                        pending: Vec::new(),
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Continues writing of partially written chunks, disconnected clients are removed
+    fn flush_clients(&mut self) {
+        if self.clients.iter().any(|client| !client.pending.is_empty()) {
+            self.clients = std::mem::take(&mut self.clients)
+                .into_iter()
+                .filter_map(|mut client| {
+                    if client.flush_pending() {
+                        Some(client)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+        }
+    }
+
+    fn send_block(&mut self) -> Result<(), ActionFileError> {
+        let actions = std::mem::take(&mut self.staging);
+        self.accept_clients()?;
+        if self.clients.is_empty() {
+            return Ok(());
+        }
+
+        let mut data = Vec::new();
+        bincode::serialize_into(snap::write::FrameEncoder::new(&mut data), &actions)?;
+        let mut chunk = Vec::with_capacity(data.len() + 4);
+        chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        chunk.extend_from_slice(&data);
+
+        // client, which has not received the previous block yet, cannot keep up and is disconnected,
+        // the rest of new chunk, which cannot be written now, is written on the next recorded action
+        self.clients = std::mem::take(&mut self.clients)
+            .into_iter()
+            .filter_map(|mut client| {
+                if !client.flush_pending() || !client.pending.is_empty() {
+                    return None;
+                }
+                client.pending = chunk.clone();
+                if client.flush_pending() {
+                    Some(client)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Ok(())
+    }
+}
+
+impl ActionRecorder for ActionStreamRecorder {
+    fn record(&mut self, action: &ContextAction) -> Result<(), StorageError> {
+        match action {
+            ContextAction::Commit { .. } => {
+                self.staging.push(action.clone());
+                self.send_block().map_err(ActionRecordError::from)?;
+                Ok(())
+            }
+            ContextAction::Shutdown => Ok(()),
+            _ => {
+                self.staging.push(action.clone());
+                self.flush_clients();
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Drop for ActionStreamRecorder {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    fn commit() -> ContextAction {
+        ContextAction::Commit {
+            parent_context_hash: None,
+            block_hash: Some(vec![1; 32]),
+            new_context_hash: vec![2; 32],
+            tree_hash: None,
+            tree_id: 0,
+            author: "author".to_string(),
+            message: "message".to_string(),
+            date: 0,
+            parents: vec![],
+            start_time: 0_f64,
+            end_time: 0_f64,
+        }
+    }
+
+    #[test]
+    fn test_stream_block_to_connected_client() -> Result<(), failure::Error> {
+        let path = std::env::temp_dir().join("test_stream_block_to_connected_client.sock");
+        let mut recorder = ActionStreamRecorder::bind(path.clone())?;
+        let mut client = UnixStream::connect(&path)?;
+
+        let checkout = ContextAction::Checkout {
+            context_hash: vec![2; 32],
+            start_time: 0_f64,
+            end_time: 0_f64,
+        };
+        recorder.record(&checkout)?;
+        recorder.record(&commit())?;
+        assert_eq!(recorder.clients_count(), 1);
+
+        let mut len = [0_u8; 4];
+        client.read_exact(&mut len)?;
+        let mut data = vec![0_u8; u32::from_be_bytes(len) as usize];
+        client.read_exact(&mut data)?;
+        let actions: Vec<ContextAction> =
+            bincode::deserialize_from(snap::read::FrameDecoder::new(&data[..]))?;
+        assert_eq!(actions.len(), 2);
+        assert!(matches!(actions[1], ContextAction::Commit { .. }));
+
+        // disconnected client is removed
+        drop(client);
+        recorder.record(&commit())?;
+        recorder.record(&commit())?;
+        assert_eq!(recorder.clients_count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_block_larger_than_socket_buffer() -> Result<(), failure::Error> {
+        let path = std::env::temp_dir().join("test_stream_block_larger_than_socket_buffer.sock");
+        let mut recorder = ActionStreamRecorder::bind(path.clone())?;
+        let mut client = UnixStream::connect(&path)?;
+
+        // pseudo random (not compressible) value, so the chunk cannot be written at once
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let value = (0..4 * 1024 * 1024)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                seed as u8
+            })
+            .collect::<Vec<_>>();
+        let set = ContextAction::Set {
+            context_hash: None,
+            block_hash: None,
+            operation_hash: None,
+            key: vec!["data".to_string()],
+            value,
+            value_as_json: None,
+            tree_hash: None,
+            new_tree_hash: None,
+            tree_id: 0,
+            new_tree_id: 1,
+            start_time: 0_f64,
+            end_time: 0_f64,
+        };
+        recorder.record(&set)?;
+        recorder.record(&commit())?;
+        assert_eq!(recorder.clients_count(), 1);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut read_block = || -> Result<Vec<ContextAction>, failure::Error> {
+                let mut len = [0_u8; 4];
+                client.read_exact(&mut len)?;
+                let mut data = vec![0_u8; u32::from_be_bytes(len) as usize];
+                client.read_exact(&mut data)?;
+                Ok(bincode::deserialize_from(snap::read::FrameDecoder::new(
+                    &data[..],
+                ))?)
+            };
+            let _ = tx.send(read_block());
+        });
+
+        // rest of the chunk is written with next actions
+        let checkout = ContextAction::Checkout {
+            context_hash: vec![2; 32],
+            start_time: 0_f64,
+            end_time: 0_f64,
+        };
+        let actions = loop {
+            match rx.try_recv() {
+                Ok(actions) => break actions?,
+                Err(std::sync::mpsc::TryRecvError::Empty) => {
+                    recorder.record(&checkout)?;
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+        assert_eq!(recorder.clients_count(), 1);
+        assert_eq!(actions.len(), 2);
+        assert!(
+            matches!(&actions[0], ContextAction::Set { value, .. } if value.len() == 4 * 1024 * 1024)
+        );
+
+        Ok(())
+    }
+}
@@ -174,6 +174,69 @@ impl BlockMetaStorage {
         Ok(())
     }
 
+    /// Returns tips of applied side branches, which fork from the branch of [head] at most [max_depth] predecessors back.
+    ///
+    /// Tip is an applied block without any applied successor, [head] itself is not included.
+    pub fn find_alternate_heads(
+        &self,
+        head: &BlockHash,
+        max_depth: usize,
+    ) -> Result<Vec<BlockHash>, StorageError> {
+        let mut alternate_heads = Vec::new();
+        let mut branch_block = head.clone();
+        let mut branch_successor: Option<BlockHash> = None;
+
+        for _ in 0..=max_depth {
+            let meta = match self.get(&branch_block)? {
+                Some(meta) => meta,
+                None => break,
+            };
+
+            for successor in &meta.successors {
+                if Some(successor) != branch_successor.as_ref() {
+                    self.collect_applied_tips(successor, &mut alternate_heads)?;
+                }
+            }
+
+            match meta.predecessor {
+                // Note: genesis is its own predecessor
+                Some(predecessor) if predecessor != branch_block => {
+                    branch_successor = Some(std::mem::replace(&mut branch_block, predecessor));
+                }
+                _ => break,
+            }
+        }
+
+        Ok(alternate_heads)
+    }
+
+    /// Walks applied successors of [block_hash] and collects the ones without any applied successor
+    fn collect_applied_tips(
+        &self,
+        block_hash: &BlockHash,
+        tips: &mut Vec<BlockHash>,
+    ) -> Result<(), StorageError> {
+        let mut to_visit = vec![block_hash.clone()];
+        while let Some(block_hash) = to_visit.pop() {
+            let meta = match self.get(&block_hash)? {
+                Some(meta) if meta.is_applied => meta,
+                _ => continue,
+            };
+
+            let mut has_applied_successor = false;
+            for successor in meta.successors {
+                if self.is_applied(&successor)? {
+                    has_applied_successor = true;
+                    to_visit.push(successor);
+                }
+            }
+            if !has_applied_successor {
+                tips.push(block_hash);
+            }
+        }
+        Ok(())
+    }
+
     #[inline]
     pub fn put(&self, block_hash: &BlockHash, meta: &Meta) -> Result<(), StorageError> {
         self.kv.merge(block_hash, meta).map_err(StorageError::from)
@@ -775,6 +838,53 @@ mod tests {
         assert!(DB::destroy(&Options::default(), path).is_ok());
     }
 
+    #[test]
+    fn find_alternate_heads_test() -> Result<(), Error> {
+        let tmp_storage = TmpStorage::create("__blockmeta_find_alternate_heads_test")?;
+        let storage = BlockMetaStorage::new(tmp_storage.storage());
+        let chain_id: ChainId = vec![44; 4].try_into().unwrap();
+        let hash = |n: u8| -> BlockHash { vec![n; 32].try_into().unwrap() };
+        let put = |n: u8, predecessor: u8, level: i32, successors: Vec<u8>, is_applied: bool| {
+            storage.put(
+                &hash(n),
+                &Meta {
+                    is_applied,
+                    predecessor: Some(hash(predecessor)),
+                    successors: successors.into_iter().map(hash).collect(),
+                    level,
+                    chain_id: chain_id.clone(),
+                },
+            )
+        };
+
+        // genesis(0) - 1 - 2 - 3 - 4 (head)
+        //                \             //                 \     5 (applied tip)
+        //                  6 - 7 (applied tip) - 8 (not applied)
+        put(0, 0, 0, vec![1], true)?;
+        put(1, 0, 1, vec![2, 6], true)?;
+        put(2, 1, 2, vec![3, 5], true)?;
+        put(3, 2, 3, vec![4], true)?;
+        put(4, 3, 4, vec![], true)?;
+        put(5, 2, 3, vec![], true)?;
+        put(6, 1, 2, vec![7], true)?;
+        put(7, 6, 3, vec![8], true)?;
+        put(8, 7, 4, vec![], false)?;
+
+        let alternate_heads: HashSet<BlockHash> = storage
+            .find_alternate_heads(&hash(4), 10)?
+            .into_iter()
+            .collect();
+        assert_eq!(
+            alternate_heads,
+            vec![hash(5), hash(7)].into_iter().collect()
+        );
+
+        // fork of block 1 is too deep
+        assert_eq!(storage.find_alternate_heads(&hash(4), 2)?, vec![hash(5)]);
+
+        Ok(())
+    }
+
     /// Create and return a storage with [number_of_blocks] blocks and the last BlockHash in it
     fn init_mocked_storage(
         number_of_blocks: usize,
@@ -32,6 +32,11 @@ pub trait ChainMetaStorageReader: Sync + Send {
 
     /// Load genesis for chain_id from dedicated storage
     fn get_genesis(&self, chain_id: &ChainId) -> Result<Option<Head>, StorageError>;
+
+    /// Load checkpoint for chain_id from dedicated storage
+    ///
+    /// Checkpoint is a block, which must be part of every accepted branch (fitness of checkpoint is not known, so it is empty)
+    fn get_checkpoint(&self, chain_id: &ChainId) -> Result<Option<Head>, StorageError>;
 }
 
 /// Represents storage of the chain metadata (current_head, test_chain, ...).
@@ -93,6 +98,16 @@ impl ChainMetaStorage {
             .map_err(StorageError::from)
     }
 
+    #[inline]
+    pub fn set_checkpoint(&self, chain_id: &ChainId, head: Head) -> Result<(), StorageError> {
+        self.kv
+            .put(
+                &MetaKey::key_checkpoint(chain_id.clone()),
+                &MetadataValue::Head(head),
+            )
+            .map_err(StorageError::from)
+    }
+
     #[inline]
     pub fn get_test_chain_id(&self, chain_id: &ChainId) -> Result<Option<ChainId>, StorageError> {
         self.kv
@@ -159,6 +174,17 @@ impl ChainMetaStorageReader for ChainMetaStorage {
             })
             .map_err(StorageError::from)
     }
+
+    #[inline]
+    fn get_checkpoint(&self, chain_id: &ChainId) -> Result<Option<Head>, StorageError> {
+        self.kv
+            .get(&MetaKey::key_checkpoint(chain_id.clone()))
+            .map(|result| match result {
+                Some(MetadataValue::Head(value)) => Some(value),
+                _ => None,
+            })
+            .map_err(StorageError::from)
+    }
 }
 
 impl KeyValueSchema for ChainMetaStorage {
@@ -191,6 +217,7 @@ impl MetaKey {
     const KEY_CABOOSE: &'static str = "cbs";
     const KEY_GENESIS: &'static str = "gns";
     const KEY_TEST_CHAIN_ID: &'static str = "tcid";
+    const KEY_CHECKPOINT: &'static str = "chkp";
 
     fn key_current_head(chain_id: ChainId) -> MetaKey {
         MetaKey {
@@ -213,6 +240,13 @@ impl MetaKey {
         }
     }
 
+    fn key_checkpoint(chain_id: ChainId) -> MetaKey {
+        MetaKey {
+            chain_id,
+            key: Self::KEY_CHECKPOINT.to_string(),
+        }
+    }
+
     fn key_test_chain_id(chain_id: ChainId) -> MetaKey {
         MetaKey {
             chain_id,
@@ -388,6 +422,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_checkpoint() -> Result<(), Error> {
+        let tmp_storage = TmpStorage::create("__test_checkpoint")?;
+        let index = ChainMetaStorage::new(tmp_storage.storage());
+
+        let chain_id1 = "NetXgtSLGNJvNye".try_into()?;
+        let block_1 = Head::new(
+            "BLockGenesisGenesisGenesisGenesisGenesisb83baZgbyZe".try_into()?,
+            1,
+            vec![],
+        );
+        let chain_id2 = "NetXjD3HPJJjmcd".try_into()?;
+
+        // no checkpoints
+        assert!(index.get_checkpoint(&chain_id1)?.is_none());
+        assert!(index.get_checkpoint(&chain_id2)?.is_none());
+
+        // set for chain_id1
+        index.set_checkpoint(&chain_id1, block_1.clone())?;
+        let checkpoint = index.get_checkpoint(&chain_id1)?.unwrap();
+        assert_eq!(checkpoint.block_hash(), block_1.block_hash());
+        assert_eq!(checkpoint.level(), block_1.level());
+        assert!(index.get_checkpoint(&chain_id2)?.is_none());
+
+        // checkpoint does not affect other metadata
+        assert!(index.get_current_head(&chain_id1)?.is_none());
+        assert!(index.get_caboose(&chain_id1)?.is_none());
+
+        Ok(())
+    }
+
     #[test]
     fn test_genesis() -> Result<(), Error> {
         let tmp_storage = TmpStorage::create("__test_genesis")?;
@@ -372,7 +372,7 @@ fn extract_contract_addresses(value: &ContextActionRecordValue) -> Vec<ContractA
 /// 2. "data", "contracts", "index", "p256", "6f", "de", "46", "af", "03", "56a0476dae4e4600172dc9309b3aa4", "balance"
 /// - in this case we use exact hash to transform: p2566fde46af0356a0476dae4e4600172dc9309b3aa4, which conforms "contract id index" length [LEN_TOTAL] [contract_id_to_contract_address]
 ///
-fn action_key_to_contract_address(key: &[String]) -> Option<ContractAddress> {
+pub(crate) fn action_key_to_contract_address(key: &[String]) -> Option<ContractAddress> {
     if key.len() >= 10 && "data" == key[0] && "contracts" == key[1] && "index" == key[2] {
         // check if case 1.
         let contract_id = hex::decode(&key[9]).ok();
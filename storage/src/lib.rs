@@ -48,11 +48,17 @@ use crate::persistent::ActionRecordError;
 use crate::persistent::{CommitLogError, DBError, Decoder, Encoder, SchemaError};
 pub use crate::predecessor_storage::PredecessorStorage;
 pub use crate::system_storage::SystemStorage;
-pub use action_file_storage::ActionFileStorage;
+pub use action_file_storage::{ActionFileStorage, RotatingActionFileStorage};
+pub use action_recorder::{
+    ContextActionRecordFilter, ContextActionSink, ContextActionSinkKind, FilteredActionRecorder,
+};
+pub use action_stream::ActionStreamRecorder;
 use std::str::FromStr;
 
 pub mod action_file;
 pub mod action_file_storage;
+pub mod action_recorder;
+pub mod action_stream;
 pub mod backend;
 pub mod block_meta_storage;
 pub mod block_storage;
@@ -1,6 +1,7 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::{
@@ -556,6 +557,12 @@ pub fn parse_bootstrap_addr_port(
             Some(idx) => {
                 // parse addr
                 let addr_part = &addr[1..idx];
+                if addr_part.is_empty() {
+                    return Err(AddrParseError(format!(
+                        "Invalid value '{}' - empty ipv6 addr part",
+                        addr
+                    )));
+                }
                 let port_part = if idx >= addr.len() - 1 {
                     // no port
                     None
@@ -642,6 +649,21 @@ pub fn parse_bootstrap_addr_port(
     Ok((addr.to_string(), port))
 }
 
+/// Parse ip:port from str to [SocketAddr], if port is not present, then uses default
+///
+/// Unlike [parse_bootstrap_addr_port], address must be <IP> and not domain/host name,
+/// IPv6 addresses must be enclosed with brackets '[...]'
+pub fn parse_socket_addr(addr: &str, default_port: u16) -> Result<SocketAddr, AddrParseError> {
+    let (ip, port) = parse_bootstrap_addr_port(addr, default_port)?;
+    match ip.parse::<IpAddr>() {
+        Ok(ip) => Ok(SocketAddr::new(ip, port)),
+        Err(e) => Err(AddrParseError(format!(
+            "Invalid value '{}' - invalid ip address '{}', reason: {}",
+            addr, ip, e
+        ))),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ZcashParams {
     pub init_sapling_spend_params_file: PathBuf,
@@ -808,6 +830,33 @@ mod tests {
         assert!(parse_bootstrap_addr_port("fe80:e828:209d:20e:c0ae:375", 5).is_err());
         assert!(parse_bootstrap_addr_port("188.40.128.216:a", 5).is_err());
         assert!(parse_bootstrap_addr_port("188.40.128.216:", 5).is_err());
+        assert!(parse_bootstrap_addr_port("[]:9732", 5).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_socket_addr() -> Result<(), AddrParseError> {
+        assert_eq!(
+            "188.40.128.216:5".parse::<SocketAddr>().unwrap(),
+            parse_socket_addr("188.40.128.216", 5)?
+        );
+        assert_eq!(
+            "0.0.0.0:9732".parse::<SocketAddr>().unwrap(),
+            parse_socket_addr("0.0.0.0:9732", 5)?
+        );
+        assert_eq!(
+            "[::]:5".parse::<SocketAddr>().unwrap(),
+            parse_socket_addr("[::]", 5)?
+        );
+        assert_eq!(
+            "[2a01:4f8:171:1f2d::2]:9732".parse::<SocketAddr>().unwrap(),
+            parse_socket_addr("[2a01:4f8:171:1f2d::2]:9732", 5)?
+        );
+
+        assert!(parse_socket_addr("edonet.tezos.co.il:9732", 5).is_err());
+        assert!(parse_socket_addr("2a01:4f8:171:1f2d::2", 5).is_err());
+        assert!(parse_socket_addr("[2a01:4f8:171:1f2d::2]:", 5).is_err());
 
         Ok(())
     }
@@ -0,0 +1,397 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Generates description of the binary representation of [Encoding] in the same format
+//! as `Data_encoding.Binary_schema` used by Tezos RPC `/describe`.
+//!
+//! Schema consists of a `toplevel` encoding and a list of named `fields` (descriptions
+//! of nested objects, unions and recursive encodings), which are referenced from layouts by name.
+
+use serde_json::{json, Value};
+
+use crate::encoding::{Encoding, Field, HasEncoding, SchemaType, TagMap};
+use crate::json_schema::definition_name;
+
+/// Creates description of the binary representation of the `encoding`.
+pub fn binary_schema(encoding: &Encoding) -> Value {
+    let mut generator = BinarySchemaGenerator::default();
+    let toplevel = generator.toplevel(encoding);
+    let fields = generator
+        .descriptions
+        .into_iter()
+        .map(|(name, encoding)| {
+            json!({
+                "description": { "title": name },
+                "encoding": encoding,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "toplevel": toplevel,
+        "fields": fields,
+    })
+}
+
+/// Creates description of the binary representation of the type `T`.
+pub fn binary_schema_of<T: HasEncoding>() -> Value {
+    binary_schema(T::encoding())
+}
+
+/// Size of the binary data described by the field
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DataKind {
+    Fixed(usize),
+    /// Size is known from the data itself (e.g. size prefix)
+    Dynamic,
+    /// Data consumes the rest of the available bytes
+    Variable,
+}
+
+impl DataKind {
+    fn to_json(self) -> Value {
+        match self {
+            // Data_encoding really names the fixed kind "Float"
+            DataKind::Fixed(size) => json!({ "size": size, "kind": "Float" }),
+            DataKind::Dynamic => json!({ "kind": "Dynamic" }),
+            DataKind::Variable => json!({ "kind": "Variable" }),
+        }
+    }
+
+    /// Data kind of the sequence of encodings
+    fn sequence<I: IntoIterator<Item = DataKind>>(kinds: I) -> DataKind {
+        kinds
+            .into_iter()
+            .fold(DataKind::Fixed(0), |acc, kind| match (acc, kind) {
+                (DataKind::Fixed(a), DataKind::Fixed(b)) => DataKind::Fixed(a + b),
+                (DataKind::Variable, _) | (_, DataKind::Variable) => DataKind::Variable,
+                _ => DataKind::Dynamic,
+            })
+    }
+}
+
+#[derive(Default)]
+struct BinarySchemaGenerator {
+    /// Named descriptions in order of registration
+    descriptions: Vec<(String, Value)>,
+}
+
+impl BinarySchemaGenerator {
+    fn toplevel(&mut self, encoding: &Encoding) -> Value {
+        match encoding {
+            Encoding::Obj(fields) => json!({ "fields": self.object_fields(fields) }),
+            Encoding::Tags(tag_size, tags) => self.cases(*tag_size, tags),
+            Encoding::Split(fn_encoding) => self.toplevel(&fn_encoding(SchemaType::Binary)),
+            encoding => json!({ "fields": self.fields(None, encoding) }),
+        }
+    }
+
+    fn object_fields(&mut self, fields: &[Field]) -> Vec<Value> {
+        fields
+            .iter()
+            .flat_map(|field| self.fields(Some(field.get_name()), field.get_encoding()))
+            .collect()
+    }
+
+    fn cases(&mut self, tag_size: usize, tags: &TagMap) -> Value {
+        let mut tags = tags.tags().collect::<Vec<_>>();
+        tags.sort_by_key(|tag| tag.get_id());
+        let cases = tags
+            .into_iter()
+            .map(|tag| {
+                json!({
+                    "tag": tag.get_id(),
+                    "fields": self.fields(None, tag.get_encoding()),
+                    "name": tag.get_variant(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let kind = DataKind::sequence(vec![DataKind::Fixed(tag_size), DataKind::Dynamic]);
+        json!({
+            "tag_size": if tag_size == 1 { "Uint8" } else { "Uint16" },
+            "kind": kind.to_json(),
+            "cases": cases,
+        })
+    }
+
+    /// Describes encoding as a list of fields, `name` is [None] for anonymous fields.
+    fn fields(&mut self, name: Option<&String>, encoding: &Encoding) -> Vec<Value> {
+        match encoding {
+            Encoding::Unit => vec![],
+            Encoding::Obj(fields) => self.object_fields(fields),
+            Encoding::Tup(encodings) => encodings
+                .iter()
+                .flat_map(|encoding| self.fields(None, encoding))
+                .collect(),
+            Encoding::OptionalField(inner) | Encoding::Option(inner) => {
+                let mut fields = vec![json!({
+                    "kind": "option_indicator",
+                    "name": name.map(String::as_str).unwrap_or("option"),
+                })];
+                fields.extend(self.fields(name, inner));
+                fields
+            }
+            Encoding::String | Encoding::BoundedString(_) => vec![
+                dynamic_size(name),
+                field(name, DataKind::Variable, json!({ "kind": "String" })),
+            ],
+            Encoding::Dynamic(inner) | Encoding::BoundedDynamic(_, inner) => {
+                let mut fields = vec![dynamic_size(name)];
+                fields.extend(self.fields(name, inner));
+                fields
+            }
+            Encoding::Bounded(_, inner) => self.fields(name, inner),
+            Encoding::Split(fn_encoding) => self.fields(name, &fn_encoding(SchemaType::Binary)),
+            encoding => {
+                let layout = self.layout(name, encoding);
+                vec![field(name, data_kind(encoding), layout)]
+            }
+        }
+    }
+
+    fn layout(&mut self, name: Option<&String>, encoding: &Encoding) -> Value {
+        match encoding {
+            Encoding::Unit => json!({ "kind": "Zero_width" }),
+            Encoding::Int8 => int_layout("Int8"),
+            Encoding::Uint8 => int_layout("Uint8"),
+            Encoding::Int16 => int_layout("Int16"),
+            Encoding::Uint16 => int_layout("Uint16"),
+            Encoding::Int31 | Encoding::RangedInt => int_layout("Int31"),
+            Encoding::Int32 | Encoding::Uint32 => int_layout("Int32"),
+            Encoding::Int64 | Encoding::Timestamp => int_layout("Int64"),
+            Encoding::Float | Encoding::RangedFloat => json!({ "kind": "Float" }),
+            Encoding::Bool => json!({ "kind": "Bool" }),
+            Encoding::Enum => json!({
+                "size": "Uint8",
+                "reference": name.map(String::as_str).unwrap_or("enum"),
+                "kind": "Enum",
+            }),
+            Encoding::Z => self.zarith_reference("Z.t"),
+            Encoding::Mutez => self.zarith_reference("N.t"),
+            Encoding::Bytes | Encoding::Hash(_) | Encoding::Custom(_) => json!({ "kind": "Bytes" }),
+            Encoding::String | Encoding::BoundedString(_) => json!({ "kind": "String" }),
+            Encoding::List(inner) => json!({ "layout": self.layout(name, inner), "kind": "Seq" }),
+            Encoding::BoundedList(max, inner) => json!({
+                "layout": self.layout(name, inner),
+                "kind": "Seq",
+                "max_length": max,
+            }),
+            Encoding::Sized(_, inner) | Encoding::Bounded(_, inner) | Encoding::Greedy(inner) => {
+                self.layout(name, inner)
+            }
+            Encoding::Split(fn_encoding) => self.layout(name, &fn_encoding(SchemaType::Binary)),
+            Encoding::Lazy(fn_encoding) => {
+                let reference = definition_name(fn_encoding.as_ref());
+                if !self.is_registered(&reference) {
+                    // register name before expansion to stop the recursion
+                    self.descriptions.push((reference.clone(), Value::Null));
+                    let toplevel = self.toplevel(&fn_encoding());
+                    self.update(&reference, toplevel);
+                }
+                ref_layout(&reference)
+            }
+            // the rest of the encodings is described by a separate (referenced) description
+            encoding => {
+                let reference = self.unique_name(name.map(String::as_str).unwrap_or("object"));
+                self.descriptions.push((reference.clone(), Value::Null));
+                let toplevel = self.toplevel(encoding);
+                self.update(&reference, toplevel);
+                ref_layout(&reference)
+            }
+        }
+    }
+
+    fn zarith_reference(&mut self, reference: &str) -> Value {
+        if !self.is_registered(reference) {
+            self.descriptions.push((
+                reference.to_string(),
+                json!({
+                    "fields": [{
+                        "name": reference,
+                        "layout": { "kind": "Bytes" },
+                        "data_kind": DataKind::Dynamic.to_json(),
+                        "kind": "named",
+                    }]
+                }),
+            ));
+        }
+        ref_layout(reference)
+    }
+
+    fn is_registered(&self, reference: &str) -> bool {
+        self.descriptions.iter().any(|(name, _)| name == reference)
+    }
+
+    fn update(&mut self, reference: &str, toplevel: Value) {
+        if let Some((_, description)) = self
+            .descriptions
+            .iter_mut()
+            .find(|(name, _)| name == reference)
+        {
+            *description = toplevel;
+        }
+    }
+
+    fn unique_name(&self, name: &str) -> String {
+        let mut unique_name = name.to_string();
+        let mut counter = 0;
+        while self.is_registered(&unique_name) {
+            counter += 1;
+            unique_name = format!("{}.{}", name, counter);
+        }
+        unique_name
+    }
+}
+
+fn data_kind(encoding: &Encoding) -> DataKind {
+    match encoding {
+        Encoding::Unit => DataKind::Fixed(0),
+        Encoding::Int8 | Encoding::Uint8 | Encoding::Bool | Encoding::Enum => DataKind::Fixed(1),
+        Encoding::Int16 | Encoding::Uint16 => DataKind::Fixed(2),
+        Encoding::Int31 | Encoding::Int32 | Encoding::Uint32 | Encoding::RangedInt => {
+            DataKind::Fixed(4)
+        }
+        Encoding::Int64 | Encoding::Timestamp | Encoding::Float | Encoding::RangedFloat => {
+            DataKind::Fixed(8)
+        }
+        Encoding::Hash(hash_type) => DataKind::Fixed(hash_type.size()),
+        Encoding::Sized(size, _) => DataKind::Fixed(*size),
+        Encoding::Z
+        | Encoding::Mutez
+        | Encoding::String
+        | Encoding::BoundedString(_)
+        | Encoding::Dynamic(_)
+        | Encoding::BoundedDynamic(..)
+        | Encoding::Lazy(_) => DataKind::Dynamic,
+        Encoding::Bytes
+        | Encoding::Custom(_)
+        | Encoding::List(_)
+        | Encoding::BoundedList(..)
+        | Encoding::Greedy(_) => DataKind::Variable,
+        Encoding::Bounded(_, inner) => data_kind(inner),
+        Encoding::Split(fn_encoding) => data_kind(&fn_encoding(SchemaType::Binary)),
+        Encoding::Obj(fields) => {
+            DataKind::sequence(fields.iter().map(|field| data_kind(field.get_encoding())))
+        }
+        Encoding::Tup(encodings) => DataKind::sequence(encodings.iter().map(data_kind)),
+        Encoding::Option(inner) | Encoding::OptionalField(inner) => match data_kind(inner) {
+            DataKind::Variable => DataKind::Variable,
+            _ => DataKind::Dynamic,
+        },
+        Encoding::Tags(_, tags) => {
+            if tags
+                .tags()
+                .any(|tag| data_kind(tag.get_encoding()) == DataKind::Variable)
+            {
+                DataKind::Variable
+            } else {
+                DataKind::Dynamic
+            }
+        }
+    }
+}
+
+fn field(name: Option<&String>, kind: DataKind, layout: Value) -> Value {
+    match name {
+        Some(name) => json!({
+            "name": name,
+            "layout": layout,
+            "data_kind": kind.to_json(),
+            "kind": "named",
+        }),
+        None => json!({
+            "layout": layout,
+            "kind": "anon",
+            "data_kind": kind.to_json(),
+        }),
+    }
+}
+
+/// Size prefix of the dynamic data (4 bytes)
+fn dynamic_size(name: Option<&String>) -> Value {
+    match name {
+        Some(name) => json!({ "name": name, "kind": "dyn", "num_fields": 1, "size": "Uint30" }),
+        None => json!({ "kind": "dyn", "num_fields": 1, "size": "Uint30" }),
+    }
+}
+
+fn int_layout(size: &str) -> Value {
+    json!({ "size": size, "kind": "Int" })
+}
+
+fn ref_layout(reference: &str) -> Value {
+    json!({ "name": reference, "kind": "Ref" })
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto::hash::HashType;
+
+    use crate::encoding::Tag;
+
+    use super::*;
+
+    #[test]
+    fn can_generate_object_binary_schema() {
+        let encoding = Encoding::Obj(vec![
+            Field::new("level", Encoding::Int32),
+            Field::new("predecessor", Encoding::Hash(HashType::BlockHash)),
+            Field::new("balance", Encoding::Mutez),
+            Field::new("message", Encoding::option_field(Encoding::String)),
+        ]);
+
+        let schema = binary_schema(&encoding);
+        let fields = &schema["toplevel"]["fields"];
+        assert_eq!(
+            json!({ "name": "level", "layout": { "size": "Int32", "kind": "Int" }, "data_kind": { "size": 4, "kind": "Float" }, "kind": "named" }),
+            fields[0]
+        );
+        assert_eq!(
+            json!({ "name": "predecessor", "layout": { "kind": "Bytes" }, "data_kind": { "size": 32, "kind": "Float" }, "kind": "named" }),
+            fields[1]
+        );
+        assert_eq!(json!({ "name": "N.t", "kind": "Ref" }), fields[2]["layout"]);
+        assert_eq!("option_indicator", fields[3]["kind"]);
+        assert_eq!("dyn", fields[4]["kind"]);
+        assert_eq!("String", fields[5]["layout"]["kind"]);
+
+        assert_eq!("N.t", schema["fields"][0]["description"]["title"]);
+    }
+
+    #[test]
+    fn can_generate_union_binary_schema() {
+        let encoding = Encoding::Obj(vec![Field::new(
+            "contents",
+            Encoding::dynamic(Encoding::list(Encoding::Tags(
+                1,
+                TagMap::new(vec![
+                    Tag::new(0, "Nonce", Encoding::sized(32, Encoding::Bytes)),
+                    Tag::new(
+                        1,
+                        "Ballot",
+                        Encoding::Obj(vec![Field::new("period", Encoding::Int32)]),
+                    ),
+                ]),
+            ))),
+        )]);
+
+        let schema = binary_schema(&encoding);
+        let fields = &schema["toplevel"]["fields"];
+        assert_eq!("dyn", fields[0]["kind"]);
+        assert_eq!("Seq", fields[1]["layout"]["kind"]);
+        assert_eq!(
+            json!({ "name": "contents", "kind": "Ref" }),
+            fields[1]["layout"]["layout"]
+        );
+
+        let cases = &schema["fields"][0];
+        assert_eq!("contents", cases["description"]["title"]);
+        assert_eq!("Uint8", cases["encoding"]["tag_size"]);
+        assert_eq!("Nonce", cases["encoding"]["cases"][0]["name"]);
+        assert_eq!(
+            32,
+            cases["encoding"]["cases"][0]["fields"][0]["data_kind"]["size"]
+        );
+        assert_eq!("Ballot", cases["encoding"]["cases"][1]["name"]);
+        assert_eq!("period", cases["encoding"]["cases"][1]["fields"][0]["name"]);
+    }
+}
@@ -62,6 +62,15 @@ where
     Ok(data)
 }
 
+/// Convert [intermediate form](Value) into Tezos binary form. Binary form is defined by [`encoding`](Encoding).
+pub fn write_value(value: &Value, encoding: &Encoding) -> Result<Vec<u8>, BinaryWriterError> {
+    let mut data = Vec::with_capacity(512);
+
+    encode_any(&mut data, value, encoding)?;
+
+    Ok(data)
+}
+
 fn encode_any(
     data: &mut Vec<u8>,
     value: &Value,
@@ -208,6 +217,14 @@ fn encode_value(
                     Err(Error::custom("Value is outside of Uint32 range"))?
                 }
             }
+            // values above i32::MAX
+            Value::Int64(v) => match u32::try_from(*v) {
+                Ok(v) => {
+                    data.put_u32(v);
+                    Ok(size_of::<u32>())
+                }
+                Err(_) => Err(Error::custom("Value is outside of Uint32 range").into()),
+            },
             _ => Err(Error::encoding_mismatch(encoding, value))?,
         },
         Encoding::RangedInt => Err(Error::custom("Encoding::RangedInt is not implemented"))?,
@@ -7,7 +7,12 @@ use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
 
-use crypto::hash::HashType;
+use crypto::hash::{
+    BlockHash, BlockMetadataHash, ChainId, ContextHash, ContractKt1Hash, ContractTz1Hash,
+    ContractTz2Hash, ContractTz3Hash, CryptoboxPublicKeyHash, HashType, OperationHash,
+    OperationListListHash, OperationMetadataHash, OperationMetadataListListHash, ProtocolHash,
+    PublicKeyEd25519, PublicKeyP256, PublicKeySecp256k1,
+};
 
 use crate::binary_reader::BinaryReaderError;
 use crate::ser::Error;
@@ -127,9 +132,19 @@ impl fmt::Debug for dyn SplitEncodingFn<Output = Encoding> + Send + Sync {
     }
 }
 
-pub trait RecursiveEncodingFn: Fn() -> Encoding + Send + Sync {}
+pub trait RecursiveEncodingFn: Fn() -> Encoding + Send + Sync {
+    /// Name of the function, identifies recursive encoding in generated schemas.
+    fn name(&self) -> &'static str;
+}
 
-impl<F> RecursiveEncodingFn for F where F: Fn() -> Encoding + Send + Sync {}
+impl<F> RecursiveEncodingFn for F
+where
+    F: Fn() -> Encoding + Send + Sync,
+{
+    fn name(&self) -> &'static str {
+        std::any::type_name::<F>()
+    }
+}
 
 impl fmt::Debug for dyn RecursiveEncodingFn<Output = Encoding> + Send + Sync {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -354,6 +369,9 @@ pub trait HasEncoding {
     fn encoding() -> &'static Encoding;
 }
 
+/// Derives [HasEncoding] from the type definition, see [tezos_encoding_derive] for attributes.
+pub use tezos_encoding_derive::HasEncoding;
+
 /// Creates impl HasEncoding for given struct backed by lazy_static ref instance with encoding.
 #[macro_export]
 macro_rules! has_encoding {
@@ -372,6 +390,53 @@ macro_rules! has_encoding {
     };
 }
 
+/// Implements [HasEncoding] for types with encoding known at compile time.
+macro_rules! has_static_encoding {
+    ($($type_name:ty => $encoding:expr),* $(,)?) => {
+        $(
+            impl HasEncoding for $type_name {
+                fn encoding() -> &'static Encoding {
+                    static ENCODING: Encoding = $encoding;
+                    &ENCODING
+                }
+            }
+        )*
+    };
+}
+
+has_static_encoding!(
+    bool => Encoding::Bool,
+    i8 => Encoding::Int8,
+    u8 => Encoding::Uint8,
+    i16 => Encoding::Int16,
+    u16 => Encoding::Uint16,
+    i32 => Encoding::Int32,
+    u32 => Encoding::Uint32,
+    i64 => Encoding::Int64,
+    f64 => Encoding::Float,
+    String => Encoding::String,
+);
+
+has_static_encoding!(
+    ChainId => Encoding::Hash(HashType::ChainId),
+    BlockHash => Encoding::Hash(HashType::BlockHash),
+    BlockMetadataHash => Encoding::Hash(HashType::BlockMetadataHash),
+    OperationHash => Encoding::Hash(HashType::OperationHash),
+    OperationListListHash => Encoding::Hash(HashType::OperationListListHash),
+    OperationMetadataHash => Encoding::Hash(HashType::OperationMetadataHash),
+    OperationMetadataListListHash => Encoding::Hash(HashType::OperationMetadataListListHash),
+    ContextHash => Encoding::Hash(HashType::ContextHash),
+    ProtocolHash => Encoding::Hash(HashType::ProtocolHash),
+    ContractKt1Hash => Encoding::Hash(HashType::ContractKt1Hash),
+    ContractTz1Hash => Encoding::Hash(HashType::ContractTz1Hash),
+    ContractTz2Hash => Encoding::Hash(HashType::ContractTz2Hash),
+    ContractTz3Hash => Encoding::Hash(HashType::ContractTz3Hash),
+    CryptoboxPublicKeyHash => Encoding::Hash(HashType::CryptoboxPublicKeyHash),
+    PublicKeyEd25519 => Encoding::Hash(HashType::PublicKeyEd25519),
+    PublicKeySecp256k1 => Encoding::Hash(HashType::PublicKeySecp256k1),
+    PublicKeyP256 => Encoding::Hash(HashType::PublicKeyP256),
+);
+
 #[cfg(test)]
 mod tests {
     use crate::binary_reader::BinaryReader;
@@ -43,6 +43,10 @@ impl<T: Display + Send + Sync + Clone + 'static> EncodingError<T> {
     pub(crate) fn element_of(&self) -> ErrorInfo<T> {
         self.inner.get_context().element_of()
     }
+
+    pub(crate) fn element_at(&self, index: usize) -> ErrorInfo<T> {
+        self.inner.get_context().element_at(index)
+    }
 }
 
 impl<T: Display + Send + Sync + 'static> From<T> for EncodingError<T> {
@@ -88,6 +92,15 @@ impl<T: Display + Send + Sync + Clone + 'static> ErrorInfo<T> {
         };
         ErrorInfo(self.0.clone(), msg)
     }
+
+    pub fn element_at(&self, index: usize) -> Self {
+        let msg = if self.1.is_empty() {
+            format!("list element #{}", index)
+        } else {
+            format!("{} @ list element #{}", self.1, index)
+        };
+        ErrorInfo(self.0.clone(), msg)
+    }
 }
 
 impl<T: Display + Send + Sync + 'static> Display for ErrorInfo<T> {
@@ -0,0 +1,662 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Tezos JSON data reader.
+//!
+//! Reads JSON in the format produced by Tezos RPC (Base58Check encoded hashes, decimal strings
+//! for `Z`, `Mutez` and 64 bit integers, RFC 3339 timestamps, hex encoded bytes) into
+//! the [intermediate form](Value), which can be converted to rust types with [crate::de]
+//! or to the Tezos binary form with [crate::binary_writer].
+
+use std::convert::TryFrom;
+
+use chrono::DateTime;
+use failure::{Fail, ResultExt};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::binary_writer;
+use crate::encoding::{Encoding, Field, SchemaType, TagMap};
+use crate::types::Value;
+
+use super::error_context::EncodingError;
+
+/// Error produced by a [JsonReader].
+pub type JsonReaderError = EncodingError<JsonReaderErrorKind>;
+
+/// Kind of error for [JsonReaderError]
+#[derive(Debug, Fail, Clone)]
+pub enum JsonReaderErrorKind {
+    /// JSON value type does not correspond to the encoding.
+    #[fail(display = "Was expecting {} but found: {}", expected, found)]
+    TypeMismatch {
+        expected: &'static str,
+        found: String,
+    },
+    /// JSON value has expected type, but its content is not valid for the encoding.
+    #[fail(display = "Invalid value: {}", reason)]
+    InvalidValue { reason: String },
+    /// Required field is missing in the JSON object.
+    #[fail(display = "Missing field: {}", name)]
+    MissingField { name: String },
+    /// JSON object contains field, which is not defined by the encoding.
+    #[fail(display = "Unexpected field: {}", name)]
+    UnexpectedField { name: String },
+    /// None of the tagged encodings matched the JSON value.
+    #[fail(display = "No tag matches the value: {}", value)]
+    NoMatchingTag { value: String },
+    /// Encoding boundary constraint violation
+    #[fail(
+        display = "Encoded data {} exceeded its size boundary: {}, actual: {}",
+        name, boundary, actual
+    )]
+    EncodingBoundaryExceeded {
+        name: String,
+        boundary: usize,
+        actual: usize,
+    },
+    /// Encoding cannot be read from JSON.
+    #[fail(display = "Unsupported encoding: {}", encoding)]
+    UnsupportedEncoding { encoding: &'static str },
+    /// Intermediate form could not be written in binary form.
+    #[fail(display = "Binary writer error: {}", reason)]
+    BinaryWriterError { reason: String },
+}
+
+/// Converts Tezos JSON into [intermediate form](Value) or Tezos binary form.
+pub struct JsonReader;
+
+impl JsonReader {
+    /// Construct new instance of the [JsonReader].
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Convert Tezos JSON into [intermediate form](Value). Input JSON is parsed according to [`encoding`](Encoding).
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use tezos_encoding::json_reader::JsonReader;
+    /// use tezos_encoding::de;
+    /// use tezos_encoding::encoding::{Field, Encoding};
+    ///
+    /// #[derive(Deserialize, Debug, PartialEq)]
+    /// struct Version {
+    ///    name: String,
+    ///    major: u16,
+    ///    minor: u16,
+    /// }
+    ///
+    /// let version_schema = Encoding::Obj(vec![
+    ///     Field::new("name", Encoding::String),
+    ///     Field::new("major", Encoding::Uint16),
+    ///     Field::new("minor", Encoding::Uint16)
+    /// ]);
+    ///
+    /// let json = serde_json::json!({ "name": "v1.0", "major": 1, "minor": 0 });
+    ///
+    /// let reader = JsonReader::new();
+    /// // create intermediate form
+    /// let intermediate = reader.read(&json, &version_schema).unwrap();
+    /// // deserialize from intermediate form
+    /// let version = de::from_value::<Version>(&intermediate).unwrap();
+    ///
+    /// let version_expected = Version { name: "v1.0".into(), major: 1, minor: 0 };
+    ///
+    /// assert_eq!(version, version_expected);
+    /// ```
+    pub fn read(&self, json: &JsonValue, encoding: &Encoding) -> Result<Value, JsonReaderError> {
+        self.decode_value(json, encoding)
+    }
+
+    /// Convert Tezos JSON into Tezos binary form. Input JSON is parsed according to [`encoding`](Encoding).
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use tezos_encoding::json_reader::JsonReader;
+    /// use tezos_encoding::encoding::{Field, Encoding};
+    ///
+    /// let version_schema = Encoding::Obj(vec![
+    ///     Field::new("name", Encoding::String),
+    ///     Field::new("major", Encoding::Uint16),
+    ///     Field::new("minor", Encoding::Uint16)
+    /// ]);
+    ///
+    /// let json = serde_json::json!({ "name": "v1.0", "major": 1, "minor": 0 });
+    ///
+    /// let binary = JsonReader::new().read_binary(&json, &version_schema).unwrap();
+    ///
+    /// assert_eq!(binary, hex::decode("0000000476312e3000010000").unwrap());
+    /// ```
+    pub fn read_binary(
+        &self,
+        json: &JsonValue,
+        encoding: &Encoding,
+    ) -> Result<Vec<u8>, JsonReaderError> {
+        let value = self.read(json, encoding)?;
+        binary_writer::write_value(&value, encoding).map_err(|e| {
+            JsonReaderErrorKind::BinaryWriterError {
+                reason: e.to_string(),
+            }
+            .into()
+        })
+    }
+
+    fn decode_record(&self, json: &JsonValue, schema: &[Field]) -> Result<Value, JsonReaderError> {
+        let object = as_object(json)?;
+        if let Some(name) = object
+            .keys()
+            .find(|name| !schema.iter().any(|field| field.get_name() == *name))
+        {
+            return Err(JsonReaderErrorKind::UnexpectedField { name: name.clone() }.into());
+        }
+
+        let mut values = Vec::with_capacity(schema.len());
+        for field in schema {
+            let name = field.get_name();
+            let value = match (object.get(name), field.get_encoding()) {
+                (None, Encoding::OptionalField(_)) => Value::Option(None),
+                (None, _) => {
+                    return Err(JsonReaderErrorKind::MissingField { name: name.clone() }.into());
+                }
+                (Some(json), encoding) => self
+                    .decode_value(json, encoding)
+                    .with_context(|e| e.field(name))?,
+            };
+            values.push((name.clone(), value));
+        }
+        Ok(Value::Record(values))
+    }
+
+    fn decode_tuple(
+        &self,
+        json: &JsonValue,
+        encodings: &[Encoding],
+    ) -> Result<Value, JsonReaderError> {
+        let array = as_array(json)?;
+        if array.len() != encodings.len() {
+            return Err(JsonReaderErrorKind::InvalidValue {
+                reason: format!(
+                    "Was expecting tuple of {} elements but found {}",
+                    encodings.len(),
+                    array.len()
+                ),
+            }
+            .into());
+        }
+        let mut values = Vec::with_capacity(encodings.len());
+        for (index, (json, encoding)) in array.iter().zip(encodings).enumerate() {
+            values.push(
+                self.decode_value(json, encoding)
+                    .with_context(|e| e.element_at(index))?,
+            );
+        }
+        Ok(Value::Tuple(values))
+    }
+
+    fn decode_list(
+        &self,
+        json: &JsonValue,
+        max: Option<usize>,
+        encoding: &Encoding,
+    ) -> Result<Value, JsonReaderError> {
+        let array = as_array(json)?;
+        if let Some(max) = max {
+            if array.len() > max {
+                return Err(JsonReaderErrorKind::EncodingBoundaryExceeded {
+                    name: "Encoding::BoundedList".to_string(),
+                    boundary: max,
+                    actual: array.len(),
+                }
+                .into());
+            }
+        }
+        let mut values = Vec::with_capacity(array.len());
+        for (index, json) in array.iter().enumerate() {
+            values.push(
+                self.decode_value(json, encoding)
+                    .with_context(|e| e.element_at(index))?,
+            );
+        }
+        Ok(Value::List(values))
+    }
+
+    fn decode_tag(&self, json: &JsonValue, tag_map: &TagMap) -> Result<Value, JsonReaderError> {
+        let mut tags = tag_map.tags().collect::<Vec<_>>();
+        tags.sort_by_key(|tag| tag.get_id());
+
+        // Tezos objects usually contain `kind` field, which identifies the variant
+        let kind = json
+            .get("kind")
+            .and_then(JsonValue::as_str)
+            .map(normalize_variant);
+        if let Some(kind) = kind {
+            if let Some(tag) = tags
+                .iter()
+                .find(|tag| normalize_variant(tag.get_variant()) == kind)
+            {
+                let value = self.decode_value(json, tag.get_encoding())?;
+                return Ok(Value::Tag(tag.get_variant().clone(), Box::new(value)));
+            }
+        }
+
+        // otherwise the first variant, which is able to read the value, is used
+        tags.iter()
+            .find_map(|tag| {
+                self.decode_value(json, tag.get_encoding())
+                    .ok()
+                    .map(|value| Value::Tag(tag.get_variant().clone(), Box::new(value)))
+            })
+            .ok_or_else(|| {
+                JsonReaderErrorKind::NoMatchingTag {
+                    value: describe(json),
+                }
+                .into()
+            })
+    }
+
+    fn decode_value(
+        &self,
+        json: &JsonValue,
+        encoding: &Encoding,
+    ) -> Result<Value, JsonReaderError> {
+        match encoding {
+            Encoding::Unit => Ok(Value::Unit),
+            Encoding::Int8 => Ok(Value::Int8(as_integer(json)?)),
+            Encoding::Uint8 => Ok(Value::Uint8(as_integer(json)?)),
+            Encoding::Int16 => Ok(Value::Int16(as_integer(json)?)),
+            Encoding::Uint16 => Ok(Value::Uint16(as_integer(json)?)),
+            Encoding::Int31 => {
+                let value: i32 = as_integer(json)?;
+                if (-(1 << 30)..(1 << 30)).contains(&value) {
+                    Ok(Value::Int32(value))
+                } else {
+                    Err(invalid_value(format!("{} is outside of Int31 range", value)).into())
+                }
+            }
+            Encoding::Int32 => Ok(Value::Int32(as_integer(json)?)),
+            // Uint32 is represented by i32 in the intermediate form, values above i32::MAX by i64
+            Encoding::Uint32 => {
+                let value: i64 = as_integer(json)?;
+                if u32::try_from(value).is_err() {
+                    Err(invalid_value(format!("{} is outside of Uint32 range", value)).into())
+                } else if let Ok(value) = i32::try_from(value) {
+                    Ok(Value::Int32(value))
+                } else {
+                    Ok(Value::Int64(value))
+                }
+            }
+            Encoding::RangedInt => Ok(Value::RangedInt(as_integer(json)?)),
+            Encoding::Int64 => Ok(Value::Int64(as_int64(json)?)),
+            Encoding::Timestamp => match json {
+                JsonValue::String(value) => match DateTime::parse_from_rfc3339(value) {
+                    Ok(timestamp) => Ok(Value::Int64(timestamp.timestamp())),
+                    // Tezos also accepts timestamp as a number of seconds
+                    Err(_) => Ok(Value::Int64(as_int64(json).map_err(|_| {
+                        invalid_value(format!("{} is not valid RFC 3339 timestamp", value))
+                    })?)),
+                },
+                _ => Ok(Value::Int64(as_int64(json)?)),
+            },
+            Encoding::Z => Ok(Value::String(as_big_int(json, false)?)),
+            Encoding::Mutez => Ok(Value::String(as_big_int(json, true)?)),
+            Encoding::Float => Ok(Value::Float(as_float(json)?)),
+            Encoding::RangedFloat => Ok(Value::RangedFloat(as_float(json)?)),
+            Encoding::Bool => match json {
+                JsonValue::Bool(value) => Ok(Value::Bool(*value)),
+                _ => Err(type_mismatch("boolean", json).into()),
+            },
+            Encoding::String => Ok(Value::String(as_str(json)?.to_string())),
+            Encoding::BoundedString(max) => {
+                let value = as_str(json)?;
+                if value.len() > *max {
+                    Err(JsonReaderErrorKind::EncodingBoundaryExceeded {
+                        name: "Encoding::BoundedString".to_string(),
+                        boundary: *max,
+                        actual: value.len(),
+                    }
+                    .into())
+                } else {
+                    Ok(Value::String(value.to_string()))
+                }
+            }
+            Encoding::Bytes => {
+                let bytes = hex::decode(as_str(json)?)
+                    .map_err(|e| invalid_value(format!("Invalid hex value: {}", e)))?;
+                Ok(bytes_value(bytes))
+            }
+            Encoding::Hash(hash_type) => {
+                let hash = hash_type
+                    .b58check_to_hash(as_str(json)?)
+                    .map_err(|e| invalid_value(format!("Invalid {:?}: {}", hash_type, e)))?;
+                Ok(bytes_value(hash))
+            }
+            Encoding::Enum => match json {
+                JsonValue::String(variant) => Ok(Value::Enum(Some(variant.clone()), None)),
+                _ => Ok(Value::Enum(None, Some(as_integer(json)?))),
+            },
+            Encoding::Tags(_, tag_map) => self.decode_tag(json, tag_map),
+            Encoding::List(inner) => self.decode_list(json, None, inner),
+            Encoding::BoundedList(max, inner) => self.decode_list(json, Some(*max), inner),
+            Encoding::Option(inner) | Encoding::OptionalField(inner) => match json {
+                JsonValue::Null => Ok(Value::Option(None)),
+                json => Ok(Value::Option(Some(Box::new(
+                    self.decode_value(json, inner)?,
+                )))),
+            },
+            Encoding::Obj(schema) => self.decode_record(json, schema),
+            Encoding::Tup(encodings) => self.decode_tuple(json, encodings),
+            // binary size constraints are checked by binary writer
+            Encoding::Dynamic(inner)
+            | Encoding::BoundedDynamic(_, inner)
+            | Encoding::Sized(_, inner)
+            | Encoding::Bounded(_, inner)
+            | Encoding::Greedy(inner) => self.decode_value(json, inner),
+            Encoding::Split(fn_encoding) => self.decode_value(json, &fn_encoding(SchemaType::Json)),
+            Encoding::Lazy(fn_encoding) => self.decode_value(json, &fn_encoding()),
+            Encoding::Custom(_) => Err(JsonReaderErrorKind::UnsupportedEncoding {
+                encoding: "Encoding::Custom",
+            }
+            .into()),
+        }
+    }
+}
+
+impl Default for JsonReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn type_mismatch(expected: &'static str, json: &JsonValue) -> JsonReaderErrorKind {
+    JsonReaderErrorKind::TypeMismatch {
+        expected,
+        found: describe(json),
+    }
+}
+
+fn invalid_value(reason: String) -> JsonReaderErrorKind {
+    JsonReaderErrorKind::InvalidValue { reason }
+}
+
+/// Short description of the JSON value for error messages
+fn describe(json: &JsonValue) -> String {
+    match json {
+        JsonValue::Object(_) => "object".to_string(),
+        JsonValue::Array(_) => "array".to_string(),
+        json => json.to_string(),
+    }
+}
+
+/// Variant names are compared without case and underscores, e.g.: `seed_nonce_revelation` == `SeedNonceRevelation`
+fn normalize_variant(variant: &str) -> String {
+    variant
+        .chars()
+        .filter(|c| *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn bytes_value(bytes: Vec<u8>) -> Value {
+    Value::List(bytes.into_iter().map(Value::Uint8).collect())
+}
+
+fn as_object(json: &JsonValue) -> Result<&Map<String, JsonValue>, JsonReaderErrorKind> {
+    json.as_object()
+        .ok_or_else(|| type_mismatch("object", json))
+}
+
+fn as_array(json: &JsonValue) -> Result<&Vec<JsonValue>, JsonReaderErrorKind> {
+    json.as_array().ok_or_else(|| type_mismatch("array", json))
+}
+
+fn as_str(json: &JsonValue) -> Result<&str, JsonReaderErrorKind> {
+    json.as_str().ok_or_else(|| type_mismatch("string", json))
+}
+
+fn as_float(json: &JsonValue) -> Result<f64, JsonReaderErrorKind> {
+    json.as_f64().ok_or_else(|| type_mismatch("number", json))
+}
+
+fn as_integer<T: TryFrom<i64>>(json: &JsonValue) -> Result<T, JsonReaderErrorKind> {
+    let value = json
+        .as_i64()
+        .ok_or_else(|| type_mismatch("integer", json))?;
+    T::try_from(value).map_err(|_| {
+        invalid_value(format!(
+            "{} is outside of {} range",
+            value,
+            std::any::type_name::<T>()
+        ))
+    })
+}
+
+/// 64 bit integers are encoded as decimal strings in Tezos JSON, but numbers are accepted too
+fn as_int64(json: &JsonValue) -> Result<i64, JsonReaderErrorKind> {
+    match json {
+        JsonValue::String(value) => value
+            .parse()
+            .map_err(|_| invalid_value(format!("{} is not valid 64 bit integer", value))),
+        _ => as_integer(json),
+    }
+}
+
+/// Big numbers are encoded as decimal strings in Tezos JSON, intermediate form uses hex string
+fn as_big_int(json: &JsonValue, positive: bool) -> Result<String, JsonReaderErrorKind> {
+    let value = match json {
+        JsonValue::String(value) => value.clone(),
+        JsonValue::Number(value) if value.is_i64() || value.is_u64() => value.to_string(),
+        _ => return Err(type_mismatch("decimal string", json)),
+    };
+    let digits = value.strip_prefix('-').unwrap_or(&value);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid_value(format!(
+            "{} is not valid decimal number",
+            value
+        )));
+    }
+    let number = num_bigint::BigInt::parse_bytes(value.as_bytes(), 10)
+        .ok_or_else(|| invalid_value(format!("{} is not valid decimal number", value)))?;
+    if positive && number.sign() == num_bigint::Sign::Minus {
+        return Err(invalid_value(format!("{} is not positive number", value)));
+    }
+    Ok(number.to_str_radix(16))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use crypto::hash::HashType;
+
+    use crate::encoding::Tag;
+
+    use super::*;
+
+    fn block_header_encoding() -> Encoding {
+        Encoding::Obj(vec![
+            Field::new("level", Encoding::Int32),
+            Field::new("proto", Encoding::Uint8),
+            Field::new("predecessor", Encoding::Hash(HashType::BlockHash)),
+            Field::new("timestamp", Encoding::Timestamp),
+            Field::new("validation_pass", Encoding::Uint8),
+            Field::new(
+                "operations_hash",
+                Encoding::Hash(HashType::OperationListListHash),
+            ),
+            Field::new(
+                "fitness",
+                Encoding::Split(Arc::new(|schema_type| match schema_type {
+                    SchemaType::Json => Encoding::dynamic(Encoding::list(Encoding::Bytes)),
+                    SchemaType::Binary => Encoding::dynamic(Encoding::list(Encoding::dynamic(
+                        Encoding::list(Encoding::Uint8),
+                    ))),
+                })),
+            ),
+            Field::new("context", Encoding::Hash(HashType::ContextHash)),
+            Field::new("protocol_data", Encoding::Bytes),
+        ])
+    }
+
+    #[test]
+    fn can_read_block_header_to_binary() {
+        let json = json!({
+            "level": 28014,
+            "proto": 1,
+            "predecessor": "BKjYUUtYXtXjEuL49jB8ZbFwVdg4hU6U7oKKSC5vp6stYsfFDVN",
+            "timestamp": "2018-12-13T15:10:48Z",
+            "validation_pass": 4,
+            "operations_hash": "LLoZi3xywrX9swZQgC82m7vj5hmuz6LGAatNq2Muh34oNn71JruZs",
+            "fitness": ["00", "00000000000c15ef"],
+            "context": "CoUoqw1cVKUUNWyAviph5cdsjDpgeNhH2DGkMtgy7N6kfwnbewvS",
+            "protocol_data": "000000000003c762c7df00a856b8bfcaf0676f069f825ca75f37f2bee9fe55ba109cec3d1d041d8c03519626c0c0faa557e778cb09d2e0c729e8556ed6a7a518c84982d1f2682bc6aa753f"
+        });
+
+        let binary = JsonReader::new()
+            .read_binary(&json, &block_header_encoding())
+            .unwrap();
+        assert_eq!(
+            "00006d6e0102dd00defaf70c53e180ea148b349a6feb4795610b2abc7b07fe91ce50a90814000000005c1276780432bc1d3a28df9a67b363aa1638f807214bb8987e5f9c0abcbd69531facffd1c80000001100000001000000000800000000000c15ef15a6f54021cb353780e2847fb9c546f1d72c1dc17c3db510f45553ce501ce1de000000000003c762c7df00a856b8bfcaf0676f069f825ca75f37f2bee9fe55ba109cec3d1d041d8c03519626c0c0faa557e778cb09d2e0c729e8556ed6a7a518c84982d1f2682bc6aa753f",
+            hex::encode(binary)
+        );
+    }
+
+    #[test]
+    fn can_read_big_numbers() {
+        let reader = JsonReader::new();
+        let encoding = Encoding::Obj(vec![
+            Field::new("change", Encoding::Z),
+            Field::new("counter", Encoding::Int64),
+        ]);
+        let json = json!({ "change": "-42", "counter": "9007199254740993" });
+
+        let binary = reader.read_binary(&json, &encoding).unwrap();
+        assert_eq!("6a0020000000000001", hex::encode(binary));
+
+        let encoding = Encoding::Obj(vec![Field::new("balance", Encoding::Mutez)]);
+        let value = reader
+            .read(&json!({ "balance": "3000" }), &encoding)
+            .unwrap();
+        assert_eq!(
+            Value::Record(vec![(
+                "balance".to_string(),
+                Value::String("bb8".to_string())
+            )]),
+            value
+        );
+
+        let error = reader
+            .read(&json!({ "balance": "-1" }), &encoding)
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            JsonReaderErrorKind::InvalidValue { .. }
+        ));
+        assert_eq!("field `balance`", error.location());
+    }
+
+    #[test]
+    fn can_read_uint32_boundaries() {
+        let reader = JsonReader::new();
+        let encoding = Encoding::Uint32;
+
+        let read = |value: i64| reader.read_binary(&json!(value), &encoding);
+        assert_eq!("00000000", hex::encode(read(0).unwrap()));
+        assert_eq!("7fffffff", hex::encode(read(i32::MAX as i64).unwrap()));
+        assert_eq!("80000000", hex::encode(read(i32::MAX as i64 + 1).unwrap()));
+        assert_eq!("ffffffff", hex::encode(read(u32::MAX as i64).unwrap()));
+
+        for value in &[-1, u32::MAX as i64 + 1] {
+            let error = reader.read(&json!(value), &encoding).unwrap_err();
+            assert!(matches!(
+                error.kind(),
+                JsonReaderErrorKind::InvalidValue { .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn can_read_tags_and_options() {
+        let encoding = Encoding::list(Encoding::Tags(
+            1,
+            TagMap::new(vec![
+                Tag::new(
+                    0,
+                    "Endorsement",
+                    Encoding::Obj(vec![
+                        Field::new("kind", Encoding::String),
+                        Field::new("level", Encoding::Int32),
+                    ]),
+                ),
+                Tag::new(
+                    1,
+                    "Seed_nonce_revelation",
+                    Encoding::Obj(vec![
+                        Field::new("kind", Encoding::String),
+                        Field::new("level", Encoding::Int32),
+                        Field::new("nonce", Encoding::option_field(Encoding::Bytes)),
+                    ]),
+                ),
+            ]),
+        ));
+        let json = json!([
+            { "kind": "seed_nonce_revelation", "level": 1 },
+            { "kind": "endorsement", "level": 2 },
+        ]);
+
+        let value = JsonReader::new().read(&json, &encoding).unwrap();
+        match value {
+            Value::List(values) => {
+                assert!(
+                    matches!(&values[0], Value::Tag(variant, _) if variant == "Seed_nonce_revelation")
+                );
+                assert!(matches!(&values[1], Value::Tag(variant, _) if variant == "Endorsement"));
+            }
+            _ => panic!("Was expecting list, but found: {:?}", value),
+        }
+    }
+
+    #[test]
+    fn reports_error_location() {
+        let reader = JsonReader::new();
+        let encoding = Encoding::Obj(vec![Field::new(
+            "operations",
+            Encoding::list(Encoding::Obj(vec![
+                Field::new("branch", Encoding::Hash(HashType::BlockHash)),
+                Field::new("data", Encoding::Bytes),
+            ])),
+        )]);
+
+        let json = json!({ "operations": [
+            { "branch": "BKjYUUtYXtXjEuL49jB8ZbFwVdg4hU6U7oKKSC5vp6stYsfFDVN", "data": "00" },
+            { "branch": "BKjYUUtYXtXjEuL49jB8ZbFwVdg4hU6U7oKKSC5vp6stYsfFDVN", "data": "xyz" },
+        ]});
+        let error = reader.read(&json, &encoding).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            JsonReaderErrorKind::InvalidValue { .. }
+        ));
+        assert_eq!(
+            "field `data` @ list element #1 @ field `operations`",
+            error.location()
+        );
+
+        let json = json!({ "operations": [{ "branch": "BKjYUUtYXtXjEuL49jB8ZbFwVdg4hU6U7oKKSC5vp6stYsfFDVN" }]});
+        let error = reader.read(&json, &encoding).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            JsonReaderErrorKind::MissingField { name } if name == "data"
+        ));
+
+        let json = json!({ "operations": [], "unknown": 1 });
+        let error = reader.read(&json, &encoding).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            JsonReaderErrorKind::UnexpectedField { name } if name == "unknown"
+        ));
+    }
+}
@@ -0,0 +1,404 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Generates JSON schema (draft-04, as used by Tezos RPC `/describe`) from [Encoding].
+//!
+//! Hashes and recursive ([Encoding::Lazy]) encodings are placed into `definitions`
+//! and referenced by `$ref`, so recursive types produce finite schemas.
+
+use std::collections::HashSet;
+
+use serde_json::{json, Map, Value};
+
+use crypto::hash::HashType;
+
+use crate::encoding::{Encoding, Field, HasEncoding, RecursiveEncodingFn, SchemaType, TagMap};
+
+/// JSON schema dialect used by Tezos
+pub const JSON_SCHEMA_DRAFT: &str = "http://json-schema.org/draft-04/schema#";
+
+/// Alphabet used by Base58Check encoded hashes
+const BASE58_ALPHABET: &str = "[1-9A-HJ-NP-Za-km-z]";
+
+/// Creates toplevel JSON schema document describing JSON representation of the `encoding`.
+pub fn json_schema(encoding: &Encoding) -> Value {
+    let mut generator = JsonSchemaGenerator::default();
+    let mut schema = match generator.schema(encoding) {
+        Value::Object(schema) if !schema.contains_key("$ref") => schema,
+        other => {
+            let mut schema = Map::new();
+            schema.insert("allOf".to_string(), json!([other]));
+            schema
+        }
+    };
+    schema.insert("$schema".to_string(), json!(JSON_SCHEMA_DRAFT));
+    if !generator.definitions.is_empty() {
+        schema.insert(
+            "definitions".to_string(),
+            Value::Object(generator.definitions),
+        );
+    }
+    Value::Object(schema)
+}
+
+/// Creates toplevel JSON schema document describing JSON representation of the type `T`.
+pub fn json_schema_of<T: HasEncoding>() -> Value {
+    json_schema(T::encoding())
+}
+
+/// Converts name of the recursive encoding function to the name of a schema definition,
+/// e.g.: `tezos_messages::p2p::encoding::tree_encoding` -> `tezos_messages.p2p.encoding.tree_encoding`.
+pub(crate) fn definition_name(
+    f: &(dyn RecursiveEncodingFn<Output = Encoding> + Send + Sync),
+) -> String {
+    f.name()
+        .replace("::", ".")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '_')
+        .collect()
+}
+
+#[derive(Default)]
+struct JsonSchemaGenerator {
+    definitions: Map<String, Value>,
+    /// Recursive encodings, which are being expanded right now
+    expanding: HashSet<String>,
+}
+
+impl JsonSchemaGenerator {
+    fn schema(&mut self, encoding: &Encoding) -> Value {
+        match encoding {
+            Encoding::Unit => {
+                json!({ "type": "object", "properties": {}, "additionalProperties": false })
+            }
+            Encoding::Int8 => integer_schema(i8::MIN as i64, i8::MAX as i64),
+            Encoding::Uint8 => integer_schema(u8::MIN as i64, u8::MAX as i64),
+            Encoding::Int16 => integer_schema(i16::MIN as i64, i16::MAX as i64),
+            Encoding::Uint16 => integer_schema(u16::MIN as i64, u16::MAX as i64),
+            Encoding::Int31 => integer_schema(-(1 << 30), (1 << 30) - 1),
+            Encoding::Int32 => integer_schema(i32::MIN as i64, i32::MAX as i64),
+            Encoding::Uint32 => integer_schema(u32::MIN as i64, u32::MAX as i64),
+            Encoding::RangedInt => json!({ "type": "integer" }),
+            Encoding::Int64 => json!({
+                "title": "64 bit integers",
+                "type": "string",
+                "pattern": "^-?[0-9]+$",
+            }),
+            Encoding::Z => json!({
+                "title": "Big number",
+                "description": "Decimal representation of a big number",
+                "type": "string",
+                "pattern": "^-?[0-9]+$",
+            }),
+            Encoding::Mutez => json!({
+                "title": "Positive big number",
+                "description": "Decimal representation of a positive big number",
+                "type": "string",
+                "pattern": "^[0-9]+$",
+            }),
+            Encoding::Float | Encoding::RangedFloat => json!({ "type": "number" }),
+            Encoding::Bool => json!({ "type": "boolean" }),
+            Encoding::String | Encoding::Enum => json!({ "type": "string" }),
+            Encoding::BoundedString(max) => json!({ "type": "string", "maxLength": max }),
+            Encoding::Bytes => bytes_schema(),
+            Encoding::Timestamp => json!({
+                "description": "A date notation as defined in RFC 3339",
+                "type": "string",
+                "format": "date-time",
+            }),
+            Encoding::Hash(hash_type) => self.hash_schema(*hash_type),
+            Encoding::List(inner) => json!({ "type": "array", "items": self.schema(inner) }),
+            Encoding::BoundedList(max, inner) => json!({
+                "type": "array",
+                "items": self.schema(inner),
+                "maxItems": max,
+            }),
+            Encoding::Option(inner) => json!({
+                "oneOf": [
+                    self.schema(inner),
+                    { "title": "None", "type": "null" },
+                ]
+            }),
+            Encoding::OptionalField(inner) | Encoding::Dynamic(inner) | Encoding::Greedy(inner) => {
+                self.schema(inner)
+            }
+            Encoding::Sized(size, inner) => {
+                let schema = self.schema(inner);
+                with_length_limit(schema, inner, Some(*size), *size)
+            }
+            Encoding::BoundedDynamic(max, inner) | Encoding::Bounded(max, inner) => {
+                let schema = self.schema(inner);
+                with_length_limit(schema, inner, None, *max)
+            }
+            Encoding::Obj(fields) => self.object_schema(fields),
+            Encoding::Tup(encodings) => json!({
+                "type": "array",
+                "items": encodings.iter().map(|e| self.schema(e)).collect::<Vec<_>>(),
+                "additionalItems": false,
+            }),
+            Encoding::Tags(_, tags) => self.tags_schema(tags),
+            Encoding::Split(fn_encoding) => self.schema(&fn_encoding(SchemaType::Json)),
+            Encoding::Lazy(fn_encoding) => {
+                let name = definition_name(fn_encoding.as_ref());
+                if !self.definitions.contains_key(&name) && !self.expanding.contains(&name) {
+                    self.expanding.insert(name.clone());
+                    let schema = self.schema(&fn_encoding());
+                    self.expanding.remove(&name);
+                    self.definitions.insert(name.clone(), schema);
+                }
+                definition_ref(&name)
+            }
+            Encoding::Custom(_) => {
+                // custom codec does not provide any schema, so any value is accepted
+                json!({})
+            }
+        }
+    }
+
+    fn hash_schema(&mut self, hash_type: HashType) -> Value {
+        let name = hash_definition_name(hash_type);
+        if !self.definitions.contains_key(&name) {
+            let (min_length, max_length) = base58_length(hash_type);
+            self.definitions.insert(
+                name.clone(),
+                json!({
+                    "title": format!("{:?} (Base58Check-encoded)", hash_type),
+                    "type": "string",
+                    "pattern": format!("^{}{{{},{}}}$", BASE58_ALPHABET, min_length, max_length),
+                }),
+            );
+        }
+        definition_ref(&name)
+    }
+
+    fn object_schema(&mut self, fields: &[Field]) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for field in fields {
+            properties.insert(field.get_name().clone(), self.schema(field.get_encoding()));
+            if !matches!(field.get_encoding(), Encoding::OptionalField(_)) {
+                required.push(Value::String(field.get_name().clone()));
+            }
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    }
+
+    fn tags_schema(&mut self, tags: &TagMap) -> Value {
+        let mut tags = tags.tags().collect::<Vec<_>>();
+        tags.sort_by_key(|tag| tag.get_id());
+        let cases = tags
+            .into_iter()
+            .map(|tag| {
+                let mut schema = match self.schema(tag.get_encoding()) {
+                    Value::Object(schema) if !schema.contains_key("$ref") => schema,
+                    other => {
+                        let mut schema = Map::new();
+                        schema.insert("allOf".to_string(), json!([other]));
+                        schema
+                    }
+                };
+                schema.insert("title".to_string(), json!(tag.get_variant()));
+                Value::Object(schema)
+            })
+            .collect::<Vec<_>>();
+        json!({ "oneOf": cases })
+    }
+}
+
+fn integer_schema(minimum: i64, maximum: i64) -> Value {
+    json!({ "type": "integer", "minimum": minimum, "maximum": maximum })
+}
+
+fn bytes_schema() -> Value {
+    json!({
+        "type": "string",
+        "pattern": "^([a-zA-Z0-9][a-zA-Z0-9])*$",
+    })
+}
+
+fn definition_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/definitions/{}", name) })
+}
+
+/// Binary size limits can be expressed in JSON only for strings and bytes (hex encoded).
+fn with_length_limit(
+    mut schema: Value,
+    inner: &Encoding,
+    min_size: Option<usize>,
+    max_size: usize,
+) -> Value {
+    let multiplier = match inner {
+        Encoding::String | Encoding::BoundedString(_) => 1,
+        Encoding::Bytes => 2,
+        _ => return schema,
+    };
+    if let Value::Object(schema) = &mut schema {
+        if let Some(min_size) = min_size {
+            schema.insert("minLength".to_string(), json!(min_size * multiplier));
+        }
+        let max_length = match schema.get("maxLength").and_then(Value::as_u64) {
+            Some(max_length) => std::cmp::min(max_length as usize, max_size * multiplier),
+            None => max_size * multiplier,
+        };
+        schema.insert("maxLength".to_string(), json!(max_length));
+    }
+    schema
+}
+
+/// Converts hash type to snake case name, e.g.: `BlockHash` -> `block_hash`
+fn hash_definition_name(hash_type: HashType) -> String {
+    let mut name = String::new();
+    for c in format!("{:?}", hash_type).chars() {
+        if c.is_ascii_uppercase() {
+            if !name.is_empty() {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// Returns minimal and maximal length of the Base58Check representation of the hash type
+fn base58_length(hash_type: HashType) -> (usize, usize) {
+    let length = |byte: u8| {
+        hash_type
+            .hash_to_b58check(&vec![byte; hash_type.size()])
+            .map(|encoded| encoded.len())
+            .unwrap_or(0)
+    };
+    let (min, max) = (length(0x00), length(0xff));
+    (std::cmp::min(min, max), std::cmp::max(min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::Tag;
+
+    use super::*;
+
+    fn tree_encoding() -> Encoding {
+        Encoding::Tags(
+            1,
+            TagMap::new(vec![
+                Tag::new(0, "Leaf", Encoding::Hash(HashType::ContextHash)),
+                Tag::new(
+                    1,
+                    "Node",
+                    Encoding::list(Encoding::Lazy(std::sync::Arc::new(tree_encoding))),
+                ),
+            ]),
+        )
+    }
+
+    #[test]
+    fn can_generate_object_schema() {
+        let encoding = Encoding::Obj(vec![
+            Field::new("block", Encoding::Hash(HashType::BlockHash)),
+            Field::new("level", Encoding::Int32),
+            Field::new("timestamp", Encoding::Timestamp),
+            Field::new("fitness", Encoding::list(Encoding::Bytes)),
+            Field::new("message", Encoding::option_field(Encoding::String)),
+        ]);
+
+        let schema = json_schema(&encoding);
+        assert_eq!(JSON_SCHEMA_DRAFT, schema["$schema"]);
+        assert_eq!("object", schema["type"]);
+        assert_eq!(
+            json!(["block", "level", "timestamp", "fitness"]),
+            schema["required"]
+        );
+        assert_eq!(
+            "#/definitions/block_hash",
+            schema["properties"]["block"]["$ref"]
+        );
+        assert_eq!("integer", schema["properties"]["level"]["type"]);
+        assert_eq!("date-time", schema["properties"]["timestamp"]["format"]);
+        assert_eq!("array", schema["properties"]["fitness"]["type"]);
+        assert_eq!("string", schema["properties"]["fitness"]["items"]["type"]);
+        assert_eq!("string", schema["properties"]["message"]["type"]);
+    }
+
+    #[test]
+    fn can_generate_primitive_schema() {
+        let schema = json_schema(&Encoding::list(Encoding::Hash(HashType::OperationHash)));
+        assert_eq!(JSON_SCHEMA_DRAFT, schema["$schema"]);
+        assert_eq!("array", schema["type"]);
+
+        let schema = json_schema(&Encoding::option(Encoding::Int64));
+        assert_eq!(JSON_SCHEMA_DRAFT, schema["$schema"]);
+        assert_eq!("string", schema["oneOf"][0]["type"]);
+        assert_eq!("null", schema["oneOf"][1]["type"]);
+
+        let schema = json_schema(&Encoding::Hash(HashType::ChainId));
+        assert_eq!("#/definitions/chain_id", schema["allOf"][0]["$ref"]);
+    }
+
+    #[test]
+    fn can_generate_hash_schema() {
+        let schema = json_schema(&Encoding::Hash(HashType::BlockHash));
+        let definition = &schema["definitions"]["block_hash"];
+        assert_eq!("string", definition["type"]);
+        assert_eq!("^[1-9A-HJ-NP-Za-km-z]{51,51}$", definition["pattern"]);
+
+        let schema = json_schema(&Encoding::Hash(HashType::ContractTz1Hash));
+        assert_eq!(
+            "^[1-9A-HJ-NP-Za-km-z]{36,36}$",
+            schema["definitions"]["contract_tz1_hash"]["pattern"]
+        );
+    }
+
+    #[test]
+    fn can_generate_limits_schema() {
+        let encoding = Encoding::Obj(vec![
+            Field::new("name", Encoding::BoundedString(10)),
+            Field::new("key", Encoding::sized(32, Encoding::Bytes)),
+            Field::new("data", Encoding::bounded_dynamic(100, Encoding::Bytes)),
+            Field::new("items", Encoding::bounded_list(5, Encoding::Uint16)),
+            Field::new(
+                "pair",
+                Encoding::Tup(vec![Encoding::Bool, Encoding::String]),
+            ),
+        ]);
+
+        let schema = json_schema(&encoding);
+        let properties = &schema["properties"];
+        assert_eq!(10, properties["name"]["maxLength"]);
+        assert_eq!(64, properties["key"]["minLength"]);
+        assert_eq!(64, properties["key"]["maxLength"]);
+        assert_eq!(200, properties["data"]["maxLength"]);
+        assert!(properties["data"]["minLength"].is_null());
+        assert_eq!(5, properties["items"]["maxItems"]);
+        assert_eq!(65535, properties["items"]["items"]["maximum"]);
+        assert_eq!("boolean", properties["pair"]["items"][0]["type"]);
+        assert_eq!("string", properties["pair"]["items"][1]["type"]);
+    }
+
+    #[test]
+    fn can_generate_recursive_schema() {
+        let schema = json_schema(&tree_encoding());
+
+        assert_eq!("Leaf", schema["oneOf"][0]["title"]);
+        assert_eq!(
+            "#/definitions/context_hash",
+            schema["oneOf"][0]["allOf"][0]["$ref"]
+        );
+        assert_eq!("Node", schema["oneOf"][1]["title"]);
+        assert_eq!("array", schema["oneOf"][1]["type"]);
+
+        let reference = schema["oneOf"][1]["items"]["$ref"].as_str().unwrap();
+        let name = reference.trim_start_matches("#/definitions/");
+        assert!(name.ends_with("tree_encoding"));
+
+        // recursive definition refers to itself
+        let definition = &schema["definitions"][name];
+        assert_eq!(reference, definition["oneOf"][1]["items"]["$ref"]);
+    }
+}
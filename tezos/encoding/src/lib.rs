@@ -8,9 +8,12 @@ mod bit_utils;
 pub mod types;
 
 pub mod binary_reader;
+pub mod binary_schema;
 pub mod binary_writer;
 pub mod de;
 pub mod encoding;
 pub mod error_context;
+pub mod json_reader;
+pub mod json_schema;
 pub mod json_writer;
 pub mod ser;
@@ -0,0 +1,290 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Parsing of `#[encoding(...)]` attributes and of the `#[serde(...)]` attributes
+//! that affect how a value is represented during serialization.
+
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::{Attribute, Expr, Ident, Lit, Meta, NestedMeta, Path};
+
+/// Encodings which can be used with `builtin = "..."`.
+const BUILTIN_ENCODINGS: &[&str] = &[
+    "Unit", "Int8", "Uint8", "Int16", "Uint16", "Int31", "Int32", "Uint32", "Int64", "RangedInt",
+    "Z", "Mutez", "Float", "RangedFloat", "Bool", "String", "Bytes", "Enum", "Timestamp",
+];
+
+pub enum ModifierKind {
+    /// `dynamic` - `Encoding::Dynamic`
+    Dynamic,
+    /// `bounded_dynamic = "MAX"` - `Encoding::BoundedDynamic`
+    BoundedDynamic(Expr),
+    /// `bounded = "MAX"` - `Encoding::Bounded`
+    Bounded(Expr),
+    /// `sized = "SIZE"` - `Encoding::Sized`
+    Sized(Expr),
+    /// `list` - `Encoding::List`, applies to `Vec<T>` and continues with `T`
+    List,
+    /// `bounded_list = "MAX"` - `Encoding::BoundedList`, applies to `Vec<T>` and continues with `T`
+    BoundedList(Expr),
+    /// `option` - `Encoding::Option` instead of `Encoding::OptionalField` for `Option<T>`
+    Option,
+    /// `builtin = "Timestamp"` - any encoding without parameters, e.g. `Encoding::Timestamp`
+    Builtin(Ident),
+    /// `hash = "BlockHash"` - `Encoding::Hash`
+    Hash(Ident),
+    /// `bytes` - `Encoding::Bytes`
+    Bytes,
+    /// `custom = "path::to::fn"` - encoding returned by the function
+    Custom(Path),
+}
+
+/// Single encoding modifier. Modifiers are applied outer-first, in the order they are written.
+pub struct Modifier {
+    pub kind: ModifierKind,
+    pub span: Span,
+}
+
+impl Modifier {
+    /// Terminal modifiers provide the whole encoding and cannot be followed by other modifiers.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self.kind,
+            ModifierKind::Builtin(_)
+                | ModifierKind::Hash(_)
+                | ModifierKind::Bytes
+                | ModifierKind::Custom(_)
+        )
+    }
+
+    /// Wrapping modifiers do not depend on the type and can be used on the container too.
+    pub fn is_wrapper(&self) -> bool {
+        matches!(
+            self.kind,
+            ModifierKind::Dynamic
+                | ModifierKind::BoundedDynamic(_)
+                | ModifierKind::Bounded(_)
+                | ModifierKind::Sized(_)
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct ContainerAttrs {
+    /// Size of the tag for enums, `tags = "u8"` or `tags = "u16"`
+    pub tags: Option<Ident>,
+    pub modifiers: Vec<Modifier>,
+}
+
+#[derive(Default)]
+pub struct VariantAttrs {
+    pub name: Option<String>,
+    pub skip: bool,
+    pub tag: Option<(u16, Span)>,
+}
+
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub name: Option<String>,
+    pub skip: bool,
+    pub modifiers: Vec<Modifier>,
+}
+
+pub fn container_attrs(attrs: &[Attribute]) -> syn::Result<ContainerAttrs> {
+    let mut result = ContainerAttrs::default();
+    for meta in encoding_metas(attrs)? {
+        if meta.path().is_ident("tags") {
+            let size = ident_value(&meta)?;
+            if size != "u8" && size != "u16" {
+                return Err(syn::Error::new(
+                    size.span(),
+                    "expected tag size \"u8\" or \"u16\"",
+                ));
+            }
+            result.tags = Some(size);
+            continue;
+        }
+        let modifier = modifier(&meta)?;
+        if !modifier.is_wrapper() {
+            return Err(syn::Error::new(
+                modifier.span,
+                "only `dynamic`, `bounded_dynamic`, `bounded` and `sized` can be used on a type",
+            ));
+        }
+        result.modifiers.push(modifier);
+    }
+    Ok(result)
+}
+
+pub fn variant_attrs(attrs: &[Attribute]) -> syn::Result<VariantAttrs> {
+    let (name, skip) = serde_attrs(attrs);
+    let mut result = VariantAttrs {
+        name,
+        skip,
+        tag: None,
+    };
+    for meta in encoding_metas(attrs)? {
+        if meta.path().is_ident("tag") {
+            result.tag = Some((tag_value(&meta)?, meta.span()));
+        } else if meta.path().is_ident("skip") {
+            flag(&meta)?;
+            result.skip = true;
+        } else {
+            return Err(syn::Error::new(
+                meta.span(),
+                "expected `tag` or `skip` on enum variant",
+            ));
+        }
+    }
+    Ok(result)
+}
+
+pub fn field_attrs(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let (name, skip) = serde_attrs(attrs);
+    let mut result = FieldAttrs {
+        name,
+        skip,
+        modifiers: Vec::new(),
+    };
+    for meta in encoding_metas(attrs)? {
+        if meta.path().is_ident("skip") {
+            flag(&meta)?;
+            result.skip = true;
+        } else {
+            result.modifiers.push(modifier(&meta)?);
+        }
+    }
+    Ok(result)
+}
+
+/// Collects all items from all `#[encoding(...)]` attributes.
+fn encoding_metas(attrs: &[Attribute]) -> syn::Result<Vec<Meta>> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("encoding")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(meta) => metas.push(meta),
+                        NestedMeta::Lit(lit) => {
+                            return Err(syn::Error::new(lit.span(), "unexpected literal"))
+                        }
+                    }
+                }
+            }
+            meta => return Err(syn::Error::new(meta.span(), "expected #[encoding(...)]")),
+        }
+    }
+    Ok(metas)
+}
+
+/// Serde attributes change names and presence of values produced by the serializer,
+/// so the encoding has to follow them.
+fn serde_attrs(attrs: &[Attribute]) -> (Option<String>, bool) {
+    let mut name = None;
+    let mut skip = false;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("serde")) {
+        if let Ok(Meta::List(list)) = attr.parse_meta() {
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(path))
+                        if path.is_ident("skip") || path.is_ident("skip_serializing") =>
+                    {
+                        skip = true
+                    }
+                    NestedMeta::Meta(Meta::NameValue(name_value))
+                        if name_value.path.is_ident("rename") =>
+                    {
+                        if let Lit::Str(value) = name_value.lit {
+                            name = Some(value.value());
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    (name, skip)
+}
+
+fn modifier(meta: &Meta) -> syn::Result<Modifier> {
+    let name = match meta.path().get_ident() {
+        Some(name) => name.to_string(),
+        None => return Err(syn::Error::new(meta.span(), "unknown encoding attribute")),
+    };
+    let kind = match name.as_str() {
+        "dynamic" => flag(meta).map(|_| ModifierKind::Dynamic)?,
+        "bounded_dynamic" => ModifierKind::BoundedDynamic(expr_value(meta)?),
+        "bounded" => ModifierKind::Bounded(expr_value(meta)?),
+        "sized" => ModifierKind::Sized(expr_value(meta)?),
+        "list" => flag(meta).map(|_| ModifierKind::List)?,
+        "bounded_list" => ModifierKind::BoundedList(expr_value(meta)?),
+        "option" => flag(meta).map(|_| ModifierKind::Option)?,
+        "builtin" => {
+            let builtin = ident_value(meta)?;
+            if !BUILTIN_ENCODINGS.iter().any(|name| builtin == name) {
+                return Err(syn::Error::new(
+                    builtin.span(),
+                    format!(
+                        "unknown builtin encoding, expected one of: {}",
+                        BUILTIN_ENCODINGS.join(", ")
+                    ),
+                ));
+            }
+            ModifierKind::Builtin(builtin)
+        }
+        "hash" => ModifierKind::Hash(ident_value(meta)?),
+        "bytes" => flag(meta).map(|_| ModifierKind::Bytes)?,
+        "custom" => ModifierKind::Custom(string_value(meta)?.parse()?),
+        _ => return Err(syn::Error::new(meta.span(), "unknown encoding attribute")),
+    };
+    Ok(Modifier {
+        kind,
+        span: meta.span(),
+    })
+}
+
+fn flag(meta: &Meta) -> syn::Result<()> {
+    match meta {
+        Meta::Path(_) => Ok(()),
+        _ => Err(syn::Error::new(meta.span(), "unexpected attribute value")),
+    }
+}
+
+fn lit_value(meta: &Meta) -> syn::Result<&Lit> {
+    match meta {
+        Meta::NameValue(name_value) => Ok(&name_value.lit),
+        _ => Err(syn::Error::new(meta.span(), "expected `name = value`")),
+    }
+}
+
+fn string_value(meta: &Meta) -> syn::Result<syn::LitStr> {
+    match lit_value(meta)? {
+        Lit::Str(value) => Ok(value.clone()),
+        lit => Err(syn::Error::new(lit.span(), "expected string literal")),
+    }
+}
+
+fn ident_value(meta: &Meta) -> syn::Result<Ident> {
+    string_value(meta)?.parse()
+}
+
+/// Sizes and limits can be given either as an integer or as a string containing an expression,
+/// e.g. `bounded = "BLOCK_HEADER_MAX_SIZE"`.
+fn expr_value(meta: &Meta) -> syn::Result<Expr> {
+    match lit_value(meta)? {
+        Lit::Str(value) => value.parse(),
+        lit @ Lit::Int(_) => Ok(syn::parse_quote!(#lit)),
+        lit => Err(syn::Error::new(
+            lit.span(),
+            "expected integer or string literal",
+        )),
+    }
+}
+
+fn tag_value(meta: &Meta) -> syn::Result<u16> {
+    match lit_value(meta)? {
+        Lit::Int(value) => value.base10_parse(),
+        lit => Err(syn::Error::new(lit.span(), "expected integer literal")),
+    }
+}
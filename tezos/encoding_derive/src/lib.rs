@@ -0,0 +1,359 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+#![forbid(unsafe_code)]
+
+//! Provides `#[derive(HasEncoding)]`, which generates `HasEncoding` implementation from the
+//! type definition, so the encoding cannot get out of sync with the struct fields.
+//!
+//! Use it through `tezos_encoding::encoding::HasEncoding`, which re-exports the derive
+//! together with the trait. Like `has_encoding!`, the generated code uses `lazy_static`,
+//! so the crate deriving the trait has to depend on it.
+//!
+//! Structs are encoded as `Encoding::Obj` with fields in the order of declaration,
+//! fields skipped or renamed with `#[serde(skip)]`, `#[serde(skip_serializing)]` or
+//! `#[serde(rename = "...")]` are skipped or renamed in the encoding too.
+//! Encoding of the field is derived from its type:
+//! - `Option<T>` is encoded as `Encoding::OptionalField`,
+//! - `Vec<T>` is encoded as `Encoding::List`,
+//! - any other type `T` uses `<T as HasEncoding>::encoding()`.
+//!
+//! It can be changed with `#[encoding(...)]` attribute, which contains list of modifiers.
+//! Modifiers are applied outer-first, so `#[encoding(dynamic, bounded_list = "MAX")]` on
+//! `Vec<BlockHash>` results in `Encoding::dynamic(Encoding::bounded_list(MAX, ...))`.
+//! `list` and `bounded_list` are applied to the `Vec<T>` and the rest of modifiers is applied
+//! to `T`. `Option<T>` is transparent to modifiers, unless `option` is used to select
+//! `Encoding::Option` instead of `Encoding::OptionalField`.
+//!
+//! | modifier | encoding |
+//! |---|---|
+//! | `dynamic` | `Encoding::Dynamic` |
+//! | `bounded_dynamic = "MAX"` | `Encoding::BoundedDynamic` |
+//! | `bounded = "MAX"` | `Encoding::Bounded` |
+//! | `sized = "SIZE"` | `Encoding::Sized` |
+//! | `list` | `Encoding::List` |
+//! | `bounded_list = "MAX"` | `Encoding::BoundedList` |
+//! | `option` | `Encoding::Option` |
+//! | `builtin = "Timestamp"` | any encoding without parameters, e.g. `Encoding::Timestamp` |
+//! | `hash = "BlockHash"` | `Encoding::Hash(HashType::BlockHash)` |
+//! | `bytes` | `Encoding::Bytes` |
+//! | `custom = "path::to::fn"` | encoding returned by the function |
+//!
+//! `builtin`, `hash`, `bytes` and `custom` provide the whole encoding, so they have to be the
+//! last modifier. `dynamic`, `bounded_dynamic`, `bounded` and `sized` can also be used on the type
+//! itself to wrap the whole encoding. Fields can be excluded with `#[encoding(skip)]`.
+//!
+//! Enums are encoded as `Encoding::Tags`, each variant is encoded like a struct with the same
+//! fields. Tag size is set with `#[encoding(tags = "u16")]` on the enum (`u8` by default) and
+//! tag ids with `#[encoding(tag = 0x10)]` on variants. Variants without explicit id follow
+//! the previous one, the same way as enum discriminants do.
+//!
+//! ```ignore
+//! use tezos_encoding::encoding::HasEncoding;
+//!
+//! #[derive(Serialize, Deserialize, HasEncoding)]
+//! #[encoding(bounded = "BLOCK_HEADER_MAX_SIZE")]
+//! pub struct BlockHeader {
+//!     #[encoding(builtin = "Int32")]
+//!     level: Level,
+//!     predecessor: BlockHash,
+//!     #[encoding(builtin = "Timestamp")]
+//!     timestamp: i64,
+//!     #[encoding(custom = "fitness_encoding")]
+//!     fitness: Fitness,
+//!     #[serde(skip_serializing)]
+//!     body: BinaryDataCache,
+//! }
+//!
+//! #[derive(Serialize, Deserialize, HasEncoding)]
+//! pub enum AckMessage {
+//!     #[encoding(tag = 0x00)]
+//!     Ack,
+//!     Nack(NackInfo),
+//!     #[encoding(tag = 0xFF)]
+//!     NackV0,
+//! }
+//! ```
+
+extern crate proc_macro;
+
+mod attr;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Data, DataEnum, DeriveInput, Fields, GenericArgument, PathArguments, Type,
+};
+
+use crate::attr::{ContainerAttrs, Modifier, ModifierKind};
+
+#[proc_macro_derive(HasEncoding, attributes(encoding))]
+pub fn derive_has_encoding(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "HasEncoding cannot be derived for generic types",
+        ));
+    }
+    let container = attr::container_attrs(&input.attrs)?;
+    let mut encoding = match &input.data {
+        Data::Struct(data) => {
+            if let Some(tags) = &container.tags {
+                return Err(syn::Error::new(
+                    tags.span(),
+                    "`tags` can only be used on enums",
+                ));
+            }
+            fields_encoding(&data.fields)?
+        }
+        Data::Enum(data) => enum_encoding(data, &container)?,
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span(),
+                "HasEncoding cannot be derived for unions",
+            ))
+        }
+    };
+    for modifier in container.modifiers.iter().rev() {
+        encoding = wrap(modifier, encoding);
+    }
+
+    let name = &input.ident;
+    Ok(quote! {
+        impl tezos_encoding::encoding::HasEncoding for #name {
+            fn encoding() -> &'static tezos_encoding::encoding::Encoding {
+                lazy_static::lazy_static! {
+                    static ref ENCODING: tezos_encoding::encoding::Encoding = #encoding;
+                }
+                &ENCODING
+            }
+        }
+    })
+}
+
+fn enum_encoding(data: &DataEnum, container: &ContainerAttrs) -> syn::Result<TokenStream2> {
+    let tag_size = match &container.tags {
+        Some(size) => size.to_string(),
+        None => "u8".to_string(),
+    };
+    let max_id = if tag_size == "u8" {
+        u8::MAX as u16
+    } else {
+        u16::MAX
+    };
+
+    let mut ids = Vec::new();
+    let mut tags = Vec::new();
+    let mut next_id = Some(0u16);
+    for variant in &data.variants {
+        let attrs = attr::variant_attrs(&variant.attrs)?;
+        let (id, span) = match attrs.tag {
+            Some(tag) => tag,
+            None => match next_id {
+                Some(id) => (id, variant.span()),
+                None => return Err(syn::Error::new(variant.span(), "tag id overflow")),
+            },
+        };
+        if id > max_id {
+            return Err(syn::Error::new(
+                span,
+                format!("tag id does not fit into {}", tag_size),
+            ));
+        }
+        if ids.contains(&id) {
+            return Err(syn::Error::new(span, "duplicate tag id"));
+        }
+        ids.push(id);
+        next_id = id.checked_add(1);
+
+        if attrs.skip {
+            continue;
+        }
+        let name = attrs
+            .name
+            .unwrap_or_else(|| variant.ident.unraw().to_string());
+        let encoding = fields_encoding(&variant.fields)?;
+        tags.push(quote! {
+            tezos_encoding::encoding::Tag::new(#id, #name, #encoding)
+        });
+    }
+
+    let tag_size = syn::Ident::new(&tag_size, proc_macro2::Span::call_site());
+    Ok(quote! {
+        tezos_encoding::encoding::Encoding::Tags(
+            std::mem::size_of::<#tag_size>(),
+            tezos_encoding::encoding::TagMap::new(vec![#(#tags),*]),
+        )
+    })
+}
+
+fn fields_encoding(fields: &Fields) -> syn::Result<TokenStream2> {
+    match fields {
+        Fields::Named(named) => {
+            let mut schema = Vec::new();
+            for field in &named.named {
+                let attrs = attr::field_attrs(&field.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                let name = match (attrs.name, &field.ident) {
+                    (Some(name), _) => name,
+                    (None, Some(ident)) => ident.unraw().to_string(),
+                    (None, None) => unreachable!("named field without name"),
+                };
+                let encoding = type_encoding(&field.ty, &attrs.modifiers)?;
+                schema.push(quote! {
+                    tezos_encoding::encoding::Field::new(#name, #encoding)
+                });
+            }
+            Ok(quote! {
+                tezos_encoding::encoding::Encoding::Obj(vec![#(#schema),*])
+            })
+        }
+        Fields::Unnamed(unnamed) => {
+            let mut encodings = Vec::new();
+            for field in &unnamed.unnamed {
+                let attrs = attr::field_attrs(&field.attrs)?;
+                if !attrs.skip {
+                    encodings.push(type_encoding(&field.ty, &attrs.modifiers)?);
+                }
+            }
+            // newtypes are serialized as the value they wrap
+            if unnamed.unnamed.len() == 1 && encodings.len() == 1 {
+                Ok(encodings.remove(0))
+            } else {
+                Ok(quote! {
+                    tezos_encoding::encoding::Encoding::Tup(vec![#(#encodings),*])
+                })
+            }
+        }
+        Fields::Unit => Ok(quote!(tezos_encoding::encoding::Encoding::Unit)),
+    }
+}
+
+/// Creates encoding for the type, modifiers are consumed outer-first.
+fn type_encoding(ty: &Type, modifiers: &[Modifier]) -> syn::Result<TokenStream2> {
+    let (modifier, rest) = match modifiers.split_first() {
+        Some(split) => split,
+        None => return Ok(default_encoding(ty)),
+    };
+    if modifier.is_terminal() {
+        if let Some(next) = rest.first() {
+            return Err(syn::Error::new(
+                next.span,
+                "no modifier is allowed after `builtin`, `hash`, `bytes` or `custom`",
+            ));
+        }
+    }
+
+    if let Some(inner) = generic_argument(ty, "Option") {
+        return Ok(match modifier.kind {
+            ModifierKind::Option => {
+                let inner = type_encoding(inner, rest)?;
+                quote!(tezos_encoding::encoding::Encoding::option(#inner))
+            }
+            _ => {
+                let inner = type_encoding(inner, modifiers)?;
+                quote!(tezos_encoding::encoding::Encoding::option_field(#inner))
+            }
+        });
+    }
+
+    Ok(match &modifier.kind {
+        ModifierKind::Dynamic
+        | ModifierKind::BoundedDynamic(_)
+        | ModifierKind::Bounded(_)
+        | ModifierKind::Sized(_) => wrap(modifier, type_encoding(ty, rest)?),
+        ModifierKind::List => {
+            let item = type_encoding(list_item(ty, modifier)?, rest)?;
+            quote!(tezos_encoding::encoding::Encoding::list(#item))
+        }
+        ModifierKind::BoundedList(max) => {
+            let item = type_encoding(list_item(ty, modifier)?, rest)?;
+            quote!(tezos_encoding::encoding::Encoding::bounded_list(#max, #item))
+        }
+        ModifierKind::Option => {
+            return Err(syn::Error::new(
+                modifier.span,
+                "`option` can only be used on `Option<T>`",
+            ))
+        }
+        ModifierKind::Builtin(name) => quote!(tezos_encoding::encoding::Encoding::#name),
+        ModifierKind::Hash(name) => {
+            quote!(tezos_encoding::encoding::Encoding::Hash(crypto::hash::HashType::#name))
+        }
+        ModifierKind::Bytes => quote!(tezos_encoding::encoding::Encoding::Bytes),
+        ModifierKind::Custom(path) => quote!(#path()),
+    })
+}
+
+/// Wraps encoding with one of the modifiers which do not depend on the type.
+fn wrap(modifier: &Modifier, encoding: TokenStream2) -> TokenStream2 {
+    match &modifier.kind {
+        ModifierKind::Dynamic => quote!(tezos_encoding::encoding::Encoding::dynamic(#encoding)),
+        ModifierKind::BoundedDynamic(max) => {
+            quote!(tezos_encoding::encoding::Encoding::bounded_dynamic(#max, #encoding))
+        }
+        ModifierKind::Bounded(max) => {
+            quote!(tezos_encoding::encoding::Encoding::bounded(#max, #encoding))
+        }
+        ModifierKind::Sized(size) => {
+            quote!(tezos_encoding::encoding::Encoding::sized(#size, #encoding))
+        }
+        _ => unreachable!("not a wrapping modifier"),
+    }
+}
+
+/// Encoding of the type when there is no `#[encoding(...)]` attribute.
+fn default_encoding(ty: &Type) -> TokenStream2 {
+    if let Some(inner) = generic_argument(ty, "Option") {
+        let inner = default_encoding(inner);
+        quote!(tezos_encoding::encoding::Encoding::option_field(#inner))
+    } else if let Some(item) = generic_argument(ty, "Vec") {
+        let item = default_encoding(item);
+        quote!(tezos_encoding::encoding::Encoding::list(#item))
+    } else {
+        quote_spanned! {ty.span()=>
+            <#ty as tezos_encoding::encoding::HasEncoding>::encoding().clone()
+        }
+    }
+}
+
+fn list_item<'a>(ty: &'a Type, modifier: &Modifier) -> syn::Result<&'a Type> {
+    generic_argument(ty, "Vec").ok_or_else(|| {
+        syn::Error::new(
+            modifier.span,
+            "`list` and `bounded_list` can only be used on `Vec<T>`",
+        )
+    })
+}
+
+/// Returns `T` if the type is `name<T>`, e.g. `Vec<T>` or `Option<T>`.
+fn generic_argument<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let path = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match arguments.args.first() {
+                Some(GenericArgument::Type(ty)) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Micheline expressions (Michelson data, types and code) and their binary decoding.
+//!
+//! Decoding follows the binary form used by Octez `Micheline_encoding` with Michelson
+//! primitives of the current protocol. Decoded values can be serialized to the Octez JSON
+//! format (`{"prim": "Pair", "args": [...]}`) or displayed in Michelson text format.
+
+use std::fmt;
+
+use failure::Fail;
+use num_bigint::BigInt;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+
+/// Michelson primitives indexed by their binary tag
+#[rustfmt::skip]
+const PRIMITIVES: &[&str] = &[
+    "parameter", "storage", "code", "False", "Elt", "Left", "None", "Pair", "Right", "Some",
+    "True", "Unit", "PACK", "UNPACK", "BLAKE2B", "SHA256", "SHA512", "ABS", "ADD", "AMOUNT",
+    "AND", "BALANCE", "CAR", "CDR", "CHECK_SIGNATURE", "COMPARE", "CONCAT", "CONS",
+    "CREATE_ACCOUNT", "CREATE_CONTRACT", "IMPLICIT_ACCOUNT", "DIP", "DROP", "DUP", "EDIV",
+    "EMPTY_MAP", "EMPTY_SET", "EQ", "EXEC", "FAILWITH", "GE", "GET", "GT", "HASH_KEY", "IF",
+    "IF_CONS", "IF_LEFT", "IF_NONE", "INT", "LAMBDA", "LE", "LEFT", "LOOP", "LSL", "LSR", "LT",
+    "MAP", "MEM", "MUL", "NEG", "NEQ", "NIL", "NONE", "NOT", "NOW", "OR", "PAIR", "PUSH", "RIGHT",
+    "SIZE", "SOME", "SOURCE", "SENDER", "SELF", "STEPS_TO_QUOTA", "SUB", "SWAP",
+    "TRANSFER_TOKENS", "SET_DELEGATE", "UNIT", "UPDATE", "XOR", "ITER", "LOOP_LEFT", "ADDRESS",
+    "CONTRACT", "ISNAT", "CAST", "RENAME", "bool", "contract", "int", "key", "key_hash", "lambda",
+    "list", "map", "big_map", "nat", "option", "or", "pair", "set", "signature", "string",
+    "bytes", "mutez", "timestamp", "unit", "operation", "address", "SLICE", "DIG", "DUG",
+    "EMPTY_BIG_MAP", "APPLY", "chain_id", "CHAIN_ID", "LEVEL", "SELF_ADDRESS", "never", "NEVER",
+    "UNPAIR", "VOTING_POWER", "TOTAL_VOTING_POWER", "KECCAK", "SHA3", "PAIRING_CHECK",
+    "bls12_381_g1", "bls12_381_g2", "bls12_381_fr", "sapling_state", "sapling_transaction",
+    "SAPLING_EMPTY_STATE", "SAPLING_VERIFY_UPDATE", "ticket", "TICKET", "READ_TICKET",
+    "SPLIT_TICKET", "JOIN_TICKETS", "GET_AND_UPDATE",
+];
+
+/// Maximal nesting depth of decoded expressions.
+///
+/// Octez `Micheline_encoding` itself does not bound the nesting, stored scripts are bounded by
+/// the protocol only indirectly (by their size). Decoding, printing and dropping of expressions
+/// here is recursive, so deeper input is rejected to keep them within the default 2 MiB thread stack.
+pub const MAX_NESTING_DEPTH: usize = 1_000;
+
+#[derive(Debug, Fail, PartialEq)]
+pub enum MichelineError {
+    #[fail(display = "Unexpected end of data at offset {}", offset)]
+    UnexpectedEnd { offset: usize },
+    #[fail(display = "Unknown node tag {} at offset {}", tag, offset)]
+    UnknownTag { tag: u8, offset: usize },
+    #[fail(display = "Unknown primitive {} at offset {}", primitive, offset)]
+    UnknownPrimitive { primitive: u8, offset: usize },
+    #[fail(display = "Invalid UTF-8 string at offset {}", offset)]
+    InvalidString { offset: usize },
+    #[fail(display = "{} unexpected bytes after the expression", remaining)]
+    TrailingBytes { remaining: usize },
+    #[fail(display = "Expression nested too deep at offset {}", offset)]
+    TooDeep { offset: usize },
+}
+
+/// Micheline expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Micheline {
+    Int(BigInt),
+    String(String),
+    Bytes(Vec<u8>),
+    Prim {
+        prim: String,
+        args: Vec<Micheline>,
+        annots: Vec<String>,
+    },
+    Seq(Vec<Micheline>),
+}
+
+impl Micheline {
+    pub fn prim(prim: &str, args: Vec<Micheline>) -> Self {
+        Micheline::Prim {
+            prim: prim.to_string(),
+            args,
+            annots: vec![],
+        }
+    }
+
+    /// Decodes the whole `bytes` as single expression.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MichelineError> {
+        let mut reader = Reader { bytes, offset: 0 };
+        let expression = reader.expression(0)?;
+        match bytes.len() - reader.offset {
+            0 => Ok(expression),
+            remaining => Err(MichelineError::TrailingBytes { remaining }),
+        }
+    }
+
+    /// Decodes script values stored in the context, which are either plain expressions
+    /// (e.g. big map values) or "lazy" expressions prefixed with their size (contract code and storage).
+    pub fn from_context_bytes(bytes: &[u8]) -> Result<Self, MichelineError> {
+        if bytes.len() >= 4 {
+            let size = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+            if size == bytes.len() - 4 {
+                if let Ok(expression) = Self::from_bytes(&bytes[4..]) {
+                    return Ok(expression);
+                }
+            }
+        }
+        Self::from_bytes(bytes)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, size: usize) -> Result<&'a [u8], MichelineError> {
+        if self.bytes.len() - self.offset < size {
+            return Err(MichelineError::UnexpectedEnd {
+                offset: self.bytes.len(),
+            });
+        }
+        let data = &self.bytes[self.offset..self.offset + size];
+        self.offset += size;
+        Ok(data)
+    }
+
+    fn byte(&mut self) -> Result<u8, MichelineError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn size(&mut self) -> Result<usize, MichelineError> {
+        let data = self.take(4)?;
+        Ok(u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize)
+    }
+
+    fn dynamic(&mut self) -> Result<&'a [u8], MichelineError> {
+        let size = self.size()?;
+        self.take(size)
+    }
+
+    fn string(&mut self) -> Result<String, MichelineError> {
+        let offset = self.offset;
+        String::from_utf8(self.dynamic()?.to_vec())
+            .map_err(|_| MichelineError::InvalidString { offset })
+    }
+
+    /// Zarith encoding - 7 bits per byte (6 bits and sign in the first one), high bit means continuation
+    fn z(&mut self) -> Result<BigInt, MichelineError> {
+        let first = self.byte()?;
+        let negative = first & 0x40 != 0;
+        let mut value = BigInt::from(first & 0x3f);
+        let mut shift = 6;
+        let mut byte = first;
+        while byte & 0x80 != 0 {
+            byte = self.byte()?;
+            value += BigInt::from(byte & 0x7f) << shift;
+            shift += 7;
+        }
+        Ok(if negative { -value } else { value })
+    }
+
+    fn primitive(&mut self) -> Result<String, MichelineError> {
+        let offset = self.offset;
+        let primitive = self.byte()?;
+        PRIMITIVES
+            .get(primitive as usize)
+            .map(|prim| prim.to_string())
+            .ok_or(MichelineError::UnknownPrimitive { primitive, offset })
+    }
+
+    fn annots(&mut self) -> Result<Vec<String>, MichelineError> {
+        Ok(self
+            .string()?
+            .split_whitespace()
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn expressions(
+        &mut self,
+        count: usize,
+        depth: usize,
+    ) -> Result<Vec<Micheline>, MichelineError> {
+        let mut items = Vec::with_capacity(count);
+        for _ in 0..count {
+            items.push(self.expression(depth)?);
+        }
+        Ok(items)
+    }
+
+    fn sequence(&mut self, depth: usize) -> Result<Vec<Micheline>, MichelineError> {
+        let data = self.dynamic()?;
+        let mut reader = Reader {
+            bytes: &self.bytes[..self.offset],
+            offset: self.offset - data.len(),
+        };
+        let mut items = Vec::new();
+        while reader.offset < reader.bytes.len() {
+            items.push(reader.expression(depth)?);
+        }
+        Ok(items)
+    }
+
+    /// Decodes expression nested in `depth` enclosing sequences and primitive applications
+    fn expression(&mut self, depth: usize) -> Result<Micheline, MichelineError> {
+        let offset = self.offset;
+        if depth >= MAX_NESTING_DEPTH {
+            return Err(MichelineError::TooDeep { offset });
+        }
+        let tag = self.byte()?;
+        let expression = match tag {
+            0 => Micheline::Int(self.z()?),
+            1 => Micheline::String(self.string()?),
+            2 => Micheline::Seq(self.sequence(depth + 1)?),
+            3..=8 => {
+                let prim = self.primitive()?;
+                // tags 3, 5, 7 - 0, 1, 2 arguments without annotations, 4, 6, 8 - with annotations
+                let args = self.expressions(((tag - 3) / 2) as usize, depth + 1)?;
+                let annots = if tag % 2 == 0 { self.annots()? } else { vec![] };
+                Micheline::Prim { prim, args, annots }
+            }
+            9 => {
+                let prim = self.primitive()?;
+                let args = self.sequence(depth + 1)?;
+                let annots = self.annots()?;
+                Micheline::Prim { prim, args, annots }
+            }
+            10 => Micheline::Bytes(self.dynamic()?.to_vec()),
+            tag => return Err(MichelineError::UnknownTag { tag, offset }),
+        };
+        Ok(expression)
+    }
+}
+
+/// Octez JSON representation of Micheline
+impl Serialize for Micheline {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Micheline::Int(value) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("int", &value.to_string())?;
+                map.end()
+            }
+            Micheline::String(value) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("string", value)?;
+                map.end()
+            }
+            Micheline::Bytes(value) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("bytes", &hex::encode(value))?;
+                map.end()
+            }
+            Micheline::Prim { prim, args, annots } => {
+                let len = 1 + !args.is_empty() as usize + !annots.is_empty() as usize;
+                let mut map = serializer.serialize_map(Some(len))?;
+                map.serialize_entry("prim", prim)?;
+                if !args.is_empty() {
+                    map.serialize_entry("args", args)?;
+                }
+                if !annots.is_empty() {
+                    map.serialize_entry("annots", annots)?;
+                }
+                map.end()
+            }
+            Micheline::Seq(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+/// Michelson text representation, e.g. `Pair 1 (Some "a")`, `{ DROP ; NIL operation }`
+impl fmt::Display for Micheline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_expression(self, f, false)
+    }
+}
+
+fn write_expression(
+    expression: &Micheline,
+    f: &mut fmt::Formatter<'_>,
+    nested: bool,
+) -> fmt::Result {
+    match expression {
+        Micheline::Int(value) => write!(f, "{}", value),
+        Micheline::String(value) => write_string(value, f),
+        Micheline::Bytes(value) => write!(f, "0x{}", hex::encode(value)),
+        Micheline::Prim { prim, args, annots } => {
+            let parenthesize = nested && !(args.is_empty() && annots.is_empty());
+            if parenthesize {
+                f.write_str("(")?;
+            }
+            f.write_str(prim)?;
+            for annot in annots {
+                write!(f, " {}", annot)?;
+            }
+            for arg in args {
+                f.write_str(" ")?;
+                write_expression(arg, f, true)?;
+            }
+            if parenthesize {
+                f.write_str(")")?;
+            }
+            Ok(())
+        }
+        Micheline::Seq(items) if items.is_empty() => f.write_str("{}"),
+        Micheline::Seq(items) => {
+            f.write_str("{ ")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(" ; ")?;
+                }
+                write_expression(item, f, false)?;
+            }
+            f.write_str(" }")
+        }
+    }
+}
+
+/// Quotes string the same way as Octez `Micheline_printer`
+fn write_string(value: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\u{8}' => f.write_str("\\b")?,
+            '\t' => f.write_str("\\t")?,
+            '\\' => f.write_str("\\\\")?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
@@ -2,26 +2,28 @@
 // SPDX-License-Identifier: MIT
 
 use std::fmt;
-use std::mem::size_of;
 
 use getset::Getters;
 use serde::{Deserialize, Serialize};
 
-use tezos_encoding::encoding::{Encoding, Field, HasEncoding, Tag, TagMap};
-use tezos_encoding::has_encoding;
+use tezos_encoding::encoding::HasEncoding;
 
 use crate::non_cached_data;
 
 use super::limits::{NACK_PEERS_MAX_LENGTH, P2P_POINT_MAX_SIZE};
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, HasEncoding)]
 pub enum AckMessage {
+    #[encoding(tag = 0x00)]
     Ack,
+    #[encoding(tag = 0xFF)]
     NackV0,
+    #[encoding(tag = 0x01)]
     Nack(NackInfo),
 }
 
-#[derive(Serialize, Deserialize, PartialEq)]
+#[derive(Serialize, Deserialize, PartialEq, HasEncoding)]
+#[encoding(tags = "u16")]
 pub enum NackMotive {
     NoMotive,
     TooManyConnections,
@@ -51,11 +53,16 @@ impl fmt::Debug for NackMotive {
     }
 }
 
-#[derive(Serialize, Deserialize, Getters, PartialEq)]
+#[derive(Serialize, Deserialize, Getters, PartialEq, HasEncoding)]
 pub struct NackInfo {
     #[get = "pub"]
     motive: NackMotive,
     #[get = "pub"]
+    #[encoding(
+        dynamic,
+        bounded_list = "NACK_PEERS_MAX_LENGTH",
+        bounded = "P2P_POINT_MAX_SIZE"
+    )]
     potential_peers_to_connect: Vec<String>,
 }
 
@@ -79,42 +86,4 @@ impl fmt::Debug for NackInfo {
     }
 }
 
-impl NackInfo {
-    fn encoding() -> Encoding {
-        Encoding::Obj(vec![
-            Field::new(
-                "motive",
-                Encoding::Tags(
-                    size_of::<u16>(),
-                    TagMap::new(vec![
-                        Tag::new(0, "NoMotive", Encoding::Unit),
-                        Tag::new(1, "TooManyConnections", Encoding::Unit),
-                        Tag::new(2, "UnknownChainName", Encoding::Unit),
-                        Tag::new(3, "DeprecatedP2pVersion", Encoding::Unit),
-                        Tag::new(4, "DeprecatedDistributedDbVersion", Encoding::Unit),
-                        Tag::new(5, "AlreadyConnected", Encoding::Unit),
-                    ]),
-                ),
-            ),
-            Field::new(
-                "potential_peers_to_connect",
-                Encoding::dynamic(Encoding::bounded_list(
-                    NACK_PEERS_MAX_LENGTH,
-                    Encoding::bounded(P2P_POINT_MAX_SIZE, Encoding::String),
-                )),
-            ),
-        ])
-    }
-}
-
 non_cached_data!(AckMessage);
-has_encoding!(AckMessage, ACK_MESSAGE_ENCODING, {
-    Encoding::Tags(
-        size_of::<u8>(),
-        TagMap::new(vec![
-            Tag::new(0x00, "Ack", Encoding::Unit),
-            Tag::new(0x01, "Nack", NackInfo::encoding()),
-            Tag::new(0xFF, "NackV0", Encoding::Unit),
-        ]),
-    )
-});
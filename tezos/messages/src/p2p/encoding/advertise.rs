@@ -1,21 +1,24 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 use getset::Getters;
 use serde::{Deserialize, Serialize};
 
-use tezos_encoding::encoding::{Encoding, Field, HasEncoding};
-use tezos_encoding::has_encoding;
+use tezos_encoding::encoding::HasEncoding;
 
 use super::limits::{ADVERTISE_ID_LIST_MAX_LENGTH, P2P_POINT_MAX_SIZE};
 use crate::cached_data;
 use crate::p2p::binary_message::cache::BinaryDataCache;
 
-#[derive(Serialize, Deserialize, Debug, Getters, Clone)]
+#[derive(Serialize, Deserialize, Debug, Getters, Clone, HasEncoding)]
 pub struct AdvertiseMessage {
     #[get = "pub"]
+    #[encoding(
+        bounded_list = "ADVERTISE_ID_LIST_MAX_LENGTH",
+        bounded = "P2P_POINT_MAX_SIZE"
+    )]
     id: Vec<String>,
 
     #[serde(skip_serializing)]
@@ -25,22 +28,45 @@ pub struct AdvertiseMessage {
 impl AdvertiseMessage {
     pub fn new(addresses: &[SocketAddr]) -> Self {
         Self {
-            id: addresses
-                .iter()
-                .map(|address| format!("{}", address))
-                .collect(),
+            id: addresses.iter().map(p2p_point_to_string).collect(),
             body: Default::default(),
         }
     }
 }
 
+/// Formats socket address as p2p point in the same way as Tezos does,
+/// IPv4 (also IPv4-mapped IPv6) as `IP:PORT` and IPv6 as `[IP]:PORT`.
+///
+/// Note: see p2p_point.ml -> Id.pp
+pub fn p2p_point_to_string(address: &SocketAddr) -> String {
+    match canonical_ip(address.ip()) {
+        IpAddr::V4(ip) => format!("{}:{}", ip, address.port()),
+        IpAddr::V6(ip) => format!("[{}]:{}", ip, address.port()),
+    }
+}
+
+/// Parses p2p point `IP:PORT` or `[IP]:PORT` received from other peers (advertise, nack),
+/// IPv4-mapped IPv6 addresses are converted to plain IPv4.
+pub fn parse_p2p_point(point: &str) -> Option<SocketAddr> {
+    point
+        .trim()
+        .parse::<SocketAddr>()
+        .ok()
+        .map(|address| SocketAddr::new(canonical_ip(address.ip()), address.port()))
+}
+
+/// Converts IPv4-mapped IPv6 address (`::ffff:a.b.c.d`) to IPv4, other addresses are untouched.
+pub fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ipv6) => match ipv6.segments() {
+            [0, 0, 0, 0, 0, 0xffff, _, _] => {
+                let octets = ipv6.octets();
+                IpAddr::from([octets[12], octets[13], octets[14], octets[15]])
+            }
+            _ => ip,
+        },
+        IpAddr::V4(_) => ip,
+    }
+}
+
 cached_data!(AdvertiseMessage, body);
-has_encoding!(AdvertiseMessage, ADVERTISE_MESSAGE_ENCODING, {
-    Encoding::Obj(vec![Field::new(
-        "id",
-        Encoding::bounded_list(
-            ADVERTISE_ID_LIST_MAX_LENGTH,
-            Encoding::bounded(P2P_POINT_MAX_SIZE, Encoding::String),
-        ),
-    )])
-});
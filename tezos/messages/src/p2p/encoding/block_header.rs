@@ -7,9 +7,8 @@ use derive_builder::Builder;
 use getset::{CopyGetters, Getters};
 use serde::{Deserialize, Serialize};
 
-use crypto::hash::{BlockHash, ContextHash, HashType, OperationListListHash};
-use tezos_encoding::encoding::{Encoding, Field, HasEncoding, SchemaType};
-use tezos_encoding::has_encoding;
+use crypto::hash::{BlockHash, ContextHash, OperationListListHash};
+use tezos_encoding::encoding::{Encoding, HasEncoding, SchemaType};
 
 use crate::cached_data;
 use crate::p2p::binary_message::cache::BinaryDataCache;
@@ -28,6 +27,13 @@ pub fn fitness_encoding() -> Encoding {
     }))
 }
 
+fn protocol_data_encoding() -> Encoding {
+    Encoding::Split(Arc::new(|schema_type| match schema_type {
+        SchemaType::Json => Encoding::Bytes,
+        SchemaType::Binary => Encoding::list(Encoding::Uint8),
+    }))
+}
+
 pub fn display_fitness(fitness: &Fitness) -> String {
     fitness
         .iter()
@@ -36,7 +42,7 @@ pub fn display_fitness(fitness: &Fitness) -> String {
         .join("::")
 }
 
-#[derive(Serialize, Deserialize, Debug, Getters, Clone)]
+#[derive(Serialize, Deserialize, Debug, Getters, Clone, HasEncoding)]
 pub struct BlockHeaderMessage {
     #[get = "pub"]
     block_header: BlockHeader,
@@ -46,12 +52,6 @@ pub struct BlockHeaderMessage {
 }
 
 cached_data!(BlockHeaderMessage, body);
-has_encoding!(BlockHeaderMessage, BLOCK_HEADER_MESSAGE_ENCODING, {
-    Encoding::Obj(vec![Field::new(
-        "block_header",
-        BlockHeader::encoding().clone(),
-    )])
-});
 
 impl From<BlockHeader> for BlockHeaderMessage {
     fn from(block_header: BlockHeader) -> Self {
@@ -69,9 +69,10 @@ impl From<BlockHeaderMessage> for BlockHeader {
 }
 
 // -----------------------------------------------------------------------------------------------
-#[derive(Serialize, Deserialize, Debug, Getters, Clone)]
+#[derive(Serialize, Deserialize, Debug, Getters, Clone, HasEncoding)]
 pub struct GetBlockHeadersMessage {
     #[get = "pub"]
+    #[encoding(dynamic, bounded_list = "GET_BLOCK_HEADERS_MAX_LENGTH")]
     get_block_headers: Vec<BlockHash>,
 
     #[serde(skip_serializing)]
@@ -88,22 +89,12 @@ impl GetBlockHeadersMessage {
 }
 
 cached_data!(GetBlockHeadersMessage, body);
-has_encoding!(
-    GetBlockHeadersMessage,
-    GET_BLOCK_HEADERS_MESSAGE_ENCODING,
-    {
-        Encoding::Obj(vec![Field::new(
-            "get_block_headers",
-            Encoding::dynamic(Encoding::bounded_list(
-                GET_BLOCK_HEADERS_MAX_LENGTH,
-                Encoding::Hash(HashType::BlockHash),
-            )),
-        )])
-    }
-);
 
 // -----------------------------------------------------------------------------------------------
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Builder, Getters, CopyGetters)]
+#[derive(
+    Serialize, Deserialize, PartialEq, Debug, Clone, Builder, Getters, CopyGetters, HasEncoding,
+)]
+#[encoding(bounded = "BLOCK_HEADER_MAX_SIZE")]
 pub struct BlockHeader {
     #[get_copy = "pub"]
     level: Level,
@@ -112,17 +103,20 @@ pub struct BlockHeader {
     #[get = "pub"]
     predecessor: BlockHash,
     #[get_copy = "pub"]
+    #[encoding(builtin = "Timestamp")]
     timestamp: i64,
     #[get_copy = "pub"]
     validation_pass: u8,
     #[get = "pub"]
     operations_hash: OperationListListHash,
     #[get = "pub"]
+    #[encoding(custom = "fitness_encoding")]
     fitness: Fitness,
     #[get = "pub"]
     context: ContextHash,
 
     #[get = "pub"]
+    #[encoding(custom = "protocol_data_encoding")]
     protocol_data: Vec<u8>,
 
     #[serde(skip_serializing)]
@@ -131,28 +125,3 @@ pub struct BlockHeader {
 }
 
 cached_data!(BlockHeader, body);
-has_encoding!(BlockHeader, BLOCK_HEADER_ENCODING, {
-    Encoding::bounded(
-        BLOCK_HEADER_MAX_SIZE,
-        Encoding::Obj(vec![
-            Field::new("level", Encoding::Int32),
-            Field::new("proto", Encoding::Uint8),
-            Field::new("predecessor", Encoding::Hash(HashType::BlockHash)),
-            Field::new("timestamp", Encoding::Timestamp),
-            Field::new("validation_pass", Encoding::Uint8),
-            Field::new(
-                "operations_hash",
-                Encoding::Hash(HashType::OperationListListHash),
-            ),
-            Field::new("fitness", fitness_encoding()),
-            Field::new("context", Encoding::Hash(HashType::ContextHash)),
-            Field::new(
-                "protocol_data",
-                Encoding::Split(Arc::new(|schema_type| match schema_type {
-                    SchemaType::Json => Encoding::Bytes,
-                    SchemaType::Binary => Encoding::list(Encoding::Uint8),
-                })),
-            ),
-        ]),
-    )
-});
@@ -7,16 +7,15 @@ use getset::Getters;
 use serde::{Deserialize, Serialize};
 
 use crypto::hash::{BlockHash, ChainId, HashType};
-use tezos_encoding::encoding::{Encoding, Field, HasEncoding, SchemaType};
-use tezos_encoding::has_encoding;
+use tezos_encoding::encoding::{Encoding, HasEncoding, SchemaType};
 
 use crate::cached_data;
 use crate::p2p::binary_message::cache::BinaryDataCache;
 use crate::p2p::encoding::block_header::BlockHeader;
 
-use super::limits::CURRENT_BRANCH_HISTORY_MAX_LENGTH;
+use super::limits::{BLOCK_HEADER_MAX_SIZE, CURRENT_BRANCH_HISTORY_MAX_LENGTH};
 
-#[derive(Clone, Serialize, Deserialize, Debug, Getters)]
+#[derive(Clone, Serialize, Deserialize, Debug, Getters, HasEncoding)]
 pub struct CurrentBranchMessage {
     #[get = "pub"]
     chain_id: ChainId,
@@ -37,20 +36,16 @@ impl CurrentBranchMessage {
 }
 
 cached_data!(CurrentBranchMessage, body);
-has_encoding!(CurrentBranchMessage, CURRENT_BRANCH_MESSAGE_ENCODING, {
-    Encoding::Obj(vec![
-        Field::new("chain_id", Encoding::Hash(HashType::ChainId)),
-        Field::new("current_branch", CurrentBranch::encoding().clone()),
-    ])
-});
 
 // -----------------------------------------------------------------------------------------------
-#[derive(Clone, Serialize, Deserialize, Debug, Getters)]
+#[derive(Clone, Serialize, Deserialize, Debug, Getters, HasEncoding)]
 pub struct CurrentBranch {
     #[get = "pub"]
+    #[encoding(bounded_dynamic = "BLOCK_HEADER_MAX_SIZE")]
     current_head: BlockHeader,
     /// These hashes go from the top of the chain to the bottom (to genesis)
     #[get = "pub"]
+    #[encoding(custom = "history_encoding")]
     history: Vec<BlockHash>,
     #[serde(skip_serializing)]
     body: BinaryDataCache,
@@ -67,30 +62,19 @@ impl CurrentBranch {
 }
 
 cached_data!(CurrentBranch, body);
-has_encoding!(CurrentBranch, CURRENT_BRANCH_ENCODING, {
-    Encoding::Obj(vec![
-        Field::new(
-            "current_head",
-            Encoding::bounded_dynamic(
-                super::limits::BLOCK_HEADER_MAX_SIZE,
-                BlockHeader::encoding().clone(),
-            ),
-        ),
-        Field::new(
-            "history",
-            Encoding::Split(Arc::new(|schema_type| match schema_type {
-                SchemaType::Json => Encoding::Unit, // TODO: decode as list of hashes when history is needed
-                SchemaType::Binary => Encoding::bounded_list(
-                    CURRENT_BRANCH_HISTORY_MAX_LENGTH,
-                    Encoding::Hash(HashType::BlockHash),
-                ),
-            })),
+
+fn history_encoding() -> Encoding {
+    Encoding::Split(Arc::new(|schema_type| match schema_type {
+        SchemaType::Json => Encoding::Unit, // TODO: decode as list of hashes when history is needed
+        SchemaType::Binary => Encoding::bounded_list(
+            CURRENT_BRANCH_HISTORY_MAX_LENGTH,
+            Encoding::Hash(HashType::BlockHash),
         ),
-    ])
-});
+    }))
+}
 
 // -----------------------------------------------------------------------------------------------
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, HasEncoding)]
 pub struct GetCurrentBranchMessage {
     pub chain_id: ChainId,
 
@@ -108,13 +92,3 @@ impl GetCurrentBranchMessage {
 }
 
 cached_data!(GetCurrentBranchMessage, body);
-has_encoding!(
-    GetCurrentBranchMessage,
-    GET_CURRENT_BRANCH_MESSAGE_ENCODING,
-    {
-        Encoding::Obj(vec![Field::new(
-            "chain_id",
-            Encoding::Hash(HashType::ChainId),
-        )])
-    }
-);
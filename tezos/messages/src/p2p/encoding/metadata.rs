@@ -6,12 +6,11 @@ use std::fmt;
 use getset::CopyGetters;
 use serde::{Deserialize, Serialize};
 
-use tezos_encoding::encoding::{Encoding, Field, HasEncoding};
-use tezos_encoding::has_encoding;
+use tezos_encoding::encoding::HasEncoding;
 
 use crate::non_cached_data;
 
-#[derive(Serialize, Deserialize, CopyGetters, Clone)]
+#[derive(Serialize, Deserialize, CopyGetters, Clone, HasEncoding)]
 pub struct MetadataMessage {
     #[get_copy = "pub"]
     disable_mempool: bool,
@@ -39,9 +38,3 @@ impl fmt::Debug for MetadataMessage {
 }
 
 non_cached_data!(MetadataMessage);
-has_encoding!(MetadataMessage, METADATA_MESSAGE_ENCODING, {
-    Encoding::Obj(vec![
-        Field::new("disable_mempool", Encoding::Bool),
-        Field::new("private_node", Encoding::Bool),
-    ])
-});
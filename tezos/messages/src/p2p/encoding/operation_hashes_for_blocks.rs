@@ -4,17 +4,18 @@
 use getset::{CopyGetters, Getters};
 use serde::{Deserialize, Serialize};
 
-use crypto::hash::{BlockHash, HashType, OperationHash};
-use tezos_encoding::encoding::{Encoding, Field, HasEncoding};
-use tezos_encoding::has_encoding;
+use crypto::hash::{BlockHash, OperationHash};
+use tezos_encoding::encoding::HasEncoding;
 
 use crate::cached_data;
 use crate::p2p::binary_message::cache::BinaryDataCache;
+use crate::p2p::encoding::operations_for_blocks::PathCodec;
 use crate::p2p::encoding::prelude::Path;
 
-#[derive(Serialize, Deserialize, Debug, Getters, Clone)]
+#[derive(Serialize, Deserialize, Debug, Getters, Clone, HasEncoding)]
 pub struct GetOperationHashesForBlocksMessage {
     #[get = "pub"]
+    #[encoding(dynamic, list)]
     get_operation_hashes_for_blocks: Vec<OperationHashesForBlock>,
 
     #[serde(skip_serializing)]
@@ -31,25 +32,17 @@ impl GetOperationHashesForBlocksMessage {
 }
 
 cached_data!(GetOperationHashesForBlocksMessage, body);
-has_encoding!(
-    GetOperationHashesForBlocksMessage,
-    GET_OPERATION_HASHES_FOR_BLOCKS_MESSAGE_ENCODING,
-    {
-        Encoding::Obj(vec![Field::new(
-            "get_operation_hashes_for_blocks",
-            Encoding::dynamic(Encoding::list(OperationHashesForBlock::encoding().clone())),
-        )])
-    }
-);
 
 // ------------------ Response ------------------ //
-#[derive(Serialize, Deserialize, Debug, Getters, Clone)]
+#[derive(Serialize, Deserialize, Debug, Getters, Clone, HasEncoding)]
 pub struct OperationHashesForBlocksMessage {
     #[get = "pub"]
     operation_hashes_for_block: OperationHashesForBlock,
     #[get = "pub"]
+    #[encoding(custom = "PathCodec::get_encoding")]
     operation_hashes_path: Path,
     #[get = "pub"]
+    #[encoding(list, dynamic)]
     operation_hashes: Vec<OperationHash>,
 
     #[serde(skip_serializing)]
@@ -72,29 +65,9 @@ impl OperationHashesForBlocksMessage {
 }
 
 cached_data!(OperationHashesForBlocksMessage, body);
-has_encoding!(
-    OperationHashesForBlocksMessage,
-    OPERATION_HASHES_FOR_BLOCKS_MESSAGE_ENCODING,
-    {
-        Encoding::Obj(vec![
-            Field::new(
-                "operation_hashes_for_block",
-                OperationHashesForBlock::encoding().clone(),
-            ),
-            Field::new(
-                "operation_hashes_path",
-                super::operations_for_blocks::PathCodec::get_encoding(),
-            ),
-            Field::new(
-                "operation_hashes",
-                Encoding::list(Encoding::dynamic(Encoding::list(Encoding::Uint8))),
-            ),
-        ])
-    }
-);
 
 // ------------------ Inner message for operation hashes message ------------------ //
-#[derive(Serialize, Deserialize, Debug, Getters, CopyGetters, Clone)]
+#[derive(Serialize, Deserialize, Debug, Getters, CopyGetters, Clone, HasEncoding)]
 pub struct OperationHashesForBlock {
     #[get = "pub"]
     hash: BlockHash,
@@ -116,13 +89,3 @@ impl OperationHashesForBlock {
 }
 
 cached_data!(OperationHashesForBlock, body);
-has_encoding!(
-    OperationHashesForBlock,
-    OPERATION_HASHES_FOR_BLOCK_ENCODING,
-    {
-        Encoding::Obj(vec![
-            Field::new("hash", Encoding::Hash(HashType::BlockHash)),
-            Field::new("validation_pass", Encoding::Int8),
-        ])
-    }
-);
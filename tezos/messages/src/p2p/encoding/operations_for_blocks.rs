@@ -11,10 +11,10 @@ use serde::{Deserialize, Serialize};
 
 use crypto::hash::{BlockHash, Hash, HashType};
 use tezos_encoding::binary_reader::{ActualSize, BinaryReaderError, BinaryReaderErrorKind};
-use tezos_encoding::encoding::{CustomCodec, Encoding, Field, HasEncoding};
+use tezos_encoding::encoding::{CustomCodec, Encoding, HasEncoding};
+use tezos_encoding::safe;
 use tezos_encoding::ser::Error;
 use tezos_encoding::types::Value;
-use tezos_encoding::{has_encoding, safe};
 
 use crate::cached_data;
 use crate::p2p::binary_message::cache::BinaryDataCache;
@@ -36,7 +36,7 @@ use super::limits::{GET_OPERATIONS_FOR_BLOCKS_MAX_LENGTH, OPERATION_LIST_MAX_SIZ
 /// TODO: Implement mechanism for updating this, when Tezos implements this.
 pub const MAX_PASS_MERKLE_DEPTH: Option<usize> = Some(3);
 
-#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, CopyGetters, Getters)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, CopyGetters, Getters, HasEncoding)]
 pub struct OperationsForBlock {
     #[get = "pub"]
     hash: BlockHash,
@@ -63,20 +63,16 @@ impl OperationsForBlock {
 }
 
 cached_data!(OperationsForBlock, body);
-has_encoding!(OperationsForBlock, OPERATIONS_FOR_BLOCK_ENCODING, {
-    Encoding::Obj(vec![
-        Field::new("hash", Encoding::Hash(HashType::BlockHash)),
-        Field::new("validation_pass", Encoding::Int8),
-    ])
-});
 // -----------------------------------------------------------------------------------------------
-#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Getters)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Getters, HasEncoding)]
 pub struct OperationsForBlocksMessage {
     #[get = "pub"]
     operations_for_block: OperationsForBlock,
     #[get = "pub"]
+    #[encoding(custom = "PathCodec::get_encoding")]
     operation_hashes_path: Path,
     #[get = "pub"]
+    #[encoding(bounded = "OPERATION_LIST_MAX_SIZE", list, dynamic)]
     operations: Vec<Operation>,
     #[serde(skip_serializing)]
     body: BinaryDataCache,
@@ -98,26 +94,6 @@ impl OperationsForBlocksMessage {
 }
 
 cached_data!(OperationsForBlocksMessage, body);
-has_encoding!(
-    OperationsForBlocksMessage,
-    OPERATIONS_FOR_BLOCKS_MESSAGE_ENCODING,
-    {
-        Encoding::Obj(vec![
-            Field::new(
-                "operations_for_block",
-                OperationsForBlock::encoding().clone(),
-            ),
-            Field::new("operation_hashes_path", PathCodec::get_encoding()),
-            Field::new(
-                "operations",
-                Encoding::bounded(
-                    OPERATION_LIST_MAX_SIZE,
-                    Encoding::list(Encoding::dynamic(Operation::encoding().clone())),
-                ),
-            ),
-        ])
-    }
-);
 
 impl From<OperationsForBlocksMessage> for Vec<Operation> {
     fn from(msg: OperationsForBlocksMessage) -> Self {
@@ -213,9 +189,10 @@ impl Serialize for Path {
     }
 }
 // -----------------------------------------------------------------------------------------------
-#[derive(Serialize, Deserialize, Debug, Getters, Clone)]
+#[derive(Serialize, Deserialize, Debug, Getters, Clone, HasEncoding)]
 pub struct GetOperationsForBlocksMessage {
     #[get = "pub"]
+    #[encoding(dynamic, bounded_list = "GET_OPERATIONS_FOR_BLOCKS_MAX_LENGTH")]
     get_operations_for_blocks: Vec<OperationsForBlock>,
     #[serde(skip_serializing)]
     body: BinaryDataCache,
@@ -231,19 +208,6 @@ impl GetOperationsForBlocksMessage {
 }
 
 cached_data!(GetOperationsForBlocksMessage, body);
-has_encoding!(
-    GetOperationsForBlocksMessage,
-    GET_OPERATIONS_FOR_BLOCKS_MESSAGE_ENCODING,
-    {
-        Encoding::Obj(vec![Field::new(
-            "get_operations_for_blocks",
-            Encoding::dynamic(Encoding::bounded_list(
-                GET_OPERATIONS_FOR_BLOCKS_MAX_LENGTH,
-                OperationsForBlock::encoding().clone(),
-            )),
-        )])
-    }
-);
 
 // ---------------------------------------
 
@@ -1,13 +1,10 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
-use std::mem::size_of;
-
 use getset::Getters;
 use serde::{Deserialize, Serialize};
 
-use tezos_encoding::encoding::{Encoding, Field, HasEncoding, Tag, TagMap};
-use tezos_encoding::has_encoding;
+use tezos_encoding::encoding::HasEncoding;
 
 use crate::cached_data;
 use crate::p2p::binary_message::cache::BinaryDataCache;
@@ -15,31 +12,53 @@ use crate::p2p::encoding::prelude::*;
 
 use super::limits::MESSAGE_MAX_SIZE;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, HasEncoding)]
+#[encoding(tags = "u16")]
 pub enum PeerMessage {
+    #[encoding(tag = 0x01)]
     Disconnect,
+    #[encoding(tag = 0x03)]
     Advertise(AdvertiseMessage),
+    #[encoding(tag = 0x04)]
     SwapRequest(SwapMessage),
+    #[encoding(tag = 0x05)]
     SwapAck(SwapMessage),
+    #[encoding(tag = 0x02)]
     Bootstrap,
+    #[encoding(tag = 0x10)]
     GetCurrentBranch(GetCurrentBranchMessage),
+    #[encoding(tag = 0x11)]
     CurrentBranch(CurrentBranchMessage),
+    #[encoding(tag = 0x12)]
     Deactivate(DeactivateMessage),
+    #[encoding(tag = 0x13)]
     GetCurrentHead(GetCurrentHeadMessage),
+    #[encoding(tag = 0x14)]
     CurrentHead(CurrentHeadMessage),
+    #[encoding(tag = 0x20)]
     GetBlockHeaders(GetBlockHeadersMessage),
+    #[encoding(tag = 0x21)]
     BlockHeader(BlockHeaderMessage),
+    #[encoding(tag = 0x30)]
     GetOperations(GetOperationsMessage),
+    #[encoding(tag = 0x31)]
     Operation(OperationMessage),
+    #[encoding(tag = 0x40)]
     GetProtocols(GetProtocolsMessage),
+    #[encoding(tag = 0x41)]
     Protocol(ProtocolMessage),
+    #[encoding(tag = 0x50)]
     GetOperationHashesForBlocks(GetOperationHashesForBlocksMessage),
+    #[encoding(tag = 0x51)]
     OperationHashesForBlock(OperationHashesForBlocksMessage),
+    #[encoding(tag = 0x60)]
     GetOperationsForBlocks(GetOperationsForBlocksMessage),
+    #[encoding(tag = 0x61)]
     OperationsForBlocks(OperationsForBlocksMessage),
 }
 
-#[derive(Serialize, Deserialize, Debug, Getters)]
+#[derive(Serialize, Deserialize, Debug, Getters, HasEncoding)]
+#[encoding(bounded_dynamic = "MESSAGE_MAX_SIZE")]
 pub struct PeerMessageResponse {
     #[get = "pub"]
     message: PeerMessage,
@@ -48,79 +67,6 @@ pub struct PeerMessageResponse {
 }
 
 cached_data!(PeerMessageResponse, body);
-has_encoding!(PeerMessageResponse, PEER_MESSAGE_RESPONSE_ENCODING, {
-    Encoding::bounded_dynamic(
-        MESSAGE_MAX_SIZE,
-        Encoding::Obj(vec![Field::new(
-            "message",
-            Encoding::Tags(
-                size_of::<u16>(),
-                TagMap::new(vec![
-                    Tag::new(0x01, "Disconnect", Encoding::Unit),
-                    Tag::new(0x02, "Bootstrap", Encoding::Unit),
-                    Tag::new(0x03, "Advertise", AdvertiseMessage::encoding().clone()),
-                    Tag::new(0x04, "SwapRequest", SwapMessage::encoding().clone()),
-                    Tag::new(0x05, "SwapAck", SwapMessage::encoding().clone()),
-                    Tag::new(
-                        0x10,
-                        "GetCurrentBranch",
-                        GetCurrentBranchMessage::encoding().clone(),
-                    ),
-                    Tag::new(
-                        0x11,
-                        "CurrentBranch",
-                        CurrentBranchMessage::encoding().clone(),
-                    ),
-                    Tag::new(0x12, "Deactivate", DeactivateMessage::encoding().clone()),
-                    Tag::new(
-                        0x13,
-                        "GetCurrentHead",
-                        GetCurrentHeadMessage::encoding().clone(),
-                    ),
-                    Tag::new(0x14, "CurrentHead", CurrentHeadMessage::encoding().clone()),
-                    Tag::new(
-                        0x20,
-                        "GetBlockHeaders",
-                        GetBlockHeadersMessage::encoding().clone(),
-                    ),
-                    Tag::new(0x21, "BlockHeader", BlockHeaderMessage::encoding().clone()),
-                    Tag::new(
-                        0x30,
-                        "GetOperations",
-                        GetOperationsMessage::encoding().clone(),
-                    ),
-                    Tag::new(0x31, "Operation", OperationMessage::encoding().clone()),
-                    Tag::new(
-                        0x40,
-                        "GetProtocols",
-                        GetProtocolsMessage::encoding().clone(),
-                    ),
-                    Tag::new(0x41, "Protocol", ProtocolMessage::encoding().clone()),
-                    Tag::new(
-                        0x50,
-                        "GetOperationHashesForBlocks",
-                        GetOperationHashesForBlocksMessage::encoding().clone(),
-                    ),
-                    Tag::new(
-                        0x51,
-                        "OperationHashesForBlocks",
-                        OperationHashesForBlocksMessage::encoding().clone(),
-                    ),
-                    Tag::new(
-                        0x60,
-                        "GetOperationsForBlocks",
-                        GetOperationsForBlocksMessage::encoding().clone(),
-                    ),
-                    Tag::new(
-                        0x61,
-                        "OperationsForBlocks",
-                        OperationsForBlocksMessage::encoding().clone(),
-                    ),
-                ]),
-            ),
-        )]),
-    )
-});
 
 impl From<PeerMessage> for PeerMessageResponse {
     fn from(message: PeerMessage) -> Self {
@@ -1,21 +1,21 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
-use crypto::hash::{CryptoboxPublicKeyHash, HashType};
+use crypto::hash::CryptoboxPublicKeyHash;
 use getset::Getters;
 use serde::{Deserialize, Serialize};
 
-use tezos_encoding::encoding::{Encoding, Field, HasEncoding};
-use tezos_encoding::has_encoding;
+use tezos_encoding::encoding::HasEncoding;
 
 use crate::cached_data;
 use crate::p2p::binary_message::cache::BinaryDataCache;
 
 use super::limits::P2P_POINT_MAX_SIZE;
 
-#[derive(Serialize, Deserialize, Debug, Getters, Clone)]
+#[derive(Serialize, Deserialize, Debug, Getters, Clone, HasEncoding)]
 pub struct SwapMessage {
     #[get = "pub"]
+    #[encoding(bounded = "P2P_POINT_MAX_SIZE")]
     point: String,
     #[get = "pub"]
     peer_id: CryptoboxPublicKeyHash,
@@ -35,12 +35,3 @@ impl SwapMessage {
 }
 
 cached_data!(SwapMessage, body);
-has_encoding!(SwapMessage, SWAP_MESSAGE_ENCODING, {
-    Encoding::Obj(vec![
-        Field::new(
-            "point",
-            Encoding::bounded(P2P_POINT_MAX_SIZE, Encoding::String),
-        ),
-        Field::new("peer_id", Encoding::Hash(HashType::CryptoboxPublicKeyHash)),
-    ])
-});
@@ -25,6 +25,8 @@ pub mod proto_006;
 pub mod proto_007;
 pub mod proto_008;
 pub mod proto_008_2;
+pub mod receipts;
+pub mod rights;
 
 lazy_static! {
     pub static ref SUPPORTED_PROTOCOLS: HashMap<String, SupportedProtocol> = init();
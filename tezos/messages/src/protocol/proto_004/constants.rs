@@ -6,11 +6,7 @@ use std::collections::HashMap;
 use getset::{CopyGetters, Getters, Setters};
 use serde::{Deserialize, Serialize};
 
-use tezos_encoding::{
-    encoding::{Encoding, Field, HasEncoding},
-    has_encoding,
-    types::BigInt,
-};
+use tezos_encoding::{encoding::HasEncoding, types::BigInt};
 
 use crate::base::rpc_support::{ToRpcJsonMap, UniversalValue};
 use crate::non_cached_data;
@@ -58,7 +54,7 @@ impl ToRpcJsonMap for FixedConstants {
 }
 
 // -----------------------------------------------------------------------------------------------
-#[derive(Serialize, Deserialize, Debug, Clone, Getters, CopyGetters, Setters)]
+#[derive(Serialize, Deserialize, Debug, Clone, Getters, CopyGetters, Setters, HasEncoding)]
 pub struct ParametricConstants {
     #[get_copy = "pub"]
     preserved_cycles: Option<u8>,
@@ -69,29 +65,40 @@ pub struct ParametricConstants {
     blocks_per_roll_snapshot: Option<i32>,
     blocks_per_voting_period: Option<i32>,
     #[get = "pub"]
+    #[encoding(dynamic)]
     time_between_blocks: Option<Vec<i64>>,
     #[get_copy = "pub"]
     endorsers_per_block: Option<u16>,
+    #[encoding(builtin = "Z")]
     hard_gas_limit_per_operation: Option<BigInt>,
+    #[encoding(builtin = "Z")]
     hard_gas_limit_per_block: Option<BigInt>,
     proof_of_work_threshold: Option<i64>,
+    #[encoding(builtin = "Mutez")]
     tokens_per_roll: Option<BigInt>,
     michelson_maximum_type_size: Option<u16>,
+    #[encoding(builtin = "Mutez")]
     seed_nonce_revelation_tip: Option<BigInt>,
     origination_size: Option<i32>,
     #[get = "pub"]
     #[set = "pub"]
+    #[encoding(builtin = "Mutez")]
     block_security_deposit: Option<BigInt>,
     #[get = "pub"]
     #[set = "pub"]
+    #[encoding(builtin = "Mutez")]
     endorsement_security_deposit: Option<BigInt>,
     #[get = "pub"]
     #[set = "pub"]
+    #[encoding(builtin = "Mutez")]
     block_reward: Option<BigInt>,
     #[get = "pub"]
     #[set = "pub"]
+    #[encoding(builtin = "Mutez")]
     endorsement_reward: Option<BigInt>,
+    #[encoding(builtin = "Mutez")]
     cost_per_byte: Option<BigInt>,
+    #[encoding(builtin = "Z")]
     hard_storage_limit_per_operation: Option<BigInt>,
     test_chain_duration: Option<i64>,
 }
@@ -285,73 +292,3 @@ impl ToRpcJsonMap for ParametricConstants {
 }
 
 non_cached_data!(ParametricConstants);
-has_encoding!(ParametricConstants, PARAMETRIC_CONSTANTS_ENCODING, {
-    Encoding::Obj(vec![
-        Field::new("preserved_cycles", Encoding::option_field(Encoding::Uint8)),
-        Field::new("blocks_per_cycle", Encoding::option_field(Encoding::Int32)),
-        Field::new(
-            "blocks_per_commitment",
-            Encoding::option_field(Encoding::Int32),
-        ),
-        Field::new(
-            "blocks_per_roll_snapshot",
-            Encoding::option_field(Encoding::Int32),
-        ),
-        Field::new(
-            "blocks_per_voting_period",
-            Encoding::option_field(Encoding::Int32),
-        ),
-        Field::new(
-            "time_between_blocks",
-            Encoding::option_field(Encoding::dynamic(Encoding::list(Encoding::Int64))),
-        ),
-        Field::new(
-            "endorsers_per_block",
-            Encoding::option_field(Encoding::Uint16),
-        ),
-        Field::new(
-            "hard_gas_limit_per_operation",
-            Encoding::option_field(Encoding::Z),
-        ),
-        Field::new(
-            "hard_gas_limit_per_block",
-            Encoding::option_field(Encoding::Z),
-        ),
-        Field::new(
-            "proof_of_work_threshold",
-            Encoding::option_field(Encoding::Int64),
-        ),
-        Field::new("tokens_per_roll", Encoding::option_field(Encoding::Mutez)),
-        Field::new(
-            "michelson_maximum_type_size",
-            Encoding::option_field(Encoding::Uint16),
-        ),
-        Field::new(
-            "seed_nonce_revelation_tip",
-            Encoding::option_field(Encoding::Mutez),
-        ),
-        Field::new("origination_size", Encoding::option_field(Encoding::Int32)),
-        Field::new(
-            "block_security_deposit",
-            Encoding::option_field(Encoding::Mutez),
-        ),
-        Field::new(
-            "endorsement_security_deposit",
-            Encoding::option_field(Encoding::Mutez),
-        ),
-        Field::new("block_reward", Encoding::option_field(Encoding::Mutez)),
-        Field::new(
-            "endorsement_reward",
-            Encoding::option_field(Encoding::Mutez),
-        ),
-        Field::new("cost_per_byte", Encoding::option_field(Encoding::Mutez)),
-        Field::new(
-            "hard_storage_limit_per_operation",
-            Encoding::option_field(Encoding::Z),
-        ),
-        Field::new(
-            "test_chain_duration",
-            Encoding::option_field(Encoding::Int64),
-        ),
-    ])
-});
@@ -4,11 +4,7 @@ use std::collections::HashMap;
 // SPDX-License-Identifier: MIT
 use serde::{Deserialize, Serialize};
 
-use tezos_encoding::{
-    encoding::{Encoding, Field, HasEncoding},
-    has_encoding,
-    types::BigInt,
-};
+use tezos_encoding::{encoding::HasEncoding, types::BigInt};
 
 use crate::base::rpc_support::{ToRpcJsonMap, UniversalValue};
 use crate::non_cached_data;
@@ -55,27 +51,38 @@ impl ToRpcJsonMap for FixedConstants {
 }
 
 // -----------------------------------------------------------------------------------------------
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, HasEncoding)]
 pub struct ParametricConstants {
     preserved_cycles: u8,
     blocks_per_cycle: i32,
     blocks_per_commitment: i32,
     blocks_per_roll_snapshot: i32,
     blocks_per_voting_period: i32,
+    #[encoding(dynamic)]
     time_between_blocks: Vec<i64>,
     endorsers_per_block: u16,
+    #[encoding(builtin = "Z")]
     hard_gas_limit_per_operation: BigInt,
+    #[encoding(builtin = "Z")]
     hard_gas_limit_per_block: BigInt,
     proof_of_work_threshold: i64,
+    #[encoding(builtin = "Mutez")]
     tokens_per_roll: BigInt,
     michelson_maximum_type_size: u16,
+    #[encoding(builtin = "Mutez")]
     seed_nonce_revelation_tip: BigInt,
     origination_size: i32,
+    #[encoding(builtin = "Mutez")]
     block_security_deposit: BigInt,
+    #[encoding(builtin = "Mutez")]
     endorsement_security_deposit: BigInt,
+    #[encoding(builtin = "Mutez")]
     block_reward: BigInt,
+    #[encoding(builtin = "Mutez")]
     endorsement_reward: BigInt,
+    #[encoding(builtin = "Mutez")]
     cost_per_byte: BigInt,
+    #[encoding(builtin = "Z")]
     hard_storage_limit_per_operation: BigInt,
     test_chain_duration: i64,
     quorum_min: i32,
@@ -191,36 +198,3 @@ impl ToRpcJsonMap for ParametricConstants {
 }
 
 non_cached_data!(ParametricConstants);
-has_encoding!(ParametricConstants, PARAMETRIC_CONSTANTS_ENCODING, {
-    Encoding::Obj(vec![
-        Field::new("preserved_cycles", Encoding::Uint8),
-        Field::new("blocks_per_cycle", Encoding::Int32),
-        Field::new("blocks_per_commitment", Encoding::Int32),
-        Field::new("blocks_per_roll_snapshot", Encoding::Int32),
-        Field::new("blocks_per_voting_period", Encoding::Int32),
-        Field::new(
-            "time_between_blocks",
-            Encoding::dynamic(Encoding::list(Encoding::Int64)),
-        ),
-        Field::new("endorsers_per_block", Encoding::Uint16),
-        Field::new("hard_gas_limit_per_operation", Encoding::Z),
-        Field::new("hard_gas_limit_per_block", Encoding::Z),
-        Field::new("proof_of_work_threshold", Encoding::Int64),
-        Field::new("tokens_per_roll", Encoding::Mutez),
-        Field::new("michelson_maximum_type_size", Encoding::Uint16),
-        Field::new("seed_nonce_revelation_tip", Encoding::Mutez),
-        Field::new("origination_size", Encoding::Int32),
-        Field::new("block_security_deposit", Encoding::Mutez),
-        Field::new("endorsement_security_deposit", Encoding::Mutez),
-        Field::new("block_reward", Encoding::Mutez),
-        Field::new("endorsement_reward", Encoding::Mutez),
-        Field::new("cost_per_byte", Encoding::Mutez),
-        Field::new("hard_storage_limit_per_operation", Encoding::Z),
-        Field::new("test_chain_duration", Encoding::Int64),
-        Field::new("quorum_min", Encoding::Int32),
-        Field::new("quorum_max", Encoding::Int32),
-        Field::new("min_proposal_quorum", Encoding::Int32),
-        Field::new("initial_endorsers", Encoding::Uint16),
-        Field::new("delay_per_missing_endorsement", Encoding::Int64),
-    ])
-});
@@ -4,17 +4,14 @@
 use getset::Getters;
 use serde::{Deserialize, Serialize};
 
-use tezos_encoding::{
-    encoding::{Encoding, Field, HasEncoding},
-    has_encoding,
-    types::BigInt,
-};
+use tezos_encoding::{encoding::HasEncoding, types::BigInt};
 
 use crate::non_cached_data;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Getters)]
+#[derive(Serialize, Deserialize, Debug, Clone, Getters, HasEncoding)]
 pub struct Counter {
     #[get = "pub"]
+    #[encoding(builtin = "Z")]
     counter: BigInt,
 }
 
@@ -25,6 +22,3 @@ impl Counter {
 }
 
 non_cached_data!(Counter);
-has_encoding!(Counter, COUNTER_ENCODING, {
-    Encoding::Obj(vec![Field::new("counter", Encoding::Z)])
-});
@@ -0,0 +1,351 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Block header metadata and operation receipts of the supported protocols.
+//!
+//! Metadata are stored (as json) after block application, these decoders read them natively in rust,
+//! so historical blocks can be served without protocol runner. Known fields are declared in the same order
+//! as Octez encodings, so metadata consisting of known fields are re-encoded the same as Octez output.
+//!
+//! Fields unknown to this decoder (e.g. from newer protocols) are kept in flattened maps, which are serialized
+//! at their declaration position, so unknown fields of [BlockHeaderMetadata] and [OperationContentsMetadata]
+//! are moved after the known ones and the re-encoded json can differ from Octez output in field order.
+//! Flattened maps keep their own fields in the original order (serde_json `preserve_order`).
+//! Balance updates of unknown kind are kept as they are (see [BalanceUpdate::Other]).
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use failure::Fail;
+use serde::de::{DeserializeSeed, IgnoredAny, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+/// Status, which Octez uses for metadata of operations, which were too large to be stored
+pub const OPERATION_METADATA_TOO_LARGE: &str = "too large";
+
+#[derive(Debug, Fail)]
+#[fail(display = "Decode receipts error, reason: {}", reason)]
+pub struct ReceiptsDecodeError {
+    reason: serde_json::Error,
+}
+
+impl From<serde_json::Error> for ReceiptsDecodeError {
+    fn from(error: serde_json::Error) -> Self {
+        ReceiptsDecodeError { reason: error }
+    }
+}
+
+/// Decodes block header metadata (shell and protocol part)
+pub fn decode_block_header_metadata(
+    json: &str,
+) -> Result<BlockHeaderMetadata, ReceiptsDecodeError> {
+    serde_json::from_str(json).map_err(ReceiptsDecodeError::from)
+}
+
+/// Decodes all operations of block with receipts, grouped by validation passes
+pub fn decode_operations(json: &str) -> Result<Vec<Vec<Operation>>, ReceiptsDecodeError> {
+    serde_json::from_str(json).map_err(ReceiptsDecodeError::from)
+}
+
+/// Decodes operations with receipts of one validation pass, other validation passes are skipped without decoding.
+/// Returns `None`, if block has no such validation pass.
+pub fn decode_validation_pass_operations(
+    json: &str,
+    list_offset: usize,
+) -> Result<Option<Vec<Operation>>, ReceiptsDecodeError> {
+    decode_seed(
+        json,
+        NthElement {
+            index: list_offset,
+            seed: PhantomData::<Vec<Operation>>,
+        },
+    )
+}
+
+/// Decodes one operation with receipts, other operations are skipped without decoding.
+/// Returns `None`, if block has no such operation.
+pub fn decode_operation(
+    json: &str,
+    list_offset: usize,
+    operation_offset: usize,
+) -> Result<Option<Operation>, ReceiptsDecodeError> {
+    decode_seed(
+        json,
+        NthElement {
+            index: list_offset,
+            seed: NthElement {
+                index: operation_offset,
+                seed: PhantomData::<Operation>,
+            },
+        },
+    )
+    .map(Option::flatten)
+}
+
+fn decode_seed<'de, S: DeserializeSeed<'de>>(
+    json: &'de str,
+    seed: S,
+) -> Result<S::Value, ReceiptsDecodeError> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let value = seed.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Deserializes just `index`-th element of array by `seed`, other elements are only skipped
+struct NthElement<S> {
+    index: usize,
+    seed: S,
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for NthElement<S> {
+    type Value = Option<S::Value>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, S: DeserializeSeed<'de>> Visitor<'de> for NthElement<S> {
+    type Value = Option<S::Value>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "array with at least {} elements", self.index + 1)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        for _ in 0..self.index {
+            if seq.next_element::<IgnoredAny>()?.is_none() {
+                return Ok(None);
+            }
+        }
+        let value = seq.next_element_seed(self.seed)?;
+        // the whole array has to be consumed
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(value)
+    }
+}
+
+/// Block header metadata, protocol part fields are optional, because they differ between protocols
+/// (and genesis block has none of them)
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BlockHeaderMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_protocol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_chain_status: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_operations_ttl: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_operation_data_length: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_block_header_length: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_operation_list_length: Option<Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baker: Option<String>,
+    /// deprecated since 008, replaced by `level_info`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level_info: Option<Value>,
+    /// deprecated since 008, replaced by `voting_period_info`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voting_period_kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voting_period_info: Option<Value>,
+    /// Note: null is valid value here, so None means missing field
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub nonce_hash: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consumed_gas: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deactivated: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance_updates: Option<Vec<BalanceUpdate>>,
+
+    /// Fields unknown to this decoder (e.g. from newer protocols)
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl BlockHeaderMetadata {
+    /// Returns `cycle_position` from level, which is available for all protocols (except genesis)
+    pub fn cycle_position(&self) -> Option<i64> {
+        self.level_info
+            .as_ref()
+            .or_else(|| self.level.as_ref())
+            .and_then(|level| level["cycle_position"].as_i64())
+    }
+}
+
+/// Balance update - receipts of block (baker rewards) and operations (fees, transfers, deposits...)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum BalanceUpdate {
+    Known(KnownBalanceUpdate),
+    /// Balance update of kind (or with fields) unknown to this decoder (e.g. from newer protocols),
+    /// it is kept as raw json, so it is re-encoded unchanged
+    Other(Value),
+}
+
+/// Balance update of kind known to this decoder, unknown fields are not allowed,
+/// so such balance update is kept as [BalanceUpdate::Other] instead of losing them
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum KnownBalanceUpdate {
+    Contract {
+        contract: String,
+        change: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        origin: Option<String>,
+    },
+    Freezer {
+        category: String,
+        delegate: String,
+        /// Protocols 004+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cycle: Option<i32>,
+        /// Protocols 001-003
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        level: Option<i32>,
+        change: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        origin: Option<String>,
+    },
+}
+
+/// Operation from block with receipts of all its contents
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub protocol: String,
+    pub chain_id: String,
+    pub hash: String,
+    pub branch: String,
+    pub contents: Vec<OperationContents>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl Operation {
+    /// Returns operation without receipts (Octez `?metadata=never`)
+    pub fn without_metadata(mut self) -> Self {
+        self.contents
+            .iter_mut()
+            .for_each(|contents| contents.metadata = None);
+        self
+    }
+}
+
+/// Content of operation, operation data are protocol specific, but metadata are always the last field
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OperationContents {
+    /// Operation data including `kind`
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<OperationContentsReceipt>,
+}
+
+impl OperationContents {
+    pub fn kind(&self) -> Option<&str> {
+        self.data.get("kind").and_then(Value::as_str)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OperationContentsReceipt {
+    Receipt(Box<OperationContentsMetadata>),
+    /// Receipt was not stored, see [OPERATION_METADATA_TOO_LARGE]
+    TooLarge(String),
+}
+
+/// Receipt of operation content, all fields are optional, because they depend on operation kind
+/// (e.g. proposals and ballots have empty metadata)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OperationContentsMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance_updates: Option<Vec<BalanceUpdate>>,
+    /// Endorsements
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegate: Option<String>,
+    /// Endorsements
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slots: Option<Vec<u16>>,
+    /// Manager operations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_result: Option<OperationResult>,
+    /// Manager operations, which emitted internal operations (e.g. smart contract calls)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub internal_operation_results: Option<Vec<InternalOperationResult>>,
+
+    /// Fields unknown to this decoder (e.g. from newer protocols)
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationResultStatus {
+    Applied,
+    Failed,
+    Skipped,
+    Backtracked,
+}
+
+/// Result of manager operation, the rest of fields (storage, consumed_gas, errors...) depends on operation kind and protocol
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OperationResult {
+    pub status: OperationResultStatus,
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+impl OperationResult {
+    pub fn is_applied(&self) -> bool {
+        self.status == OperationResultStatus::Applied
+    }
+
+    /// Balance updates caused by operation (applied or backtracked)
+    pub fn balance_updates(&self) -> Result<Option<Vec<BalanceUpdate>>, ReceiptsDecodeError> {
+        self.details
+            .get("balance_updates")
+            .map(|balance_updates| serde_json::from_value(balance_updates.clone()))
+            .transpose()
+            .map_err(ReceiptsDecodeError::from)
+    }
+
+    /// Errors of failed or backtracked operation
+    pub fn errors(&self) -> Option<&Vec<Value>> {
+        self.details.get("errors").and_then(Value::as_array)
+    }
+}
+
+/// Result of internal operation emitted by smart contract
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InternalOperationResult {
+    pub kind: String,
+    pub source: String,
+    pub nonce: u16,
+    /// Operation kind specific fields (e.g. amount, destination and parameters for transaction),
+    /// they are serialized between `nonce` and `result` as Octez does
+    #[serde(flatten)]
+    pub parameters: Map<String, Value>,
+    pub result: OperationResult,
+}
+
+/// Distinguishes null value from missing field for Option<Value>
+fn deserialize_nullable<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
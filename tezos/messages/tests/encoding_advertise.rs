@@ -6,6 +6,7 @@ use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use failure::Error;
 
 use tezos_encoding::binary_reader::{ActualSize, BinaryReaderErrorKind};
+use tezos_messages::p2p::encoding::advertise::{p2p_point_to_string, parse_p2p_point};
 use tezos_messages::p2p::encoding::prelude::*;
 use tezos_messages::p2p::{
     binary_message::BinaryMessage, encoding::limits::ADVERTISE_ID_LIST_MAX_LENGTH,
@@ -37,6 +38,49 @@ fn can_format_ip_address() {
     assert_eq!("[fe80:e828:209d:20e:c0ae::]:375", &message.id()[1]);
 }
 
+#[test]
+fn can_format_ipv4_mapped_address() {
+    let addresses = vec![SocketAddr::new(
+        IpAddr::V6(Ipv4Addr::new(123, 123, 124, 21).to_ipv6_mapped()),
+        9876,
+    )];
+    let message = AdvertiseMessage::new(&addresses);
+    assert_eq!("123.123.124.21:9876", &message.id()[0]);
+    assert_eq!(
+        "[::1]:9732",
+        p2p_point_to_string(&SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 9732))
+    );
+}
+
+#[test]
+fn can_parse_p2p_point() {
+    assert_eq!(
+        Some(SocketAddr::new(
+            IpAddr::V4(Ipv4Addr::new(123, 123, 124, 21)),
+            9876
+        )),
+        parse_p2p_point("123.123.124.21:9876")
+    );
+    assert_eq!(
+        Some(SocketAddr::new(
+            IpAddr::V4(Ipv4Addr::new(123, 123, 124, 21)),
+            9876
+        )),
+        parse_p2p_point("[::ffff:123.123.124.21]:9876")
+    );
+    assert_eq!(
+        Some(SocketAddr::new(
+            IpAddr::V6(Ipv6Addr::new(
+                0xfe80, 0xe828, 0x209d, 0x20e, 0xc0ae, 0, 0, 0,
+            )),
+            375,
+        )),
+        parse_p2p_point("[fe80:e828:209d:20e:c0ae::]:375")
+    );
+    assert_eq!(None, parse_p2p_point("fe80:e828:209d:20e:c0ae::375"));
+    assert_eq!(None, parse_p2p_point("123.123.124.21"));
+}
+
 #[test]
 fn can_serialize_max_advertise() {
     let addr = SocketAddr::new(
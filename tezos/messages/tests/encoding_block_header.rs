@@ -3,6 +3,10 @@
 
 use crypto::hash::HashType;
 use failure::Error;
+use tezos_encoding::binary_schema::binary_schema_of;
+use tezos_encoding::encoding::HasEncoding;
+use tezos_encoding::json_reader::JsonReader;
+use tezos_encoding::json_schema::json_schema_of;
 use tezos_messages::p2p::binary_message::{BinaryMessage, MessageHash};
 use tezos_messages::p2p::encoding::prelude::*;
 
@@ -57,3 +61,54 @@ fn can_deserialize_get_block_headers() -> Result<(), Error> {
         _ => panic!("Unsupported encoding: {:?}", message),
     }
 }
+
+#[test]
+fn can_generate_block_header_schema() {
+    let schema = json_schema_of::<BlockHeader>();
+    assert_eq!("object", schema["type"]);
+    assert_eq!(9, schema["required"].as_array().unwrap().len());
+    assert_eq!(
+        "#/definitions/block_hash",
+        schema["properties"]["predecessor"]["$ref"]
+    );
+    assert_eq!("date-time", schema["properties"]["timestamp"]["format"]);
+    assert_eq!(
+        "^([a-zA-Z0-9][a-zA-Z0-9])*$",
+        schema["properties"]["protocol_data"]["pattern"]
+    );
+    assert!(schema["definitions"]["operation_list_list_hash"].is_object());
+    assert!(schema["definitions"]["context_hash"].is_object());
+
+    let schema = binary_schema_of::<BlockHeader>();
+    let fields = schema["toplevel"]["fields"].as_array().unwrap();
+    assert_eq!("level", fields[0]["name"]);
+    assert_eq!("Int32", fields[0]["layout"]["size"]);
+    assert_eq!("protocol_data", fields.last().unwrap()["name"]);
+    assert_eq!("Seq", fields.last().unwrap()["layout"]["kind"]);
+    assert_eq!("Uint8", fields.last().unwrap()["layout"]["layout"]["size"]);
+}
+
+#[test]
+fn can_read_block_header_from_json() -> Result<(), Error> {
+    let json = serde_json::json!({
+        "level": 28014,
+        "proto": 1,
+        "predecessor": "BKjYUUtYXtXjEuL49jB8ZbFwVdg4hU6U7oKKSC5vp6stYsfFDVN",
+        "timestamp": "2018-12-13T15:10:48Z",
+        "validation_pass": 4,
+        "operations_hash": "LLoZi3xywrX9swZQgC82m7vj5hmuz6LGAatNq2Muh34oNn71JruZs",
+        "fitness": ["00", "00000000000c15ef"],
+        "context": "CoUoqw1cVKUUNWyAviph5cdsjDpgeNhH2DGkMtgy7N6kfwnbewvS",
+        "protocol_data": "000000000003c762c7df00a856b8bfcaf0676f069f825ca75f37f2bee9fe55ba109cec3d1d041d8c03519626c0c0faa557e778cb09d2e0c729e8556ed6a7a518c84982d1f2682bc6aa753f"
+    });
+
+    let message_bytes = JsonReader::new().read_binary(&json, BlockHeader::encoding())?;
+    let block_header = BlockHeader::from_bytes(message_bytes)?;
+    assert_eq!(28014, block_header.level());
+    assert_eq!(1544713848, block_header.timestamp());
+    assert_eq!(
+        "BKoBK7Qa8J4Wvz85MDRWmpAntd5UhPhCh3p6Ga6woJywF8cZkeJ",
+        HashType::BlockHash.hash_to_b58check(&block_header.message_hash()?)?
+    );
+    Ok(())
+}
@@ -0,0 +1,146 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::convert::TryFrom;
+use std::mem::size_of;
+
+use failure::Error;
+use serde::{Deserialize, Serialize};
+
+use crypto::hash::{BlockHash, HashType, OperationHash};
+use tezos_encoding::binary_reader::BinaryReader;
+use tezos_encoding::encoding::{Encoding, Field, HasEncoding};
+use tezos_encoding::{binary_writer, de};
+use tezos_messages::p2p::binary_message::BinaryMessage;
+use tezos_messages::p2p::encoding::operation_hashes_for_blocks::OperationHashesForBlock;
+use tezos_messages::p2p::encoding::prelude::*;
+
+const MAX_HASHES: usize = 2;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, HasEncoding)]
+#[encoding(bounded_dynamic = "1024")]
+struct Sample {
+    #[encoding(builtin = "Timestamp")]
+    timestamp: i64,
+    #[encoding(dynamic, bounded_list = "MAX_HASHES")]
+    hashes: Vec<BlockHash>,
+    #[encoding(bounded = 16)]
+    name: String,
+    comment: Option<String>,
+    #[serde(rename = "kind")]
+    sample_kind: SampleKind,
+    #[serde(skip)]
+    cache: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, HasEncoding)]
+#[encoding(tags = "u16")]
+enum SampleKind {
+    #[encoding(tag = 0x10)]
+    Empty,
+    Value(i32),
+    #[encoding(tag = 0x20)]
+    Named {
+        value: bool,
+    },
+}
+
+#[test]
+fn derived_struct_encoding_follows_fields() {
+    let expected = Encoding::bounded_dynamic(
+        1024,
+        Encoding::Obj(vec![
+            Field::new("timestamp", Encoding::Timestamp),
+            Field::new(
+                "hashes",
+                Encoding::dynamic(Encoding::bounded_list(
+                    MAX_HASHES,
+                    Encoding::Hash(HashType::BlockHash),
+                )),
+            ),
+            Field::new("name", Encoding::bounded(16, Encoding::String)),
+            Field::new("comment", Encoding::option_field(Encoding::String)),
+            Field::new("kind", SampleKind::encoding().clone()),
+        ]),
+    );
+    assert_eq!(
+        format!("{:?}", expected),
+        format!("{:?}", Sample::encoding())
+    );
+}
+
+#[test]
+fn derived_enum_encoding_uses_tags() {
+    let tags = match SampleKind::encoding() {
+        Encoding::Tags(tag_size, tags) => {
+            assert_eq!(size_of::<u16>(), *tag_size);
+            tags
+        }
+        encoding => panic!("unexpected encoding: {:?}", encoding),
+    };
+    let expected = vec![
+        (0x10, "Empty", Encoding::Unit),
+        (0x11, "Value", Encoding::Int32),
+        (
+            0x20,
+            "Named",
+            Encoding::Obj(vec![Field::new("value", Encoding::Bool)]),
+        ),
+    ];
+    assert_eq!(expected.len(), tags.tags().count());
+    for (id, variant, encoding) in expected {
+        let tag = tags.find_by_variant(variant).expect("tag for variant");
+        assert_eq!(id, tag.get_id());
+        assert_eq!(
+            format!("{:?}", encoding),
+            format!("{:?}", tag.get_encoding())
+        );
+    }
+}
+
+#[test]
+fn can_serialize_and_deserialize_derived_encoding() -> Result<(), Error> {
+    let sample = Sample {
+        timestamp: 1_600_000_000,
+        hashes: vec![BlockHash::try_from(vec![1; HashType::BlockHash.size()])?],
+        name: "sample".to_string(),
+        comment: None,
+        sample_kind: SampleKind::Value(-7),
+        cache: None,
+    };
+
+    let bytes = binary_writer::write(&sample, Sample::encoding())?;
+    assert_eq!(
+        "0000003d000000005f5e10000000002001010101010101010101010101010101010101010101010101010101010101010000000673616d706c65000011fffffff9",
+        hex::encode(&bytes)
+    );
+
+    let value = BinaryReader::new().read(bytes, Sample::encoding())?;
+    let deserialized: Sample = de::from_value(&value)?;
+    assert_eq!(sample, deserialized);
+    Ok(())
+}
+
+#[test]
+fn can_serialize_operation_hashes_for_block_peer_message() -> Result<(), Error> {
+    let message = OperationHashesForBlocksMessage::new(
+        OperationHashesForBlock::new(BlockHash::try_from(vec![2; HashType::BlockHash.size()])?, 1),
+        Path::op(),
+        vec![OperationHash::try_from(vec![
+            3;
+            HashType::OperationHash.size()
+        ])?],
+    );
+    let bytes =
+        PeerMessageResponse::from(PeerMessage::OperationHashesForBlock(message)).as_bytes()?;
+
+    let response = PeerMessageResponse::from_bytes(bytes)?;
+    match response.message() {
+        PeerMessage::OperationHashesForBlock(message) => {
+            assert_eq!(1, message.operation_hashes_for_block().validation_pass());
+            assert_eq!(1, message.operation_hashes().len());
+        }
+        message => panic!("unexpected message: {:?}", message),
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use failure::Error;
+use num_bigint::BigInt;
+use serde_json::json;
+
+use tezos_messages::base::micheline::{Micheline, MichelineError, MAX_NESTING_DEPTH};
+
+fn int(value: i64) -> Micheline {
+    Micheline::Int(BigInt::from(value))
+}
+
+#[test]
+fn can_decode_data() -> Result<(), Error> {
+    // Pair 1 "a"
+    let value = Micheline::from_bytes(&hex::decode("07070001010000000161")?)?;
+    assert_eq!(
+        Micheline::prim("Pair", vec![int(1), Micheline::String("a".to_string())]),
+        value
+    );
+    assert_eq!(
+        json!({"prim": "Pair", "args": [{"int": "1"}, {"string": "a"}]}),
+        serde_json::to_value(&value)?
+    );
+    assert_eq!("Pair 1 \"a\"", value.to_string());
+
+    // Some 0xcafe
+    let value = Micheline::from_bytes(&hex::decode("05090a00000002cafe")?)?;
+    assert_eq!(
+        json!({"prim": "Some", "args": [{"bytes": "cafe"}]}),
+        serde_json::to_value(&value)?
+    );
+    assert_eq!("Some 0xcafe", value.to_string());
+    Ok(())
+}
+
+#[test]
+fn can_print_escaped_string() {
+    // the same escaping as Octez Micheline_printer, not Rust debug escaping
+    let value = Micheline::String("a \"b\"\n\t\\ é\u{8}".to_string());
+    assert_eq!(r#""a \"b\"\n\t\\ é\b""#, value.to_string());
+}
+
+#[test]
+fn can_decode_nested_up_to_max_depth() -> Result<(), Error> {
+    // Some (Some (... Unit)) - each `Some` adds one level
+    let nested = |somes: usize| hex::decode(format!("{}030b", "0509".repeat(somes)));
+
+    let value = Micheline::from_bytes(&nested(MAX_NESTING_DEPTH - 1)?)?;
+    assert!(value.to_string().starts_with("Some (Some (Some"));
+    assert!(serde_json::to_value(&value).is_ok());
+
+    assert_eq!(
+        Err(MichelineError::TooDeep {
+            offset: 2 * MAX_NESTING_DEPTH
+        }),
+        Micheline::from_bytes(&nested(MAX_NESTING_DEPTH)?)
+    );
+    // does not overflow the stack
+    assert_eq!(
+        Err(MichelineError::TooDeep {
+            offset: 2 * MAX_NESTING_DEPTH
+        }),
+        Micheline::from_bytes(&nested(1_000_000)?)
+    );
+
+    // nested sequences - { { { ... } } }
+    let mut bytes = vec![];
+    for _ in 0..=MAX_NESTING_DEPTH {
+        let mut outer = vec![0x02];
+        outer.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        outer.extend_from_slice(&bytes);
+        bytes = outer;
+    }
+    assert_eq!(
+        Err(MichelineError::TooDeep {
+            offset: 5 * MAX_NESTING_DEPTH
+        }),
+        Micheline::from_bytes(&bytes)
+    );
+    assert!(Micheline::from_bytes(&bytes[5..]).is_ok());
+    Ok(())
+}
+
+#[test]
+fn can_decode_z() -> Result<(), Error> {
+    assert_eq!(int(64), Micheline::from_bytes(&hex::decode("008001")?)?);
+    assert_eq!(int(-1000), Micheline::from_bytes(&hex::decode("00e80f")?)?);
+    let big = Micheline::from_bytes(&hex::decode("00ffffffffffffffffff7f")?)?;
+    assert_eq!(
+        json!({"int": "-590295810358705651711"}),
+        serde_json::to_value(&big)?
+    );
+    Ok(())
+}
+
+#[test]
+fn can_decode_code() -> Result<(), Error> {
+    // parameter (unit %default)
+    let value = Micheline::from_bytes(&hex::decode("0500046c000000082564656661756c74")?)?;
+    assert_eq!(
+        json!({"prim": "parameter", "args": [{"prim": "unit", "annots": ["%default"]}]}),
+        serde_json::to_value(&value)?
+    );
+    assert_eq!("parameter (unit %default)", value.to_string());
+
+    // { CDR ; NIL operation ; PAIR }
+    let value = Micheline::from_bytes(&hex::decode("02000000080317053d036d0342")?)?;
+    assert_eq!(
+        json!([{"prim": "CDR"}, {"prim": "NIL", "args": [{"prim": "operation"}]}, {"prim": "PAIR"}]),
+        serde_json::to_value(&value)?
+    );
+    assert_eq!("{ CDR ; NIL operation ; PAIR }", value.to_string());
+    assert_eq!(
+        "{}",
+        Micheline::from_bytes(&hex::decode("0200000000")?)?.to_string()
+    );
+
+    // generic primitive - Pair 1 2 3
+    let value = Micheline::from_bytes(&hex::decode("09070000000600010002000300000000")?)?;
+    assert_eq!(Micheline::prim("Pair", vec![int(1), int(2), int(3)]), value);
+    Ok(())
+}
+
+#[test]
+fn can_decode_context_value() -> Result<(), Error> {
+    // contract storage is stored with size prefix, big map values without it
+    let expected = Micheline::prim("Pair", vec![int(1), Micheline::String("a".to_string())]);
+    assert_eq!(
+        expected,
+        Micheline::from_context_bytes(&hex::decode("0000000a07070001010000000161")?)?
+    );
+    assert_eq!(
+        expected,
+        Micheline::from_context_bytes(&hex::decode("07070001010000000161")?)?
+    );
+    Ok(())
+}
+
+#[test]
+fn decoding_fails_on_invalid_data() -> Result<(), Error> {
+    assert_eq!(
+        Err(MichelineError::UnknownTag { tag: 11, offset: 0 }),
+        Micheline::from_bytes(&[11])
+    );
+    assert_eq!(
+        Err(MichelineError::UnknownPrimitive {
+            primitive: 0xff,
+            offset: 1
+        }),
+        Micheline::from_bytes(&[3, 0xff])
+    );
+    assert_eq!(
+        Err(MichelineError::TrailingBytes { remaining: 1 }),
+        Micheline::from_bytes(&hex::decode("00010a")?)
+    );
+    assert!(Micheline::from_bytes(&hex::decode("0707000101000000")?).is_err());
+    assert!(Micheline::from_bytes(&hex::decode("0200000004000100")?).is_err());
+    Ok(())
+}
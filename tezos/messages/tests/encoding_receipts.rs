@@ -0,0 +1,227 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use failure::Error;
+
+use tezos_messages::protocol::receipts::{
+    decode_block_header_metadata, decode_operation, decode_operations,
+    decode_validation_pass_operations, BalanceUpdate, InternalOperationResult, KnownBalanceUpdate,
+    OperationContentsMetadata, OperationContentsReceipt, OperationResultStatus,
+    OPERATION_METADATA_TOO_LARGE,
+};
+
+#[test]
+fn can_decode_block_header_metadata_008() -> Result<(), Error> {
+    let json = r#"{"protocol":"PtEdo2ZkT9oKpimTah6x2embF25oss54njMuPzkJTEi5RqfdZFA","next_protocol":"PtEdo2ZkT9oKpimTah6x2embF25oss54njMuPzkJTEi5RqfdZFA","test_chain_status":{"status":"not_running"},"max_operations_ttl":60,"max_operation_data_length":32768,"max_block_header_length":238,"max_operation_list_length":[{"max_size":4194304,"max_op":2048},{"max_size":32768},{"max_size":135168,"max_op":132},{"max_size":524288}],"baker":"tz1aWXP237BLwNHJcCD4b3DutCevhqq2T1Z9","level":{"level":1343489,"level_position":1343488,"cycle":328,"cycle_position":0,"voting_period":41,"voting_period_position":0,"expected_commitment":false},"level_info":{"level":1343489,"level_position":1343488,"cycle":328,"cycle_position":0,"expected_commitment":false},"voting_period_kind":"proposal","voting_period_info":{"voting_period":{"index":42,"kind":"proposal","start_position":1343488},"position":0,"remaining":32767},"nonce_hash":null,"consumed_gas":"0","deactivated":["tz1bWGbVbPjLLeJvbzLGqjG2jqtHjgXDD8xA"],"balance_updates":[{"kind":"contract","contract":"tz1aWXP237BLwNHJcCD4b3DutCevhqq2T1Z9","change":"-512000000"},{"kind":"freezer","category":"deposits","delegate":"tz1aWXP237BLwNHJcCD4b3DutCevhqq2T1Z9","cycle":328,"change":"512000000"}]}"#;
+
+    let metadata = decode_block_header_metadata(json)?;
+    assert_eq!(
+        Some("PtEdo2ZkT9oKpimTah6x2embF25oss54njMuPzkJTEi5RqfdZFA"),
+        metadata.protocol.as_deref()
+    );
+    assert_eq!(Some(serde_json::Value::Null), metadata.nonce_hash);
+    assert_eq!(Some(0), metadata.cycle_position());
+    assert_eq!(
+        Some(&BalanceUpdate::Known(KnownBalanceUpdate::Freezer {
+            category: "deposits".to_string(),
+            delegate: "tz1aWXP237BLwNHJcCD4b3DutCevhqq2T1Z9".to_string(),
+            cycle: Some(328),
+            level: None,
+            change: "512000000".to_string(),
+            origin: None,
+        })),
+        metadata
+            .balance_updates
+            .as_ref()
+            .and_then(|balance_updates| balance_updates.get(1))
+    );
+    assert!(metadata.other.is_empty());
+
+    // re-encoded json is the same as the original one
+    assert_eq!(json, serde_json::to_string(&metadata)?);
+    Ok(())
+}
+
+#[test]
+fn can_decode_block_header_metadata_001() -> Result<(), Error> {
+    // freezer balance update with level instead of cycle
+    let json = r#"{"protocol":"PtCJ7pwoxe8JasnHY8YonnLYjcVHmhiARPJvqcC6VfHT5s8k8sY","next_protocol":"PtCJ7pwoxe8JasnHY8YonnLYjcVHmhiARPJvqcC6VfHT5s8k8sY","test_chain_status":{"status":"not_running"},"max_operations_ttl":60,"max_operation_data_length":16384,"max_block_header_length":238,"max_operation_list_length":[{"max_size":32768,"max_op":32},{"max_size":32768},{"max_size":135168,"max_op":132},{"max_size":524288}],"baker":"tz3NdTPb3Ax2rVW2Kq9QEdzfYFkRwhrQRPhX","level":{"level":3,"level_position":2,"cycle":0,"cycle_position":2,"voting_period":0,"voting_period_position":2,"expected_commitment":false},"voting_period_kind":"proposal","nonce_hash":null,"consumed_gas":"0","deactivated":[],"balance_updates":[{"kind":"contract","contract":"tz3NdTPb3Ax2rVW2Kq9QEdzfYFkRwhrQRPhX","change":"-0"},{"kind":"freezer","category":"deposits","delegate":"tz3NdTPb3Ax2rVW2Kq9QEdzfYFkRwhrQRPhX","level":0,"change":"0"}]}"#;
+
+    let metadata = decode_block_header_metadata(json)?;
+    assert_eq!(None, metadata.level_info);
+    assert_eq!(Some(2), metadata.cycle_position());
+    assert_eq!(json, serde_json::to_string(&metadata)?);
+    Ok(())
+}
+
+#[test]
+fn can_decode_block_header_metadata_genesis() -> Result<(), Error> {
+    let json = r#"{"protocol":"PrihK96nBAFSxVL1GLJTVhu9YnzkMFiBeuJRPA8NwuZVZCE1L6i","next_protocol":"PtCJ7pwoxe8JasnHY8YonnLYjcVHmhiARPJvqcC6VfHT5s8k8sY","test_chain_status":{"status":"not_running"},"max_operations_ttl":0,"max_operation_data_length":0,"max_block_header_length":115,"max_operation_list_length":[]}"#;
+
+    let metadata = decode_block_header_metadata(json)?;
+    assert_eq!(None, metadata.baker);
+    assert_eq!(None, metadata.nonce_hash);
+    assert_eq!(None, metadata.cycle_position());
+    assert_eq!(json, serde_json::to_string(&metadata)?);
+    Ok(())
+}
+
+#[test]
+fn can_decode_unknown_balance_updates() -> Result<(), Error> {
+    // balance update of newer protocol kind and known kind with unknown field are kept unchanged
+    let json = r#"{"protocol":"PtEdo2ZkT9oKpimTah6x2embF25oss54njMuPzkJTEi5RqfdZFA","next_protocol":"PtEdo2ZkT9oKpimTah6x2embF25oss54njMuPzkJTEi5RqfdZFA","balance_updates":[{"kind":"minted","category":"baking rewards","change":"-10000000","origin":"block"},{"kind":"contract","contract":"tz1aWXP237BLwNHJcCD4b3DutCevhqq2T1Z9","change":"10000000","origin":"block","staker":"tz1aWXP237BLwNHJcCD4b3DutCevhqq2T1Z9"},{"kind":"contract","contract":"tz1aWXP237BLwNHJcCD4b3DutCevhqq2T1Z9","change":"-512000000","origin":"block"}]}"#;
+
+    let metadata = decode_block_header_metadata(json)?;
+    let balance_updates = metadata.balance_updates.clone().unwrap_or_default();
+    assert_eq!(3, balance_updates.len());
+    assert!(matches!(balance_updates[0], BalanceUpdate::Other(_)));
+    assert!(matches!(balance_updates[1], BalanceUpdate::Other(_)));
+    assert!(matches!(
+        balance_updates[2],
+        BalanceUpdate::Known(KnownBalanceUpdate::Contract { .. })
+    ));
+
+    assert_eq!(json, serde_json::to_string(&metadata)?);
+    Ok(())
+}
+
+#[test]
+fn can_decode_operations_with_receipts() -> Result<(), Error> {
+    let json = r#"[[{"protocol":"PsCARTHAGazKbHtnKfLzQg3kms52kSRpgnDY982a9oYsSXRLQEb","chain_id":"NetXdQprcVkpaWU","hash":"opNSHdr3wsaBcqLmqizwmZeBNVTcqtuEd5HW1yaM36D5ovAAZuA","branch":"BLuXs7bnCMDHYnXDLf3HmHsUUvwasg5ZrcBnmUxj9ksb7QwPKVi","contents":[{"kind":"endorsement","level":1168127,"metadata":{"balance_updates":[{"kind":"contract","contract":"tz1Zhv3RkfU2pHrmaiDyxp7kFZpZrUCu1CiF","change":"-1250000"},{"kind":"freezer","category":"deposits","delegate":"tz1Zhv3RkfU2pHrmaiDyxp7kFZpZrUCu1CiF","cycle":285,"change":"1250000"}],"delegate":"tz1Zhv3RkfU2pHrmaiDyxp7kFZpZrUCu1CiF","slots":[26]}}],"signature":"sigtN5p8EYpGFTGTfuDTC68QGyUGN9jm8U3DvUV3Dbz5TjxyhCcuaTC7bymqnDMDXhAFj5mZqBKgCNCt4esiq7Nqw6wWzbTk"}],[],[{"protocol":"PsCARTHAGazKbHtnKfLzQg3kms52kSRpgnDY982a9oYsSXRLQEb","chain_id":"NetXdQprcVkpaWU","hash":"onrvFLT4EBMTC3TkbtwNn4Hr3xXfaptKUqCmrg5SBP8NwpCLNHs","branch":"BLuXs7bnCMDHYnXDLf3HmHsUUvwasg5ZrcBnmUxj9ksb7QwPKVi","contents":[{"kind":"proposals","source":"tz1dgZzNaLYGpxfxFmqJWXyt7ZcU3BuxGs7c","period":36,"proposals":["PtEdoTezd3RHSC31mpxxo1npxFjoWWcFgQtxapi51Z8TLu6v6Uq"],"metadata":{}}],"signature":"sigPoJD9xhTrYAh5aoGxTBT7YpBRcexZydcdbx5K2z4Ezm8DjghQtycNwhmR2jjq89S9qqHJThyM2LnDTqjnUsXRmxYxWFT6"}],[{"protocol":"PsCARTHAGazKbHtnKfLzQg3kms52kSRpgnDY982a9oYsSXRLQEb","chain_id":"NetXdQprcVkpaWU","hash":"oo8oG5dJPJTwD3yMYfMJpWjE6kbnLD1yVpNbkyACEjQhoqdhuHs","branch":"BLuXs7bnCMDHYnXDLf3HmHsUUvwasg5ZrcBnmUxj9ksb7QwPKVi","contents":[{"kind":"transaction","source":"tz1MBidfvWhJ64MuJapKExcP5SV4HQWyiJwS","fee":"4092","counter":"4924405","gas_limit":"37858","storage_limit":"0","amount":"0","destination":"KT1Hkg5qeNhfwpKW4fXvq7HGZB9z2EnmCCA9","parameters":{"entrypoint":"transfer","value":{"prim":"Pair","args":[{"string":"tz1MBidfvWhJ64MuJapKExcP5SV4HQWyiJwS"},{"int":"1000"}]}},"metadata":{"balance_updates":[{"kind":"contract","contract":"tz1MBidfvWhJ64MuJapKExcP5SV4HQWyiJwS","change":"-4092"},{"kind":"freezer","category":"fees","delegate":"tz1Zhv3RkfU2pHrmaiDyxp7kFZpZrUCu1CiF","cycle":285,"change":"4092"}],"operation_result":{"status":"applied","storage":{"bytes":"00"},"big_map_diff":[],"consumed_gas":"37558","consumed_milligas":"37557420","storage_size":"6789"},"internal_operation_results":[{"kind":"transaction","source":"KT1Hkg5qeNhfwpKW4fXvq7HGZB9z2EnmCCA9","nonce":0,"amount":"1000","destination":"tz1MBidfvWhJ64MuJapKExcP5SV4HQWyiJwS","result":{"status":"applied","balance_updates":[{"kind":"contract","contract":"KT1Hkg5qeNhfwpKW4fXvq7HGZB9z2EnmCCA9","change":"-1000"},{"kind":"contract","contract":"tz1MBidfvWhJ64MuJapKExcP5SV4HQWyiJwS","change":"1000"}],"consumed_gas":"1427","consumed_milligas":"1427000"}}]}},{"kind":"reveal","source":"tz1MBidfvWhJ64MuJapKExcP5SV4HQWyiJwS","fee":"1269","counter":"4924406","gas_limit":"10000","storage_limit":"0","public_key":"edpkuBknW28nW72KG6RoHtYW7p12T6GKc7nAbwYX5m8Wd9sDVC9yav","metadata":{"balance_updates":[],"operation_result":{"status":"backtracked","errors":[{"kind":"temporary","id":"proto.006-PsCARTHA.gas_exhausted.operation"}],"consumed_gas":"10000"}}}],"signature":"sigvWzy3rWAXyGmRBzXZmbAAq7Hv3g2sUq1gpHk9FeYnpBZKWYyJHAUWhkHvkLgwBAxGm5SSgqwCqy2cJHAGpEZKAbZpYpLR"},{"protocol":"PsCARTHAGazKbHtnKfLzQg3kms52kSRpgnDY982a9oYsSXRLQEb","chain_id":"NetXdQprcVkpaWU","hash":"ooJEHtebaB6ohYVd2qBrLfBFzuatwcs6SXXzWfy6pqZmWDPjovK","branch":"BLuXs7bnCMDHYnXDLf3HmHsUUvwasg5ZrcBnmUxj9ksb7QwPKVi","contents":[{"kind":"transaction","source":"tz1MBidfvWhJ64MuJapKExcP5SV4HQWyiJwS","fee":"1420","counter":"4924407","gas_limit":"10307","storage_limit":"0","amount":"1","destination":"tz1Zhv3RkfU2pHrmaiDyxp7kFZpZrUCu1CiF","metadata":"too large"}],"signature":"sigTkxmYjqmt2RFuUcqAgHVSBDNnJ4vfCxRLqghS4gjt9YhLb7GcUBk4w5bKqVX1jTNXcNUBcDz1H8PcVyfBxsD7L9bqRq2J"}]]"#;
+
+    let operations = decode_operations(json)?;
+    assert_eq!(4, operations.len());
+    assert_eq!(1, operations[0].len());
+    assert!(operations[1].is_empty());
+    assert_eq!(2, operations[3].len());
+
+    // endorsement
+    let endorsement = &operations[0][0].contents[0];
+    assert_eq!(Some("endorsement"), endorsement.kind());
+    match &endorsement.metadata {
+        Some(OperationContentsReceipt::Receipt(metadata)) => {
+            assert_eq!(Some(vec![26]), metadata.slots);
+            assert!(metadata.operation_result.is_none());
+        }
+        _ => panic!("Expected endorsement receipt"),
+    }
+
+    // manager operations
+    let transaction = &operations[3][0].contents[0];
+    assert_eq!(Some("transaction"), transaction.kind());
+    match &transaction.metadata {
+        Some(OperationContentsReceipt::Receipt(metadata)) => {
+            let operation_result = metadata.operation_result.as_ref().unwrap();
+            assert!(operation_result.is_applied());
+            assert_eq!(None, operation_result.errors());
+
+            let internal_operation_results = metadata.internal_operation_results.as_ref().unwrap();
+            assert_eq!(1, internal_operation_results.len());
+            assert_eq!(
+                "KT1Hkg5qeNhfwpKW4fXvq7HGZB9z2EnmCCA9",
+                internal_operation_results[0].source
+            );
+            assert_eq!(
+                Some(2),
+                internal_operation_results[0]
+                    .result
+                    .balance_updates()?
+                    .map(|balance_updates| balance_updates.len())
+            );
+        }
+        _ => panic!("Expected transaction receipt"),
+    }
+    let reveal = &operations[3][0].contents[1];
+    match &reveal.metadata {
+        Some(OperationContentsReceipt::Receipt(metadata)) => {
+            let operation_result = metadata.operation_result.as_ref().unwrap();
+            assert_eq!(OperationResultStatus::Backtracked, operation_result.status);
+            assert_eq!(Some(1), operation_result.errors().map(Vec::len));
+        }
+        _ => panic!("Expected reveal receipt"),
+    }
+    assert_eq!(
+        Some(OperationContentsReceipt::TooLarge(
+            OPERATION_METADATA_TOO_LARGE.to_string()
+        )),
+        operations[3][1].contents[0].metadata
+    );
+
+    // re-encoded json is the same as the original one
+    assert_eq!(json, serde_json::to_string(&operations)?);
+
+    // without metadata
+    let operation = operations[3][0].clone().without_metadata();
+    assert!(operation
+        .contents
+        .iter()
+        .all(|contents| contents.metadata.is_none()));
+    assert!(!serde_json::to_string(&operation)?.contains("metadata"));
+
+    Ok(())
+}
+
+#[test]
+fn can_reencode_receipts_with_unknown_fields() -> Result<(), Error> {
+    // kind specific fields of internal operation stay between nonce and result, as in Octez output
+    let internal_operation_result = r#"{"kind":"transaction","source":"KT1Hkg5qeNhfwpKW4fXvq7HGZB9z2EnmCCA9","nonce":0,"amount":"0","destination":"KT1PWx2mnDueood7fEmfbBDKx1D9BAnnXitn","parameters":{"entrypoint":"default","value":{"prim":"Unit"}},"result":{"status":"applied","storage":{"prim":"Unit"},"consumed_gas":"1427"}}"#;
+    let decoded: InternalOperationResult = serde_json::from_str(internal_operation_result)?;
+    assert_eq!(
+        vec!["amount", "destination", "parameters"],
+        decoded.parameters.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(internal_operation_result, serde_json::to_string(&decoded)?);
+
+    // unknown fields are kept in their order, but they are moved after the known ones
+    let json = format!(
+        r#"{{"balance_updates":[],"paid_storage_size_diff":"1","operation_result":{{"status":"applied","consumed_gas":"10207"}},"internal_operation_results":[{}],"allocated_destination_contract":true}}"#,
+        internal_operation_result
+    );
+    let reencoded = format!(
+        r#"{{"balance_updates":[],"operation_result":{{"status":"applied","consumed_gas":"10207"}},"internal_operation_results":[{}],"paid_storage_size_diff":"1","allocated_destination_contract":true}}"#,
+        internal_operation_result
+    );
+    let metadata: OperationContentsMetadata = serde_json::from_str(&json)?;
+    assert_eq!(
+        vec!["paid_storage_size_diff", "allocated_destination_contract"],
+        metadata.other.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(reencoded, serde_json::to_string(&metadata)?);
+    assert_eq!(
+        metadata,
+        serde_json::from_str::<OperationContentsMetadata>(&reencoded)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn can_decode_selected_operations() -> Result<(), Error> {
+    // the second validation pass is not decodable as operations, but it is only skipped
+    let json = r#"[[{"protocol":"PtEdo2ZkT9oKpimTah6x2embF25oss54njMuPzkJTEi5RqfdZFA","chain_id":"NetXdQprcVkpaWU","hash":"opNSHdr3wsaBcqLmqizwmZeBNVTcqtuEd5HW1yaM36D5ovAAZuA","branch":"BLuXs7bnCMDHYnXDLf3HmHsUUvwasg5ZrcBnmUxj9ksb7QwPKVi","contents":[{"kind":"endorsement","level":1,"metadata":{}}],"signature":"sigtN5p8EYpGFTGTfuDTC68QGyUGN9jm8U3DvUV3Dbz5TjxyhCcuaTC7bymqnDMDXhAFj5mZqBKgCNCt4esiq7Nqw6wWzbTk"}],[{"unknown":[1,{"a":"b"}]}],[{"protocol":"PtEdo2ZkT9oKpimTah6x2embF25oss54njMuPzkJTEi5RqfdZFA","chain_id":"NetXdQprcVkpaWU","hash":"onrvFLT4EBMTC3TkbtwNn4Hr3xXfaptKUqCmrg5SBP8NwpCLNHs","branch":"BLuXs7bnCMDHYnXDLf3HmHsUUvwasg5ZrcBnmUxj9ksb7QwPKVi","contents":[{"kind":"proposals","period":36,"metadata":{}}]},{"protocol":"PtEdo2ZkT9oKpimTah6x2embF25oss54njMuPzkJTEi5RqfdZFA","chain_id":"NetXdQprcVkpaWU","hash":"oo8oG5dJPJTwD3yMYfMJpWjE6kbnLD1yVpNbkyACEjQhoqdhuHs","branch":"BLuXs7bnCMDHYnXDLf3HmHsUUvwasg5ZrcBnmUxj9ksb7QwPKVi","contents":[{"kind":"ballot","period":36,"metadata":{}}]}]]"#;
+    assert!(decode_operations(json).is_err());
+
+    let validation_pass = decode_validation_pass_operations(json, 2)?.unwrap_or_default();
+    assert_eq!(2, validation_pass.len());
+    assert_eq!(
+        "oo8oG5dJPJTwD3yMYfMJpWjE6kbnLD1yVpNbkyACEjQhoqdhuHs",
+        validation_pass[1].hash
+    );
+    assert_eq!(None, decode_validation_pass_operations(json, 3)?);
+    assert!(decode_validation_pass_operations(json, 1).is_err());
+
+    let operation = decode_operation(json, 2, 1)?;
+    assert_eq!(Some(&validation_pass[1]), operation.as_ref());
+    assert_eq!(
+        Some("opNSHdr3wsaBcqLmqizwmZeBNVTcqtuEd5HW1yaM36D5ovAAZuA"),
+        decode_operation(json, 0, 0)?
+            .as_ref()
+            .map(|operation| operation.hash.as_str())
+    );
+    assert_eq!(None, decode_operation(json, 2, 2)?);
+    assert_eq!(None, decode_operation(json, 4, 0)?);
+
+    // invalid json is detected even in skipped elements
+    assert!(decode_operation(&json[..json.len() - 1], 0, 0).is_err());
+    Ok(())
+}
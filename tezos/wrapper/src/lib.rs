@@ -1,15 +1,17 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
-#![forbid(unsafe_code)]
+#![deny(unsafe_code)]
 
 //! This crate provides core implementation for a protocol runner (both IPC server and client parts).
 
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 use failure::Fail;
 use getset::{CopyGetters, Getters};
 use r2d2::{CustomizeConnection, Pool};
+use serde::Serialize;
 use slog::{Level, Logger};
 
 use tezos_api::environment::TezosEnvironmentConfiguration;
@@ -17,10 +19,16 @@ use tezos_api::ffi::TezosRuntimeConfiguration;
 
 use crate::pool::{
     InitReadonlyContextProtocolRunnerConnectionCustomizer, NoopProtocolRunnerConnectionCustomizer,
-    PoolError, ProtocolRunnerConnection, ProtocolRunnerManager, SlogErrorHandler,
+    PoolError, ProtocolRunnerManager, SlogErrorHandler,
 };
-use crate::runner::ExecutableProtocolRunner;
+use crate::runner::{ExecutableProtocolRunner, ProtocolRunner};
 
+pub use crate::pool::{
+    ProtocolRunnerConnection, ProtocolRunnerMetrics, ProtocolRunnerRecycle, ProtocolRunnerStats,
+};
+
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
 mod pool;
 pub mod protocol;
 pub mod runner;
@@ -39,18 +47,36 @@ pub struct TezosApiConnectionPoolConfiguration {
     pub max_lifetime: Duration,
     /// if protocol_runner is not used 'idle_timeout', than is closed
     pub idle_timeout: Duration,
+
+    /// resource limits for every protocol_runner of the pool
+    pub limits: ProtocolRunnerLimits,
 }
 
-/// This pool is "hard-coded" for ExecutableProtocolRunner, but it is easily extended as [TezosApiConnectionPool<Runner: ProtocolRunner + 'static>] if needed
+/// Resource limits for protocol_runner sub-process (None means unlimited)
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ProtocolRunnerLimits {
+    /// Limit of virtual memory (setrlimit RLIMIT_AS), allocations over the limit fail in protocol_runner
+    pub max_virtual_memory_bytes: Option<u64>,
+    /// Limit of consumed CPU time (setrlimit RLIMIT_CPU), protocol_runner is killed, when exceeded
+    pub max_cpu_time: Option<Duration>,
+    /// protocol_runner with larger resident memory is recycled (replaced by a new one) at the next return to the pool
+    pub recycle_resident_memory_bytes: Option<u64>,
+}
+
+/// Default runner of the pool, other runners (e.g. [mock::MockProtocolRunner] for tests) can be used as [TezosApiConnectionPool<Runner>]
 pub type RunnerType = ExecutableProtocolRunner;
 
 /// Wrapper for r2d2 pool with managed protocol_runner "connections", protocol runners sub-processes are now managed and started by the pool.
 /// Automatically refreshes old protocol_runner sub-processes [idle_timeout][max_lifetime]
 ///
 /// One connection means one protocol_runner sub-process and one IPC
-pub struct TezosApiConnectionPool {
-    pub pool: Pool<ProtocolRunnerManager<RunnerType>>,
+///
+/// Crashed/stuck protocol_runner sub-processes are restarted with exponential backoff, see [metrics] for their failures
+pub struct TezosApiConnectionPool<Runner: ProtocolRunner + 'static = RunnerType> {
+    pub pool: Pool<ProtocolRunnerManager<Runner>>,
     pub pool_name: String,
+    pub metrics: Arc<ProtocolRunnerMetrics>,
+    pub limits: ProtocolRunnerLimits,
 }
 
 /// Errors for connection pool
@@ -67,14 +93,14 @@ impl From<r2d2::Error> for TezosApiConnectionPoolError {
     }
 }
 
-impl TezosApiConnectionPool {
+impl<Runner: ProtocolRunner + 'static> TezosApiConnectionPool<Runner> {
     /// Pool with ffi initialized context for readonly - see description AT_LEAST_ONE_WRITE_PROTOCOL_CONTEXT_WAS_SUCCESS_AT_FIRST_LOCK
     pub fn new_with_readonly_context(
         pool_name: String,
         pool_cfg: TezosApiConnectionPoolConfiguration,
         endpoint_cfg: ProtocolEndpointConfiguration,
         log: Logger,
-    ) -> Result<TezosApiConnectionPool, TezosApiConnectionPoolError> {
+    ) -> Result<Self, TezosApiConnectionPoolError> {
         Self::new(
             pool_name,
             pool_cfg,
@@ -91,7 +117,7 @@ impl TezosApiConnectionPool {
         pool_cfg: TezosApiConnectionPoolConfiguration,
         endpoint_cfg: ProtocolEndpointConfiguration,
         log: Logger,
-    ) -> Result<TezosApiConnectionPool, TezosApiConnectionPoolError> {
+    ) -> Result<Self, TezosApiConnectionPoolError> {
         Self::new(
             pool_name,
             pool_cfg,
@@ -106,14 +132,19 @@ impl TezosApiConnectionPool {
         pool_cfg: TezosApiConnectionPoolConfiguration,
         endpoint_cfg: ProtocolEndpointConfiguration,
         log: Logger,
-        initializer: Box<dyn CustomizeConnection<ProtocolRunnerConnection<RunnerType>, PoolError>>,
-    ) -> Result<TezosApiConnectionPool, TezosApiConnectionPoolError> {
+        initializer: Box<dyn CustomizeConnection<ProtocolRunnerConnection<Runner>, PoolError>>,
+    ) -> Result<Self, TezosApiConnectionPoolError> {
         // create manager
-        let manager = ProtocolRunnerManager::<RunnerType>::new(
+        let limits = pool_cfg.limits.clone();
+        let metrics = Arc::new(ProtocolRunnerMetrics::default());
+        let error_handler = SlogErrorHandler::new(log.clone(), pool_name.clone());
+        let manager = ProtocolRunnerManager::<Runner>::new(
             pool_name.clone(),
             pool_cfg.connection_timeout,
-            endpoint_cfg,
-            log.clone(),
+            endpoint_cfg.with_limits(limits.clone()),
+            metrics.clone(),
+            error_handler.clone(),
+            log,
         );
 
         // create pool for ffi protocol runner connections
@@ -124,14 +155,19 @@ impl TezosApiConnectionPool {
             .max_lifetime(Some(pool_cfg.max_lifetime))
             .idle_timeout(Some(pool_cfg.idle_timeout))
             .connection_customizer(initializer)
-            .error_handler(Box::new(SlogErrorHandler::new(log, pool_name.clone())))
+            .error_handler(Box::new(error_handler))
             .build(manager)?;
 
-        Ok(TezosApiConnectionPool { pool, pool_name })
+        Ok(TezosApiConnectionPool {
+            pool,
+            pool_name,
+            metrics,
+            limits,
+        })
     }
 }
 
-impl Drop for TezosApiConnectionPool {
+impl<Runner: ProtocolRunner + 'static> Drop for TezosApiConnectionPool<Runner> {
     fn drop(&mut self) {
         // TODO: ensure all connections are dropped and protocol_runners are closed
     }
@@ -152,6 +188,8 @@ pub struct ProtocolEndpointConfiguration {
     executable_path: PathBuf,
     #[get = "pub"]
     log_level: Level,
+    #[get = "pub"]
+    limits: ProtocolRunnerLimits,
     event_server_path: Option<PathBuf>,
 }
 
@@ -172,7 +210,14 @@ impl ProtocolEndpointConfiguration {
             data_dir: data_dir.as_ref().into(),
             executable_path: executable_path.as_ref().into(),
             log_level,
+            limits: ProtocolRunnerLimits::default(),
             event_server_path,
         }
     }
+
+    /// Set resource limits for protocol_runner sub-process
+    pub fn with_limits(mut self, limits: ProtocolRunnerLimits) -> Self {
+        self.limits = limits;
+        self
+    }
 }
@@ -0,0 +1,662 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Pure Rust protocol runner for deterministic tests without OCaml runtime (`libtezos`).
+//!
+//! [`MockProtocolApi`] answers protocol commands with responses chosen by [`MockProtocolScript`]
+//! and can emit [`ContextAction`] events for applied blocks. It can be served directly
+//! by [`MockProtocolApi::serve`] (uses [`process_protocol_commands`]) or by the pool
+//! as [`TezosApiConnectionPool<MockProtocolRunner>`](crate::TezosApiConnectionPool),
+//! where protocol runners are threads instead of sub-processes.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use slog::{o, warn, Discard, Logger};
+
+use crypto::hash::{ChainId, ContextHash, ProtocolHash};
+use ipc::{IpcChannelCloser, IpcClient, IpcError, IpcSender};
+use tezos_api::ffi::*;
+use tezos_context::channel::ContextAction;
+
+use crate::protocol::ProtocolApi;
+use crate::runner::{ProtocolRunner, ProtocolRunnerError, ProtocolRunnerExit};
+use crate::service::{serve_protocol_commands, NodeMessage, NoopMessage, ProtocolMessage};
+use crate::ProtocolEndpointConfiguration;
+
+type Handler<Request, Response> = Arc<dyn Fn(&Request) -> Response + Send + Sync>;
+
+lazy_static! {
+    /// Scripts registered for [`MockProtocolRunner`], key is fake executable path
+    static ref REGISTERED_SCRIPTS: Mutex<HashMap<PathBuf, MockProtocolScript>> = Mutex::new(HashMap::new());
+}
+static REGISTERED_SCRIPTS_COUNTER: AtomicUsize = AtomicUsize::new(1);
+
+thread_local! {
+    /// Script (and event channel) of the mock protocol runner served by the current thread
+    static SERVED: RefCell<Option<MockProtocolRunnerState>> = RefCell::new(None);
+}
+
+struct MockProtocolRunnerState {
+    script: MockProtocolScript,
+    events: Option<IpcSender<ContextAction>>,
+}
+
+/// Responses of [`MockProtocolApi`], not scripted calls fail with "not scripted" error message
+/// (except of runtime configuration, context initialization and protocol data encoding, which just succeed).
+#[derive(Clone, Default)]
+pub struct MockProtocolScript {
+    apply_block: Option<Handler<ApplyBlockRequest, Result<ApplyBlockResponse, ApplyBlockError>>>,
+    apply_block_context_actions: Option<Handler<ApplyBlockRequest, Vec<ContextAction>>>,
+    begin_application: Option<
+        Handler<BeginApplicationRequest, Result<BeginApplicationResponse, BeginApplicationError>>,
+    >,
+    begin_construction: Option<
+        Handler<BeginConstructionRequest, Result<PrevalidatorWrapper, BeginConstructionError>>,
+    >,
+    validate_operation: Option<
+        Handler<
+            ValidateOperationRequest,
+            Result<ValidateOperationResponse, ValidateOperationError>,
+        >,
+    >,
+    call_protocol_rpc:
+        Option<Handler<ProtocolRpcRequest, Result<ProtocolRpcResponse, ProtocolRpcError>>>,
+    helpers_preapply_operations:
+        Option<Handler<ProtocolRpcRequest, Result<HelpersPreapplyResponse, HelpersPreapplyError>>>,
+    helpers_preapply_block: Option<
+        Handler<HelpersPreapplyBlockRequest, Result<HelpersPreapplyResponse, HelpersPreapplyError>>,
+    >,
+    compute_path:
+        Option<Handler<ComputePathRequest, Result<ComputePathResponse, ComputePathError>>>,
+    genesis_commit_hash: Option<ContextHash>,
+    genesis_result_data: Option<CommitGenesisResult>,
+    supported_protocols: Vec<ProtocolHash>,
+
+    /// All received apply block requests (shared by all clones of script)
+    apply_block_requests: Arc<Mutex<Vec<ApplyBlockRequest>>>,
+}
+
+impl MockProtocolScript {
+    pub fn with_apply_block<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&ApplyBlockRequest) -> Result<ApplyBlockResponse, ApplyBlockError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.apply_block = Some(Arc::new(handler));
+        self
+    }
+
+    /// Context actions sent to the event server (if configured) before apply block response
+    pub fn with_apply_block_context_actions<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&ApplyBlockRequest) -> Vec<ContextAction> + Send + Sync + 'static,
+    {
+        self.apply_block_context_actions = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn with_begin_application<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&BeginApplicationRequest) -> Result<BeginApplicationResponse, BeginApplicationError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.begin_application = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn with_begin_construction<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&BeginConstructionRequest) -> Result<PrevalidatorWrapper, BeginConstructionError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.begin_construction = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn with_validate_operation<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(
+                &ValidateOperationRequest,
+            ) -> Result<ValidateOperationResponse, ValidateOperationError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.validate_operation = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn with_call_protocol_rpc<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&ProtocolRpcRequest) -> Result<ProtocolRpcResponse, ProtocolRpcError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.call_protocol_rpc = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn with_helpers_preapply_operations<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&ProtocolRpcRequest) -> Result<HelpersPreapplyResponse, HelpersPreapplyError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.helpers_preapply_operations = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn with_helpers_preapply_block<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(
+                &HelpersPreapplyBlockRequest,
+            ) -> Result<HelpersPreapplyResponse, HelpersPreapplyError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.helpers_preapply_block = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn with_compute_path<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&ComputePathRequest) -> Result<ComputePathResponse, ComputePathError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.compute_path = Some(Arc::new(handler));
+        self
+    }
+
+    /// Context hash returned, when context is initialized with `commit_genesis`
+    pub fn with_genesis_commit_hash(mut self, genesis_commit_hash: ContextHash) -> Self {
+        self.genesis_commit_hash = Some(genesis_commit_hash);
+        self
+    }
+
+    pub fn with_genesis_result_data(mut self, genesis_result_data: CommitGenesisResult) -> Self {
+        self.genesis_result_data = Some(genesis_result_data);
+        self
+    }
+
+    /// Protocols reported in handshake and context initialization
+    pub fn with_supported_protocols(mut self, supported_protocols: Vec<ProtocolHash>) -> Self {
+        self.supported_protocols = supported_protocols;
+        self
+    }
+
+    /// Returns all apply block requests received by runners with this script (in order of arrival)
+    pub fn apply_block_requests(&self) -> Vec<ApplyBlockRequest> {
+        self.apply_block_requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_default()
+    }
+
+    /// Registers script for [`MockProtocolRunner`] and returns fake executable path,
+    /// which should be used in [`ProtocolEndpointConfiguration`] of the pool.
+    pub fn register(self) -> PathBuf {
+        let executable_path = PathBuf::from(format!(
+            "mock-protocol-runner-{}",
+            REGISTERED_SCRIPTS_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        if let Ok(mut scripts) = REGISTERED_SCRIPTS.lock() {
+            scripts.insert(executable_path.clone(), self);
+        }
+        executable_path
+    }
+
+    fn registered(executable_path: &Path) -> Option<MockProtocolScript> {
+        REGISTERED_SCRIPTS
+            .lock()
+            .ok()
+            .and_then(|scripts| scripts.get(executable_path).cloned())
+    }
+}
+
+fn not_scripted(call: &str) -> String {
+    format!("mock protocol runner: '{}' is not scripted", call)
+}
+
+/// [`ProtocolApi`] driven by [`MockProtocolScript`] of the current thread, see [`MockProtocolApi::serve`]
+pub struct MockProtocolApi;
+
+impl MockProtocolApi {
+    /// Connects to the node (and to the event server, if `evt_socket_path` is set)
+    /// and processes protocol commands with `script` until shutdown.
+    pub fn serve<P: AsRef<Path>>(
+        script: MockProtocolScript,
+        cmd_socket_path: P,
+        evt_socket_path: Option<&Path>,
+        log: &Logger,
+    ) -> Result<(), IpcError> {
+        Self::serve_killable(
+            script,
+            cmd_socket_path,
+            evt_socket_path,
+            &KillSwitch::default(),
+            log,
+        )
+    }
+
+    /// Like [`MockProtocolApi::serve`], but stops, when `kill_switch` is killed (like killed process)
+    fn serve_killable<P: AsRef<Path>>(
+        script: MockProtocolScript,
+        cmd_socket_path: P,
+        evt_socket_path: Option<&Path>,
+        kill_switch: &KillSwitch,
+        log: &Logger,
+    ) -> Result<(), IpcError> {
+        let events = match evt_socket_path {
+            Some(evt_socket_path) => {
+                let ipc_client: IpcClient<NoopMessage, ContextAction> =
+                    IpcClient::new(evt_socket_path);
+                let (_, tx) = ipc_client.connect()?;
+                Some(tx)
+            }
+            None => None,
+        };
+        let ipc_client: IpcClient<ProtocolMessage, NodeMessage> = IpcClient::new(cmd_socket_path);
+        let (rx, tx) = ipc_client.connect()?;
+        if !kill_switch.arm(rx.closer()?) {
+            // killed before connected
+            return Ok(());
+        }
+
+        SERVED.with(|served| {
+            *served.borrow_mut() = Some(MockProtocolRunnerState { script, events });
+        });
+
+        let result = serve_protocol_commands::<Self, _>(rx, tx, log, |log| {
+            // like a real protocol runner, notify context listener, that we are finished
+            if let Err(e) = Self::send_context_actions(vec![ContextAction::Shutdown]) {
+                warn!(log, "Failed to send shutdown to event server"; "reason" => format!("{}", e));
+            }
+        });
+
+        SERVED.with(|served| served.borrow_mut().take());
+        result
+    }
+
+    fn script() -> MockProtocolScript {
+        SERVED.with(|served| {
+            served
+                .borrow()
+                .as_ref()
+                .map(|state| state.script.clone())
+                .expect("MockProtocolApi can be used only by MockProtocolApi::serve")
+        })
+    }
+
+    fn send_context_actions(actions: Vec<ContextAction>) -> Result<(), IpcError> {
+        SERVED.with(|served| {
+            if let Some(MockProtocolRunnerState {
+                events: Some(events),
+                ..
+            }) = served.borrow_mut().as_mut()
+            {
+                for action in &actions {
+                    events.send(action)?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+impl ProtocolApi for MockProtocolApi {
+    fn apply_block(request: ApplyBlockRequest) -> Result<ApplyBlockResponse, ApplyBlockError> {
+        let script = Self::script();
+        if let Ok(mut requests) = script.apply_block_requests.lock() {
+            requests.push(request.clone());
+        }
+
+        if let Some(context_actions) = &script.apply_block_context_actions {
+            Self::send_context_actions(context_actions(&request)).map_err(|e| {
+                ApplyBlockError::FailedToApplyBlock {
+                    message: format!(
+                        "mock protocol runner: failed to send context actions: {}",
+                        e
+                    ),
+                }
+            })?;
+        }
+
+        match &script.apply_block {
+            Some(apply_block) => apply_block(&request),
+            None => Err(ApplyBlockError::FailedToApplyBlock {
+                message: not_scripted("apply_block"),
+            }),
+        }
+    }
+
+    fn begin_application(
+        request: BeginApplicationRequest,
+    ) -> Result<BeginApplicationResponse, BeginApplicationError> {
+        match &Self::script().begin_application {
+            Some(begin_application) => begin_application(&request),
+            None => Err(BeginApplicationError::FailedToBeginApplication {
+                message: not_scripted("begin_application"),
+            }),
+        }
+    }
+
+    fn begin_construction(
+        request: BeginConstructionRequest,
+    ) -> Result<PrevalidatorWrapper, BeginConstructionError> {
+        match &Self::script().begin_construction {
+            Some(begin_construction) => begin_construction(&request),
+            None => Err(BeginConstructionError::FailedToBeginConstruction {
+                message: not_scripted("begin_construction"),
+            }),
+        }
+    }
+
+    fn validate_operation(
+        request: ValidateOperationRequest,
+    ) -> Result<ValidateOperationResponse, ValidateOperationError> {
+        match &Self::script().validate_operation {
+            Some(validate_operation) => validate_operation(&request),
+            None => Err(ValidateOperationError::FailedToValidateOperation {
+                message: not_scripted("validate_operation"),
+            }),
+        }
+    }
+
+    fn call_protocol_rpc(
+        request: ProtocolRpcRequest,
+    ) -> Result<ProtocolRpcResponse, ProtocolRpcError> {
+        match &Self::script().call_protocol_rpc {
+            Some(call_protocol_rpc) => call_protocol_rpc(&request),
+            None => Err(ProtocolRpcError::FailedToCallProtocolRpc(not_scripted(
+                "call_protocol_rpc",
+            ))),
+        }
+    }
+
+    fn helpers_preapply_operations(
+        request: ProtocolRpcRequest,
+    ) -> Result<HelpersPreapplyResponse, HelpersPreapplyError> {
+        match &Self::script().helpers_preapply_operations {
+            Some(helpers_preapply_operations) => helpers_preapply_operations(&request),
+            None => Err(HelpersPreapplyError::FailedToCallProtocolRpc {
+                message: not_scripted("helpers_preapply_operations"),
+            }),
+        }
+    }
+
+    fn helpers_preapply_block(
+        request: HelpersPreapplyBlockRequest,
+    ) -> Result<HelpersPreapplyResponse, HelpersPreapplyError> {
+        match &Self::script().helpers_preapply_block {
+            Some(helpers_preapply_block) => helpers_preapply_block(&request),
+            None => Err(HelpersPreapplyError::FailedToCallProtocolRpc {
+                message: not_scripted("helpers_preapply_block"),
+            }),
+        }
+    }
+
+    fn change_runtime_configuration(
+        _: TezosRuntimeConfiguration,
+    ) -> Result<(), TezosRuntimeConfigurationError> {
+        Ok(())
+    }
+
+    fn init_protocol_context(
+        _: String,
+        _: GenesisChain,
+        _: ProtocolOverrides,
+        commit_genesis: bool,
+        _: bool,
+        _: bool,
+        _: Option<PatchContext>,
+    ) -> Result<InitProtocolContextResult, TezosStorageInitError> {
+        let script = Self::script();
+        let genesis_commit_hash = if commit_genesis {
+            match script.genesis_commit_hash {
+                Some(genesis_commit_hash) => Some(genesis_commit_hash),
+                None => {
+                    return Err(TezosStorageInitError::InitializeError {
+                        message: not_scripted("genesis_commit_hash"),
+                    })
+                }
+            }
+        } else {
+            None
+        };
+        Ok(InitProtocolContextResult {
+            supported_protocol_hashes: script.supported_protocols,
+            genesis_commit_hash,
+        })
+    }
+
+    fn genesis_result_data(
+        _: &ContextHash,
+        _: &ChainId,
+        _: &ProtocolHash,
+        _: u16,
+    ) -> Result<CommitGenesisResult, GetDataError> {
+        Self::script()
+            .genesis_result_data
+            .ok_or_else(|| GetDataError::ReadError {
+                message: not_scripted("genesis_result_data"),
+            })
+    }
+
+    fn compute_path(request: ComputePathRequest) -> Result<ComputePathResponse, ComputePathError> {
+        match &Self::script().compute_path {
+            Some(compute_path) => compute_path(&request),
+            None => Err(ComputePathError::PathError {
+                message: not_scripted("compute_path"),
+            }),
+        }
+    }
+
+    fn assert_encoding_for_protocol_data(
+        _: ProtocolHash,
+        _: Vec<u8>,
+    ) -> Result<(), ProtocolDataError> {
+        Ok(())
+    }
+
+    fn supported_protocols() -> Vec<ProtocolHash> {
+        Self::script().supported_protocols
+    }
+}
+
+/// Protocol runner, which runs [`MockProtocolApi`] in a thread instead of sub-process.
+///
+/// Script is found by executable path of [`ProtocolEndpointConfiguration`], see [`MockProtocolScript::register`].
+#[derive(Clone)]
+pub struct MockProtocolRunner {
+    script: MockProtocolScript,
+    sock_cmd_path: PathBuf,
+    sock_evt_path: Option<PathBuf>,
+    endpoint_name: String,
+}
+
+/// Stops serving thread of [`MockProtocolApi`] by closing its command channel
+#[derive(Default)]
+struct KillSwitch(Mutex<KillSwitchState>);
+
+#[derive(Default)]
+struct KillSwitchState {
+    killed: bool,
+    closer: Option<IpcChannelCloser>,
+}
+
+impl KillSwitch {
+    /// Returns false, if already killed (and closes the channel)
+    fn arm(&self, closer: IpcChannelCloser) -> bool {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if state.killed {
+            let _ = closer.close();
+            false
+        } else {
+            state.closer = Some(closer);
+            true
+        }
+    }
+
+    fn kill(&self) {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        state.killed = true;
+        if let Some(closer) = state.closer.take() {
+            let _ = closer.close();
+        }
+    }
+}
+
+/// Running [`MockProtocolRunner`] thread
+pub struct MockProtocolRunnerThread {
+    thread: Option<JoinHandle<Result<(), IpcError>>>,
+    finished: Arc<AtomicBool>,
+    result: Option<Result<(), String>>,
+    kill_switch: Arc<KillSwitch>,
+    killed: bool,
+}
+
+impl MockProtocolRunnerThread {
+    fn is_finished(&mut self) -> bool {
+        if self.result.is_none() && self.finished.load(Ordering::Acquire) {
+            if let Some(thread) = self.thread.take() {
+                self.result = Some(match thread.join() {
+                    Ok(Ok(())) => Ok(()),
+                    Ok(Err(e)) => Err(format!("{}", e)),
+                    Err(_) => Err("mock protocol runner panicked".to_string()),
+                });
+            }
+        }
+        self.result.is_some()
+    }
+}
+
+impl ProtocolRunner for MockProtocolRunner {
+    type Subprocess = MockProtocolRunnerThread;
+    const PROCESS_TERMINATE_WAIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+    fn new(
+        configuration: ProtocolEndpointConfiguration,
+        sock_cmd_path: &Path,
+        endpoint_name: String,
+    ) -> Self {
+        MockProtocolRunner {
+            script: MockProtocolScript::registered(configuration.executable_path())
+                .unwrap_or_default(),
+            sock_cmd_path: sock_cmd_path.to_path_buf(),
+            sock_evt_path: configuration.event_server_path.clone(),
+            endpoint_name,
+        }
+    }
+
+    fn spawn(&self) -> Result<Self::Subprocess, ProtocolRunnerError> {
+        let finished = Arc::new(AtomicBool::new(false));
+        let kill_switch = Arc::new(KillSwitch::default());
+        let thread = {
+            let runner = self.clone();
+            let finished = finished.clone();
+            let kill_switch = kill_switch.clone();
+            thread::Builder::new()
+                .name(format!("mock-{}", self.endpoint_name))
+                .spawn(move || {
+                    let log = Logger::root(Discard, o!("endpoint" => runner.endpoint_name));
+                    let result = MockProtocolApi::serve_killable(
+                        runner.script,
+                        &runner.sock_cmd_path,
+                        runner.sock_evt_path.as_deref(),
+                        &kill_switch,
+                        &log,
+                    );
+                    finished.store(true, Ordering::Release);
+                    result
+                })
+                .map_err(|e| ProtocolRunnerError::SpawnError { reason: e })?
+        };
+        Ok(MockProtocolRunnerThread {
+            thread: Some(thread),
+            finished,
+            result: None,
+            kill_switch,
+            killed: false,
+        })
+    }
+
+    fn wait_and_terminate_ref(
+        process: &mut Self::Subprocess,
+        wait_timeout: Duration,
+    ) -> Result<(), ProtocolRunnerError> {
+        let start = Instant::now();
+        while !process.is_finished() {
+            if start.elapsed() > wait_timeout {
+                return Self::kill(process);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+
+    fn is_running(process: &mut Self::Subprocess) -> bool {
+        !process.is_finished()
+    }
+
+    /// Closes command channel of the thread, so it stops like killed process
+    /// (thread blocked by scripted handler finishes, when the handler returns)
+    fn kill(process: &mut Self::Subprocess) -> Result<(), ProtocolRunnerError> {
+        if !process.is_finished() {
+            process.killed = true;
+            process.kill_switch.kill();
+
+            let start = Instant::now();
+            while !process.is_finished() && start.elapsed() < Self::PROCESS_TERMINATE_WAIT_TIMEOUT {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        Ok(())
+    }
+
+    fn exit_report(process: &mut Self::Subprocess) -> Option<ProtocolRunnerExit> {
+        if process.killed {
+            return Some(ProtocolRunnerExit {
+                exit_code: None,
+                signal: Some(9),
+                stderr: Vec::new(),
+            });
+        }
+        if !process.is_finished() {
+            return None;
+        }
+        match &process.result {
+            Some(Err(e)) => Some(ProtocolRunnerExit {
+                exit_code: Some(1),
+                signal: None,
+                stderr: vec![e.clone()],
+            }),
+            _ => Some(ProtocolRunnerExit {
+                exit_code: Some(0),
+                signal: None,
+                stderr: Vec::new(),
+            }),
+        }
+    }
+
+    fn resident_memory(_: &mut Self::Subprocess) -> Option<u64> {
+        None
+    }
+}
@@ -2,17 +2,26 @@
 // SPDX-License-Identifier: MIT
 
 use std::fmt::Formatter;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::{error, fmt};
 
 use failure::_core::marker::PhantomData;
 use r2d2::{CustomizeConnection, HandleError, ManageConnection};
+use serde::Serialize;
 use slog::{debug, error, info, o, warn, Logger};
 
+use crypto::hash::ProtocolHash;
 use ipc::IpcError;
-
-use crate::runner::{ProtocolRunner, ProtocolRunnerError};
+use tezos_api::ffi::{
+    BeginApplicationRequest, BeginApplicationResponse, BeginConstructionRequest,
+    ComputePathRequest, ComputePathResponse, InitProtocolContextResult, PatchContext,
+    PrevalidatorWrapper, ProtocolRpcRequest, ProtocolRpcResponse, RustBytes,
+    ValidateOperationRequest, ValidateOperationResponse,
+};
+
+use crate::runner::{ProtocolRunner, ProtocolRunnerError, ProtocolRunnerExit};
 use crate::service::{ProtocolController, ProtocolRunnerEndpoint, ProtocolServiceError};
 use crate::ProtocolEndpointConfiguration;
 
@@ -30,6 +39,17 @@ pub enum PoolError {
         message: String,
         error: ProtocolServiceError,
     },
+    AcceptError {
+        reason: String,
+        error: ProtocolServiceError,
+    },
+    RunnerRecycled {
+        recycle: ProtocolRunnerRecycle,
+    },
+    RestartBackoff {
+        consecutive_failures: u32,
+        retry_in: Duration,
+    },
 }
 
 impl std::error::Error for PoolError {}
@@ -40,89 +60,172 @@ impl fmt::Display for PoolError {
             PoolError::SpawnRunnerError { ref error } => write!(f, "Create pool connection error - fail to spawn sub-process, reason: {:?}", error),
             PoolError::IpcError { ref reason, ref error } => write!(f, "Create pool connection IPC error - {}, error: {:?}", reason, error),
             PoolError::InitContextError { ref message, ref error } => write!(f, "Create pool connection error - fail to initialize context, message: {}, reason: {:?}", message, error),
+            PoolError::AcceptError { ref reason, ref error } => write!(f, "Create pool connection error - {}, error: {}", reason, error),
+            PoolError::RunnerRecycled { ref recycle } => write!(f, "Protocol runner was recycled - {}", recycle),
+            PoolError::RestartBackoff { consecutive_failures, retry_in } => write!(f, "Create pool connection error - protocol runners failed recently (consecutive failures: {}), next start is allowed in {:?}", consecutive_failures, retry_in),
         }
     }
 }
 
-/// Protocol runner sub-process wrapper which acts as connection
-pub struct ProtocolRunnerConnection<Runner: ProtocolRunner> {
-    pub api: ProtocolController,
-    subprocess: Runner::Subprocess,
-    log: Logger,
-    pub name: String,
-
-    /// Indicates that we want to release this connection on return to pool (used for gracefull shutdown)
-    release_on_return_to_pool: bool,
+/// Failure counters of the protocol runners of one pool
+#[derive(Default, Debug)]
+pub struct ProtocolRunnerMetrics {
+    crashes: AtomicUsize,
+    timeouts: AtomicUsize,
+    failed_starts: AtomicUsize,
+    restarts: AtomicUsize,
+    retried_calls: AtomicUsize,
+    recycled: AtomicUsize,
+    last_exit: Mutex<Option<ProtocolRunnerExit>>,
+    last_recycle: Mutex<Option<ProtocolRunnerRecycle>>,
 }
 
-impl<Runner: ProtocolRunner + 'static> ProtocolRunnerConnection<Runner> {
-    pub fn is_valid(&mut self) -> Result<(), PoolError> {
-        // TODO: check if unix socket is connected?
-        Ok(())
-    }
+/// Describes why protocol runner was recycled (replaced by a new one)
+#[derive(Serialize, Debug, Clone)]
+pub struct ProtocolRunnerRecycle {
+    /// Endpoint name of the recycled protocol runner
+    pub endpoint: String,
+    /// Resident memory of protocol runner at the time of recycle
+    pub resident_memory_bytes: u64,
+    /// Configured limit, which was exceeded
+    pub limit_bytes: u64,
+}
 
-    fn has_broken(&mut self) -> bool {
-        if self.release_on_return_to_pool {
-            return true;
-        }
-        let is_subprocess_running = Runner::is_running(&mut self.subprocess);
-        !is_subprocess_running
+impl fmt::Display for ProtocolRunnerRecycle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "endpoint: {}, resident memory: {} bytes exceeded limit: {} bytes",
+            self.endpoint, self.resident_memory_bytes, self.limit_bytes
+        )
     }
+}
 
-    pub fn terminate_subprocess(&mut self) {
-        // try shutdown gracefully
-        if let Err(e) = self.api.shutdown() {
-            warn!(self.log, "Failed to shutdown protocol runner gracefully"; "reason" => e);
-        };
+/// Snapshot of [`ProtocolRunnerMetrics`]
+#[derive(Serialize, Debug, Clone)]
+pub struct ProtocolRunnerStats {
+    /// Protocol runners, which exited unexpectedly
+    pub crashes: usize,
+    /// Protocol runners, which did not respond in time (and were killed)
+    pub timeouts: usize,
+    /// Protocol runners, which failed to start (spawn/handshake/context initialization)
+    pub failed_starts: usize,
+    /// Protocol runners restarted by connection after crash/timeout
+    pub restarts: usize,
+    /// Calls, which were transparently retried on restarted protocol runner
+    pub retried_calls: usize,
+    /// Protocol runners recycled because of exceeded resident memory
+    pub recycled: usize,
+    /// The last crashed protocol runner
+    pub last_exit: Option<ProtocolRunnerExit>,
+    /// The last recycled protocol runner
+    pub last_recycle: Option<ProtocolRunnerRecycle>,
+}
 
-        // try terminate sub-process (if running)
-        if let Err(e) = Runner::wait_and_terminate_ref(
-            &mut self.subprocess,
-            Runner::PROCESS_TERMINATE_WAIT_TIMEOUT,
-        ) {
-            warn!(self.log, "Failed to terminate/kill protocol runner"; "reason" => e);
+impl ProtocolRunnerMetrics {
+    pub fn stats(&self) -> ProtocolRunnerStats {
+        ProtocolRunnerStats {
+            crashes: self.crashes.load(Ordering::Acquire),
+            timeouts: self.timeouts.load(Ordering::Acquire),
+            failed_starts: self.failed_starts.load(Ordering::Acquire),
+            restarts: self.restarts.load(Ordering::Acquire),
+            retried_calls: self.retried_calls.load(Ordering::Acquire),
+            recycled: self.recycled.load(Ordering::Acquire),
+            last_exit: self
+                .last_exit
+                .lock()
+                .map(|last_exit| last_exit.clone())
+                .unwrap_or(None),
+            last_recycle: self
+                .last_recycle
+                .lock()
+                .map(|last_recycle| last_recycle.clone())
+                .unwrap_or(None),
         }
     }
-
-    /// Mark connection as "destroy" when return back to pool
-    pub fn set_release_on_return_to_pool(&mut self) {
-        self.release_on_return_to_pool = true;
-    }
 }
 
-/// Connection manager, which creates new connections:
-/// - runs new sub-process
-/// - starts IPC accept
-pub struct ProtocolRunnerManager<Runner: ProtocolRunner> {
+/// Starts protocol runners for the pool and keeps track of their failures.
+///
+/// Supervisor is shared by [`ProtocolRunnerManager`] and all its connections, which restart crashed protocol runners.
+struct ProtocolRunnerSupervisor<Runner: ProtocolRunner> {
     pool_name: String,
     pool_name_counter: AtomicUsize,
     pool_connection_timeout: Duration,
 
-    pub endpoint_cfg: ProtocolEndpointConfiguration,
-    pub log: Logger,
+    /// Failures (crashes/timeouts/failed starts) since the last healthy protocol runner, drives restart backoff
+    consecutive_failures: AtomicU32,
+    /// Protocol runner cannot be started before this time (restart backoff after the last failure)
+    next_start_allowed_at: Mutex<Option<Instant>>,
+    metrics: Arc<ProtocolRunnerMetrics>,
+
+    endpoint_cfg: ProtocolEndpointConfiguration,
+    error_handler: SlogErrorHandler,
+    log: Logger,
     _phantom: PhantomData<Runner>,
 }
 
-impl<Runner: ProtocolRunner + 'static> ProtocolRunnerManager<Runner> {
+impl<Runner: ProtocolRunner + 'static> ProtocolRunnerSupervisor<Runner> {
     const MIN_ACCEPT_TIMEOUT: Duration = Duration::from_secs(3);
+    const RESTART_BACKOFF_BASE: Duration = Duration::from_millis(500);
+    const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+    /// Exponential backoff before the next start of protocol runner
+    fn restart_backoff(consecutive_failures: u32) -> Duration {
+        let exponent = std::cmp::min(consecutive_failures.saturating_sub(1), 16);
+        std::cmp::min(
+            Self::RESTART_BACKOFF_BASE * 2u32.pow(exponent),
+            Self::RESTART_BACKOFF_MAX,
+        )
+    }
 
-    pub fn new(
-        pool_name: String,
-        pool_connection_timeout: Duration,
-        endpoint_cfg: ProtocolEndpointConfiguration,
-        log: Logger,
-    ) -> Self {
-        Self {
-            pool_name,
-            pool_name_counter: AtomicUsize::new(1),
-            pool_connection_timeout,
-            endpoint_cfg,
-            log,
-            _phantom: PhantomData,
+    /// Spawns new protocol runner sub-process and accepts its IPC connection,
+    /// if protocol runners failed recently, fails fast until the restart backoff elapses
+    /// (so the caller, e.g. r2d2 `connect`, is not blocked).
+    fn start_runner(&self) -> Result<(String, ProtocolController, Runner::Subprocess), PoolError> {
+        let next_start_allowed_at = self
+            .next_start_allowed_at
+            .lock()
+            .map(|next_start_allowed_at| *next_start_allowed_at)
+            .unwrap_or(None);
+        if let Some(next_start_allowed_at) = next_start_allowed_at {
+            let now = Instant::now();
+            if now < next_start_allowed_at {
+                let consecutive_failures = self.consecutive_failures.load(Ordering::Acquire);
+                let retry_in = next_start_allowed_at - now;
+                warn!(self.log, "Protocol runners failed recently, new one is not started yet";
+                                "consecutive_failures" => consecutive_failures,
+                                "retry_in" => format!("{:?}", retry_in));
+                return Err(PoolError::RestartBackoff {
+                    consecutive_failures,
+                    retry_in,
+                });
+            }
         }
+
+        let result = self.spawn_and_accept();
+        if result.is_err() {
+            self.metrics.failed_starts.fetch_add(1, Ordering::AcqRel);
+            self.register_failure();
+        }
+        result
     }
 
-    pub fn create_connection(&self) -> Result<ProtocolRunnerConnection<Runner>, PoolError> {
+    /// Postpones the next start of protocol runner by exponential backoff,
+    /// the first failure after healthy protocol runner is restarted immediately (so the failed call can be retried).
+    fn register_failure(&self) {
+        let consecutive_failures = self.consecutive_failures.fetch_add(1, Ordering::AcqRel) + 1;
+        if consecutive_failures > 1 {
+            if let Ok(mut next_start_allowed_at) = self.next_start_allowed_at.lock() {
+                *next_start_allowed_at =
+                    Some(Instant::now() + Self::restart_backoff(consecutive_failures - 1));
+            }
+        }
+    }
+
+    fn spawn_and_accept(
+        &self,
+    ) -> Result<(String, ProtocolController, Runner::Subprocess), PoolError> {
         let endpoint_name = format!(
             "{}_{:?}",
             &self.pool_name,
@@ -170,22 +273,371 @@ impl<Runner: ProtocolRunner + 'static> ProtocolRunnerManager<Runner> {
                 ) {
                     warn!(self.log, "Failed to terminate/kill protocol runner (create connection)"; "reason" => e);
                 }
-                return Err(PoolError::IpcError {
+                return Err(PoolError::AcceptError {
                     reason: "fail to accept IPC for sub-process".to_string(),
                     error: e,
                 });
             }
         };
 
-        debug!(self.log, "Connection for protocol runner was created successfully"; "endpoint" => endpoint_name.clone());
-        Ok(ProtocolRunnerConnection {
+        debug!(self.log, "Connection for protocol runner was created successfully"; "endpoint" => endpoint_name.clone(),
+                         "runner_version" => api.runner().version.to_string(),
+                         "runner_build_hash" => api.runner().build_hash.clone());
+        Ok((endpoint_name, api, subprocess))
+    }
+
+    fn register_crash(&self, endpoint_name: &str, exit: Option<ProtocolRunnerExit>) {
+        self.metrics.crashes.fetch_add(1, Ordering::AcqRel);
+        self.register_failure();
+        match exit {
+            Some(exit) => {
+                error!(self.log, "Protocol runner crashed"; "endpoint" => endpoint_name, "exit" => exit.to_string());
+                if let Ok(mut last_exit) = self.metrics.last_exit.lock() {
+                    *last_exit = Some(exit);
+                }
+            }
+            None => error!(self.log, "Protocol runner crashed"; "endpoint" => endpoint_name),
+        }
+    }
+
+    fn register_timeout(&self, endpoint_name: &str) {
+        self.metrics.timeouts.fetch_add(1, Ordering::AcqRel);
+        self.register_failure();
+        error!(self.log, "Protocol runner did not respond in time (so kill sub-process)"; "endpoint" => endpoint_name);
+    }
+
+    /// Recycle is not a failure, so it does not affect restart backoff
+    fn register_recycle(&self, recycle: ProtocolRunnerRecycle) {
+        self.metrics.recycled.fetch_add(1, Ordering::AcqRel);
+        if let Ok(mut last_recycle) = self.metrics.last_recycle.lock() {
+            *last_recycle = Some(recycle.clone());
+        }
+        self.error_handler
+            .handle_error(PoolError::RunnerRecycled { recycle });
+    }
+
+    /// Protocol runner works fine, so next restart does not need to wait
+    fn register_healthy(&self) {
+        self.consecutive_failures.store(0, Ordering::Release);
+        if let Ok(mut next_start_allowed_at) = self.next_start_allowed_at.lock() {
+            *next_start_allowed_at = None;
+        }
+    }
+}
+
+/// How was context of protocol runner initialized, so we can initialize it again after restart
+#[derive(Clone)]
+enum ContextInitialization {
+    Readonly,
+    Writeable { patch_context: Option<PatchContext> },
+}
+
+/// Protocol runner sub-process wrapper which acts as connection
+pub struct ProtocolRunnerConnection<Runner: ProtocolRunner + 'static> {
+    pub api: ProtocolController,
+    subprocess: Runner::Subprocess,
+    supervisor: Arc<ProtocolRunnerSupervisor<Runner>>,
+    context: Option<ContextInitialization>,
+    log: Logger,
+    pub name: String,
+
+    /// Indicates that we want to release this connection on return to pool (used for gracefull shutdown)
+    release_on_return_to_pool: bool,
+    /// Indicates that crash of current sub-process was already registered
+    crash_registered: bool,
+}
+
+impl<Runner: ProtocolRunner + 'static> ProtocolRunnerConnection<Runner> {
+    fn new(
+        supervisor: Arc<ProtocolRunnerSupervisor<Runner>>,
+        (name, api, subprocess): (String, ProtocolController, Runner::Subprocess),
+    ) -> Self {
+        Self {
             api,
             subprocess,
-            log: self.log.new(o!("endpoint" => endpoint_name.clone())),
-            name: endpoint_name,
+            log: supervisor.log.new(o!("endpoint" => name.clone())),
+            supervisor,
+            context: None,
+            name,
             release_on_return_to_pool: false,
+            crash_registered: false,
+        }
+    }
+
+    pub fn is_valid(&mut self) -> Result<(), PoolError> {
+        // TODO: check if unix socket is connected?
+        Ok(())
+    }
+
+    fn has_broken(&mut self) -> bool {
+        let is_subprocess_running = Runner::is_running(&mut self.subprocess);
+        if !is_subprocess_running {
+            self.register_crash();
+            return true;
+        }
+        if self.release_on_return_to_pool {
+            return true;
+        }
+        self.exceeds_resident_memory_limit()
+    }
+
+    /// Checks resident memory of idle protocol runner, if it is over the configured limit,
+    /// connection is released and pool replaces it with a new protocol runner.
+    fn exceeds_resident_memory_limit(&mut self) -> bool {
+        let limit_bytes = match self
+            .supervisor
+            .endpoint_cfg
+            .limits()
+            .recycle_resident_memory_bytes
+        {
+            Some(limit_bytes) => limit_bytes,
+            None => return false,
+        };
+        match Runner::resident_memory(&mut self.subprocess) {
+            Some(resident_memory_bytes) if resident_memory_bytes > limit_bytes => {
+                self.supervisor.register_recycle(ProtocolRunnerRecycle {
+                    endpoint: self.name.clone(),
+                    resident_memory_bytes,
+                    limit_bytes,
+                });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn register_crash(&mut self) {
+        if !self.crash_registered {
+            self.crash_registered = true;
+            let exit = Runner::exit_report(&mut self.subprocess);
+            self.supervisor.register_crash(&self.name, exit);
+        }
+    }
+
+    pub fn terminate_subprocess(&mut self) {
+        // try shutdown gracefully
+        if let Err(e) = self.api.shutdown() {
+            warn!(self.log, "Failed to shutdown protocol runner gracefully"; "reason" => e);
+        };
+
+        // try terminate sub-process (if running)
+        if let Err(e) = Runner::wait_and_terminate_ref(
+            &mut self.subprocess,
+            Runner::PROCESS_TERMINATE_WAIT_TIMEOUT,
+        ) {
+            warn!(self.log, "Failed to terminate/kill protocol runner"; "reason" => e);
+        }
+    }
+
+    /// Mark connection as "destroy" when return back to pool
+    pub fn set_release_on_return_to_pool(&mut self) {
+        self.release_on_return_to_pool = true;
+    }
+
+    /// Initialize protocol environment from default configuration (readonly),
+    /// context is initialized again, if protocol runner is restarted.
+    pub fn init_protocol_for_read(
+        &mut self,
+    ) -> Result<InitProtocolContextResult, ProtocolServiceError> {
+        let result = self.api.init_protocol_for_read()?;
+        self.context = Some(ContextInitialization::Readonly);
+        self.supervisor.register_healthy();
+        Ok(result)
+    }
+
+    /// Initialize protocol environment from default configuration (writeable),
+    /// context is initialized again (without genesis commit), if protocol runner is restarted.
+    pub fn init_protocol_for_write(
+        &mut self,
+        commit_genesis: bool,
+        patch_context: &Option<PatchContext>,
+    ) -> Result<InitProtocolContextResult, ProtocolServiceError> {
+        let result = self
+            .api
+            .init_protocol_for_write(commit_genesis, patch_context)?;
+        self.context = Some(ContextInitialization::Writeable {
+            patch_context: patch_context.clone(),
+        });
+        self.supervisor.register_healthy();
+        Ok(result)
+    }
+
+    /// Call protocol rpc, retried once on restarted protocol runner, if protocol runner crashed/timeouted
+    pub fn call_protocol_rpc(
+        &mut self,
+        request: ProtocolRpcRequest,
+    ) -> Result<ProtocolRpcResponse, ProtocolServiceError> {
+        self.call_with_restart(|api| api.call_protocol_rpc(request.clone()))
+    }
+
+    /// Begin construction, retried once on restarted protocol runner, if protocol runner crashed/timeouted
+    pub fn begin_construction(
+        &mut self,
+        request: BeginConstructionRequest,
+    ) -> Result<PrevalidatorWrapper, ProtocolServiceError> {
+        self.call_with_restart(|api| api.begin_construction(request.clone()))
+    }
+
+    /// Begin application, retried once on restarted protocol runner, if protocol runner crashed/timeouted
+    pub fn begin_application(
+        &mut self,
+        request: BeginApplicationRequest,
+    ) -> Result<BeginApplicationResponse, ProtocolServiceError> {
+        self.call_with_restart(|api| api.begin_application(request.clone()))
+    }
+
+    /// Check encoding of protocol data, retried once on restarted protocol runner, if protocol runner crashed/timeouted
+    pub fn assert_encoding_for_protocol_data(
+        &mut self,
+        protocol_hash: ProtocolHash,
+        protocol_data: RustBytes,
+    ) -> Result<(), ProtocolServiceError> {
+        self.call_with_restart(|api| {
+            api.assert_encoding_for_protocol_data(protocol_hash.clone(), protocol_data.clone())
         })
     }
+
+    /// Validate operation, retried once on restarted protocol runner, if protocol runner crashed/timeouted
+    pub fn validate_operation(
+        &mut self,
+        request: ValidateOperationRequest,
+    ) -> Result<ValidateOperationResponse, ProtocolServiceError> {
+        self.call_with_restart(|api| api.validate_operation(request.clone()))
+    }
+
+    /// Compute path, retried once on restarted protocol runner, if protocol runner crashed/timeouted
+    pub fn compute_path(
+        &mut self,
+        request: ComputePathRequest,
+    ) -> Result<ComputePathResponse, ProtocolServiceError> {
+        self.call_with_restart(|api| api.compute_path(request.clone()))
+    }
+
+    /// Calls idempotent `call`, if it fails on IPC (protocol runner crashed or does not respond),
+    /// protocol runner is restarted and `call` is retried once.
+    ///
+    /// Restart backoff is reset only by call answered by protocol runner (even with protocol error).
+    fn call_with_restart<T, F>(&mut self, call: F) -> Result<T, ProtocolServiceError>
+    where
+        F: Fn(&ProtocolController) -> Result<T, ProtocolServiceError>,
+    {
+        let error = match call(&self.api) {
+            Err(ProtocolServiceError::IpcError { reason }) => reason,
+            result => {
+                self.supervisor.register_healthy();
+                return result;
+            }
+        };
+
+        if let IpcError::ReceiveMessageTimeouted = error {
+            self.supervisor.register_timeout(&self.name);
+        }
+
+        if let Err(e) = self.restart() {
+            warn!(self.log, "Failed to restart protocol runner"; "reason" => format!("{}", e));
+            self.set_release_on_return_to_pool();
+            return Err(ProtocolServiceError::IpcError { reason: error });
+        }
+
+        info!(self.log, "Retrying call on restarted protocol runner"; "reason" => format!("{}", error));
+        self.supervisor
+            .metrics
+            .retried_calls
+            .fetch_add(1, Ordering::AcqRel);
+        let result = call(&self.api);
+        if !matches!(result, Err(ProtocolServiceError::IpcError { .. })) {
+            self.supervisor.register_healthy();
+        }
+        result
+    }
+
+    /// Replaces crashed/stuck protocol runner with a new one and initializes its context the same way
+    fn restart(&mut self) -> Result<(), PoolError> {
+        if Runner::is_running(&mut self.subprocess) {
+            // running sub-process is stuck or out of sync, so there is no reason to wait for graceful shutdown
+            if let Err(e) = Runner::kill(&mut self.subprocess) {
+                warn!(self.log, "Failed to kill protocol runner"; "reason" => e);
+            }
+        } else {
+            self.register_crash();
+        }
+
+        let (name, api, subprocess) = self.supervisor.start_runner()?;
+        self.supervisor
+            .metrics
+            .restarts
+            .fetch_add(1, Ordering::AcqRel);
+        self.log = self.supervisor.log.new(o!("endpoint" => name.clone()));
+        self.name = name;
+        self.api = api;
+        self.subprocess = subprocess;
+        self.crash_registered = false;
+
+        let result = match &self.context {
+            Some(ContextInitialization::Readonly) => self
+                .api
+                .init_protocol_for_read()
+                .map(|_| ())
+                .map_err(|error| ("readonly_context", error)),
+            Some(ContextInitialization::Writeable { patch_context }) => self
+                .api
+                .init_protocol_for_write(false, patch_context)
+                .map(|_| ())
+                .map_err(|error| ("writeable_context", error)),
+            None => Ok(()),
+        };
+        if let Err((message, error)) = result {
+            self.supervisor.register_failure();
+            return Err(PoolError::InitContextError {
+                message: message.to_string(),
+                error,
+            });
+        }
+
+        info!(self.log, "Protocol runner was restarted");
+        Ok(())
+    }
+}
+
+/// Connection manager, which creates new connections:
+/// - runs new sub-process
+/// - starts IPC accept
+pub struct ProtocolRunnerManager<Runner: ProtocolRunner> {
+    supervisor: Arc<ProtocolRunnerSupervisor<Runner>>,
+}
+
+impl<Runner: ProtocolRunner + 'static> ProtocolRunnerManager<Runner> {
+    pub fn new(
+        pool_name: String,
+        pool_connection_timeout: Duration,
+        endpoint_cfg: ProtocolEndpointConfiguration,
+        metrics: Arc<ProtocolRunnerMetrics>,
+        error_handler: SlogErrorHandler,
+        log: Logger,
+    ) -> Self {
+        Self {
+            supervisor: Arc::new(ProtocolRunnerSupervisor {
+                pool_name,
+                pool_name_counter: AtomicUsize::new(1),
+                pool_connection_timeout,
+                consecutive_failures: AtomicU32::new(0),
+                next_start_allowed_at: Mutex::new(None),
+                metrics,
+                endpoint_cfg,
+                error_handler,
+                log,
+                _phantom: PhantomData,
+            }),
+        }
+    }
+
+    pub fn create_connection(&self) -> Result<ProtocolRunnerConnection<Runner>, PoolError> {
+        let runner = self.supervisor.start_runner()?;
+        Ok(ProtocolRunnerConnection::new(
+            self.supervisor.clone(),
+            runner,
+        ))
+    }
 }
 
 impl<Runner: ProtocolRunner + 'static> ManageConnection for ProtocolRunnerManager<Runner> {
@@ -214,7 +666,7 @@ impl<Runner: ProtocolRunner + 'static>
     for InitReadonlyContextProtocolRunnerConnectionCustomizer
 {
     fn on_acquire(&self, conn: &mut ProtocolRunnerConnection<Runner>) -> Result<(), PoolError> {
-        match conn.api.init_protocol_for_read() {
+        match conn.init_protocol_for_read() {
             Ok(_) => {
                 info!(conn.log, "Connection for protocol runner was successfully initialized with readonly context");
                 Ok(())
@@ -286,3 +738,79 @@ where
                        "pool_name" => self.1.clone());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use slog::{o, Discard, Level};
+
+    use tezos_api::environment::{TezosEnvironment, TEZOS_ENV};
+    use tezos_api::ffi::TezosRuntimeConfiguration;
+
+    use crate::mock::{MockProtocolRunner, MockProtocolScript};
+
+    use super::*;
+
+    #[test]
+    fn test_connect_fails_fast_during_restart_backoff() {
+        let log = Logger::root(Discard, o!());
+        let tezos_env = TEZOS_ENV
+            .get(&TezosEnvironment::Sandbox)
+            .expect("no environment configuration");
+        let manager = ProtocolRunnerManager::<MockProtocolRunner>::new(
+            "test_backoff".to_string(),
+            Duration::from_secs(5),
+            ProtocolEndpointConfiguration::new(
+                TezosRuntimeConfiguration {
+                    log_enabled: false,
+                    debug_mode: false,
+                    compute_context_action_tree_hashes: false,
+                },
+                tezos_env.clone(),
+                false,
+                std::env::temp_dir().as_path(),
+                MockProtocolScript::default().register().as_path(),
+                Level::Debug,
+                None,
+            ),
+            Arc::new(ProtocolRunnerMetrics::default()),
+            SlogErrorHandler::new(log.clone(), "test_backoff".to_string()),
+            log,
+        );
+        let supervisor = manager.supervisor.clone();
+        let mut connections = vec![];
+
+        // the first failure is restarted immediately
+        supervisor.register_crash("test_backoff_1", None);
+        connections.push(manager.connect().expect("restart after the first failure"));
+
+        // repeated failure postpones the next start, connect does not wait for it
+        supervisor.register_crash("test_backoff_2", None);
+        let started_at = Instant::now();
+        match manager.connect() {
+            Err(PoolError::RestartBackoff {
+                consecutive_failures,
+                retry_in,
+            }) => {
+                assert_eq!(2, consecutive_failures);
+                assert!(
+                    retry_in
+                        <= ProtocolRunnerSupervisor::<MockProtocolRunner>::RESTART_BACKOFF_BASE
+                );
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("protocol runner started during restart backoff"),
+        }
+        assert!(
+            started_at.elapsed()
+                < ProtocolRunnerSupervisor::<MockProtocolRunner>::RESTART_BACKOFF_BASE
+        );
+
+        // healthy protocol runner resets backoff
+        supervisor.register_healthy();
+        connections.push(manager.connect().expect("start after healthy runner"));
+
+        connections
+            .iter_mut()
+            .for_each(ProtocolRunnerConnection::terminate_subprocess);
+    }
+}
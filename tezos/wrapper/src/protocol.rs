@@ -71,4 +71,7 @@ pub trait ProtocolApi {
         protocol_hash: ProtocolHash,
         protocol_data: Vec<u8>,
     ) -> Result<(), ProtocolDataError>;
+
+    /// Protocols, which can be applied by this protocol runner (reported to node in handshake)
+    fn supported_protocols() -> Vec<ProtocolHash>;
 }
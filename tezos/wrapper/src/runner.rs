@@ -1,19 +1,26 @@
 // Copyright (c) SimpleStaking and Tezedge Contributors
 // SPDX-License-Identifier: MIT
 
-use std::io;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command};
+use std::process::{Child, ChildStderr, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::Duration;
 
 use failure::Fail;
 use nix::sys::signal;
 use nix::sys::signal::Signal;
 use nix::unistd::Pid;
+use serde::Serialize;
 use slog::Level;
 use wait_timeout::ChildExt;
 
-use crate::ProtocolEndpointConfiguration;
+use crate::{ProtocolEndpointConfiguration, ProtocolRunnerLimits};
 
 /// Errors generated by `protocol_runner`.
 #[derive(Fail, Debug)]
@@ -41,6 +48,105 @@ impl slog::Value for ProtocolRunnerError {
     }
 }
 
+/// Describes how protocol runner sub-process ended.
+#[derive(Serialize, Debug, Clone)]
+pub struct ProtocolRunnerExit {
+    /// Exit code, if sub-process exited by itself
+    pub exit_code: Option<i32>,
+    /// Signal, which terminated sub-process (e.g. 9 - SIGKILL, 11 - SIGSEGV)
+    pub signal: Option<i32>,
+    /// Last lines written by sub-process to stderr
+    pub stderr: Vec<String>,
+}
+
+impl ProtocolRunnerExit {
+    fn new(status: ExitStatus, stderr: Vec<String>) -> Self {
+        Self {
+            exit_code: status.code(),
+            signal: status.signal(),
+            stderr,
+        }
+    }
+}
+
+impl fmt::Display for ProtocolRunnerExit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.exit_code, self.signal) {
+            (Some(code), _) => write!(f, "exit code: {}", code)?,
+            (None, Some(signal)) => write!(f, "signal: {}", signal)?,
+            (None, None) => write!(f, "unknown exit status")?,
+        };
+        if !self.stderr.is_empty() {
+            write!(f, ", stderr:\n{}", self.stderr.join("\n"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Running protocol runner sub-process, which keeps the last lines of its stderr.
+pub struct ProtocolRunnerProcess {
+    process: Child,
+    stderr: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl ProtocolRunnerProcess {
+    /// How many last stderr lines are kept for the [`ProtocolRunnerExit`] report
+    const STDERR_TAIL_LINES: usize = 50;
+
+    fn new(mut process: Child) -> Self {
+        let stderr = Arc::new(Mutex::new(VecDeque::with_capacity(Self::STDERR_TAIL_LINES)));
+        if let Some(process_stderr) = process.stderr.take() {
+            Self::spawn_stderr_reader(process_stderr, stderr.clone());
+        }
+        Self { process, stderr }
+    }
+
+    /// Forwards sub-process stderr to our stderr (as it was before) and remembers last lines.
+    /// Thread finishes, when sub-process closes stderr (exits).
+    fn spawn_stderr_reader(process_stderr: ChildStderr, tail: Arc<Mutex<VecDeque<String>>>) {
+        let _ = thread::Builder::new()
+            .name("protocol-runner-stderr".to_string())
+            .spawn(move || {
+                for line in BufReader::new(process_stderr).lines() {
+                    let line = match line {
+                        Ok(line) => line,
+                        Err(_) => break,
+                    };
+                    eprintln!("{}", line);
+                    if let Ok(mut tail) = tail.lock() {
+                        if tail.len() == Self::STDERR_TAIL_LINES {
+                            tail.pop_front();
+                        }
+                        tail.push_back(line);
+                    }
+                }
+            });
+    }
+
+    /// Kills sub-process (SIGKILL)
+    pub fn kill(&mut self) -> io::Result<()> {
+        self.process.kill()
+    }
+
+    /// Returns resident set size of sub-process (VmRSS from `/proc/<pid>/status`) in bytes
+    pub fn resident_memory(&self) -> Option<u64> {
+        let status = fs::read_to_string(format!("/proc/{}/status", self.process.id())).ok()?;
+        status
+            .lines()
+            .find(|line| line.starts_with("VmRSS:"))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|kb| kb.parse::<u64>().ok())
+            .map(|kb| kb * 1024)
+    }
+
+    fn stderr_tail(&self) -> Vec<String> {
+        match self.stderr.lock() {
+            Ok(tail) => tail.iter().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
 /// Control protocol runner sub-process.
 #[derive(Clone)]
 pub struct ExecutableProtocolRunner {
@@ -49,11 +155,54 @@ pub struct ExecutableProtocolRunner {
     executable_path: PathBuf,
     endpoint_name: String,
     log_level: Level,
+    limits: ProtocolRunnerLimits,
 }
 
 impl ExecutableProtocolRunner {
+    /// Applies configured limits with `setrlimit` in the forked child, just before exec,
+    /// so the limits are enforced by kernel only for the protocol runner sub-process.
+    #[allow(unsafe_code)]
+    fn set_resource_limits(command: &mut Command, limits: &ProtocolRunnerLimits) {
+        // type of resource differs between platforms (glibc, macOS), so it is inferred from constants
+        let mut rlimits = Vec::new();
+        if let Some(max_virtual_memory_bytes) = limits.max_virtual_memory_bytes {
+            rlimits.push((libc::RLIMIT_AS, max_virtual_memory_bytes as libc::rlim_t));
+        }
+        if let Some(max_cpu_time) = limits.max_cpu_time {
+            // RLIMIT_CPU has granularity of seconds, so sub-second part is rounded up (at least 1s)
+            let mut max_cpu_secs = max_cpu_time.as_secs();
+            if max_cpu_time.subsec_nanos() > 0 || max_cpu_secs == 0 {
+                max_cpu_secs += 1;
+            }
+            rlimits.push((libc::RLIMIT_CPU, max_cpu_secs as libc::rlim_t));
+        }
+        if rlimits.is_empty() {
+            return;
+        }
+
+        // only async-signal-safe calls are allowed here, so everything is prepared before fork
+        unsafe {
+            command.pre_exec(move || {
+                for (resource, value) in &rlimits {
+                    let rlimit = libc::rlimit {
+                        rlim_cur: *value,
+                        rlim_max: *value,
+                    };
+                    if libc::setrlimit(*resource, &rlimit) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
     /// Send SIGINT signal to the sub-process, which is cheking for this ctrl-c signal and shuts down gracefully if recieved
-    fn terminate_or_kill(process: &mut Child, reason: String) -> Result<(), ProtocolRunnerError> {
+    fn terminate_or_kill(
+        process: &mut ProtocolRunnerProcess,
+        reason: String,
+    ) -> Result<(), ProtocolRunnerError> {
+        let process = &mut process.process;
         // try to send SIGINT (ctrl-c)
         match signal::kill(Pid::from_raw(process.id() as i32), Signal::SIGINT) {
             Ok(_) => Ok(()),
@@ -74,7 +223,7 @@ impl ExecutableProtocolRunner {
 }
 
 impl ProtocolRunner for ExecutableProtocolRunner {
-    type Subprocess = Child;
+    type Subprocess = ProtocolRunnerProcess;
     const PROCESS_TERMINATE_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
 
     fn new(
@@ -86,6 +235,7 @@ impl ProtocolRunner for ExecutableProtocolRunner {
             event_server_path,
             executable_path,
             log_level,
+            limits,
             ..
         } = configuration;
         ExecutableProtocolRunner {
@@ -94,40 +244,35 @@ impl ProtocolRunner for ExecutableProtocolRunner {
             executable_path,
             endpoint_name,
             log_level,
+            limits,
         }
     }
 
     fn spawn(&self) -> Result<Self::Subprocess, ProtocolRunnerError> {
-        let process = match &self.sock_evt_path {
-            Some(sep) => Command::new(&self.executable_path)
-                .arg("--sock-cmd")
-                .arg(&self.sock_cmd_path)
-                .arg("--sock-evt")
-                .arg(&sep)
-                .arg("--endpoint")
-                .arg(&self.endpoint_name)
-                .arg("--log-level")
-                .arg(&self.log_level.as_str().to_lowercase())
-                .spawn()
-                .map_err(|err| ProtocolRunnerError::SpawnError { reason: err })?,
-            None => Command::new(&self.executable_path)
-                .arg("--sock-cmd")
-                .arg(&self.sock_cmd_path)
-                .arg("--endpoint")
-                .arg(&self.endpoint_name)
-                .arg("--log-level")
-                .arg(&self.log_level.as_str().to_lowercase())
-                .spawn()
-                .map_err(|err| ProtocolRunnerError::SpawnError { reason: err })?,
-        };
-        Ok(process)
+        let mut command = Command::new(&self.executable_path);
+        command.arg("--sock-cmd").arg(&self.sock_cmd_path);
+        if let Some(sep) = &self.sock_evt_path {
+            command.arg("--sock-evt").arg(sep);
+        }
+        command
+            .arg("--endpoint")
+            .arg(&self.endpoint_name)
+            .arg("--log-level")
+            .arg(&self.log_level.as_str().to_lowercase())
+            .stderr(Stdio::piped());
+        Self::set_resource_limits(&mut command, &self.limits);
+
+        let process = command
+            .spawn()
+            .map_err(|err| ProtocolRunnerError::SpawnError { reason: err })?;
+        Ok(ProtocolRunnerProcess::new(process))
     }
 
     fn wait_and_terminate_ref(
         process: &mut Self::Subprocess,
         wait_timeout: Duration,
     ) -> Result<(), ProtocolRunnerError> {
-        match process.wait_timeout(wait_timeout) {
+        match process.process.wait_timeout(wait_timeout) {
             Ok(Some(_exit_status)) => {
                 // process exited, so we are ok
                 Ok(())
@@ -138,7 +283,29 @@ impl ProtocolRunner for ExecutableProtocolRunner {
     }
 
     fn is_running(process: &mut Self::Subprocess) -> bool {
-        matches!(process.try_wait(), Ok(None))
+        matches!(process.process.try_wait(), Ok(None))
+    }
+
+    fn kill(process: &mut Self::Subprocess) -> Result<(), ProtocolRunnerError> {
+        process
+            .process
+            .kill()
+            .and_then(|_| process.process.wait())
+            .map(|_| ())
+            .map_err(|e| ProtocolRunnerError::TerminateError {
+                reason: format!("Failed to kill sub-process, reason: {}", e),
+            })
+    }
+
+    fn exit_report(process: &mut Self::Subprocess) -> Option<ProtocolRunnerExit> {
+        match process.process.try_wait() {
+            Ok(Some(status)) => Some(ProtocolRunnerExit::new(status, process.stderr_tail())),
+            _ => None,
+        }
+    }
+
+    fn resident_memory(process: &mut Self::Subprocess) -> Option<u64> {
+        process.resident_memory()
     }
 }
 
@@ -162,4 +329,13 @@ pub trait ProtocolRunner: Clone + Send + Sync {
 
     /// Checks if process is running
     fn is_running(process: &mut Self::Subprocess) -> bool;
+
+    /// Kills process immediately, used for stuck process, which does not respond anymore
+    fn kill(process: &mut Self::Subprocess) -> Result<(), ProtocolRunnerError>;
+
+    /// Returns how the process ended (exit code/signal and last stderr lines), or None if process is still running
+    fn exit_report(process: &mut Self::Subprocess) -> Option<ProtocolRunnerExit>;
+
+    /// Returns resident memory of running process in bytes, or None if it cannot be measured
+    fn resident_memory(process: &mut Self::Subprocess) -> Option<u64>;
 }
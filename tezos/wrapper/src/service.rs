@@ -3,9 +3,10 @@
 
 use std::cell::RefCell;
 use std::convert::AsRef;
+use std::fmt;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Condvar, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use failure::Fail;
 use lazy_static::lazy_static;
@@ -14,6 +15,7 @@ use slog::{debug, warn, Logger};
 use strum_macros::IntoStaticStr;
 
 use crypto::hash::{ChainId, ContextHash, ProtocolHash};
+pub use ipc::IpcTransport;
 use ipc::*;
 use tezos_api::environment::TezosEnvironmentConfiguration;
 use tezos_api::ffi::*;
@@ -37,9 +39,74 @@ lazy_static! {
     static ref AT_LEAST_ONE_WRITE_PROTOCOL_CONTEXT_WAS_SUCCESS_AT_FIRST_LOCK: Arc<(Mutex<bool>, Condvar)> = Arc::new((Mutex::new(false), Condvar::new()));
 }
 
+/// Version of the IPC protocol spoken between tezedge node and protocol runner (`ProtocolMessage`/`NodeMessage`).
+///
+/// Node and protocol runner are separate binaries, which can be upgraded independently,
+/// so the messages can evolve only by these rules:
+/// * `HandshakeCall`/`HandshakeResult` are always the first variants and [`NodeHandshake`]/[`ProtocolRunnerHandshake`] never change,
+/// * new message is appended to the end of `ProtocolMessage`/`NodeMessage` and `minor` is increased,
+///   node can send it only to protocol runners with at least this `minor` version (see [`ProtocolController::runner_supports`]),
+/// * any other change (removed/reordered messages, changed request/response types) increases `major`
+///   and resets `minor` to 0, node refuses protocol runners with different `major`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IpcProtocolVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl IpcProtocolVersion {
+    /// Returns true, if both sides can communicate together (at least with the messages of the lower `minor` version),
+    /// so different `minor` is tolerated, different `major` is not.
+    pub fn is_compatible_with(&self, other: &IpcProtocolVersion) -> bool {
+        self.major == other.major
+    }
+
+    /// Returns true, if side with this version understands messages added in `version`
+    /// (the same `major` and at least the same `minor`).
+    pub fn supports(&self, version: &IpcProtocolVersion) -> bool {
+        self.major == version.major && self.minor >= version.minor
+    }
+}
+
+impl fmt::Display for IpcProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Current version of the IPC protocol, see [`IpcProtocolVersion`] for the rules, how to change it.
+pub const IPC_PROTOCOL_VERSION: IpcProtocolVersion = IpcProtocolVersion { major: 1, minor: 0 };
+
+/// Git commit, from which was this binary built.
+fn build_hash() -> String {
+    option_env!("GIT_HASH")
+        .map(|hash| hash.trim())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Handshake sent by tezedge node as the very first message.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NodeHandshake {
+    pub version: IpcProtocolVersion,
+    pub build_hash: String,
+}
+
+/// Handshake sent by protocol runner as a response to the [`NodeHandshake`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProtocolRunnerHandshake {
+    pub version: IpcProtocolVersion,
+    pub build_hash: String,
+    /// Protocols, which can be applied by protocol runner
+    pub supported_protocols: Vec<ProtocolHash>,
+}
+
 /// This command message is generated by tezedge node and is received by the protocol runner.
+///
+/// Variants are identified by their position, see [`IpcProtocolVersion`] before any change.
 #[derive(Serialize, Deserialize, Debug, IntoStaticStr)]
-enum ProtocolMessage {
+pub(crate) enum ProtocolMessage {
+    HandshakeCall(NodeHandshake),
     ApplyBlockCall(ApplyBlockRequest),
     AssertEncodingForProtocolDataCall(ProtocolHash, RustBytes),
     BeginApplicationCall(BeginApplicationRequest),
@@ -56,7 +123,7 @@ enum ProtocolMessage {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct InitProtocolContextParams {
+pub(crate) struct InitProtocolContextParams {
     storage_data_dir: String,
     genesis: GenesisChain,
     genesis_max_operations_ttl: u16,
@@ -68,7 +135,7 @@ struct InitProtocolContextParams {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct GenesisResultDataParams {
+pub(crate) struct GenesisResultDataParams {
     genesis_context_hash: ContextHash,
     chain_id: ChainId,
     genesis_protocol_hash: ProtocolHash,
@@ -76,8 +143,11 @@ struct GenesisResultDataParams {
 }
 
 /// This event message is generated as a response to the `ProtocolMessage` command.
+///
+/// Variants are identified by their position, see [`IpcProtocolVersion`] before any change.
 #[derive(Serialize, Deserialize, Debug, IntoStaticStr)]
-enum NodeMessage {
+pub(crate) enum NodeMessage {
+    HandshakeResult(ProtocolRunnerHandshake),
     ApplyBlockResult(Result<ApplyBlockResponse, ApplyBlockError>),
     AssertEncodingForProtocolDataResult(Result<(), ProtocolDataError>),
     BeginApplicationResult(Result<BeginApplicationResponse, BeginApplicationError>),
@@ -94,7 +164,7 @@ enum NodeMessage {
 
 /// Empty message
 #[derive(Serialize, Deserialize, Debug)]
-struct NoopMessage;
+pub(crate) struct NoopMessage;
 
 pub fn process_protocol_events<P: AsRef<Path>>(socket_path: P) -> Result<(), IpcError> {
     let ipc_client: IpcClient<NoopMessage, ContextAction> = IpcClient::new(socket_path);
@@ -117,9 +187,34 @@ pub fn process_protocol_commands<Proto: ProtocolApi, P: AsRef<Path>, SDC: Fn(&Lo
     shutdown_callback: SDC,
 ) -> Result<(), IpcError> {
     let ipc_client: IpcClient<ProtocolMessage, NodeMessage> = IpcClient::new(socket_path);
-    let (mut rx, mut tx) = ipc_client.connect()?;
+    let (rx, tx) = ipc_client.connect()?;
+    serve_protocol_commands::<Proto, _>(rx, tx, log, shutdown_callback)
+}
+
+/// Begin receiving commands from already connected tezedge node
+/// until `ShutdownCall` command is received or the channel is closed.
+pub(crate) fn serve_protocol_commands<Proto: ProtocolApi, SDC: Fn(&Logger)>(
+    mut rx: IpcReceiver<ProtocolMessage>,
+    mut tx: IpcSender<NodeMessage>,
+    log: &Logger,
+    shutdown_callback: SDC,
+) -> Result<(), IpcError> {
     while let Ok(cmd) = rx.receive() {
         match cmd {
+            ProtocolMessage::HandshakeCall(node) => {
+                if !IPC_PROTOCOL_VERSION.is_compatible_with(&node.version) {
+                    warn!(log, "Node uses incompatible IPC protocol version";
+                               "node_version" => node.version.to_string(),
+                               "node_build_hash" => node.build_hash,
+                               "version" => IPC_PROTOCOL_VERSION.to_string());
+                }
+                // node decides if it can continue, so we always respond with our handshake
+                tx.send(&NodeMessage::HandshakeResult(ProtocolRunnerHandshake {
+                    version: IPC_PROTOCOL_VERSION,
+                    build_hash: build_hash(),
+                    supported_protocols: Proto::supported_protocols(),
+                }))?;
+            }
             ProtocolMessage::ApplyBlockCall(request) => {
                 let res = Proto::apply_block(request);
                 tx.send(&NodeMessage::ApplyBlockResult(res))?;
@@ -257,6 +352,23 @@ pub enum ProtocolServiceError {
     /// Lock error
     #[fail(display = "Lock error: {:?}", message)]
     LockPoisonError { message: String },
+    /// Protocol runner did not respond to the handshake, e.g. it was built from the older version without handshake
+    #[fail(
+        display = "Handshake with protocol runner failed (is protocol runner built from the same version as node?), reason: {}",
+        reason
+    )]
+    HandshakeError { reason: IpcError },
+    /// Protocol runner speaks different (incompatible) version of the IPC protocol
+    #[fail(
+        display = "Incompatible protocol runner - node IPC protocol version: {} (build: {}), protocol runner IPC protocol version: {} (build: {})",
+        node_version, node_build_hash, runner_version, runner_build_hash
+    )]
+    IncompatibleProtocolRunner {
+        node_version: IpcProtocolVersion,
+        node_build_hash: String,
+        runner_version: IpcProtocolVersion,
+        runner_build_hash: String,
+    },
 }
 
 impl<T> From<std::sync::PoisonError<T>> for ProtocolServiceError {
@@ -295,7 +407,10 @@ pub fn handle_protocol_service_error<LC: Fn(ProtocolServiceError)>(
     log_callback: LC,
 ) -> Result<(), ProtocolServiceError> {
     match error {
-        ProtocolServiceError::IpcError { .. } | ProtocolServiceError::UnexpectedMessage { .. } => {
+        ProtocolServiceError::IpcError { .. }
+        | ProtocolServiceError::UnexpectedMessage { .. }
+        | ProtocolServiceError::HandshakeError { .. }
+        | ProtocolServiceError::IncompatibleProtocolRunner { .. } => {
             // we need to refresh protocol runner endpoint, so propagate error
             Err(error)
         }
@@ -329,21 +444,65 @@ impl IpcCmdServer {
 
     /// Start accepting incoming IPC connection.
     ///
-    /// Returns a [`protocol controller`](ProtocolController) if new IPC channel is successfully created.
+    /// Returns a [`protocol controller`](ProtocolController) if new IPC channel is successfully created
+    /// and protocol runner passed the handshake (uses compatible [`IpcProtocolVersion`]).
     /// This is a blocking operation.
-    pub fn try_accept(&mut self, timeout: Duration) -> Result<ProtocolController, IpcError> {
-        let (rx, tx) = self.0.try_accept(timeout)?;
+    pub fn try_accept(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<ProtocolController, ProtocolServiceError> {
+        let (mut rx, mut tx) = self.0.try_accept(timeout)?;
         // configure default IO timeouts
         rx.set_read_timeout(Some(Self::IO_TIMEOUT))
             .and(tx.set_write_timeout(Some(Self::IO_TIMEOUT)))
             .map_err(|err| IpcError::SocketConfigurationError { reason: err })?;
 
+        let runner = Self::handshake(&mut rx, &mut tx)?;
+
         Ok(ProtocolController {
             io: RefCell::new(IpcIO { rx, tx }),
             configuration: self.1.clone(),
+            runner,
             shutting_down: false,
         })
     }
+
+    /// Exchange handshakes, this must be the first message on the channel.
+    fn handshake(
+        rx: &mut IpcReceiver<NodeMessage>,
+        tx: &mut IpcSender<ProtocolMessage>,
+    ) -> Result<ProtocolRunnerHandshake, ProtocolServiceError> {
+        let node_build_hash = build_hash();
+        tx.send(&ProtocolMessage::HandshakeCall(NodeHandshake {
+            version: IPC_PROTOCOL_VERSION,
+            build_hash: node_build_hash.clone(),
+        }))
+        .map_err(|reason| ProtocolServiceError::HandshakeError { reason })?;
+
+        // protocol runner without handshake support fails to decode the message and closes connection
+        let runner = match rx
+            .receive()
+            .map_err(|reason| ProtocolServiceError::HandshakeError { reason })?
+        {
+            NodeMessage::HandshakeResult(runner) => runner,
+            message => {
+                return Err(ProtocolServiceError::UnexpectedMessage {
+                    message: message.into(),
+                })
+            }
+        };
+
+        if IPC_PROTOCOL_VERSION.is_compatible_with(&runner.version) {
+            Ok(runner)
+        } else {
+            Err(ProtocolServiceError::IncompatibleProtocolRunner {
+                node_version: IPC_PROTOCOL_VERSION,
+                node_build_hash,
+                runner_version: runner.version,
+                runner_build_hash: runner.build_hash,
+            })
+        }
+    }
 }
 
 /// IPC event server is listening for incoming IPC connections.
@@ -353,8 +512,18 @@ pub struct IpcEvtServer(IpcServer<ContextAction, NoopMessage>);
 /// * `IpcCmdServer` is used to create IPC channel over which commands from node are transferred to the protocol runner.
 /// * `IpcEvtServer` is used to create IPC channel over which events are transmitted from protocol runner to the tezedge node.
 impl IpcEvtServer {
-    pub fn try_bind_new() -> Result<Self, IpcError> {
-        Ok(IpcEvtServer(IpcServer::bind_path(&temp_sock())?))
+    /// Context actions are very high volume (especially during bootstrap),
+    /// so they are transferred through shared memory instead of the socket by default.
+    ///
+    /// Single context action cannot be larger than the capacity, use [`IpcTransport::Socket`] for larger ones.
+    pub const DEFAULT_TRANSPORT: IpcTransport = IpcTransport::SharedMemory {
+        capacity: 32 * 1024 * 1024,
+    };
+
+    pub fn try_bind_new(transport: IpcTransport) -> Result<Self, IpcError> {
+        Ok(IpcEvtServer(
+            IpcServer::bind_path(&temp_sock())?.with_transport(transport),
+        ))
     }
 
     /// Synchronously wait for new incoming IPC connection.
@@ -372,6 +541,15 @@ impl IpcEvtServer {
     }
 }
 
+/// Durations of apply block call to `protocol_runner`
+#[derive(Clone, Debug)]
+pub struct ApplyBlockCallTimer {
+    /// Sending of request over IPC
+    pub send_elapsed: Duration,
+    /// Waiting for response - protocol (ocaml) apply and receiving of response over IPC
+    pub receive_elapsed: Duration,
+}
+
 struct IpcIO {
     rx: IpcReceiver<NodeMessage>,
     tx: IpcSender<ProtocolMessage>,
@@ -381,6 +559,8 @@ struct IpcIO {
 pub struct ProtocolController {
     io: RefCell<IpcIO>,
     configuration: ProtocolEndpointConfiguration,
+    /// Handshake received from the connected protocol runner
+    runner: ProtocolRunnerHandshake,
     /// Indicates that was triggered shutting down
     shutting_down: bool,
 }
@@ -399,22 +579,53 @@ impl ProtocolController {
     const COMPUTE_PATH_TIMEOUT: Duration = Duration::from_secs(30);
     const ASSERT_ENCODING_FOR_PROTOCOL_DATA_TIMEOUT: Duration = Duration::from_secs(15);
 
+    /// Returns handshake of the connected protocol runner (IPC protocol version, build and supported protocols).
+    pub fn runner(&self) -> &ProtocolRunnerHandshake {
+        &self.runner
+    }
+
+    /// Returns true, if connected protocol runner understands messages added in IPC protocol `version`,
+    /// messages added in newer `minor` version than the runner's one must not be sent.
+    pub fn runner_supports(&self, version: &IpcProtocolVersion) -> bool {
+        self.runner.version.supports(version)
+    }
+
     /// Apply block
     pub fn apply_block(
         &self,
         request: ApplyBlockRequest,
     ) -> Result<ApplyBlockResponse, ProtocolServiceError> {
+        self.apply_block_with_timer(request)
+            .map(|(response, _)| response)
+    }
+
+    /// Apply block and measure, how long it took to send request and to wait for response
+    pub fn apply_block_with_timer(
+        &self,
+        request: ApplyBlockRequest,
+    ) -> Result<(ApplyBlockResponse, ApplyBlockCallTimer), ProtocolServiceError> {
         let mut io = self.io.borrow_mut();
+        let send_timer = Instant::now();
         io.tx.send(&ProtocolMessage::ApplyBlockCall(request))?;
+        let send_elapsed = send_timer.elapsed();
 
         // this might take a while, so we will use unusually long timeout
+        let receive_timer = Instant::now();
         match io.rx.try_receive(
             Some(Self::APPLY_BLOCK_TIMEOUT),
             Some(IpcCmdServer::IO_TIMEOUT),
         )? {
-            NodeMessage::ApplyBlockResult(result) => {
-                result.map_err(|err| ProtocolError::ApplyBlockError { reason: err }.into())
-            }
+            NodeMessage::ApplyBlockResult(result) => result
+                .map(|response| {
+                    (
+                        response,
+                        ApplyBlockCallTimer {
+                            send_elapsed,
+                            receive_elapsed: receive_timer.elapsed(),
+                        },
+                    )
+                })
+                .map_err(|err| ProtocolError::ApplyBlockError { reason: err }.into()),
             message => Err(ProtocolServiceError::UnexpectedMessage {
                 message: message.into(),
             }),
@@ -860,3 +1071,74 @@ impl<Runner: ProtocolRunner + 'static> ProtocolRunnerEndpoint<Runner> {
         self.runner.spawn()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    fn version(major: u16, minor: u16) -> IpcProtocolVersion {
+        IpcProtocolVersion { major, minor }
+    }
+
+    /// Handshake of the node with fake protocol runner, which responds with `runner_version`
+    fn handshake_with_runner(
+        runner_version: IpcProtocolVersion,
+    ) -> Result<ProtocolRunnerHandshake, ProtocolServiceError> {
+        let mut server: IpcServer<NodeMessage, ProtocolMessage> =
+            IpcServer::bind_path(&temp_sock())?;
+        let client: IpcClient<ProtocolMessage, NodeMessage> = IpcClient::new(&server.path);
+        let runner = thread::spawn(move || -> Result<(), IpcError> {
+            let (mut rx, mut tx) = client.connect()?;
+            if let ProtocolMessage::HandshakeCall(_) = rx.receive()? {
+                tx.send(&NodeMessage::HandshakeResult(ProtocolRunnerHandshake {
+                    version: runner_version,
+                    build_hash: "test".to_string(),
+                    supported_protocols: vec![],
+                }))?;
+            }
+            Ok(())
+        });
+        let (mut rx, mut tx) = server.try_accept(Duration::from_secs(5))?;
+        let result = IpcCmdServer::handshake(&mut rx, &mut tx);
+        runner.join().expect("fake protocol runner panicked")?;
+        result
+    }
+
+    #[test]
+    fn test_version_compatibility() {
+        // minor mismatch is tolerated both ways, major is not
+        assert!(version(1, 0).is_compatible_with(&version(1, 3)));
+        assert!(version(1, 3).is_compatible_with(&version(1, 0)));
+        assert!(!version(1, 0).is_compatible_with(&version(2, 0)));
+        assert!(!version(2, 1).is_compatible_with(&version(1, 1)));
+
+        // messages of newer minor cannot be sent to older runner
+        assert!(version(1, 2).supports(&version(1, 2)));
+        assert!(version(1, 2).supports(&version(1, 1)));
+        assert!(!version(1, 1).supports(&version(1, 2)));
+        assert!(!version(2, 2).supports(&version(1, 1)));
+    }
+
+    #[test]
+    fn test_handshake_accepts_minor_mismatch() -> Result<(), ProtocolServiceError> {
+        let runner_version = version(IPC_PROTOCOL_VERSION.major, IPC_PROTOCOL_VERSION.minor + 1);
+        let runner = handshake_with_runner(runner_version)?;
+        assert_eq!(runner_version, runner.version);
+        Ok(())
+    }
+
+    #[test]
+    fn test_handshake_rejects_major_mismatch() {
+        let runner_version = version(IPC_PROTOCOL_VERSION.major + 1, IPC_PROTOCOL_VERSION.minor);
+        match handshake_with_runner(runner_version) {
+            Err(ProtocolServiceError::IncompatibleProtocolRunner {
+                runner_version: rejected,
+                ..
+            }) => assert_eq!(runner_version, rejected),
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("incompatible protocol runner accepted"),
+        }
+    }
+}
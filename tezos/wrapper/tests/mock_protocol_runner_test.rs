@@ -0,0 +1,223 @@
+// Copyright (c) SimpleStaking and Tezedge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::convert::{TryFrom, TryInto};
+use std::thread;
+use std::time::Duration;
+
+use slog::{o, Discard, Level, Logger};
+
+use crypto::hash::{ContextHash, OperationListListHash};
+use tezos_api::environment::{TezosEnvironment, TEZOS_ENV};
+use tezos_api::ffi::{
+    ApplyBlockError, ApplyBlockRequest, ApplyBlockResponse, ProtocolRpcResponse,
+    TezosRuntimeConfiguration,
+};
+use tezos_context::channel::ContextAction;
+use tezos_messages::p2p::binary_message::MessageHash;
+use tezos_messages::p2p::encoding::block_header::BlockHeaderBuilder;
+use tezos_messages::p2p::encoding::prelude::BlockHeader;
+use tezos_wrapper::mock::{MockProtocolRunner, MockProtocolScript};
+use tezos_wrapper::service::{IpcEvtServer, ProtocolError, ProtocolServiceError};
+use tezos_wrapper::{
+    ProtocolEndpointConfiguration, ProtocolRunnerLimits, TezosApiConnectionPool,
+    TezosApiConnectionPoolConfiguration,
+};
+
+#[test]
+fn test_mock_protocol_runner_pool_apply_block_and_context_actions() -> Result<(), failure::Error> {
+    let log = Logger::root(Discard, o!());
+    let tezos_env = TEZOS_ENV
+        .get(&TezosEnvironment::Sandbox)
+        .expect("no environment configuration");
+    let protocol_hash = tezos_env.genesis_protocol()?;
+    let context_hash = ContextHash::try_from(vec![1; 32])?;
+
+    // level 1 is applied, level 2 fails on unknown predecessor context
+    let script = MockProtocolScript::default()
+        .with_supported_protocols(vec![protocol_hash.clone()])
+        .with_apply_block({
+            let context_hash = context_hash.clone();
+            move |request| match request.block_header.level() {
+                1 => Ok(apply_block_response(context_hash.clone())),
+                level => Err(ApplyBlockError::UnknownPredecessorContext {
+                    message: format!("level: {}", level),
+                }),
+            }
+        })
+        .with_apply_block_context_actions({
+            let context_hash = context_hash.clone();
+            move |_| {
+                vec![ContextAction::Checkout {
+                    context_hash: context_hash.as_ref().clone(),
+                    start_time: 0_f64,
+                    end_time: 0_f64,
+                }]
+            }
+        })
+        .with_call_protocol_rpc(|_| Ok(ProtocolRpcResponse::RPCOk("mocked".to_string())));
+    let executable_path = script.clone().register();
+
+    // context actions are received like from a real protocol runner
+    let mut event_server = IpcEvtServer::try_bind_new(IpcEvtServer::DEFAULT_TRANSPORT)?;
+    let event_server_path = event_server.server_path();
+    let events = thread::spawn(move || -> Result<usize, failure::Error> {
+        let mut rx = event_server.try_accept(Duration::from_secs(5))?;
+        let mut checkouts = 0;
+        while let ContextAction::Checkout { .. } = rx.receive()? {
+            checkouts += 1;
+        }
+        Ok(checkouts)
+    });
+
+    let pool: TezosApiConnectionPool<MockProtocolRunner> =
+        TezosApiConnectionPool::new_without_context(
+            "test_mock_pool".to_string(),
+            TezosApiConnectionPoolConfiguration {
+                min_connections: 0,
+                max_connections: 1,
+                connection_timeout: Duration::from_secs(5),
+                max_lifetime: Duration::from_secs(60),
+                idle_timeout: Duration::from_secs(60),
+                limits: ProtocolRunnerLimits::default(),
+            },
+            ProtocolEndpointConfiguration::new(
+                TezosRuntimeConfiguration {
+                    log_enabled: false,
+                    debug_mode: false,
+                    compute_context_action_tree_hashes: false,
+                },
+                tezos_env.clone(),
+                false,
+                std::env::temp_dir().as_path(),
+                executable_path.as_path(),
+                Level::Debug,
+                Some(event_server_path),
+            ),
+            log,
+        )?;
+
+    {
+        let mut connection = pool.pool.get()?;
+        assert_eq!(
+            connection.api.runner().supported_protocols,
+            vec![protocol_hash]
+        );
+        assert_eq!(
+            connection
+                .api
+                .init_protocol_for_write(false, &None)?
+                .genesis_commit_hash,
+            None
+        );
+
+        let genesis = tezos_env.genesis_header(
+            context_hash.clone(),
+            OperationListListHash::try_from(vec![0; 32])?,
+        )?;
+        let block_1 = block_header(&genesis, 1)?;
+        let block_2 = block_header(&block_1, 2)?;
+
+        let response = connection.api.apply_block(apply_block_request(
+            &tezos_env.main_chain_id()?,
+            &genesis,
+            &block_1,
+        ))?;
+        assert_eq!(response.context_hash, context_hash);
+
+        match connection.api.apply_block(apply_block_request(
+            &tezos_env.main_chain_id()?,
+            &block_1,
+            &block_2,
+        )) {
+            Err(ProtocolServiceError::ProtocolError {
+                reason:
+                    ProtocolError::ApplyBlockError {
+                        reason: ApplyBlockError::UnknownPredecessorContext { .. },
+                    },
+            }) => (/* ok */),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let rpc_request = tezos_api::ffi::ProtocolRpcRequest {
+            block_header: block_1,
+            chain_arg: "main".to_string(),
+            chain_id: tezos_env.main_chain_id()?,
+            request: tezos_api::ffi::RpcRequest {
+                body: "".to_string(),
+                context_path: "/".to_string(),
+                meth: tezos_api::ffi::RpcMethod::GET,
+                content_type: None,
+                accept: None,
+            },
+        };
+        assert_eq!(
+            connection.call_protocol_rpc(rpc_request)?,
+            ProtocolRpcResponse::RPCOk("mocked".to_string())
+        );
+
+        let applied_levels: Vec<i32> = script
+            .apply_block_requests()
+            .iter()
+            .map(|request| request.block_header.level())
+            .collect();
+        assert_eq!(applied_levels, vec![1, 2]);
+
+        // released connection is shut down by pool (mock runner sends shutdown to event server)
+        connection.set_release_on_return_to_pool();
+    }
+
+    // both applied blocks sent context actions, followed by shutdown
+    assert_eq!(events.join().expect("event thread panicked")?, 2);
+    assert_eq!(pool.metrics.stats().crashes, 0);
+
+    Ok(())
+}
+
+fn block_header(predecessor: &BlockHeader, level: i32) -> Result<BlockHeader, failure::Error> {
+    BlockHeaderBuilder::default()
+        .level(level)
+        .proto(1)
+        .predecessor(predecessor.message_hash()?.try_into()?)
+        .timestamp(predecessor.timestamp() + 1)
+        .validation_pass(4)
+        .operations_hash(predecessor.operations_hash().clone())
+        .fitness(vec![])
+        .context(predecessor.context().clone())
+        .protocol_data(vec![])
+        .build()
+        .map_err(|e| failure::format_err!("{}", e))
+}
+
+fn apply_block_request(
+    chain_id: &crypto::hash::ChainId,
+    pred_header: &BlockHeader,
+    block_header: &BlockHeader,
+) -> ApplyBlockRequest {
+    ApplyBlockRequest {
+        chain_id: chain_id.clone(),
+        block_header: block_header.clone(),
+        pred_header: pred_header.clone(),
+        max_operations_ttl: 60,
+        operations: vec![vec![]; 4],
+        predecessor_block_metadata_hash: None,
+        predecessor_ops_metadata_hash: None,
+    }
+}
+
+fn apply_block_response(context_hash: ContextHash) -> ApplyBlockResponse {
+    ApplyBlockResponse {
+        validation_result_message: "mocked".to_string(),
+        context_hash,
+        block_header_proto_json: "{}".to_string(),
+        block_header_proto_metadata_json: "{}".to_string(),
+        operations_proto_metadata_json: "[]".to_string(),
+        max_operations_ttl: 60,
+        last_allowed_fork_level: 0,
+        forking_testchain: false,
+        forking_testchain_data: None,
+        block_metadata_hash: None,
+        ops_metadata_hashes: None,
+        ops_metadata_hash: None,
+    }
+}